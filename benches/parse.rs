@@ -0,0 +1,54 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use game_quest_parser_Hodik::{Lexer, Parser};
+
+/// Generates a quest pack shaped like our generated content: many quests with
+/// several steps, objectives and extra properties each.
+fn quest_pack(quests: usize) -> String {
+    let mut source = String::from("let BASE_REWARD = 100\n");
+    for i in 0..quests {
+        source.push_str(&format!(
+            r#"// Generated quest {i}
+quest "Quest number {i}" {{
+    active: true,
+    reward: $BASE_REWARD,
+    requires: "Quest number {prev}",
+    step: "Talk to the blacksmith in the northern village",
+    step: "Find the \"hidden\" cave entrance",
+    steps: ["Defeat the guardian", "Return to town"],
+    objective {{ kind: "kill", target: "Skeleton", count: 3 }},
+    zone: "North",
+    difficulty: 2.5,
+    tags: ["main", "generated"],
+}}
+"#,
+            prev = i.saturating_sub(1),
+        ));
+    }
+    source
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for quests in [100, 1_000] {
+        let source = quest_pack(quests);
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_with_input(BenchmarkId::new("lex", quests), &source, |b, source| {
+            b.iter(|| Lexer::new(black_box(source)).count())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("parse_quests", quests),
+            &source,
+            |b, source| {
+                b.iter(|| {
+                    Parser::new(black_box(source))
+                        .and_then(|mut p| p.parse_quests())
+                        .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+//! Collects the grammar rules documented on the parser's methods into
+//! `$OUT_DIR/grammar.ebnf`, which `grammar::ebnf()` returns. The doc comments
+//! stay the only place the grammar is written down.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const PARSER_SOURCE: &str = "src/lib.rs";
+
+fn main() {
+    println!("cargo:rerun-if-changed={PARSER_SOURCE}");
+    let source = fs::read_to_string(PARSER_SOURCE).expect("failed to read the parser source");
+
+    let mut rules: Vec<(String, Vec<String>)> = Vec::new();
+    let mut in_block = false;
+    for line in source.lines() {
+        let Some(doc) = line.trim_start().strip_prefix("///") else {
+            in_block = false;
+            continue;
+        };
+        match doc.trim() {
+            "```ebnf" => in_block = true,
+            "```" => in_block = false,
+            "" => {}
+            _ if !in_block => {}
+            rule => match rule.split_once("::=") {
+                Some((name, body)) => {
+                    rules.push((name.trim().to_string(), vec![body.trim().to_string()]))
+                }
+                None => rules
+                    .last_mut()
+                    .expect("grammar block starts with a continuation line")
+                    .1
+                    .push(rule.to_string()),
+            },
+        }
+    }
+
+    // A rule documented on several methods is listed once, as long as every
+    // copy says the same thing.
+    let mut unique: Vec<(String, Vec<String>)> = Vec::new();
+    for (name, lines) in rules {
+        match unique.iter().find(|(other, _)| *other == name) {
+            Some((_, first)) if *first == lines => {}
+            Some(_) => panic!("grammar rule {name} is documented twice with different definitions"),
+            None => unique.push((name, lines)),
+        }
+    }
+
+    let width = unique.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut ebnf = String::new();
+    for (name, lines) in &unique {
+        writeln!(ebnf, "{name:<width$} ::= {}", lines[0]).unwrap();
+        for line in &lines[1..] {
+            writeln!(ebnf, "{:width$}   {line}", "").unwrap();
+        }
+    }
+
+    let out_dir = env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    fs::write(Path::new(&out_dir).join("grammar.ebnf"), ebnf)
+        .expect("failed to write grammar.ebnf");
+}
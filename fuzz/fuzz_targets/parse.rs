@@ -0,0 +1,19 @@
+#![no_main]
+
+use game_quest_parser_Hodik::{Parser, Token};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let mut lexer = game_quest_parser_Hodik::Lexer::new(input);
+    while let Ok((token, _)) = lexer.next_token() {
+        if token == Token::Eof {
+            break;
+        }
+    }
+
+    let _ = Parser::parse_untrusted(input);
+});
@@ -0,0 +1,29 @@
+use crate::prelude::*;
+use crate::Value;
+
+/// An engine action run when a quest starts or is completed, declared in an
+/// `on_start { ... }` or `on_complete { ... }` block:
+///
+/// ```text
+/// on_start { give_item: "Map", play_sound: "fanfare" }
+/// on_complete { spawn: ["wolf", 3] }
+/// ```
+///
+/// Each key of the block is an action. A list value passes its elements as
+/// separate arguments; any other value is the only argument. The parser does
+/// not check action names, which are up to the game.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Action {
+    pub name: String,
+    pub args: Vec<Value>,
+}
+
+impl Action {
+    pub fn new(name: impl Into<String>, args: Vec<Value>) -> Action {
+        Action {
+            name: name.into(),
+            args,
+        }
+    }
+}
@@ -0,0 +1,620 @@
+//! A lossless syntax tree of quest source, for tools that work on the text
+//! as written rather than on the [`Quest`](crate::Quest) it means.
+//!
+//! The tree keeps every quest's properties in source order with their raw
+//! value text, the comments around them and hints about how they were laid
+//! out, and every node records the [`Span`] it was read from. Nothing is
+//! evaluated: `let` constants, includes and templates stay as written, and
+//! values are only checked for balanced brackets. Use [`Parser`](crate::Parser)
+//! to get the meaning of a file.
+//!
+//! ```
+//! use game_quest_parser_Hodik::ast;
+//!
+//! let source = "// The opening quest\nquest \"Intro\" {\n    reward: 100 gold, 5 gems // starter pack\n}\n";
+//! let tree = ast::parse(source)?;
+//! let quest = tree.quests().next().unwrap();
+//! assert_eq!(quest.comments[0].text, " The opening quest");
+//! let reward = quest.property("reward").unwrap();
+//! assert_eq!(reward.value, "100 gold, 5 gems");
+//! assert_eq!(reward.trailing_comment.as_ref().unwrap().text, " starter pack");
+//! # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+//! ```
+
+use crate::prelude::*;
+use crate::{Lexer, ParseError, Span, Token};
+
+/// A parsed source file. Spans index into [`SyntaxTree::source`], which is
+/// the input unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxTree {
+    pub items: Vec<Item>,
+    /// Comments after the last item.
+    pub trailing_comments: Vec<Comment>,
+    source: String,
+}
+
+impl SyntaxTree {
+    /// The text the tree was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The source text covered by `span`.
+    pub fn text(&self, span: Span) -> &str {
+        &self.source[span.start..span.end]
+    }
+
+    pub fn quests(&self) -> impl Iterator<Item = &QuestNode> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Quest(quest) => Some(quest),
+            Item::Declaration(_) => None,
+        })
+    }
+
+    /// The top-level property whose key is at byte `offset`, with its quest.
+    pub fn key_at(&self, offset: usize) -> Option<(&QuestNode, &Property)> {
+        self.quests().find_map(|quest| {
+            let property = quest
+                .properties
+                .iter()
+                .find(|p| p.key_span.start <= offset && offset <= p.key_span.end)?;
+            Some((quest, property))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Quest(QuestNode),
+    /// Anything else at the top level, such as a `let`, `include`, `npc`,
+    /// `item` or `template` declaration or the `#version` pragma, kept as a
+    /// span of source.
+    Declaration(Declaration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    /// The word the declaration starts with, or `#` for a pragma.
+    pub keyword: String,
+    pub comments: Vec<Comment>,
+    pub blank_line_before: bool,
+    pub span: Span,
+}
+
+/// How a `quest` item gets its properties.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuestKind {
+    /// `quest "Name" { ... }`
+    Definition,
+    /// `quest "Name" override { ... }`
+    Override,
+    /// `quest "Name" from template(...)`, which has no properties of its own.
+    Instantiation { template: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuestNode {
+    /// Comments on the lines before the quest.
+    pub comments: Vec<Comment>,
+    /// Whether a blank line separates the quest, or its comments, from what
+    /// comes before it.
+    pub blank_line_before: bool,
+    /// Attributes in front of the `quest` keyword, in source order.
+    pub attributes: Vec<Attribute>,
+    pub name: String,
+    pub name_span: Span,
+    pub kind: QuestKind,
+    pub properties: Vec<Property>,
+    /// Comments after the last property, before the closing brace.
+    pub closing_comments: Vec<Comment>,
+    /// From the `quest` keyword to the closing brace or parenthesis.
+    pub span: Span,
+}
+
+impl QuestNode {
+    /// The first property with the given key.
+    pub fn property(&self, key: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.key == key)
+    }
+}
+
+/// One `key: value` or `key { ... }` property of a quest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    /// Comments on the lines before the property.
+    pub comments: Vec<Comment>,
+    pub blank_line_before: bool,
+    /// Attributes in front of the key, in source order.
+    pub attributes: Vec<Attribute>,
+    pub key: String,
+    pub key_span: Span,
+    /// The value as written, without the colon: `100 gold, 5 gems`, or the
+    /// whole `{ ... }` of a block property, or `(3) step: "..."` after
+    /// `repeat`. Comments inside it are kept.
+    pub value: String,
+    pub value_span: Span,
+    /// Whether the value is a block written without a colon, such as
+    /// `objective { ... }`.
+    pub is_block: bool,
+    /// Whether a comma follows the property.
+    pub comma: bool,
+    /// A comment on the same line, after the property.
+    pub trailing_comment: Option<Comment>,
+    /// From the key to the value or its comma.
+    pub span: Span,
+}
+
+/// A `#[...]` attribute such as `#[if(feature = "halloween")]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    /// The text between the brackets: `if(feature = "halloween")`.
+    pub text: String,
+    /// From the `#` to the closing bracket.
+    pub span: Span,
+}
+
+/// A `//` comment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The text after the `//`, up to the end of the line.
+    pub text: String,
+    pub span: Span,
+}
+
+/// Parses `input` into a [`SyntaxTree`].
+///
+/// Fails on input the lexer rejects, on unbalanced brackets and on quests
+/// whose name or braces are missing.
+pub fn parse(input: &str) -> Result<SyntaxTree, ParseError> {
+    let mut lexer = Lexer::new(input);
+    let mut tokens = Vec::new();
+    loop {
+        let (token, span) = lexer.next_token()?;
+        let end = token == Token::Eof;
+        tokens.push((token, span));
+        if end {
+            break;
+        }
+    }
+    let mut builder = Builder {
+        input,
+        tokens,
+        pos: 0,
+        prev_end: 0,
+    };
+    let mut items = Vec::new();
+    loop {
+        let (mut comments, blank_line_before) = builder.trivia();
+        let attributes = builder.attributes(&mut comments)?;
+        match builder.token(builder.pos) {
+            Token::QuestKeyword => {
+                let quest = builder.quest(comments, blank_line_before, attributes)?;
+                items.push(Item::Quest(quest));
+            }
+            _ if !attributes.is_empty() => return Err(builder.syntax_error(builder.pos, "Quest")),
+            Token::Eof => {
+                return Ok(SyntaxTree {
+                    items,
+                    trailing_comments: comments,
+                    source: input.to_string(),
+                })
+            }
+            _ if builder.starts_item(builder.pos) => {
+                items.push(Item::Declaration(
+                    builder.declaration(comments, blank_line_before)?,
+                ));
+            }
+            _ => return Err(builder.syntax_error(builder.pos, "Quest")),
+        }
+    }
+}
+
+struct Builder<'a> {
+    input: &'a str,
+    /// Every token including comments, ending with [`Token::Eof`].
+    tokens: Vec<(Token<'a>, Span)>,
+    pos: usize,
+    /// End of the last token consumed.
+    prev_end: usize,
+}
+
+impl<'a> Builder<'a> {
+    fn token(&self, i: usize) -> &Token<'a> {
+        &self.tokens[i].0
+    }
+
+    fn span(&self, i: usize) -> Span {
+        self.tokens[i].1
+    }
+
+    /// Index of the first token from `i` on that is not a comment.
+    fn code(&self, mut i: usize) -> usize {
+        while let Token::Comment(_) = self.token(i) {
+            i += 1;
+        }
+        i
+    }
+
+    /// Consumes the current token, returning its span.
+    fn bump(&mut self) -> Span {
+        let span = self.span(self.pos);
+        self.prev_end = span.end;
+        self.pos += 1;
+        span
+    }
+
+    fn expect(&mut self, expected: Token<'_>, name: &str) -> Result<Span, ParseError> {
+        if core::mem::discriminant(self.token(self.pos)) != core::mem::discriminant(&expected) {
+            return Err(self.syntax_error(self.pos, name));
+        }
+        Ok(self.bump())
+    }
+
+    fn syntax_error(&self, i: usize, expected: &str) -> ParseError {
+        ParseError::SyntaxError {
+            expected: expected.to_string(),
+            found: format!("{:?}", self.token(i)),
+            span: self.span(i),
+        }
+    }
+
+    /// Pops the innermost open bracket off `open`, failing unless token `i`
+    /// is the matching closing bracket.
+    fn close(&self, open: &mut Vec<usize>, i: usize) -> Result<(), ParseError> {
+        let expected = match open.pop().map(|bracket| self.token(bracket)) {
+            Some(Token::LBrace) => Token::RBrace,
+            Some(Token::LBracket) => Token::RBracket,
+            _ => Token::RParen,
+        };
+        if *self.token(i) != expected {
+            return Err(self.syntax_error(i, &format!("{expected:?}")));
+        }
+        Ok(())
+    }
+
+    /// The error for input ending at token `eof` while the bracket at token
+    /// `open` is unclosed.
+    fn unclosed(&self, open: usize, eof: usize, expected: &str) -> ParseError {
+        match self.token(open) {
+            Token::LBrace => ParseError::UnclosedBrace {
+                opened_at: self.span(open),
+            },
+            _ => self.syntax_error(eof, expected),
+        }
+    }
+
+    /// Consumes the comments in front of the next token, and tells whether a
+    /// blank line comes before them or the token.
+    fn trivia(&mut self) -> (Vec<Comment>, bool) {
+        let mut comments = Vec::new();
+        let mut blank_line = false;
+        loop {
+            let span = self.span(self.pos);
+            let gap = &self.input[self.prev_end.min(span.start)..span.start];
+            blank_line |= self.prev_end > 0 && gap.matches('\n').count() >= 2;
+            let Token::Comment(text) = self.token(self.pos) else {
+                return (comments, blank_line);
+            };
+            comments.push(Comment {
+                text: text.to_string(),
+                span,
+            });
+            self.bump();
+        }
+    }
+
+    /// Whether a top-level item starts at token `i`.
+    fn starts_item(&self, i: usize) -> bool {
+        matches!(
+            self.token(i),
+            Token::QuestKeyword
+                | Token::LetKeyword
+                | Token::IncludeKeyword
+                | Token::Hash
+                | Token::Identifier("npc" | "item" | "template" | "package")
+        )
+    }
+
+    /// Whether an attribute starts at token `i`.
+    fn starts_attribute(&self, i: usize) -> bool {
+        *self.token(i) == Token::Hash && *self.token(self.code(i + 1)) == Token::LBracket
+    }
+
+    /// Whether a property starts at token `i`: a key followed by a colon or
+    /// a block, `reward tier`, `repeat(`, or an attribute in front of one.
+    fn starts_property(&self, i: usize) -> bool {
+        let i = self.code(i);
+        let next = self.token(self.code(i + 1));
+        self.starts_attribute(i)
+            || matches!(self.token(i), Token::Identifier(_))
+                && matches!(next, Token::Colon | Token::LBrace)
+            || *self.token(i) == Token::Identifier("reward") && *next == Token::Identifier("tier")
+            || *self.token(i) == Token::Identifier("repeat") && *next == Token::LParen
+    }
+
+    /// Consumes the attributes in front of a quest or property. Comments
+    /// between them are added to `comments`.
+    fn attributes(&mut self, comments: &mut Vec<Comment>) -> Result<Vec<Attribute>, ParseError> {
+        let mut attributes = Vec::new();
+        while self.starts_attribute(self.pos) {
+            let start = self.bump().start;
+            self.pos = self.code(self.pos);
+            let open = self.span(self.pos).end;
+            let end = self.balanced(Token::LBracket, "LBracket")?;
+            attributes.push(Attribute {
+                text: self.input[open..end - 1].trim().to_string(),
+                span: Span::new(start, end),
+            });
+            comments.extend(self.trivia().0);
+        }
+        Ok(attributes)
+    }
+
+    fn declaration(
+        &mut self,
+        comments: Vec<Comment>,
+        blank_line_before: bool,
+    ) -> Result<Declaration, ParseError> {
+        let keyword = match self.token(self.pos) {
+            Token::Hash => "#".to_string(),
+            _ => {
+                let span = self.span(self.pos);
+                self.input[span.start..span.end].to_string()
+            }
+        };
+        let first = self.bump();
+        // Tokens of the brackets still open, innermost last.
+        let mut open = Vec::new();
+        let mut last = first;
+        loop {
+            let i = self.code(self.pos);
+            match self.token(i) {
+                Token::Eof => match open.last() {
+                    Some(&bracket) => return Err(self.unclosed(bracket, i, "RBrace")),
+                    None => break,
+                },
+                _ if open.is_empty() && self.starts_item(i) => break,
+                Token::LBrace | Token::LBracket | Token::LParen => open.push(i),
+                Token::RBrace | Token::RBracket | Token::RParen if open.is_empty() => {
+                    return Err(self.syntax_error(i, "Declaration"))
+                }
+                Token::RBrace | Token::RBracket | Token::RParen => self.close(&mut open, i)?,
+                _ => {}
+            }
+            self.pos = i;
+            last = self.bump();
+        }
+        Ok(Declaration {
+            keyword,
+            comments,
+            blank_line_before,
+            span: Span::new(first.start, last.end),
+        })
+    }
+
+    fn quest(
+        &mut self,
+        comments: Vec<Comment>,
+        blank_line_before: bool,
+        attributes: Vec<Attribute>,
+    ) -> Result<QuestNode, ParseError> {
+        let start = self.bump().start;
+        self.pos = self.code(self.pos);
+        let name = match self.token(self.pos) {
+            Token::Identifier(name) => name.to_string(),
+            Token::StringLiteral(name) => name.to_string(),
+            _ => return Err(self.syntax_error(self.pos, "Identifier or String")),
+        };
+        let name_span = self.bump();
+        self.pos = self.code(self.pos);
+
+        let kind = match self.token(self.pos) {
+            Token::Identifier("override") => {
+                self.bump();
+                self.pos = self.code(self.pos);
+                QuestKind::Override
+            }
+            Token::Identifier("from") => {
+                self.bump();
+                self.pos = self.code(self.pos);
+                let Token::Identifier(template) = self.token(self.pos) else {
+                    return Err(self.syntax_error(self.pos, "Identifier"));
+                };
+                let template = template.to_string();
+                self.bump();
+                self.pos = self.code(self.pos);
+                let end = self.balanced(Token::LParen, "LParen")?;
+                return Ok(QuestNode {
+                    comments,
+                    blank_line_before,
+                    attributes,
+                    name,
+                    name_span,
+                    kind: QuestKind::Instantiation { template },
+                    properties: Vec::new(),
+                    closing_comments: Vec::new(),
+                    span: Span::new(start, end),
+                });
+            }
+            _ => QuestKind::Definition,
+        };
+        let opened_at = self.expect(Token::LBrace, "LBrace")?;
+
+        let mut properties = Vec::new();
+        loop {
+            let (mut property_comments, blank_line) = self.trivia();
+            let property_attributes = self.attributes(&mut property_comments)?;
+            match self.token(self.pos) {
+                Token::Identifier(_) => {
+                    let property =
+                        self.property(property_comments, blank_line, property_attributes)?;
+                    properties.push(property);
+                }
+                _ if !property_attributes.is_empty() => {
+                    return Err(self.syntax_error(self.pos, "Property Key"))
+                }
+                Token::RBrace => {
+                    let end = self.bump().end;
+                    return Ok(QuestNode {
+                        comments,
+                        blank_line_before,
+                        attributes,
+                        name,
+                        name_span,
+                        kind,
+                        properties,
+                        closing_comments: property_comments,
+                        span: Span::new(start, end),
+                    });
+                }
+                Token::Eof => return Err(ParseError::UnclosedBrace { opened_at }),
+                _ => return Err(self.syntax_error(self.pos, "Property Key")),
+            }
+        }
+    }
+
+    /// Consumes a bracketed group opened by `open`, returning where it ends.
+    fn balanced(&mut self, opener: Token<'_>, name: &str) -> Result<usize, ParseError> {
+        let mut open = vec![self.pos];
+        self.expect(opener, name)?;
+        while let Some(&bracket) = open.last() {
+            let i = self.code(self.pos);
+            match self.token(i) {
+                Token::Eof => return Err(self.unclosed(bracket, i, "Closing bracket")),
+                Token::LBrace | Token::LBracket | Token::LParen => open.push(i),
+                Token::RBrace | Token::RBracket | Token::RParen => self.close(&mut open, i)?,
+                _ => {}
+            }
+            self.pos = i;
+            self.bump();
+        }
+        Ok(self.prev_end)
+    }
+
+    fn property(
+        &mut self,
+        comments: Vec<Comment>,
+        blank_line_before: bool,
+        attributes: Vec<Attribute>,
+    ) -> Result<Property, ParseError> {
+        let key_span = self.bump();
+        let key = self.input[key_span.start..key_span.end].to_string();
+        let colon = self.code(self.pos);
+        let is_block = *self.token(colon) != Token::Colon;
+        if !is_block {
+            self.pos = colon;
+            self.bump();
+        }
+
+        let first = self.code(self.pos);
+        let mut i = first;
+        let mut open = Vec::new();
+        let mut last = None;
+        let mut comma = None;
+        // The name in `reward tier silver { ... }` is not the key of a block.
+        if key == "reward" && *self.token(first) == Token::Identifier("tier") {
+            let name = self.code(first + 1);
+            if matches!(
+                self.token(name),
+                Token::Identifier(_) | Token::StringLiteral(_)
+            ) {
+                last = Some(name);
+                i = self.code(name + 1);
+            }
+        }
+        // `repeat(3) step: "..."` is one property, up to the end of the step.
+        if key == "repeat" && *self.token(first) == Token::LParen {
+            let mut depth = 0;
+            loop {
+                match self.token(i) {
+                    Token::LParen => depth += 1,
+                    Token::RParen => depth -= 1,
+                    Token::Eof => return Err(self.unclosed(first, i, "RParen")),
+                    _ => {}
+                }
+                i = self.code(i + 1);
+                if depth == 0 {
+                    break;
+                }
+            }
+        }
+        loop {
+            let token = self.token(i);
+            if open.is_empty() {
+                match token {
+                    Token::RBrace | Token::Eof => break,
+                    Token::Comma if last.is_none() => {
+                        comma = Some(i);
+                        break;
+                    }
+                    Token::Comma => {
+                        let next = self.code(i + 1);
+                        if matches!(self.token(next), Token::RBrace | Token::Eof)
+                            || self.starts_property(next)
+                        {
+                            comma = Some(i);
+                            break;
+                        }
+                    }
+                    _ if last.is_some() && self.starts_property(i) => break,
+                    _ => {}
+                }
+            }
+            match token {
+                Token::Eof => {
+                    let bracket = *open.last().expect("the value ends at the end of input");
+                    return Err(self.unclosed(bracket, i, "RBrace"));
+                }
+                Token::LBrace | Token::LBracket | Token::LParen => open.push(i),
+                Token::RBracket | Token::RParen if open.is_empty() => {
+                    return Err(self.syntax_error(i, "Value"))
+                }
+                Token::RBrace | Token::RBracket | Token::RParen => self.close(&mut open, i)?,
+                _ => {}
+            }
+            last = Some(i);
+            i = self.code(i + 1);
+        }
+
+        let value_span = match last {
+            Some(last) => Span::new(self.span(first).start, self.span(last).end),
+            None => Span::new(self.prev_end, self.prev_end),
+        };
+        if let Some(last) = last {
+            self.pos = last;
+            self.bump();
+        }
+        if let Some(comma) = comma {
+            self.pos = comma;
+            self.bump();
+        }
+        let span = Span::new(key_span.start, self.prev_end);
+
+        let mut trailing_comment = None;
+        if let Token::Comment(text) = self.token(self.pos) {
+            let comment_span = self.span(self.pos);
+            if !self.input[self.prev_end..comment_span.start].contains('\n') {
+                trailing_comment = Some(Comment {
+                    text: text.to_string(),
+                    span: comment_span,
+                });
+                self.bump();
+            }
+        }
+
+        Ok(Property {
+            comments,
+            blank_line_before,
+            attributes,
+            key,
+            key_span,
+            value: self.input[value_span.start..value_span.end].to_string(),
+            value_span,
+            is_block,
+            comma: comma.is_some(),
+            trailing_comment,
+            span,
+        })
+    }
+}
@@ -0,0 +1,320 @@
+//! Checks quest rewards against the reward designers expect at each player
+//! level, for the `balance` command and design tools.
+
+use thiserror::Error;
+
+use crate::{Quest, Span};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BalanceConfigError {
+    #[error("Invalid balance config: {0}")]
+    Toml(String),
+    #[error("Invalid value for `{key}`: {message}")]
+    InvalidValue { key: String, message: String },
+}
+
+/// Expected reward by player level, through points `(level, reward)`.
+/// Levels between two points are interpolated linearly; levels before the
+/// first point or after the last get the reward of that point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardCurve {
+    /// Sorted by level, without duplicate levels.
+    points: Vec<(i64, f64)>,
+}
+
+impl RewardCurve {
+    /// A curve through `points`, in any order. Fails if there are none, if
+    /// two share a level or if a reward is not positive.
+    pub fn new(points: &[(i64, f64)]) -> Result<RewardCurve, String> {
+        let mut points = points.to_vec();
+        points.sort_by_key(|&(level, _)| level);
+        if points.is_empty() {
+            return Err("a curve needs at least one point".to_string());
+        }
+        if let Some(pair) = points.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(format!("level {} is given twice", pair[0].0));
+        }
+        if let Some(&(level, _)) = points
+            .iter()
+            .find(|&&(_, reward)| reward.is_nan() || reward <= 0.0)
+        {
+            return Err(format!("the reward at level {level} must be positive"));
+        }
+        Ok(RewardCurve { points })
+    }
+
+    /// The reward expected at `level`.
+    pub fn expected(&self, level: f64) -> f64 {
+        let after = self.points.partition_point(|&(at, _)| (at as f64) < level);
+        if after == 0 {
+            return self.points[0].1;
+        }
+        let Some(&(x1, y1)) = self.points.get(after) else {
+            return self.points[after - 1].1;
+        };
+        let (x0, y0) = self.points[after - 1];
+        y0 + (y1 - y0) * (level - x0 as f64) / (x1 - x0) as f64
+    }
+}
+
+/// A curve for the quests with a tag, such as a steeper one for `main`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagCurve {
+    pub tag: String,
+    pub curve: RewardCurve,
+    pub tolerance: f64,
+}
+
+/// The curves quests are checked against, usually read from a balance
+/// config or the `[balance]` table of `quest-parser.toml`:
+///
+/// ```toml
+/// tolerance = 0.5
+/// curve = [[1, 50], [10, 500], [30, 3000]]
+///
+/// [tags.main]
+/// curve = [[1, 100], [30, 6000]]
+/// tolerance = 0.25
+/// ```
+///
+/// Each `curve` lists `[level, reward]` points. A tag's `tolerance` defaults
+/// to the top-level one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceConfig {
+    /// Curve for quests without a tag that has a curve of its own; quests
+    /// are skipped if there is none.
+    pub curve: Option<RewardCurve>,
+    /// How far a reward may be above or below the expected one, as a fraction
+    /// of it, before the quest is an outlier.
+    pub tolerance: f64,
+    /// Curves by tag, in config order. A quest uses the first of them whose
+    /// tag it has.
+    pub tags: Vec<TagCurve>,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> Self {
+        Self {
+            curve: None,
+            tolerance: 0.5,
+            tags: Vec::new(),
+        }
+    }
+}
+
+impl BalanceConfig {
+    /// Reads a config in the format above, starting from the defaults.
+    pub fn from_toml(input: &str) -> Result<BalanceConfig, BalanceConfigError> {
+        let table: toml::Table = input
+            .parse()
+            .map_err(|e: toml::de::Error| BalanceConfigError::Toml(e.to_string()))?;
+        Self::from_table(table)
+    }
+
+    /// Reads the settings of an already parsed config, such as the
+    /// `[balance]` table of a [`ProjectConfig`](crate::ProjectConfig).
+    pub(crate) fn from_table(table: toml::Table) -> Result<BalanceConfig, BalanceConfigError> {
+        let mut config = BalanceConfig::default();
+        // Tag tolerances default to the top-level one wherever it is written.
+        let mut tags = None;
+        for (key, value) in table {
+            match (key.as_str(), value) {
+                ("tolerance", value) => config.tolerance = tolerance(&key, &value)?,
+                ("curve", value) => config.curve = Some(curve(&key, &value)?),
+                ("tags", toml::Value::Table(table)) => tags = Some(table),
+                ("tags", _) => return Err(invalid(&key, "expected a table")),
+                _ => return Err(invalid(&key, "unknown setting")),
+            }
+        }
+        for (tag, value) in tags.unwrap_or_default() {
+            let toml::Value::Table(table) = value else {
+                return Err(invalid(&format!("tags.{tag}"), "expected a table"));
+            };
+            let mut curve_of_tag = None;
+            let mut tolerance_of_tag = config.tolerance;
+            for (name, value) in table {
+                let key = format!("tags.{tag}.{name}");
+                match name.as_str() {
+                    "curve" => curve_of_tag = Some(curve(&key, &value)?),
+                    "tolerance" => tolerance_of_tag = tolerance(&key, &value)?,
+                    _ => return Err(invalid(&key, "unknown setting")),
+                }
+            }
+            let curve =
+                curve_of_tag.ok_or_else(|| invalid(&format!("tags.{tag}"), "missing `curve`"))?;
+            config.tags.push(TagCurve {
+                tag,
+                curve,
+                tolerance: tolerance_of_tag,
+            });
+        }
+        Ok(config)
+    }
+
+    /// The curve and tolerance for `quest`, with the tag it was picked by.
+    fn curve_for(&self, quest: &Quest) -> Option<(Option<&str>, &RewardCurve, f64)> {
+        match self.tags.iter().find(|tag| quest.tags.contains(&tag.tag)) {
+            Some(tag) => Some((Some(tag.tag.as_str()), &tag.curve, tag.tolerance)),
+            None => Some((None, self.curve.as_ref()?, self.tolerance)),
+        }
+    }
+}
+
+fn tolerance(key: &str, value: &toml::Value) -> Result<f64, BalanceConfigError> {
+    let tolerance = match value {
+        toml::Value::Float(x) => *x,
+        toml::Value::Integer(n) => *n as f64,
+        _ => return Err(invalid(key, "expected a number")),
+    };
+    if tolerance.is_nan() || tolerance < 0.0 {
+        return Err(invalid(key, "must not be negative"));
+    }
+    Ok(tolerance)
+}
+
+fn curve(key: &str, value: &toml::Value) -> Result<RewardCurve, BalanceConfigError> {
+    let expected = || invalid(key, "expected a list of [level, reward] points");
+    let points = value.as_array().ok_or_else(expected)?;
+    let points = points
+        .iter()
+        .map(|point| match point.as_array().map(Vec::as_slice) {
+            Some([toml::Value::Integer(level), reward]) => {
+                let reward = match reward {
+                    toml::Value::Integer(n) => *n as f64,
+                    toml::Value::Float(x) => *x,
+                    _ => return Err(expected()),
+                };
+                Ok((*level, reward))
+            }
+            _ => Err(expected()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    RewardCurve::new(&points).map_err(|message| invalid(key, &message))
+}
+
+fn invalid(key: &str, message: &str) -> BalanceConfigError {
+    BalanceConfigError::InvalidValue {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// How one quest's reward compares to its curve.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceEntry {
+    pub quest: String,
+    /// The middle of the quest's `level` range.
+    pub level: f64,
+    pub reward: i64,
+    /// The reward the curve expects at `level`.
+    pub expected: f64,
+    /// `reward / expected`: 1 on the curve, 2 for twice the expected reward.
+    /// The same as the quest's reward per level over the curve's.
+    pub ratio: f64,
+    /// The tag whose curve was used, or `None` for the top-level curve.
+    pub tag: Option<String>,
+    /// Whether the reward is off the curve by more than the tolerance.
+    pub outlier: bool,
+    /// Where the quest's `reward` was written, or its name when the reward
+    /// is the default, and the input it was parsed from, when known.
+    pub span: Option<Span>,
+    pub file: Option<String>,
+}
+
+impl BalanceEntry {
+    /// How far the reward is above (positive) or below (negative) the
+    /// expected one, as a fraction of it.
+    pub fn deviation(&self) -> f64 {
+        self.ratio - 1.0
+    }
+}
+
+/// Orders of a [`BalanceReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceSort {
+    /// Furthest off the curve first, in either direction.
+    Deviation,
+    /// By quest name.
+    Name,
+    /// Lowest level first.
+    Level,
+    /// Highest reward first.
+    Reward,
+}
+
+/// The result of [`balance`]: an entry for each quest with a fixed reward, a
+/// level and a curve, in quest order until sorted.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BalanceReport {
+    pub entries: Vec<BalanceEntry>,
+    /// Names of the quests left out: without a `level`, with a reward given
+    /// as an expression, or without a curve that applies to them.
+    pub skipped: Vec<String>,
+}
+
+impl BalanceReport {
+    pub fn outliers(&self) -> impl Iterator<Item = &BalanceEntry> {
+        self.entries.iter().filter(|entry| entry.outlier)
+    }
+
+    /// Sorts the entries, keeping the quest order of equal ones.
+    pub fn sort_by(&mut self, order: BalanceSort) {
+        match order {
+            BalanceSort::Deviation => self
+                .entries
+                .sort_by(|a, b| b.deviation().abs().total_cmp(&a.deviation().abs())),
+            BalanceSort::Name => self.entries.sort_by(|a, b| a.quest.cmp(&b.quest)),
+            BalanceSort::Level => self.entries.sort_by(|a, b| a.level.total_cmp(&b.level)),
+            BalanceSort::Reward => self
+                .entries
+                .sort_by_key(|entry| std::cmp::Reverse(entry.reward)),
+        }
+    }
+}
+
+/// Compares the reward of every quest with the curve `config` gives it.
+///
+/// ```
+/// use game_quest_parser_Hodik::{balance, BalanceConfig, Parser};
+///
+/// let quests = Parser::new(
+///     r#"quest "Rats" { level: 1..3, reward: 40 }
+///        quest "Dragon" { level: 10..10, reward: 5000 }"#,
+/// )?
+/// .parse_quests()?;
+/// let config = BalanceConfig::from_toml("curve = [[1, 20], [10, 200]]")?;
+/// let report = balance(&quests, &config);
+/// assert_eq!(report.entries[0].expected, 40.0);
+/// let outliers: Vec<_> = report.outliers().map(|entry| &entry.quest).collect();
+/// assert_eq!(outliers, ["Dragon"]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn balance(quests: &[Quest], config: &BalanceConfig) -> BalanceReport {
+    let mut report = BalanceReport::default();
+    for quest in quests {
+        let found = quest
+            .level_range
+            .filter(|_| !quest.expressions.contains_key("reward"))
+            .zip(config.curve_for(quest));
+        let Some(((min, max), (tag, curve, tolerance))) = found else {
+            report.skipped.push(quest.name.clone());
+            continue;
+        };
+        let level = (min as f64 + max as f64) / 2.0;
+        let expected = curve.expected(level);
+        let ratio = quest.reward as f64 / expected;
+        let map = quest.source_map();
+        report.entries.push(BalanceEntry {
+            quest: quest.name.clone(),
+            level,
+            reward: quest.reward,
+            expected,
+            ratio,
+            tag: tag.map(str::to_string),
+            outlier: (ratio - 1.0).abs() > tolerance,
+            span: map.property("reward").or(map.name()),
+            file: map.file().map(str::to_string),
+        });
+    }
+    report
+}
@@ -0,0 +1,474 @@
+//! Language server for quest files, speaking LSP over stdio.
+//!
+//! Publishes parse errors and warnings as diagnostics whenever a document
+//! changes, completes what fits the grammar at the cursor, and shows property
+//! docs on hover.
+//!
+//! Documents are parsed with the options the CLI would use: the nearest
+//! quest-parser.toml above the document, then the `--strict`, `--no-strict`
+//! and `--flag NAME` arguments the server was started with.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+
+use game_quest_parser_Hodik::{
+    ast, property_info, CompletionContext, Lexer, ParseError, Parser, ParserOptions, ProjectConfig,
+    Span, Token, ValueKind, KNOWN_PROPERTIES,
+};
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{Completion, HoverRequest, Request as RequestTrait};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse, Diagnostic,
+    DiagnosticSeverity, Documentation, Hover, HoverContents, HoverProviderCapability,
+    InitializeParams, MarkupContent, MarkupKind, NumberOrString, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, Url,
+};
+
+const KEYWORDS: &[&str] = &[
+    "quest", "let", "include", "package", "npc", "item", "template",
+];
+const OBJECTIVE_KINDS: &[&str] = &["kill", "collect", "talk_to", "reach"];
+const STEP_ATTRIBUTES: &[&str] = &["optional", "hidden", "order"];
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+fn main() -> Result<()> {
+    let flags = Flags::from_args(std::env::args().skip(1))?;
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions::default()),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        ..Default::default()
+    };
+    let init = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let _params: InitializeParams = serde_json::from_value(init)?;
+
+    let mut server = Server {
+        connection,
+        documents: HashMap::new(),
+        flags,
+    };
+    server.run()?;
+    let Server { connection, .. } = server;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+/// The command-line arguments that change how documents are parsed, as on
+/// the CLI's `parse`.
+#[derive(Default)]
+struct Flags {
+    /// `Some(true)` for `--strict`, `Some(false)` for `--no-strict`.
+    strict: Option<bool>,
+    features: HashSet<String>,
+}
+
+impl Flags {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Flags> {
+        let mut flags = Flags::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--strict" => flags.strict = Some(true),
+                "--no-strict" => flags.strict = Some(false),
+                "--flag" => {
+                    let name = args.next().ok_or("--flag needs the name of a flag")?;
+                    flags.features.insert(name);
+                }
+                // Editors often pass this to pick the transport.
+                "--stdio" => {}
+                _ => return Err(format!("unknown argument {arg:?}").into()),
+            }
+        }
+        Ok(flags)
+    }
+
+    /// The options to parse a document in `dir` with: those of the nearest
+    /// project config, overridden by the flags.
+    fn options(&self, dir: Option<&Path>) -> std::result::Result<ParserOptions, String> {
+        let project = match dir.and_then(ProjectConfig::find) {
+            Some(path) => {
+                ProjectConfig::load(&path).map_err(|e| format!("In {}: {e}", path.display()))?
+            }
+            None => ProjectConfig::default(),
+        };
+        let mut options = project.parser_options();
+        options.strict = self.strict.unwrap_or(options.strict);
+        options.flags.extend(self.features.iter().cloned());
+        Ok(options)
+    }
+}
+
+struct Server {
+    connection: Connection,
+    documents: HashMap<Url, String>,
+    flags: Flags,
+}
+
+impl Server {
+    fn run(&mut self) -> Result<()> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    self.handle_request(request)?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: Request) -> Result<()> {
+        let (id, result) = match request.method.as_str() {
+            Completion::METHOD => {
+                let (id, params) =
+                    request.extract::<lsp_types::CompletionParams>(Completion::METHOD)?;
+                let position = params.text_document_position;
+                let items = self
+                    .documents
+                    .get(&position.text_document.uri)
+                    .map(|text| completion_items(text, position.position));
+                (id, serde_json::to_value(items)?)
+            }
+            HoverRequest::METHOD => {
+                let (id, params) =
+                    request.extract::<lsp_types::HoverParams>(HoverRequest::METHOD)?;
+                let position = params.text_document_position_params;
+                let hover = self
+                    .documents
+                    .get(&position.text_document.uri)
+                    .and_then(|text| hover_at(text, position.position));
+                (id, serde_json::to_value(hover)?)
+            }
+            _ => {
+                let response = Response::new_err(
+                    request.id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("unsupported request {}", request.method),
+                );
+                self.connection.sender.send(response.into())?;
+                return Ok(());
+            }
+        };
+        self.respond(id, result)
+    }
+
+    fn respond(&self, id: RequestId, result: serde_json::Value) -> Result<()> {
+        let response = Response {
+            id,
+            result: Some(result),
+            error: None,
+        };
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let document = params.text_document;
+                self.update(document.uri, document.text)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // Full sync: the last change holds the whole document.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.update(params.text_document.uri, change.text)?;
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                self.publish(params.text_document.uri, Vec::new())?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, uri: Url, text: String) -> Result<()> {
+        let diagnostics = diagnostics_for(&uri, &text, &self.flags);
+        self.documents.insert(uri.clone(), text);
+        self.publish(uri, diagnostics)
+    }
+
+    fn publish(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> Result<()> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+}
+
+/// The warnings and the error, if any, of parsing `text`.
+fn diagnostics_for(uri: &Url, text: &str, flags: &Flags) -> Vec<Diagnostic> {
+    let path = uri.to_file_path().ok();
+    let options = match flags.options(path.as_deref().and_then(Path::parent)) {
+        Ok(options) => options,
+        Err(message) => {
+            return vec![Diagnostic {
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("quest".to_string()),
+                message,
+                ..Default::default()
+            }]
+        }
+    };
+    let mut parser = match Parser::with_options(text, options) {
+        Ok(parser) => parser,
+        Err(error) => return vec![to_diagnostic(text, &error)],
+    };
+    if let Some(path) = &path {
+        parser = parser.with_source_name(path.display().to_string());
+    }
+    let result = parser.parse_quests();
+    let mut diagnostics: Vec<Diagnostic> = parser
+        .diagnostics()
+        .warnings()
+        .iter()
+        .map(|warning| warning_to_diagnostic(text, warning))
+        .collect();
+    if let Err(error) = result {
+        diagnostics.push(to_diagnostic(text, &error));
+    }
+    diagnostics
+}
+
+fn warning_to_diagnostic(text: &str, warning: &game_quest_parser_Hodik::Diagnostic) -> Diagnostic {
+    use game_quest_parser_Hodik::Diagnostic as Warning;
+
+    // Warnings from included files are reported at the top of the document,
+    // like their errors.
+    let range = match warning {
+        Warning::InInclude { .. } => Range::default(),
+        _ => Range::new(
+            offset_to_position(text, warning.span().start),
+            offset_to_position(text, warning.span().end),
+        ),
+    };
+    let mut message = warning.to_string();
+    match warning {
+        Warning::UnknownProperty {
+            suggestion: Some(name),
+            ..
+        } => message.push_str(&format!(" (did you mean `{name}`?)")),
+        Warning::Deprecated { replacement, .. } => {
+            message.push_str(&format!(" (write `{replacement}` instead)"))
+        }
+        _ => {}
+    }
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::WARNING),
+        source: Some("quest".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+fn to_diagnostic(text: &str, error: &ParseError) -> Diagnostic {
+    // Errors inside included files have spans into those files; report them at
+    // the top of the including document instead.
+    let range = match (error, error.span()) {
+        (ParseError::InInclude { .. }, _) | (_, None) => match error {
+            ParseError::UnexpectedEOF => {
+                let end = offset_to_position(text, text.len());
+                Range::new(end, end)
+            }
+            _ => Range::default(),
+        },
+        (_, Some(span)) => Range::new(
+            offset_to_position(text, span.start),
+            offset_to_position(text, span.end),
+        ),
+    };
+    let mut message = error.to_string();
+    if let ParseError::UnknownProperty {
+        suggestion: Some(name),
+        ..
+    } = error
+    {
+        message.push_str(&format!(" (did you mean `{name}`?)"));
+    }
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String(error.code().to_string())),
+        source: Some("quest".to_string()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// What may be typed at `position`: keywords between items, the property
+/// keys a quest has not set yet, and values such as quest names for
+/// `requires` or objective kinds.
+fn completion_items(text: &str, position: Position) -> CompletionResponse {
+    let partial = Parser::parse_partial(text, position_to_offset(text, position));
+    let words = |words: &[&str], kind| -> Vec<CompletionItem> {
+        words
+            .iter()
+            .map(|&word| CompletionItem {
+                label: word.to_string(),
+                kind: Some(kind),
+                ..Default::default()
+            })
+            .collect()
+    };
+    // Quoted unless the cursor is already in a string.
+    let strings = |words: Vec<&str>, kind| -> Vec<CompletionItem> {
+        words
+            .into_iter()
+            .map(|word| CompletionItem {
+                label: word.to_string(),
+                insert_text: (!partial.in_string).then(|| format!("{word:?}")),
+                kind: Some(kind),
+                ..Default::default()
+            })
+            .collect()
+    };
+    let items = match partial.context {
+        CompletionContext::TopLevel => words(KEYWORDS, CompletionItemKind::KEYWORD),
+        CompletionContext::PropertyKey { scope, present } => match scope.block.as_deref() {
+            _ if scope.keyword != "quest" && scope.keyword != "template" => Vec::new(),
+            None => KNOWN_PROPERTIES
+                .iter()
+                .filter(|property| {
+                    matches!(property.name, "step" | "requires" | "objective")
+                        || !present.iter().any(|key| key == property.name)
+                })
+                .map(|property| CompletionItem {
+                    label: property.name.to_string(),
+                    kind: Some(CompletionItemKind::PROPERTY),
+                    documentation: Some(Documentation::String(property.doc.to_string())),
+                    ..Default::default()
+                })
+                .collect(),
+            Some("objective") => words(&["kind", "target", "count"], CompletionItemKind::FIELD),
+            Some("on_success" | "on_failure") => {
+                words(&["reward", "penalty", "unlock"], CompletionItemKind::FIELD)
+            }
+            Some(_) => Vec::new(),
+        },
+        CompletionContext::PropertyValue { scope, kind, .. } => match kind {
+            ValueKind::Bool => words(&["true", "false"], CompletionItemKind::VALUE),
+            ValueKind::ObjectiveKind => {
+                strings(OBJECTIVE_KINDS.to_vec(), CompletionItemKind::ENUM_MEMBER)
+            }
+            ValueKind::QuestName => strings(
+                partial
+                    .quests
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|&name| name != scope.name)
+                    .collect(),
+                CompletionItemKind::REFERENCE,
+            ),
+            _ => Vec::new(),
+        },
+        CompletionContext::StepAttribute { .. } => {
+            words(STEP_ATTRIBUTES, CompletionItemKind::KEYWORD)
+        }
+        _ => Vec::new(),
+    };
+    CompletionResponse::Array(items)
+}
+
+fn hover_at(text: &str, position: Position) -> Option<Hover> {
+    let offset = position_to_offset(text, position);
+    let (name, span) = key_at(text, offset)?;
+    let info = property_info(&name)?;
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!("**{}**\n\n{}", info.name, info.doc),
+        }),
+        range: Some(Range::new(
+            offset_to_position(text, span.start),
+            offset_to_position(text, span.end),
+        )),
+    })
+}
+
+/// The property key at `offset`. While the document has a syntax error any
+/// identifier there is taken as a key.
+fn key_at(text: &str, offset: usize) -> Option<(String, Span)> {
+    if let Ok(tree) = ast::parse(text) {
+        let (_, property) = tree.key_at(offset)?;
+        return Some((property.key.clone(), property.key_span));
+    }
+    let (token, span) = Lexer::new(text)
+        .map_while(|token| token.ok())
+        .find(|(_, span)| span.start <= offset && offset <= span.end)?;
+    match token {
+        Token::Identifier(name) => Some((name.to_string(), span)),
+        _ => None,
+    }
+}
+
+/// Converts a byte offset into an LSP position, whose character offsets count
+/// UTF-16 code units. Lines end at `\n`, `\r\n` or a lone `\r`, as in LSP.
+fn offset_to_position(text: &str, offset: usize) -> Position {
+    let mut line = 0;
+    let mut character = 0;
+    for (i, c) in text.char_indices() {
+        if i >= offset {
+            break;
+        }
+        if ends_line(text, i, c) {
+            line += 1;
+            character = 0;
+        } else if c != '\r' {
+            character += c.len_utf16() as u32;
+        }
+    }
+    Position::new(line, character)
+}
+
+fn position_to_offset(text: &str, position: Position) -> usize {
+    let mut line = 0;
+    let mut character = 0;
+    for (i, c) in text.char_indices() {
+        if line == position.line && character >= position.character {
+            return i;
+        }
+        if c == '\r' || c == '\n' {
+            if line == position.line {
+                return i;
+            }
+            if ends_line(text, i, c) {
+                line += 1;
+                character = 0;
+            }
+        } else {
+            character += c.len_utf16() as u32;
+        }
+    }
+    text.len()
+}
+
+/// Whether `c`, at byte `i` of `text`, ends a line: a `\n`, or a `\r` not
+/// followed by one.
+fn ends_line(text: &str, i: usize, c: char) -> bool {
+    c == '\n' || (c == '\r' && !text[i + 1..].starts_with('\n'))
+}
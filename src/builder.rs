@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+
+use crate::encoding::read_source;
+use crate::{
+    DynamicQuest, IncludeResolver, IndexMap, ParseError, Parser, ParserLimits, ParserOptions,
+    Quest, QuestSchema,
+};
+
+/// Parser settings configured once and shared by many parses, such as the
+/// validation step of an asset pipeline.
+///
+/// The builder is `Send + Sync` and cheap to clone: clones share the include
+/// resolver, schema and cache, so one can be handed to every worker thread.
+///
+/// ```
+/// use game_quest_parser_Hodik::{ParserBuilder, ParserLimits};
+///
+/// let builder = ParserBuilder::new()
+///     .with_limits(ParserLimits::UNTRUSTED)
+///     .with_cache();
+/// let input = r#"quest "Intro" { reward: 50 }"#;
+/// assert_eq!(builder.parse_quests(input)?[0].reward, 50);
+/// builder.parse_quests(input)?;
+/// assert_eq!(builder.cache().map(|cache| cache.hits()), Some(1));
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+#[derive(Clone, Default)]
+pub struct ParserBuilder {
+    options: ParserOptions,
+    resolver: Option<Arc<dyn IncludeResolver>>,
+    schema: Option<Arc<QuestSchema>>,
+    cache: Option<Arc<ParseCache>>,
+}
+
+impl ParserBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces every option, limits included.
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: ParserLimits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Replaces the [`FsIncludeResolver`](crate::FsIncludeResolver) the
+    /// parsers would otherwise build from
+    /// [`ParserOptions::include_paths`].
+    pub fn with_include_resolver(mut self, resolver: impl IncludeResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// The schema [`ParserBuilder::parse_dynamic`] checks quests against.
+    pub fn with_schema(mut self, schema: QuestSchema) -> Self {
+        self.schema = Some(Arc::new(schema));
+        self
+    }
+
+    /// Keeps the quests of every input parsed with
+    /// [`ParserBuilder::parse_quests`] or [`ParserBuilder::parse_file`], so
+    /// parsing the same text under the same name again skips the parser.
+    /// See [`ParseCache`] for what it keeps.
+    pub fn with_cache(mut self) -> Self {
+        self.cache = Some(Arc::new(ParseCache::default()));
+        self
+    }
+
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    pub fn schema(&self) -> Option<&QuestSchema> {
+        self.schema.as_deref()
+    }
+
+    pub fn cache(&self) -> Option<&ParseCache> {
+        self.cache.as_deref()
+    }
+
+    /// A parser for `input` with the builder's options and include resolver.
+    pub fn parser<'a>(&self, input: &'a str) -> Result<Parser<'a>, ParseError> {
+        let mut parser = Parser::with_options(input, self.options.clone())?;
+        if let Some(resolver) = &self.resolver {
+            parser.resolver = Arc::clone(resolver);
+        }
+        Ok(parser)
+    }
+
+    /// Parses every quest of `input`, or returns them from the cache.
+    pub fn parse_quests(&self, input: &str) -> Result<Vec<Quest>, ParseError> {
+        self.parse_cached(input, None)
+    }
+
+    /// Reads and parses every quest of one file, naming the input after its
+    /// path so relative includes resolve against it. The cache is keyed by
+    /// the path and checked against the file's contents, so it still reads
+    /// the file.
+    pub fn parse_file(&self, path: &Path) -> Result<Vec<Quest>, ParseError> {
+        let source = read_source(path)?;
+        self.parse_cached(&source, Some(&path.display().to_string()))
+    }
+
+    /// Parses every quest of `input` against the builder's schema, see
+    /// [`Parser::parse_dynamic`]. Without a schema every property is
+    /// unknown. Dynamic quests are not cached.
+    pub fn parse_dynamic(&self, input: &str) -> Result<Vec<DynamicQuest>, ParseError> {
+        let schema = self.schema.as_deref().cloned().unwrap_or_default();
+        self.parser(input)?.parse_dynamic_quests(&schema)
+    }
+
+    fn parse_cached(&self, input: &str, name: Option<&str>) -> Result<Vec<Quest>, ParseError> {
+        if let Some(quests) = self.cache.as_ref().and_then(|cache| cache.get(input, name)) {
+            return Ok(quests);
+        }
+        let mut parser = self.parser(input)?;
+        if let Some(name) = name {
+            parser = parser.with_source_name(name);
+        }
+        let quests = parser.parse_quests()?;
+        // Included files are not part of the key, so a change to one would
+        // go unnoticed.
+        if let (Some(cache), false) = (&self.cache, parser.resolved_includes) {
+            cache.insert(input, name, &quests);
+        }
+        Ok(quests)
+    }
+}
+
+/// Quests of inputs parsed before, keyed by a SHA-256 digest of the input
+/// so that the text itself is not kept.
+///
+/// A named input, such as a file, has one entry under its name, holding the
+/// digest of its text and its quests. The entry answers only a parse of the
+/// same text, and is dropped once the text changes, so a file edited many
+/// times is kept once. Inputs without a name are kept under their digest,
+/// and only the [`ParseCache::UNNAMED_CAPACITY`] most recently used of them.
+/// Inputs that include other files and inputs that failed to parse are not
+/// kept.
+#[derive(Debug, Default)]
+pub struct ParseCache {
+    entries: Mutex<Entries>,
+    hits: AtomicUsize,
+}
+
+type InputDigest = [u8; 32];
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// The digest of the text and the quests of each named input.
+    named: HashMap<String, (InputDigest, Arc<[Quest]>)>,
+    /// Least recently used first.
+    unnamed: IndexMap<InputDigest, Arc<[Quest]>>,
+}
+
+impl ParseCache {
+    /// How many inputs without a name are kept at most.
+    pub const UNNAMED_CAPACITY: usize = 256;
+
+    /// Number of inputs whose quests are kept.
+    pub fn len(&self) -> usize {
+        let entries = self.entries();
+        entries.named.len() + entries.unnamed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many parses were answered from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries();
+        entries.named.clear();
+        entries.unnamed.clear();
+    }
+
+    /// The quests of `input` if it was parsed under `name` before. An entry
+    /// for `name` with other text is stale and dropped.
+    fn get(&self, input: &str, name: Option<&str>) -> Option<Vec<Quest>> {
+        let digest = digest(input);
+        let mut entries = self.entries();
+        let quests = match name {
+            Some(name) => match entries.named.get(name) {
+                Some((known, quests)) if *known == digest => Arc::clone(quests),
+                Some(_) => {
+                    entries.named.remove(name);
+                    return None;
+                }
+                None => return None,
+            },
+            None => {
+                let index = entries.unnamed.get_index_of(&digest)?;
+                let last = entries.unnamed.len() - 1;
+                entries.unnamed.move_index(index, last);
+                Arc::clone(&entries.unnamed[last])
+            }
+        };
+        drop(entries);
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(quests.to_vec())
+    }
+
+    fn insert(&self, input: &str, name: Option<&str>, quests: &[Quest]) {
+        let digest = digest(input);
+        let mut entries = self.entries();
+        match name {
+            Some(name) => {
+                entries
+                    .named
+                    .insert(name.to_string(), (digest, quests.into()));
+            }
+            None => {
+                if entries.unnamed.len() >= Self::UNNAMED_CAPACITY {
+                    entries.unnamed.shift_remove_index(0);
+                }
+                entries.unnamed.insert(digest, quests.into());
+            }
+        }
+    }
+
+    /// The entries, even if a thread panicked while holding the lock: every
+    /// write leaves them consistent.
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn digest(input: &str) -> InputDigest {
+    Sha256::digest(input.as_bytes()).into()
+}
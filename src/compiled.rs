@@ -0,0 +1,55 @@
+//! Pre-parsed binary quest packs, so games can load quests without parsing
+//! text at runtime.
+
+use thiserror::Error;
+
+use crate::Quest;
+
+/// Bytes every pack starts with.
+const MAGIC: &[u8; 4] = b"GQPK";
+/// Revision of the pack layout, bumped whenever [`Quest`] changes shape.
+const FORMAT_VERSION: u8 = 3;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum LoadError {
+    #[error("Not a compiled quest pack")]
+    NotAPack,
+    #[error("Quest pack has format {0}, but this build reads format {FORMAT_VERSION}")]
+    UnsupportedFormat(u8),
+    #[error("Corrupt quest pack: {0}")]
+    Corrupt(String),
+}
+
+/// Encodes already parsed quests as a compact binary pack that
+/// [`load_compiled`] reads back.
+///
+/// A pack is a short header followed by the quests as CBOR. Packs are tied to
+/// the version of this crate that wrote them; recompile them from source after
+/// upgrading.
+pub fn compile(quests: &[Quest]) -> Vec<u8> {
+    let mut pack = MAGIC.to_vec();
+    pack.push(FORMAT_VERSION);
+    ciborium::into_writer(quests, &mut pack).expect("writing to a Vec cannot fail");
+    pack
+}
+
+/// Reads the quests of a pack written by [`compile`].
+///
+/// ```
+/// use game_quest_parser_Hodik::{compile, load_compiled, Parser};
+///
+/// let quests = Parser::new(r#"quest "Wolves" { reward: 40 }"#)?.parse_quests()?;
+/// let pack = compile(&quests);
+/// assert_eq!(load_compiled(&pack).unwrap(), quests);
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn load_compiled(bytes: &[u8]) -> Result<Vec<Quest>, LoadError> {
+    let body = bytes.strip_prefix(MAGIC).ok_or(LoadError::NotAPack)?;
+    match body.split_first() {
+        Some((&FORMAT_VERSION, quests)) => {
+            ciborium::from_reader(quests).map_err(|e| LoadError::Corrupt(e.to_string()))
+        }
+        Some((&version, _)) => Err(LoadError::UnsupportedFormat(version)),
+        None => Err(LoadError::NotAPack),
+    }
+}
@@ -0,0 +1,131 @@
+use core::fmt;
+
+use crate::prelude::*;
+use crate::{BinaryOp, Expr};
+
+/// When a quest counts as completed, from a `complete_when:` property that
+/// joins objective names with `&&` and `||`.
+///
+/// ```text
+/// complete_when: kill_goblins && (find_key || bribe_guard)
+/// ```
+///
+/// The names are the game's own identifiers for its objectives; the parser
+/// does not check them against the quest's `objective` blocks. Evaluate a
+/// condition against the player's [`ProgressState`] to use the same rules
+/// the parser read at runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Condition {
+    /// Holds once the named objective is done.
+    Objective(String),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    /// Builds a condition from a parsed expression, if it only joins names
+    /// with `&&` and `||`.
+    pub(crate) fn from_expr(expr: Expr) -> Option<Condition> {
+        match expr {
+            Expr::Identifier(name) => Some(Condition::Objective(name)),
+            Expr::Binary { op, lhs, rhs } => {
+                let lhs = Box::new(Condition::from_expr(*lhs)?);
+                let rhs = Box::new(Condition::from_expr(*rhs)?);
+                match op {
+                    BinaryOp::And => Some(Condition::And(lhs, rhs)),
+                    BinaryOp::Or => Some(Condition::Or(lhs, rhs)),
+                    _ => None,
+                }
+            }
+            Expr::Literal(_) => None,
+        }
+    }
+
+    /// Whether the condition holds for the objectives `progress` marks as
+    /// done. Objectives it does not know of are not done.
+    pub fn evaluate(&self, progress: &ProgressState) -> bool {
+        match self {
+            Condition::Objective(name) => progress.is_done(name),
+            Condition::And(lhs, rhs) => lhs.evaluate(progress) && rhs.evaluate(progress),
+            Condition::Or(lhs, rhs) => lhs.evaluate(progress) || rhs.evaluate(progress),
+        }
+    }
+
+    /// Every objective the condition names, in source order and without
+    /// repeats.
+    pub fn objectives(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        let mut pending = vec![self];
+        while let Some(condition) = pending.pop() {
+            match condition {
+                Condition::Objective(name) => {
+                    if !names.contains(&name.as_str()) {
+                        names.push(name);
+                    }
+                }
+                Condition::And(lhs, rhs) | Condition::Or(lhs, rhs) => {
+                    pending.push(rhs);
+                    pending.push(lhs);
+                }
+            }
+        }
+        names
+    }
+}
+
+impl fmt::Display for Condition {
+    /// Writes the condition as it would appear after `complete_when:`, with
+    /// only the parentheses needed to read it back the same.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let grouped = |f: &mut fmt::Formatter<'_>, condition: &Condition, parens: bool| {
+            if parens {
+                write!(f, "({condition})")
+            } else {
+                write!(f, "{condition}")
+            }
+        };
+        match self {
+            Condition::Objective(name) => f.write_str(name),
+            Condition::And(lhs, rhs) => {
+                grouped(f, lhs, matches!(**lhs, Condition::Or(..)))?;
+                f.write_str(" && ")?;
+                grouped(f, rhs, !matches!(**rhs, Condition::Objective(_)))
+            }
+            Condition::Or(lhs, rhs) => {
+                write!(f, "{lhs} || ")?;
+                grouped(f, rhs, matches!(**rhs, Condition::Or(..)))
+            }
+        }
+    }
+}
+
+/// Which objectives a player has done, for [`Condition::evaluate`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ProgressState {
+    done: HashSet<String>,
+}
+
+impl ProgressState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks the named objective as done.
+    pub fn complete(&mut self, objective: impl Into<String>) {
+        self.done.insert(objective.into());
+    }
+
+    pub fn is_done(&self, objective: &str) -> bool {
+        self.done.contains(objective)
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for ProgressState {
+    fn from_iter<I: IntoIterator<Item = S>>(objectives: I) -> Self {
+        ProgressState {
+            done: objectives.into_iter().map(Into::into).collect(),
+        }
+    }
+}
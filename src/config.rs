@@ -0,0 +1,156 @@
+//! Project-wide defaults read from `quest-parser.toml`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::{
+    BalanceConfig, BalanceConfigError, GrammarVersion, LintConfig, LintConfigError, ParserOptions,
+};
+
+/// Name of the project config the CLI looks up in the working directory and
+/// its parent directories.
+pub const PROJECT_CONFIG_FILE: &str = "quest-parser.toml";
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ConfigError {
+    #[error("Cannot read {path:?}: {message}")]
+    Read { path: PathBuf, message: String },
+    #[error("Invalid project config: {0}")]
+    Toml(String),
+    #[error("Invalid value for `{key}`: {message}")]
+    InvalidValue { key: String, message: String },
+    #[error("In [lint]: {0}")]
+    Lint(#[from] LintConfigError),
+    #[error("In [balance]: {0}")]
+    Balance(#[from] BalanceConfigError),
+}
+
+/// Defaults a team shares for every invocation of the CLI, so they do not
+/// have to be passed as flags. Flags given on the command line still win.
+///
+/// ```toml
+/// strict = true
+/// version = 2
+/// include-paths = ["shared"]
+/// format = "json"
+///
+/// [lint]
+/// max-steps = 30
+/// rules = { zero-reward = "off" }
+///
+/// [balance]
+/// curve = [[1, 50], [30, 3000]]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectConfig {
+    /// Reject unknown properties, as `parse --strict` does.
+    pub strict: bool,
+    /// Grammar version of files without a `#version` pragma.
+    pub version: GrammarVersion,
+    /// Directories searched for includes that are not found next to the
+    /// including file, as written in the config: relative paths are relative
+    /// to the directory holding it.
+    pub include_paths: Vec<PathBuf>,
+    /// Print JSON from every command that can, as `--format json` does.
+    pub json: bool,
+    /// Lint rules in the `.questlint.toml` format, used when no
+    /// `.questlint.toml` is closer to the linted file.
+    pub lint: Option<LintConfig>,
+    /// Reward curves in the [`BalanceConfig`] format, used by `balance`
+    /// when it is not given a config of its own.
+    pub balance: Option<BalanceConfig>,
+}
+
+impl ProjectConfig {
+    /// Reads a config in the `quest-parser.toml` format, starting from the
+    /// defaults.
+    pub fn from_toml(input: &str) -> Result<ProjectConfig, ConfigError> {
+        let table: toml::Table = input
+            .parse()
+            .map_err(|e: toml::de::Error| ConfigError::Toml(e.to_string()))?;
+        let mut config = ProjectConfig::default();
+        for (key, value) in table {
+            match (key.as_str(), value) {
+                ("strict", toml::Value::Boolean(strict)) => config.strict = strict,
+                ("version", toml::Value::Integer(n)) => {
+                    config.version = GrammarVersion::from_number(n).ok_or_else(|| {
+                        invalid(
+                            &key,
+                            &format!("the latest grammar version is {}", GrammarVersion::LATEST),
+                        )
+                    })?;
+                }
+                ("include-paths", toml::Value::Array(paths)) => {
+                    for path in paths {
+                        let path = path
+                            .as_str()
+                            .ok_or_else(|| invalid(&key, "expected a list of paths"))?;
+                        config.include_paths.push(PathBuf::from(path));
+                    }
+                }
+                ("format", toml::Value::String(format)) => {
+                    config.json = match format.as_str() {
+                        "text" => false,
+                        "json" => true,
+                        _ => return Err(invalid(&key, "expected \"text\" or \"json\"")),
+                    };
+                }
+                ("lint", toml::Value::Table(lint)) => {
+                    config.lint = Some(LintConfig::from_table(lint)?);
+                }
+                ("balance", toml::Value::Table(balance)) => {
+                    config.balance = Some(BalanceConfig::from_table(balance)?);
+                }
+                ("strict", _) => return Err(invalid(&key, "expected a boolean")),
+                ("version", _) => return Err(invalid(&key, "expected an integer")),
+                ("include-paths", _) => return Err(invalid(&key, "expected a list of paths")),
+                ("format", _) => return Err(invalid(&key, "expected a string")),
+                ("lint", _) | ("balance", _) => return Err(invalid(&key, "expected a table")),
+                _ => return Err(invalid(&key, "unknown setting")),
+            }
+        }
+        Ok(config)
+    }
+
+    /// The nearest [`PROJECT_CONFIG_FILE`] in `dir` or one of its parents.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Reads the config file at `path`, with its include paths joined to the
+    /// directory holding it.
+    pub fn load(path: &Path) -> Result<ProjectConfig, ConfigError> {
+        let source = fs::read_to_string(path).map_err(|e| ConfigError::Read {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        let mut config = ProjectConfig::from_toml(&source)?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+        for include in &mut config.include_paths {
+            *include = dir.join(&*include);
+        }
+        Ok(config)
+    }
+
+    /// Parser options with the config's strictness, grammar version and
+    /// include paths, and defaults for everything else.
+    pub fn parser_options(&self) -> ParserOptions {
+        ParserOptions {
+            strict: self.strict,
+            version: self.version,
+            include_paths: self.include_paths.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+fn invalid(key: &str, message: &str) -> ConfigError {
+    ConfigError::InvalidValue {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
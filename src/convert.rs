@@ -0,0 +1,739 @@
+//! Converting quests between the DSL and TOML, YAML or JSON documents.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::lexer::parse_duration;
+use crate::package::is_valid_package;
+use crate::writer::{format_duration, write_quests};
+use crate::{
+    Action, Condition, Currency, DateTime, Expr, GrammarVersion, NpcRef, Objective, Outcome,
+    ParseError, Parser, ParserOptions, Quest, RewardTier, Stage, StateMachine, Step, StepTree,
+    Text, Token, Transition, Value,
+};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ConvertError {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("Invalid TOML: {0}")]
+    Toml(String),
+    #[error("Invalid YAML: {0}")]
+    Yaml(String),
+    #[error("Invalid JSON: {0}")]
+    Json(String),
+    #[error("Invalid `{field}` in quest {quest:?}: {message}")]
+    InvalidField {
+        quest: String,
+        field: String,
+        message: String,
+    },
+}
+
+/// A representation quests can be converted from and to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Dsl,
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl Format {
+    /// Guesses the format from a file extension: `.quest` and `.txt` files
+    /// hold DSL, `.toml` TOML, `.yaml` or `.yml` YAML, and `.json` JSON.
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "quest" | "txt" => Some(Format::Dsl),
+            "toml" => Some(Format::Toml),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Converts a document holding quests from one format to another.
+///
+/// DSL input is resolved as by [`Parser::parse_world`], so `let` constants
+/// are substituted and included quests are inlined; NPC and item
+/// definitions cannot be converted and are rejected with
+/// [`ParseError::Unformattable`]. DSL output is canonical, as written by
+/// [`to_quest_string`](crate::to_quest_string).
+pub fn convert(input: &str, from: Format, to: Format) -> Result<String, ConvertError> {
+    let quests = match from {
+        Format::Dsl => parse_dsl(input)?,
+        Format::Toml => from_toml(input)?,
+        Format::Yaml => from_yaml(input)?,
+        Format::Json => from_json_many(input)?,
+    };
+    match to {
+        Format::Dsl => Ok(write_quests(&quests, GrammarVersion::V1)),
+        Format::Toml => to_toml(&quests),
+        Format::Yaml => to_yaml(&quests),
+        Format::Json => to_json(&quests),
+    }
+}
+
+/// Renders quests as a TOML document with one `[[quest]]` table per quest.
+///
+/// ```toml
+/// [[quest]]
+/// name = "Wolves"
+/// package = "act1.village"
+/// active = true
+/// reward = 40
+/// rewards = [["gems", 5]]
+/// steps = ["Hunt", { en = "Return", uk = "Повернись" }]
+/// stages = [["start", "hunt"], ["hunt", "end"]]
+///
+/// [[quest.objective]]
+/// kind = "kill"
+/// target = "Wolf"
+/// count = 3
+///
+/// [[quest.reward_tier]]
+/// name = "gold"
+/// rewards = [["gold", 500], ["gems", 10]]
+///
+/// [quest.on_success]
+/// reward = 100
+/// unlocks = ["Den"]
+///
+/// [quest.extra]
+/// region = "North"
+///
+/// [quest.expressions]
+/// xp = "level * 10"
+/// ```
+///
+/// Currency amounts are written as `[currency, amount]` pairs and stage flows as
+/// `[from, to]` pairs in which `start` and `end` name the implicit stages.
+/// Expressions and the time limit are written as their source text, such as
+/// `"level * 10"` and `"2h30m"`.
+pub fn to_toml(quests: &[Quest]) -> Result<String, ConvertError> {
+    toml::to_string(&Document::from(quests)).map_err(|e| ConvertError::Toml(e.to_string()))
+}
+
+/// Reads quests from a TOML document laid out as by [`to_toml`].
+pub fn from_toml(input: &str) -> Result<Vec<Quest>, ConvertError> {
+    let document: Document =
+        toml::from_str(input).map_err(|e| ConvertError::Toml(e.to_string()))?;
+    document.into_quests()
+}
+
+/// Renders quests as a YAML document with the layout of [`to_toml`]: a
+/// `quest` sequence holding one mapping per quest.
+pub fn to_yaml(quests: &[Quest]) -> Result<String, ConvertError> {
+    serde_yaml::to_string(&Document::from(quests)).map_err(|e| ConvertError::Yaml(e.to_string()))
+}
+
+/// Reads quests from a YAML document laid out as by [`to_yaml`].
+pub fn from_yaml(input: &str) -> Result<Vec<Quest>, ConvertError> {
+    let document: Document =
+        serde_yaml::from_str(input).map_err(|e| ConvertError::Yaml(e.to_string()))?;
+    document.into_quests()
+}
+
+/// Renders quests as a JSON document with the layout of [`to_toml`]: an
+/// object whose `quest` array holds one object per quest.
+pub fn to_json(quests: &[Quest]) -> Result<String, ConvertError> {
+    serde_json::to_string_pretty(&Document::from(quests))
+        .map(|json| json + "\n")
+        .map_err(|e| ConvertError::Json(e.to_string()))
+}
+
+/// Reads the one quest of a JSON dump, see [`from_json_many`].
+pub fn from_json(input: &str) -> Result<Quest, ConvertError> {
+    let mut quests = from_json_many(input)?;
+    match quests.len() {
+        1 => Ok(quests.remove(0)),
+        n => Err(ConvertError::Json(format!("expected one quest, found {n}"))),
+    }
+}
+
+/// Reads quests from a JSON dump: a document laid out as by [`to_json`], an
+/// array of quest objects, or a single quest object. The output of
+/// `parse --format json`, whose `quests` hold [`Quest`] as serialized by
+/// serde, is read too.
+///
+/// ```json
+/// [{ "name": "Wolves", "reward": 40, "steps": ["Hunt"], "region": "North" }]
+/// ```
+///
+/// Quest objects have the fields of a TOML `[[quest]]` table. Unlike TOML
+/// and YAML input, keys they do not know are kept as extra properties, as
+/// dumps by older tools carry fields of their own; such keys set to `null`
+/// are dropped.
+pub fn from_json_many(input: &str) -> Result<Vec<Quest>, ConvertError> {
+    use serde_json::Value as Json;
+
+    let invalid = |e: serde_json::Error| ConvertError::Json(e.to_string());
+    let quests = match serde_json::from_str(input).map_err(invalid)? {
+        Json::Object(mut export) if export.contains_key("quests") => {
+            let quests = export.remove("quests").unwrap_or_default();
+            return serde_json::from_value(quests).map_err(invalid);
+        }
+        Json::Array(quests) => quests,
+        Json::Object(mut document) if document.len() == 1 && document.contains_key("quest") => {
+            match document.remove("quest") {
+                Some(Json::Array(quests)) => quests,
+                _ => return Err(ConvertError::Json("`quest` must be an array".to_string())),
+            }
+        }
+        quest @ Json::Object(_) => vec![quest],
+        _ => {
+            return Err(ConvertError::Json(
+                "expected a quest object or an array of them".to_string(),
+            ))
+        }
+    };
+    quests
+        .into_iter()
+        .map(|quest| {
+            let Json::Object(mut fields) = quest else {
+                return Err(ConvertError::Json(
+                    "every quest must be an object".to_string(),
+                ));
+            };
+            let mut extra = match fields.remove("extra") {
+                Some(Json::Object(extra)) => extra,
+                Some(Json::Null) | None => serde_json::Map::new(),
+                Some(_) => return Err(ConvertError::Json("`extra` must be an object".to_string())),
+            };
+            for (key, value) in std::mem::take(&mut fields) {
+                if QUEST_DOC_FIELDS.contains(&key.as_str()) {
+                    fields.insert(key, value);
+                } else if !value.is_null() {
+                    extra.insert(key, value);
+                }
+            }
+            fields.insert("extra".to_string(), Json::Object(extra));
+            serde_json::from_value::<QuestDoc>(Json::Object(fields))
+                .map_err(invalid)?
+                .into_quest()
+        })
+        .collect()
+}
+
+fn parse_dsl(input: &str) -> Result<Vec<Quest>, ParseError> {
+    let options = ParserOptions {
+        allow_external_refs: true,
+        ..Default::default()
+    };
+    let world = Parser::with_options(input, options)?.parse_world()?;
+    if !world.symbols.is_empty() {
+        return Err(ParseError::Unformattable(
+            "npc and item definitions".to_string(),
+        ));
+    }
+    Ok(world.quests)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Document {
+    #[serde(default)]
+    quest: Vec<QuestDoc>,
+}
+
+/// The fields of [`QuestDoc`] but `extra`, for telling keys of a JSON
+/// dump that belong in `extra` apart. Keep in step with the struct.
+const QUEST_DOC_FIELDS: &[&str] = &[
+    "name",
+    "id",
+    "package",
+    "active",
+    "reward",
+    "rewards",
+    "reward_tier",
+    "requires",
+    "tags",
+    "giver",
+    "turn_in",
+    "steps",
+    "step_groups",
+    "objective",
+    "complete_when",
+    "stages",
+    "time_limit",
+    "level",
+    "starts_at",
+    "ends_at",
+    "on_success",
+    "on_failure",
+    "on_start",
+    "on_complete",
+    "expressions",
+];
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QuestDoc {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    active: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reward: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    rewards: Vec<(Currency, i64)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    reward_tier: Vec<RewardTierDoc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    requires: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    giver: Option<NpcRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    turn_in: Option<NpcRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    steps: Vec<StepDoc>,
+    /// The items of a `steps { ... }` block, in place of `steps`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    step_groups: Option<Vec<StepTreeDoc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    objective: Vec<ObjectiveDoc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    complete_when: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stages: Option<Vec<[String; 2]>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_limit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    level: Option<[i64; 2]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    starts_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ends_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    on_success: Option<OutcomeDoc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    on_failure: Option<OutcomeDoc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    on_start: Vec<Action>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    on_complete: Vec<Action>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    extra: BTreeMap<String, Value>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    expressions: BTreeMap<String, String>,
+}
+
+/// A step, written as its bare text unless it has attributes.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StepDoc {
+    Detailed(DetailedStepDoc),
+    Text(Text),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DetailedStepDoc {
+    text: Text,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    optional: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    hidden: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    order: Option<i64>,
+}
+
+impl From<&Step> for StepDoc {
+    fn from(step: &Step) -> Self {
+        if step.is_plain() {
+            return StepDoc::Text(step.text.clone());
+        }
+        StepDoc::Detailed(DetailedStepDoc {
+            text: step.text.clone(),
+            optional: step.optional,
+            hidden: step.hidden,
+            order: step.order,
+        })
+    }
+}
+
+/// A step, or a group written as a table with one key, such as
+/// `{ any_of = ["Bribe", "Sneak"] }`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StepTreeDoc {
+    Group(StepGroupDoc),
+    Step(StepDoc),
+}
+
+/// A group, with exactly one of its fields set. A struct rather than an
+/// enum, since YAML writes enums as tags the untagged [`StepTreeDoc`] cannot
+/// read back.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct StepGroupDoc {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ordered: Option<Vec<StepTreeDoc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    any_of: Option<Vec<StepTreeDoc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    all_of: Option<Vec<StepTreeDoc>>,
+}
+
+impl From<&StepTree> for StepTreeDoc {
+    fn from(tree: &StepTree) -> Self {
+        let items = |items: &[StepTree]| Some(items.iter().map(StepTreeDoc::from).collect());
+        let group = match tree {
+            StepTree::Step(step) => return StepTreeDoc::Step(StepDoc::from(step)),
+            StepTree::Sequence(group) => StepGroupDoc {
+                ordered: items(group),
+                ..Default::default()
+            },
+            StepTree::AnyOf(group) => StepGroupDoc {
+                any_of: items(group),
+                ..Default::default()
+            },
+            StepTree::AllOf(group) => StepGroupDoc {
+                all_of: items(group),
+                ..Default::default()
+            },
+        };
+        StepTreeDoc::Group(group)
+    }
+}
+
+impl StepTreeDoc {
+    fn into_tree(self) -> Result<StepTree, String> {
+        let group = match self {
+            StepTreeDoc::Step(step) => return Ok(StepTree::Step(Step::from(step))),
+            StepTreeDoc::Group(group) => group,
+        };
+        let items = |items: Vec<StepTreeDoc>| {
+            items
+                .into_iter()
+                .map(StepTreeDoc::into_tree)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        match (group.ordered, group.any_of, group.all_of) {
+            (Some(group), None, None) => Ok(StepTree::Sequence(items(group)?)),
+            (None, Some(group), None) => Ok(StepTree::AnyOf(items(group)?)),
+            (None, None, Some(group)) => Ok(StepTree::AllOf(items(group)?)),
+            _ => Err("a group needs exactly one of ordered, any_of and all_of".to_string()),
+        }
+    }
+}
+
+impl From<StepDoc> for Step {
+    fn from(doc: StepDoc) -> Self {
+        match doc {
+            StepDoc::Text(text) => Step::new(text),
+            StepDoc::Detailed(doc) => Step {
+                text: doc.text,
+                optional: doc.optional,
+                hidden: doc.hidden,
+                order: doc.order,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RewardTierDoc {
+    name: String,
+    rewards: Vec<(Currency, i64)>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjectiveDoc {
+    kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    count: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutcomeDoc {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reward: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    penalty: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    unlocks: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    extra: BTreeMap<String, Value>,
+}
+
+impl From<&Outcome> for OutcomeDoc {
+    fn from(outcome: &Outcome) -> Self {
+        OutcomeDoc {
+            reward: (outcome.reward != 0).then_some(outcome.reward),
+            penalty: (outcome.penalty != 0).then_some(outcome.penalty),
+            unlocks: outcome.unlocks.clone(),
+            extra: outcome.extra.clone().into_iter().collect(),
+        }
+    }
+}
+
+impl From<OutcomeDoc> for Outcome {
+    fn from(doc: OutcomeDoc) -> Self {
+        Outcome {
+            reward: doc.reward.unwrap_or_default(),
+            penalty: doc.penalty.unwrap_or_default(),
+            unlocks: doc.unlocks,
+            extra: doc.extra.into_iter().collect(),
+        }
+    }
+}
+
+impl From<&[Quest]> for Document {
+    fn from(quests: &[Quest]) -> Self {
+        Document {
+            quest: quests.iter().map(QuestDoc::from).collect(),
+        }
+    }
+}
+
+impl Document {
+    fn into_quests(self) -> Result<Vec<Quest>, ConvertError> {
+        self.quest.into_iter().map(QuestDoc::into_quest).collect()
+    }
+}
+
+impl From<&Quest> for QuestDoc {
+    fn from(quest: &Quest) -> Self {
+        let objective = quest
+            .objectives
+            .iter()
+            .map(|objective| {
+                let (kind, target, count) = objective.fields();
+                ObjectiveDoc {
+                    kind: kind.to_string(),
+                    target: target.cloned(),
+                    count: count.copied(),
+                }
+            })
+            .collect();
+        let stages = quest.stages.as_ref().map(|stages| {
+            stages
+                .transitions()
+                .iter()
+                .map(|t| [stage_name(&t.from), stage_name(&t.to)])
+                .collect()
+        });
+        QuestDoc {
+            name: quest.name.clone(),
+            id: quest.id.clone(),
+            package: quest.package.clone(),
+            active: (!quest.expressions.contains_key("active")).then_some(quest.active),
+            reward: (!quest.expressions.contains_key("reward")).then_some(quest.reward),
+            rewards: quest.rewards.clone(),
+            reward_tier: quest
+                .reward_tiers
+                .iter()
+                .map(|tier| RewardTierDoc {
+                    name: tier.name.clone(),
+                    rewards: tier.rewards.clone(),
+                })
+                .collect(),
+            requires: quest.requires.clone(),
+            tags: quest.tags.clone(),
+            giver: quest.giver.clone(),
+            turn_in: quest.turn_in.clone(),
+            steps: match quest.step_tree {
+                Some(_) => Vec::new(),
+                None => quest.steps.iter().map(StepDoc::from).collect(),
+            },
+            step_groups: quest.step_tree.as_ref().map(|tree| match tree {
+                StepTree::AllOf(items) => items.iter().map(StepTreeDoc::from).collect(),
+                tree => vec![StepTreeDoc::from(tree)],
+            }),
+            objective,
+            complete_when: quest.complete_when.as_ref().map(Condition::to_string),
+            stages,
+            time_limit: quest.time_limit.map(format_duration),
+            level: quest.level_range.map(|(min, max)| [min, max]),
+            starts_at: quest.starts_at,
+            ends_at: quest.ends_at,
+            on_success: quest.on_success.as_ref().map(OutcomeDoc::from),
+            on_failure: quest.on_failure.as_ref().map(OutcomeDoc::from),
+            on_start: quest.on_start.clone(),
+            on_complete: quest.on_complete.clone(),
+            extra: quest.extra.clone().into_iter().collect(),
+            expressions: quest
+                .expressions
+                .iter()
+                .map(|(key, expr)| (key.clone(), expr.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl QuestDoc {
+    fn into_quest(self) -> Result<Quest, ConvertError> {
+        let name = self.name;
+        let invalid = |field: &str, message: String| ConvertError::InvalidField {
+            quest: name.clone(),
+            field: field.to_string(),
+            message,
+        };
+
+        let mut quest = Quest {
+            name: name.clone(),
+            id: self.id,
+            package: self.package,
+            steps: self.steps.into_iter().map(Step::from).collect(),
+            requires: self.requires,
+            tags: self.tags,
+            giver: self.giver,
+            turn_in: self.turn_in,
+            reward: self.reward.unwrap_or_default(),
+            rewards: self.rewards,
+            active: self.active.unwrap_or_default(),
+            on_success: self.on_success.map(Outcome::from),
+            on_failure: self.on_failure.map(Outcome::from),
+            on_start: self.on_start,
+            on_complete: self.on_complete,
+            starts_at: self.starts_at,
+            ends_at: self.ends_at,
+            extra: self.extra.into_iter().collect(),
+            ..Default::default()
+        };
+        if let Some(package) = &quest.package {
+            if !is_valid_package(package) {
+                return Err(invalid(
+                    "package",
+                    format!("{package:?} is not identifiers joined by dots"),
+                ));
+            }
+        }
+        if let Some(groups) = self.step_groups {
+            if !quest.steps.is_empty() {
+                return Err(invalid(
+                    "step_groups",
+                    "cannot be combined with steps".to_string(),
+                ));
+            }
+            let items = groups
+                .into_iter()
+                .map(StepTreeDoc::into_tree)
+                .collect::<Result<_, _>>()
+                .map_err(|message| invalid("step_groups", message))?;
+            let tree = StepTree::AllOf(items);
+            quest.steps = tree.steps().into_iter().cloned().collect();
+            quest.step_tree = Some(tree);
+        }
+        for tier in self.reward_tier {
+            if tier.rewards.is_empty() {
+                return Err(invalid(
+                    "reward_tier",
+                    format!("tier {:?} has no rewards", tier.name),
+                ));
+            }
+            if quest.reward_tiers.iter().any(|t| t.name == tier.name) {
+                return Err(invalid(
+                    "reward_tier",
+                    format!("tier {:?} is given twice", tier.name),
+                ));
+            }
+            quest
+                .reward_tiers
+                .push(RewardTier::new(tier.name, tier.rewards));
+        }
+        for objective in self.objective {
+            let objective =
+                Objective::from_fields(objective.kind, objective.target, objective.count)
+                    .map_err(|e| invalid("objective", e.to_string()))?;
+            quest.objectives.push(objective);
+        }
+        if let Some(source) = self.complete_when {
+            let expr =
+                parse_expression(&source).map_err(|e| invalid("complete_when", e.to_string()))?;
+            let condition = Condition::from_expr(expr)
+                .ok_or_else(|| invalid("complete_when", format!("not a condition: {source:?}")))?;
+            quest.complete_when = Some(condition);
+        }
+        if let Some(pairs) = self.stages {
+            let transitions = pairs
+                .into_iter()
+                .map(|[from, to]| Transition {
+                    from: stage(from),
+                    to: stage(to),
+                })
+                .collect();
+            let stages =
+                StateMachine::new(transitions).map_err(|e| invalid("stages", e.to_string()))?;
+            quest.stages = Some(stages);
+        }
+        if let Some(time_limit) = self.time_limit {
+            let duration = parse_duration(&time_limit)
+                .ok_or_else(|| invalid("time_limit", format!("invalid duration {time_limit:?}")))?;
+            quest.time_limit = Some(duration);
+        }
+        if let Some([min, max]) = self.level {
+            if min > max {
+                return Err(invalid("level", format!("empty range {min}..{max}")));
+            }
+            quest.level_range = Some((min, max));
+        }
+        if let (Some(starts_at), Some(ends_at)) = (quest.starts_at, quest.ends_at) {
+            if starts_at >= ends_at {
+                return Err(invalid(
+                    "ends_at",
+                    format!("{ends_at} is not after starts_at {starts_at}"),
+                ));
+            }
+        }
+        for (key, source) in self.expressions {
+            let expr = parse_expression(&source).map_err(|e| invalid(&key, e.to_string()))?;
+            match (key.as_str(), expr) {
+                (_, expr @ (Expr::Identifier(_) | Expr::Binary { .. })) => {
+                    quest.expressions.insert(key, expr);
+                }
+                ("reward", Expr::Literal(Value::Number(n))) => quest.reward = n,
+                ("active", Expr::Literal(Value::Bool(b))) => quest.active = b,
+                ("reward" | "active", Expr::Literal(other)) => {
+                    return Err(invalid(&key, format!("unexpected {other:?}")));
+                }
+                (_, Expr::Literal(value)) => {
+                    quest.extra.insert(key, value);
+                }
+            }
+        }
+        Ok(quest)
+    }
+}
+
+/// Parses a whole expression the way a property value is parsed, folding it
+/// to a literal when it refers to no game state.
+fn parse_expression(source: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser::new(source)?;
+    let expr = parser.parse_property_expr()?;
+    if parser.current_token != Token::Eof {
+        return Err(parser.syntax_error("end of expression"));
+    }
+    Ok(expr)
+}
+
+fn stage_name(stage: &Stage) -> String {
+    match stage {
+        Stage::Start => "start".to_string(),
+        Stage::End => "end".to_string(),
+        Stage::Named(name) => name.clone(),
+    }
+}
+
+fn stage(name: String) -> Stage {
+    match name.as_str() {
+        "start" => Stage::Start,
+        "end" => Stage::End,
+        _ => Stage::Named(name),
+    }
+}
@@ -0,0 +1,71 @@
+use crate::prelude::*;
+
+/// What a reward amount is paid in, as in `reward: 100 gold, 5 gems`.
+///
+/// Currencies are written as identifiers. The ones every game has get their
+/// own variant; any other name is kept as [`Currency::Custom`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "String", into = "String"))]
+pub enum Currency {
+    Gold,
+    Gems,
+    Reputation,
+    Custom(String),
+}
+
+impl Currency {
+    pub fn from_name(name: &str) -> Currency {
+        match name {
+            "gold" => Currency::Gold,
+            "gems" => Currency::Gems,
+            "reputation" => Currency::Reputation,
+            _ => Currency::Custom(name.to_string()),
+        }
+    }
+
+    /// The identifier the currency is written as.
+    pub fn name(&self) -> &str {
+        match self {
+            Currency::Gold => "gold",
+            Currency::Gems => "gems",
+            Currency::Reputation => "reputation",
+            Currency::Custom(name) => name,
+        }
+    }
+}
+
+impl From<String> for Currency {
+    fn from(name: String) -> Self {
+        Currency::from_name(&name)
+    }
+}
+
+impl From<Currency> for String {
+    fn from(currency: Currency) -> Self {
+        match currency {
+            Currency::Custom(name) => name,
+            known => known.name().to_string(),
+        }
+    }
+}
+
+/// One tier of a challenge quest's reward, from a
+/// `reward tier "gold" { gold: 500, gems: 10 }` block. The engine decides
+/// which tier a completion earns.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RewardTier {
+    pub name: String,
+    /// Amounts in source order; never empty in a parsed quest.
+    pub rewards: Vec<(Currency, i64)>,
+}
+
+impl RewardTier {
+    pub fn new(name: impl Into<String>, rewards: Vec<(Currency, i64)>) -> RewardTier {
+        RewardTier {
+            name: name.into(),
+            rewards,
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::prelude::*;
+use crate::Quest;
+
+/// An instant in UTC to the second, written as an ISO-8601 literal such as
+/// `2025-10-31T00:00:00Z` or `2025-10-31T02:00:00+02:00`.
+///
+/// Literals with an offset are converted to UTC, so two literals naming the
+/// same instant are equal. Years run from 0 to 9999.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct DateTime {
+    /// Seconds since 1970-01-01T00:00:00Z.
+    seconds: i64,
+}
+
+/// Seconds from 1970 to the first and past the last second of the years a
+/// literal can name.
+const MIN_SECONDS: i64 = -62_167_219_200;
+const MAX_SECONDS: i64 = 253_402_300_799;
+
+impl DateTime {
+    /// The instant `seconds` after 1970-01-01T00:00:00Z, if it falls in the
+    /// years 0 to 9999.
+    pub fn from_unix_timestamp(seconds: i64) -> Option<DateTime> {
+        (MIN_SECONDS..=MAX_SECONDS)
+            .contains(&seconds)
+            .then_some(DateTime { seconds })
+    }
+
+    /// The instant at a date and time in UTC, if they exist.
+    pub fn from_utc(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) -> Option<DateTime> {
+        let valid = (0..=9999).contains(&year)
+            && (1..=12).contains(&month)
+            && (1..=days_in_month(year, month)).contains(&day)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+        if !valid {
+            return None;
+        }
+        let days = days_from_civil(year, month, day);
+        let seconds = days * 86_400 + i64::from(hour * 3_600 + minute * 60 + second);
+        Some(DateTime { seconds })
+    }
+
+    /// The current time, from the system clock.
+    #[cfg(feature = "std")]
+    pub fn now() -> DateTime {
+        let seconds = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        };
+        DateTime {
+            seconds: seconds.clamp(MIN_SECONDS, MAX_SECONDS),
+        }
+    }
+
+    /// Parses the text of a literal: a date, `T`, a time to the second and
+    /// either `Z` or an offset from UTC such as `+02:00`.
+    pub fn parse(text: &str) -> Option<DateTime> {
+        let bytes = text.as_bytes();
+        if bytes.len() < 20 || !text.is_ascii() {
+            return None;
+        }
+        let number = |range: core::ops::Range<usize>| -> Option<u32> {
+            let digits = &text[range];
+            digits
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+                .then(|| digits.parse().ok())?
+        };
+        let separators = [(4, b'-'), (7, b'-'), (10, b'T'), (13, b':'), (16, b':')];
+        if separators.iter().any(|&(i, c)| bytes[i] != c) {
+            return None;
+        }
+        let local = DateTime::from_utc(
+            number(0..4)? as i32,
+            number(5..7)?,
+            number(8..10)?,
+            number(11..13)?,
+            number(14..16)?,
+            number(17..19)?,
+        )?;
+        let offset = match &text[19..] {
+            "Z" => 0,
+            zone if zone.len() == 6 && &zone[3..4] == ":" => {
+                let (hours, minutes) = (number(20..22)?, number(23..25)?);
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                let offset = i64::from(hours * 3_600 + minutes * 60);
+                match &zone[..1] {
+                    "+" => offset,
+                    "-" => -offset,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        DateTime::from_unix_timestamp(local.seconds - offset)
+    }
+
+    /// Seconds since 1970-01-01T00:00:00Z; negative before it.
+    pub fn unix_timestamp(self) -> i64 {
+        self.seconds
+    }
+
+    /// The year, month and day in UTC.
+    pub fn date(self) -> (i32, u32, u32) {
+        civil_from_days(self.seconds.div_euclid(86_400))
+    }
+
+    /// The hour, minute and second in UTC.
+    pub fn time(self) -> (u32, u32, u32) {
+        let seconds = self.seconds.rem_euclid(86_400) as u32;
+        (seconds / 3_600, seconds / 60 % 60, seconds % 60)
+    }
+}
+
+impl fmt::Display for DateTime {
+    /// Writes the instant in UTC, as in `2025-10-31T00:00:00Z`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.date();
+        let (hour, minute, second) = self.time();
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z"
+        )
+    }
+}
+
+impl TryFrom<String> for DateTime {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        DateTime::parse(&text).ok_or_else(|| format!("invalid date and time {text:?}"))
+    }
+}
+
+impl From<DateTime> for String {
+    fn from(instant: DateTime) -> Self {
+        instant.to_string()
+    }
+}
+
+impl Quest {
+    /// Whether a limited-time event quest is available at `now`: from its
+    /// `starts_at`, inclusive, until its `ends_at`, exclusive. A missing bound
+    /// leaves that side open, so a quest with neither is always running.
+    pub fn is_running_at(&self, now: DateTime) -> bool {
+        self.starts_at.is_none_or(|starts_at| starts_at <= now)
+            && self.ends_at.is_none_or(|ends_at| now < ends_at)
+    }
+}
+
+fn is_leap_year(year: i32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days from 1970-01-01 to a date of the proleptic Gregorian calendar, after
+/// Howard Hinnant's `days_from_civil`.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = i64::from(year) - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date `days` after 1970-01-01, the inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month, day)
+}
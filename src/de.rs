@@ -0,0 +1,255 @@
+//! Deserializing quest files straight into user types with serde.
+
+use std::fmt;
+
+use serde::de::value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use thiserror::Error;
+
+use crate::{Expr, LocalizedString, ParseError, Parser, Span, Token, Value};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum DeserializeError {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("Expected exactly one quest, found {0}")]
+    QuestCount(usize),
+    #[error("{0}")]
+    Message(String),
+}
+
+impl de::Error for DeserializeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeserializeError::Message(msg.to_string())
+    }
+}
+
+/// Deserializes quest source into any type implementing
+/// [`Deserialize`](serde::Deserialize).
+///
+/// Each quest is presented as a map holding its `name` followed by its
+/// properties; nested blocks such as `objective { ... }` become nested maps.
+/// A key given more than once becomes a sequence, and a single value is
+/// accepted wherever a sequence is expected, so `step: "a"` fills a
+/// `Vec<String>`. Expressions that reference game state and durations are
+/// passed on as their source text. Deserializing into a sequence type yields
+/// every quest in the input; anything else requires the input to hold exactly
+/// one.
+///
+/// `let` constants are substituted as usual. `include` directives, `stages`
+/// blocks and step attributes are not supported.
+///
+/// ```
+/// #[derive(serde::Deserialize)]
+/// struct Bounty {
+///     name: String,
+///     gold: u32,
+///     #[serde(default)]
+///     step: Vec<String>,
+/// }
+///
+/// let bounty: Bounty =
+///     game_quest_parser_Hodik::from_str(r#"quest "Wolves" { gold: 40, step: "Hunt" }"#)?;
+/// assert_eq!(bounty.gold, 40);
+/// assert_eq!(bounty.step, vec!["Hunt"]);
+/// # Ok::<(), game_quest_parser_Hodik::DeserializeError>(())
+/// ```
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, DeserializeError> {
+    let mut parser = Parser::new(input)?;
+    let mut quests = Vec::new();
+    loop {
+        match parser.current_token {
+            Token::LetKeyword => parser.parse_let()?,
+            Token::Eof => break,
+            _ => {
+                let (name, opened_at) = parser.parse_quest_header()?;
+                let mut entries = vec![("name".to_string(), Node::Scalar(Value::String(name)))];
+                entries.extend(parse_block(&mut parser, opened_at)?);
+                quests.push(Node::Map(entries));
+            }
+        }
+    }
+    T::deserialize(Document(quests))
+}
+
+/// A parsed property value, before it is handed to a visitor.
+#[derive(Debug, Clone)]
+enum Node {
+    /// Any value but a list or a localized string.
+    Scalar(Value),
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+impl From<Value> for Node {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::List(items) => Node::List(items.into_iter().map(Node::from).collect()),
+            Value::Localized(LocalizedString(texts)) => Node::Map(
+                texts
+                    .into_iter()
+                    .map(|(lang, text)| (lang, Node::Scalar(Value::String(text))))
+                    .collect(),
+            ),
+            value => Node::Scalar(value),
+        }
+    }
+}
+
+/// Parses block entries up to and including the closing brace of the block
+/// opened at `opened_at`. Entries with the same key are merged into a list at
+/// the position of the first one.
+fn parse_block(parser: &mut Parser, opened_at: Span) -> Result<Vec<(String, Node)>, ParseError> {
+    let mut entries: Vec<(String, Vec<Node>)> = Vec::new();
+    while parser.current_token != Token::RBrace && parser.current_token != Token::Eof {
+        let key = parser.parse_key()?;
+        let node = if parser.current_token == Token::LBrace {
+            parser.enter()?;
+            let opened_at = parser.open_brace()?;
+            let node = Node::Map(parse_block(parser, opened_at)?);
+            parser.depth -= 1;
+            node
+        } else {
+            parser.eat(Token::Colon)?;
+            match parser.parse_property_expr()? {
+                Expr::Literal(value) => Node::from(value),
+                expr => Node::Scalar(Value::String(expr.to_string())),
+            }
+        };
+        match entries.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, nodes)) => nodes.push(node),
+            None => entries.push((key, vec![node])),
+        }
+        if parser.current_token == Token::Comma {
+            parser.eat(Token::Comma)?;
+        }
+    }
+    parser.close_brace(opened_at)?;
+
+    Ok(entries
+        .into_iter()
+        .map(|(key, mut nodes)| {
+            let node = match nodes.len() {
+                1 => nodes.remove(0),
+                _ => Node::List(nodes),
+            };
+            (key, node)
+        })
+        .collect())
+}
+
+/// Every quest of the input.
+struct Document(Vec<Node>);
+
+impl Document {
+    fn single(self) -> Result<Node, DeserializeError> {
+        let mut quests = self.0;
+        match quests.len() {
+            1 => Ok(quests.remove(0)),
+            n => Err(DeserializeError::QuestCount(n)),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Document {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.single()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.len() {
+            0 => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(SeqDeserializer::new(self.0.into_iter()))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeserializeError> for Node {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Node {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Node::Scalar(Value::String(s)) => visitor.visit_string(s),
+            Node::Scalar(Value::Number(n)) => visitor.visit_i64(n),
+            Node::Scalar(Value::Float(f)) => visitor.visit_f64(f),
+            Node::Scalar(Value::Bool(b)) => visitor.visit_bool(b),
+            Node::Scalar(Value::Raw { body, .. }) => visitor.visit_string(body),
+            Node::Scalar(value @ (Value::Duration(_) | Value::DateTime(_))) => {
+                visitor.visit_string(value.to_string())
+            }
+            Node::Scalar(Value::Range(min, max)) => {
+                let bounds = [min, max].map(|n| Node::Scalar(Value::Number(n)));
+                visitor.visit_seq(SeqDeserializer::new(bounds.into_iter()))
+            }
+            Node::Scalar(value @ (Value::List(_) | Value::Localized(_))) => {
+                Node::from(value).deserialize_any(visitor)
+            }
+            Node::List(items) => visitor.visit_seq(SeqDeserializer::new(items.into_iter())),
+            Node::Map(entries) => visitor.visit_map(MapDeserializer::new(entries.into_iter())),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // A property that is present always holds a value.
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Node::List(items) => visitor.visit_seq(SeqDeserializer::new(items.into_iter())),
+            node => visitor.visit_seq(SeqDeserializer::new(std::iter::once(node))),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            Node::Scalar(Value::String(s)) => visitor.visit_enum(s.into_deserializer()),
+            Node::Map(entries) => {
+                let map = MapDeserializer::new(entries.into_iter());
+                visitor.visit_enum(MapAccessDeserializer::new(map))
+            }
+            node => node.deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct tuple tuple_struct map
+        struct identifier ignored_any
+    }
+}
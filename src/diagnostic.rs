@@ -0,0 +1,351 @@
+use core::fmt::Write;
+
+use thiserror::Error;
+
+use crate::encoding::BOM;
+use crate::prelude::*;
+use crate::properties::KNOWN_PROPERTIES;
+use crate::{GrammarVersion, ParseError, Quest, Span, Text};
+
+/// A problem the parser noticed that does not stop it, reported as a
+/// warning in [`Diagnostics`] instead of failing like a [`ParseError`].
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// A property the parser gives no meaning to. With
+    /// [`ParserOptions::collect_extra`](crate::ParserOptions::collect_extra)
+    /// off it is dropped and always reported; otherwise it is kept in
+    /// [`Quest::extra`](crate::Quest::extra) and only reported when it looks
+    /// like a typo of a known property.
+    #[error("Unknown property `{key}`")]
+    UnknownProperty {
+        key: String,
+        suggestion: Option<String>,
+        span: Span,
+    },
+    #[error("Step {text} is listed more than once")]
+    DuplicateStep { text: Text, span: Span },
+    /// Syntax that still parses but has a newer form, in a file written in
+    /// grammar version `since` or later. `replacement` is the source text to
+    /// write in place of `span`, as [`fix_deprecated`] does.
+    #[error("{feature} is deprecated since grammar version {since}")]
+    Deprecated {
+        feature: String,
+        since: GrammarVersion,
+        replacement: String,
+        span: Span,
+    },
+    /// A warning inside an included file. `chain` lists the included files
+    /// from the root input down to the file containing the warning.
+    #[error("In {}: {warning}", .chain.join(" -> "))]
+    InInclude {
+        chain: Vec<String>,
+        warning: Box<Diagnostic>,
+    },
+}
+
+impl Diagnostic {
+    /// Location of the warning in the file it was raised for.
+    pub fn span(&self) -> Span {
+        match self {
+            Diagnostic::UnknownProperty { span, .. }
+            | Diagnostic::DuplicateStep { span, .. }
+            | Diagnostic::Deprecated { span, .. } => *span,
+            Diagnostic::InInclude { warning, .. } => warning.span(),
+        }
+    }
+
+    /// The span of the warning in the input that was parsed, to be moved
+    /// when that input is part of a larger one. Warnings inside an included
+    /// file have none, as their spans refer to that file.
+    #[cfg(feature = "std")]
+    pub(crate) fn span_mut(&mut self) -> Option<&mut Span> {
+        match self {
+            Diagnostic::UnknownProperty { span, .. }
+            | Diagnostic::DuplicateStep { span, .. }
+            | Diagnostic::Deprecated { span, .. } => Some(span),
+            Diagnostic::InInclude { .. } => None,
+        }
+    }
+}
+
+/// A quest with the warnings raised while parsing it, from
+/// [`Parser::parse_quest_with_warnings`](crate::Parser::parse_quest_with_warnings).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOutput {
+    pub quest: Quest,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// The warnings raised while parsing, in the order they were found. See
+/// [`Parser::diagnostics`](crate::Parser::diagnostics).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diagnostics {
+    warnings: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn warn(&mut self, warning: Diagnostic) {
+        self.warnings.push(warning);
+    }
+
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    pub fn into_warnings(self) -> Vec<Diagnostic> {
+        self.warnings
+    }
+}
+
+/// Converts a byte offset into a 1-based `(line, column)` pair, counting
+/// columns in characters.
+///
+/// Lines end at `\n`, `\r\n` or a lone `\r`, and a byte order mark at the
+/// start of the input is not counted as a column.
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let offset = floor_char_boundary(source, offset.min(source.len()));
+    let (line, line_start) = line_start(source, offset);
+    let mut text = &source[line_start..offset];
+    if line_start == 0 {
+        text = text.strip_prefix(BOM).unwrap_or(text);
+    }
+    (line, text.chars().count() + 1)
+}
+
+/// The 1-based number of the line holding byte `offset`, and the byte its
+/// text starts at.
+fn line_start(source: &str, offset: usize) -> (usize, usize) {
+    let bytes = source.as_bytes();
+    let mut line = 1;
+    let mut start = 0;
+    for (i, &b) in bytes[..offset].iter().enumerate() {
+        if b == b'\n' || (b == b'\r' && bytes.get(i + 1) != Some(&b'\n')) {
+            line += 1;
+            start = i + 1;
+        }
+    }
+    (line, start)
+}
+
+/// The text of the line holding byte `offset`, without its line break.
+fn line_text(source: &str, offset: usize) -> &str {
+    let offset = floor_char_boundary(source, offset.min(source.len()));
+    let (_, start) = line_start(source, offset);
+    let rest = &source[start..];
+    let line = &rest[..rest.find(['\r', '\n']).unwrap_or(rest.len())];
+    match start {
+        0 => line.strip_prefix(BOM).unwrap_or(line),
+        _ => line,
+    }
+}
+
+fn floor_char_boundary(s: &str, mut offset: usize) -> usize {
+    while !s.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Suggests the known property key closest to `key`, if any is close enough
+/// to be a plausible typo.
+pub fn suggest_property(key: &str) -> Option<&'static str> {
+    closest_match(key, KNOWN_PROPERTIES.iter().map(|p| p.name))
+}
+
+/// The candidate closest to `key` by edit distance, allowing about one edit
+/// per three characters. Ties go to the alphabetically first candidate.
+pub(crate) fn closest_match<'a>(
+    key: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = (key.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|name| (edit_distance(key, name), name))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min()
+        .map(|(_, name)| name)
+}
+
+/// Renders an error as a human-readable report with the offending source
+/// line and an underline below the error's span:
+///
+/// ```text
+/// error[E0102]: Unknown property `rewrd`
+///   --> main.quest:2:5
+///    |
+///  2 |     rewrd: 100,
+///    |     ^^^^^
+///    |
+///    = help: did you mean `reward`?
+/// ```
+///
+/// The code in brackets is the error's [`ParseError::code`].
+///
+/// `source` must be the text the error was raised for; for errors inside an
+/// included file that is the innermost file of the include chain.
+pub fn render_error(error: &ParseError, source: &str, file_name: &str) -> String {
+    let span = error.span().or(match error {
+        ParseError::UnexpectedEOF => Some(Span::new(source.len(), source.len())),
+        _ => None,
+    });
+    render(
+        &format!("error[{}]: {error}", error.code()),
+        span,
+        help_message(error),
+        source,
+        file_name,
+    )
+}
+
+/// Renders a warning the same way [`render_error`] renders an error, with
+/// `warning:` in front.
+///
+/// `source` must be the text the warning was raised for; for warnings inside
+/// an included file that is the innermost file of the include chain.
+pub fn render_warning(warning: &Diagnostic, source: &str, file_name: &str) -> String {
+    let help = match innermost(warning) {
+        Diagnostic::UnknownProperty {
+            suggestion: Some(name),
+            ..
+        } => Some(format!("did you mean `{name}`?")),
+        Diagnostic::Deprecated { replacement, .. } => {
+            Some(format!("write `{replacement}` instead"))
+        }
+        _ => None,
+    };
+    render(
+        &format!("warning: {warning}"),
+        Some(warning.span()),
+        help,
+        source,
+        file_name,
+    )
+}
+
+/// Rewrites `source` with the replacement of every [`Diagnostic::Deprecated`]
+/// warning raised for it, migrating old syntax to its newer form. Warnings in
+/// included files are left out, as they point into other sources.
+///
+/// ```
+/// use game_quest_parser_Hodik::{fix_deprecated, Parser};
+///
+/// let input = "#version 2\nquest \"A\" { level: 5 }";
+/// let mut parser = Parser::new(input)?;
+/// parser.parse_quests()?;
+/// let fixed = fix_deprecated(input, parser.diagnostics().warnings());
+/// assert_eq!(fixed, "#version 2\nquest \"A\" { level: 5..5 }");
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn fix_deprecated(source: &str, warnings: &[Diagnostic]) -> String {
+    let mut fixes: Vec<(Span, &str)> = warnings
+        .iter()
+        .filter_map(|warning| match warning {
+            Diagnostic::Deprecated {
+                replacement, span, ..
+            } => Some((*span, replacement.as_str())),
+            _ => None,
+        })
+        .collect();
+    // A template used by several quests warns once per use.
+    fixes.sort_by_key(|&(span, _)| (span.start, span.end));
+    fixes.dedup_by_key(|&mut (span, _)| span);
+    let mut out = source.to_string();
+    for (span, replacement) in fixes.into_iter().rev() {
+        out.replace_range(span.start..span.end, replacement);
+    }
+    out
+}
+
+fn innermost(warning: &Diagnostic) -> &Diagnostic {
+    match warning {
+        Diagnostic::InInclude { warning, .. } => innermost(warning),
+        warning => warning,
+    }
+}
+
+fn render(
+    headline: &str,
+    span: Option<Span>,
+    help: Option<String>,
+    source: &str,
+    file_name: &str,
+) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{headline}");
+    let Some(span) = span else {
+        let _ = writeln!(out, "  --> {file_name}");
+        return out;
+    };
+
+    let (line, column) = line_col(source, span.start);
+    let text = line_text(source, span.start);
+    let gutter = line.to_string().len();
+    let pad = " ".repeat(gutter);
+
+    let underline_len = source
+        .get(span.start..span.end)
+        .map_or(0, |text| {
+            text.split(['\r', '\n'])
+                .next()
+                .unwrap_or("")
+                .chars()
+                .count()
+        })
+        .max(1);
+
+    let _ = writeln!(out, "{pad}--> {file_name}:{line}:{column}");
+    let _ = writeln!(out, "{pad} |");
+    let _ = writeln!(out, "{line} | {text}");
+    let _ = writeln!(
+        out,
+        "{pad} | {}{}",
+        " ".repeat(column - 1),
+        "^".repeat(underline_len)
+    );
+
+    if let Some(help) = help {
+        let _ = writeln!(out, "{pad} |");
+        let _ = writeln!(out, "{pad} = help: {help}");
+    }
+    out
+}
+
+fn help_message(error: &ParseError) -> Option<String> {
+    match error {
+        ParseError::UnknownProperty {
+            suggestion: Some(name),
+            ..
+        } => Some(format!("did you mean `{name}`?")),
+        ParseError::InInclude { error, .. } => help_message(error),
+        _ => None,
+    }
+}
@@ -0,0 +1,666 @@
+//! Semantic comparison of two versions of a quest.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::time::Duration;
+
+use crate::writer::{format_actions, format_duration, quoted};
+use crate::{
+    Action, Condition, Currency, DateTime, Expr, NpcRef, Objective, Outcome, Quest, StateMachine,
+    Step, StepTree, Value,
+};
+
+/// One difference between two versions of a quest, found by [`quest_diff`].
+///
+/// Indices are zero-based. Removed steps and objectives are indexed by their
+/// position in the old quest; added and changed ones by their position in the
+/// new quest.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuestChange {
+    Renamed {
+        from: String,
+        to: String,
+    },
+    IdChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    PackageChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    ActiveToggled {
+        active: bool,
+    },
+    RewardChanged {
+        from: i64,
+        to: i64,
+    },
+    /// The amount in a currency was added (`from` is `None`), removed (`to`
+    /// is `None`) or changed.
+    NamedRewardChanged {
+        currency: Currency,
+        from: Option<i64>,
+        to: Option<i64>,
+    },
+    RewardTierAdded(String),
+    RewardTierRemoved(String),
+    /// The amount in a currency of a reward tier both versions have was
+    /// added, removed or changed, as in [`QuestChange::NamedRewardChanged`].
+    TierRewardChanged {
+        tier: String,
+        currency: Currency,
+        from: Option<i64>,
+        to: Option<i64>,
+    },
+    RequirementAdded(String),
+    RequirementRemoved(String),
+    TagAdded(String),
+    TagRemoved(String),
+    StepAdded {
+        index: usize,
+        step: Step,
+    },
+    StepRemoved {
+        index: usize,
+        step: Step,
+    },
+    StepChanged {
+        index: usize,
+        from: Step,
+        to: Step,
+    },
+    /// The steps were grouped differently, or grouped or ungrouped. Changes
+    /// to the steps themselves are reported as step changes only.
+    StepGroupsChanged {
+        from: Option<StepTree>,
+        to: Option<StepTree>,
+    },
+    ObjectiveAdded {
+        index: usize,
+        objective: Objective,
+    },
+    ObjectiveRemoved {
+        index: usize,
+        objective: Objective,
+    },
+    ObjectiveChanged {
+        index: usize,
+        from: Objective,
+        to: Objective,
+    },
+    CompletionChanged {
+        from: Option<Condition>,
+        to: Option<Condition>,
+    },
+    StagesChanged {
+        from: Option<StateMachine>,
+        to: Option<StateMachine>,
+    },
+    TimeLimitChanged {
+        from: Option<Duration>,
+        to: Option<Duration>,
+    },
+    LevelRangeChanged {
+        from: Option<(i64, i64)>,
+        to: Option<(i64, i64)>,
+    },
+    /// The `giver` or `turn_in` NPC, named by `key`, changed.
+    NpcChanged {
+        key: &'static str,
+        from: Option<NpcRef>,
+        to: Option<NpcRef>,
+    },
+    /// The `starts_at` or `ends_at` date, named by `key`, changed.
+    EventWindowChanged {
+        key: &'static str,
+        from: Option<DateTime>,
+        to: Option<DateTime>,
+    },
+    /// The `on_success` or `on_failure` block, named by `key`, changed.
+    OutcomeChanged {
+        key: &'static str,
+        from: Option<Outcome>,
+        to: Option<Outcome>,
+    },
+    /// The `on_start` or `on_complete` block, named by `key`, changed.
+    ActionsChanged {
+        key: &'static str,
+        from: Vec<Action>,
+        to: Vec<Action>,
+    },
+    PropertyAdded {
+        key: String,
+        value: Value,
+    },
+    PropertyRemoved {
+        key: String,
+        value: Value,
+    },
+    PropertyChanged {
+        key: String,
+        from: Value,
+        to: Value,
+    },
+    ExpressionAdded {
+        key: String,
+        expr: Expr,
+    },
+    ExpressionRemoved {
+        key: String,
+        expr: Expr,
+    },
+    ExpressionChanged {
+        key: String,
+        from: Expr,
+        to: Expr,
+    },
+}
+
+impl fmt::Display for QuestChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Steps and objectives are numbered from 1, as in lint messages.
+        match self {
+            QuestChange::Renamed { from, to } => {
+                write!(f, "renamed from {} to {}", quoted(from), quoted(to))
+            }
+            QuestChange::IdChanged { from, to } => write!(
+                f,
+                "id changed from {} to {}",
+                optional(from.as_deref().map(quoted)),
+                optional(to.as_deref().map(quoted))
+            ),
+            QuestChange::PackageChanged { from, to } => write!(
+                f,
+                "package changed from {} to {}",
+                optional(from.as_deref().map(quoted)),
+                optional(to.as_deref().map(quoted))
+            ),
+            QuestChange::ActiveToggled { active } => write!(f, "active toggled to {active}"),
+            QuestChange::RewardChanged { from, to } => {
+                write!(f, "reward changed from {from} to {to}")
+            }
+            QuestChange::NamedRewardChanged { currency, from, to } => write!(
+                f,
+                "reward {currency} changed from {} to {}",
+                optional(*from),
+                optional(*to)
+            ),
+            QuestChange::RewardTierAdded(name) => write!(f, "reward tier {} added", quoted(name)),
+            QuestChange::RewardTierRemoved(name) => {
+                write!(f, "reward tier {} removed", quoted(name))
+            }
+            QuestChange::TierRewardChanged {
+                tier,
+                currency,
+                from,
+                to,
+            } => write!(
+                f,
+                "reward tier {} {currency} changed from {} to {}",
+                quoted(tier),
+                optional(*from),
+                optional(*to)
+            ),
+            QuestChange::RequirementAdded(name) => write!(f, "requirement {} added", quoted(name)),
+            QuestChange::RequirementRemoved(name) => {
+                write!(f, "requirement {} removed", quoted(name))
+            }
+            QuestChange::TagAdded(tag) => write!(f, "tag {} added", quoted(tag)),
+            QuestChange::TagRemoved(tag) => write!(f, "tag {} removed", quoted(tag)),
+            QuestChange::StepAdded { index, step } => write!(f, "step {} added: {step}", index + 1),
+            QuestChange::StepRemoved { index, step } => {
+                write!(f, "step {} removed: {step}", index + 1)
+            }
+            QuestChange::StepChanged { index, from, to } => {
+                write!(f, "step {} changed from {from} to {to}", index + 1)
+            }
+            QuestChange::StepGroupsChanged { from, to } => write!(
+                f,
+                "step groups changed from {} to {}",
+                optional(from.as_ref()),
+                optional(to.as_ref())
+            ),
+            QuestChange::ObjectiveAdded { index, objective } => {
+                write!(f, "objective {} added: {objective}", index + 1)
+            }
+            QuestChange::ObjectiveRemoved { index, objective } => {
+                write!(f, "objective {} removed: {objective}", index + 1)
+            }
+            QuestChange::ObjectiveChanged { index, from, to } => {
+                write!(f, "objective {} changed from {from} to {to}", index + 1)
+            }
+            QuestChange::CompletionChanged { from, to } => write!(
+                f,
+                "complete_when changed from {} to {}",
+                optional(from.as_ref()),
+                optional(to.as_ref())
+            ),
+            QuestChange::StagesChanged { from, to } => write!(
+                f,
+                "stages changed from {} to {}",
+                optional(from.as_ref()),
+                optional(to.as_ref())
+            ),
+            QuestChange::TimeLimitChanged { from, to } => write!(
+                f,
+                "time_limit changed from {} to {}",
+                optional(from.map(format_duration)),
+                optional(to.map(format_duration))
+            ),
+            QuestChange::LevelRangeChanged { from, to } => write!(
+                f,
+                "level changed from {} to {}",
+                optional(from.map(|(min, max)| format!("{min}..{max}"))),
+                optional(to.map(|(min, max)| format!("{min}..{max}")))
+            ),
+            QuestChange::NpcChanged { key, from, to } => write!(
+                f,
+                "{key} changed from {} to {}",
+                optional(from.as_ref()),
+                optional(to.as_ref())
+            ),
+            QuestChange::EventWindowChanged { key, from, to } => write!(
+                f,
+                "{key} changed from {} to {}",
+                optional(from.as_ref()),
+                optional(to.as_ref())
+            ),
+            QuestChange::OutcomeChanged { key, from, to } => write!(
+                f,
+                "{key} changed from {} to {}",
+                optional(from.as_ref()),
+                optional(to.as_ref())
+            ),
+            QuestChange::ActionsChanged { key, from, to } => write!(
+                f,
+                "{key} changed from {} to {}",
+                format_actions(from),
+                format_actions(to)
+            ),
+            QuestChange::PropertyAdded { key, value } => write!(f, "{key} added: {value}"),
+            QuestChange::PropertyRemoved { key, value } => write!(f, "{key} removed: {value}"),
+            QuestChange::PropertyChanged { key, from, to } => {
+                write!(f, "{key} changed from {from} to {to}")
+            }
+            QuestChange::ExpressionAdded { key, expr } => write!(f, "{key} added: {expr}"),
+            QuestChange::ExpressionRemoved { key, expr } => write!(f, "{key} removed: {expr}"),
+            QuestChange::ExpressionChanged { key, from, to } => {
+                write!(f, "{key} changed from {from} to {to}")
+            }
+        }
+    }
+}
+
+/// Whether two step trees group their steps the same way, whatever the
+/// steps are.
+fn same_shape(a: &StepTree, b: &StepTree) -> bool {
+    let same_items = |a: &[StepTree], b: &[StepTree]| {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_shape(a, b))
+    };
+    match (a, b) {
+        (StepTree::Step(_), StepTree::Step(_)) => true,
+        (StepTree::Sequence(a), StepTree::Sequence(b))
+        | (StepTree::AnyOf(a), StepTree::AnyOf(b))
+        | (StepTree::AllOf(a), StepTree::AllOf(b)) => same_items(a, b),
+        _ => false,
+    }
+}
+
+fn optional(value: Option<impl fmt::Display>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+/// Lists what changed from quest `a` to quest `b`, ignoring how either was
+/// written.
+///
+/// Changes come in the order the writer emits properties, with extra
+/// properties and expressions sorted by key. Steps and objectives are matched
+/// up by content, so inserting a step is reported as one added step rather
+/// than a change to every step after it; requirements are compared as a set.
+///
+/// ```
+/// use game_quest_parser_Hodik::{quest_diff, Parser, QuestChange};
+///
+/// let a = Parser::new(r#"quest "Wolves" { reward: 40, step: "Hunt" }"#)?.parse_quest()?;
+/// let b = Parser::new(r#"quest "Wolves" { reward: 50, step: "Hunt", step: "Return" }"#)?
+///     .parse_quest()?;
+/// assert_eq!(
+///     quest_diff(&a, &b),
+///     vec![
+///         QuestChange::RewardChanged { from: 40, to: 50 },
+///         QuestChange::StepAdded { index: 1, step: "Return".into() },
+///     ]
+/// );
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn quest_diff(a: &Quest, b: &Quest) -> Vec<QuestChange> {
+    let mut changes = Vec::new();
+    if a.name != b.name {
+        changes.push(QuestChange::Renamed {
+            from: a.name.clone(),
+            to: b.name.clone(),
+        });
+    }
+    if a.id != b.id {
+        changes.push(QuestChange::IdChanged {
+            from: a.id.clone(),
+            to: b.id.clone(),
+        });
+    }
+    if a.package != b.package {
+        changes.push(QuestChange::PackageChanged {
+            from: a.package.clone(),
+            to: b.package.clone(),
+        });
+    }
+    if a.active != b.active {
+        changes.push(QuestChange::ActiveToggled { active: b.active });
+    }
+    if a.reward != b.reward {
+        changes.push(QuestChange::RewardChanged {
+            from: a.reward,
+            to: b.reward,
+        });
+    }
+    for (currency, from, to) in amount_changes(&a.rewards, &b.rewards) {
+        changes.push(QuestChange::NamedRewardChanged { currency, from, to });
+    }
+    for tier in &a.reward_tiers {
+        match b.reward_tiers.iter().find(|t| t.name == tier.name) {
+            Some(new) => {
+                for (currency, from, to) in amount_changes(&tier.rewards, &new.rewards) {
+                    changes.push(QuestChange::TierRewardChanged {
+                        tier: tier.name.clone(),
+                        currency,
+                        from,
+                        to,
+                    });
+                }
+            }
+            None => changes.push(QuestChange::RewardTierRemoved(tier.name.clone())),
+        }
+    }
+    for tier in &b.reward_tiers {
+        if !a.reward_tiers.iter().any(|t| t.name == tier.name) {
+            changes.push(QuestChange::RewardTierAdded(tier.name.clone()));
+        }
+    }
+
+    for name in &a.requires {
+        if !b.requires.contains(name) {
+            changes.push(QuestChange::RequirementRemoved(name.clone()));
+        }
+    }
+    for name in &b.requires {
+        if !a.requires.contains(name) {
+            changes.push(QuestChange::RequirementAdded(name.clone()));
+        }
+    }
+
+    for tag in &a.tags {
+        if !b.tags.contains(tag) {
+            changes.push(QuestChange::TagRemoved(tag.clone()));
+        }
+    }
+    for tag in &b.tags {
+        if !a.tags.contains(tag) {
+            changes.push(QuestChange::TagAdded(tag.clone()));
+        }
+    }
+
+    for (key, from, to) in [
+        ("giver", &a.giver, &b.giver),
+        ("turn_in", &a.turn_in, &b.turn_in),
+    ] {
+        if from != to {
+            changes.push(QuestChange::NpcChanged {
+                key,
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+    }
+
+    for edit in diff_sequence(&a.steps, &b.steps) {
+        changes.push(match edit {
+            Edit::Added(index, step) => QuestChange::StepAdded {
+                index,
+                step: step.clone(),
+            },
+            Edit::Removed(index, step) => QuestChange::StepRemoved {
+                index,
+                step: step.clone(),
+            },
+            Edit::Changed(index, from, to) => QuestChange::StepChanged {
+                index,
+                from: from.clone(),
+                to: to.clone(),
+            },
+        });
+    }
+    let same_groups = match (&a.step_tree, &b.step_tree) {
+        (Some(from), Some(to)) => same_shape(from, to),
+        (from, to) => from.is_none() && to.is_none(),
+    };
+    if !same_groups {
+        changes.push(QuestChange::StepGroupsChanged {
+            from: a.step_tree.clone(),
+            to: b.step_tree.clone(),
+        });
+    }
+    for edit in diff_sequence(&a.objectives, &b.objectives) {
+        changes.push(match edit {
+            Edit::Added(index, objective) => QuestChange::ObjectiveAdded {
+                index,
+                objective: objective.clone(),
+            },
+            Edit::Removed(index, objective) => QuestChange::ObjectiveRemoved {
+                index,
+                objective: objective.clone(),
+            },
+            Edit::Changed(index, from, to) => QuestChange::ObjectiveChanged {
+                index,
+                from: from.clone(),
+                to: to.clone(),
+            },
+        });
+    }
+    if a.complete_when != b.complete_when {
+        changes.push(QuestChange::CompletionChanged {
+            from: a.complete_when.clone(),
+            to: b.complete_when.clone(),
+        });
+    }
+
+    if a.stages != b.stages {
+        changes.push(QuestChange::StagesChanged {
+            from: a.stages.clone(),
+            to: b.stages.clone(),
+        });
+    }
+    if a.time_limit != b.time_limit {
+        changes.push(QuestChange::TimeLimitChanged {
+            from: a.time_limit,
+            to: b.time_limit,
+        });
+    }
+    if a.level_range != b.level_range {
+        changes.push(QuestChange::LevelRangeChanged {
+            from: a.level_range,
+            to: b.level_range,
+        });
+    }
+    for (key, from, to) in [
+        ("starts_at", a.starts_at, b.starts_at),
+        ("ends_at", a.ends_at, b.ends_at),
+    ] {
+        if from != to {
+            changes.push(QuestChange::EventWindowChanged { key, from, to });
+        }
+    }
+    for (key, from, to) in [
+        ("on_success", &a.on_success, &b.on_success),
+        ("on_failure", &a.on_failure, &b.on_failure),
+    ] {
+        if from != to {
+            changes.push(QuestChange::OutcomeChanged {
+                key,
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+    }
+    for (key, from, to) in [
+        ("on_start", &a.on_start, &b.on_start),
+        ("on_complete", &a.on_complete, &b.on_complete),
+    ] {
+        if from != to {
+            changes.push(QuestChange::ActionsChanged {
+                key,
+                from: from.clone(),
+                to: to.clone(),
+            });
+        }
+    }
+
+    let keys: BTreeSet<&String> = a
+        .extra
+        .keys()
+        .chain(a.expressions.keys())
+        .chain(b.extra.keys())
+        .chain(b.expressions.keys())
+        .collect();
+    for key in keys {
+        match (a.extra.get(key), b.extra.get(key)) {
+            (Some(from), Some(to)) if from != to => changes.push(QuestChange::PropertyChanged {
+                key: key.clone(),
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            (Some(value), None) => changes.push(QuestChange::PropertyRemoved {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            (None, Some(value)) => changes.push(QuestChange::PropertyAdded {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            _ => {}
+        }
+        match (a.expressions.get(key), b.expressions.get(key)) {
+            (Some(from), Some(to)) if from != to => changes.push(QuestChange::ExpressionChanged {
+                key: key.clone(),
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            (Some(expr), None) => changes.push(QuestChange::ExpressionRemoved {
+                key: key.clone(),
+                expr: expr.clone(),
+            }),
+            (None, Some(expr)) => changes.push(QuestChange::ExpressionAdded {
+                key: key.clone(),
+                expr: expr.clone(),
+            }),
+            _ => {}
+        }
+    }
+    changes
+}
+
+/// An edit turning one sequence into another; see [`QuestChange`] for which
+/// sequence each index refers to.
+/// The currencies whose amount differs between `a` and `b`, with the old and
+/// new amount: the ones of `a` in its order, then the ones only `b` has.
+fn amount_changes(
+    a: &[(Currency, i64)],
+    b: &[(Currency, i64)],
+) -> Vec<(Currency, Option<i64>, Option<i64>)> {
+    let amount = |rewards: &[(Currency, i64)], currency: &Currency| {
+        rewards
+            .iter()
+            .find(|(c, _)| c == currency)
+            .map(|(_, amount)| *amount)
+    };
+    let mut changes = Vec::new();
+    for (currency, from) in a {
+        let to = amount(b, currency);
+        if to != Some(*from) {
+            changes.push((currency.clone(), Some(*from), to));
+        }
+    }
+    for (currency, to) in b {
+        if amount(a, currency).is_none() {
+            changes.push((currency.clone(), None, Some(*to)));
+        }
+    }
+    changes
+}
+
+enum Edit<'a, T> {
+    Added(usize, &'a T),
+    Removed(usize, &'a T),
+    Changed(usize, &'a T, &'a T),
+}
+
+/// The edits turning `a` into `b`, keeping a longest common subsequence in
+/// place. Within each run of differing items, removals and additions are
+/// paired up into changes.
+fn diff_sequence<'a, T: PartialEq>(a: &'a [T], b: &'a [T]) -> Vec<Edit<'a, T>> {
+    let (n, m) = (a.len(), b.len());
+    // common[i][j] is the length of the longest common subsequence of
+    // a[i..] and b[j..].
+    let mut common = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            common[i][j] = if a[i] == b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            flush_run(a, b, &mut removed, &mut added, &mut edits);
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || common[i][j + 1] >= common[i + 1][j]) {
+            added.push(j);
+            j += 1;
+        } else {
+            removed.push(i);
+            i += 1;
+        }
+    }
+    flush_run(a, b, &mut removed, &mut added, &mut edits);
+    edits
+}
+
+fn flush_run<'a, T>(
+    a: &'a [T],
+    b: &'a [T],
+    removed: &mut Vec<usize>,
+    added: &mut Vec<usize>,
+    edits: &mut Vec<Edit<'a, T>>,
+) {
+    let paired = removed.len().min(added.len());
+    for (&i, &j) in removed.iter().zip(added.iter()) {
+        edits.push(Edit::Changed(j, &a[i], &b[j]));
+    }
+    for &i in &removed[paired..] {
+        edits.push(Edit::Removed(i, &a[i]));
+    }
+    for &j in &added[paired..] {
+        edits.push(Edit::Added(j, &b[j]));
+    }
+    removed.clear();
+    added.clear();
+}
@@ -0,0 +1,80 @@
+//! Decoding quest files written by tools that save UTF-16 or add a byte
+//! order mark.
+
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::prelude::*;
+use crate::ParseError;
+
+/// The byte order mark, which the lexer skips at the start of the input.
+pub(crate) const BOM: char = '\u{feff}';
+
+/// Decodes the bytes of a quest file into text.
+///
+/// A UTF-16 byte order mark, little- or big-endian, selects UTF-16; any
+/// other input is read as UTF-8. A UTF-8 byte order mark is dropped, and
+/// line endings are kept as written, since spans and
+/// [`line_col`](crate::line_col) handle `\r\n` as well as `\n`. UTF-16
+/// without a byte order mark cannot be told apart from UTF-8 and fails as
+/// invalid UTF-8.
+///
+/// ```
+/// use game_quest_parser_Hodik::{decode_source, Parser};
+///
+/// let mut bytes = vec![0xFF, 0xFE];
+/// for unit in "quest \"Wolves\" {}\r\n".encode_utf16() {
+///     bytes.extend(unit.to_le_bytes());
+/// }
+/// let source = decode_source(&bytes)?;
+/// assert_eq!(Parser::new(&source)?.parse_quest()?.name, "Wolves");
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn decode_source(bytes: &[u8]) -> Result<String, ParseError> {
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, "UTF-16LE", u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, "UTF-16BE", u16::from_be_bytes),
+        _ => {
+            let rest = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            let bom_len = bytes.len() - rest.len();
+            match core::str::from_utf8(rest) {
+                Ok(text) => Ok(text.to_string()),
+                Err(error) => Err(ParseError::InvalidEncoding {
+                    encoding: "UTF-8",
+                    offset: bom_len + error.valid_up_to(),
+                }),
+            }
+        }
+    }
+}
+
+/// Reads a quest file and decodes it with [`decode_source`].
+#[cfg(feature = "std")]
+pub(crate) fn read_source(path: &Path) -> Result<String, ParseError> {
+    let bytes = fs::read(path).map_err(|e| ParseError::Io(e.to_string()))?;
+    decode_source(&bytes)
+}
+
+/// Decodes UTF-16 code units after a two-byte byte order mark.
+fn decode_utf16(
+    bytes: &[u8],
+    encoding: &'static str,
+    unit: fn([u8; 2]) -> u16,
+) -> Result<String, ParseError> {
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut offset = 2;
+    for c in char::decode_utf16(units) {
+        let Ok(c) = c else {
+            return Err(ParseError::InvalidEncoding { encoding, offset });
+        };
+        text.push(c);
+        offset += c.len_utf16() * 2;
+    }
+    if !bytes.len().is_multiple_of(2) {
+        return Err(ParseError::InvalidEncoding { encoding, offset });
+    }
+    Ok(text)
+}
@@ -0,0 +1,367 @@
+//! Stable codes for every [`ParseError`], so error trackers can group errors
+//! by kind without matching on messages that may be reworded.
+//!
+//! Codes are grouped by hundreds: `E00xx` for the lexer, `E01xx` for the
+//! structure of a quest, `E02xx` for values, `E03xx` for names, `E04xx` for
+//! grammar versions, `E05xx` for parser limits and `E06xx` for files. A code
+//! is never reused for another kind of error.
+
+use crate::ParseError;
+
+/// An entry of the error catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorInfo {
+    pub code: &'static str,
+    /// One line naming the kind of error, without the details of one error.
+    pub title: &'static str,
+    /// What causes the error and how to fix it, with an example.
+    pub explanation: &'static str,
+}
+
+/// Every code a [`ParseError`] can have, in order.
+pub const ERROR_CODES: &[ErrorInfo] = &[
+    ErrorInfo {
+        code: "E0001",
+        title: "Unexpected character",
+        explanation: "The lexer found a character that cannot start any token, such as `@` or `;` outside a string. Remove it, or put the text in quotes if it belongs to a name or step: `step: \"Meet @ noon\"`.",
+    },
+    ErrorInfo {
+        code: "E0002",
+        title: "Unexpected end of file",
+        explanation: "The input ends in the middle of a quest, usually after a property key or an operator with nothing following it. Finish the property or remove it.",
+    },
+    ErrorInfo {
+        code: "E0003",
+        title: "Unterminated string",
+        explanation: "A string is opened with `\"` but never closed before the end of the input. Add the closing quote; a quote inside the string is written `\\\"`.",
+    },
+    ErrorInfo {
+        code: "E0004",
+        title: "Unterminated raw block",
+        explanation: "A raw block such as ```` ```lua ```` or a `<<<END` heredoc is never closed. End a fenced block with ```` ``` ```` and a heredoc with its delimiter alone on a line.",
+    },
+    ErrorInfo {
+        code: "E0005",
+        title: "Invalid heredoc",
+        explanation: "A heredoc starts with `<<<` followed by a delimiter such as `END` and a line break; the body starts on the next line. Write `script: <<<END` and the body below it.",
+    },
+    ErrorInfo {
+        code: "E0006",
+        title: "Invalid escape sequence",
+        explanation: "A backslash in a string is followed by a character that has no escape. The escapes are `\\n`, `\\t`, `\\r`, `\\\"` and `\\\\`; write `\\\\` for a backslash itself.",
+    },
+    ErrorInfo {
+        code: "E0007",
+        title: "Invalid number",
+        explanation: "A number literal is malformed, such as `0x` without digits or `1__000` with doubled separators. Write integers as digits, optionally with single `_` separators between them, or in hexadecimal after `0x`.",
+    },
+    ErrorInfo {
+        code: "E0008",
+        title: "Number out of range",
+        explanation: "A number or the result of a constant expression does not fit in a 64-bit signed integer. Use a smaller value.",
+    },
+    ErrorInfo {
+        code: "E0009",
+        title: "Unclosed brace",
+        explanation: "A `{` opening a quest body or a block is never closed by a `}` before the end of the input. Add the missing `}`; the error points at the `{` it belongs to.",
+    },
+    ErrorInfo {
+        code: "E0101",
+        title: "Syntax error",
+        explanation: "The parser found a token it did not expect at that position, such as a string where a number is needed or a missing `:` after a property key. The message names what was expected and what was found.",
+    },
+    ErrorInfo {
+        code: "E0102",
+        title: "Unknown property",
+        explanation: "Strict mode or a schema does not allow this property key. Fix the spelling, often suggested in the message, or turn strict mode off to keep unknown properties as extra data.",
+    },
+    ErrorInfo {
+        code: "E0103",
+        title: "Duplicate property",
+        explanation: "Something that can be given once is given twice: a step attribute, as in `[optional, optional]`, a currency of a reward, as in `reward: 10 gold, 5 gold`, or a property a schema does not mark as repeatable. Remove one of them or merge the two.",
+    },
+    ErrorInfo {
+        code: "E0104",
+        title: "Block expected",
+        explanation: "The property is written as a block, such as `objective { kind: \"kill\", target: \"Wolf\" }`, but was given a value after a `:`. Drop the `:` and write the block.",
+    },
+    ErrorInfo {
+        code: "E0105",
+        title: "Value expected",
+        explanation: "The property takes a value, such as `reward: 100`, but was written as a block. Write `key: value` instead of `key { ... }`.",
+    },
+    ErrorInfo {
+        code: "E0106",
+        title: "Unknown step attribute",
+        explanation: "A step attribute in `[...]` after a step is not one the parser knows. The attributes are `optional`, `hidden` and `order: N`, as in `step: \"Dig\" [optional, order: 2]`.",
+    },
+    ErrorInfo {
+        code: "E0107",
+        title: "Missing field",
+        explanation: "A block lacks a field it needs, such as the `target` of a `kill` objective. Add the field named in the message.",
+    },
+    ErrorInfo {
+        code: "E0108",
+        title: "Unknown objective kind",
+        explanation: "An objective block has an empty `kind`. Give it a kind such as `kill`, `collect`, `talk_to` or `reach`, or a custom kind your engine understands.",
+    },
+    ErrorInfo {
+        code: "E0109",
+        title: "Mixed steps",
+        explanation: "A quest with a `steps { ... }` block also declares steps elsewhere. The block must hold every step of the quest; move the other steps into it.",
+    },
+    ErrorInfo {
+        code: "E0110",
+        title: "Invalid repeat count",
+        explanation: "`repeat(N)` needs a count that is not negative. Write a count of 1 or more, as in `repeat(3) step: \"Defeat wave {n}\"`.",
+    },
+    ErrorInfo {
+        code: "E0111",
+        title: "Duplicate reward tier",
+        explanation: "Two `reward tier` blocks of one quest share a name, so the engine could not tell which one a completion earns. Rename or merge them, as in `reward tier \"gold\" { ... } reward tier \"silver\" { ... }`.",
+    },
+    ErrorInfo {
+        code: "E0112",
+        title: "Empty reward tier",
+        explanation: "A `reward tier` block holds no amounts. Give each tier at least one, as in `reward tier \"bronze\" { gold: 50 }`, or remove it.",
+    },
+    ErrorInfo {
+        code: "E0201",
+        title: "Invalid duration",
+        explanation: "A duration such as `time_limit: 2h30m` uses units `d`, `h`, `m` and `s`, each at most once and from largest to smallest. Write `1h30m`, not `30m1h`.",
+    },
+    ErrorInfo {
+        code: "E0202",
+        title: "Negative duration",
+        explanation: "A duration literal is written with a minus sign, such as `-5m`. Durations are lengths of time; drop the sign.",
+    },
+    ErrorInfo {
+        code: "E0203",
+        title: "Invalid date and time",
+        explanation: "A date and time is written as `2025-10-31T00:00:00Z`, or with an offset such as `+02:00` in place of `Z`. Check the format and that the date exists.",
+    },
+    ErrorInfo {
+        code: "E0204",
+        title: "Invalid event window",
+        explanation: "A quest's `ends_at` is not after its `starts_at`, so the event would never run. Swap the two or fix one of them.",
+    },
+    ErrorInfo {
+        code: "E0205",
+        title: "Empty range",
+        explanation: "The lower bound of a range such as `level: 20..10` is greater than its upper bound. Write the smaller bound first: `10..20`.",
+    },
+    ErrorInfo {
+        code: "E0206",
+        title: "Invalid completion condition",
+        explanation: "`complete_when` takes objective names joined by `&&` and `||`, with parentheses for grouping, such as `kill_goblins && (find_key || bribe_guard)`. Other expressions are not allowed there.",
+    },
+    ErrorInfo {
+        code: "E0207",
+        title: "Invalid expression",
+        explanation: "An expression can be folded to a constant but cannot be evaluated, for example because it divides by zero or mixes types. The message names the evaluation error.",
+    },
+    ErrorInfo {
+        code: "E0208",
+        title: "Invalid stages block",
+        explanation: "A `stages { ... }` block is not a valid state machine, for example because a transition names a stage that does not exist. The message names the problem with the stages.",
+    },
+    ErrorInfo {
+        code: "E0301",
+        title: "Undefined variable",
+        explanation: "A `$name` is used without a `let $name = ...` before it. Define the variable earlier in the file or fix its spelling.",
+    },
+    ErrorInfo {
+        code: "E0302",
+        title: "Duplicate variable",
+        explanation: "A `let $name = ...` defines a variable that is already defined. Give the second one another name.",
+    },
+    ErrorInfo {
+        code: "E0303",
+        title: "Duplicate definition",
+        explanation: "Two quests, NPCs, items or templates share a name, or a template lists a parameter twice. Names must be unique across the parsed files; rename one of them.",
+    },
+    ErrorInfo {
+        code: "E0304",
+        title: "Undefined reference",
+        explanation: "A quest refers to an NPC or item that is not defined. Define it, fix the name, or allow external references if it is defined outside the parsed files.",
+    },
+    ErrorInfo {
+        code: "E0305",
+        title: "Unknown template",
+        explanation: "A quest uses a template that is not defined before it. Define the template with `template Name(args) { ... }` earlier in the file or fix its name.",
+    },
+    ErrorInfo {
+        code: "E0306",
+        title: "Missing template argument",
+        explanation: "A template is used without an argument it declares. Pass every argument the template lists.",
+    },
+    ErrorInfo {
+        code: "E0307",
+        title: "Unexpected template argument",
+        explanation: "A template is given an argument it does not declare. Remove the argument or add it to the template.",
+    },
+    ErrorInfo {
+        code: "E0308",
+        title: "Invalid package name",
+        explanation: "A `package` declaration names a package that is not made of identifiers joined by dots. Write names such as `package \"act1.village\"`, without spaces, slashes or empty parts.",
+    },
+    ErrorInfo {
+        code: "E0401",
+        title: "Unsupported grammar version",
+        explanation: "A `#version` pragma names a grammar version this parser does not know. Use a version up to the latest one, or update the parser.",
+    },
+    ErrorInfo {
+        code: "E0402",
+        title: "Misplaced version pragma",
+        explanation: "A `#version` pragma comes after other content. It must be the first thing in the file, before any quest, include or attribute.",
+    },
+    ErrorInfo {
+        code: "E0403",
+        title: "Feature needs a newer grammar version",
+        explanation: "The file uses syntax from a later grammar version than the one it is written in. Add the `#version` pragma named in the message at the top of the file.",
+    },
+    ErrorInfo {
+        code: "E0501",
+        title: "Input too large",
+        explanation: "The input is longer than the parser's size limit. Split the file, or raise the limit in the parser options if the input is trusted.",
+    },
+    ErrorInfo {
+        code: "E0502",
+        title: "Nesting too deep",
+        explanation: "Lists, blocks or expressions are nested deeper than the parser's limit. Flatten the value, or raise the limit if the input is trusted.",
+    },
+    ErrorInfo {
+        code: "E0503",
+        title: "Too many steps",
+        explanation: "A quest has more steps than the parser's limit, counting repeated steps. Split the quest, or raise the limit if the input is trusted.",
+    },
+    ErrorInfo {
+        code: "E0504",
+        title: "String too long",
+        explanation: "A string is longer than the parser's limit. Shorten it, or raise the limit if the input is trusted.",
+    },
+    ErrorInfo {
+        code: "E0505",
+        title: "Too many repeats",
+        explanation: "A `repeat(n) step` asks for more copies of the step than the parser's limit, 10000 by default. Lower the count, or raise the limit if the input is trusted.",
+    },
+    ErrorInfo {
+        code: "E0601",
+        title: "I/O error",
+        explanation: "A file could not be read. Check that it exists and is readable; the message holds the error from the system.",
+    },
+    ErrorInfo {
+        code: "E0602",
+        title: "Include failed",
+        explanation: "An `include` names a file the include resolver cannot find or read. Check the path, which is relative to the including file or one of the include paths.",
+    },
+    ErrorInfo {
+        code: "E0603",
+        title: "Include cycle",
+        explanation: "Files include each other in a cycle, listed in the message. Move the shared part into a file that both include instead.",
+    },
+    ErrorInfo {
+        code: "E0604",
+        title: "Unformattable source",
+        explanation: "The formatter cannot lay out the file without dropping part of it, such as a comment between a key and its colon, which the syntax tree does not keep, or a construct the converter cannot write in another format. Move the comment to a line of its own or after the property's value.",
+    },
+    ErrorInfo {
+        code: "E0605",
+        title: "Invalid text encoding",
+        explanation: "A file holds bytes that are not text in its encoding, such as UTF-16 saved without a byte order mark, which is read as UTF-8. Save the file as UTF-8, or as UTF-16 with a byte order mark.",
+    },
+];
+
+/// Looks up a code of the catalog, ignoring ASCII case.
+///
+/// ```
+/// use game_quest_parser_Hodik::error_info;
+///
+/// assert_eq!(error_info("e0103").map(|info| info.title), Some("Duplicate property"));
+/// assert!(error_info("E9999").is_none());
+/// ```
+pub fn error_info(code: &str) -> Option<&'static ErrorInfo> {
+    ERROR_CODES
+        .iter()
+        .find(|info| info.code.eq_ignore_ascii_case(code))
+}
+
+impl ParseError {
+    /// The stable code of the error, such as `E0103` for a duplicate
+    /// property. An error inside an included file has the code of the
+    /// error it wraps.
+    ///
+    /// ```
+    /// use game_quest_parser_Hodik::Parser;
+    ///
+    /// let error = Parser::new("quest \"A\" { step: \"Dig\" [optional, optional] }")?
+    ///     .parse_quest()
+    ///     .unwrap_err();
+    /// assert_eq!(error.code(), "E0103");
+    /// assert!(error.explain().starts_with("Something that can be given once"));
+    /// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedChar { .. } => "E0001",
+            ParseError::UnexpectedEOF => "E0002",
+            ParseError::UnterminatedString { .. } => "E0003",
+            ParseError::UnterminatedRaw { .. } => "E0004",
+            ParseError::InvalidHeredoc { .. } => "E0005",
+            ParseError::InvalidEscape(_) => "E0006",
+            ParseError::InvalidNumber { .. } => "E0007",
+            ParseError::NumberOverflow(_) => "E0008",
+            ParseError::UnclosedBrace { .. } => "E0009",
+            ParseError::SyntaxError { .. } => "E0101",
+            ParseError::UnknownProperty { .. } => "E0102",
+            ParseError::DuplicateProperty { .. } => "E0103",
+            ParseError::BlockExpected { .. } => "E0104",
+            ParseError::ValueExpected { .. } => "E0105",
+            ParseError::UnknownAttribute { .. } => "E0106",
+            ParseError::MissingField { .. } => "E0107",
+            ParseError::UnknownObjectiveKind(_) => "E0108",
+            ParseError::MixedSteps { .. } => "E0109",
+            ParseError::InvalidRepeatCount { .. } => "E0110",
+            ParseError::DuplicateTier { .. } => "E0111",
+            ParseError::EmptyTier { .. } => "E0112",
+            ParseError::InvalidDuration { .. } => "E0201",
+            ParseError::NegativeDuration { .. } => "E0202",
+            ParseError::InvalidDateTime { .. } => "E0203",
+            ParseError::InvalidEventWindow { .. } => "E0204",
+            ParseError::InvalidRange { .. } => "E0205",
+            ParseError::InvalidCondition { .. } => "E0206",
+            ParseError::InvalidExpression { .. } => "E0207",
+            ParseError::InvalidStages { .. } => "E0208",
+            ParseError::UndefinedVariable { .. } => "E0301",
+            ParseError::DuplicateVariable(_) => "E0302",
+            ParseError::DuplicateDefinition { .. } => "E0303",
+            ParseError::UndefinedReference { .. } => "E0304",
+            ParseError::UnknownTemplate { .. } => "E0305",
+            ParseError::MissingArgument { .. } => "E0306",
+            ParseError::UnexpectedArgument { .. } => "E0307",
+            ParseError::InvalidPackage { .. } => "E0308",
+            ParseError::UnsupportedVersion { .. } => "E0401",
+            ParseError::MisplacedVersion { .. } => "E0402",
+            ParseError::RequiresVersion { .. } => "E0403",
+            ParseError::InputTooLarge { .. } => "E0501",
+            ParseError::NestingTooDeep { .. } => "E0502",
+            ParseError::TooManySteps { .. } => "E0503",
+            ParseError::StringTooLong { .. } => "E0504",
+            ParseError::TooManyRepeats { .. } => "E0505",
+            ParseError::Io(_) => "E0601",
+            ParseError::IncludeFailed { .. } => "E0602",
+            ParseError::IncludeCycle(_) => "E0603",
+            ParseError::Unformattable(_) => "E0604",
+            ParseError::InvalidEncoding { .. } => "E0605",
+            ParseError::InInclude { error, .. } => error.code(),
+        }
+    }
+
+    /// The catalog's explanation of the error's code: what causes it and
+    /// how to fix it.
+    pub fn explain(&self) -> &'static str {
+        error_info(self.code())
+            .expect("every error code is in the catalog")
+            .explanation
+    }
+}
@@ -0,0 +1,278 @@
+use core::cmp::Ordering;
+
+use thiserror::Error;
+
+use crate::prelude::*;
+use crate::{DateTime, Token, Value};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum EvalError {
+    #[error("Unknown variable `{0}`")]
+    UnknownVariable(String),
+    #[error("Cannot apply `{op}` to {lhs} and {rhs}")]
+    InvalidOperands {
+        op: &'static str,
+        lhs: &'static str,
+        rhs: &'static str,
+    },
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Arithmetic overflow")]
+    Overflow,
+    #[error("Expected a Bool, found {0}")]
+    ExpectedBool(&'static str),
+}
+
+/// Looks up the game-state variables an [`Expr`] refers to by name.
+pub trait Context {
+    fn lookup(&self, name: &str) -> Option<Value>;
+}
+
+impl Context for HashMap<String, Value> {
+    fn lookup(&self, name: &str) -> Option<Value> {
+        self.get(name).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    pub(crate) fn from_token(token: &Token) -> Option<BinaryOp> {
+        let op = match token {
+            Token::Plus => BinaryOp::Add,
+            Token::Minus => BinaryOp::Sub,
+            Token::Star => BinaryOp::Mul,
+            Token::Slash => BinaryOp::Div,
+            Token::Percent => BinaryOp::Rem,
+            Token::EqEq => BinaryOp::Eq,
+            Token::NotEq => BinaryOp::Ne,
+            Token::Less => BinaryOp::Lt,
+            Token::LessEq => BinaryOp::Le,
+            Token::Greater => BinaryOp::Gt,
+            Token::GreaterEq => BinaryOp::Ge,
+            Token::AndAnd => BinaryOp::And,
+            Token::OrOr => BinaryOp::Or,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Binding power of the operator; all operators are left-associative.
+    pub(crate) fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq | BinaryOp::Ne => 3,
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 4,
+            BinaryOp::Add | BinaryOp::Sub => 5,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 6,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+        }
+    }
+}
+
+/// A property value computed from game state, such as `100 + level * 10`.
+///
+/// Bare identifiers name variables supplied by a [`Context`] when the
+/// expression is evaluated; `$NAME` constants are substituted while parsing
+/// and appear as literals.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Expr {
+    Literal(Value),
+    Identifier(String),
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+impl Expr {
+    /// Whether the expression refers to no variables, so it evaluates the same
+    /// in every context.
+    pub fn is_constant(&self) -> bool {
+        match self {
+            Expr::Literal(_) => true,
+            Expr::Identifier(_) => false,
+            Expr::Binary { lhs, rhs, .. } => lhs.is_constant() && rhs.is_constant(),
+        }
+    }
+
+    /// Computes the value of the expression.
+    ///
+    /// Integers and floats mix by promoting to float, `+` also concatenates
+    /// strings, durations can be added, subtracted and compared, comparisons
+    /// work on numbers and strings, and `&&`/`||` take booleans and
+    /// short-circuit. Subtracting a longer duration from a shorter one is an
+    /// [`EvalError::Overflow`].
+    pub fn evaluate<C: Context + ?Sized>(&self, context: &C) -> Result<Value, EvalError> {
+        match self {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Identifier(name) => context
+                .lookup(name)
+                .ok_or_else(|| EvalError::UnknownVariable(name.clone())),
+            Expr::Binary { op, lhs, rhs } => {
+                let lhs = lhs.evaluate(context)?;
+                match (op, &lhs) {
+                    (BinaryOp::And, Value::Bool(false)) => return Ok(lhs),
+                    (BinaryOp::Or, Value::Bool(true)) => return Ok(lhs),
+                    _ => {}
+                }
+                apply(*op, lhs, rhs.evaluate(context)?)
+            }
+        }
+    }
+}
+
+fn apply(op: BinaryOp, lhs: Value, rhs: Value) -> Result<Value, EvalError> {
+    use BinaryOp::*;
+
+    let invalid = |lhs: &Value, rhs: &Value| EvalError::InvalidOperands {
+        op: op.symbol(),
+        lhs: type_name(lhs),
+        rhs: type_name(rhs),
+    };
+
+    match op {
+        And | Or => match (&lhs, &rhs) {
+            (Value::Bool(_), Value::Bool(_)) => Ok(rhs),
+            _ => Err(invalid(&lhs, &rhs)),
+        },
+        Eq | Ne => {
+            let equal = match (&lhs, &rhs) {
+                (Value::Number(a), Value::Float(b)) => (*a as f64) == *b,
+                (Value::Float(a), Value::Number(b)) => *a == (*b as f64),
+                _ => lhs == rhs,
+            };
+            Ok(Value::Bool(equal == (op == Eq)))
+        }
+        Lt | Le | Gt | Ge => {
+            let ordering = match (&lhs, &rhs) {
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                (Value::Duration(a), Value::Duration(b)) => Some(a.cmp(b)),
+                (Value::DateTime(a), Value::DateTime(b)) => Some(a.cmp(b)),
+                _ => match (as_float(&lhs), as_float(&rhs)) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    _ => return Err(invalid(&lhs, &rhs)),
+                },
+            };
+            let result = ordering.is_some_and(|ordering| match op {
+                Lt => ordering == Ordering::Less,
+                Le => ordering != Ordering::Greater,
+                Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            });
+            Ok(Value::Bool(result))
+        }
+        Add | Sub | Mul | Div | Rem => match (&lhs, &rhs) {
+            (Value::String(a), Value::String(b)) if op == Add => {
+                Ok(Value::String(format!("{a}{b}")))
+            }
+            (Value::Duration(a), Value::Duration(b)) if matches!(op, Add | Sub) => {
+                let result = match op {
+                    Add => a.checked_add(*b),
+                    _ => a.checked_sub(*b),
+                };
+                result.map(Value::Duration).ok_or(EvalError::Overflow)
+            }
+            (Value::DateTime(instant), Value::Duration(duration)) if matches!(op, Add | Sub) => {
+                let seconds = i64::try_from(duration.as_secs()).map_err(|_| EvalError::Overflow)?;
+                let result = match op {
+                    Add => instant.unix_timestamp().checked_add(seconds),
+                    _ => instant.unix_timestamp().checked_sub(seconds),
+                };
+                result
+                    .and_then(DateTime::from_unix_timestamp)
+                    .map(Value::DateTime)
+                    .ok_or(EvalError::Overflow)
+            }
+            (Value::Number(a), Value::Number(b)) => {
+                let result = match op {
+                    Add => a.checked_add(*b),
+                    Sub => a.checked_sub(*b),
+                    Mul => a.checked_mul(*b),
+                    _ if *b == 0 => return Err(EvalError::DivisionByZero),
+                    Div => a.checked_div(*b),
+                    _ => a.checked_rem(*b),
+                };
+                result.map(Value::Number).ok_or(EvalError::Overflow)
+            }
+            _ => {
+                let (Some(a), Some(b)) = (as_float(&lhs), as_float(&rhs)) else {
+                    return Err(invalid(&lhs, &rhs));
+                };
+                let result = match op {
+                    Add => a + b,
+                    Sub => a - b,
+                    Mul => a * b,
+                    _ if b == 0.0 => return Err(EvalError::DivisionByZero),
+                    Div => a / b,
+                    _ => a % b,
+                };
+                if !result.is_finite() {
+                    return Err(EvalError::Overflow);
+                }
+                Ok(Value::Float(result))
+            }
+        },
+    }
+}
+
+fn as_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => Some(*n as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "String",
+        Value::Number(_) => "Number",
+        Value::Float(_) => "Float",
+        Value::Bool(_) => "Bool",
+        Value::List(_) => "List",
+        Value::Raw { .. } => "Raw",
+        Value::Localized(_) => "Localized",
+        Value::Duration(_) => "Duration",
+        Value::DateTime(_) => "DateTime",
+        Value::Range(..) => "Range",
+    }
+}
@@ -0,0 +1,39 @@
+use std::path::Path;
+#[cfg(feature = "parallel")]
+use std::path::PathBuf;
+
+use crate::encoding::read_source;
+use crate::{ParseError, Parser, ParserOptions, Quest};
+
+/// Reads and parses every quest of one file, naming the input after its path
+/// so relative includes resolve against it.
+pub fn parse_file(path: &Path, options: &ParserOptions) -> Result<Vec<Quest>, ParseError> {
+    let source = read_source(path)?;
+    Parser::with_options(&source, options.clone())?
+        .with_source_name(path.display().to_string())
+        .parse_quests()
+}
+
+/// Parses many files across all cores with default options.
+///
+/// Results come back in the order of `paths`. Runs on rayon's global thread
+/// pool; call it inside [`rayon::ThreadPool::install`] to bound the number of
+/// threads.
+#[cfg(feature = "parallel")]
+pub fn parse_files_parallel(paths: &[PathBuf]) -> Vec<(PathBuf, Result<Vec<Quest>, ParseError>)> {
+    parse_files_parallel_with_options(paths, &ParserOptions::default())
+}
+
+/// [`parse_files_parallel`] with the given parser options.
+#[cfg(feature = "parallel")]
+pub fn parse_files_parallel_with_options(
+    paths: &[PathBuf],
+    options: &ParserOptions,
+) -> Vec<(PathBuf, Result<Vec<Quest>, ParseError>)> {
+    use rayon::prelude::*;
+
+    paths
+        .par_iter()
+        .map(|path| (path.clone(), parse_file(path, options)))
+        .collect()
+}
@@ -0,0 +1,71 @@
+//! Entry points for pieces of a quest, so tools such as an editor's inline
+//! field validator can check one value or property without wrapping it in a
+//! `quest X { ... }` shell.
+
+use crate::{Expr, ParseError, Parser, Quest, Token, Value};
+
+/// Parses a single value, such as `100`, `"Find the sword"`, `[1, 2]` or
+/// `2h30m`. The input must hold nothing else.
+///
+/// ```
+/// use game_quest_parser_Hodik::{parse_value, Value};
+///
+/// assert_eq!(parse_value("[1, 2]")?, Value::List(vec![Value::Number(1), Value::Number(2)]));
+/// assert!(parse_value("1 2").is_err());
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn parse_value(input: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser::new(input)?;
+    let value = parser.rule("value", Parser::parse_value)?;
+    expect_end(&parser)?;
+    Ok(value)
+}
+
+/// Parses a single expression, such as `reward > 100 && active`, as written
+/// on the right of a property. Identifiers are left as variables and
+/// constant parts are not folded.
+///
+/// ```
+/// use game_quest_parser_Hodik::{parse_expr, Expr};
+///
+/// assert_eq!(parse_expr("level")?, Expr::Identifier("level".to_string()));
+/// assert!(parse_expr("level >").is_err());
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn parse_expr(input: &str) -> Result<Expr, ParseError> {
+    let mut parser = Parser::new(input)?;
+    let expr = parser.rule("expression", |p| p.parse_expr(0))?;
+    expect_end(&parser)?;
+    Ok(expr)
+}
+
+/// Parses a single property, such as `reward: 100` or an `objective { ... }`
+/// block, and returns an unnamed quest with only that property set. The
+/// input may end with a comma, as the property would inside a quest, but
+/// must hold nothing else.
+///
+/// ```
+/// use game_quest_parser_Hodik::parse_property;
+///
+/// assert_eq!(parse_property("reward: 50 + 50,")?.reward, 100);
+/// assert!(parse_property("reward: \"lots\"").is_err());
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn parse_property(input: &str) -> Result<Quest, ParseError> {
+    let mut parser = Parser::new(input)?;
+    let mut quest = Quest::default();
+    parser.rule("property", |p| p.parse_property(&mut quest))?;
+    if parser.current_token == Token::Comma {
+        parser.eat(Token::Comma)?;
+    }
+    expect_end(&parser)?;
+    Ok(quest)
+}
+
+fn expect_end(parser: &Parser) -> Result<(), ParseError> {
+    if parser.current_token == Token::Eof {
+        Ok(())
+    } else {
+        Err(parser.syntax_error("end of input"))
+    }
+}
@@ -0,0 +1,87 @@
+//! The grammar the parser implements, for documentation and tooling.
+//!
+//! Both exports are generated at build time from the `# Grammar Rule` blocks
+//! on the parser's methods, so they describe exactly what the parser accepts.
+
+use core::fmt::Write;
+
+use crate::prelude::*;
+
+/// Tokens produced by the lexer that the grammar uses without defining.
+pub const TOKENS: &[&str] = &[
+    "IDENTIFIER",
+    "STRING",
+    "RAW",
+    "INTEGER",
+    "FLOAT",
+    "DATETIME",
+    "BOOLEAN",
+    "NEWLINE",
+];
+
+/// The whole grammar in EBNF, one rule per line, in the order the parser
+/// documents them. Alternatives that do not fit on one line continue on
+/// indented lines.
+///
+/// ```
+/// let grammar = game_quest_parser_Hodik::grammar::ebnf();
+/// assert!(grammar.lines().any(|line| line.starts_with("QUEST_DEF")));
+/// ```
+pub fn ebnf() -> &'static str {
+    include_str!(concat!(env!("OUT_DIR"), "/grammar.ebnf"))
+}
+
+/// The grammar as a Graphviz digraph with one node per rule and an edge to
+/// every rule or token it refers to, for `dot -Tsvg`.
+///
+/// Rules are drawn as boxes and lexer [`TOKENS`] as ellipses.
+pub fn dot() -> String {
+    let rules = rules();
+    let mut out = String::from("digraph grammar {\n    rankdir=LR;\n");
+    for (name, _) in &rules {
+        writeln!(out, "    {name} [shape=box];").unwrap();
+    }
+    for token in TOKENS {
+        writeln!(out, "    {token} [shape=ellipse];").unwrap();
+    }
+    for (name, body) in &rules {
+        let mut seen = Vec::new();
+        for reference in references(body) {
+            if !seen.contains(&reference) {
+                writeln!(out, "    {name} -> {reference};").unwrap();
+                seen.push(reference);
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Every rule of [`ebnf`] with its definition, continuation lines joined
+/// into one.
+pub fn rules() -> Vec<(&'static str, String)> {
+    let mut rules: Vec<(&str, String)> = Vec::new();
+    for line in ebnf().lines() {
+        match line.split_once("::=") {
+            Some((name, body)) if !line.starts_with(' ') => {
+                rules.push((name.trim(), body.trim().to_string()))
+            }
+            _ => {
+                if let Some((_, body)) = rules.last_mut() {
+                    body.push(' ');
+                    body.push_str(line.trim());
+                }
+            }
+        }
+    }
+    rules
+}
+
+/// The names of the rules and tokens a definition refers to, skipping
+/// quoted terminals.
+fn references(body: &str) -> impl Iterator<Item = &str> {
+    body.split('"')
+        .step_by(2)
+        .flat_map(|unquoted| unquoted.split(|c: char| !(c.is_ascii_uppercase() || c == '_')))
+        .filter(|word| !word.is_empty())
+}
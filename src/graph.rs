@@ -0,0 +1,247 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Write;
+
+use thiserror::Error;
+
+use crate::package::candidates;
+use crate::{Quest, ReferenceKind};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum GraphError {
+    #[error("Quest {0:?} is defined more than once")]
+    DuplicateQuest(String),
+    #[error("Quest {quest:?} requires unknown quest {requires:?}")]
+    UnknownPrerequisite { quest: String, requires: String },
+    #[error("Dependency cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// Dependency graph built from the `requires` properties of a set of quests.
+///
+/// Edges point from a quest to each of its prerequisites. Every prerequisite
+/// must be part of the same set. Quests are named by their
+/// [qualified name](Quest::qualified_name), and references name a quest by
+/// its id or else by its name, as for [`resolve`](crate::resolve).
+#[derive(Debug)]
+pub struct QuestGraph<'a> {
+    quests: Vec<&'a Quest>,
+    index: HashMap<String, usize>,
+    /// Qualified ids of the quests that have one.
+    ids: HashMap<String, usize>,
+    edges: Vec<Vec<usize>>,
+}
+
+impl<'a> QuestGraph<'a> {
+    pub fn new(quests: &'a [Quest]) -> Result<Self, GraphError> {
+        let mut graph = Self {
+            quests: quests.iter().collect(),
+            index: HashMap::new(),
+            ids: HashMap::new(),
+            edges: Vec::with_capacity(quests.len()),
+        };
+        for (i, quest) in quests.iter().enumerate() {
+            let name = quest.qualified_name();
+            if graph.index.insert(name.clone(), i).is_some() {
+                return Err(GraphError::DuplicateQuest(name));
+            }
+            if quest.id.is_some() {
+                graph.ids.insert(quest.qualified_id(), i);
+            }
+        }
+
+        for quest in quests {
+            let mut deps = Vec::with_capacity(quest.requires.len());
+            for name in &quest.requires {
+                match graph.lookup(name, quest.package.as_deref()) {
+                    Some(dep) => deps.push(dep),
+                    None => {
+                        return Err(GraphError::UnknownPrerequisite {
+                            quest: quest.name.clone(),
+                            requires: name.clone(),
+                        })
+                    }
+                }
+            }
+            graph.edges.push(deps);
+        }
+        Ok(graph)
+    }
+
+    pub fn quests(&self) -> &[&'a Quest] {
+        &self.quests
+    }
+
+    pub fn get(&self, name: &str) -> Option<&'a Quest> {
+        self.index.get(name).map(|&i| self.quests[i])
+    }
+
+    /// Returns the direct prerequisites of the named quest.
+    pub fn prerequisites(&self, name: &str) -> Option<Vec<&'a Quest>> {
+        let &i = self.index.get(name)?;
+        Some(self.edges[i].iter().map(|&d| self.quests[d]).collect())
+    }
+
+    /// Finds a dependency cycle, if any.
+    ///
+    /// The cycle is returned as quest names along the `requires` edges, with
+    /// the first quest repeated at the end (`["A", "B", "A"]`). The search
+    /// keeps its own stack, so long chains of prerequisites cannot overflow
+    /// the thread's.
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            New,
+            Visiting,
+            Done,
+        }
+
+        let mut marks = vec![Mark::New; self.quests.len()];
+        // The quests being visited, each with the next of its edges to follow.
+        let mut path: Vec<(usize, usize)> = Vec::new();
+        for root in 0..self.quests.len() {
+            if marks[root] != Mark::New {
+                continue;
+            }
+            marks[root] = Mark::Visiting;
+            path.push((root, 0));
+            while let Some(top) = path.last_mut() {
+                let (node, next) = *top;
+                let Some(&dep) = self.edges[node].get(next) else {
+                    marks[node] = Mark::Done;
+                    path.pop();
+                    continue;
+                };
+                top.1 += 1;
+                match marks[dep] {
+                    Mark::Visiting => {
+                        let start = path.iter().position(|&(n, _)| n == dep).unwrap_or(0);
+                        let mut cycle: Vec<String> = path[start..]
+                            .iter()
+                            .map(|&(n, _)| self.quests[n].name.clone())
+                            .collect();
+                        cycle.push(self.quests[dep].name.clone());
+                        return Some(cycle);
+                    }
+                    Mark::New => {
+                        marks[dep] = Mark::Visiting;
+                        path.push((dep, 0));
+                    }
+                    Mark::Done => {}
+                }
+            }
+        }
+        None
+    }
+
+    /// Orders the quests so that every quest comes after all of its
+    /// prerequisites. Quests without a mutual dependency keep their input order.
+    pub fn topological_order(&self) -> Result<Vec<&'a Quest>, GraphError> {
+        if let Some(cycle) = self.find_cycle() {
+            return Err(GraphError::Cycle(cycle));
+        }
+
+        let mut remaining: Vec<usize> = self.edges.iter().map(Vec::len).collect();
+        let mut dependents = vec![Vec::new(); self.quests.len()];
+        for (node, deps) in self.edges.iter().enumerate() {
+            for &dep in deps {
+                dependents[dep].push(node);
+            }
+        }
+
+        // Quests whose prerequisites are all ordered, earliest in the input
+        // first.
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..self.quests.len())
+            .filter(|&n| remaining[n] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(self.quests.len());
+        while let Some(Reverse(next)) = ready.pop() {
+            for &dependent in &dependents[next] {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    ready.push(Reverse(dependent));
+                }
+            }
+            order.push(self.quests[next]);
+        }
+        Ok(order)
+    }
+
+    /// Renders the graph as a Graphviz digraph, for `dot -Tsvg`.
+    ///
+    /// Edges follow the flow of the campaign: a solid edge leads from a
+    /// prerequisite to the quest requiring it, and a dashed edge from a quest
+    /// to each quest its `on_success` or `on_failure` block unlocks, looked
+    /// up by id or name. Unlocked quests outside the graph are drawn dashed.
+    ///
+    /// ```
+    /// use game_quest_parser_Hodik::{Parser, QuestGraph};
+    ///
+    /// let quests = Parser::new(r#"quest "Intro" {} quest "Wolves" { requires: "Intro" }"#)?
+    ///     .parse_quests()?;
+    /// let dot = QuestGraph::new(&quests).unwrap().to_dot();
+    /// assert!(dot.contains("q0 -> q1;"));
+    /// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph quests {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (node, quest) in self.quests.iter().enumerate() {
+            writeln!(out, "    q{node} [label={}];", dot_string(&quest.name)).unwrap();
+        }
+
+        let mut missing: Vec<&str> = Vec::new();
+        let mut edges = String::new();
+        for (node, deps) in self.edges.iter().enumerate() {
+            for dep in deps {
+                writeln!(edges, "    q{dep} -> q{node};").unwrap();
+            }
+        }
+        for (node, quest) in self.quests.iter().enumerate() {
+            let outcomes = [
+                (ReferenceKind::SuccessUnlock, &quest.on_success),
+                (ReferenceKind::FailureUnlock, &quest.on_failure),
+            ];
+            for (kind, outcome) in outcomes {
+                for target in outcome.iter().flat_map(|o| &o.unlocks) {
+                    let to = match self.lookup(target, quest.package.as_deref()) {
+                        Some(i) => format!("q{i}"),
+                        None => {
+                            let i = missing.iter().position(|m| m == target).unwrap_or_else(|| {
+                                missing.push(target);
+                                missing.len() - 1
+                            });
+                            format!("missing{i}")
+                        }
+                    };
+                    let label = dot_string(&kind.to_string());
+                    writeln!(edges, "    q{node} -> {to} [style=dashed, label={label}];").unwrap();
+                }
+            }
+        }
+        for (i, name) in missing.iter().enumerate() {
+            writeln!(
+                out,
+                "    missing{i} [label={}, style=dashed];",
+                dot_string(name)
+            )
+            .unwrap();
+        }
+        out.push_str(&edges);
+        out.push_str("}\n");
+        out
+    }
+
+    /// Finds the quest a reference from `package` names, by id or else by
+    /// name.
+    fn lookup(&self, target: &str, package: Option<&str>) -> Option<usize> {
+        candidates(target, package)
+            .iter()
+            .find_map(|c| self.ids.get(c).or_else(|| self.index.get(c)).copied())
+    }
+}
+
+/// Quotes `s` as a DOT string.
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
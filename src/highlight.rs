@@ -0,0 +1,80 @@
+use crate::prelude::*;
+use crate::{Lexer, Span, Token};
+
+/// Coarse token category for syntax highlighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    Keyword,
+    String,
+    Number,
+    Punctuation,
+    Comment,
+    Identifier,
+}
+
+impl TokenClass {
+    pub fn of(token: &Token) -> Option<TokenClass> {
+        let class = match token {
+            Token::QuestKeyword
+            | Token::LetKeyword
+            | Token::IncludeKeyword
+            | Token::True
+            | Token::False => TokenClass::Keyword,
+            Token::Identifier(_) | Token::Variable(_) => TokenClass::Identifier,
+            Token::StringLiteral(_) | Token::Raw { .. } => TokenClass::String,
+            Token::Number(_) | Token::Float(_) | Token::Duration(_) | Token::DateTime(_) => {
+                TokenClass::Number
+            }
+            Token::LBrace
+            | Token::RBrace
+            | Token::LBracket
+            | Token::RBracket
+            | Token::Colon
+            | Token::Comma
+            | Token::Equals
+            | Token::Arrow
+            | Token::DotDot
+            | Token::Hash
+            | Token::LParen
+            | Token::RParen
+            | Token::Plus
+            | Token::Minus
+            | Token::Star
+            | Token::Slash
+            | Token::Percent
+            | Token::EqEq
+            | Token::NotEq
+            | Token::Less
+            | Token::LessEq
+            | Token::Greater
+            | Token::GreaterEq
+            | Token::AndAnd
+            | Token::OrOr => TokenClass::Punctuation,
+            Token::Comment(_) => TokenClass::Comment,
+            Token::Eof => return None,
+        };
+        Some(class)
+    }
+}
+
+/// Classifies every token of `input` for editor highlighting.
+///
+/// Unlike the parser this never fails: characters the lexer rejects are
+/// skipped and classification resumes after them, so half-typed documents
+/// still highlight.
+pub fn highlight(input: &str) -> Vec<(Span, TokenClass)> {
+    let mut lexer = Lexer::new(input);
+    let mut classes = Vec::new();
+    loop {
+        match lexer.next_token() {
+            Ok((Token::Eof, _)) => break,
+            Ok((token, span)) => {
+                if let Some(class) = TokenClass::of(&token) {
+                    classes.push((span, class));
+                }
+            }
+            Err(_) => {}
+        }
+    }
+    classes
+}
@@ -0,0 +1,98 @@
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "std")]
+use crate::decode_source;
+use crate::prelude::*;
+
+/// Contents of a file pulled in by an `include` directive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncludeSource {
+    /// Canonical name of the file. Two includes resolving to the same name
+    /// are treated as the same file for cycle detection and error chains.
+    pub name: String,
+    pub source: String,
+}
+
+/// Loads the files named by `include "..."` directives.
+pub trait IncludeResolver: Send + Sync {
+    /// Resolves `path` as written in a directive inside the file named `from`
+    /// (`None` when the directive is in the root input).
+    fn resolve(&self, path: &str, from: Option<&str>) -> Result<IncludeSource, String>;
+}
+
+/// Default resolver reading includes from the filesystem.
+///
+/// Paths are relative to the directory of the including file, or to `root`
+/// for directives in input that has no file name. Paths not found there are
+/// looked up in the search paths, in order.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct FsIncludeResolver {
+    root: PathBuf,
+    search_paths: Vec<PathBuf>,
+}
+
+#[cfg(feature = "std")]
+impl FsIncludeResolver {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            search_paths: Vec::new(),
+        }
+    }
+
+    /// Also looks for includes in `paths`, such as a directory of files
+    /// shared between packs.
+    pub fn with_search_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.search_paths = paths;
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for FsIncludeResolver {
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+#[cfg(feature = "std")]
+impl IncludeResolver for FsIncludeResolver {
+    fn resolve(&self, path: &str, from: Option<&str>) -> Result<IncludeSource, String> {
+        let base = match from.and_then(|f| Path::new(f).parent()) {
+            Some(dir) => dir.to_path_buf(),
+            None => self.root.clone(),
+        };
+        let full = base.join(path);
+        let found = std::iter::once(&base)
+            .chain(&self.search_paths)
+            .map(|dir| dir.join(path))
+            .find(|candidate| candidate.exists());
+        // Report the path next to the including file when nothing matches.
+        let full = found.unwrap_or(full);
+        let canonical = fs::canonicalize(&full).map_err(|e| format!("{}: {e}", full.display()))?;
+        let source = fs::read(&canonical)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| decode_source(&bytes).map_err(|e| e.to_string()))
+            .map_err(|e| format!("{}: {e}", full.display()))?;
+        Ok(IncludeSource {
+            name: canonical.display().to_string(),
+            source,
+        })
+    }
+}
+
+/// Resolver that refuses every include, for input that must be parsed on
+/// its own (untrusted uploads, in-browser validation). The default without
+/// the `std` feature, which has no filesystem to read from.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoIncludeResolver;
+
+impl IncludeResolver for NoIncludeResolver {
+    fn resolve(&self, _path: &str, _from: Option<&str>) -> Result<IncludeSource, String> {
+        Err("includes are disabled".to_string())
+    }
+}
@@ -0,0 +1,118 @@
+use alloc::borrow::Cow;
+
+use crate::prelude::*;
+use crate::{LocalizedString, Quest, Step, StepTree, Text, Value};
+
+impl Quest {
+    /// Returns a copy of the quest with every `{name}` placeholder in its
+    /// steps and in the strings of its extra properties replaced by
+    /// `vars["name"]`. Placeholders without a value are left as they are.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use game_quest_parser_Hodik::Parser;
+    ///
+    /// let quest = Parser::new(r#"quest "Arena" { step: "Survive {waves} waves in {arena}" }"#)?
+    ///     .parse_quest()?;
+    /// let vars = HashMap::from([("arena".to_string(), "the Pit".to_string())]);
+    /// let quest = quest.interpolate(&vars);
+    /// assert_eq!(quest.steps[0], "Survive {waves} waves in the Pit");
+    /// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+    /// ```
+    pub fn interpolate(&self, vars: &HashMap<String, String>) -> Quest {
+        let mut quest = self.clone();
+        for step in &mut quest.steps {
+            *step = step.interpolate(vars);
+        }
+        if let Some(tree) = &mut quest.step_tree {
+            interpolate_tree(tree, vars);
+        }
+        for value in quest.extra.values_mut() {
+            interpolate_value(value, vars);
+        }
+        quest
+    }
+}
+
+impl Step {
+    /// The step with the placeholders in its text replaced, in every
+    /// language of a localized text.
+    pub(crate) fn interpolate(&self, vars: &HashMap<String, String>) -> Step {
+        let text = match &self.text {
+            Text::Plain(s) => Text::Plain(interpolate_str(s, vars).into_owned()),
+            Text::Localized(localized) => Text::Localized(interpolate_localized(localized, vars)),
+        };
+        Step {
+            text,
+            ..self.clone()
+        }
+    }
+}
+
+fn interpolate_tree(tree: &mut StepTree, vars: &HashMap<String, String>) {
+    match tree {
+        StepTree::Step(step) => *step = step.interpolate(vars),
+        StepTree::Sequence(items) | StepTree::AnyOf(items) | StepTree::AllOf(items) => {
+            for item in items {
+                interpolate_tree(item, vars);
+            }
+        }
+    }
+}
+
+fn interpolate_localized(
+    localized: &LocalizedString,
+    vars: &HashMap<String, String>,
+) -> LocalizedString {
+    LocalizedString(
+        localized
+            .0
+            .iter()
+            .map(|(lang, s)| (lang.clone(), interpolate_str(s, vars).into_owned()))
+            .collect(),
+    )
+}
+
+fn interpolate_value(value: &mut Value, vars: &HashMap<String, String>) {
+    match value {
+        Value::String(s) => {
+            if let Cow::Owned(replaced) = interpolate_str(s, vars) {
+                *s = replaced;
+            }
+        }
+        Value::Localized(localized) => *localized = interpolate_localized(localized, vars),
+        Value::List(items) => {
+            for item in items {
+                interpolate_value(item, vars);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn interpolate_str<'t>(text: &'t str, vars: &HashMap<String, String>) -> Cow<'t, str> {
+    if !text.contains('{') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after
+            .find('}')
+            .and_then(|close| Some((close, vars.get(&after[..close])?)));
+        match value {
+            Some((close, value)) => {
+                out.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
@@ -0,0 +1,617 @@
+use alloc::borrow::Cow;
+use core::iter::Peekable;
+use core::num::IntErrorKind;
+use core::str::CharIndices;
+use core::time::Duration;
+
+use unicode_ident::{is_xid_continue, is_xid_start};
+
+use crate::encoding::BOM;
+use crate::prelude::*;
+use crate::{nfc, DateTime, ParseError};
+
+/// Byte range of a token in the source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+}
+
+/// A lexical token. Text-carrying tokens borrow from the source; a string
+/// literal only owns its text when it contains escape sequences or is not in
+/// Unicode NFC, to which it is normalized.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Token<'a> {
+    QuestKeyword,
+    LetKeyword,
+    IncludeKeyword,
+    Identifier(&'a str),
+    /// A `$NAME` reference to a `let` constant.
+    Variable(&'a str),
+    StringLiteral(Cow<'a, str>),
+    Number(i64),
+    Float(f64),
+    /// A duration literal such as `2h30m`.
+    Duration(Duration),
+    /// A date and time literal such as `2025-10-31T00:00:00Z`.
+    DateTime(DateTime),
+    /// A verbatim block, fenced as in ```` ```lua ... ``` ```` or written as
+    /// a heredoc, `<<<END` up to a line starting with `END`. The body is
+    /// borrowed as written, without escapes or normalization.
+    Raw {
+        lang: Option<&'a str>,
+        body: &'a str,
+    },
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Colon,
+    Comma,
+    Equals,
+    /// `->` between two stages of a `stages` block.
+    Arrow,
+    /// `..` between the bounds of a range such as `10..20`.
+    DotDot,
+    /// `#` opening a pragma such as `#version 2`.
+    Hash,
+    LParen,
+    RParen,
+    Plus,
+    /// A `-` that does not start a number literal.
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    EqEq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    AndAnd,
+    OrOr,
+    True,
+    False,
+    /// A `//` line comment; the text excludes the slashes and the newline.
+    Comment(&'a str),
+    Eof,
+}
+
+/// Tokenizer for quest source text.
+///
+/// Besides feeding the [`Parser`](crate::Parser), the lexer can be used on its
+/// own as an iterator of tokens with their spans. Iteration stops after the
+/// last token (the `Eof` token is not yielded) or after the first error.
+pub struct Lexer<'a> {
+    source: &'a str,
+    input: Peekable<CharIndices<'a>>,
+    len: usize,
+    finished: bool,
+}
+
+impl<'a> Lexer<'a> {
+    /// Starts lexing `input`, skipping a byte order mark at its start. Spans
+    /// still count the mark's bytes, so they index into `input` itself.
+    pub fn new(input: &'a str) -> Self {
+        let mut chars = input.char_indices().peekable();
+        chars.next_if(|&(_, c)| c == BOM);
+        Self {
+            source: input,
+            input: chars,
+            len: input.len(),
+            finished: false,
+        }
+    }
+
+    /// Returns the next token and its span. Once the input is exhausted every
+    /// call returns `Token::Eof` with an empty span at the end of the input.
+    pub fn next_token(&mut self) -> Result<(Token<'a>, Span), ParseError> {
+        while let Some(&(_, c)) = self.input.peek() {
+            if c.is_whitespace() {
+                self.input.next();
+            } else {
+                break;
+            }
+        }
+
+        let Some((start, c)) = self.input.next() else {
+            return Ok((Token::Eof, Span::new(self.len, self.len)));
+        };
+        let token = match c {
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '+' => Token::Plus,
+            '*' => Token::Star,
+            '%' => Token::Percent,
+            '#' => Token::Hash,
+            '=' => self.read_pair('=', Token::EqEq, Token::Equals),
+            '<' if self.source[start..].starts_with("<<<") => self.read_heredoc(start)?,
+            '<' => self.read_pair('=', Token::LessEq, Token::Less),
+            '`' if self.source[start..].starts_with("```") => self.read_fenced(start)?,
+            '>' => self.read_pair('=', Token::GreaterEq, Token::Greater),
+            '!' if self.next_is('=') => Token::NotEq,
+            '&' if self.next_is('&') => Token::AndAnd,
+            '|' if self.next_is('|') => Token::OrOr,
+            '$' => match self.input.next() {
+                Some((i, c)) if is_xid_start(c) || c == '_' => match self.read_identifier(i) {
+                    Token::Identifier(name) => Token::Variable(name),
+                    keyword => Token::Variable(keyword_text(&keyword)),
+                },
+                _ => {
+                    return Err(ParseError::UnexpectedChar {
+                        found: '$',
+                        span: Span::new(start, start + 1),
+                    })
+                }
+            },
+            '-' if self.next_is('>') => Token::Arrow,
+            '.' if self.next_is('.') => Token::DotDot,
+            '"' => self.read_string(start + 1)?,
+            '/' if self.next_is('/') => self.read_comment(),
+            '/' => Token::Slash,
+            c if is_xid_start(c) => self.read_identifier(start),
+            '-' if !matches!(self.input.peek(), Some((_, c)) if c.is_ascii_digit() || *c == '-') => {
+                Token::Minus
+            }
+            c if c.is_ascii_digit() || c == '-' => self.read_number(start)?,
+            c => {
+                return Err(ParseError::UnexpectedChar {
+                    found: c,
+                    span: Span::new(start, start + c.len_utf8()),
+                })
+            }
+        };
+        Ok((token, Span::new(start, self.offset())))
+    }
+
+    /// Consumes the next character if it is `expected`.
+    fn next_is(&mut self, expected: char) -> bool {
+        self.input.next_if(|&(_, c)| c == expected).is_some()
+    }
+
+    /// Returns `pair` if the next character is `second`, consuming it, and
+    /// `single` otherwise.
+    fn read_pair(&mut self, second: char, pair: Token<'a>, single: Token<'a>) -> Token<'a> {
+        if self.next_is(second) {
+            pair
+        } else {
+            single
+        }
+    }
+
+    /// Byte offset of the next unread character.
+    fn offset(&mut self) -> usize {
+        self.input.peek().map_or(self.len, |&(i, _)| i)
+    }
+
+    /// Reads a string literal whose text starts at `start`, just after the
+    /// opening quote. The text is borrowed unless an escape or normalizing it
+    /// to NFC forces a copy.
+    fn read_string(&mut self, start: usize) -> Result<Token<'a>, ParseError> {
+        let mut owned: Option<String> = None;
+        while let Some((i, c)) = self.input.next() {
+            let unescaped = match c {
+                '"' => {
+                    let text = match owned {
+                        Some(s) => Cow::Owned(s),
+                        None => Cow::Borrowed(&self.source[start..i]),
+                    };
+                    let normalized = match nfc(&text) {
+                        Cow::Owned(normalized) => Some(normalized),
+                        Cow::Borrowed(_) => None,
+                    };
+                    return Ok(Token::StringLiteral(normalized.map_or(text, Cow::Owned)));
+                }
+                '\\' => match self.input.next() {
+                    Some((_, '"')) => '"',
+                    Some((_, '\\')) => '\\',
+                    Some((_, 'n')) => '\n',
+                    Some((_, 'r')) => '\r',
+                    Some((_, 't')) => '\t',
+                    Some((_, other)) => return Err(ParseError::InvalidEscape(other)),
+                    None => break,
+                },
+                c => {
+                    if let Some(s) = &mut owned {
+                        s.push(c);
+                    }
+                    continue;
+                }
+            };
+            owned
+                .get_or_insert_with(|| self.source[start..i].to_string())
+                .push(unescaped);
+        }
+        Err(ParseError::UnterminatedString {
+            started_at: Span::new(start - 1, start),
+        })
+    }
+
+    /// Reads a fenced raw block starting at byte `start`, whose first
+    /// backtick has been consumed. The fence of three or more backticks may
+    /// be followed by a language name on its own line, and the block ends at
+    /// the next run of as many backticks. Like in Markdown, a body on the
+    /// lines between the fences leaves out the line breaks next to them.
+    fn read_fenced(&mut self, start: usize) -> Result<Token<'a>, ParseError> {
+        let source = self.source;
+        let width = source[start..].chars().take_while(|&c| c == '`').count();
+        for _ in 1..width {
+            self.input.next();
+        }
+        let mut body_start = start + width;
+        let mut lang = None;
+        let info = &source[body_start..];
+        let line_end = info.find('\n').unwrap_or(info.len());
+        let name = info[..line_end].trim_end();
+        let is_name =
+            name.chars().next().is_some_and(is_xid_start) && name.chars().all(is_xid_continue);
+        if name.is_empty() || is_name {
+            lang = is_name.then_some(name);
+            body_start += (line_end + 1).min(info.len());
+        }
+        let fence = &source[start..start + width];
+        let Some(body_len) = source[body_start..].find(fence) else {
+            return Err(ParseError::UnterminatedRaw {
+                started_at: Span::new(start, start + width),
+            });
+        };
+        let end = body_start + body_len + width;
+        while self.input.next_if(|&(i, _)| i < end).is_some() {}
+        let body = &source[body_start..body_start + body_len];
+        Ok(Token::Raw {
+            lang,
+            body: strip_closing_line(body),
+        })
+    }
+
+    /// Reads a heredoc starting at byte `start`, whose first `<` has been
+    /// consumed: `<<<`, a delimiter, a line break, then the body up to the
+    /// line whose first word is the delimiter. Lexing goes on right after
+    /// the closing delimiter.
+    fn read_heredoc(&mut self, start: usize) -> Result<Token<'a>, ParseError> {
+        let source = self.source;
+        let name_start = start + 3;
+        let name_len = source[name_start..]
+            .char_indices()
+            .find(|&(i, c)| {
+                !(if i == 0 {
+                    is_xid_start(c)
+                } else {
+                    is_xid_continue(c)
+                })
+            })
+            .map_or(source.len() - name_start, |(i, _)| i);
+        let started_at = Span::new(start, name_start + name_len);
+        let name = &source[name_start..started_at.end];
+        let rest = &source[started_at.end..];
+        let line_end = rest.find('\n');
+        if name.is_empty() || line_end.is_none_or(|i| !rest[..i].trim().is_empty()) {
+            return Err(ParseError::InvalidHeredoc { span: started_at });
+        }
+        let body_start = started_at.end + line_end.unwrap_or_default() + 1;
+        let mut line_start = body_start;
+        loop {
+            let line = &source[line_start..];
+            let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+            let closes = line[indent..]
+                .strip_prefix(name)
+                .is_some_and(|after| !after.starts_with(is_xid_continue));
+            if closes {
+                let end = line_start + indent + name.len();
+                while self.input.next_if(|&(i, _)| i < end).is_some() {}
+                let body = &source[body_start..line_start];
+                return Ok(Token::Raw {
+                    lang: None,
+                    body: strip_line_break(body),
+                });
+            }
+            match line.find('\n') {
+                Some(i) => line_start += i + 1,
+                None => return Err(ParseError::UnterminatedRaw { started_at }),
+            }
+        }
+    }
+
+    fn read_comment(&mut self) -> Token<'a> {
+        let start = self.offset();
+        while self.input.next_if(|&(_, c)| c != '\n').is_some() {}
+        let text = &self.source[start..self.offset()];
+        Token::Comment(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// Reads an identifier or keyword starting at byte `start`, whose first
+    /// character has already been consumed.
+    ///
+    /// Identifiers follow Unicode's default syntax: a character with the
+    /// `XID_Start` property, then any with `XID_Continue`, which takes in
+    /// digits, `_` and combining marks. The token keeps the text as written;
+    /// the parser normalizes it to NFC when it takes it as a name.
+    fn read_identifier(&mut self, start: usize) -> Token<'a> {
+        while self.input.next_if(|&(_, c)| is_xid_continue(c)).is_some() {}
+        match &self.source[start..self.offset()] {
+            "quest" => Token::QuestKeyword,
+            "let" => Token::LetKeyword,
+            "include" => Token::IncludeKeyword,
+            "true" => Token::True,
+            "false" => Token::False,
+            ident => Token::Identifier(ident),
+        }
+    }
+
+    /// Reads a number literal starting at byte `start`, whose first character
+    /// (a digit or `-`) has already been consumed: an integer (`-42`,
+    /// `10_000`, `0x1F`) or a decimal float (`0.25`). `_` may separate two
+    /// digits. A `.` is only part of the number when a digit follows it.
+    fn read_number(&mut self, start: usize) -> Result<Token<'a>, ParseError> {
+        let source = self.source;
+        let negative = source[start..].starts_with('-');
+        if negative && self.input.next_if(|&(_, c)| c.is_ascii_digit()).is_none() {
+            return Err(self.invalid_number(start));
+        }
+        let unsigned = &source[start + usize::from(negative)..];
+        if unsigned.starts_with("0x") || unsigned.starts_with("0X") {
+            self.input.next();
+            return self.read_hex(start);
+        }
+        self.skip_digits(10);
+        if !negative && is_datetime_start(&source[start..]) {
+            return self.read_datetime(start);
+        }
+
+        let mut lookahead = self.input.clone();
+        let is_float = matches!(lookahead.next(), Some((_, '.')))
+            && matches!(lookahead.next(), Some((_, c)) if c.is_ascii_digit());
+        if is_float {
+            self.input.next();
+            self.skip_digits(10);
+            let text = &source[start..self.offset()];
+            let Some(num) = remove_separators(text, 10).and_then(|t| t.parse::<f64>().ok()) else {
+                return Err(self.invalid_number(start));
+            };
+            if !num.is_finite() {
+                return Err(ParseError::NumberOverflow(text.to_string()));
+            }
+            return Ok(Token::Float(num));
+        }
+
+        if matches!(self.input.peek(), Some((_, 'd' | 'h' | 'm' | 's'))) {
+            return self.read_duration(start);
+        }
+        let text = &source[start..self.offset()];
+        let Some(digits) = remove_separators(text, 10) else {
+            return Err(self.invalid_number(start));
+        };
+        self.parse_integer(start, &digits, 10)
+    }
+
+    /// Reads the digits of a hexadecimal literal once its `0x` prefix has
+    /// been consumed.
+    fn read_hex(&mut self, start: usize) -> Result<Token<'a>, ParseError> {
+        let digits_start = self.offset();
+        self.skip_digits(16);
+        if matches!(self.input.peek(), Some((_, c)) if c.is_alphanumeric()) {
+            return Err(self.invalid_number(start));
+        }
+        let digits = &self.source[digits_start..self.offset()];
+        match remove_separators(digits, 16) {
+            Some(digits) if !digits.is_empty() => {
+                let sign = if self.source[start..].starts_with('-') {
+                    "-"
+                } else {
+                    ""
+                };
+                self.parse_integer(start, &format!("{sign}{digits}"), 16)
+            }
+            _ => Err(self.invalid_number(start)),
+        }
+    }
+
+    /// Parses the digits of an integer literal starting at byte `start`,
+    /// with separators already removed.
+    fn parse_integer(
+        &mut self,
+        start: usize,
+        digits: &str,
+        radix: u32,
+    ) -> Result<Token<'a>, ParseError> {
+        match i64::from_str_radix(digits, radix) {
+            Ok(num) => Ok(Token::Number(num)),
+            Err(e) => match e.kind() {
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => Err(
+                    ParseError::NumberOverflow(self.source[start..self.offset()].to_string()),
+                ),
+                _ => Err(self.invalid_number(start)),
+            },
+        }
+    }
+
+    /// Consumes the rest of a malformed number literal starting at byte
+    /// `start` and reports all of it.
+    fn invalid_number(&mut self, start: usize) -> ParseError {
+        while self
+            .input
+            .next_if(|&(_, c)| c.is_alphanumeric() || c == '_' || c == '-')
+            .is_some()
+        {}
+        ParseError::InvalidNumber {
+            text: self.source[start..self.offset()].to_string(),
+            span: Span::new(start, self.offset()),
+        }
+    }
+
+    /// Reads a duration literal starting at byte `start`, once its first run
+    /// of digits has been consumed and a unit follows.
+    fn read_duration(&mut self, start: usize) -> Result<Token<'a>, ParseError> {
+        while self
+            .input
+            .next_if(|&(_, c)| c.is_alphanumeric() || c == '_')
+            .is_some()
+        {}
+        let text = &self.source[start..self.offset()];
+        let span = Span::new(start, self.offset());
+        if text.starts_with('-') {
+            return Err(ParseError::NegativeDuration { span });
+        }
+        parse_duration(text)
+            .map(Token::Duration)
+            .ok_or_else(|| ParseError::InvalidDuration {
+                text: text.to_string(),
+                span,
+            })
+    }
+
+    /// Reads a date and time literal starting at byte `start`, once the
+    /// digits of its year have been consumed.
+    fn read_datetime(&mut self, start: usize) -> Result<Token<'a>, ParseError> {
+        while self
+            .input
+            .next_if(|&(_, c)| c.is_ascii_alphanumeric() || matches!(c, ':' | '+' | '-' | '.'))
+            .is_some()
+        {}
+        let text = &self.source[start..self.offset()];
+        DateTime::parse(text)
+            .map(Token::DateTime)
+            .ok_or_else(|| ParseError::InvalidDateTime {
+                text: text.to_string(),
+                span: Span::new(start, self.offset()),
+            })
+    }
+
+    /// Skips the digits in `radix` and `_` separators that follow.
+    fn skip_digits(&mut self, radix: u32) {
+        while self
+            .input
+            .next_if(|&(_, c)| c.is_digit(radix) || c == '_')
+            .is_some()
+        {}
+    }
+}
+
+/// Drops the last line of a fenced body if it holds nothing but the
+/// indentation of the closing fence, with the line break before it.
+fn strip_closing_line(body: &str) -> &str {
+    match body.rfind('\n') {
+        Some(i) if body[i + 1..].trim_matches([' ', '\t']).is_empty() => {
+            strip_line_break(&body[..=i])
+        }
+        _ => body,
+    }
+}
+
+/// Drops one `\n` or `\r\n` from the end of `text`.
+fn strip_line_break(text: &str) -> &str {
+    match text.strip_suffix('\n') {
+        Some(text) => text.strip_suffix('\r').unwrap_or(text),
+        None => text,
+    }
+}
+
+/// Removes the `_` separators from a number literal, or returns `None` if
+/// one of them is not between two digits in `radix`.
+fn remove_separators(text: &str, radix: u32) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut digits = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c != '_' {
+            digits.push(c);
+            continue;
+        }
+        let before = i.checked_sub(1).map(|j| chars[j]);
+        let after = chars.get(i + 1).copied();
+        if !(before.is_some_and(|c| c.is_digit(radix)) && after.is_some_and(|c| c.is_digit(radix)))
+        {
+            return None;
+        }
+    }
+    Some(digits)
+}
+
+/// Whether `text` starts like a date and time literal, `YYYY-MM-DDT`. A
+/// date alone is left to be read as a subtraction.
+fn is_datetime_start(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() > 10
+        && [0, 1, 2, 3, 5, 6, 8, 9]
+            .iter()
+            .all(|&i| bytes[i].is_ascii_digit())
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && bytes[10] == b'T'
+}
+
+/// Parses the text of a duration literal: whole numbers followed by the
+/// units `d`, `h`, `m` or `s`, each used at most once and from largest to
+/// smallest, as in `1d12h` or `90s`.
+pub(crate) fn parse_duration(text: &str) -> Option<Duration> {
+    let mut rest = text;
+    let mut total: u64 = 0;
+    let mut previous_unit = u64::MAX;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(digits);
+        let mut chars = tail.chars();
+        let unit = match chars.next()? {
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        if number.is_empty() || unit >= previous_unit {
+            return None;
+        }
+        let seconds = number.parse::<u64>().ok()?.checked_mul(unit)?;
+        total = total.checked_add(seconds)?;
+        previous_unit = unit;
+        rest = chars.as_str();
+    }
+    Some(Duration::from_secs(total))
+}
+
+fn keyword_text(token: &Token<'_>) -> &'static str {
+    match token {
+        Token::QuestKeyword => "quest",
+        Token::LetKeyword => "let",
+        Token::IncludeKeyword => "include",
+        Token::True => "true",
+        Token::False => "false",
+        _ => "",
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<(Token<'a>, Span), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        match self.next_token() {
+            Ok((Token::Eof, _)) => {
+                self.finished = true;
+                None
+            }
+            Ok(item) => Some(Ok(item)),
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
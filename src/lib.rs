@@ -1,149 +1,1101 @@
+// The crate is named after the package, which is not snake case.
+#![allow(non_snake_case)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
 use thiserror::Error;
 
+use diagnostic::closest_match;
+use prelude::*;
+use visitor::{visit_property, visit_quest, Mark};
+
+mod action;
+pub mod ast;
+#[cfg(feature = "std")]
+mod balance;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "compile")]
+mod compiled;
+mod condition;
+#[cfg(feature = "std")]
+mod config;
+#[cfg(feature = "convert")]
+mod convert;
+mod currency;
+mod datetime;
+#[cfg(feature = "serde")]
+mod de;
+mod diagnostic;
+#[cfg(feature = "std")]
+mod diff;
+mod encoding;
+mod error_codes;
+mod expr;
+#[cfg(feature = "std")]
+mod files;
+mod fragment;
+pub mod grammar;
+#[cfg(feature = "std")]
+mod graph;
+mod highlight;
+mod include;
+mod interpolate;
+mod lexer;
+#[cfg(feature = "std")]
+mod lint;
+mod locale;
+mod merge;
+mod nfc;
+mod outcome;
+mod package;
+mod partial;
+mod prelude;
+mod properties;
+#[cfg(feature = "std")]
+mod query;
+#[cfg(feature = "std")]
+mod refactor;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+mod resolve;
+mod schema;
+#[cfg(feature = "std")]
+pub mod sim;
+mod source_map;
+mod stages;
+#[cfg(feature = "std")]
+mod stats;
+mod step;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+mod summary;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod version;
+mod visitor;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod world;
+mod writer;
+
+pub use action::Action;
+#[cfg(feature = "std")]
+pub use balance::{
+    balance, BalanceConfig, BalanceConfigError, BalanceEntry, BalanceReport, BalanceSort,
+    RewardCurve, TagCurve,
+};
+#[cfg(feature = "std")]
+pub use builder::{ParseCache, ParserBuilder};
+#[cfg(feature = "compile")]
+pub use compiled::{compile, load_compiled, LoadError};
+pub use condition::{Condition, ProgressState};
+#[cfg(feature = "std")]
+pub use config::{ConfigError, ProjectConfig, PROJECT_CONFIG_FILE};
+#[cfg(feature = "convert")]
+pub use convert::{
+    convert, from_json, from_json_many, from_toml, from_yaml, to_json, to_toml, to_yaml,
+    ConvertError, Format,
+};
+pub use currency::{Currency, RewardTier};
+pub use datetime::DateTime;
+#[cfg(feature = "serde")]
+pub use de::{from_str, DeserializeError};
+pub use diagnostic::{
+    fix_deprecated, line_col, render_error, render_warning, suggest_property, Diagnostic,
+    Diagnostics, ParseOutput,
+};
+#[cfg(feature = "std")]
+pub use diff::{quest_diff, QuestChange};
+pub use encoding::decode_source;
+pub use error_codes::{error_info, ErrorInfo, ERROR_CODES};
+pub use expr::{BinaryOp, Context, EvalError, Expr};
+#[cfg(feature = "std")]
+pub use files::parse_file;
+#[cfg(feature = "parallel")]
+pub use files::{parse_files_parallel, parse_files_parallel_with_options};
+pub use fragment::{parse_expr, parse_property, parse_value};
+#[cfg(feature = "std")]
+pub use graph::{GraphError, QuestGraph};
+pub use highlight::{highlight, TokenClass};
+#[cfg(feature = "std")]
+pub use include::FsIncludeResolver;
+pub use include::{IncludeResolver, IncludeSource, NoIncludeResolver};
+/// The map of [`Quest::extra`], [`Quest::expressions`] and other properties
+/// the parser keeps in the order they were written.
+#[cfg(feature = "std")]
+pub use indexmap::IndexMap;
+/// The map of [`Quest::extra`], [`Quest::expressions`] and other properties
+/// the parser keeps in the order they were written.
+#[cfg(not(feature = "std"))]
+pub type IndexMap<K, V> = indexmap::IndexMap<K, V, hashbrown::DefaultHashBuilder>;
+pub use lexer::{Lexer, Span, Token};
+#[cfg(feature = "std")]
+pub use lint::{lint, Lint, LintConfig, LintConfigError, LintRule, Severity, LINT_RULES};
+pub use locale::{LangCode, LocalizedString, Text};
+pub use merge::{merge, AppliedOverride, MergeError, QuestOverride};
+pub use nfc::nfc;
+pub use outcome::Outcome;
+pub use partial::{CompletionContext, PartialResult, PropertyScope, ValueKind};
+pub use properties::{property_info, PropertyInfo, KNOWN_PROPERTIES};
+#[cfg(feature = "std")]
+pub use query::{QuestQuery, QuestSet};
+#[cfg(feature = "std")]
+pub use refactor::{rename_quest, renumber_steps, Rewrite};
+#[cfg(feature = "std")]
+pub use registry::{QuestRegistry, RegistryError};
+#[cfg(feature = "std")]
+pub use resolve::{resolve, ReferenceKind, ResolutionError, ResolvedWorld};
+pub use schema::{DynamicQuest, PropertySpec, QuestSchema, ValueType};
+pub use source_map::SourceMap;
+pub use stages::{Stage, StageError, StateMachine, Transition};
+#[cfg(feature = "std")]
+pub use stats::QuestStats;
+pub use step::{Step, StepTree};
+#[cfg(feature = "std")]
+pub use stream::QuestStream;
+pub use version::GrammarVersion;
+pub use visitor::{PropertyValue, QuestVisitor};
+pub use world::{Item, Npc, NpcRef, SymbolTable, World};
+pub use writer::{format_source, to_quest_string, to_quests_string};
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ParseError {
-    #[error("Unexpected character: {0}")]
-    UnexpectedChar(char),
+    #[error("Unexpected character: {found}")]
+    UnexpectedChar { found: char, span: Span },
     #[error("Unexpected end of file")]
     UnexpectedEOF,
+    #[error("Unterminated string; the closing `\"` is missing")]
+    UnterminatedString { started_at: Span },
+    #[error("Unterminated raw block; the input ends before its closing delimiter")]
+    UnterminatedRaw { started_at: Span },
+    #[error("A heredoc needs a delimiter such as `<<<END` and a line break after it")]
+    InvalidHeredoc { span: Span },
+    #[error("Unclosed `{{`; the input ends before its `}}`")]
+    UnclosedBrace { opened_at: Span },
     #[error("Expected {expected}, found {found}")]
-    SyntaxError { expected: String, found: String },
-    #[error("Invalid number format")]
-    InvalidNumber,
+    SyntaxError {
+        expected: String,
+        found: String,
+        span: Span,
+    },
+    #[error("Invalid number `{text}`")]
+    InvalidNumber { text: String, span: Span },
+    #[error("Invalid duration {text:?}; expected units d, h, m and s from largest to smallest")]
+    InvalidDuration { text: String, span: Span },
+    #[error("Invalid date and time {text:?}; expected a literal such as `2025-10-31T00:00:00Z`, or with an offset such as `+02:00` instead of `Z`")]
+    InvalidDateTime { text: String, span: Span },
+    #[error("Quest ends at {ends_at}, which is not after it starts at {starts_at}")]
+    InvalidEventWindow {
+        starts_at: DateTime,
+        ends_at: DateTime,
+        span: Span,
+    },
+    #[error("`complete_when` takes objective names joined by `&&` and `||`, such as `kill_goblins && (find_key || bribe_guard)`")]
+    InvalidCondition { span: Span },
+    #[error("A step cannot be repeated {count} times")]
+    InvalidRepeatCount { count: i64, span: Span },
+    #[error("A `steps {{ ... }}` block must hold every step of its quest")]
+    MixedSteps { span: Span },
+    #[error("Durations cannot be negative")]
+    NegativeDuration { span: Span },
+    #[error("Range {start}..{end} is empty; the lower bound must not exceed the upper")]
+    InvalidRange { start: i64, end: i64, span: Span },
+    #[error("Number out of range: {0}")]
+    NumberOverflow(String),
+    #[error("I/O error: {0}")]
+    Io(String),
+    /// Bytes that are not text in `encoding`, from `offset` on in the raw
+    /// input; see [`decode_source`].
+    #[error("Input is not valid {encoding} at byte {offset}")]
+    InvalidEncoding {
+        encoding: &'static str,
+        offset: usize,
+    },
+    #[error("Invalid escape sequence: \\{0}")]
+    InvalidEscape(char),
+    #[error("Missing required field `{field}` in {block} block")]
+    MissingField { block: String, field: String },
+    #[error("Unknown objective kind: {0}")]
+    UnknownObjectiveKind(String),
+    #[error("Undefined variable: ${name}")]
+    UndefinedVariable { name: String, span: Span },
+    /// A key that strict mode or a schema does not allow. `suggestion` is
+    /// the allowed key closest to it, if it looks like a typo of one.
+    #[error("Unknown property `{key}`")]
+    UnknownProperty {
+        key: String,
+        suggestion: Option<String>,
+        span: Span,
+    },
+    #[error("`{key}` is written as a block, `{key} {{ ... }}`")]
+    BlockExpected { key: String, span: Span },
+    #[error("`{key}` takes a value, `{key}: ...`, not a block")]
+    ValueExpected { key: String, span: Span },
+    #[error("Unknown step attribute `{name}`; expected `optional`, `hidden` or `order`")]
+    UnknownAttribute { name: String, span: Span },
+    #[error("Property `{key}` can only be set once")]
+    DuplicateProperty { key: String, span: Span },
+    #[error("Reward tier {name:?} is defined more than once")]
+    DuplicateTier { name: String, span: Span },
+    #[error("Reward tier {name:?} has no rewards; give it at least one amount")]
+    EmptyTier { name: String, span: Span },
+    #[error("The {kind} {name:?} is defined more than once")]
+    DuplicateDefinition { kind: String, name: String },
+    #[error("Quest {quest:?} references undefined {kind} {name:?}")]
+    UndefinedReference {
+        quest: String,
+        kind: String,
+        name: String,
+    },
+    #[error("Unknown template `{name}`")]
+    UnknownTemplate { name: String, span: Span },
+    #[error("Missing argument `{name}` for template `{template}`")]
+    MissingArgument {
+        template: String,
+        name: String,
+        span: Span,
+    },
+    #[error("Unexpected argument `{name}` for template `{template}`")]
+    UnexpectedArgument {
+        template: String,
+        name: String,
+        span: Span,
+    },
+    #[error("Variable ${0} is already defined")]
+    DuplicateVariable(String),
+    #[error("Invalid package name {name:?}; expected identifiers joined by dots, such as `act1.village`")]
+    InvalidPackage { name: String, span: Span },
+    #[error("Cannot include {path:?}: {message}")]
+    IncludeFailed { path: String, message: String },
+    #[error("Cannot format {0} without losing part of the file")]
+    Unformattable(String),
+    #[error("Invalid expression: {error}")]
+    InvalidExpression { error: EvalError, span: Span },
+    #[error("Invalid stages block: {error}")]
+    InvalidStages { error: StageError, span: Span },
+    #[error("Values are nested more than {limit} levels deep")]
+    NestingTooDeep { limit: usize, span: Span },
+    #[error("Input is {size} bytes long; the limit is {limit}")]
+    InputTooLarge { size: usize, limit: usize },
+    #[error("Quest has more than {limit} steps")]
+    TooManySteps { limit: usize, span: Span },
+    #[error("String is longer than {limit} bytes")]
+    StringTooLong { limit: usize, span: Span },
+    #[error("A step cannot be repeated more than {limit} times")]
+    TooManyRepeats { limit: usize, span: Span },
+    #[error(
+        "Unsupported grammar version {version}; the latest is {}",
+        GrammarVersion::LATEST
+    )]
+    UnsupportedVersion { version: i64, span: Span },
+    #[error("A `#version` pragma must come before everything else in the file")]
+    MisplacedVersion { span: Span },
+    #[error("{feature} requires grammar version {version}; add `#version {version}` at the top of the file")]
+    RequiresVersion {
+        feature: String,
+        version: GrammarVersion,
+        span: Span,
+    },
+    #[error("Include cycle: {}", .0.join(" -> "))]
+    IncludeCycle(Vec<String>),
+    /// An error inside an included file. `chain` lists the include path from
+    /// the root input down to the file containing the error.
+    #[error("In {}: {error}", .chain.join(" -> "))]
+    InInclude {
+        chain: Vec<String>,
+        error: Box<ParseError>,
+    },
+}
+
+impl ParseError {
+    /// Location of the error in the input it was raised for, when known.
+    ///
+    /// For [`ParseError::InInclude`] the span refers to the innermost
+    /// included file.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ParseError::UnexpectedChar { span, .. }
+            | ParseError::SyntaxError { span, .. }
+            | ParseError::UndefinedVariable { span, .. }
+            | ParseError::UnknownProperty { span, .. }
+            | ParseError::BlockExpected { span, .. }
+            | ParseError::ValueExpected { span, .. }
+            | ParseError::UnknownAttribute { span, .. }
+            | ParseError::DuplicateProperty { span, .. }
+            | ParseError::DuplicateTier { span, .. }
+            | ParseError::EmptyTier { span, .. }
+            | ParseError::InvalidExpression { span, .. }
+            | ParseError::InvalidStages { span, .. }
+            | ParseError::NestingTooDeep { span, .. }
+            | ParseError::TooManySteps { span, .. }
+            | ParseError::TooManyRepeats { span, .. }
+            | ParseError::StringTooLong { span, .. }
+            | ParseError::InvalidNumber { span, .. }
+            | ParseError::InvalidDuration { span, .. }
+            | ParseError::InvalidDateTime { span, .. }
+            | ParseError::InvalidEventWindow { span, .. }
+            | ParseError::InvalidCondition { span }
+            | ParseError::InvalidRepeatCount { span, .. }
+            | ParseError::MixedSteps { span }
+            | ParseError::UnterminatedString { started_at: span }
+            | ParseError::UnterminatedRaw { started_at: span }
+            | ParseError::InvalidHeredoc { span }
+            | ParseError::UnclosedBrace { opened_at: span }
+            | ParseError::NegativeDuration { span }
+            | ParseError::InvalidRange { span, .. }
+            | ParseError::UnknownTemplate { span, .. }
+            | ParseError::MissingArgument { span, .. }
+            | ParseError::UnexpectedArgument { span, .. }
+            | ParseError::InvalidPackage { span, .. }
+            | ParseError::UnsupportedVersion { span, .. }
+            | ParseError::MisplacedVersion { span }
+            | ParseError::RequiresVersion { span, .. } => Some(*span),
+            ParseError::InInclude { error, .. } => error.span(),
+            _ => None,
+        }
+    }
+
+    /// The span of the error in the input that was parsed, to be moved when
+    /// that input is part of a larger one. Errors inside an included file
+    /// have none, as their spans refer to that file.
+    #[cfg(feature = "std")]
+    pub(crate) fn span_mut(&mut self) -> Option<&mut Span> {
+        match self {
+            ParseError::UnexpectedChar { span, .. }
+            | ParseError::SyntaxError { span, .. }
+            | ParseError::UndefinedVariable { span, .. }
+            | ParseError::UnknownProperty { span, .. }
+            | ParseError::BlockExpected { span, .. }
+            | ParseError::ValueExpected { span, .. }
+            | ParseError::UnknownAttribute { span, .. }
+            | ParseError::DuplicateProperty { span, .. }
+            | ParseError::DuplicateTier { span, .. }
+            | ParseError::EmptyTier { span, .. }
+            | ParseError::InvalidExpression { span, .. }
+            | ParseError::InvalidStages { span, .. }
+            | ParseError::NestingTooDeep { span, .. }
+            | ParseError::TooManySteps { span, .. }
+            | ParseError::TooManyRepeats { span, .. }
+            | ParseError::StringTooLong { span, .. }
+            | ParseError::InvalidNumber { span, .. }
+            | ParseError::InvalidDuration { span, .. }
+            | ParseError::InvalidDateTime { span, .. }
+            | ParseError::InvalidEventWindow { span, .. }
+            | ParseError::InvalidCondition { span }
+            | ParseError::InvalidRepeatCount { span, .. }
+            | ParseError::MixedSteps { span }
+            | ParseError::UnterminatedString { started_at: span }
+            | ParseError::UnterminatedRaw { started_at: span }
+            | ParseError::InvalidHeredoc { span }
+            | ParseError::UnclosedBrace { opened_at: span }
+            | ParseError::NegativeDuration { span }
+            | ParseError::InvalidRange { span, .. }
+            | ParseError::UnknownTemplate { span, .. }
+            | ParseError::MissingArgument { span, .. }
+            | ParseError::UnexpectedArgument { span, .. }
+            | ParseError::InvalidPackage { span, .. }
+            | ParseError::UnsupportedVersion { span, .. }
+            | ParseError::MisplacedVersion { span }
+            | ParseError::RequiresVersion { span, .. } => Some(span),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum Value {
     String(String),
-    Number(i32),
+    Number(i64),
+    Float(f64),
     Bool(bool),
+    List(Vec<Value>),
+    /// A verbatim block such as an embedded Lua script, written
+    /// ```` ```lua ... ``` ```` or as a `<<<END` heredoc, kept uninterpreted.
+    /// Listed before [`Value::Localized`] so that serde, which tries the
+    /// variants in order, does not read it back as a localized string.
+    Raw {
+        #[cfg_attr(
+            feature = "serde",
+            serde(default, skip_serializing_if = "Option::is_none")
+        )]
+        lang: Option<String>,
+        body: String,
+    },
+    Localized(LocalizedString),
+    Duration(Duration),
+    /// An inclusive range of integers written `10..20`; the first bound is
+    /// never greater than the second.
+    Range(i64, i64),
+    /// A date and time such as `2025-10-31T00:00:00Z`.
+    DateTime(DateTime),
+}
+
+/// A structured quest objective declared with an `objective { ... }` block.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum Objective {
+    Kill {
+        target: String,
+        count: i64,
+    },
+    Collect {
+        item: String,
+        count: i64,
+    },
+    TalkTo {
+        npc: String,
+    },
+    Reach {
+        location: String,
+    },
+    /// Any kind the parser does not model; the raw `kind` string is kept.
+    Custom {
+        kind: String,
+        target: Option<String>,
+        count: Option<i64>,
+    },
+}
+
+impl Objective {
+    /// Builds the objective an `objective { kind, target, count }` block
+    /// describes.
+    pub(crate) fn from_fields(
+        kind: String,
+        target: Option<String>,
+        count: Option<i64>,
+    ) -> Result<Objective, ParseError> {
+        let objective = match kind.as_str() {
+            "kill" => Objective::Kill {
+                target: target.ok_or_else(|| missing_field("objective", "target"))?,
+                count: count.unwrap_or(1),
+            },
+            "collect" => Objective::Collect {
+                item: target.ok_or_else(|| missing_field("objective", "target"))?,
+                count: count.unwrap_or(1),
+            },
+            "talk_to" => Objective::TalkTo {
+                npc: target.ok_or_else(|| missing_field("objective", "target"))?,
+            },
+            "reach" => Objective::Reach {
+                location: target.ok_or_else(|| missing_field("objective", "target"))?,
+            },
+            "" => return Err(ParseError::UnknownObjectiveKind(kind)),
+            _ => Objective::Custom {
+                kind,
+                target,
+                count,
+            },
+        };
+        Ok(objective)
+    }
+
+    /// The `kind`, `target` and `count` fields of the objective's block.
+    pub(crate) fn fields(&self) -> (&str, Option<&String>, Option<&i64>) {
+        match self {
+            Objective::Kill { target, count } => ("kill", Some(target), Some(count)),
+            Objective::Collect { item, count } => ("collect", Some(item), Some(count)),
+            Objective::TalkTo { npc } => ("talk_to", Some(npc), None),
+            Objective::Reach { location } => ("reach", Some(location), None),
+            Objective::Custom {
+                kind,
+                target,
+                count,
+            } => (kind.as_str(), target.as_ref(), count.as_ref()),
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Quest {
     pub name: String,
-    pub steps: Vec<String>,
-    pub reward: i32,
+    /// Stable identifier from `id: "main_01"`, for references that must
+    /// survive the quest being renamed. See [`QuestRegistry`].
+    pub id: Option<String>,
+    /// Package from the `package "act1.village"` declaration in front of the
+    /// quest, if any. See [`Quest::qualified_id`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub package: Option<String>,
+    /// Steps in declaration order. See [`Quest::step_texts`] for just their
+    /// text.
+    pub steps: Vec<Step>,
+    /// How the steps depend on each other, when they are declared in a
+    /// `steps { ... }` block; [`Quest::steps`] then lists the same steps in
+    /// declaration order.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub step_tree: Option<StepTree>,
+    pub objectives: Vec<Objective>,
+    /// When the quest counts as completed, from
+    /// `complete_when: kill_goblins && (find_key || bribe_guard)`.
+    pub complete_when: Option<Condition>,
+    pub requires: Vec<String>,
+    /// Labels from `tags: ["main", "act1"]` for grouping quests, in source
+    /// order. See [`QuestSet::by_tag`].
+    pub tags: Vec<String>,
+    /// Who hands out the quest, from `giver: npc("Elder") at "Village Square"`.
+    pub giver: Option<NpcRef>,
+    /// Who the player returns to, from `turn_in: npc("Elder")`. See
+    /// [`Quest::turn_in_npc`].
+    pub turn_in: Option<NpcRef>,
+    pub reward: i64,
+    /// Amounts in specific currencies from `reward: 100 gold, 5 gems` or a
+    /// `reward { gold: 100, gems: 5 }` block, in source order. Needs
+    /// `#version 2`.
+    pub rewards: Vec<(Currency, i64)>,
+    /// Tiers from `reward tier "gold" { ... }` blocks, in source order, each
+    /// with a distinct name. Needs `#version 2`.
+    pub reward_tiers: Vec<RewardTier>,
     pub active: bool,
+    /// Stage flow from a `stages { ... }` block, if the quest declares one.
+    pub stages: Option<StateMachine>,
+    /// How long the player has to complete the quest, from `time_limit: 2h30m`.
+    pub time_limit: Option<Duration>,
+    /// Lowest and highest player level the quest is meant for, both
+    /// inclusive, from `level: 10..20`. A single `level: 10` gives `(10, 10)`.
+    pub level_range: Option<(i64, i64)>,
+    /// When a limited-time event quest becomes available, from
+    /// `starts_at: 2025-10-31T00:00:00Z`. See [`Quest::is_running_at`].
+    pub starts_at: Option<DateTime>,
+    /// When a limited-time event quest stops being available, from
+    /// `ends_at: 2025-11-07T00:00:00Z`; always after `starts_at` when both
+    /// are given.
+    pub ends_at: Option<DateTime>,
+    /// Outcome of completing the quest, from an `on_success { ... }` block.
+    pub on_success: Option<Outcome>,
+    /// Outcome of failing the quest, from an `on_failure { ... }` block.
+    pub on_failure: Option<Outcome>,
+    /// Actions run when the quest starts, from an `on_start { ... }` block,
+    /// in source order.
+    pub on_start: Vec<Action>,
+    /// Actions run when the quest is completed, from an `on_complete { ... }`
+    /// block, in source order.
+    pub on_complete: Vec<Action>,
+    /// Properties the parser does not model, keyed by property name in the
+    /// order they were first written.
+    pub extra: IndexMap<String, Value>,
+    /// Properties whose value depends on game state, kept unevaluated and
+    /// keyed by property name; see [`Expr::evaluate`]. A `reward` or `active`
+    /// given as such an expression leaves the field at its default.
+    pub expressions: IndexMap<String, Expr>,
+    /// Where the quest's fields were written; see [`Quest::source_map`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub source_map: SourceMap,
 }
 
-#[derive(Debug, PartialEq, Clone)]
-enum Token {
-    QuestKeyword,
-    Identifier(String),
-    StringLiteral(String),
-    Number(i32),
-    LBrace,
-    RBrace,
-    Colon,
-    Comma,
-    True,
-    False,
-    Eof,
-}
-
-struct Lexer<'a> {
-    input: std::iter::Peekable<std::str::Chars<'a>>,
-}
-
-impl<'a> Lexer<'a> {
-    fn new(input: &'a str) -> Self {
+/// Settings that change how a [`Parser`] treats its input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserOptions {
+    /// Keep unknown properties in [`Quest::extra`] instead of dropping them.
+    pub collect_extra: bool,
+    /// Reject unknown properties with [`ParseError::UnknownProperty`].
+    pub strict: bool,
+    /// Let [`Parser::parse_world`] accept objectives, givers and turn-ins
+    /// naming NPCs and items that are not defined in the parsed files.
+    pub allow_external_refs: bool,
+    /// Bounds on the size of the input and what it may contain.
+    pub limits: ParserLimits,
+    /// Log every grammar rule the parser enters and leaves, with the token
+    /// and position it is at, through the [`log`] crate at trace level.
+    pub trace: bool,
+    /// Enabled feature flags. A quest or property guarded by
+    /// `#[if(feature = "name")]` is kept only if `name` is in the set, and
+    /// dropped otherwise.
+    pub flags: HashSet<String>,
+    /// Grammar version of input without a `#version` pragma.
+    pub version: GrammarVersion,
+    /// Directories the default [`FsIncludeResolver`] searches, in order, for
+    /// includes that are not found relative to the including file.
+    #[cfg(feature = "std")]
+    pub include_paths: Vec<PathBuf>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
         Self {
-            input: input.chars().peekable(),
+            collect_extra: true,
+            strict: false,
+            allow_external_refs: false,
+            limits: ParserLimits::default(),
+            trace: false,
+            flags: HashSet::new(),
+            version: GrammarVersion::default(),
+            #[cfg(feature = "std")]
+            include_paths: Vec::new(),
         }
     }
+}
 
-    fn next_token(&mut self) -> Result<Token, ParseError> {
-        while let Some(&c) = self.input.peek() {
-            if c.is_whitespace() {
-                self.input.next();
-            } else {
-                break;
-            }
+/// Resource limits enforced while parsing, so input from untrusted sources
+/// cannot make the parser use unbounded memory or stack. Each limit fails
+/// with its own [`ParseError`] once exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserLimits {
+    /// Size of the input in bytes, checked before lexing with
+    /// [`ParseError::InputTooLarge`]. Included files are checked separately.
+    pub max_input_bytes: usize,
+    /// Number of steps a single quest may have, see
+    /// [`ParseError::TooManySteps`].
+    pub max_steps: usize,
+    /// How deeply lists, parentheses and operators may nest inside a single
+    /// value before parsing fails with [`ParseError::NestingTooDeep`].
+    pub max_nesting: usize,
+    /// Length in bytes of a string literal after unescaping, see
+    /// [`ParseError::StringTooLong`].
+    pub max_string_len: usize,
+    /// Count of a single `repeat(n) step`, checked before the step is copied,
+    /// see [`ParseError::TooManyRepeats`].
+    pub max_repeat: usize,
+}
+
+impl ParserLimits {
+    /// Nesting limit of [`ParserLimits::default`].
+    pub const DEFAULT_MAX_NESTING: usize = 256;
+
+    /// Repeat limit of [`ParserLimits::default`]. A few bytes of `repeat`
+    /// would otherwise ask for any number of steps.
+    pub const DEFAULT_MAX_REPEAT: usize = 10_000;
+
+    /// Limits used by [`Parser::parse_untrusted`]: 1 MiB of input, 1000 steps
+    /// per quest, 64 levels of nesting, 64 KiB per string and 1000 copies of a
+    /// repeated step.
+    pub const UNTRUSTED: Self = Self {
+        max_input_bytes: 1 << 20,
+        max_steps: 1000,
+        max_nesting: 64,
+        max_string_len: 64 << 10,
+        max_repeat: 1000,
+    };
+}
+
+impl Default for ParserLimits {
+    fn default() -> Self {
+        Self {
+            max_input_bytes: usize::MAX,
+            max_steps: usize::MAX,
+            max_nesting: Self::DEFAULT_MAX_NESTING,
+            max_string_len: usize::MAX,
+            max_repeat: Self::DEFAULT_MAX_REPEAT,
         }
+    }
+}
+
+/// Properties only written as a block, `key { ... }`. `reward` takes either
+/// form.
+const BLOCK_PROPERTIES: &[&str] = &[
+    "objective",
+    "stages",
+    "on_success",
+    "on_failure",
+    "on_start",
+    "on_complete",
+];
+
+/// The fields of an `objective` block.
+const OBJECTIVE_FIELDS: &[&str] = &["kind", "target", "count"];
+
+/// Fields that every property writing them adds to, instead of replacing
+/// what an earlier one set. `reward tier` adds to `reward` as well.
+const GATHERED_FIELDS: &[&str] = &[
+    "step",
+    "requires",
+    "tags",
+    "objective",
+    "on_start",
+    "on_complete",
+];
+
+/// A `template` definition: its parameters and the source of its body, from
+/// `start` up to the closing brace at `end`, which is lexed again wherever the
+/// template is instantiated. The body is a `&str` of the input, or a `String`
+/// for the templates a [`QuestStream`] keeps from earlier chunks.
+#[derive(Clone)]
+struct Template<B> {
+    params: Vec<String>,
+    body: B,
+    /// Offset of the body in the input it was written in.
+    start: usize,
+    end: Span,
+}
+
+pub struct Parser<'a> {
+    input: &'a str,
+    lexer: Lexer<'a>,
+    current_token: Token<'a>,
+    current_span: Span,
+    /// End of the previously consumed token, used to span multi-token values.
+    prev_end: usize,
+    options: ParserOptions,
+    variables: HashMap<String, Value>,
+    resolver: Arc<dyn IncludeResolver>,
+    /// Names of the files currently being parsed, outermost first.
+    include_chain: Vec<String>,
+    source_name: Option<String>,
+    /// Package of the quests that follow, from the last `package`
+    /// declaration.
+    package: Option<String>,
+    included: VecDeque<Quest>,
+    symbols: SymbolTable,
+    overrides: Vec<QuestOverride>,
+    /// Nesting level of the value being parsed, see [`ParserLimits::max_nesting`].
+    depth: usize,
+    templates: HashMap<String, Template<&'a str>>,
+    /// Tokens after the current one, read ahead by [`Parser::peek_n`] or
+    /// queued to expand a template, consumed before the lexer is resumed.
+    buffer: VecDeque<(Token<'a>, Span)>,
+    version: GrammarVersion,
+    /// Number of grammar rules being traced, see [`ParserOptions::trace`].
+    trace_depth: usize,
+    diagnostics: Diagnostics,
+    /// Whether an `include` directive of the input was resolved, see
+    /// [`ParseCache`].
+    resolved_includes: bool,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Result<Self, ParseError> {
+        Self::with_options(input, ParserOptions::default())
+    }
 
-        match self.input.next() {
-            None => Ok(Token::Eof),
-            Some('{') => Ok(Token::LBrace),
-            Some('}') => Ok(Token::RBrace),
-            Some(':') => Ok(Token::Colon),
-            Some(',') => Ok(Token::Comma),
-            Some('"') => self.read_string(),
-            Some(c) if c.is_alphabetic() => self.read_identifier(c),
-            Some(c) if c.is_ascii_digit() || c == '-' => self.read_number(c),
-            Some(c) => Err(ParseError::UnexpectedChar(c)),
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Result<Self, ParseError> {
+        let limit = options.limits.max_input_bytes;
+        if input.len() > limit {
+            return Err(ParseError::InputTooLarge {
+                size: input.len(),
+                limit,
+            });
+        }
+        #[cfg(feature = "std")]
+        let resolver =
+            FsIncludeResolver::default().with_search_paths(options.include_paths.clone());
+        #[cfg(not(feature = "std"))]
+        let resolver = NoIncludeResolver;
+        let version = options.version;
+        let mut parser = Self {
+            input,
+            lexer: Lexer::new(input),
+            current_token: Token::Eof,
+            current_span: Span::default(),
+            prev_end: 0,
+            options,
+            variables: HashMap::new(),
+            resolver: Arc::new(resolver),
+            include_chain: Vec::new(),
+            source_name: None,
+            package: None,
+            included: VecDeque::new(),
+            symbols: SymbolTable::default(),
+            overrides: Vec::new(),
+            depth: 0,
+            templates: HashMap::new(),
+            buffer: VecDeque::new(),
+            version,
+            trace_depth: 0,
+            diagnostics: Diagnostics::new(),
+            resolved_includes: false,
+        };
+        parser.advance()?;
+        // A `#[...]` attribute on the first quest is not a pragma.
+        if parser.current_token == Token::Hash && parser.peek_n(1)? != &Token::LBracket {
+            parser.rule("pragma", Self::parse_version)?;
         }
+        parser.prev_end = 0;
+        Ok(parser)
     }
 
-    fn read_string(&mut self) -> Result<Token, ParseError> {
-        let mut s = String::new();
-        while let Some(&c) = self.input.peek() {
-            if c == '"' {
-                self.input.next();
-                return Ok(Token::StringLiteral(s));
-            }
-            s.push(self.input.next().unwrap());
+    /// Parses the `#version` pragma that may open a file.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// PRAGMA ::= "#" "version" INTEGER
+    /// ```
+    fn parse_version(&mut self) -> Result<(), ParseError> {
+        let start = self.current_span.start;
+        self.eat(Token::Hash)?;
+        if self.current_token != Token::Identifier("version") {
+            return Err(self.syntax_error("version"));
         }
-        Err(ParseError::UnexpectedEOF)
+        self.advance()?;
+        let Token::Number(n) = self.current_token else {
+            return Err(self.syntax_error("Number"));
+        };
+        self.version = GrammarVersion::from_number(n).ok_or(ParseError::UnsupportedVersion {
+            version: n,
+            span: Span::new(start, self.current_span.end),
+        })?;
+        self.advance()
     }
 
-    fn read_identifier(&mut self, first: char) -> Result<Token, ParseError> {
-        let mut ident = String::from(first);
-        while let Some(&c) = self.input.peek() {
-            if c.is_alphanumeric() || c == '_' {
-                ident.push(self.input.next().unwrap());
-            } else {
-                break;
+    /// The warnings raised so far, for the quests parsed so far.
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    /// Takes the warnings raised so far, leaving none behind, to tell them
+    /// apart from the ones of the quests parsed next.
+    pub fn take_diagnostics(&mut self) -> Diagnostics {
+        core::mem::take(&mut self.diagnostics)
+    }
+
+    /// The grammar version the input selected with its `#version` pragma.
+    pub fn version(&self) -> GrammarVersion {
+        self.version
+    }
+
+    /// Fails unless the input is written in `version` or later, naming the
+    /// `feature` that needs it.
+    fn require_version(
+        &self,
+        version: GrammarVersion,
+        feature: &str,
+        span: Span,
+    ) -> Result<(), ParseError> {
+        if self.version >= version {
+            return Ok(());
+        }
+        Err(ParseError::RequiresVersion {
+            feature: feature.to_string(),
+            version,
+            span,
+        })
+    }
+
+    /// Replaces the resolver used for `include` directives: the
+    /// [`FsIncludeResolver`], or [`NoIncludeResolver`] without the `std`
+    /// feature.
+    pub fn with_include_resolver(mut self, resolver: impl IncludeResolver + 'static) -> Self {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Names the input, typically with its file path. The name is passed to
+    /// the include resolver so relative includes resolve against it.
+    pub fn with_source_name(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.include_chain = vec![name.clone()];
+        self.source_name = Some(name);
+        self
+    }
+
+    /// Parses every quest of input that comes from users, such as uploaded
+    /// mods.
+    ///
+    /// Safe to call on arbitrary text: it never panics, never touches the
+    /// file system (`include` directives fail with
+    /// [`ParseError::IncludeFailed`]), and input size, steps, nesting and
+    /// strings are bounded by [`ParserLimits::UNTRUSTED`] so hostile input
+    /// cannot exhaust memory or the stack. Unknown properties are kept as
+    /// usual.
+    pub fn parse_untrusted(input: &str) -> Result<Vec<Quest>, ParseError> {
+        let options = ParserOptions {
+            limits: ParserLimits::UNTRUSTED,
+            ..Default::default()
+        };
+        Parser::with_options(input, options)?
+            .with_include_resolver(NoIncludeResolver)
+            .parse_quests()
+    }
+
+    /// Streams quests from a buffered reader without loading the whole input.
+    ///
+    /// See [`QuestStream`] for how the input is split, and
+    /// [`QuestStream::with_options`] to stream with other options.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: std::io::BufRead>(reader: R) -> QuestStream<R> {
+        QuestStream::with_options(reader, ParserOptions::default())
+    }
+
+    /// Byte range of the token the parser is currently looking at.
+    pub fn current_span(&self) -> Span {
+        self.current_span
+    }
+
+    fn advance(&mut self) -> Result<(), ParseError> {
+        let (token, span) = self.next_significant_token()?;
+        if let Token::StringLiteral(text) = &token {
+            let limit = self.options.limits.max_string_len;
+            if text.len() > limit {
+                return Err(ParseError::StringTooLong { limit, span });
             }
         }
-        match ident.as_str() {
-            "quest" => Ok(Token::QuestKeyword),
-            "true" => Ok(Token::True),
-            "false" => Ok(Token::False),
-            _ => Ok(Token::Identifier(ident)),
+        self.prev_end = self.current_span.end;
+        self.current_token = token;
+        self.current_span = span;
+        Ok(())
+    }
+
+    /// Looks `k` tokens past the current one without consuming any;
+    /// `peek_n(0)` is the current token. Comments are skipped as usual, and
+    /// past the end of the input every token is [`Token::Eof`].
+    fn peek_n(&mut self, k: usize) -> Result<&Token<'a>, ParseError> {
+        if k == 0 {
+            return Ok(&self.current_token);
+        }
+        while self.buffer.len() < k {
+            let next = self.lex_significant_token()?;
+            self.buffer.push_back(next);
         }
+        Ok(&self.buffer[k - 1].0)
     }
 
-    fn read_number(&mut self, first: char) -> Result<Token, ParseError> {
-        let mut num_str = String::from(first);
-        while let Some(&c) = self.input.peek() {
-            if c.is_ascii_digit() {
-                num_str.push(self.input.next().unwrap());
-            } else {
-                break;
+    fn next_significant_token(&mut self) -> Result<(Token<'a>, Span), ParseError> {
+        match self.buffer.pop_front() {
+            Some(next) => Ok(next),
+            None => self.lex_significant_token(),
+        }
+    }
+
+    fn lex_significant_token(&mut self) -> Result<(Token<'a>, Span), ParseError> {
+        loop {
+            match self.lexer.next_token()? {
+                (Token::Comment(_), _) => continue,
+                other => return Ok(other),
             }
         }
-        let num = num_str
-            .parse::<i32>()
-            .map_err(|_| ParseError::InvalidNumber)?;
-        Ok(Token::Number(num))
     }
-}
 
-pub struct Parser<'a> {
-    lexer: Lexer<'a>,
-    current_token: Token,
-}
+    /// Runs `parse` as the grammar rule `name`, logging where it starts and
+    /// where it ends or fails when [`ParserOptions::trace`] is on.
+    fn rule<T>(
+        &mut self,
+        name: &str,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if !self.options.trace {
+            return parse(self);
+        }
+        let indent = self.trace_depth * 2;
+        log::trace!("{:indent$}> {name} at {}", "", self.position());
+        self.trace_depth += 1;
+        let result = parse(self);
+        self.trace_depth -= 1;
+        match &result {
+            Ok(_) => log::trace!("{:indent$}< {name} before {}", "", self.position()),
+            Err(error) => log::trace!("{:indent$}< {name} failed: {error}", ""),
+        }
+        result
+    }
 
-impl<'a> Parser<'a> {
-    pub fn new(input: &'a str) -> Result<Self, ParseError> {
-        let mut lexer = Lexer::new(input);
-        let current_token = lexer.next_token()?;
-        Ok(Self {
-            lexer,
-            current_token,
-        })
+    /// The current token and where it is, for trace messages.
+    fn position(&self) -> String {
+        let (line, column) = line_col(self.input, self.current_span.start);
+        let source = self.source_name.as_deref().unwrap_or("<input>");
+        format!("{:?} ({source}:{line}:{column})", self.current_token)
     }
 
-    fn eat(&mut self, expected: Token) -> Result<(), ParseError> {
-        if std::mem::discriminant(&self.current_token) == std::mem::discriminant(&expected) {
-            self.current_token = self.lexer.next_token()?;
-            Ok(())
+    fn eat(&mut self, expected: Token<'_>) -> Result<(), ParseError> {
+        if core::mem::discriminant(&self.current_token) == core::mem::discriminant(&expected) {
+            self.advance()
         } else {
-            Err(ParseError::SyntaxError {
-                expected: format!("{:?}", expected),
-                found: format!("{:?}", self.current_token),
-            })
+            Err(self.syntax_error(format!("{:?}", expected)))
+        }
+    }
+
+    /// Enters one more level of nesting, failing once
+    /// [`ParserLimits::max_nesting`] is exceeded.
+    fn enter(&mut self) -> Result<(), ParseError> {
+        let limit = self.options.limits.max_nesting;
+        if self.depth >= limit {
+            return Err(ParseError::NestingTooDeep {
+                limit,
+                span: self.current_span,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Fails once `quest` has more than [`ParserLimits::max_steps`] steps,
+    /// blaming the property at `span` that added the last ones.
+    fn check_steps(&self, quest: &Quest, span: Span) -> Result<(), ParseError> {
+        let limit = self.options.limits.max_steps;
+        if quest.steps.len() > limit {
+            return Err(ParseError::TooManySteps { limit, span });
+        }
+        Ok(())
+    }
+
+    /// Warns when `quest` already has a step with the text `text`, which is
+    /// about to be added by the property at `span`.
+    fn warn_duplicate_step(&mut self, quest: &Quest, text: &Text, span: Span) {
+        if quest.steps.iter().any(|step| step.text == *text) {
+            self.diagnostics.warn(Diagnostic::DuplicateStep {
+                text: text.clone(),
+                span,
+            });
+        }
+    }
+
+    /// Warns that the construct at `span` has a newer form to write instead,
+    /// if the input is written in version `since` or later. Older files keep
+    /// parsing quietly, so upgrading the parser alone never adds warnings.
+    fn warn_deprecated(
+        &mut self,
+        feature: &str,
+        since: GrammarVersion,
+        replacement: String,
+        span: Span,
+    ) {
+        if self.version >= since {
+            self.diagnostics.warn(Diagnostic::Deprecated {
+                feature: feature.to_string(),
+                since,
+                replacement,
+                span,
+            });
+        }
+    }
+
+    /// Consumes the separator between two items of a list-like construct: a
+    /// comma, or nothing when the next item starts on a new line. Returns
+    /// whether there was one; a trailing comma is consumed like any other.
+    fn eat_separator(&mut self) -> Result<bool, ParseError> {
+        if self.current_token == Token::Comma {
+            self.eat(Token::Comma)?;
+            return Ok(true);
+        }
+        let gap = self.input.get(self.prev_end..self.current_span.start);
+        Ok(gap.is_some_and(|gap| gap.contains('\n')))
+    }
+
+    /// Whether the current token names the currency of an amount: an
+    /// identifier on the same line as the number before it that is not the
+    /// key of the next property.
+    fn at_currency(&mut self) -> Result<bool, ParseError> {
+        if !matches!(self.current_token, Token::Identifier(_)) {
+            return Ok(false);
+        }
+        let gap = self.input.get(self.prev_end..self.current_span.start);
+        if gap.is_some_and(|gap| gap.contains('\n')) {
+            return Ok(false);
+        }
+        Ok(self.peek_n(1)? != &Token::Colon)
+    }
+
+    fn syntax_error(&self, expected: impl Into<String>) -> ParseError {
+        ParseError::SyntaxError {
+            expected: expected.into(),
+            found: format!("{:?}", self.current_token),
+            span: self.current_span,
         }
     }
 
@@ -151,105 +1103,1745 @@ impl<'a> Parser<'a> {
     ///
     /// # Grammar Rule
     /// ```ebnf
-    /// QUEST_DEF ::= "quest" (IDENTIFIER | STRING) "{" BODY "}"
+    /// QUEST_DEF ::= ATTRIBUTE* "quest" (IDENTIFIER | STRING) ("{" BODY "}" | INSTANTIATION | OVERRIDE)
     /// ```
     ///
     /// This is the entry point for the parser. It expects the keyword `quest`,
     /// followed by a name, and then a block of properties enclosed in curly braces.
+    /// Any declarations in front of the quest are evaluated first; if one of
+    /// them includes a file, the first quest of that file is returned.
+    /// Overrides in front of the quest are collected, see [`World::overrides`].
     pub fn parse_quest(&mut self) -> Result<Quest, ParseError> {
-        self.eat(Token::QuestKeyword)?;
-
-        let quest_name = match &self.current_token {
-            Token::Identifier(name) | Token::StringLiteral(name) => name.clone(),
-            _ => {
-                return Err(ParseError::SyntaxError {
-                    expected: "Identifier or String".to_string(),
-                    found: format!("{:?}", self.current_token),
-                })
+        loop {
+            self.parse_declarations()?;
+            if let Some(quest) = self.included.pop_front() {
+                return Ok(quest);
             }
-        };
-        self.current_token = self.lexer.next_token()?;
+            if let Some(quest) = self.rule("quest", |p| p.parse_quest_def(None))? {
+                return Ok(quest);
+            }
+        }
+    }
 
-        self.eat(Token::LBrace)?;
+    /// Like [`Parser::parse_quest`], also returning the warnings raised
+    /// while parsing the quest and the declarations in front of it. Warnings
+    /// never fail the parse.
+    pub fn parse_quest_with_warnings(&mut self) -> Result<ParseOutput, ParseError> {
+        let quest = self.parse_quest()?;
+        Ok(ParseOutput {
+            quest,
+            warnings: self.take_diagnostics().into_warnings(),
+        })
+    }
 
+    /// Parses a quest, either written out or instantiated from a template,
+    /// or an override of a quest, which is collected instead of returned.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// QUEST_DEF ::= ATTRIBUTE* "quest" (IDENTIFIER | STRING) ("{" BODY "}" | INSTANTIATION | OVERRIDE)
+    /// BODY      ::= (ATTRIBUTE* PROPERTY ","?)*
+    /// OVERRIDE  ::= "override" "{" BODY "}"
+    /// ```
+    ///
+    /// A quest or property whose attributes are not all enabled is still
+    /// parsed, so errors in it are reported, but then dropped.
+    fn parse_quest_def(
+        &mut self,
+        visitor: Option<&mut (dyn QuestVisitor + '_)>,
+    ) -> Result<Option<Quest>, ParseError> {
+        let enabled = self.parse_attributes()?;
+        let start = self.current_span.start;
+        self.eat(Token::QuestKeyword)?;
+        let name_span = self.current_span;
+        let name = match &self.current_token {
+            Token::Identifier(name) => nfc(name).into_owned(),
+            Token::StringLiteral(name) => name.to_string(),
+            _ => return Err(self.syntax_error("Identifier or String")),
+        };
+        self.advance()?;
+        let overriding = self.current_token == Token::Identifier("override");
+        let (opened_at, shadowed) = if overriding {
+            self.advance()?;
+            (self.open_brace()?, Vec::new())
+        } else if self.current_token == Token::Identifier("from") {
+            (self.current_span, self.instantiate()?)
+        } else {
+            (self.open_brace()?, Vec::new())
+        };
         let mut quest = Quest {
-            name: quest_name,
+            name,
             ..Default::default()
         };
+        quest.source_map.file = self.source_name.clone();
+        quest.source_map.name = Some(name_span);
+        quest.package = self.package.clone();
+        let mut visitor = visitor.filter(|_| enabled && !overriding);
+        if let Some(visitor) = visitor.as_deref_mut() {
+            visitor.on_quest_start(&quest.name, name_span);
+        }
 
+        let mut fields: Vec<String> = Vec::new();
         while self.current_token != Token::RBrace && self.current_token != Token::Eof {
-            self.parse_property(&mut quest)?;
+            if !self.parse_attributes()? {
+                self.rule("property", |p| p.parse_property(&mut Quest::default()))?;
+                if self.current_token == Token::Comma {
+                    self.eat(Token::Comma)?;
+                }
+                continue;
+            }
+            let start = self.current_span.start;
+            let mut field = None;
+            let mut replaces = true;
+            if let Token::Identifier(key) = self.current_token {
+                let repeat = key == "repeat" && self.peek_n(1)? == &Token::LParen;
+                let key = if key == "steps" || repeat {
+                    "step"
+                } else {
+                    key
+                };
+                let tier = key == "reward" && self.peek_n(1)? == &Token::Identifier("tier");
+                replaces = !tier && !GATHERED_FIELDS.contains(&key);
+                let key = nfc(key).into_owned();
+                if !fields.contains(&key) {
+                    fields.push(key.clone());
+                }
+                field = Some(key);
+            }
+            let mark = visitor.is_some().then(|| Mark::of(&quest));
+            self.rule("property", |p| p.parse_property(&mut quest))?;
+            if let Some(field) = field {
+                let span = Span::new(start, self.prev_end);
+                quest.source_map.add_property(&field, span, replaces);
+                if let (Some(visitor), Some(mark)) = (visitor.as_deref_mut(), mark) {
+                    visit_property(visitor, &quest, &field, span, mark);
+                }
+            }
             if self.current_token == Token::Comma {
                 self.eat(Token::Comma)?;
             }
         }
 
-        self.eat(Token::RBrace)?;
-        Ok(quest)
+        self.close_brace(opened_at)?;
+        for (param, previous) in shadowed {
+            match previous {
+                Some(value) => self.variables.insert(param, value),
+                None => self.variables.remove(&param),
+            };
+        }
+        if let Some(visitor) = visitor {
+            visitor.on_quest_end();
+        }
+        if !enabled {
+            return Ok(None);
+        }
+        if overriding {
+            self.overrides.push(QuestOverride {
+                name: quest.name.clone(),
+                patch: quest,
+                fields,
+                span: Span::new(start, self.prev_end),
+            });
+            return Ok(None);
+        }
+        Ok(Some(quest))
+    }
+
+    /// Parses `quest NAME {`, returning the name and the span of the brace.
+    fn parse_quest_header(&mut self) -> Result<(String, Span), ParseError> {
+        self.eat(Token::QuestKeyword)?;
+        self.parse_block_name()
     }
 
-    /// Parses individual properties inside the Quest body.
+    /// Parses the name of a top-level block and its opening brace, returning
+    /// the name and the span of the brace.
+    fn parse_block_name(&mut self) -> Result<(String, Span), ParseError> {
+        let name = match &self.current_token {
+            Token::Identifier(name) => nfc(name).into_owned(),
+            Token::StringLiteral(name) => name.to_string(),
+            _ => return Err(self.syntax_error("Identifier or String")),
+        };
+        self.advance()?;
+        Ok((name, self.open_brace()?))
+    }
+
+    /// Consumes the `{` opening a block, returning its span for
+    /// [`Parser::close_brace`].
+    fn open_brace(&mut self) -> Result<Span, ParseError> {
+        let span = self.current_span;
+        self.eat(Token::LBrace)?;
+        Ok(span)
+    }
+
+    /// Consumes the `}` closing the block opened at `opened_at`, failing with
+    /// [`ParseError::UnclosedBrace`] if the input ends first.
+    fn close_brace(&mut self, opened_at: Span) -> Result<(), ParseError> {
+        if self.current_token == Token::Eof {
+            return Err(ParseError::UnclosedBrace { opened_at });
+        }
+        self.eat(Token::RBrace)
+    }
+
+    /// Parses the next quest against `schema` instead of the built-in
+    /// properties.
     ///
     /// # Grammar Rule
     /// ```ebnf
-    /// PROPERTY ::= KEY ":" VALUE
-    /// KEY      ::= "reward" | "active" | "step"
-    /// VALUE    ::= INTEGER | BOOLEAN | STRING
+    /// DYNAMIC_QUEST ::= "quest" (IDENTIFIER | STRING) "{" (KEY ":" (VALUE | EXPR) ","?)* "}"
     /// ```
     ///
-    /// Handles specific keys:
-    /// - `reward`: Expects an integer number.
-    /// - `active`: Expects a boolean (`true`/`false`).
-    /// - `step`: Expects a string literal (can be repeated).
-    fn parse_property(&mut self, quest: &mut Quest) -> Result<(), ParseError> {
-        let key = match &self.current_token {
-            Token::Identifier(k) => k.clone(),
-            _ => {
-                return Err(ParseError::SyntaxError {
-                    expected: "Property Key".to_string(),
-                    found: format!("{:?}", self.current_token),
-                })
-            }
+    /// Every key must be registered in the schema, and its value must have
+    /// the registered [`ValueType`]. A non-repeatable property can be set only
+    /// once, and missing required properties are reported once the block is
+    /// closed. `let` declarations in front of the quest are evaluated;
+    /// `include` directives are not supported, as included files yield
+    /// [`Quest`]s.
+    pub fn parse_dynamic(&mut self, schema: &QuestSchema) -> Result<DynamicQuest, ParseError> {
+        while self.current_token == Token::LetKeyword {
+            self.parse_let()?;
+        }
+        let (name, opened_at) = self.parse_quest_header()?;
+        let mut quest = DynamicQuest {
+            name,
+            ..Default::default()
         };
-        self.current_token = self.lexer.next_token()?;
 
-        self.eat(Token::Colon)?;
+        while self.current_token != Token::RBrace && self.current_token != Token::Eof {
+            let key_span = self.current_span;
+            let key = self.parse_key()?;
+            let Some(spec) = schema.get(&key) else {
+                return Err(ParseError::UnknownProperty {
+                    suggestion: schema.suggest(&key).map(str::to_string),
+                    key,
+                    span: key_span,
+                });
+            };
+            if !spec.repeatable && quest.properties.contains_key(&key) {
+                return Err(ParseError::DuplicateProperty {
+                    key,
+                    span: key_span,
+                });
+            }
+            self.eat(Token::Colon)?;
 
-        match key.as_str() {
-            "reward" => {
-                if let Token::Number(n) = self.current_token {
-                    quest.reward = n;
-                    self.current_token = self.lexer.next_token()?;
-                } else {
+            let start = self.current_span.start;
+            let value = match self.parse_property_expr()? {
+                Expr::Literal(value) => value,
+                expr => {
                     return Err(ParseError::SyntaxError {
-                        expected: "Number".into(),
-                        found: format!("{:?}", self.current_token),
-                    });
+                        expected: spec.value_type.to_string(),
+                        found: format!("expression `{expr}`"),
+                        span: Span::new(start, self.prev_end),
+                    })
                 }
+            };
+            let value = spec.value_type.coerce(value).map_err(|value| {
+                let expected = spec.value_type.to_string();
+                type_mismatch(&expected, &value, Span::new(start, self.prev_end))
+            })?;
+            quest.properties.entry(key).or_default().push(value);
+
+            if self.current_token == Token::Comma {
+                self.eat(Token::Comma)?;
             }
-            "active" => {
-                match self.current_token {
-                    Token::True => quest.active = true,
-                    Token::False => quest.active = false,
-                    _ => {
-                        return Err(ParseError::SyntaxError {
-                            expected: "Bool".into(),
-                            found: format!("{:?}", self.current_token),
-                        })
-                    }
-                }
-                self.current_token = self.lexer.next_token()?;
+        }
+        self.close_brace(opened_at)?;
+
+        match schema
+            .required_names()
+            .into_iter()
+            .find(|name| !quest.properties.contains_key(*name))
+        {
+            Some(name) => Err(missing_field("quest", name)),
+            None => Ok(quest),
+        }
+    }
+
+    /// Parses every quest until the end of the input with
+    /// [`Parser::parse_dynamic`].
+    pub fn parse_dynamic_quests(
+        &mut self,
+        schema: &QuestSchema,
+    ) -> Result<Vec<DynamicQuest>, ParseError> {
+        let mut quests = Vec::new();
+        loop {
+            while self.current_token == Token::LetKeyword {
+                self.parse_let()?;
             }
-            "step" => {
-                if let Token::StringLiteral(s) = &self.current_token {
-                    quest.steps.push(s.clone());
-                    self.current_token = self.lexer.next_token()?;
+            if self.current_token == Token::Eof {
+                return Ok(quests);
+            }
+            quests.push(self.parse_dynamic(schema)?);
+        }
+    }
+
+    /// Parses every quest definition until the end of the input.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// FILE ::= PRAGMA? (LET_DEF | INCLUDE | PACKAGE | NPC_DEF | ITEM_DEF | TEMPLATE_DEF | QUEST_DEF)*
+    /// ```
+    ///
+    /// Quests from included files are returned in place of the directive.
+    pub fn parse_quests(&mut self) -> Result<Vec<Quest>, ParseError> {
+        let mut quests = Vec::new();
+        while let Some(quest) = self.parse_next_quest(None)? {
+            quests.push(quest);
+        }
+        Ok(quests)
+    }
+
+    /// Parses every quest until the end of the input like
+    /// [`Parser::parse_quests`], reporting each one to `visitor` as it is
+    /// read instead of returning it, and returns how many quests there were.
+    ///
+    /// Callbacks come in source order as soon as each property has been
+    /// parsed and checked, so an error stops the parse part way through a
+    /// quest, after the callbacks for the properties before it and without
+    /// [`QuestVisitor::on_quest_end`].
+    ///
+    /// This does not avoid building quests: each one is still parsed into a
+    /// full [`Quest`], which its checks read and the callbacks borrow their
+    /// values from. What it saves is keeping them, as each quest is dropped
+    /// once `on_quest_end` returns, so memory holds one quest however many
+    /// the input has. Quests from included files are parsed in full
+    /// first, then reported in place of the directive. Quests whose
+    /// attributes are not all enabled and overrides are not reported;
+    /// overrides are still collected, see [`World::overrides`].
+    pub fn parse_with_visitor(
+        &mut self,
+        visitor: &mut impl QuestVisitor,
+    ) -> Result<usize, ParseError> {
+        let mut count = 0;
+        while self.parse_next_quest(Some(visitor))?.is_some() {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Parses every quest, NPC and item until the end of the input.
+    ///
+    /// Unless [`ParserOptions::allow_external_refs`] is set, the quests'
+    /// references are then checked with [`World::check_references`].
+    pub fn parse_world(&mut self) -> Result<World, ParseError> {
+        let quests = self.parse_quests()?;
+        let world = World {
+            quests,
+            symbols: core::mem::take(&mut self.symbols),
+            overrides: core::mem::take(&mut self.overrides),
+        };
+        if !self.options.allow_external_refs {
+            world.check_references()?;
+        }
+        Ok(world)
+    }
+
+    /// Parses the next quest, or returns `None` once only declarations remain,
+    /// reporting it to `visitor` if there is one.
+    fn parse_next_quest(
+        &mut self,
+        mut visitor: Option<&mut (dyn QuestVisitor + '_)>,
+    ) -> Result<Option<Quest>, ParseError> {
+        loop {
+            self.parse_declarations()?;
+            if let Some(quest) = self.included.pop_front() {
+                if let Some(visitor) = visitor.as_deref_mut() {
+                    visit_quest(visitor, &quest);
                 }
+                return Ok(Some(quest));
             }
-            _ => {
-                self.current_token = self.lexer.next_token()?;
+            if self.current_token == Token::Eof {
+                return Ok(None);
+            }
+            let visitor = visitor.as_deref_mut();
+            if let Some(quest) = self.rule("quest", |p| p.parse_quest_def(visitor))? {
+                return Ok(Some(quest));
+            }
+        }
+    }
+
+    /// Parses top-level declarations up to the next quest. Stops early once
+    /// an include has produced quests, so they are returned in file order.
+    fn parse_declarations(&mut self) -> Result<(), ParseError> {
+        while self.included.is_empty() {
+            match self.current_token {
+                Token::LetKeyword => self.rule("let", Self::parse_let)?,
+                Token::IncludeKeyword => self.rule("include", Self::parse_include)?,
+                Token::Identifier("npc") => {
+                    self.advance()?;
+                    let (name, properties) = self.rule("definition", Self::parse_definition)?;
+                    self.symbols.add_npc(Npc { name, properties })?;
+                }
+                Token::Identifier("item") => {
+                    self.advance()?;
+                    let (name, properties) = self.rule("definition", Self::parse_definition)?;
+                    self.symbols.add_item(Item { name, properties })?;
+                }
+                Token::Identifier("template") => self.rule("template", Self::parse_template)?,
+                Token::Identifier("package") => self.rule("package", Self::parse_package)?,
+                _ => break,
             }
         }
         Ok(())
     }
+
+    /// Parses the attributes in front of a quest or property, and tells
+    /// whether every feature flag they name is enabled.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// ATTRIBUTE ::= "#" "[" "if" "(" "feature" "=" STRING ")" "]"
+    /// ```
+    ///
+    /// See [`ParserOptions::flags`].
+    fn parse_attributes(&mut self) -> Result<bool, ParseError> {
+        let mut enabled = true;
+        while self.current_token == Token::Hash {
+            if self.peek_n(1)? == &Token::Identifier("version") {
+                return Err(ParseError::MisplacedVersion {
+                    span: self.current_span,
+                });
+            }
+            self.advance()?;
+            self.eat(Token::LBracket)?;
+            if self.current_token != Token::Identifier("if") {
+                return Err(self.syntax_error("if"));
+            }
+            self.advance()?;
+            self.eat(Token::LParen)?;
+            if self.current_token != Token::Identifier("feature") {
+                return Err(self.syntax_error("feature"));
+            }
+            self.advance()?;
+            self.eat(Token::Equals)?;
+            let flag = self.expect_string()?;
+            self.eat(Token::RParen)?;
+            self.eat(Token::RBracket)?;
+            enabled &= self.options.flags.contains(&flag);
+        }
+        Ok(enabled)
+    }
+
+    /// Parses the name and body of an NPC or item definition, after its
+    /// keyword.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// NPC_DEF  ::= "npc" (IDENTIFIER | STRING) "{" (KEY ":" VALUE ","?)* "}"
+    /// ITEM_DEF ::= "item" (IDENTIFIER | STRING) "{" (KEY ":" VALUE ","?)* "}"
+    /// ```
+    ///
+    /// `npc` and `item` are only keywords at the top level, so they stay
+    /// usable as property keys.
+    fn parse_definition(&mut self) -> Result<(String, IndexMap<String, Value>), ParseError> {
+        let (name, opened_at) = self.parse_block_name()?;
+        let mut properties = IndexMap::default();
+        while self.current_token != Token::RBrace && self.current_token != Token::Eof {
+            let key = self.parse_key()?;
+            self.eat(Token::Colon)?;
+            properties.insert(key, self.parse_value()?);
+            if self.current_token == Token::Comma {
+                self.eat(Token::Comma)?;
+            }
+        }
+        self.close_brace(opened_at)?;
+        Ok((name, properties))
+    }
+
+    /// Parses a quest template.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// TEMPLATE_DEF ::= "template" (IDENTIFIER | STRING) "(" (IDENTIFIER (SEP IDENTIFIER)* ","?)? ")" "{" BODY "}"
+    /// ```
+    ///
+    /// The body is kept unparsed and only checked when the template is
+    /// instantiated, see [`Parser::instantiate`]. Parameters are referenced
+    /// as `$NAME` in the body. Like `npc`, `template` is only a keyword at the
+    /// top level.
+    fn parse_template(&mut self) -> Result<(), ParseError> {
+        self.advance()?;
+        let name = match &self.current_token {
+            Token::Identifier(name) => nfc(name).into_owned(),
+            Token::StringLiteral(name) => name.to_string(),
+            _ => return Err(self.syntax_error("Identifier or String")),
+        };
+        if self.templates.contains_key(&name) {
+            return Err(ParseError::DuplicateDefinition {
+                kind: "template".to_string(),
+                name,
+            });
+        }
+        self.advance()?;
+
+        self.eat(Token::LParen)?;
+        let mut params = Vec::new();
+        while self.current_token != Token::RParen {
+            let param = self.parse_key()?;
+            if params.contains(&param) {
+                return Err(ParseError::DuplicateDefinition {
+                    kind: "parameter".to_string(),
+                    name: param,
+                });
+            }
+            params.push(param);
+            if !self.eat_separator()? {
+                break;
+            }
+        }
+        self.eat(Token::RParen)?;
+
+        // Every brace still open, the template's own first.
+        let mut open = vec![self.open_brace()?];
+        let start = open[0].end;
+        loop {
+            match self.current_token {
+                Token::Eof => {
+                    let opened_at = *open.last().expect("the template's brace is open");
+                    return Err(ParseError::UnclosedBrace { opened_at });
+                }
+                Token::RBrace if open.len() == 1 => break,
+                Token::LBrace => open.push(self.current_span),
+                Token::RBrace => {
+                    open.pop();
+                }
+                _ => {}
+            }
+            self.advance()?;
+        }
+        let end = self.current_span;
+        self.advance()?;
+
+        let body = &self.input[start..end.start];
+        let template = Template {
+            params,
+            body,
+            start,
+            end,
+        };
+        self.templates.insert(name, template);
+        Ok(())
+    }
+
+    /// Parses the instantiation of a template, after the quest's name, and
+    /// feeds the template's body back to the parser with the arguments bound
+    /// to its parameters.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// INSTANTIATION ::= "from" IDENTIFIER "(" (IDENTIFIER ":" VALUE (SEP IDENTIFIER ":" VALUE)* ","?)? ")"
+    /// ```
+    ///
+    /// Every parameter needs exactly one argument. The quest body is then
+    /// parsed from the template's tokens as if it had been written out, so
+    /// errors in the body point into the template. Returns the constants the
+    /// arguments shadow, to be restored once the body has been parsed.
+    fn instantiate(&mut self) -> Result<Vec<(String, Option<Value>)>, ParseError> {
+        self.advance()?;
+        let start = self.current_span.start;
+        let name = match &self.current_token {
+            Token::Identifier(name) => nfc(name).into_owned(),
+            Token::StringLiteral(name) => name.to_string(),
+            _ => return Err(self.syntax_error("Template Name")),
+        };
+        let Some(template) = self.templates.get(&name).cloned() else {
+            return Err(ParseError::UnknownTemplate {
+                name,
+                span: self.current_span,
+            });
+        };
+        self.advance()?;
+
+        self.eat(Token::LParen)?;
+        let mut args: HashMap<String, Value> = HashMap::new();
+        while self.current_token != Token::RParen {
+            let key_span = self.current_span;
+            let key = self.parse_key()?;
+            if !template.params.contains(&key) || args.contains_key(&key) {
+                return Err(ParseError::UnexpectedArgument {
+                    template: name,
+                    name: key,
+                    span: key_span,
+                });
+            }
+            self.eat(Token::Colon)?;
+            let value = self.parse_value()?;
+            args.insert(key, value);
+            if !self.eat_separator()? {
+                break;
+            }
+        }
+        self.eat(Token::RParen)?;
+        let span = Span::new(start, self.prev_end);
+        if let Some(missing) = template.params.iter().find(|p| !args.contains_key(*p)) {
+            return Err(ParseError::MissingArgument {
+                template: name,
+                name: missing.clone(),
+                span,
+            });
+        }
+
+        let shadowed = args
+            .into_iter()
+            .map(|(param, value)| {
+                let previous = self.variables.insert(param.clone(), value);
+                (param, previous)
+            })
+            .collect();
+
+        // The body goes in front of the current token and anything read
+        // ahead of it.
+        let ahead = core::mem::take(&mut self.buffer);
+        let mut lexer = Lexer::new(template.body);
+        loop {
+            match lexer.next_token()? {
+                (Token::Eof, _) => break,
+                (Token::Comment(_), _) => {}
+                (token, span) => {
+                    let span = Span::new(template.start + span.start, template.start + span.end);
+                    self.buffer.push_back((token, span));
+                }
+            }
+        }
+        self.buffer.push_back((Token::RBrace, template.end));
+        let current = core::mem::replace(&mut self.current_token, Token::Eof);
+        self.buffer.push_back((current, self.current_span));
+        self.buffer.extend(ahead);
+        self.advance()?;
+        Ok(shadowed)
+    }
+
+    /// Parses a constant declaration.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// LET_DEF ::= "let" IDENTIFIER "=" VALUE
+    /// ```
+    ///
+    /// Constants are referenced as `$NAME` wherever a value is expected and
+    /// are substituted while parsing. A constant must be declared before its
+    /// first use and cannot be redefined.
+    fn parse_let(&mut self) -> Result<(), ParseError> {
+        self.eat(Token::LetKeyword)?;
+        let name = self.parse_key()?;
+        self.eat(Token::Equals)?;
+        let value = self.parse_value()?;
+        if self.variables.contains_key(&name) {
+            return Err(ParseError::DuplicateVariable(name));
+        }
+        self.variables.insert(name, value);
+        Ok(())
+    }
+
+    /// Parses a package declaration.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// PACKAGE ::= "package" STRING
+    /// ```
+    ///
+    /// The package holds for every quest after it, up to the next
+    /// declaration or the end of the file; included files declare their own.
+    /// A name is made of identifiers joined by dots, such as `act1.village`.
+    fn parse_package(&mut self) -> Result<(), ParseError> {
+        self.advance()?;
+        let span = self.current_span;
+        let name = self.expect_string()?;
+        if !package::is_valid_package(&name) {
+            return Err(ParseError::InvalidPackage { name, span });
+        }
+        self.package = Some(name);
+        Ok(())
+    }
+
+    /// Parses an include directive and everything in the included file.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// INCLUDE ::= "include" STRING
+    /// ```
+    ///
+    /// The included file shares `let` constants with the including one in
+    /// both directions. Errors inside it are wrapped in
+    /// [`ParseError::InInclude`] with the chain of files leading to it.
+    fn parse_include(&mut self) -> Result<(), ParseError> {
+        self.eat(Token::IncludeKeyword)?;
+        let path = self.expect_string()?;
+
+        let IncludeSource { name, source } = self
+            .resolver
+            .resolve(&path, self.source_name.as_deref())
+            .map_err(|message| ParseError::IncludeFailed {
+                path: path.clone(),
+                message,
+            })?;
+        self.resolved_includes = true;
+        if self.include_chain.contains(&name) {
+            let mut chain = self.include_chain.clone();
+            chain.push(name);
+            return Err(ParseError::IncludeCycle(chain));
+        }
+
+        let wrap = |error: ParseError| match error {
+            ParseError::InInclude { mut chain, error } => {
+                chain.insert(0, name.clone());
+                ParseError::InInclude { chain, error }
+            }
+            ParseError::IncludeCycle(chain) => ParseError::IncludeCycle(chain),
+            error => ParseError::InInclude {
+                chain: vec![name.clone()],
+                error: Box::new(error),
+            },
+        };
+
+        let mut parser = Parser::with_options(&source, self.options.clone()).map_err(wrap)?;
+        parser.resolver = Arc::clone(&self.resolver);
+        parser.source_name = Some(name.clone());
+        parser.include_chain = self.include_chain.clone();
+        parser.include_chain.push(name.clone());
+        parser.variables = core::mem::take(&mut self.variables);
+        let result = parser.parse_quests();
+        self.variables = core::mem::take(&mut parser.variables);
+        self.included.extend(result.map_err(wrap)?);
+        self.symbols.extend(parser.symbols)?;
+        self.overrides.extend(parser.overrides);
+        for warning in parser.diagnostics.into_warnings() {
+            let warning = match warning {
+                Diagnostic::InInclude { mut chain, warning } => {
+                    chain.insert(0, name.clone());
+                    Diagnostic::InInclude { chain, warning }
+                }
+                warning => Diagnostic::InInclude {
+                    chain: vec![name.clone()],
+                    warning: Box::new(warning),
+                },
+            };
+            self.diagnostics.warn(warning);
+        }
+        Ok(())
+    }
+
+    /// Parses individual properties inside the Quest body.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// PROPERTY ::= KEY ":" (VALUE | EXPR) | "objective" OBJECTIVE_BLOCK | "stages" STAGES_BLOCK
+    ///            | "step" ":" VALUE STEP_ATTRS? | "steps" STEP_GROUP | "reward" ":" AMOUNTS | "reward" REWARD_BLOCK
+    ///            | REWARD_TIER
+    ///            | ("on_success" | "on_failure") OUTCOME_BLOCK
+    ///            | ("on_start" | "on_complete") ACTION_BLOCK
+    ///            | ("giver" | "turn_in") ":" NPC_REF | REPEAT
+    /// AMOUNTS  ::= INTEGER IDENTIFIER ("," INTEGER IDENTIFIER)*
+    /// KEY      ::= "reward" | "active" | "step" | "steps" | "requires" | "tags" | "giver" | "turn_in" | "level" | "starts_at" | "ends_at" | "complete_when" | IDENTIFIER
+    /// ```
+    ///
+    /// Handles specific keys:
+    /// - `reward`: Expects an integer number or an expression. From `#version 2`
+    ///   on it also takes amounts in currencies, either as a list such as
+    ///   `100 gold, 5 gems` or as a block, see [`Parser::parse_rewards`]. A
+    ///   currency must be on the same line as its amount.
+    ///   `reward tier` starts a named tier instead, see
+    ///   [`Parser::parse_reward_tier`].
+    /// - `active`: Expects a boolean (`true`/`false`) or an expression.
+    /// - `step`: Expects a string literal or a localized string (can be repeated),
+    ///   optionally followed by attributes, see [`Parser::parse_step_attributes`].
+    /// - `steps`: Expects a list of strings, appended to the steps, or a block
+    ///   grouping every step of the quest, see [`Parser::parse_step_group`].
+    ///   From `#version 2` on the list is [deprecated](Diagnostic::Deprecated)
+    ///   in favour of one `step` per item.
+    /// - `requires`: Expects the name of a prerequisite quest (can be repeated).
+    /// - `tags`: Expects a list of strings, appended to the tags.
+    /// - `giver`, `turn_in`: Expect an NPC, see [`Parser::parse_npc_ref`].
+    /// - `repeat(n)`: Expects a `step` to add `n` times, see
+    ///   [`Parser::parse_repeat`].
+    /// - `time_limit`: Expects a duration such as `2h30m`.
+    /// - `level`: Expects a range such as `10..20`, or a single level, which
+    ///   is deprecated from `#version 2` on in favour of a range like `10..10`.
+    /// - `starts_at`, `ends_at`: Expect a date and time such as
+    ///   `2025-10-31T00:00:00Z`; the quest must end after it starts.
+    /// - `complete_when`: Expects objective names joined by `&&` and `||`,
+    ///   grouped with parentheses, see [`Condition`].
+    /// - `objective`: Expects a block, see [`Parser::parse_objective`].
+    /// - `stages`: Expects a block, see [`Parser::parse_stages`].
+    /// - `on_success`, `on_failure`: Expect a block, see [`Parser::parse_outcome`].
+    /// - `on_start`, `on_complete`: Expect a block, see [`Parser::parse_actions`].
+    ///
+    /// The token after the key tells the two forms apart: `{` starts a block
+    /// and anything else must be `:`. A block-only key given a value fails
+    /// with [`ParseError::BlockExpected`], any other key given a block with
+    /// [`ParseError::ValueExpected`].
+    ///
+    /// Expressions are evaluated while parsing when they only involve
+    /// literals; ones that reference game state are stored in
+    /// [`Quest::expressions`]. Any other key is stored in [`Quest::extra`] unless
+    /// [`ParserOptions::collect_extra`] is turned off, or rejected in
+    /// [`ParserOptions::strict`] mode.
+    fn parse_property(&mut self, quest: &mut Quest) -> Result<(), ParseError> {
+        let key_span = self.current_span;
+        let block = self.peek_n(1)? == &Token::LBrace;
+        let key = self.parse_key()?;
+        let step_block = key == "steps" && block;
+        if matches!(key.as_str(), "step" | "steps" | "repeat")
+            && (quest.step_tree.is_some() || step_block && !quest.steps.is_empty())
+        {
+            return Err(ParseError::MixedSteps { span: key_span });
+        }
+        if key == "repeat" && self.current_token == Token::LParen {
+            return self.rule("repeat", |p| p.parse_repeat(quest));
+        }
+        if key == "reward" && self.current_token == Token::Identifier("tier") {
+            self.require_version(GrammarVersion::V2, "A `reward tier` block", key_span)?;
+            return self.rule("reward tier", |p| p.parse_reward_tier(quest));
+        }
+        if block {
+            return self.parse_block_property(quest, key, key_span);
+        }
+        if BLOCK_PROPERTIES.contains(&key.as_str()) {
+            return Err(ParseError::BlockExpected {
+                key,
+                span: key_span,
+            });
+        }
+
+        self.eat(Token::Colon)?;
+
+        match key.as_str() {
+            "reward" => {
+                let start = self.current_span.start;
+                match self.rule("expression", Self::parse_property_expr)? {
+                    Expr::Literal(Value::Number(n))
+                        if self.version >= GrammarVersion::V2 && self.at_currency()? =>
+                    {
+                        quest.rewards = self.rule("amounts", |p| p.parse_amounts(n, start))?;
+                    }
+                    Expr::Literal(Value::Number(n)) => {
+                        quest.reward = n;
+                        quest.expressions.shift_remove(&key);
+                    }
+                    Expr::Literal(other) => {
+                        let span = Span::new(start, self.prev_end);
+                        return Err(type_mismatch("Number", &other, span));
+                    }
+                    expr => {
+                        quest.expressions.insert(key, expr);
+                    }
+                }
+            }
+            "active" => {
+                let start = self.current_span.start;
+                match self.rule("expression", Self::parse_property_expr)? {
+                    Expr::Literal(Value::Bool(b)) => {
+                        quest.active = b;
+                        quest.expressions.shift_remove(&key);
+                    }
+                    Expr::Literal(other) => {
+                        let span = Span::new(start, self.prev_end);
+                        return Err(type_mismatch("Bool", &other, span));
+                    }
+                    expr => {
+                        quest.expressions.insert(key, expr);
+                    }
+                }
+            }
+            "step" => {
+                let start = self.current_span.start;
+                let mut step = match self.parse_value()? {
+                    Value::String(s) => Step::new(s),
+                    Value::Localized(l) => Step::new(Text::Localized(l)),
+                    other => {
+                        let span = Span::new(start, self.prev_end);
+                        return Err(type_mismatch("String", &other, span));
+                    }
+                };
+                if self.current_token == Token::LBracket {
+                    self.rule("step attributes", |p| p.parse_step_attributes(&mut step))?;
+                }
+                let span = Span::new(start, self.prev_end);
+                self.warn_duplicate_step(quest, &step.text, span);
+                quest.steps.push(step);
+                quest.source_map.steps.push(span);
+                self.check_steps(quest, span)?;
+            }
+            "steps" => {
+                let start = self.current_span.start;
+                let mut replacement = Vec::new();
+                for (value, span) in self.expect_list()? {
+                    let text = match value {
+                        Value::String(s) => Text::Plain(s),
+                        Value::Localized(l) => Text::Localized(l),
+                        other => return Err(type_mismatch("String", &other, span)),
+                    };
+                    self.warn_duplicate_step(quest, &text, span);
+                    replacement.push(format!("step: {text}"));
+                    quest.steps.push(Step::new(text));
+                    quest.source_map.steps.push(span);
+                }
+                if !replacement.is_empty() {
+                    self.warn_deprecated(
+                        "A `steps: [...]` list",
+                        GrammarVersion::V2,
+                        replacement.join(", "),
+                        Span::new(key_span.start, self.prev_end),
+                    );
+                }
+                self.check_steps(quest, Span::new(start, self.prev_end))?;
+            }
+            "id" => quest.id = Some(self.expect_string()?),
+            "requires" => {
+                let start = self.current_span.start;
+                quest.requires.push(self.expect_string()?);
+                let span = Span::new(start, self.prev_end);
+                quest.source_map.requires.push(span);
+            }
+            "tags" => {
+                for (value, span) in self.expect_list()? {
+                    match value {
+                        Value::String(tag) => quest.tags.push(tag),
+                        other => return Err(type_mismatch("String", &other, span)),
+                    }
+                }
+            }
+            "giver" => quest.giver = Some(self.rule("npc", Self::parse_npc_ref)?),
+            "turn_in" => quest.turn_in = Some(self.rule("npc", Self::parse_npc_ref)?),
+            "time_limit" => {
+                let start = self.current_span.start;
+                match self.parse_value()? {
+                    Value::Duration(duration) => quest.time_limit = Some(duration),
+                    other => {
+                        let span = Span::new(start, self.prev_end);
+                        return Err(type_mismatch("Duration", &other, span));
+                    }
+                }
+            }
+            "starts_at" | "ends_at" => {
+                let start = self.current_span.start;
+                let instant = match self.parse_value()? {
+                    Value::DateTime(instant) => instant,
+                    other => {
+                        let span = Span::new(start, self.prev_end);
+                        return Err(type_mismatch("DateTime", &other, span));
+                    }
+                };
+                if key == "starts_at" {
+                    quest.starts_at = Some(instant);
+                } else {
+                    quest.ends_at = Some(instant);
+                }
+                if let (Some(starts_at), Some(ends_at)) = (quest.starts_at, quest.ends_at) {
+                    if starts_at >= ends_at {
+                        return Err(ParseError::InvalidEventWindow {
+                            starts_at,
+                            ends_at,
+                            span: Span::new(start, self.prev_end),
+                        });
+                    }
+                }
+            }
+            "complete_when" => {
+                let start = self.current_span.start;
+                let expr = self.rule("expression", |p| p.parse_expr(0))?;
+                let span = Span::new(start, self.prev_end);
+                let condition =
+                    Condition::from_expr(expr).ok_or(ParseError::InvalidCondition { span })?;
+                quest.complete_when = Some(condition);
+            }
+            "level" => {
+                let start = self.current_span.start;
+                let literal = matches!(self.current_token, Token::Number(_));
+                match self.parse_value()? {
+                    Value::Range(min, max) => quest.level_range = Some((min, max)),
+                    Value::Number(level) => {
+                        if literal {
+                            self.warn_deprecated(
+                                "A single `level`",
+                                GrammarVersion::V2,
+                                format!("{level}..{level}"),
+                                Span::new(start, self.prev_end),
+                            );
+                        }
+                        quest.level_range = Some((level, level));
+                    }
+                    other => {
+                        let span = Span::new(start, self.prev_end);
+                        return Err(type_mismatch("Range", &other, span));
+                    }
+                }
+            }
+            _ if self.options.strict => {
+                return Err(ParseError::UnknownProperty {
+                    suggestion: suggest_property(&key).map(str::to_string),
+                    key,
+                    span: key_span,
+                })
+            }
+            _ => {
+                let suggestion = suggest_property(&key);
+                if suggestion.is_some() || !self.options.collect_extra {
+                    self.diagnostics.warn(Diagnostic::UnknownProperty {
+                        key: key.clone(),
+                        suggestion: suggestion.map(str::to_string),
+                        span: key_span,
+                    });
+                }
+                let expr = self.rule("expression", Self::parse_property_expr)?;
+                if self.options.collect_extra {
+                    match expr {
+                        Expr::Literal(value) => {
+                            quest.expressions.shift_remove(&key);
+                            quest.extra.insert(key, value);
+                        }
+                        expr => {
+                            quest.extra.shift_remove(&key);
+                            quest.expressions.insert(key, expr);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a property value that may be an expression, evaluating it right
+    /// away if it does not reference any game state.
+    fn parse_property_expr(&mut self) -> Result<Expr, ParseError> {
+        let start = self.current_span.start;
+        let expr = self.parse_expr(0)?;
+        if matches!(expr, Expr::Literal(_)) || !expr.is_constant() {
+            return Ok(expr);
+        }
+        expr.evaluate(&HashMap::new())
+            .map(Expr::Literal)
+            .map_err(|error| ParseError::InvalidExpression {
+                error,
+                span: Span::new(start, self.prev_end),
+            })
+    }
+
+    /// Parses an expression with operators binding tighter than `min_precedence`.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// EXPR    ::= OPERAND (BINARY_OP OPERAND)*
+    /// OPERAND ::= VALUE | IDENTIFIER | "(" EXPR ")"
+    /// BINARY_OP ::= "||" | "&&" | "==" | "!=" | "<" | "<=" | ">" | ">="
+    ///             | "+" | "-" | "*" | "/" | "%"
+    /// ```
+    ///
+    /// Operators are listed from loosest to tightest binding, with `<` and
+    /// its relatives sharing a level; all of them associate to the left.
+    fn parse_expr(&mut self, min_precedence: u8) -> Result<Expr, ParseError> {
+        let lhs = self.parse_operand()?;
+        self.parse_binary(lhs, min_precedence)
+    }
+
+    /// Every operator counts as a level of nesting, as chains like
+    /// `a + b + c` build trees as deep as they are long.
+    fn parse_binary(&mut self, mut lhs: Expr, min_precedence: u8) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        while let Some((op, glued)) = self.binary_op() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.enter()?;
+            self.advance()?;
+            let rhs = match glued {
+                Some(operand) => self.parse_binary(Expr::Literal(operand), op.precedence() + 1)?,
+                None => self.parse_expr(op.precedence() + 1)?,
+            };
+            lhs = Expr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    /// Returns the binary operator at the current token, if any.
+    ///
+    /// The lexer reads `level -5` as an identifier and a negative number, so a
+    /// negative literal in operator position is taken apart into `-` and its
+    /// magnitude, which is returned as the right operand's first token.
+    fn binary_op(&self) -> Option<(BinaryOp, Option<Value>)> {
+        if let Some(op) = BinaryOp::from_token(&self.current_token) {
+            return Some((op, None));
+        }
+        if !self.input[self.current_span.start..].starts_with('-') {
+            return None;
+        }
+        let magnitude = match self.current_token {
+            Token::Number(n) => Value::Number(n.checked_neg()?),
+            Token::Float(f) => Value::Float(-f),
+            _ => return None,
+        };
+        Some((BinaryOp::Sub, Some(magnitude)))
+    }
+
+    fn parse_operand(&mut self) -> Result<Expr, ParseError> {
+        match &self.current_token {
+            Token::LParen => {
+                self.enter()?;
+                self.advance()?;
+                let expr = self.parse_expr(0)?;
+                self.eat(Token::RParen)?;
+                self.depth -= 1;
+                Ok(expr)
+            }
+            Token::Identifier(name) => {
+                let expr = Expr::Identifier(nfc(name).into_owned());
+                self.advance()?;
+                Ok(expr)
+            }
+            _ => self.parse_value().map(Expr::Literal),
+        }
+    }
+
+    /// Parses a literal value.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// VALUE ::= INTEGER | RANGE | FLOAT | DURATION | DATETIME | BOOLEAN | STRING | RAW | LIST | LOCALIZED | VARIABLE
+    /// RANGE ::= INTEGER ".." INTEGER
+    /// LIST  ::= "[" (VALUE (SEP VALUE)* ","?)? "]"
+    /// LOCALIZED ::= "{" (IDENTIFIER ":" STRING (SEP IDENTIFIER ":" STRING)* ","?)? "}"
+    /// SEP   ::= "," | NEWLINE
+    /// DURATION ::= (INTEGER "d")? (INTEGER "h")? (INTEGER "m")? (INTEGER "s")?
+    /// VARIABLE ::= "$" IDENTIFIER
+    /// ```
+    ///
+    /// Items of lists and localized strings are separated by commas or line
+    /// breaks, and may be followed by a trailing comma. Both bounds of a range
+    /// are included, and the lower one may not exceed the upper. A `RAW`
+    /// block, ```` ```lua ... ``` ```` or `<<<END ... END`, ends at the first
+    /// fence as long as its opening one, or at the first line starting with
+    /// its heredoc delimiter, and its text is kept as written, braces and
+    /// quotes included.
+    fn parse_value(&mut self) -> Result<Value, ParseError> {
+        let value = match &self.current_token {
+            Token::LBracket => {
+                let items = self.expect_list()?;
+                return Ok(Value::List(
+                    items.into_iter().map(|(item, _)| item).collect(),
+                ));
+            }
+            Token::LBrace => return self.parse_localized().map(Value::Localized),
+            Token::Variable(name) => match self.variables.get(nfc(name).as_ref()) {
+                Some(value) => value.clone(),
+                None => {
+                    return Err(ParseError::UndefinedVariable {
+                        name: name.to_string(),
+                        span: self.current_span,
+                    })
+                }
+            },
+            Token::StringLiteral(s) => Value::String(s.to_string()),
+            Token::Raw { lang, body } => Value::Raw {
+                lang: lang.map(str::to_string),
+                body: body.to_string(),
+            },
+            Token::Number(n) => {
+                let (start, n) = (self.current_span.start, *n);
+                self.advance()?;
+                if self.current_token != Token::DotDot {
+                    return Ok(Value::Number(n));
+                }
+                self.advance()?;
+                return self.parse_range_end(start, n);
+            }
+            Token::Float(f) => Value::Float(*f),
+            Token::Duration(d) => Value::Duration(*d),
+            Token::DateTime(instant) => Value::DateTime(*instant),
+            Token::True => Value::Bool(true),
+            Token::False => Value::Bool(false),
+            _ => return Err(self.syntax_error("Value")),
+        };
+        self.advance()?;
+        Ok(value)
+    }
+
+    /// Parses the upper bound of a range starting at byte `start` once its
+    /// lower bound and `..` have been consumed.
+    fn parse_range_end(&mut self, start: usize, min: i64) -> Result<Value, ParseError> {
+        let max = self.expect_number()?;
+        if min > max {
+            return Err(ParseError::InvalidRange {
+                start: min,
+                end: max,
+                span: Span::new(start, self.prev_end),
+            });
+        }
+        Ok(Value::Range(min, max))
+    }
+
+    /// Parses a string given per language, keyed by language code.
+    fn parse_localized(&mut self) -> Result<LocalizedString, ParseError> {
+        let opened_at = self.open_brace()?;
+        let mut texts = IndexMap::default();
+        while self.current_token != Token::RBrace {
+            let lang = self.parse_key()?;
+            self.eat(Token::Colon)?;
+            texts.insert(lang, self.expect_string()?);
+            if !self.eat_separator()? {
+                break;
+            }
+        }
+        self.close_brace(opened_at)?;
+        Ok(LocalizedString(texts))
+    }
+
+    /// Parses a property written as `key { ... }`, once its key has been
+    /// consumed.
+    fn parse_block_property(
+        &mut self,
+        quest: &mut Quest,
+        key: String,
+        key_span: Span,
+    ) -> Result<(), ParseError> {
+        match key.as_str() {
+            "objective" => {
+                let objective = self.rule("objective", Self::parse_objective)?;
+                quest.objectives.push(objective);
+            }
+            "stages" => {
+                quest.stages = Some(self.rule("stages", |p| p.parse_stages(key_span.start))?);
+            }
+            "steps" => {
+                let mut steps = Vec::new();
+                let tree = self.rule("step group", |p| p.parse_step_group(&mut steps))?;
+                let span = Span::new(key_span.start, self.prev_end);
+                for (step, span) in steps {
+                    self.warn_duplicate_step(quest, &step.text, span);
+                    quest.steps.push(step);
+                    quest.source_map.steps.push(span);
+                }
+                self.check_steps(quest, span)?;
+                quest.step_tree = Some(StepTree::AllOf(tree));
+            }
+            "on_success" => {
+                let spans = &mut quest.source_map.success_unlocks;
+                quest.on_success = Some(self.rule("outcome", |p| p.parse_outcome(spans))?);
+            }
+            "on_failure" => {
+                let spans = &mut quest.source_map.failure_unlocks;
+                quest.on_failure = Some(self.rule("outcome", |p| p.parse_outcome(spans))?);
+            }
+            "on_start" => quest.on_start = self.rule("actions", Self::parse_actions)?,
+            "on_complete" => quest.on_complete = self.rule("actions", Self::parse_actions)?,
+            "reward" => {
+                self.require_version(GrammarVersion::V2, "A `reward { ... }` block", key_span)?;
+                quest.rewards = self.rule("reward block", Self::parse_rewards)?;
+            }
+            _ => {
+                return Err(ParseError::ValueExpected {
+                    key,
+                    span: key_span,
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a structured objective block.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// OBJECTIVE_BLOCK ::= "{" (OBJECTIVE_FIELD (SEP OBJECTIVE_FIELD)* ","?)? "}"
+    /// OBJECTIVE_FIELD ::= ("kind" | "target") ":" STRING | "count" ":" INTEGER
+    ///                   | KEY ":" VALUE
+    /// ```
+    ///
+    /// `kind` selects the [`Objective`] variant: `kill`, `collect`, `talk_to`
+    /// and `reach` are recognised, anything else becomes [`Objective::Custom`].
+    /// `count` defaults to 1 for `kill` and `collect`. Each field can be set
+    /// once; any other key is skipped with a warning, or rejected in strict
+    /// mode.
+    fn parse_objective(&mut self) -> Result<Objective, ParseError> {
+        let opened_at = self.open_brace()?;
+
+        let mut kind = None;
+        let mut target = None;
+        let mut count = None;
+
+        let mut seen = Vec::new();
+        while self.current_token != Token::RBrace && self.current_token != Token::Eof {
+            let span = self.current_span;
+            let field = self.parse_key()?;
+            if seen.contains(&field) {
+                return Err(ParseError::DuplicateProperty { key: field, span });
+            }
+            self.eat(Token::Colon)?;
+            match field.as_str() {
+                "kind" => kind = Some(self.expect_string()?),
+                "target" => target = Some(self.expect_string()?),
+                "count" => count = Some(self.expect_number()?),
+                _ => {
+                    let suggestion = closest_match(&field, OBJECTIVE_FIELDS.iter().copied());
+                    let suggestion = suggestion.map(str::to_string);
+                    if self.options.strict {
+                        return Err(ParseError::UnknownProperty {
+                            key: field,
+                            suggestion,
+                            span,
+                        });
+                    }
+                    self.diagnostics.warn(Diagnostic::UnknownProperty {
+                        key: field.clone(),
+                        suggestion,
+                        span,
+                    });
+                    self.parse_value()?;
+                }
+            }
+            seen.push(field);
+            if !self.eat_separator()? {
+                break;
+            }
+        }
+        self.close_brace(opened_at)?;
+
+        let kind = kind.ok_or_else(|| missing_field("objective", "kind"))?;
+        Objective::from_fields(kind, target, count)
+    }
+
+    /// Parses the block of an `on_success` or `on_failure` outcome.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// OUTCOME_BLOCK ::= "{" (KEY ":" VALUE ","?)* "}"
+    /// ```
+    ///
+    /// `reward` and `penalty` expect integers and `unlock` the name of a
+    /// quest (can be repeated); any other key is kept in [`Outcome::extra`].
+    /// The span of every `unlock` is added to `unlock_spans`, replacing those
+    /// of an earlier block.
+    fn parse_outcome(&mut self, unlock_spans: &mut Vec<Span>) -> Result<Outcome, ParseError> {
+        unlock_spans.clear();
+        let opened_at = self.open_brace()?;
+        let mut outcome = Outcome::default();
+        while self.current_token != Token::RBrace && self.current_token != Token::Eof {
+            let key = self.parse_key()?;
+            self.eat(Token::Colon)?;
+            match key.as_str() {
+                "reward" => outcome.reward = self.expect_number()?,
+                "penalty" => outcome.penalty = self.expect_number()?,
+                "unlock" => {
+                    let start = self.current_span.start;
+                    outcome.unlocks.push(self.expect_string()?);
+                    unlock_spans.push(Span::new(start, self.prev_end));
+                }
+                _ => {
+                    let value = self.parse_value()?;
+                    outcome.extra.insert(key, value);
+                }
+            }
+            if self.current_token == Token::Comma {
+                self.eat(Token::Comma)?;
+            }
+        }
+        self.close_brace(opened_at)?;
+        Ok(outcome)
+    }
+
+    /// Parses the block of an `on_start` or `on_complete` property.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// ACTION_BLOCK ::= "{" (KEY ":" VALUE ","?)* "}"
+    /// ```
+    ///
+    /// Every key is an [`Action`], kept in source order and repeatable. A list
+    /// value is taken apart into the action's arguments.
+    fn parse_actions(&mut self) -> Result<Vec<Action>, ParseError> {
+        let opened_at = self.open_brace()?;
+        let mut actions = Vec::new();
+        while self.current_token != Token::RBrace && self.current_token != Token::Eof {
+            let name = self.parse_key()?;
+            self.eat(Token::Colon)?;
+            let args = match self.parse_value()? {
+                Value::List(args) => args,
+                arg => vec![arg],
+            };
+            actions.push(Action { name, args });
+            if self.current_token == Token::Comma {
+                self.eat(Token::Comma)?;
+            }
+        }
+        self.close_brace(opened_at)?;
+        Ok(actions)
+    }
+
+    /// Parses a step written once and added several times, numbered from 1.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// REPEAT ::= "repeat" "(" INTEGER ")" "step" ":" VALUE STEP_ATTRS?
+    /// ```
+    ///
+    /// Every copy has `{n}` in its text replaced by its number, so
+    /// `repeat(3) step: "Defeat wave {n}"` adds the steps `Defeat wave 1`
+    /// to `Defeat wave 3`. Other placeholders are kept for
+    /// [`Quest::interpolate`].
+    fn parse_repeat(&mut self, quest: &mut Quest) -> Result<(), ParseError> {
+        self.eat(Token::LParen)?;
+        let count_span = self.current_span;
+        let count = self.expect_number()?;
+        self.eat(Token::RParen)?;
+        if self.current_token != Token::Identifier("step") {
+            return Err(self.syntax_error("step"));
+        }
+        let start = self.current_span.start;
+        let mut once = Quest::default();
+        self.parse_property(&mut once)?;
+        let step = once.steps.pop().expect("a step property adds a step");
+        let step_span = once.source_map.steps.pop();
+
+        let count = usize::try_from(count).map_err(|_| ParseError::InvalidRepeatCount {
+            count,
+            span: count_span,
+        })?;
+        let limit = self.options.limits.max_repeat;
+        if count > limit {
+            let span = count_span;
+            return Err(ParseError::TooManyRepeats { limit, span });
+        }
+        let limit = self.options.limits.max_steps;
+        if quest.steps.len().saturating_add(count) > limit {
+            let span = Span::new(start, self.prev_end);
+            return Err(ParseError::TooManySteps { limit, span });
+        }
+        for n in 1..=count {
+            let vars = HashMap::from([("n".to_string(), n.to_string())]);
+            quest.steps.push(step.interpolate(&vars));
+            quest.source_map.steps.extend(step_span);
+        }
+        Ok(())
+    }
+
+    /// Parses a reference to an NPC and, optionally, where they are.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// NPC_REF ::= "npc" "(" STRING ")" ("at" STRING)?
+    /// ```
+    fn parse_npc_ref(&mut self) -> Result<NpcRef, ParseError> {
+        if self.current_token != Token::Identifier("npc") {
+            return Err(self.syntax_error("npc(...)"));
+        }
+        self.advance()?;
+        self.eat(Token::LParen)?;
+        let mut npc = NpcRef::new(self.expect_string()?);
+        self.eat(Token::RParen)?;
+        if self.current_token == Token::Identifier("at") {
+            self.advance()?;
+            npc.location = Some(self.expect_string()?);
+        }
+        Ok(npc)
+    }
+
+    /// Parses the attributes following a step's text.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// STEP_ATTRS ::= "[" (STEP_ATTR (SEP STEP_ATTR)* ","?)? "]"
+    /// STEP_ATTR  ::= "optional" | "hidden" | "order" ":" INTEGER
+    /// ```
+    fn parse_step_attributes(&mut self, step: &mut Step) -> Result<(), ParseError> {
+        self.eat(Token::LBracket)?;
+        let mut seen = Vec::new();
+        while self.current_token != Token::RBracket {
+            let span = self.current_span;
+            let name = self.parse_key()?;
+            if seen.contains(&name) {
+                return Err(ParseError::DuplicateProperty { key: name, span });
+            }
+            match name.as_str() {
+                "optional" => step.optional = true,
+                "hidden" => step.hidden = true,
+                "order" => {
+                    self.eat(Token::Colon)?;
+                    step.order = Some(self.expect_number()?);
+                }
+                _ => return Err(ParseError::UnknownAttribute { name, span }),
+            }
+            seen.push(name);
+            if !self.eat_separator()? {
+                break;
+            }
+        }
+        self.eat(Token::RBracket)
+    }
+
+    /// Parses a block of named reward amounts.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// REWARD_BLOCK ::= "{" (KEY ":" INTEGER (SEP KEY ":" INTEGER)* ","?)? "}"
+    /// ```
+    fn parse_rewards(&mut self) -> Result<Vec<(Currency, i64)>, ParseError> {
+        let opened_at = self.open_brace()?;
+        let mut rewards = Vec::new();
+        while self.current_token != Token::RBrace {
+            let key_span = self.current_span;
+            let currency = Currency::from_name(&self.parse_key()?);
+            self.eat(Token::Colon)?;
+            let amount = self.expect_number()?;
+            push_amount(&mut rewards, currency, amount, key_span)?;
+            if !self.eat_separator()? {
+                break;
+            }
+        }
+        self.close_brace(opened_at)?;
+        Ok(rewards)
+    }
+
+    /// Parses a named reward tier after its `reward` key, as in
+    /// `reward tier "gold" { gold: 500, gems: 10 }`.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// REWARD_TIER ::= "reward" "tier" (IDENTIFIER | STRING) REWARD_BLOCK
+    /// ```
+    ///
+    /// Fails with [`ParseError::DuplicateTier`] when the quest already has a
+    /// tier of that name and with [`ParseError::EmptyTier`] when the block
+    /// holds no amounts.
+    fn parse_reward_tier(&mut self, quest: &mut Quest) -> Result<(), ParseError> {
+        self.advance()?;
+        let name_span = self.current_span;
+        let name = match &self.current_token {
+            Token::Identifier(name) => nfc(name).into_owned(),
+            Token::StringLiteral(name) => name.to_string(),
+            _ => return Err(self.syntax_error("Identifier or String")),
+        };
+        self.advance()?;
+        if quest.reward_tiers.iter().any(|tier| tier.name == name) {
+            return Err(ParseError::DuplicateTier {
+                name,
+                span: name_span,
+            });
+        }
+        let rewards = self.rule("reward block", Self::parse_rewards)?;
+        if rewards.is_empty() {
+            return Err(ParseError::EmptyTier {
+                name,
+                span: Span::new(name_span.start, self.prev_end),
+            });
+        }
+        quest.reward_tiers.push(RewardTier { name, rewards });
+        Ok(())
+    }
+
+    /// Parses the rest of a list of currency amounts whose first amount,
+    /// starting at `start`, was already read.
+    ///
+    /// A comma followed by anything but a number ends the list and is left
+    /// consumed, as it separates the list from the next property.
+    fn parse_amounts(
+        &mut self,
+        first: i64,
+        start: usize,
+    ) -> Result<Vec<(Currency, i64)>, ParseError> {
+        let mut rewards = Vec::new();
+        let mut amount = first;
+        let mut span_start = start;
+        loop {
+            let currency = Currency::from_name(&self.parse_key()?);
+            let span = Span::new(span_start, self.prev_end);
+            push_amount(&mut rewards, currency, amount, span)?;
+            if self.current_token != Token::Comma {
+                return Ok(rewards);
+            }
+            self.advance()?;
+            if !matches!(self.current_token, Token::Number(_)) {
+                return Ok(rewards);
+            }
+            span_start = self.current_span.start;
+            amount = self.expect_number()?;
+        }
+    }
+
+    /// Parses the items of a `steps` block or of a group inside it, adding
+    /// each step with its span to `steps` as well.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// STEP_GROUP ::= "{" (STEP_ITEM SEP?)* "}"
+    /// STEP_ITEM  ::= (STRING | LOCALIZED) STEP_ATTRS? | ("ordered" | "any_of" | "all_of") STEP_GROUP
+    /// ```
+    ///
+    /// `ordered` items are done one after the other, one `any_of` item is
+    /// enough and `all_of` items are done in any order; see [`StepTree`].
+    fn parse_step_group(
+        &mut self,
+        steps: &mut Vec<(Step, Span)>,
+    ) -> Result<Vec<StepTree>, ParseError> {
+        self.enter()?;
+        let opened_at = self.open_brace()?;
+        let mut items = Vec::new();
+        while self.current_token != Token::RBrace && self.current_token != Token::Eof {
+            let group: Option<fn(Vec<StepTree>) -> StepTree> = match self.current_token {
+                Token::Identifier("ordered") => Some(StepTree::Sequence),
+                Token::Identifier("any_of") => Some(StepTree::AnyOf),
+                Token::Identifier("all_of") => Some(StepTree::AllOf),
+                _ => None,
+            };
+            if let Some(group) = group {
+                self.advance()?;
+                items.push(group(self.parse_step_group(steps)?));
+            } else {
+                let start = self.current_span.start;
+                let mut step = match self.parse_value()? {
+                    Value::String(s) => Step::new(s),
+                    Value::Localized(l) => Step::new(Text::Localized(l)),
+                    other => {
+                        let span = Span::new(start, self.prev_end);
+                        return Err(type_mismatch("String", &other, span));
+                    }
+                };
+                if self.current_token == Token::LBracket {
+                    self.rule("step attributes", |p| p.parse_step_attributes(&mut step))?;
+                }
+                steps.push((step.clone(), Span::new(start, self.prev_end)));
+                items.push(StepTree::Step(step));
+            }
+            self.eat_separator()?;
+        }
+        self.close_brace(opened_at)?;
+        self.depth -= 1;
+        Ok(items)
+    }
+
+    /// Parses a stage flow block.
+    ///
+    /// # Grammar Rule
+    /// ```ebnf
+    /// STAGES_BLOCK ::= "{" (TRANSITION (SEP TRANSITION)* ","?)? "}"
+    /// TRANSITION   ::= STAGE "->" STAGE
+    /// STAGE        ::= "start" | "end" | IDENTIFIER | STRING
+    /// ```
+    ///
+    /// The transitions are checked as a whole once the block is closed; see
+    /// [`StateMachine`] for the rules.
+    fn parse_stages(&mut self, start: usize) -> Result<StateMachine, ParseError> {
+        let opened_at = self.open_brace()?;
+        let mut transitions = Vec::new();
+        while self.current_token != Token::RBrace {
+            let from = self.parse_stage()?;
+            self.eat(Token::Arrow)?;
+            let to = self.parse_stage()?;
+            transitions.push(Transition { from, to });
+            if !self.eat_separator()? {
+                break;
+            }
+        }
+        self.close_brace(opened_at)?;
+        StateMachine::new(transitions).map_err(|error| ParseError::InvalidStages {
+            error,
+            span: Span::new(start, self.prev_end),
+        })
+    }
+
+    fn parse_stage(&mut self) -> Result<Stage, ParseError> {
+        let stage = match &self.current_token {
+            Token::Identifier("start") => Stage::Start,
+            Token::Identifier("end") => Stage::End,
+            Token::Identifier(name) => Stage::Named(nfc(name).into_owned()),
+            Token::StringLiteral(name) => Stage::Named(name.to_string()),
+            _ => return Err(self.syntax_error("Stage")),
+        };
+        self.advance()?;
+        Ok(stage)
+    }
+
+    /// Parses a list, returning each item with its span. The items of a
+    /// list held in a variable all get the span of the variable.
+    fn expect_list(&mut self) -> Result<Vec<(Value, Span)>, ParseError> {
+        if let Token::Variable(_) = self.current_token {
+            let span = self.current_span;
+            return match self.parse_value()? {
+                Value::List(items) => Ok(items.into_iter().map(|item| (item, span)).collect()),
+                other => Err(type_mismatch("List", &other, span)),
+            };
+        }
+        self.enter()?;
+        self.eat(Token::LBracket)?;
+        let mut items = Vec::new();
+        while self.current_token != Token::RBracket {
+            let start = self.current_span.start;
+            let value = self.parse_value()?;
+            items.push((value, Span::new(start, self.prev_end)));
+            if !self.eat_separator()? {
+                break;
+            }
+        }
+        self.eat(Token::RBracket)?;
+        self.depth -= 1;
+        Ok(items)
+    }
+
+    fn parse_key(&mut self) -> Result<String, ParseError> {
+        let key = match &self.current_token {
+            Token::Identifier(k) => nfc(k).into_owned(),
+            _ => return Err(self.syntax_error("Property Key")),
+        };
+        self.advance()?;
+        Ok(key)
+    }
+
+    fn expect_string(&mut self) -> Result<String, ParseError> {
+        let start = self.current_span.start;
+        match self.parse_value()? {
+            Value::String(s) => Ok(s),
+            other => Err(type_mismatch(
+                "String",
+                &other,
+                Span::new(start, self.prev_end),
+            )),
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<i64, ParseError> {
+        let start = self.current_span.start;
+        match self.parse_value()? {
+            Value::Number(n) => Ok(n),
+            other => Err(type_mismatch(
+                "Number",
+                &other,
+                Span::new(start, self.prev_end),
+            )),
+        }
+    }
+}
+
+/// Adds an amount to `rewards`, rejecting a currency that already has one.
+fn push_amount(
+    rewards: &mut Vec<(Currency, i64)>,
+    currency: Currency,
+    amount: i64,
+    span: Span,
+) -> Result<(), ParseError> {
+    if rewards.iter().any(|(c, _)| *c == currency) {
+        return Err(ParseError::DuplicateProperty {
+            key: currency.to_string(),
+            span,
+        });
+    }
+    rewards.push((currency, amount));
+    Ok(())
+}
+
+fn type_mismatch(expected: &str, found: &Value, span: Span) -> ParseError {
+    ParseError::SyntaxError {
+        expected: expected.to_string(),
+        found: format!("{:?}", found),
+        span,
+    }
+}
+
+fn missing_field(block: &str, field: &str) -> ParseError {
+    ParseError::MissingField {
+        block: block.to_string(),
+        field: field.to_string(),
+    }
 }
@@ -0,0 +1,248 @@
+//! Style checks for parsed quests, configurable per project.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::{Quest, Text};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum LintConfigError {
+    #[error("Invalid lint config: {0}")]
+    Toml(String),
+    #[error("Unknown lint rule `{0}`")]
+    UnknownRule(String),
+    #[error("Invalid value for `{key}`: {message}")]
+    InvalidValue { key: String, message: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// A check [`lint`] can run, identified by its ID in config files and output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LintRule {
+    pub id: &'static str,
+    pub description: &'static str,
+    /// Severity used unless the config overrides it.
+    pub severity: Severity,
+}
+
+/// Every rule [`lint`] knows.
+pub const LINT_RULES: &[LintRule] = &[
+    LintRule {
+        id: "title-case",
+        description: "Quest names should be Title Case, e.g. `Return of the King`.",
+        severity: Severity::Warning,
+    },
+    LintRule {
+        id: "zero-reward",
+        description: "A reward of 0 is usually a mistake.",
+        severity: Severity::Warning,
+    },
+    LintRule {
+        id: "too-many-steps",
+        description: "Quests should not have more than `max-steps` steps (20 by default).",
+        severity: Severity::Warning,
+    },
+    LintRule {
+        id: "step-period",
+        description: "Step text should not end with a period.",
+        severity: Severity::Warning,
+    },
+];
+
+/// Which rules run and how severe their findings are, usually read from a
+/// `.questlint.toml` file:
+///
+/// ```toml
+/// max-steps = 30
+///
+/// [rules]
+/// title-case = "off"
+/// zero-reward = "error"
+/// ```
+///
+/// Every rule is set to `"off"`, `"warning"` or `"error"`; rules that are
+/// not mentioned keep their default severity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintConfig {
+    /// Severity per rule ID, `None` for rules that are turned off.
+    severities: HashMap<&'static str, Option<Severity>>,
+    /// Most steps a quest may have before `too-many-steps` fires.
+    pub max_steps: usize,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            severities: LINT_RULES
+                .iter()
+                .map(|rule| (rule.id, Some(rule.severity)))
+                .collect(),
+            max_steps: 20,
+        }
+    }
+}
+
+impl LintConfig {
+    /// Reads a config in the `.questlint.toml` format, starting from the
+    /// defaults.
+    pub fn from_toml(input: &str) -> Result<LintConfig, LintConfigError> {
+        let table: toml::Table = input
+            .parse()
+            .map_err(|e: toml::de::Error| LintConfigError::Toml(e.to_string()))?;
+        Self::from_table(table)
+    }
+
+    /// Reads the settings of an already parsed config, such as the `[lint]`
+    /// table of a [`ProjectConfig`](crate::ProjectConfig).
+    pub(crate) fn from_table(table: toml::Table) -> Result<LintConfig, LintConfigError> {
+        let mut config = LintConfig::default();
+        for (key, value) in table {
+            match (key.as_str(), value) {
+                ("max-steps", toml::Value::Integer(n)) => {
+                    config.max_steps =
+                        usize::try_from(n).map_err(|_| invalid(&key, "must not be negative"))?;
+                }
+                ("rules", toml::Value::Table(rules)) => {
+                    for (id, value) in rules {
+                        let severity = match value.as_str() {
+                            Some("off") => None,
+                            Some("warning") => Some(Severity::Warning),
+                            Some("error") => Some(Severity::Error),
+                            _ => {
+                                return Err(invalid(
+                                    &id,
+                                    "expected \"off\", \"warning\" or \"error\"",
+                                ))
+                            }
+                        };
+                        config.set(&id, severity)?;
+                    }
+                }
+                ("max-steps", _) => return Err(invalid(&key, "expected an integer")),
+                ("rules", _) => return Err(invalid(&key, "expected a table")),
+                _ => return Err(invalid(&key, "unknown setting")),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Sets the severity of a rule, or turns it off with `None`.
+    pub fn set(&mut self, rule: &str, severity: Option<Severity>) -> Result<(), LintConfigError> {
+        let rule = LINT_RULES
+            .iter()
+            .find(|r| r.id == rule)
+            .ok_or_else(|| LintConfigError::UnknownRule(rule.to_string()))?;
+        self.severities.insert(rule.id, severity);
+        Ok(())
+    }
+
+    /// The severity of a rule, or `None` if it is turned off or unknown.
+    pub fn severity(&self, rule: &str) -> Option<Severity> {
+        self.severities.get(rule).copied().flatten()
+    }
+}
+
+fn invalid(key: &str, message: &str) -> LintConfigError {
+    LintConfigError::InvalidValue {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// A finding of one rule on one quest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub quest: String,
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] quest {:?}: {}",
+            self.severity, self.rule, self.quest, self.message
+        )
+    }
+}
+
+/// Runs every enabled rule on `quests`, returning the findings in quest
+/// order.
+pub fn lint(quests: &[Quest], config: &LintConfig) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    for quest in quests {
+        let mut report = |rule: &'static str, message: String| {
+            if let Some(severity) = config.severity(rule) {
+                lints.push(Lint {
+                    rule,
+                    severity,
+                    quest: quest.name.clone(),
+                    message,
+                });
+            }
+        };
+
+        if !is_title_case(&quest.name) {
+            report("title-case", "name is not Title Case".to_string());
+        }
+        if quest.reward == 0
+            && quest.rewards.is_empty()
+            && quest.reward_tiers.is_empty()
+            && !quest.expressions.contains_key("reward")
+        {
+            report("zero-reward", "reward of 0 is suspicious".to_string());
+        }
+        if quest.steps.len() > config.max_steps {
+            report(
+                "too-many-steps",
+                format!(
+                    "{} steps, more than the limit of {}",
+                    quest.steps.len(),
+                    config.max_steps
+                ),
+            );
+        }
+        for (i, step) in quest.step_texts().enumerate() {
+            let texts: Vec<&str> = match step {
+                Text::Plain(s) => vec![s],
+                Text::Localized(localized) => localized.0.values().map(String::as_str).collect(),
+            };
+            if texts.iter().any(|text| text.trim_end().ends_with('.')) {
+                report("step-period", format!("step {} ends with a period", i + 1));
+            }
+        }
+    }
+    lints
+}
+
+/// Short words that stay lowercase inside a title.
+const MINOR_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "by", "for", "from", "in", "of", "on", "or", "the", "to", "with",
+];
+
+fn is_title_case(name: &str) -> bool {
+    name.split_whitespace().enumerate().all(|(i, word)| {
+        let Some(first) = word.chars().find(|c| c.is_alphabetic()) else {
+            return true;
+        };
+        first.is_uppercase() || (i > 0 && MINOR_WORDS.contains(&word))
+    })
+}
@@ -0,0 +1,114 @@
+use crate::prelude::*;
+use crate::{IndexMap, Quest, StepTree, Value};
+
+/// Language key of a localized string, such as `en` or `uk`.
+pub type LangCode = String;
+
+/// A string given in several languages: `{ en: "Find the sword", uk: "Знайди меч" }`.
+/// The languages keep the order they were written in.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalizedString(pub IndexMap<LangCode, String>);
+
+impl LocalizedString {
+    pub fn get(&self, lang: &str) -> Option<&str> {
+        self.0.get(lang).map(String::as_str)
+    }
+
+    /// The text in `lang`, or in `default` when there is no translation.
+    pub fn resolve(&self, lang: &str, default: &str) -> Option<&str> {
+        self.get(lang).or_else(|| self.get(default))
+    }
+}
+
+/// Player-facing text: either a plain string or one per language.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum Text {
+    Plain(String),
+    Localized(LocalizedString),
+}
+
+impl Text {
+    /// The text in `lang`, falling back to `default`. Plain text is the same
+    /// in every language.
+    pub fn resolve(&self, lang: &str, default: &str) -> Option<&str> {
+        match self {
+            Text::Plain(s) => Some(s),
+            Text::Localized(localized) => localized.resolve(lang, default),
+        }
+    }
+
+    fn localize(&self, lang: &str, default: &str) -> Text {
+        match self.resolve(lang, default) {
+            Some(s) => Text::Plain(s.to_string()),
+            None => self.clone(),
+        }
+    }
+}
+
+impl From<String> for Text {
+    fn from(s: String) -> Self {
+        Text::Plain(s)
+    }
+}
+
+impl From<&str> for Text {
+    fn from(s: &str) -> Self {
+        Text::Plain(s.to_string())
+    }
+}
+
+impl PartialEq<&str> for Text {
+    fn eq(&self, other: &&str) -> bool {
+        matches!(self, Text::Plain(s) if s == other)
+    }
+}
+
+impl Quest {
+    /// Returns a copy of the quest with every localized string, in steps,
+    /// step groups and extra properties alike, replaced by its text in `lang`, or in
+    /// `default` when it has no translation for `lang`. Strings translated
+    /// into neither language are left as they are.
+    pub fn localize(&self, lang: &str, default: &str) -> Quest {
+        let mut quest = self.clone();
+        for step in &mut quest.steps {
+            step.text = step.text.localize(lang, default);
+        }
+        if let Some(tree) = &mut quest.step_tree {
+            localize_tree(tree, lang, default);
+        }
+        for value in quest.extra.values_mut() {
+            localize_value(value, lang, default);
+        }
+        quest
+    }
+}
+
+fn localize_tree(tree: &mut StepTree, lang: &str, default: &str) {
+    match tree {
+        StepTree::Step(step) => step.text = step.text.localize(lang, default),
+        StepTree::Sequence(items) | StepTree::AnyOf(items) | StepTree::AllOf(items) => {
+            for item in items {
+                localize_tree(item, lang, default);
+            }
+        }
+    }
+}
+
+fn localize_value(value: &mut Value, lang: &str, default: &str) {
+    match value {
+        Value::Localized(localized) => {
+            if let Some(s) = localized.resolve(lang, default) {
+                *value = Value::String(s.to_string());
+            }
+        }
+        Value::List(items) => {
+            for item in items {
+                localize_value(item, lang, default);
+            }
+        }
+        _ => {}
+    }
+}
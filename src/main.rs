@@ -1,8 +1,21 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Parser as ClapParser, Subcommand};
-use game_quest_parser_Hodik::Parser;
+use game_quest_parser_Hodik::sim::{Choices, Ending};
+use game_quest_parser_Hodik::{
+    ast, balance, decode_source, error_info, fix_deprecated, format_source, grammar, line_col,
+    lint, parse_expr, quest_diff, rename_quest, render_error, render_warning, renumber_steps,
+    BalanceConfig, BalanceEntry, BalanceSort, Diagnostic, Diagnostics, Lexer, LintConfig,
+    ParseError, Parser, ParserOptions, ProjectConfig, Quest, QuestGraph, QuestStats, Severity,
+    Span, Stage, ERROR_CODES, PROJECT_CONFIG_FILE,
+};
+use notify::event::{MetadataKind, ModifyKind};
+use notify::{EventKind, RecursiveMode, Watcher};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc;
+use std::time::Duration;
 
 #[derive(ClapParser)]
 #[command(name = "game_quest_parser")]
@@ -15,37 +28,1998 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     Parse {
+        #[arg(short, long, required_unless_present = "dir")]
+        file: Option<PathBuf>,
+        /// Parse every matching file below this directory and print a summary
+        #[arg(short, long, conflicts_with = "file")]
+        dir: Option<PathBuf>,
+        /// Pattern selecting files in --dir, relative to the directory
+        #[arg(long, requires = "dir", default_value = "**/*.quest")]
+        glob: String,
+        /// Number of threads parsing --dir files (default: one per core)
+        #[arg(short, long, requires = "dir")]
+        jobs: Option<usize>,
+        /// Reject properties the parser does not know
+        #[arg(long)]
+        strict: bool,
+        /// Accept unknown properties even if quest-parser.toml sets `strict`
+        #[arg(long, overrides_with = "strict")]
+        no_strict: bool,
+        /// Print every grammar rule the parser enters and leaves to stderr
+        #[arg(long)]
+        trace: bool,
+        /// How to print the parsed --file; for --dir only `json` changes the
+        /// output [default: debug, or json if quest-parser.toml sets it]
+        #[arg(long, value_enum)]
+        format: Option<OutputFormat>,
+        /// Parse again whenever the file, or a file below --dir, changes
+        #[arg(long)]
+        watch: bool,
+        /// Enable a feature flag for `#[if(feature = "...")]` attributes; can
+        /// be repeated
+        #[arg(long = "flag", value_name = "NAME")]
+        flags: Vec<String>,
+    },
+    /// Lays out a quest file in the standard style
+    Fmt {
+        #[arg(short, long)]
+        file: PathBuf,
+        /// Exit with an error instead of rewriting if the file is not formatted
+        #[arg(long)]
+        check: bool,
+        /// Migrate deprecated syntax to its newer form first; files the
+        /// formatter refuses are still migrated, without formatting
+        #[arg(long)]
+        fix: bool,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Checks quests against style rules
+    Lint {
+        #[arg(short, long)]
+        file: PathBuf,
+        /// Rule config to use instead of the nearest .questlint.toml
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Lint again whenever the file changes
+        #[arg(long)]
+        watch: bool,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Prints the tokens of a quest file with their spans, one per line
+    Tokenize {
+        #[arg(short, long)]
+        file: PathBuf,
+        /// Print a JSON array of tokens instead
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+        /// Same as `--format json`
+        #[arg(long)]
+        json: bool,
+    },
+    /// Reads quest snippets from stdin and prints what they parse to
+    ///
+    /// A snippet ends at a blank line or a line holding `;;`.
+    Repl,
+    /// Lists what changed between two versions of a quest file
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Converts quests between the DSL and TOML, YAML or JSON
+    #[cfg(feature = "convert")]
+    Convert {
+        #[arg(short, long)]
+        file: PathBuf,
+        /// Write the result here instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Format of --file (default: guessed from its extension)
+        #[arg(long, value_enum)]
+        from: Option<FormatArg>,
+        /// Format to convert to (default: guessed from --output's extension)
+        #[arg(long, value_enum)]
+        to: Option<FormatArg>,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Turns quest dumps of other tools into quest DSL
+    #[cfg(feature = "convert")]
+    Import {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[arg(long, value_enum)]
+        from: ImportFormat,
+        /// Write the quests of all files here instead of printing them
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Prints a JSON manifest of every quest below a directory with its
+    /// file and content hash
+    Manifest {
+        dir: PathBuf,
+        /// Pattern selecting files in the directory, relative to it
+        #[arg(long, default_value = "**/*.quest")]
+        glob: String,
+        /// Write the manifest here instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Prints aggregate numbers over every quest below a directory
+    Stats {
+        dir: PathBuf,
+        /// Pattern selecting files in the directory, relative to it
+        #[arg(long, default_value = "**/*.quest")]
+        glob: String,
+        /// Number of tags to list, most common first
+        #[arg(long, default_value_t = 5)]
+        top_tags: usize,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Compares the rewards of every quest below a directory with the
+    /// expected reward-per-level curves and reports the outliers
+    Balance {
+        dir: PathBuf,
+        /// Pattern selecting files in the directory, relative to it
+        #[arg(long, default_value = "**/*.quest")]
+        glob: String,
+        /// Curve config to use instead of the [balance] table of
+        /// quest-parser.toml
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// How to order the report
+        #[arg(long, value_enum, default_value_t = BalanceOrder::Deviation)]
+        sort: BalanceOrder,
+        /// List every checked quest, not only the outliers
+        #[arg(long)]
+        all: bool,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Lists the quests below a directory that match an expression
+    Find {
+        #[arg(short, long)]
+        dir: PathBuf,
+        /// Pattern selecting files in the directory, relative to it
+        #[arg(long, default_value = "**/*.quest")]
+        glob: String,
+        /// Expression a quest must make true, such as `reward > 100 && active`
+        #[arg(long = "where", value_name = "EXPR")]
+        filter: String,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Rewrites quest files in place, keeping their comments and layout
+    #[command(group = clap::ArgGroup::new("operation").required(true).multiple(true))]
+    Refactor {
+        #[arg(short, long, required_unless_present = "dir")]
+        file: Option<PathBuf>,
+        /// Rewrite every matching file below this directory
+        #[arg(short, long, conflicts_with = "file")]
+        dir: Option<PathBuf>,
+        /// Pattern selecting files in --dir, relative to the directory
+        #[arg(long, requires = "dir", default_value = "**/*.quest")]
+        glob: String,
+        /// Number the `order` attributes of each quest's steps 1, 2, 3, ...
+        /// in the order they already list the steps in
+        #[arg(long, group = "operation")]
+        renumber_steps: bool,
+        /// Rename a quest and every `requires:` naming it
+        #[arg(long, group = "operation", num_args = 2, value_names = ["OLD", "NEW"])]
+        rename_quest: Option<Vec<String>>,
+        /// Exit with an error instead of rewriting if a file would change
+        #[arg(long)]
+        check: bool,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Parses quest files into one binary pack for `load_compiled`
+    #[cfg(feature = "compile")]
+    Compile {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Renders quest files in another format, such as a Graphviz graph of how
+    /// their quests require and unlock each other
+    Export {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+        /// Write the result here instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Walks the stages of a file's quests and reports where they can end,
+    /// which stages the walks missed and what the quests reward
+    Simulate {
         #[arg(short, long)]
         file: PathBuf,
+        /// Only simulate the quest with this name
+        #[arg(short, long)]
+        quest: Option<String>,
+        /// Stage to go to at the next branch, for a single scripted walk; can
+        /// be repeated
+        #[arg(long = "choose", value_name = "STAGE", conflicts_with_all = ["seed", "runs"])]
+        choices: Vec<String>,
+        /// Seed for random walks
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Number of random walks per quest
+        #[arg(long, default_value_t = 100)]
+        runs: usize,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Explains an error code, such as E0103, or lists every code
+    Explain {
+        code: Option<String>,
+        #[arg(long, value_enum)]
+        format: Option<ReportFormat>,
+    },
+    /// Prints the grammar the parser implements
+    Grammar {
+        #[arg(long, value_enum, default_value_t = GrammarFormat::Ebnf)]
+        format: GrammarFormat,
     },
     Credits,
 }
 
-fn main() -> Result<()> {
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum OutputFormat {
+    /// The parsed Rust structures
+    Debug,
+    /// A readable report of every quest in the file
+    Summary,
+    /// The parsed quests as JSON
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+/// How the commands that report results print them. Defaults to `text`, or
+/// to `json` when quest-parser.toml sets `format = "json"`.
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum ReportFormat {
+    /// Plain text for people to read
+    Text,
+    /// One JSON document per line, for scripts; failures are printed as
+    /// JSON too
+    Json,
+}
+
+impl Commands {
+    /// Whether the command prints JSON, so its failures are printed as JSON
+    /// as well. `default` is whether it does without a `--format`.
+    fn json(&self, default: bool) -> bool {
+        let report = |format: &Option<ReportFormat>| match format {
+            Some(format) => *format == ReportFormat::Json,
+            None => default,
+        };
+        match self {
+            #[cfg(feature = "serde")]
+            Commands::Parse { format, .. } => match format {
+                Some(format) => matches!(format, OutputFormat::Json),
+                None => default,
+            },
+            Commands::Tokenize { json: true, .. } => true,
+            Commands::Fmt { format, .. }
+            | Commands::Lint { format, .. }
+            | Commands::Tokenize { format, .. }
+            | Commands::Diff { format, .. }
+            | Commands::Stats { format, .. }
+            | Commands::Balance { format, .. }
+            | Commands::Find { format, .. }
+            | Commands::Refactor { format, .. }
+            | Commands::Explain { format, .. }
+            | Commands::Manifest { format, .. }
+            | Commands::Simulate { format, .. } => report(format),
+            #[cfg(feature = "compile")]
+            Commands::Compile { format, .. } => report(format),
+            #[cfg(feature = "convert")]
+            Commands::Convert { format, .. } | Commands::Import { format, .. } => report(format),
+            Commands::Export { format, .. } => matches!(format, ExportFormat::Json),
+            Commands::Grammar { format } => matches!(format, GrammarFormat::Json),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum BalanceOrder {
+    /// Furthest off the curve first
+    Deviation,
+    /// By quest name
+    Name,
+    /// Lowest level first
+    Level,
+    /// Highest reward first
+    Reward,
+}
+
+impl From<BalanceOrder> for BalanceSort {
+    fn from(order: BalanceOrder) -> Self {
+        match order {
+            BalanceOrder::Deviation => BalanceSort::Deviation,
+            BalanceOrder::Name => BalanceSort::Name,
+            BalanceOrder::Level => BalanceSort::Level,
+            BalanceOrder::Reward => BalanceSort::Reward,
+        }
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ExportFormat {
+    /// A Graphviz digraph of `requires` and `unlock` relationships
+    Dot,
+    /// One JSON line listing every quest with the quests it requires and
+    /// unlocks
+    Json,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum GrammarFormat {
+    /// The rules in EBNF
+    Ebnf,
+    /// A Graphviz digraph of which rules refer to which
+    Dot,
+    /// One JSON line with every rule and its definition
+    Json,
+}
+
+#[cfg(feature = "convert")]
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum FormatArg {
+    Dsl,
+    Toml,
+    Yaml,
+    Json,
+}
+
+#[cfg(feature = "convert")]
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ImportFormat {
+    /// A quest object, an array of them or a `{"quest": [...]}` document
+    Json,
+}
+
+#[cfg(feature = "convert")]
+impl From<FormatArg> for game_quest_parser_Hodik::Format {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Dsl => Self::Dsl,
+            FormatArg::Toml => Self::Toml,
+            FormatArg::Yaml => Self::Yaml,
+            FormatArg::Json => Self::Json,
+        }
+    }
+}
+
+/// Exit code when an input cannot be read or parsed, or the command fails
+/// for any other reason than [`EXIT_INVALID`].
+const EXIT_FAILED: i32 = 1;
+/// Exit code when the input parsed but failed a check: lint errors, an
+/// unformatted file under `fmt --check`, a broken quest graph or stage dead
+/// ends found by `simulate`.
+const EXIT_INVALID: i32 = 2;
+
+fn main() {
     let cli = Cli::parse();
+    let project = match load_project_config() {
+        Ok(project) => project,
+        Err(error) => process::exit(report(&error, cli.command.json(false))),
+    };
+    let json = cli.command.json(project.json);
+    if let Err(error) = run(cli.command, &project, json) {
+        process::exit(report(&error, json));
+    }
+}
 
-    match cli.command {
+/// Reads the nearest quest-parser.toml in the working directory or its
+/// parents, with its include paths made relative to the working directory.
+/// Without one every setting keeps its default.
+fn load_project_config() -> Result<ProjectConfig> {
+    let cwd = std::env::current_dir().context("Failed to read the working directory")?;
+    match ProjectConfig::find(&cwd) {
+        Some(path) => {
+            ProjectConfig::load(&path).with_context(|| format!("Failed to load {:?}", path))
+        }
+        None => Ok(ProjectConfig::default()),
+    }
+}
+
+fn run(command: Commands, project: &ProjectConfig, json: bool) -> Result<()> {
+    let options = project.parser_options();
+    match command {
         Commands::Credits => {
             println!("Game Quest Parser v0.1.0");
             println!("Created by: f1ore vita");
             println!("Theme: Custom Language for RPG Quests");
         }
-        Commands::Parse { file } => {
-            println!("Reading file: {:?}", file);
-            let content = fs::read_to_string(&file)
-                .with_context(|| format!("Failed to read file {:?}", file))?;
+        Commands::Parse {
+            file,
+            dir,
+            glob,
+            jobs,
+            strict,
+            no_strict,
+            trace,
+            format,
+            watch,
+            flags,
+        } => {
+            if trace {
+                log::set_logger(&StderrLogger).context("Failed to install the trace logger")?;
+                log::set_max_level(log::LevelFilter::Trace);
+            }
+            let options = ParserOptions {
+                strict: strict || (project.strict && !no_strict),
+                trace,
+                flags: flags.into_iter().collect(),
+                ..options
+            };
+            let format = match format {
+                Some(format) => format,
+                #[cfg(feature = "serde")]
+                None if json => OutputFormat::Json,
+                None => OutputFormat::Debug,
+            };
+            let parse = || match (&file, format) {
+                (Some(file), OutputFormat::Summary) => print_summary(file, &options),
+                (Some(file), OutputFormat::Debug) => print_quest(file, &options),
+                #[cfg(feature = "serde")]
+                (Some(file), OutputFormat::Json) => print_json(file, &options),
+                (None, _) => {
+                    let dir = dir.as_deref().expect("clap requires --file or --dir");
+                    parse_dir(dir, &glob, jobs, &options, json)
+                }
+            };
+            match file.as_deref().or(dir.as_deref()) {
+                Some(path) if watch => watch_path(path, json, parse)?,
+                _ => parse()?,
+            }
+        }
+        Commands::Lint {
+            file,
+            config,
+            watch,
+            ..
+        } => {
+            let run = || lint_file(&file, config.as_deref(), project, json);
+            if watch {
+                watch_path(&file, json, run)?;
+            } else {
+                run()?;
+            }
+        }
+        Commands::Diff { old, new, .. } => diff_files(&old, &new, &options, json)?,
+        Commands::Manifest {
+            dir, glob, output, ..
+        } => write_manifest(&dir, &glob, output.as_deref(), &options, json)?,
+        Commands::Stats {
+            dir,
+            glob,
+            top_tags,
+            ..
+        } => print_stats(&dir, &glob, top_tags, &options, json)?,
+        Commands::Balance {
+            dir,
+            glob,
+            config,
+            sort,
+            all,
+            ..
+        } => {
+            let config = match config {
+                Some(path) => {
+                    let source = fs::read_to_string(&path)
+                        .with_context(|| format!("Failed to read file {:?}", path))?;
+                    BalanceConfig::from_toml(&source)
+                        .with_context(|| format!("Failed to load {:?}", path))?
+                }
+                None => project.balance.clone().with_context(|| {
+                    format!(
+                        "No reward curves; pass --config or add [balance] to {PROJECT_CONFIG_FILE}"
+                    )
+                })?,
+            };
+            print_balance(&dir, &glob, &config, sort.into(), all, &options, json)?
+        }
+        Commands::Find {
+            dir, glob, filter, ..
+        } => find_quests(&dir, &glob, &filter, &options, json)?,
+        Commands::Refactor {
+            file,
+            dir,
+            glob,
+            renumber_steps,
+            rename_quest,
+            check,
+            ..
+        } => {
+            let files = match (file, dir) {
+                (Some(file), _) => vec![file],
+                (None, Some(dir)) => find_files(&dir, &glob)?,
+                (None, None) => unreachable!("clap requires --file or --dir"),
+            };
+            let rename = rename_quest
+                .as_deref()
+                .map(|names| (&*names[0], &*names[1]));
+            refactor_files(&files, renumber_steps, rename, check, json)?
+        }
+        Commands::Export {
+            files,
+            format,
+            output,
+        } => export_files(&files, format, output.as_deref(), &options)?,
+        Commands::Simulate {
+            file,
+            quest,
+            choices,
+            seed,
+            runs,
+            ..
+        } => {
+            let choices = if choices.is_empty() {
+                Choices::Random { seed, runs }
+            } else {
+                Choices::Scripted(choices)
+            };
+            simulate_file(&file, quest.as_deref(), &choices, &options, json)?
+        }
+        Commands::Explain { code, .. } => explain(code.as_deref(), json)?,
+        Commands::Grammar { format } => match format {
+            GrammarFormat::Ebnf => print!("{}", grammar::ebnf()),
+            GrammarFormat::Dot => print!("{}", grammar::dot()),
+            GrammarFormat::Json => {
+                let rules: Vec<_> = grammar::rules()
+                    .into_iter()
+                    .map(|(name, definition)| {
+                        serde_json::json!({ "name": name, "definition": definition })
+                    })
+                    .collect();
+                print_line(&serde_json::json!({ "rules": rules, "tokens": grammar::TOKENS }))?
+            }
+        },
+        Commands::Tokenize { file, .. } => tokenize_file(&file, json)?,
+        Commands::Repl => {
+            let interactive = io::stdin().is_terminal();
+            repl(io::stdin().lock(), &mut io::stdout(), interactive)?;
+        }
+        #[cfg(feature = "convert")]
+        Commands::Convert {
+            file,
+            output,
+            from,
+            to,
+            ..
+        } => convert_file(&file, output.as_deref(), from, to, json)?,
+        #[cfg(feature = "convert")]
+        Commands::Import {
+            files,
+            from,
+            output,
+            ..
+        } => import_files(&files, from, output.as_deref(), json)?,
+        #[cfg(feature = "compile")]
+        Commands::Compile { files, output, .. } => compile_files(&files, &output, &options, json)?,
+        Commands::Fmt {
+            file, check, fix, ..
+        } => {
+            let fix = fix.then_some(&options);
+            format_file(&file, check, fix, json)?
+        }
+    }
 
-            println!("Parsing content...");
-            let mut parser = Parser::new(&content).context("Failed to initialize parser")?;
+    Ok(())
+}
 
-            let quest = parser
-                .parse_quest()
-                .context("Failed to parse quest syntax")?;
+/// Lays out `file` in the standard style, or with `check` only fails if it
+/// is not laid out so already. With `fix`, the options to parse the file
+/// with, deprecated syntax is migrated first.
+fn format_file(file: &Path, check: bool, fix: Option<&ParserOptions>, json: bool) -> Result<()> {
+    let content = read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+    let mut fixed = 0;
+    let formatted = match fix {
+        Some(options) => {
+            let options = ParserOptions {
+                allow_external_refs: true,
+                ..options.clone()
+            };
+            let mut parser = Parser::with_options(&content, options)
+                .map_err(|e| diagnostic(&e, &content, file))?
+                .with_source_name(file.display().to_string());
+            parser
+                .parse_world()
+                .map_err(|e| diagnostic(&e, &content, file))?;
+            let warnings = parser.take_diagnostics().into_warnings();
+            fixed = warnings
+                .iter()
+                .filter(|w| matches!(w, Diagnostic::Deprecated { .. }))
+                .count();
+            let migrated = fix_deprecated(&content, &warnings);
+            match format_source(&migrated) {
+                Err(ParseError::Unformattable(_)) => migrated,
+                result => result.map_err(|e| diagnostic(&e, &migrated, file))?,
+            }
+        }
+        None => format_source(&content).map_err(|e| diagnostic(&e, &content, file))?,
+    };
+    let changed = formatted != content;
+    if changed && !check {
+        fs::write(file, formatted).with_context(|| format!("Failed to write file {:?}", file))?;
+    }
+
+    if json {
+        print_line(&serde_json::json!({
+            "file": file,
+            "formatted": !changed,
+            "written": changed && !check,
+            "fixed": fixed,
+        }))?;
+    } else if fixed > 0 && !check {
+        println!("Migrated {fixed} deprecated forms in {:?}", file);
+    } else if !changed && check {
+        println!("{:?} is formatted", file);
+    } else if changed && !check {
+        println!("Formatted {:?}", file);
+    }
+    if changed && check {
+        return Err(invalid(format!("{:?} is not formatted", file)));
+    }
+    Ok(())
+}
+
+/// Reads a quest file, decoding UTF-16 and dropping a byte order mark as
+/// [`decode_source`] does.
+fn read_source(path: impl AsRef<Path>) -> io::Result<String> {
+    let bytes = fs::read(path)?;
+    decode_source(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Prints log messages to stderr as they are, for `parse --trace`.
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        eprintln!("{}", record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+/// A failure already rendered for people and for scripts, printed by
+/// [`report`] as is.
+#[derive(Debug)]
+struct Failure {
+    code: i32,
+    text: String,
+    json: serde_json::Value,
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Renders `error` in `file` as a diagnostic with the offending source line.
+///
+/// Errors inside included files are rendered against the included file, which
+/// is read back from disk using the name the include resolver produced.
+fn diagnostic(error: &ParseError, content: &str, file: &Path) -> anyhow::Error {
+    let file = file.display().to_string();
+    let (error, source, file) = match error {
+        ParseError::InInclude {
+            chain,
+            error: inner,
+        } => {
+            let included = chain.last().map(String::as_str).unwrap_or_default();
+            match read_source(included) {
+                Ok(source) => (&**inner, source, included.to_string()),
+                Err(_) => (error, content.to_string(), file),
+            }
+        }
+        _ => (error, content.to_string(), file),
+    };
+    let mut json = serde_json::json!({
+        "kind": "parse",
+        "code": error.code(),
+        "file": file,
+        "message": error.to_string(),
+    });
+    if let Some(span) = error.span() {
+        json["span"] = span_json(span, &source);
+    }
+    Failure {
+        code: EXIT_FAILED,
+        text: render_error(error, &source, &file),
+        json,
+    }
+    .into()
+}
+
+/// The warning and the source and name of the file it was raised in,
+/// unwrapping warnings from included files like [`diagnostic`] does.
+fn warning_source<'w>(
+    warning: &'w Diagnostic,
+    content: &str,
+    file: &Path,
+) -> (&'w Diagnostic, String, String) {
+    if let Diagnostic::InInclude { chain, warning } = warning {
+        let included = chain.last().map(String::as_str).unwrap_or_default();
+        if let Ok(source) = read_source(included) {
+            return (warning, source, included.to_string());
+        }
+    }
+    (warning, content.to_string(), file.display().to_string())
+}
+
+/// Prints the warnings raised while parsing `file` to stderr, in yellow when
+/// stderr is a terminal. Warnings never fail a command.
+fn print_warnings(diagnostics: &Diagnostics, content: &str, file: &Path) {
+    let color = io::stderr().is_terminal();
+    for warning in diagnostics.warnings() {
+        let (warning, source, file) = warning_source(warning, content, file);
+        let text = render_warning(warning, &source, &file);
+        if color {
+            eprint!("\x1b[33m{text}\x1b[0m");
+        } else {
+            eprint!("{text}");
+        }
+    }
+}
+
+/// The warnings raised while parsing `file` as JSON objects with the file,
+/// message and span of each.
+#[cfg(feature = "serde")]
+fn warnings_json(diagnostics: &Diagnostics, content: &str, file: &Path) -> serde_json::Value {
+    diagnostics
+        .warnings()
+        .iter()
+        .map(|warning| {
+            let (warning, source, file) = warning_source(warning, content, file);
+            serde_json::json!({
+                "file": file,
+                "message": warning.to_string(),
+                "span": span_json(warning.span(), &source),
+            })
+        })
+        .collect()
+}
+
+/// A check the input failed, exiting with [`EXIT_INVALID`].
+fn invalid(message: String) -> anyhow::Error {
+    Failure {
+        code: EXIT_INVALID,
+        text: format!("Error: {message}\n"),
+        json: serde_json::json!({ "kind": "invalid", "message": message }),
+    }
+    .into()
+}
+
+/// `span` as a JSON object with its byte offsets and the line and column it
+/// starts at in `source`.
+fn span_json(span: Span, source: &str) -> serde_json::Value {
+    let (line, column) = line_col(source, span.start);
+    serde_json::json!({
+        "start": span.start,
+        "end": span.end,
+        "line": line,
+        "column": column,
+    })
+}
+
+/// Prints `value` as one line of JSON.
+fn print_line(value: &serde_json::Value) -> Result<()> {
+    println!("{}", serde_json::to_string(value)?);
+    Ok(())
+}
 
-            println!("✅ Successfully parsed!");
-            println!("{:#?}", quest);
+/// Prints the error a command failed with and returns the exit code for it.
+///
+/// Failures are printed to stderr, diagnostics as they were rendered and
+/// anything else with its chain of causes. With `json` they are printed to
+/// stdout as an `{"error": {...}}` line instead.
+fn report(error: &anyhow::Error, json: bool) -> i32 {
+    let failure = error.downcast_ref::<Failure>();
+    if json {
+        let error = match failure {
+            Some(failure) => failure.json.clone(),
+            None => serde_json::json!({ "kind": "error", "message": format!("{error:#}") }),
+        };
+        println!("{}", serde_json::json!({ "error": error }));
+    } else {
+        match failure {
+            Some(failure) => eprint!("{failure}"),
+            None => eprintln!("Error: {error:?}"),
         }
     }
+    failure.map_or(EXIT_FAILED, |failure| failure.code)
+}
+
+/// How long `--watch` waits after a change for more before running again, as
+/// editors often save in several writes.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Runs `command`, then again whenever a file at or below `path` is created,
+/// changed or removed, until the process is interrupted. Errors are printed
+/// instead of ending the loop; the terminal is cleared before every run
+/// unless the output is `json`, which gets one line per run and nothing else.
+///
+/// A single file is watched through its directory, so editors that save by
+/// replacing the file are still seen.
+fn watch_path(path: &Path, json: bool, mut command: impl FnMut() -> Result<()>) -> Result<()> {
+    let clear = io::stdout().is_terminal() && !json;
+    let canonical =
+        fs::canonicalize(path).with_context(|| format!("Failed to watch {:?}", path))?;
+    let (dir, file, mode) = match canonical.parent() {
+        Some(parent) if !canonical.is_dir() => (
+            parent.to_path_buf(),
+            Some(canonical.clone()),
+            RecursiveMode::NonRecursive,
+        ),
+        _ => (canonical.clone(), None, RecursiveMode::Recursive),
+    };
+    let (sender, events) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(sender).context("Failed to start watching for changes")?;
+    watcher
+        .watch(&dir, mode)
+        .with_context(|| format!("Failed to watch {:?}", path))?;
+    let changed = |event: notify::Result<notify::Event>| -> Result<bool> {
+        let event = event.context("Failed to watch for changes")?;
+        let edit = matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) && event.kind
+            != EventKind::Modify(ModifyKind::Metadata(MetadataKind::AccessTime));
+        Ok(edit && (file.is_none() || event.paths.iter().any(|p| Some(p) == file.as_ref())))
+    };
+    loop {
+        if clear {
+            print!("\x1b[2J\x1b[H");
+        }
+        if let Err(error) = command() {
+            report(&error, json);
+        }
+        if !json {
+            println!("\nWatching {} for changes (Ctrl-C to stop)", path.display());
+        }
+        io::stdout().flush()?;
+        while !changed(events.recv().context("Stopped watching for changes")?)? {}
+        while let Ok(event) = events.recv_timeout(WATCH_DEBOUNCE) {
+            changed(event)?;
+        }
+    }
+}
+
+/// Name of the lint config looked up next to the linted file and in its
+/// parent directories.
+const LINT_CONFIG_FILE: &str = ".questlint.toml";
+
+/// Prints every lint in `file` and fails if any of them is an error.
+///
+/// Rules come from `config`, else the nearest .questlint.toml, else the
+/// `[lint]` table of quest-parser.toml.
+fn lint_file(
+    file: &Path,
+    config: Option<&Path>,
+    project: &ProjectConfig,
+    json: bool,
+) -> Result<()> {
+    let config_path = match config {
+        Some(path) => Some(path.to_path_buf()),
+        None => fs::canonicalize(file)
+            .with_context(|| format!("Failed to read file {:?}", file))?
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join(LINT_CONFIG_FILE))
+            .find(|path| path.is_file()),
+    };
+    let config = match &config_path {
+        Some(path) => {
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read file {:?}", path))?;
+            LintConfig::from_toml(&source).with_context(|| format!("Failed to load {:?}", path))?
+        }
+        None => project.lint.clone().unwrap_or_default(),
+    };
 
+    let content = read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+    let quests = Parser::with_options(&content, project.parser_options())
+        .map(|p| p.with_source_name(file.display().to_string()))
+        .and_then(|mut p| p.parse_quests())
+        .map_err(|e| diagnostic(&e, &content, file))?;
+
+    let lints = lint(&quests, &config);
+    if json {
+        // Findings point at the name of their quest.
+        let tree = ast::parse(&content).ok();
+        let span = |name: &str| {
+            let quest = tree.as_ref()?.quests().find(|quest| quest.name == name)?;
+            Some(span_json(quest.name_span, &content))
+        };
+        let lints: Vec<_> = lints
+            .iter()
+            .map(|found| {
+                serde_json::json!({
+                    "rule": found.rule,
+                    "severity": found.severity.to_string(),
+                    "quest": found.quest,
+                    "message": found.message,
+                    "span": span(&found.quest),
+                })
+            })
+            .collect();
+        print_line(&serde_json::json!({ "file": file, "lints": lints }))?;
+    } else {
+        for found in &lints {
+            println!("{}: {found}", file.display());
+        }
+    }
+    let errors = lints
+        .iter()
+        .filter(|found| found.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(invalid(format!("{errors} lint error(s)")));
+    }
+    Ok(())
+}
+
+/// Name shown for snippet input in rendered errors.
+const REPL_SOURCE: &str = "<repl>";
+
+/// Reads snippets from `input` until it ends, printing the quests each one
+/// parses to, or a rendered error. With `prompt`, a prompt is shown before
+/// every line.
+fn repl(input: impl BufRead, output: &mut impl Write, prompt: bool) -> Result<()> {
+    let mut snippet = String::new();
+    let mut lines = input.lines();
+    loop {
+        if prompt {
+            let marker = if snippet.is_empty() {
+                "quest> "
+            } else {
+                "  ...> "
+            };
+            write!(output, "{marker}")?;
+            output.flush()?;
+        }
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let end = line.trim_end().strip_suffix(";;");
+        match end {
+            Some(rest) => snippet.push_str(rest),
+            None if line.trim().is_empty() => {}
+            None => {
+                snippet.push_str(&line);
+                snippet.push('\n');
+                continue;
+            }
+        }
+        if !snippet.trim().is_empty() {
+            eval_snippet(&snippet, output)?;
+        }
+        snippet.clear();
+    }
+    if !snippet.trim().is_empty() {
+        eval_snippet(&snippet, output)?;
+    }
     Ok(())
 }
+
+fn eval_snippet(snippet: &str, output: &mut impl Write) -> io::Result<()> {
+    match Parser::new(snippet).and_then(|mut p| p.parse_quests()) {
+        Ok(quests) if quests.is_empty() => writeln!(output, "(no quests)"),
+        Ok(quests) => quests
+            .iter()
+            .try_for_each(|quest| writeln!(output, "{quest:#?}")),
+        Err(e) => write!(output, "{}", render_error(&e, snippet, REPL_SOURCE)),
+    }
+}
+
+/// Prints the first quest of `file` as its parsed Rust structure.
+fn print_quest(file: &Path, options: &ParserOptions) -> Result<()> {
+    println!("Reading file: {:?}", file);
+    let content = read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+
+    println!("Parsing content...");
+    let mut parser = Parser::with_options(&content, options.clone())
+        .map_err(|e| diagnostic(&e, &content, file))?
+        .with_source_name(file.display().to_string());
+    let quest = parser
+        .parse_quest()
+        .map_err(|e| diagnostic(&e, &content, file))?;
+    print_warnings(parser.diagnostics(), &content, file);
+
+    println!("✅ Successfully parsed!");
+    println!("{:#?}", quest);
+    Ok(())
+}
+
+/// Prints the quests of `file` as a JSON line of the form
+/// `{"file": ..., "quests": [...], "warnings": [...]}`.
+#[cfg(feature = "serde")]
+fn print_json(file: &Path, options: &ParserOptions) -> Result<()> {
+    let content = read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+    let mut parser = Parser::with_options(&content, options.clone())
+        .map_err(|e| diagnostic(&e, &content, file))?
+        .with_source_name(file.display().to_string());
+    let quests = parser
+        .parse_quests()
+        .map_err(|e| diagnostic(&e, &content, file))?;
+    let warnings = warnings_json(parser.diagnostics(), &content, file);
+    print_line(&serde_json::json!({ "file": file, "quests": quests, "warnings": warnings }))
+}
+
+/// Prints the summary of every quest in `file`, separated by blank lines.
+fn print_summary(file: &Path, options: &ParserOptions) -> Result<()> {
+    let content = read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+    let mut parser = Parser::with_options(&content, options.clone())
+        .map_err(|e| diagnostic(&e, &content, file))?
+        .with_source_name(file.display().to_string());
+    let quests = parser
+        .parse_quests()
+        .map_err(|e| diagnostic(&e, &content, file))?;
+    print_warnings(parser.diagnostics(), &content, file);
+    let summaries: Vec<String> = quests.iter().map(Quest::summary).collect();
+    print!("{}", summaries.join("\n"));
+    Ok(())
+}
+
+/// Prints the changes from every quest in `old` to the quest of the same name
+/// in `new`, plus the quests only one of them defines. Quests that both have
+/// an id are matched by id instead. Files holding a single quest each are
+/// compared even if the quest was renamed.
+///
+/// With `json` the sections are printed as one line of the form
+/// `{"quests": [{"name": ..., "status": ..., "changes": [...]}]}`, where the
+/// status is `changed`, `added` or `removed`.
+fn diff_files(old: &Path, new: &Path, options: &ParserOptions, json: bool) -> Result<()> {
+    let read = |file: &Path| -> Result<Vec<Quest>> {
+        let content =
+            read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+        Parser::with_options(&content, options.clone())
+            .map(|p| p.with_source_name(file.display().to_string()))
+            .and_then(|mut p| p.parse_quests())
+            .map_err(|e| diagnostic(&e, &content, file))
+    };
+    let (old_quests, new_quests) = (read(old)?, read(new)?);
+
+    let same = |a: &Quest, b: &Quest| match (&a.id, &b.id) {
+        (Some(x), Some(y)) => x == y,
+        _ => a.name == b.name,
+    };
+    let mut sections = Vec::new();
+    if let ([a], [b]) = (old_quests.as_slice(), new_quests.as_slice()) {
+        sections.push((&b.name, Some(quest_diff(a, b)), false));
+    } else {
+        for a in &old_quests {
+            let changes = new_quests
+                .iter()
+                .find(|b| same(a, b))
+                .map(|b| quest_diff(a, b));
+            sections.push((&a.name, changes, true));
+        }
+        for b in &new_quests {
+            if !old_quests.iter().any(|a| same(a, b)) {
+                sections.push((&b.name, None, false));
+            }
+        }
+    }
+
+    sections.retain(|(_, changes, _)| changes.as_ref().is_none_or(|c| !c.is_empty()));
+    if json {
+        let quests: Vec<_> = sections
+            .iter()
+            .map(|(name, changes, in_old)| {
+                let status = match changes {
+                    Some(_) => "changed",
+                    None if *in_old => "removed",
+                    None => "added",
+                };
+                let changes: Vec<String> =
+                    changes.iter().flatten().map(|c| c.to_string()).collect();
+                serde_json::json!({ "name": name, "status": status, "changes": changes })
+            })
+            .collect();
+        return print_line(&serde_json::json!({ "quests": quests }));
+    }
+    for (name, changes, in_old) in &sections {
+        match changes {
+            Some(changes) => {
+                println!("quest {name:?}:");
+                for change in changes {
+                    println!("  {change}");
+                }
+            }
+            None if *in_old => println!("quest {name:?} removed"),
+            None => println!("quest {name:?} added"),
+        }
+    }
+    if sections.is_empty() {
+        println!("No changes");
+    }
+    Ok(())
+}
+
+/// Converts `file` and writes the result to `output`, or to stdout.
+#[cfg(feature = "convert")]
+fn convert_file(
+    file: &Path,
+    output: Option<&Path>,
+    from: Option<FormatArg>,
+    to: Option<FormatArg>,
+    json: bool,
+) -> Result<()> {
+    use game_quest_parser_Hodik::{convert, ConvertError, Format};
+
+    let from = match from {
+        Some(format) => format.into(),
+        None => Format::from_path(file)
+            .with_context(|| format!("Cannot tell the format of {:?}; pass --from", file))?,
+    };
+    let to = match to {
+        Some(format) => format.into(),
+        None => output
+            .and_then(Format::from_path)
+            .context("Pass --to or an --output file")?,
+    };
+
+    let content = read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+    let converted = match convert(&content, from, to) {
+        Ok(converted) => converted,
+        Err(ConvertError::Parse(e)) => return Err(diagnostic(&e, &content, file)),
+        Err(e) => bail!("{:?}: {e}", file),
+    };
+    write_result(
+        &converted,
+        output,
+        json.then(|| serde_json::json!({ "file": file })),
+    )
+}
+
+/// Reads the quests of every file and writes them as one file of quest DSL
+/// to `output`, or to stdout.
+#[cfg(feature = "convert")]
+fn import_files(
+    files: &[PathBuf],
+    from: ImportFormat,
+    output: Option<&Path>,
+    json: bool,
+) -> Result<()> {
+    use game_quest_parser_Hodik::{from_json_many, to_quests_string};
+
+    let mut quests = Vec::new();
+    for file in files {
+        let content =
+            read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+        let imported = match from {
+            ImportFormat::Json => from_json_many(&content),
+        };
+        quests.extend(imported.with_context(|| format!("Cannot import {:?}", file))?);
+    }
+    let rendered = to_quests_string(&quests);
+    write_result(
+        &rendered,
+        output,
+        json.then(|| serde_json::json!({ "files": files, "quests": quests.len() })),
+    )
+}
+
+/// Writes `rendered` to `output`, or prints it. With a JSON `report` only a
+/// line of the report is printed, with the `output` written to or else the
+/// `text` itself.
+fn write_result(
+    rendered: &str,
+    output: Option<&Path>,
+    report: Option<serde_json::Value>,
+) -> Result<()> {
+    if let Some(path) = output {
+        fs::write(path, rendered).with_context(|| format!("Failed to write file {:?}", path))?;
+    }
+    match (report, output) {
+        (Some(mut report), Some(path)) => {
+            report["output"] = serde_json::json!(path);
+            print_line(&report)
+        }
+        (Some(mut report), None) => {
+            report["text"] = serde_json::json!(rendered);
+            print_line(&report)
+        }
+        (None, Some(_)) => Ok(()),
+        (None, None) => {
+            print!("{rendered}");
+            Ok(())
+        }
+    }
+}
+
+/// Prints the catalog entry of `code`, or a line for every code without one.
+fn explain(code: Option<&str>, json: bool) -> Result<()> {
+    let Some(code) = code else {
+        if json {
+            let codes: Vec<_> = ERROR_CODES
+                .iter()
+                .map(|info| serde_json::json!({ "code": info.code, "title": info.title }))
+                .collect();
+            return print_line(&serde_json::json!(codes));
+        }
+        for info in ERROR_CODES {
+            println!("{}  {}", info.code, info.title);
+        }
+        return Ok(());
+    };
+    let Some(info) = error_info(code) else {
+        bail!("Unknown error code {code:?}; run `explain` without a code to list them");
+    };
+    if json {
+        print_line(&serde_json::json!({
+            "code": info.code,
+            "title": info.title,
+            "explanation": info.explanation,
+        }))
+    } else {
+        println!("{}: {}\n\n{}", info.code, info.title, info.explanation);
+        Ok(())
+    }
+}
+
+/// Prints every token of `file` with its position and byte span, or all of
+/// them as a JSON array of `{kind, text, start, end, line, column}` objects.
+fn tokenize_file(file: &Path, json: bool) -> Result<()> {
+    let content = read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+    let mut tokens = Vec::new();
+    for token in Lexer::new(&content) {
+        let (token, span) = token.map_err(|e| diagnostic(&e, &content, file))?;
+        let (line, column) = line_col(&content, span.start);
+        if !json {
+            println!("{line}:{column}\t{}..{}\t{token:?}", span.start, span.end);
+            continue;
+        }
+        // The variant name, without the payload the Debug output appends.
+        let kind = format!("{token:?}");
+        let kind = kind.split(['(', ' ']).next().unwrap_or_default();
+        tokens.push(serde_json::json!({
+            "kind": kind,
+            "text": &content[span.start..span.end],
+            "start": span.start,
+            "end": span.end,
+            "line": line,
+            "column": column,
+        }));
+    }
+    if json {
+        print_line(&serde_json::Value::from(tokens))?;
+    }
+    Ok(())
+}
+
+/// Prints what simulating every quest of `file`, or only the one named
+/// `name`, with `choices` found, and fails if a quest has dead ends.
+fn simulate_file(
+    file: &Path,
+    name: Option<&str>,
+    choices: &Choices,
+    options: &ParserOptions,
+    json: bool,
+) -> Result<()> {
+    let mut quests = parse_quest_files(&[file.to_path_buf()], options)?;
+    if let Some(name) = name {
+        quests.retain(|quest| quest.name == name);
+        if quests.is_empty() {
+            bail!("{:?} has no quest named {name:?}", file);
+        }
+    }
+    if matches!(choices, Choices::Scripted(_)) && quests.len() > 1 {
+        bail!(
+            "{:?} has several quests; pick the one to walk with --quest",
+            file
+        );
+    }
+
+    let mut reports = Vec::new();
+    for quest in &quests {
+        let report = quest
+            .simulate(choices)
+            .with_context(|| format!("Cannot walk quest {:?}", quest.name))?;
+        reports.push((quest, report));
+    }
+
+    let ending_text = |ending: &Ending| match ending {
+        Ending::Completed => "completed".to_string(),
+        Ending::Stuck(stage) => format!("stuck at {stage}"),
+    };
+    if json {
+        // Stages by their bare names, as `--choose` takes them.
+        let names = |stages: &[Stage]| -> Vec<String> {
+            stages
+                .iter()
+                .map(|stage| match stage {
+                    Stage::Named(name) => name.clone(),
+                    other => other.to_string(),
+                })
+                .collect()
+        };
+        let quests: Vec<_> = reports
+            .iter()
+            .map(|(quest, report)| {
+                let walks: Vec<_> = report
+                    .walks
+                    .iter()
+                    .map(|walk| {
+                        serde_json::json!({
+                            "path": names(&walk.path),
+                            "ending": ending_text(&walk.ending),
+                        })
+                    })
+                    .collect();
+                let currencies: serde_json::Map<_, _> = report
+                    .currency_rewards
+                    .iter()
+                    .map(|(currency, amount)| (currency.to_string(), (*amount).into()))
+                    .collect();
+                serde_json::json!({
+                    "name": quest.name,
+                    "walks": walks,
+                    "endings": report.endings.iter().map(ending_text).collect::<Vec<_>>(),
+                    "unreached": names(&report.unreached),
+                    "dead_ends": names(&report.dead_ends),
+                    "success_reward": report.success_reward,
+                    "currency_rewards": currencies,
+                    "failure_reward": report.failure_reward,
+                })
+            })
+            .collect();
+        print_line(&serde_json::json!({ "quests": quests }))?;
+    } else {
+        let list = |stages: &[Stage]| {
+            let names: Vec<String> = stages.iter().map(Stage::to_string).collect();
+            names.join(", ")
+        };
+        for (i, (quest, report)) in reports.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            println!("quest {:?}:", quest.name);
+            if let [walk] = &report.walks[..] {
+                println!("  Path:      {}", list(&walk.path).replace(", ", " -> "));
+            } else {
+                println!("  Walks:     {}", report.walks.len());
+            }
+            let endings: Vec<String> = report.endings.iter().map(ending_text).collect();
+            println!("  Endings:   {}", endings.join(", "));
+            if !report.unreached.is_empty() {
+                println!("  Unreached: {}", list(&report.unreached));
+            }
+            if !report.dead_ends.is_empty() {
+                println!("  Dead ends: {}", list(&report.dead_ends));
+            }
+            let mut rewards = vec![report.success_reward.to_string()];
+            rewards.extend(
+                report
+                    .currency_rewards
+                    .iter()
+                    .map(|(currency, amount)| format!("{amount} {currency}")),
+            );
+            println!("  Success:   {}", rewards.join(", "));
+            if let Some(reward) = report.failure_reward {
+                println!("  Failure:   {reward}");
+            }
+        }
+    }
+
+    let stuck = reports
+        .iter()
+        .filter(|(_, report)| !report.dead_ends.is_empty())
+        .count();
+    if stuck > 0 {
+        return Err(invalid(format!(
+            "{stuck} quest(s) have stages that cannot reach end"
+        )));
+    }
+    Ok(())
+}
+
+/// Parses the quests of every file, failing with a diagnostic at the first
+/// file that does not parse.
+fn parse_quest_files(files: &[PathBuf], options: &ParserOptions) -> Result<Vec<Quest>> {
+    let mut quests = Vec::new();
+    for file in files {
+        let content =
+            read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+        let mut parser = Parser::with_options(&content, options.clone())
+            .map_err(|e| diagnostic(&e, &content, file))?
+            .with_source_name(file.display().to_string());
+        let parsed = parser
+            .parse_quests()
+            .map_err(|e| diagnostic(&e, &content, file))?;
+        print_warnings(parser.diagnostics(), &content, file);
+        quests.extend(parsed);
+    }
+    Ok(quests)
+}
+
+/// Parses every file in `files` and writes their quests to `output` as one
+/// compiled pack.
+#[cfg(feature = "compile")]
+fn compile_files(
+    files: &[PathBuf],
+    output: &Path,
+    options: &ParserOptions,
+    json: bool,
+) -> Result<()> {
+    let quests = parse_quest_files(files, options)?;
+    let pack = game_quest_parser_Hodik::compile(&quests);
+    fs::write(output, &pack).with_context(|| format!("Failed to write file {:?}", output))?;
+    if json {
+        return print_line(&serde_json::json!({
+            "output": output,
+            "quests": quests.len(),
+            "bytes": pack.len(),
+        }));
+    }
+    println!(
+        "Compiled {} quests into {:?} ({} bytes)",
+        quests.len(),
+        output,
+        pack.len()
+    );
+    Ok(())
+}
+
+fn export_files(
+    files: &[PathBuf],
+    format: ExportFormat,
+    output: Option<&Path>,
+    options: &ParserOptions,
+) -> Result<()> {
+    let quests = parse_quest_files(files, options)?;
+    let graph = QuestGraph::new(&quests).map_err(|e| invalid(e.to_string()))?;
+    let rendered = match format {
+        ExportFormat::Dot => graph.to_dot(),
+        ExportFormat::Json => {
+            let quests: Vec<_> = graph
+                .quests()
+                .iter()
+                .map(|quest| {
+                    let requires: Vec<&str> = graph
+                        .prerequisites(&quest.name)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|q| q.name.as_str())
+                        .collect();
+                    let unlocks: Vec<&str> = [&quest.on_success, &quest.on_failure]
+                        .into_iter()
+                        .flatten()
+                        .flat_map(|outcome| &outcome.unlocks)
+                        .map(String::as_str)
+                        .collect();
+                    serde_json::json!({
+                        "name": quest.name,
+                        "requires": requires,
+                        "unlocks": unlocks,
+                    })
+                })
+                .collect();
+            serde_json::to_string(&serde_json::json!({ "quests": quests }))? + "\n"
+        }
+    };
+    write_result(&rendered, output, None)
+}
+
+/// Parses every file below `dir` whose relative path matches `pattern`,
+/// prints one summary row per file and fails if any file did not parse.
+///
+/// Files are parsed on `jobs` threads, or one per core when not given. With
+/// `json` the rows are printed as one line of the form `{"files": [...]}`
+/// instead, each with the number of quests or the error of its file.
+fn parse_dir(
+    dir: &Path,
+    pattern: &str,
+    jobs: Option<usize>,
+    options: &ParserOptions,
+    json: bool,
+) -> Result<()> {
+    let files = find_files(dir, pattern)?;
+    if json {
+        let results = parse_all(&files, jobs, options)?;
+        let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+        let rows: Vec<_> = results
+            .into_iter()
+            .map(|(path, result)| match result {
+                Ok(quests) => serde_json::json!({ "file": path, "quests": quests.len() }),
+                Err(error) => {
+                    let mut json = serde_json::json!({
+                        "kind": "parse",
+                        "code": error.code(),
+                        "file": path,
+                        "message": error.to_string(),
+                    });
+                    let content = read_source(&path).ok();
+                    if let (Some(span), Some(content)) = (error.span(), content) {
+                        if !matches!(error, ParseError::InInclude { .. }) {
+                            json["span"] = span_json(span, &content);
+                        }
+                    }
+                    serde_json::json!({ "file": path, "error": json })
+                }
+            })
+            .collect();
+        print_line(&serde_json::json!({ "files": rows }))?;
+        if failed > 0 {
+            bail!("{failed} file(s) failed to parse");
+        }
+        return Ok(());
+    }
+    let rows: Vec<(String, Result<usize, String>)> = parse_all(&files, jobs, options)?
+        .into_iter()
+        .map(|(path, result)| {
+            let result = result
+                .map(|quests| quests.len())
+                .map_err(|e| describe_error(&path, &e));
+            (path.display().to_string(), result)
+        })
+        .collect();
+
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "{:<width$}  {:<6}  {:>6}  DETAILS",
+        "FILE", "STATUS", "QUESTS"
+    );
+    for (name, result) in &rows {
+        match result {
+            Ok(count) => println!("{name:<width$}  {:<6}  {count:>6}", "ok"),
+            Err(message) => println!("{name:<width$}  {:<6}  {:>6}  {message}", "FAILED", "-"),
+        }
+    }
+
+    let failed = rows.iter().filter(|(_, result)| result.is_err()).count();
+    println!(
+        "\n{} files, {} passed, {} failed",
+        rows.len(),
+        rows.len() - failed,
+        failed
+    );
+    if failed > 0 {
+        bail!("{failed} file(s) failed to parse");
+    }
+    Ok(())
+}
+
+/// Every file below `dir` whose relative path matches `pattern`, sorted.
+fn find_files(dir: &Path, pattern: &str) -> Result<Vec<PathBuf>> {
+    let pattern = glob::Pattern::new(pattern).context("Invalid --glob pattern")?;
+    let match_options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)
+        .with_context(|| format!("Failed to read directory {:?}", dir))?;
+    files.retain(|path| {
+        path.strip_prefix(dir)
+            .is_ok_and(|relative| pattern.matches_path_with(relative, match_options))
+    });
+    files.sort();
+    Ok(files)
+}
+
+/// Prints how the rewards of the quests below `dir` compare to the curves of
+/// `config`, failing if any quest is an outlier.
+fn print_balance(
+    dir: &Path,
+    pattern: &str,
+    config: &BalanceConfig,
+    order: BalanceSort,
+    all: bool,
+    options: &ParserOptions,
+    json: bool,
+) -> Result<()> {
+    let files = find_files(dir, pattern)?;
+    let mut quests = Vec::new();
+    for (path, result) in parse_all(&files, None, options)? {
+        match result {
+            Ok(parsed) => quests.extend(parsed),
+            Err(e) => bail!("{:?}: {}", path, describe_error(&path, &e)),
+        }
+    }
+    let mut report = balance(&quests, config);
+    report.sort_by(order);
+    let outliers = report.outliers().count();
+    let listed: Vec<_> = report
+        .entries
+        .iter()
+        .filter(|entry| all || entry.outlier)
+        .collect();
+
+    if json {
+        let entries: Vec<_> = listed
+            .iter()
+            .map(|entry| {
+                let position = entry_position(entry);
+                serde_json::json!({
+                    "quest": entry.quest,
+                    "level": entry.level,
+                    "reward": entry.reward,
+                    "expected": entry.expected,
+                    "ratio": entry.ratio,
+                    "tag": entry.tag,
+                    "outlier": entry.outlier,
+                    "file": entry.file,
+                    "line": position.map(|(line, _)| line),
+                    "column": position.map(|(_, column)| column),
+                })
+            })
+            .collect();
+        print_line(&serde_json::json!({
+            "checked": report.entries.len(),
+            "outliers": outliers,
+            "entries": entries,
+            "skipped": report.skipped,
+        }))?;
+    } else {
+        if !listed.is_empty() {
+            let width = listed
+                .iter()
+                .map(|entry| entry.quest.len())
+                .max()
+                .unwrap_or(0)
+                .max(5);
+            println!(
+                "{:<width$}  {:>6}  {:>8}  {:>8}  {:>6}",
+                "QUEST", "LEVEL", "REWARD", "EXPECTED", "RATIO"
+            );
+            for entry in &listed {
+                let flag = match (entry.outlier, &entry.file, entry_position(entry)) {
+                    (true, Some(file), Some((line, column))) => {
+                        format!("  outlier at {file}:{line}:{column}")
+                    }
+                    (true, ..) => "  outlier".to_string(),
+                    (false, ..) => String::new(),
+                };
+                println!(
+                    "{:<width$}  {:>6}  {:>8}  {:>8.0}  {:>6.2}{flag}",
+                    entry.quest, entry.level, entry.reward, entry.expected, entry.ratio
+                );
+            }
+            println!();
+        }
+        println!(
+            "{outliers} of {} quest(s) off the curve, {} skipped",
+            report.entries.len(),
+            report.skipped.len()
+        );
+    }
+    if outliers > 0 {
+        return Err(invalid(format!("{outliers} quest(s) off the reward curve")));
+    }
+    Ok(())
+}
+
+/// Line and column of the reward a balance entry is about, read back from
+/// the file its quest was parsed from.
+fn entry_position(entry: &BalanceEntry) -> Option<(usize, usize)> {
+    let content = read_source(entry.file.as_ref()?).ok()?;
+    Some(line_col(&content, entry.span?.start))
+}
+
+/// Prints the quests in the files below `dir` matching `pattern` that
+/// `filter` is true for, in file order.
+fn find_quests(
+    dir: &Path,
+    pattern: &str,
+    filter: &str,
+    options: &ParserOptions,
+    json: bool,
+) -> Result<()> {
+    let filter_expr =
+        parse_expr(filter).map_err(|e| diagnostic(&e, filter, Path::new("--where")))?;
+    let files = find_files(dir, pattern)?;
+    let mut searched = 0;
+    let mut matches = Vec::new();
+    for (path, result) in parse_all(&files, None, options)? {
+        let quests = match result {
+            Ok(quests) => quests,
+            Err(e) => bail!("{:?}: {}", path, describe_error(&path, &e)),
+        };
+        searched += quests.len();
+        for quest in quests {
+            let matched = quest
+                .matches(&filter_expr)
+                .with_context(|| format!("{:?}: quest {:?}", path, quest.name))?;
+            if matched {
+                matches.push((path.clone(), quest.name));
+            }
+        }
+    }
+
+    if json {
+        let matches: Vec<_> = matches
+            .iter()
+            .map(|(file, quest)| serde_json::json!({ "file": file, "quest": quest }))
+            .collect();
+        return print_line(&serde_json::json!({ "quests": searched, "matches": matches }));
+    }
+    for (file, quest) in &matches {
+        println!("{}: {quest}", file.display());
+    }
+    println!("{} of {searched} quest(s) match", matches.len());
+    Ok(())
+}
+
+/// Applies the `refactor` operations to every file, then writes the files
+/// that changed. Nothing is written if a file does not parse or, when
+/// renaming, if a quest already has the new name.
+fn refactor_files(
+    files: &[PathBuf],
+    renumber: bool,
+    rename: Option<(&str, &str)>,
+    check: bool,
+    json: bool,
+) -> Result<()> {
+    let mut rewrites = Vec::new();
+    for file in files {
+        let content =
+            read_source(file).with_context(|| format!("Failed to read file {:?}", file))?;
+        let tree = ast::parse(&content).map_err(|e| diagnostic(&e, &content, file))?;
+        if let Some((_, new)) = rename {
+            let taken = tree
+                .quests()
+                .any(|quest| quest.name == new && quest.kind == ast::QuestKind::Definition);
+            if taken {
+                bail!("{:?} already has a quest named {:?}", file, new);
+            }
+        }
+        let mut source = content.clone();
+        let mut changes = 0;
+        if renumber {
+            let rewrite = renumber_steps(&source).map_err(|e| diagnostic(&e, &source, file))?;
+            source = rewrite.source;
+            changes += rewrite.changes;
+        }
+        if let Some((old, new)) = rename {
+            let rewrite =
+                rename_quest(&source, old, new).map_err(|e| diagnostic(&e, &source, file))?;
+            source = rewrite.source;
+            changes += rewrite.changes;
+        }
+        if changes > 0 {
+            rewrites.push((file, source, changes));
+        }
+    }
+    if !check {
+        for (file, source, _) in &rewrites {
+            fs::write(file, source).with_context(|| format!("Failed to write file {:?}", file))?;
+        }
+    }
+
+    let changes: usize = rewrites.iter().map(|(_, _, changes)| changes).sum();
+    if json {
+        let changed: Vec<_> = rewrites
+            .iter()
+            .map(|(file, _, changes)| serde_json::json!({ "file": file, "changes": changes }))
+            .collect();
+        print_line(&serde_json::json!({
+            "files": changed,
+            "changes": changes,
+            "written": !check,
+        }))?;
+    } else {
+        for (file, _, changes) in &rewrites {
+            if check {
+                println!("{:?} would change in {changes} place(s)", file);
+            } else {
+                println!("Rewrote {:?} in {changes} place(s)", file);
+            }
+        }
+        println!(
+            "{changes} change(s) in {} of {} file(s)",
+            rewrites.len(),
+            files.len()
+        );
+    }
+    if check && !rewrites.is_empty() {
+        return Err(invalid(format!("{} file(s) would change", rewrites.len())));
+    }
+    Ok(())
+}
+
+/// Writes a JSON manifest listing every quest in the files below `dir` that
+/// match `pattern`, with its file relative to `dir` and the SHA-256 digest
+/// of its canonical form as 64 hex digits. Fails without writing anything
+/// if a file does not parse.
+fn write_manifest(
+    dir: &Path,
+    pattern: &str,
+    output: Option<&Path>,
+    options: &ParserOptions,
+    json: bool,
+) -> Result<()> {
+    let files = find_files(dir, pattern)?;
+    let mut entries = Vec::new();
+    for (path, result) in parse_all(&files, None, options)? {
+        let quests = match result {
+            Ok(quests) => quests,
+            Err(e) => bail!("{:?}: {}", path, describe_error(&path, &e)),
+        };
+        let file = path.strip_prefix(dir).unwrap_or(&path);
+        let file = file.to_string_lossy().replace('\\', "/");
+        entries.extend(quests.iter().map(|quest| {
+            serde_json::json!({
+                "name": quest.name,
+                "file": file,
+                "sha256": hex(&quest.content_hash()),
+            })
+        }));
+    }
+    let count = entries.len();
+    let manifest = serde_json::json!({ "quests": entries });
+    if json && output.is_none() {
+        return print_line(&manifest);
+    }
+    let rendered = serde_json::to_string_pretty(&manifest)? + "\n";
+    write_result(
+        &rendered,
+        output,
+        json.then(|| serde_json::json!({ "quests": count })),
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn print_stats(
+    dir: &Path,
+    pattern: &str,
+    top_tags: usize,
+    options: &ParserOptions,
+    json: bool,
+) -> Result<()> {
+    let files = find_files(dir, pattern)?;
+    let mut quests = Vec::new();
+    for (path, result) in parse_all(&files, None, options)? {
+        match result {
+            Ok(parsed) => quests.extend(parsed),
+            Err(e) => bail!("{:?}: {}", path, describe_error(&path, &e)),
+        }
+    }
+    let stats = QuestStats::from(quests.as_slice());
+    let tags = stats.top_tags(top_tags);
+    if json {
+        let steps: serde_json::Map<_, _> = stats
+            .step_counts
+            .iter()
+            .map(|(steps, quests)| (steps.to_string(), (*quests).into()))
+            .collect();
+        let tags: Vec<_> = tags
+            .iter()
+            .map(|(tag, quests)| serde_json::json!({ "tag": tag, "quests": quests }))
+            .collect();
+        return print_line(&serde_json::json!({
+            "files": files.len(),
+            "quests": stats.quests,
+            "average_reward": stats.average_reward,
+            "max_reward": stats.max_reward,
+            "step_counts": steps,
+            "top_tags": tags,
+            "without_prerequisites": stats.without_prerequisites,
+        }));
+    }
+    println!(
+        "Quests:         {} in {} file(s)",
+        stats.quests,
+        files.len()
+    );
+    match (stats.average_reward, stats.max_reward) {
+        (Some(average), Some(max)) => {
+            println!("Reward:         {average:.1} on average, {max} at most")
+        }
+        _ => println!("Reward:         -"),
+    }
+    if !stats.step_counts.is_empty() {
+        println!("Steps:");
+        for (steps, count) in &stats.step_counts {
+            println!("  {steps:>4} step(s): {count} quest(s)");
+        }
+    }
+    if !tags.is_empty() {
+        println!("Top tags:");
+        for (tag, count) in tags {
+            println!("  {tag}: {count}");
+        }
+    }
+    if !stats.without_prerequisites.is_empty() {
+        println!("No prerequisites:");
+        for name in &stats.without_prerequisites {
+            println!("  {name}");
+        }
+    }
+    Ok(())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+type FileResult = (PathBuf, Result<Vec<Quest>, ParseError>);
+
+#[cfg(feature = "parallel")]
+fn parse_all(
+    files: &[PathBuf],
+    jobs: Option<usize>,
+    options: &ParserOptions,
+) -> Result<Vec<FileResult>> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.unwrap_or(0))
+        .build()
+        .context("Failed to start parser threads")?;
+    Ok(pool.install(|| game_quest_parser_Hodik::parse_files_parallel_with_options(files, options)))
+}
+
+#[cfg(not(feature = "parallel"))]
+fn parse_all(
+    files: &[PathBuf],
+    _jobs: Option<usize>,
+    options: &ParserOptions,
+) -> Result<Vec<FileResult>> {
+    Ok(files
+        .iter()
+        .map(|path| {
+            (
+                path.clone(),
+                game_quest_parser_Hodik::parse_file(path, options),
+            )
+        })
+        .collect())
+}
+
+/// A one-line message for an error in `path`, prefixed with the error's
+/// position when it lies in the file itself.
+fn describe_error(path: &Path, error: &ParseError) -> String {
+    let position = match error.span() {
+        Some(span) if !matches!(error, ParseError::InInclude { .. }) => read_source(path)
+            .ok()
+            .map(|content| line_col(&content, span.start)),
+        _ => None,
+    };
+    match position {
+        Some((line, column)) => format!("{line}:{column}: {error}"),
+        None => error.to_string(),
+    }
+}
@@ -0,0 +1,158 @@
+use thiserror::Error;
+
+use crate::prelude::*;
+use crate::{Quest, Span};
+
+/// Changes a mod makes to a quest defined elsewhere, declared with
+/// `quest "Main Quest" override { reward: 200 }`.
+///
+/// Parsing collects overrides into [`World::overrides`](crate::World::overrides);
+/// [`merge`] applies them to the base quests.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuestOverride {
+    /// Name of the quest to patch, which must be in the package the override
+    /// is declared in, `patch.package`.
+    pub name: String,
+    /// The override's properties, parsed like a quest body. Only the
+    /// properties listed in `fields` are meaningful.
+    pub patch: Quest,
+    /// Keys of the properties the override sets, in source order and without
+    /// repeats. `steps` is listed as `step`.
+    pub fields: Vec<String>,
+    pub span: Span,
+}
+
+impl QuestOverride {
+    /// Replaces every property of `base` that the override sets. Repeatable
+    /// properties such as `step` or `requires` replace the whole list.
+    ///
+    /// The spans of replaced fields are dropped from the base's
+    /// [`SourceMap`](crate::SourceMap), since they point into another file.
+    pub fn apply(&self, base: &mut Quest) {
+        let patch = &self.patch;
+        for field in &self.fields {
+            base.source_map.forget(field);
+            match field.as_str() {
+                "id" => base.id.clone_from(&patch.id),
+                "step" => {
+                    base.steps.clone_from(&patch.steps);
+                    base.step_tree.clone_from(&patch.step_tree);
+                }
+                "objective" => base.objectives.clone_from(&patch.objectives),
+                "requires" => base.requires.clone_from(&patch.requires),
+                "reward" => {
+                    base.reward = patch.reward;
+                    base.rewards.clone_from(&patch.rewards);
+                    base.reward_tiers.clone_from(&patch.reward_tiers);
+                }
+                "tags" => base.tags.clone_from(&patch.tags),
+                "giver" => base.giver.clone_from(&patch.giver),
+                "turn_in" => base.turn_in.clone_from(&patch.turn_in),
+                "active" => base.active = patch.active,
+                "complete_when" => base.complete_when.clone_from(&patch.complete_when),
+                "stages" => base.stages.clone_from(&patch.stages),
+                "time_limit" => base.time_limit = patch.time_limit,
+                "level" => base.level_range = patch.level_range,
+                "starts_at" => base.starts_at = patch.starts_at,
+                "ends_at" => base.ends_at = patch.ends_at,
+                "on_success" => base.on_success.clone_from(&patch.on_success),
+                "on_failure" => base.on_failure.clone_from(&patch.on_failure),
+                "on_start" => base.on_start.clone_from(&patch.on_start),
+                "on_complete" => base.on_complete.clone_from(&patch.on_complete),
+                _ => {}
+            }
+            match patch.extra.get(field) {
+                Some(value) => base.extra.insert(field.clone(), value.clone()),
+                None => base.extra.shift_remove(field),
+            };
+            match patch.expressions.get(field) {
+                Some(expr) => base.expressions.insert(field.clone(), expr.clone()),
+                None => base.expressions.shift_remove(field),
+            };
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MergeError {
+    #[error("Override of unknown quest {name:?}")]
+    UnknownQuest { name: String, span: Span },
+    /// Two overrides merged together set the same property of a quest. Merge
+    /// them one after another to let the later one win.
+    #[error("Quest {quest:?} has `{field}` overridden twice")]
+    Conflict {
+        quest: String,
+        field: String,
+        first: Span,
+        second: Span,
+    },
+}
+
+/// The properties one override replaced, as reported by [`merge`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedOverride {
+    pub quest: String,
+    pub fields: Vec<String>,
+}
+
+/// Applies `overrides` to the quests they name, in order.
+///
+/// Nothing is changed if an override names no quest of `quests`, or if two
+/// overrides set the same property of the same quest; to layer mods, merge
+/// each mod's overrides in a separate call, in load order.
+///
+/// ```
+/// use game_quest_parser_Hodik::{merge, Parser};
+///
+/// let mut quests = Parser::new(r#"quest "Main Quest" { reward: 100, active: true }"#)?
+///     .parse_quests()?;
+/// let overrides = Parser::new(r#"quest "Main Quest" override { reward: 200 }"#)?
+///     .parse_world()?
+///     .overrides;
+/// let applied = merge(&mut quests, &overrides).unwrap();
+/// assert_eq!(applied[0].fields, ["reward"]);
+/// assert_eq!((quests[0].reward, quests[0].active), (200, true));
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn merge(
+    quests: &mut [Quest],
+    overrides: &[QuestOverride],
+) -> Result<Vec<AppliedOverride>, MergeError> {
+    let mut targets = Vec::with_capacity(overrides.len());
+    for (i, patch) in overrides.iter().enumerate() {
+        let Some(target) = quests
+            .iter()
+            .position(|quest| quest.name == patch.name && quest.package == patch.patch.package)
+        else {
+            return Err(MergeError::UnknownQuest {
+                name: patch.name.clone(),
+                span: patch.span,
+            });
+        };
+        for (earlier, &other) in overrides[..i].iter().zip(&targets) {
+            if other != target {
+                continue;
+            }
+            if let Some(field) = patch.fields.iter().find(|f| earlier.fields.contains(f)) {
+                return Err(MergeError::Conflict {
+                    quest: patch.name.clone(),
+                    field: field.clone(),
+                    first: earlier.span,
+                    second: patch.span,
+                });
+            }
+        }
+        targets.push(target);
+    }
+
+    let mut applied = Vec::with_capacity(overrides.len());
+    for (patch, target) in overrides.iter().zip(targets) {
+        patch.apply(&mut quests[target]);
+        applied.push(AppliedOverride {
+            quest: patch.name.clone(),
+            fields: patch.fields.clone(),
+        });
+    }
+    Ok(applied)
+}
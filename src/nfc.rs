@@ -0,0 +1,31 @@
+//! Unicode Normalization Form C, so text that looks the same compares the
+//! same however it was typed: `й` can be one character, U+0439, or `и`
+//! followed by a combining breve, U+0306, and both become U+0439.
+
+use alloc::borrow::Cow;
+
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+use crate::prelude::*;
+
+/// Normalizes `text` to NFC, borrowing it when it is in NFC already.
+///
+/// ```
+/// use game_quest_parser_Hodik::nfc;
+///
+/// assert_eq!(nfc("Тисячоли\u{0438}\u{0306}"), "Тисячолий");
+/// assert!(matches!(nfc("Тисячолий"), std::borrow::Cow::Borrowed(_)));
+/// ```
+pub fn nfc(text: &str) -> Cow<'_, str> {
+    // The quick check settles almost all text without normalizing it; only
+    // a `Maybe` needs the full pass to tell.
+    if is_nfc_quick(text.chars()) == IsNormalized::Yes {
+        return Cow::Borrowed(text);
+    }
+    let normalized: String = text.chars().nfc().collect();
+    if normalized == text {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(normalized)
+    }
+}
@@ -0,0 +1,23 @@
+use crate::prelude::*;
+use crate::IndexMap;
+
+use crate::Value;
+
+/// What happens when a quest ends, declared with an `on_success { ... }` or
+/// `on_failure { ... }` block.
+///
+/// ```text
+/// on_success { reward: 100, unlock: "Next Quest" }
+/// on_failure { penalty: 50 }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Outcome {
+    pub reward: i64,
+    pub penalty: i64,
+    /// Quests made available by this outcome, from repeated `unlock` keys.
+    pub unlocks: Vec<String>,
+    /// Other keys of the block, keyed by name.
+    pub extra: IndexMap<String, Value>,
+}
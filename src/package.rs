@@ -0,0 +1,65 @@
+//! Packages such as `act1.village`, which let quests of different parts of a
+//! campaign share names and ids.
+
+use crate::prelude::*;
+use crate::Quest;
+
+impl Quest {
+    /// The quest's id, or else its name, behind its package:
+    /// `act1.village/find_sword`. Quests outside any package keep the bare id
+    /// or name.
+    pub fn qualified_id(&self) -> String {
+        qualify(
+            self.package.as_deref(),
+            self.id.as_deref().unwrap_or(&self.name),
+        )
+    }
+
+    /// The quest's name behind its package, as [`Quest::qualified_id`] but
+    /// ignoring the id.
+    pub fn qualified_name(&self) -> String {
+        qualify(self.package.as_deref(), &self.name)
+    }
+}
+
+/// `local` behind `package` and a `/`, or `local` itself without a package.
+pub(crate) fn qualify(package: Option<&str>, local: &str) -> String {
+    match package {
+        Some(package) => format!("{package}/{local}"),
+        None => local.to_string(),
+    }
+}
+
+/// The qualified ids or names a `requires` or `unlock` of a quest in
+/// `package` may refer to, in the order they are tried.
+///
+/// A reference is absolute when it holds a `/`, as `act1.village/find_sword`
+/// does, and `/intro` names a quest outside any package. Any other reference
+/// is relative: it names a quest of the referring quest's own package, or
+/// else one outside any package. Absolute references are tried as relative
+/// ones too, so quest names holding a `/` still resolve.
+#[cfg(feature = "std")]
+pub(crate) fn candidates(reference: &str, package: Option<&str>) -> Vec<String> {
+    let mut candidates = Vec::with_capacity(3);
+    if let Some(rest) = reference.strip_prefix('/') {
+        candidates.push(rest.to_string());
+    } else if reference.contains('/') {
+        candidates.push(reference.to_string());
+    }
+    if let Some(package) = package {
+        candidates.push(qualify(Some(package), reference));
+    }
+    if !candidates.iter().any(|c| c == reference) {
+        candidates.push(reference.to_string());
+    }
+    candidates
+}
+
+/// Whether `name` is a package name: identifiers joined by dots.
+pub(crate) fn is_valid_package(name: &str) -> bool {
+    name.split('.').all(|part| {
+        let mut chars = part.chars();
+        chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
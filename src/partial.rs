@@ -0,0 +1,643 @@
+//! A tolerant reading of the text before an editor's cursor, for completion
+//! engines that need to know what may be typed there while the file does not
+//! parse yet.
+
+use crate::prelude::*;
+use crate::{nfc, Lexer, ParseError, Parser, Span, Token};
+
+/// What [`Parser::parse_partial`] found at the cursor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialResult {
+    pub context: CompletionContext,
+    /// The part of the word or string being typed that is before the cursor,
+    /// which a completion replaces. Empty after whitespace or punctuation.
+    pub prefix: String,
+    /// Where [`PartialResult::prefix`] is in the input; it ends at the cursor.
+    pub prefix_span: Span,
+    /// Whether the cursor is inside a string literal. The prefix then holds
+    /// the text after the opening quote.
+    pub in_string: bool,
+    /// Names of the quests defined anywhere in the input, for completing
+    /// `requires` and `unlock` values.
+    pub quests: Vec<String>,
+}
+
+/// Where the cursor is in the grammar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompletionContext {
+    /// Between top-level items, where `quest`, `let`, `include`, `npc`,
+    /// `item` or `template` goes.
+    TopLevel,
+    /// In the header of a `quest`, `npc`, `item` or `template` definition,
+    /// before its body. `name` is `None` until the name is written.
+    Header {
+        keyword: String,
+        name: Option<String>,
+    },
+    /// Where a property key goes. `present` lists the keys already set in
+    /// the same body, in source order.
+    PropertyKey {
+        scope: PropertyScope,
+        present: Vec<String>,
+    },
+    /// After `key:`, where a value of `kind` goes or is being written.
+    PropertyValue {
+        scope: PropertyScope,
+        key: String,
+        kind: ValueKind,
+    },
+    /// Inside the `[...]` after a step, where `optional`, `hidden` or
+    /// `order` goes.
+    StepAttribute { quest: String },
+    /// Nowhere completions make sense: a comment, a raw block, a `let`,
+    /// `include` or pragma, or text the reader cannot follow.
+    None,
+}
+
+/// The body a property belongs to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyScope {
+    /// `quest`, `npc`, `item` or `template`.
+    pub keyword: String,
+    pub name: String,
+    /// The block property the cursor is in, such as `objective` for
+    /// `objective { ... }`, or `None` directly in the body.
+    pub block: Option<String>,
+}
+
+/// The kind of value a property expects, for suggesting values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueKind {
+    String,
+    Number,
+    /// `true`, `false` or a condition such as `player_level > 5`.
+    Bool,
+    Duration,
+    DateTime,
+    Range,
+    List(Box<ValueKind>),
+    /// The name of another quest.
+    QuestName,
+    /// An NPC reference such as `npc("Elder") at "Village Square"`.
+    NpcRef,
+    /// The `kind` of an objective, such as `kill` or `talk_to`.
+    ObjectiveKind,
+    /// Objective names joined by `&&` and `||`.
+    Condition,
+    Any,
+}
+
+impl Parser<'_> {
+    /// Reads `input` up to the byte offset `cursor`, tolerating the
+    /// unfinished text an editor holds while the user types, and reports the
+    /// grammatical context at the cursor. Nothing is evaluated and no error
+    /// is raised; text the reader cannot follow gives
+    /// [`CompletionContext::None`].
+    ///
+    /// ```
+    /// use game_quest_parser_Hodik::{CompletionContext, Parser, ValueKind};
+    ///
+    /// let input = "quest \"Intro\" {}\nquest \"Next\" {\n    reward: 10\n    requires: \"In";
+    /// let result = Parser::parse_partial(input, input.len());
+    /// let CompletionContext::PropertyValue { key, kind, .. } = result.context else {
+    ///     panic!("expected a value context");
+    /// };
+    /// assert_eq!((key.as_str(), kind), ("requires", ValueKind::QuestName));
+    /// assert_eq!((result.prefix.as_str(), result.in_string), ("In", true));
+    /// assert_eq!(result.quests, ["Intro", "Next"]);
+    /// ```
+    pub fn parse_partial(input: &str, cursor: usize) -> PartialResult {
+        let mut cursor = cursor.min(input.len());
+        while !input.is_char_boundary(cursor) {
+            cursor -= 1;
+        }
+        let before = &input[..cursor];
+        let (mut tokens, end) = tolerant_tokens(before);
+
+        let mut result = PartialResult {
+            context: CompletionContext::None,
+            prefix: String::new(),
+            prefix_span: Span::new(cursor, cursor),
+            in_string: false,
+            quests: quest_names(&tolerant_tokens(input).0),
+        };
+        match end {
+            End::Complete => {}
+            End::InString(start) => {
+                result.in_string = true;
+                result.prefix = before[start + 1..].to_string();
+                result.prefix_span = Span::new(start + 1, cursor);
+            }
+            End::Stuck => return result,
+        }
+        match tokens.last() {
+            Some((Token::Comment(_), span)) if span.end == cursor => {
+                return result;
+            }
+            Some((token, span)) if !result.in_string && span.end == cursor && is_word(token) => {
+                result.prefix = before[span.start..].to_string();
+                result.prefix_span = *span;
+                tokens.pop();
+            }
+            _ => {}
+        }
+        let mut reader = Reader::default();
+        for (token, span) in &tokens {
+            reader.read(token, *span, before);
+        }
+        result.context = reader.context(&before[..result.prefix_span.start]);
+        result
+    }
+}
+
+/// How lexing the text before the cursor ended.
+enum End {
+    Complete,
+    /// In a string opened at this offset.
+    InString(usize),
+    /// At an error the lexer cannot resume after, such as an unterminated raw
+    /// block.
+    Stuck,
+}
+
+/// The tokens of `input` without comments other than the last, skipping over
+/// malformed tokens that have a span to resume after.
+fn tolerant_tokens(input: &str) -> (Vec<(Token<'_>, Span)>, End) {
+    let mut tokens = Vec::new();
+    let mut offset = 0;
+    let mut lexer = Lexer::new(input);
+    loop {
+        match lexer.next_token() {
+            Ok((Token::Eof, _)) => return (tokens, End::Complete),
+            Ok((token, span)) => {
+                if let Some((Token::Comment(_), _)) = tokens.last() {
+                    tokens.pop();
+                }
+                tokens.push((token, Span::new(span.start + offset, span.end + offset)));
+            }
+            Err(ParseError::UnterminatedString { started_at }) => {
+                return (tokens, End::InString(started_at.start + offset));
+            }
+            Err(error) => match error.span() {
+                Some(span)
+                    if span.end > 0 && !matches!(error, ParseError::UnterminatedRaw { .. }) =>
+                {
+                    offset += span.end;
+                    lexer = Lexer::new(&input[offset..]);
+                }
+                _ => return (tokens, End::Stuck),
+            },
+        }
+    }
+}
+
+fn quest_names(tokens: &[(Token, Span)]) -> Vec<String> {
+    tokens
+        .windows(2)
+        .filter_map(|pair| match pair {
+            [(Token::QuestKeyword, _), (name, _)] => name_of(name),
+            _ => None,
+        })
+        .collect()
+}
+
+fn name_of(token: &Token) -> Option<String> {
+    match token {
+        Token::Identifier(name) => Some(nfc(name).into_owned()),
+        Token::StringLiteral(name) => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+/// Whether `token` may be the start of a longer word being typed.
+fn is_word(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_)
+            | Token::Variable(_)
+            | Token::QuestKeyword
+            | Token::LetKeyword
+            | Token::IncludeKeyword
+            | Token::True
+            | Token::False
+    )
+}
+
+/// Whether `token` ends an operand, so that a value may be complete after it.
+fn ends_operand(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_)
+            | Token::Variable(_)
+            | Token::StringLiteral(_)
+            | Token::Number(_)
+            | Token::Float(_)
+            | Token::Duration(_)
+            | Token::DateTime(_)
+            | Token::Raw { .. }
+            | Token::True
+            | Token::False
+            | Token::RBrace
+            | Token::RBracket
+            | Token::RParen
+    )
+}
+
+/// A token-by-token reader following just enough of the grammar to know the
+/// context at the end of its input.
+#[derive(Default)]
+struct Reader {
+    top: Top,
+    bodies: Vec<Body>,
+}
+
+#[derive(Default)]
+enum Top {
+    #[default]
+    Item,
+    /// After a definition keyword, before the name.
+    Name(String),
+    Header {
+        keyword: String,
+        name: String,
+        /// Open parentheses of a template's parameters.
+        depth: usize,
+    },
+    Skip(Skip),
+}
+
+/// A declaration, pragma, attribute or template instantiation being passed
+/// over: `operands` more operands at depth 0, counting a bracketed group as
+/// one, end it.
+struct Skip {
+    operands: usize,
+    depth: usize,
+}
+
+impl Skip {
+    fn new(operands: usize) -> Self {
+        Skip { operands, depth: 0 }
+    }
+
+    /// Reads a token, returning whether the skipped text is over.
+    fn read(&mut self, token: &Token) -> bool {
+        match token {
+            Token::LBrace | Token::LBracket | Token::LParen => self.depth += 1,
+            Token::RBrace | Token::RBracket | Token::RParen if self.depth > 0 => {
+                self.depth -= 1;
+                if self.depth == 0 {
+                    self.operands = self.operands.saturating_sub(1);
+                }
+            }
+            token if self.depth == 0 && ends_operand(token) => {
+                self.operands = self.operands.saturating_sub(1);
+            }
+            _ => {}
+        }
+        self.operands == 0
+    }
+}
+
+struct Body {
+    scope: PropertyScope,
+    present: Vec<String>,
+    state: BodyState,
+}
+
+enum BodyState {
+    Key,
+    /// After a key, before its `:` or block.
+    AfterKey(String),
+    Value(Value),
+    Skip(Skip),
+}
+
+struct Value {
+    key: String,
+    /// Brackets opened inside the value, innermost last.
+    nesting: Vec<Nesting>,
+    /// The last token of the value at depth 0, if it ended an operand, with
+    /// where it ended and whether it was an identifier.
+    operand: Option<(usize, Option<String>)>,
+    /// Whether the last token inside a step's attributes was `:`.
+    after_colon: bool,
+}
+
+#[derive(PartialEq)]
+enum Nesting {
+    Group,
+    StepAttributes,
+}
+
+impl Reader {
+    fn read(&mut self, token: &Token, span: Span, source: &str) {
+        let Some(body) = self.bodies.last_mut() else {
+            self.read_top(token);
+            return;
+        };
+        match &mut body.state {
+            BodyState::Key => match token {
+                Token::RBrace => {
+                    self.bodies.pop();
+                }
+                Token::Hash => body.state = BodyState::Skip(Skip::new(1)),
+                token => {
+                    if let Some(key) = key_of(token) {
+                        body.state = BodyState::AfterKey(key);
+                    }
+                }
+            },
+            BodyState::AfterKey(key) => match token {
+                Token::Colon => {
+                    let key = core::mem::take(key);
+                    body.state = BodyState::Value(Value::new(key));
+                }
+                Token::LBrace => {
+                    let key = core::mem::take(key);
+                    self.open_block(key);
+                }
+                // `repeat(3) step: ...`
+                Token::LParen => body.state = BodyState::Skip(Skip::new(1)),
+                // `reward tier "gold" { ... }` takes amounts as `reward { ... }` does.
+                Token::Identifier("tier") if key == "reward" => {
+                    body.state = BodyState::AfterKey("reward tier".to_string());
+                }
+                token if key == "reward tier" && name_of(token).is_some() => {
+                    body.state = BodyState::AfterKey("reward".to_string());
+                }
+                Token::RBrace => {
+                    self.bodies.pop();
+                }
+                _ => body.state = BodyState::Key,
+            },
+            BodyState::Value(value) => {
+                if let Some(next) = value.read(token, span, source) {
+                    let key = core::mem::take(&mut value.key);
+                    body.present.push(key);
+                    match next {
+                        Next::Key => body.state = BodyState::Key,
+                        Next::AfterKey(key) => body.state = BodyState::AfterKey(key),
+                        Next::Value(key) => body.state = BodyState::Value(Value::new(key)),
+                        Next::Block(key) => self.open_block(key),
+                        Next::Close => {
+                            self.bodies.pop();
+                        }
+                    }
+                }
+            }
+            BodyState::Skip(skip) => {
+                if skip.read(token) {
+                    body.state = BodyState::Key;
+                }
+            }
+        }
+    }
+
+    fn read_top(&mut self, token: &Token) {
+        match &mut self.top {
+            Top::Item => {
+                self.top = match token {
+                    Token::QuestKeyword => Top::Name("quest".to_string()),
+                    Token::Identifier(keyword @ ("npc" | "item" | "template")) => {
+                        Top::Name(keyword.to_string())
+                    }
+                    Token::LetKeyword => Top::Skip(Skip::new(2)),
+                    Token::IncludeKeyword | Token::Hash | Token::Identifier("package") => {
+                        Top::Skip(Skip::new(1))
+                    }
+                    _ => Top::Item,
+                }
+            }
+            Top::Name(keyword) => match name_of(token) {
+                Some(name) => {
+                    self.top = Top::Header {
+                        keyword: core::mem::take(keyword),
+                        name,
+                        depth: 0,
+                    }
+                }
+                None => self.top = Top::Item,
+            },
+            Top::Header {
+                keyword,
+                name,
+                depth,
+            } => match token {
+                Token::LParen => *depth += 1,
+                Token::RParen => *depth = depth.saturating_sub(1),
+                Token::LBrace if *depth == 0 => {
+                    self.bodies.push(Body {
+                        scope: PropertyScope {
+                            keyword: core::mem::take(keyword),
+                            name: core::mem::take(name),
+                            block: None,
+                        },
+                        present: Vec::new(),
+                        state: BodyState::Key,
+                    });
+                    self.top = Top::Item;
+                }
+                // `quest "A" from Template(...)`
+                Token::Identifier("from") => self.top = Top::Skip(Skip::new(2)),
+                _ => {}
+            },
+            Top::Skip(skip) => {
+                // `#version 2` takes a second operand; `#[...]` is one group.
+                if skip.depth == 0 && token == &Token::Identifier("version") {
+                    skip.operands = 2;
+                }
+                if skip.read(token) {
+                    self.top = Top::Item;
+                }
+            }
+        }
+    }
+
+    fn open_block(&mut self, key: String) {
+        let body = self.bodies.last_mut().expect("a block opens inside a body");
+        body.present.push(key.clone());
+        body.state = BodyState::Key;
+        let scope = PropertyScope {
+            block: Some(key),
+            ..body.scope.clone()
+        };
+        self.bodies.push(Body {
+            scope,
+            present: Vec::new(),
+            state: BodyState::Key,
+        });
+    }
+
+    /// The context after the tokens read so far; `before` is the input up to
+    /// the prefix.
+    fn context(self, before: &str) -> CompletionContext {
+        let Some(body) = self.bodies.into_iter().last() else {
+            return match self.top {
+                Top::Item => CompletionContext::TopLevel,
+                Top::Name(keyword) => CompletionContext::Header {
+                    keyword,
+                    name: None,
+                },
+                Top::Header { keyword, name, .. } => CompletionContext::Header {
+                    keyword,
+                    name: Some(name),
+                },
+                Top::Skip(_) => CompletionContext::None,
+            };
+        };
+        match body.state {
+            BodyState::Key => CompletionContext::PropertyKey {
+                scope: body.scope,
+                present: body.present,
+            },
+            BodyState::Value(value) => {
+                if value.nesting.last() == Some(&Nesting::StepAttributes) {
+                    if !value.after_colon {
+                        return CompletionContext::StepAttribute {
+                            quest: body.scope.name,
+                        };
+                    }
+                    let scope = PropertyScope {
+                        block: Some("step".to_string()),
+                        ..body.scope
+                    };
+                    return CompletionContext::PropertyValue {
+                        scope,
+                        key: "order".to_string(),
+                        kind: ValueKind::Number,
+                    };
+                }
+                match value.operand {
+                    // After a complete value, a new line starts the next key.
+                    Some((end, _)) if value.nesting.is_empty() && before[end..].contains('\n') => {
+                        let mut present = body.present;
+                        present.push(value.key);
+                        CompletionContext::PropertyKey {
+                            scope: body.scope,
+                            present,
+                        }
+                    }
+                    _ => CompletionContext::PropertyValue {
+                        kind: value_kind(&body.scope, &value.key),
+                        scope: body.scope,
+                        key: value.key,
+                    },
+                }
+            }
+            BodyState::AfterKey(_) | BodyState::Skip(_) => CompletionContext::None,
+        }
+    }
+}
+
+/// What follows a value that has ended.
+enum Next {
+    Key,
+    AfterKey(String),
+    Value(String),
+    Block(String),
+    Close,
+}
+
+impl Value {
+    fn new(key: String) -> Self {
+        Value {
+            key,
+            nesting: Vec::new(),
+            operand: None,
+            after_colon: false,
+        }
+    }
+
+    /// Reads a token of the value, or returns what follows it if the token
+    /// is past its end.
+    fn read(&mut self, token: &Token, span: Span, source: &str) -> Option<Next> {
+        if let Some(nesting) = self.nesting.last() {
+            if *nesting == Nesting::StepAttributes {
+                self.after_colon = token == &Token::Colon;
+            }
+            match token {
+                Token::LBrace | Token::LBracket | Token::LParen => {
+                    self.nesting.push(Nesting::Group)
+                }
+                Token::RBrace | Token::RBracket | Token::RParen => {
+                    self.nesting.pop();
+                    if self.nesting.is_empty() {
+                        self.operand = Some((span.end, None));
+                    }
+                }
+                _ => {}
+            }
+            return None;
+        }
+        let operand = self.operand.take();
+        match (token, operand) {
+            (Token::Comma, _) => Some(Next::Key),
+            (Token::RBrace, _) => Some(Next::Close),
+            // A key written after the value without a comma.
+            (Token::Colon, Some((_, Some(key)))) => Some(Next::Value(key)),
+            (Token::LBrace, Some((_, Some(key)))) => Some(Next::Block(key)),
+            (Token::LBracket, Some(_)) if self.key == "step" => {
+                self.nesting.push(Nesting::StepAttributes);
+                None
+            }
+            // On a new line a word after a complete value is the next key; on
+            // the same line it continues the value, as `gold` in
+            // `reward: 100 gold`.
+            (token, Some((end, _))) if source[end..span.start].contains('\n') => {
+                match key_of(token) {
+                    Some(key) => Some(Next::AfterKey(key)),
+                    None => self.read(token, span, source),
+                }
+            }
+            (Token::LBrace | Token::LBracket | Token::LParen, _) => {
+                self.nesting.push(Nesting::Group);
+                None
+            }
+            (token, _) => {
+                if ends_operand(token) {
+                    let ident = match token {
+                        Token::Identifier(name) => Some(name.to_string()),
+                        _ => None,
+                    };
+                    self.operand = Some((span.end, ident));
+                }
+                None
+            }
+        }
+    }
+}
+
+/// The key a token is when written where a property key goes.
+fn key_of(token: &Token) -> Option<String> {
+    match token {
+        Token::Identifier(key) => Some(nfc(key).into_owned()),
+        Token::StringLiteral(key) => Some(key.to_string()),
+        _ => None,
+    }
+}
+
+/// The kind of value `key` takes in `scope`.
+fn value_kind(scope: &PropertyScope, key: &str) -> ValueKind {
+    if !matches!(scope.keyword.as_str(), "quest" | "template") {
+        return ValueKind::Any;
+    }
+    match (scope.block.as_deref(), key) {
+        (None, "id" | "step") => ValueKind::String,
+        (None, "reward") => ValueKind::Number,
+        (None, "active") => ValueKind::Bool,
+        (None, "steps" | "tags") => ValueKind::List(Box::new(ValueKind::String)),
+        (None, "requires") => ValueKind::QuestName,
+        (None, "giver" | "turn_in") => ValueKind::NpcRef,
+        (None, "level") => ValueKind::Range,
+        (None, "starts_at" | "ends_at") => ValueKind::DateTime,
+        (None, "time_limit") => ValueKind::Duration,
+        (None, "complete_when") => ValueKind::Condition,
+        (Some("objective"), "kind") => ValueKind::ObjectiveKind,
+        (Some("objective"), "target") => ValueKind::String,
+        (Some("objective"), "count") => ValueKind::Number,
+        (Some("on_success" | "on_failure"), "reward" | "penalty") => ValueKind::Number,
+        (Some("on_success" | "on_failure"), "unlock") => ValueKind::QuestName,
+        (Some("reward"), _) => ValueKind::Number,
+        _ => ValueKind::Any,
+    }
+}
@@ -0,0 +1,100 @@
+/// A property key the parser gives meaning to inside a quest body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PropertyInfo {
+    pub name: &'static str,
+    /// Short description of the expected value, suitable for hover text.
+    pub doc: &'static str,
+}
+
+/// Every property key the parser recognises in a quest body.
+pub const KNOWN_PROPERTIES: &[PropertyInfo] = &[
+    PropertyInfo {
+        name: "id",
+        doc: "Stable identifier of the quest, such as `\"main_01\"`, that stays the same when the quest is renamed. Expects a string; must be unique across all loaded quests.",
+    },
+    PropertyInfo {
+        name: "active",
+        doc: "Whether the quest is available. Expects `true`, `false` or a condition such as `player_level > 5`.",
+    },
+    PropertyInfo {
+        name: "reward",
+        doc: "Reward for completing the quest. Expects an integer or an expression such as `100 + level * 10`; with `#version 2`, also amounts in currencies such as `100 gold, 5 gems` or a block such as `reward { gold: 100, gems: 5 }`. `reward tier \"gold\" { gold: 500 }` declares a named tier, for quests paying out by how well they were completed.",
+    },
+    PropertyInfo {
+        name: "step",
+        doc: "One step of the quest, in order. Expects a string or a localized string such as `{ en: \"Find the sword\", uk: \"Знайди меч\" }`, optionally followed by attributes such as `[optional, hidden, order: 2]`; can be repeated.",
+    },
+    PropertyInfo {
+        name: "repeat",
+        doc: "Adds a step several times, numbered from 1: `repeat(3) step: \"Defeat wave {n}\"` adds `Defeat wave 1` to `Defeat wave 3`.",
+    },
+    PropertyInfo {
+        name: "steps",
+        doc: "Several steps at once. Expects a list of strings, or a block grouping every step of the quest such as `steps { ordered { \"A\", \"B\" } any_of { \"C\", \"D\" } }`; groups are `ordered`, `any_of` and `all_of`.",
+    },
+    PropertyInfo {
+        name: "requires",
+        doc: "Name of a quest that must be completed first. Expects a string; can be repeated.",
+    },
+    PropertyInfo {
+        name: "tags",
+        doc: "Labels for grouping quests, such as `tags: [\"main\", \"act1\"]`. Expects a list of strings.",
+    },
+    PropertyInfo {
+        name: "giver",
+        doc: "NPC who hands out the quest, such as `npc(\"Elder\") at \"Village Square\"`. The location is optional.",
+    },
+    PropertyInfo {
+        name: "turn_in",
+        doc: "NPC the player returns to when done, such as `npc(\"Elder\")`, optionally followed by `at` and a location. Defaults to the giver.",
+    },
+    PropertyInfo {
+        name: "level",
+        doc: "Player levels the quest is meant for, such as `level: 10..20`, both inclusive. Expects a range or a single level.",
+    },
+    PropertyInfo {
+        name: "starts_at",
+        doc: "When a limited-time event quest becomes available. Expects a date and time such as `2025-10-31T00:00:00Z`, or with an offset such as `+02:00` instead of `Z`.",
+    },
+    PropertyInfo {
+        name: "ends_at",
+        doc: "When a limited-time event quest stops being available. Expects a date and time after `starts_at`.",
+    },
+    PropertyInfo {
+        name: "time_limit",
+        doc: "Time the player has to complete the quest. Expects a duration such as `2h30m`, using the units `d`, `h`, `m` and `s`.",
+    },
+    PropertyInfo {
+        name: "objective",
+        doc: "Structured objective block: `objective { kind: \"kill\", target: \"Goblin\", count: 10 }`.",
+    },
+    PropertyInfo {
+        name: "complete_when",
+        doc: "When the quest counts as completed: objective names joined by `&&` and `||`, such as `kill_goblins && (find_key || bribe_guard)`.",
+    },
+    PropertyInfo {
+        name: "stages",
+        doc: "Stage flow block: `stages { start -> \"find\", \"find\" -> end }`. Every stage must be reachable from `start`.",
+    },
+    PropertyInfo {
+        name: "on_success",
+        doc: "What completing the quest does: `on_success { reward: 100, unlock: \"Next Quest\" }`. `unlock` can be repeated.",
+    },
+    PropertyInfo {
+        name: "on_failure",
+        doc: "What failing the quest does: `on_failure { penalty: 50 }`.",
+    },
+    PropertyInfo {
+        name: "on_start",
+        doc: "Engine actions run when the quest starts, in order: `on_start { give_item: \"Map\", play_sound: \"fanfare\" }`. A list passes several arguments.",
+    },
+    PropertyInfo {
+        name: "on_complete",
+        doc: "Engine actions run when the quest is completed, in order: `on_complete { spawn: [\"wolf\", 3] }`. A list passes several arguments.",
+    },
+];
+
+/// Looks up a known property by key.
+pub fn property_info(name: &str) -> Option<&'static PropertyInfo> {
+    KNOWN_PROPERTIES.iter().find(|p| p.name == name)
+}
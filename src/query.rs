@@ -0,0 +1,273 @@
+use crate::expr::type_name;
+use crate::{Context, EvalError, Expr, Quest, Value};
+
+/// A parsed quest pack that can be sliced with chainable filters.
+///
+/// Every filter returns a [`QuestQuery`] borrowing the set, which can be
+/// narrowed further and keeps the quests in their original order.
+///
+/// ```
+/// use game_quest_parser_Hodik::{Parser, QuestSet};
+///
+/// let quests = QuestSet::new(Parser::new(
+///     r#"quest "Intro" { tags: ["main", "act1"], active: true, reward: 50 }
+///        quest "Wolves" { tags: ["side", "act1"], active: true, reward: 200 }
+///        quest "Finale" { tags: ["main"], reward: 1000 }"#,
+/// )?
+/// .parse_quests()?);
+/// let act1 = quests.by_tag("act1").active().reward_at_least(100);
+/// assert_eq!(act1.names(), ["Wolves"]);
+/// assert_eq!(quests.tags(), ["act1", "main", "side"]);
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuestSet {
+    quests: Vec<Quest>,
+}
+
+impl QuestSet {
+    pub fn new(quests: impl IntoIterator<Item = Quest>) -> Self {
+        Self {
+            quests: quests.into_iter().collect(),
+        }
+    }
+
+    /// Every quest, in the order they were added.
+    pub fn quests(&self) -> &[Quest] {
+        &self.quests
+    }
+
+    pub fn into_quests(self) -> Vec<Quest> {
+        self.quests
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Quest> {
+        self.quests.iter().find(|quest| quest.name == name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.quests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quests.is_empty()
+    }
+
+    /// Every tag used by a quest of the set, sorted and without repeats.
+    pub fn tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self
+            .quests
+            .iter()
+            .flat_map(|quest| quest.tags.iter().map(String::as_str))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    /// A query matching every quest, to start a chain of filters from.
+    pub fn query(&self) -> QuestQuery<'_> {
+        QuestQuery {
+            quests: self.quests.iter().collect(),
+        }
+    }
+
+    /// See [`QuestQuery::by_tag`].
+    pub fn by_tag(&self, tag: &str) -> QuestQuery<'_> {
+        self.query().by_tag(tag)
+    }
+
+    /// See [`QuestQuery::active`].
+    pub fn active(&self) -> QuestQuery<'_> {
+        self.query().active()
+    }
+
+    /// See [`QuestQuery::reward_at_least`].
+    pub fn reward_at_least(&self, amount: i64) -> QuestQuery<'_> {
+        self.query().reward_at_least(amount)
+    }
+
+    /// See [`QuestQuery::matching`].
+    pub fn matching(&self, filter: &Expr) -> Result<QuestQuery<'_>, EvalError> {
+        self.query().matching(filter)
+    }
+
+    /// See [`QuestQuery::filter`].
+    pub fn filter(&self, predicate: impl FnMut(&Quest) -> bool) -> QuestQuery<'_> {
+        self.query().filter(predicate)
+    }
+}
+
+impl From<Vec<Quest>> for QuestSet {
+    fn from(quests: Vec<Quest>) -> Self {
+        Self { quests }
+    }
+}
+
+impl FromIterator<Quest> for QuestSet {
+    fn from_iter<I: IntoIterator<Item = Quest>>(quests: I) -> Self {
+        Self::new(quests)
+    }
+}
+
+impl<'a> IntoIterator for &'a QuestSet {
+    type Item = &'a Quest;
+    type IntoIter = std::slice::Iter<'a, Quest>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.quests.iter()
+    }
+}
+
+/// The quests of a [`QuestSet`] that passed every filter applied so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuestQuery<'a> {
+    quests: Vec<&'a Quest>,
+}
+
+impl<'a> QuestQuery<'a> {
+    /// Keeps the quests tagged `tag`.
+    pub fn by_tag(self, tag: &str) -> Self {
+        self.filter(|quest| quest.has_tag(tag))
+    }
+
+    /// Keeps the quests that are `active: true`. A quest whose `active`
+    /// depends on game state is not active until evaluated.
+    pub fn active(self) -> Self {
+        self.filter(|quest| quest.active)
+    }
+
+    /// Keeps the quests whose plain `reward` is at least `amount`. Rewards in
+    /// currencies and rewards given as an expression are not counted.
+    pub fn reward_at_least(self, amount: i64) -> Self {
+        self.filter(|quest| quest.reward >= amount)
+    }
+
+    /// Keeps the quests `filter` is true for, see [`Quest::matches`].
+    pub fn matching(self, filter: &Expr) -> Result<Self, EvalError> {
+        let mut error = None;
+        let query = self.filter(|quest| match quest.matches(filter) {
+            Ok(matches) => matches,
+            Err(e) => {
+                error.get_or_insert(e);
+                false
+            }
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(query),
+        }
+    }
+
+    /// Keeps the quests `predicate` returns `true` for.
+    pub fn filter(mut self, mut predicate: impl FnMut(&Quest) -> bool) -> Self {
+        self.quests.retain(|quest| predicate(quest));
+        self
+    }
+
+    /// The matching quests, in the order of the set.
+    pub fn quests(&self) -> &[&'a Quest] {
+        &self.quests
+    }
+
+    /// Names of the matching quests, in the order of the set.
+    pub fn names(&self) -> Vec<&'a str> {
+        self.quests
+            .iter()
+            .map(|quest| quest.name.as_str())
+            .collect()
+    }
+
+    pub fn first(&self) -> Option<&'a Quest> {
+        self.quests.first().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.quests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quests.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a Quest> + '_ {
+        self.quests.iter().copied()
+    }
+}
+
+impl<'a> IntoIterator for QuestQuery<'a> {
+    type Item = &'a Quest;
+    type IntoIter = std::vec::IntoIter<&'a Quest>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.quests.into_iter()
+    }
+}
+
+impl Quest {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Evaluates `filter` with the quest's properties as its variables, see
+    /// the [`Context`] implementation for their names. A quest without a
+    /// property the filter names does not match; a filter that is not a
+    /// boolean, or applies an operator to values it does not take, fails.
+    ///
+    /// ```
+    /// use game_quest_parser_Hodik::{parse_expr, Parser};
+    ///
+    /// let quest = Parser::new(r#"quest "Wolves" { active: true, reward: 200, level: 5..10 }"#)?
+    ///     .parse_quest()?;
+    /// assert!(quest.matches(&parse_expr("reward > 100 && active")?)?);
+    /// assert!(!quest.matches(&parse_expr("min_level >= 10")?)?);
+    /// assert!(!quest.matches(&parse_expr("id == \"wolves\"")?)?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn matches(&self, filter: &Expr) -> Result<bool, EvalError> {
+        match filter.evaluate(self) {
+            Ok(Value::Bool(matches)) => Ok(matches),
+            Ok(other) => Err(EvalError::ExpectedBool(type_name(&other))),
+            Err(EvalError::UnknownVariable(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Looks up a quest's properties by name: `name`, `id`, `reward`, `active`,
+/// `steps` (their number), `tags` (a list), `level` (a range) with
+/// `min_level` and `max_level`, `time_limit`, `starts_at`, `ends_at`, then
+/// extra properties, then the amounts of [currency rewards](Quest::rewards)
+/// by currency name. `reward` and `active` given as an expression, and
+/// properties the quest does not set, are unknown.
+impl Context for Quest {
+    fn lookup(&self, name: &str) -> Option<Value> {
+        let value = match name {
+            "name" => Value::String(self.name.clone()),
+            "id" => Value::String(self.id.clone()?),
+            "reward" if !self.expressions.contains_key("reward") => Value::Number(self.reward),
+            "active" if !self.expressions.contains_key("active") => Value::Bool(self.active),
+            "reward" | "active" => return None,
+            "steps" => Value::Number(self.steps.len() as i64),
+            "tags" => Value::List(self.tags.iter().cloned().map(Value::String).collect()),
+            "level" => {
+                let (min, max) = self.level_range?;
+                Value::Range(min, max)
+            }
+            "min_level" => Value::Number(self.level_range?.0),
+            "max_level" => Value::Number(self.level_range?.1),
+            "time_limit" => Value::Duration(self.time_limit?),
+            "starts_at" => Value::DateTime(self.starts_at?),
+            "ends_at" => Value::DateTime(self.ends_at?),
+            _ => match self.extra.get(name) {
+                Some(value) => value.clone(),
+                None => self
+                    .rewards
+                    .iter()
+                    .find(|(currency, _)| currency.name() == name)
+                    .map(|&(_, amount)| Value::Number(amount))?,
+            },
+        };
+        Some(value)
+    }
+}
@@ -0,0 +1,148 @@
+//! Rewrites of quest source for the `refactor` command. They edit the text
+//! under the [lossless syntax tree](crate::ast), so comments, blank lines and
+//! the layout of everything they do not touch stay as written.
+
+use crate::ast::{self, Property, QuestNode, SyntaxTree};
+use crate::writer::quoted;
+use crate::{Lexer, ParseError, Span, Token};
+
+/// Rewritten source with the number of places that changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rewrite {
+    pub source: String,
+    pub changes: usize,
+}
+
+/// Numbers the `order` attributes of each quest's steps 1, 2, 3, ... in the
+/// order they already list the steps in. Steps sharing an `order` keep
+/// sharing one, and steps without one are left alone.
+///
+/// ```
+/// use game_quest_parser_Hodik::renumber_steps;
+///
+/// let source = "quest \"A\" {\n    step: \"Dig\" [order: 20], // last\n    step: \"Look\" [order: 10],\n}\n";
+/// let rewrite = renumber_steps(source)?;
+/// assert_eq!(
+///     rewrite.source,
+///     "quest \"A\" {\n    step: \"Dig\" [order: 2], // last\n    step: \"Look\" [order: 1],\n}\n"
+/// );
+/// assert_eq!(rewrite.changes, 2);
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn renumber_steps(source: &str) -> Result<Rewrite, ParseError> {
+    let tree = ast::parse(source)?;
+    let mut edits = Vec::new();
+    for quest in tree.quests() {
+        let mut orders = Vec::new();
+        for property in &quest.properties {
+            if property.key == "step" || property.key == "steps" {
+                orders.extend(step_orders(&tree, property)?);
+            }
+        }
+        let mut values: Vec<i64> = orders.iter().map(|&(_, n)| n).collect();
+        values.sort_unstable();
+        values.dedup();
+        for (span, n) in orders {
+            let rank = values
+                .binary_search(&n)
+                .expect("every order is in the list");
+            edits.push((span, (rank + 1).to_string()));
+        }
+    }
+    Ok(apply(&tree, edits))
+}
+
+/// Renames the quests called `old` to `new`, and points every `requires:`
+/// naming `old` at `new`. Quests in other files that require `old` need the
+/// same rewrite.
+///
+/// ```
+/// use game_quest_parser_Hodik::rename_quest;
+///
+/// let source = "quest Intro { reward: 10 }\n// Needs the intro\nquest \"Finale\" { requires: \"Intro\" }\n";
+/// let rewrite = rename_quest(source, "Intro", "Prologue")?;
+/// assert_eq!(
+///     rewrite.source,
+///     "quest Prologue { reward: 10 }\n// Needs the intro\nquest \"Finale\" { requires: \"Prologue\" }\n"
+/// );
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn rename_quest(source: &str, old: &str, new: &str) -> Result<Rewrite, ParseError> {
+    let tree = ast::parse(source)?;
+    let mut edits = Vec::new();
+    for quest in tree.quests() {
+        if quest.name == old {
+            edits.push((quest.name_span, name_like(&tree, quest, new)));
+        }
+        for property in quest.properties.iter().filter(|p| p.key == "requires") {
+            let tokens = tokens(&tree, property)?;
+            if let [(Token::StringLiteral(name), _)] = tokens.as_slice() {
+                if name == old {
+                    edits.push((property.value_span, quoted(new)));
+                }
+            }
+        }
+    }
+    Ok(apply(&tree, edits))
+}
+
+/// The spans and values of the `order: N` attributes in a `step` or `steps`
+/// property, in source order.
+fn step_orders(tree: &SyntaxTree, property: &Property) -> Result<Vec<(Span, i64)>, ParseError> {
+    let tokens = tokens(tree, property)?;
+    Ok(tokens
+        .windows(3)
+        .filter_map(|window| match window {
+            [(Token::Identifier("order"), _), (Token::Colon, _), (Token::Number(n), span)] => {
+                Some((*span, *n))
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+/// The tokens of a property's value without its comments, with spans into
+/// the whole source.
+fn tokens<'t>(
+    tree: &'t SyntaxTree,
+    property: &Property,
+) -> Result<Vec<(Token<'t>, Span)>, ParseError> {
+    let offset = property.value_span.start;
+    let mut lexer = Lexer::new(tree.text(property.value_span));
+    let mut tokens = Vec::new();
+    loop {
+        match lexer.next_token()? {
+            (Token::Eof, _) => return Ok(tokens),
+            (Token::Comment(_), _) => {}
+            (token, span) => {
+                tokens.push((token, Span::new(span.start + offset, span.end + offset)))
+            }
+        }
+    }
+}
+
+/// `name` written the way `quest` writes its name: bare if it was bare and
+/// `name` can be, quoted otherwise.
+fn name_like(tree: &SyntaxTree, quest: &QuestNode, name: &str) -> String {
+    let bare = !tree.text(quest.name_span).starts_with('"');
+    match Lexer::new(name).next_token() {
+        Ok((Token::Identifier(ident), span)) if bare && ident == name && span.end == name.len() => {
+            name.to_string()
+        }
+        _ => quoted(name),
+    }
+}
+
+/// Replaces the text under each span, counting the edits that change it.
+fn apply(tree: &SyntaxTree, mut edits: Vec<(Span, String)>) -> Rewrite {
+    edits.retain(|(span, text)| tree.text(*span) != text);
+    edits.sort_by_key(|(span, _)| span.start);
+    let mut source = tree.source().to_string();
+    for (span, text) in edits.iter().rev() {
+        source.replace_range(span.start..span.end, text);
+    }
+    Rewrite {
+        source,
+        changes: edits.len(),
+    }
+}
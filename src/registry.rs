@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::package::candidates;
+use crate::Quest;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RegistryError {
+    #[error("Quest id {id:?} is used by both {first:?} and {second:?}")]
+    DuplicateId {
+        id: String,
+        /// Name of the quest that claimed the id first.
+        first: String,
+        second: String,
+    },
+}
+
+/// Quests gathered from any number of files, looked up by their
+/// [qualified id](Quest::qualified_id): the `id` behind the quest's package,
+/// if it has one.
+///
+/// Every qualified id may be used by one quest only, so quests of different
+/// packages may share an id. Quests without an id are kept but can only be
+/// reached through [`QuestRegistry::quests`].
+///
+/// ```
+/// use game_quest_parser_Hodik::{Parser, QuestRegistry};
+///
+/// let main = Parser::new(r#"quest "The Lost Sword" { id: "main_01" }"#)?.parse_quests()?;
+/// let side = Parser::new(r#"package "act1.village" quest "Wolves" { id: "main_01" }"#)?
+///     .parse_quests()?;
+/// let registry = QuestRegistry::new(main.into_iter().chain(side)).unwrap();
+/// assert_eq!(registry.get("main_01").unwrap().name, "The Lost Sword");
+/// assert_eq!(registry.get("act1.village/main_01").unwrap().name, "Wolves");
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QuestRegistry {
+    quests: Vec<Quest>,
+    index: HashMap<String, usize>,
+}
+
+impl QuestRegistry {
+    pub fn new(quests: impl IntoIterator<Item = Quest>) -> Result<Self, RegistryError> {
+        let mut registry = Self::default();
+        for quest in quests {
+            registry.insert(quest)?;
+        }
+        Ok(registry)
+    }
+
+    /// Adds a quest, failing without adding it if another quest already uses
+    /// its qualified id.
+    pub fn insert(&mut self, quest: Quest) -> Result<(), RegistryError> {
+        if quest.id.is_some() {
+            let id = quest.qualified_id();
+            if let Some(&i) = self.index.get(&id) {
+                return Err(RegistryError::DuplicateId {
+                    id,
+                    first: self.quests[i].name.clone(),
+                    second: quest.name,
+                });
+            }
+            self.index.insert(id, self.quests.len());
+        }
+        self.quests.push(quest);
+        Ok(())
+    }
+
+    /// Every quest, in the order they were added.
+    pub fn quests(&self) -> &[Quest] {
+        &self.quests
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Quest> {
+        self.index.get(id).map(|&i| &self.quests[i])
+    }
+
+    /// The quest an id written in `from`, as in its `requires`, refers to:
+    /// `act1.village/find_sword` is absolute, while `find_sword` names a
+    /// quest of `from`'s package or else one outside any package. See
+    /// [`resolve`](crate::resolve).
+    pub fn resolve(&self, reference: &str, from: &Quest) -> Option<&Quest> {
+        candidates(reference, from.package.as_deref())
+            .iter()
+            .find_map(|id| self.get(id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.quests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quests.is_empty()
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::package::candidates;
+use crate::{Quest, Span};
+
+/// How one quest refers to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// A `requires` property.
+    Requires,
+    /// An `unlock` in the `on_success` block.
+    SuccessUnlock,
+    /// An `unlock` in the `on_failure` block.
+    FailureUnlock,
+}
+
+impl fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReferenceKind::Requires => "requires",
+            ReferenceKind::SuccessUnlock => "unlocks on success",
+            ReferenceKind::FailureUnlock => "unlocks on failure",
+        })
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ResolutionError {
+    /// Two quests of one package share a name; holds the
+    /// [qualified name](Quest::qualified_name).
+    #[error("Quest {0:?} is defined more than once")]
+    DuplicateQuest(String),
+    /// A reference naming no quest of the set. `span` and `file` locate it
+    /// when the quest was parsed from source.
+    #[error("Quest {quest:?} {kind} unknown quest {target:?}")]
+    UnknownQuest {
+        quest: String,
+        kind: ReferenceKind,
+        target: String,
+        span: Option<Span>,
+        file: Option<String>,
+    },
+}
+
+impl ResolutionError {
+    /// Location of the dangling reference, when known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ResolutionError::UnknownQuest { span, .. } => *span,
+            ResolutionError::DuplicateQuest(_) => None,
+        }
+    }
+}
+
+/// A set of quests whose references to each other all resolved, produced by
+/// [`resolve`].
+#[derive(Debug)]
+pub struct ResolvedWorld<'a> {
+    quests: &'a [Quest],
+    index: HashMap<String, usize>,
+    requires: Vec<Vec<usize>>,
+    unlocks: Vec<Vec<usize>>,
+}
+
+impl<'a> ResolvedWorld<'a> {
+    pub fn quests(&self) -> &'a [Quest] {
+        self.quests
+    }
+
+    /// The quest of a [qualified name](Quest::qualified_name), such as
+    /// `act1.village/Find the Sword`; quests outside any package go by their
+    /// bare name.
+    pub fn get(&self, name: &str) -> Option<&'a Quest> {
+        self.index.get(name).map(|&i| &self.quests[i])
+    }
+
+    /// The quests the named quest requires, in the order it lists them.
+    pub fn prerequisites(&self, name: &str) -> Option<Vec<&'a Quest>> {
+        let &i = self.index.get(name)?;
+        Some(self.requires[i].iter().map(|&r| &self.quests[r]).collect())
+    }
+
+    /// The quests unlocked by the named quest's `on_success` and then its
+    /// `on_failure` block.
+    pub fn unlocks(&self, name: &str) -> Option<Vec<&'a Quest>> {
+        let &i = self.index.get(name)?;
+        Some(self.unlocks[i].iter().map(|&u| &self.quests[u]).collect())
+    }
+}
+
+/// Checks that every `requires` and `unlock` in `quests` names a quest of the
+/// set, by its `id` or else by its name.
+///
+/// References into other packages are written as `act1.village/find_sword`.
+/// A reference without a package names a quest of the referring quest's
+/// package, or else one outside any package; `/intro` only the latter.
+///
+/// All dangling references are reported at once, each with the span it was
+/// written at.
+///
+/// ```
+/// use game_quest_parser_Hodik::{resolve, Parser};
+///
+/// let quests = Parser::new(
+///     r#"quest "Intro" { on_success { unlock: "side_01" } }
+///        quest "Wolves" { id: "side_01", requires: "Intro" }"#,
+/// )?
+/// .parse_quests()?;
+/// let world = resolve(&quests).unwrap();
+/// assert_eq!(world.unlocks("Intro").unwrap()[0].name, "Wolves");
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub fn resolve(quests: &[Quest]) -> Result<ResolvedWorld<'_>, Vec<ResolutionError>> {
+    let mut errors = Vec::new();
+    let mut index = HashMap::new();
+    let mut ids = HashMap::new();
+    for (i, quest) in quests.iter().enumerate() {
+        let name = quest.qualified_name();
+        if index.insert(name.clone(), i).is_some() {
+            errors.push(ResolutionError::DuplicateQuest(name));
+        }
+        if quest.id.is_some() {
+            ids.insert(quest.qualified_id(), i);
+        }
+    }
+    let lookup = |target: &str, package: Option<&str>| {
+        candidates(target, package)
+            .iter()
+            .find_map(|c| ids.get(c).or_else(|| index.get(c)).copied())
+    };
+
+    let mut requires = Vec::with_capacity(quests.len());
+    let mut unlocks = Vec::with_capacity(quests.len());
+    for quest in quests {
+        let map = &quest.source_map;
+        let no_unlocks = Vec::new();
+        let success = quest
+            .on_success
+            .as_ref()
+            .map_or(&no_unlocks, |o| &o.unlocks);
+        let failure = quest
+            .on_failure
+            .as_ref()
+            .map_or(&no_unlocks, |o| &o.unlocks);
+        let references = [
+            (ReferenceKind::Requires, &quest.requires),
+            (ReferenceKind::SuccessUnlock, success),
+            (ReferenceKind::FailureUnlock, failure),
+        ];
+
+        let (mut required, mut unlocked) = (Vec::new(), Vec::new());
+        for (kind, targets) in references {
+            for (n, target) in targets.iter().enumerate() {
+                let Some(found) = lookup(target, quest.package.as_deref()) else {
+                    errors.push(ResolutionError::UnknownQuest {
+                        quest: quest.name.clone(),
+                        kind,
+                        target: target.clone(),
+                        span: match kind {
+                            ReferenceKind::Requires => map.requires(n),
+                            ReferenceKind::SuccessUnlock => map.success_unlock(n),
+                            ReferenceKind::FailureUnlock => map.failure_unlock(n),
+                        },
+                        file: map.file().map(str::to_string),
+                    });
+                    continue;
+                };
+                match kind {
+                    ReferenceKind::Requires => required.push(found),
+                    _ => unlocked.push(found),
+                }
+            }
+        }
+        requires.push(required);
+        unlocks.push(unlocked);
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(ResolvedWorld {
+        quests,
+        index,
+        requires,
+        unlocks,
+    })
+}
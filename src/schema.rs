@@ -0,0 +1,187 @@
+use core::fmt;
+
+use crate::diagnostic::closest_match;
+use crate::prelude::*;
+use crate::{IndexMap, Value};
+
+/// Type a schema property expects its value to have.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueType {
+    String,
+    Number,
+    /// A float; integers are accepted and converted.
+    Float,
+    Bool,
+    Duration,
+    DateTime,
+    /// A range such as `1..5`; a single integer `n` is accepted as `n..n`.
+    Range,
+    /// A list whose items all have the given type.
+    List(Box<ValueType>),
+    /// Any value at all.
+    Any,
+}
+
+impl ValueType {
+    /// Returns `value` converted to this type, or gives it back unchanged if
+    /// it does not fit.
+    pub(crate) fn coerce(&self, value: Value) -> Result<Value, Value> {
+        match (self, value) {
+            (ValueType::Any, value) => Ok(value),
+            (ValueType::String, value @ Value::String(_))
+            | (ValueType::Number, value @ Value::Number(_))
+            | (ValueType::Float, value @ Value::Float(_))
+            | (ValueType::Bool, value @ Value::Bool(_))
+            | (ValueType::Duration, value @ Value::Duration(_))
+            | (ValueType::DateTime, value @ Value::DateTime(_))
+            | (ValueType::Range, value @ Value::Range(..)) => Ok(value),
+            (ValueType::Float, Value::Number(n)) => Ok(Value::Float(n as f64)),
+            (ValueType::Range, Value::Number(n)) => Ok(Value::Range(n, n)),
+            (ValueType::List(item), Value::List(items)) => {
+                let original = items.clone();
+                items
+                    .into_iter()
+                    .map(|i| item.coerce(i))
+                    .collect::<Result<_, _>>()
+                    .map(Value::List)
+                    .map_err(|_| Value::List(original))
+            }
+            (_, value) => Err(value),
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueType::String => f.write_str("String"),
+            ValueType::Number => f.write_str("Number"),
+            ValueType::Float => f.write_str("Float"),
+            ValueType::Bool => f.write_str("Bool"),
+            ValueType::Duration => f.write_str("Duration"),
+            ValueType::DateTime => f.write_str("DateTime"),
+            ValueType::Range => f.write_str("Range"),
+            ValueType::List(item) => write!(f, "List of {item}"),
+            ValueType::Any => f.write_str("Value"),
+        }
+    }
+}
+
+/// How one property of a [`QuestSchema`] may be used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertySpec {
+    pub value_type: ValueType,
+    /// The property must appear at least once.
+    pub required: bool,
+    /// The property may appear more than once.
+    pub repeatable: bool,
+}
+
+impl PropertySpec {
+    /// An optional property that can be set once.
+    pub fn new(value_type: ValueType) -> Self {
+        Self {
+            value_type,
+            required: false,
+            repeatable: false,
+        }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn repeatable(mut self) -> Self {
+        self.repeatable = true;
+        self
+    }
+}
+
+/// The set of properties a game allows in a quest body, for parsing into
+/// [`DynamicQuest`]s with [`Parser::parse_dynamic`](crate::Parser::parse_dynamic).
+///
+/// ```
+/// use game_quest_parser_Hodik::{Parser, QuestSchema, ValueType};
+///
+/// let schema = QuestSchema::new()
+///     .required("xp", ValueType::Number)
+///     .optional("faction", ValueType::String)
+///     .repeatable("hint", ValueType::String);
+/// let quest = Parser::new(r#"quest "Scout" { xp: 40, hint: "Look north", hint: "Hurry" }"#)?
+///     .parse_dynamic(&schema)?;
+/// assert_eq!(quest.get_all("hint").len(), 2);
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuestSchema {
+    properties: IndexMap<String, PropertySpec>,
+}
+
+impl QuestSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a property, replacing any earlier spec for the same name.
+    pub fn property(mut self, name: impl Into<String>, spec: PropertySpec) -> Self {
+        self.properties.insert(name.into(), spec);
+        self
+    }
+
+    pub fn required(self, name: impl Into<String>, value_type: ValueType) -> Self {
+        self.property(name, PropertySpec::new(value_type).required())
+    }
+
+    pub fn optional(self, name: impl Into<String>, value_type: ValueType) -> Self {
+        self.property(name, PropertySpec::new(value_type))
+    }
+
+    /// Registers an optional property that may appear any number of times.
+    pub fn repeatable(self, name: impl Into<String>, value_type: ValueType) -> Self {
+        self.property(name, PropertySpec::new(value_type).repeatable())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PropertySpec> {
+        self.properties.get(name)
+    }
+
+    /// The registered property closest to `key`, for suggesting a fix when
+    /// `key` is not registered.
+    pub(crate) fn suggest(&self, key: &str) -> Option<&str> {
+        closest_match(key, self.properties.keys().map(String::as_str))
+    }
+
+    /// Names of the required properties, sorted.
+    pub(crate) fn required_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .properties
+            .iter()
+            .filter(|(_, spec)| spec.required)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// A quest parsed against a [`QuestSchema`] instead of into [`Quest`](crate::Quest).
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DynamicQuest {
+    pub name: String,
+    /// Values of each property that was set, in the order they appeared.
+    pub properties: IndexMap<String, Vec<Value>>,
+}
+
+impl DynamicQuest {
+    /// The first value of a property.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.get_all(name).first()
+    }
+
+    /// Every value of a property; empty if it was never set.
+    pub fn get_all(&self, name: &str) -> &[Value] {
+        self.properties.get(name).map_or(&[], Vec::as_slice)
+    }
+}
@@ -0,0 +1,270 @@
+//! Dry runs of a quest's stage flow, to check before content ships that
+//! every stage can be played and every path can be finished.
+//!
+//! A walk starts at `start` and follows the quest's
+//! [`stages`](crate::Quest::stages) until it reaches `end`. Wherever a stage
+//! leads to more than one other, the next of the given [`Choices`] decides
+//! where the walk goes. A quest without a `stages` block goes straight from
+//! `start` to `end`.
+//!
+//! ```
+//! use game_quest_parser_Hodik::sim::{Choices, Ending};
+//! use game_quest_parser_Hodik::{Parser, Stage};
+//!
+//! let quest = Parser::new(
+//!     r#"quest "Forge" {
+//!         reward: 100,
+//!         stages { start -> "smith", start -> "thief", "smith" -> end, "thief" -> "thief" }
+//!     }"#,
+//! )?
+//! .parse_quest()?;
+//! let report = quest.simulate(&Choices::Scripted(vec!["smith".into()]))?;
+//! assert_eq!(report.endings, [Ending::Completed]);
+//! assert_eq!(report.unreached, [Stage::Named("thief".into())]);
+//! assert_eq!(report.dead_ends, [Stage::Named("thief".into())]);
+//! assert_eq!(report.success_reward, 100);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use crate::{Currency, Quest, Stage};
+
+/// Stages a single random walk may enter before it is stopped as
+/// [`Ending::Stuck`], for flows whose loops are rarely left.
+const MAX_WALK_STAGES: usize = 10_000;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SimError {
+    #[error("No choice left for stage {stage}, which leads to {}", list(options))]
+    OutOfChoices { stage: Stage, options: Vec<Stage> },
+    #[error("Stage {stage} does not lead to {choice:?}, only to {}", list(options))]
+    UnknownChoice {
+        stage: Stage,
+        choice: String,
+        options: Vec<Stage>,
+    },
+    #[error("{0} choices were left over after the walk ended")]
+    UnusedChoices(usize),
+}
+
+fn list(stages: &[Stage]) -> String {
+    let names: Vec<String> = stages.iter().map(Stage::to_string).collect();
+    names.join(", ")
+}
+
+/// How a walk picks where to go at a stage that leads to several others.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Choices {
+    /// Names of the stages to go to, one per branch, used in order by a
+    /// single walk. `end` names the end stage. Every choice must be used.
+    Scripted(Vec<String>),
+    /// `runs` walks that pick uniformly at random. The same seed always
+    /// gives the same walks.
+    Random { seed: u64, runs: usize },
+}
+
+/// How a walk ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ending {
+    /// The walk reached `end`.
+    Completed,
+    /// The walk entered a stage from which `end` cannot be reached, or went
+    /// round a loop for too long.
+    Stuck(Stage),
+}
+
+/// One pass through a quest's stages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Walk {
+    /// Every stage entered, from `start` to where the walk ended.
+    pub path: Vec<Stage>,
+    pub ending: Ending,
+}
+
+/// What the walks of [`Quest::simulate`] found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimReport {
+    pub walks: Vec<Walk>,
+    /// Every ending some walk reached, in the order first reached.
+    pub endings: Vec<Ending>,
+    /// Stages of the flow no walk entered, in declaration order.
+    pub unreached: Vec<Stage>,
+    /// Stages from which no choice leads to `end`, in declaration order.
+    /// Unlike [`SimReport::unreached`], these do not depend on the walks.
+    pub dead_ends: Vec<Stage>,
+    /// The plain reward for completing the quest: `reward` plus the reward
+    /// of `on_success`.
+    pub success_reward: i64,
+    /// Amounts in specific currencies for completing the quest.
+    pub currency_rewards: Vec<(Currency, i64)>,
+    /// The reward of `on_failure` minus its penalty, if the quest can fail
+    /// because it has an `on_failure` block or a `time_limit`.
+    pub failure_reward: Option<i64>,
+}
+
+impl Quest {
+    /// Walks the quest's stages with `choices` and reports where the walks
+    /// ended, which stages they missed and what the quest can reward.
+    ///
+    /// Fails if scripted choices run out at a branch, name a stage the
+    /// current one does not lead to, or are not all used.
+    pub fn simulate(&self, choices: &Choices) -> Result<SimReport, SimError> {
+        let dead_ends = self.dead_ends();
+        let walks = match choices {
+            Choices::Scripted(script) => {
+                let mut script = script.iter();
+                let walk = self.walk(&dead_ends, |stage, options| {
+                    let owned = || options.iter().map(|&option| option.clone()).collect();
+                    let Some(choice) = script.next() else {
+                        return Err(SimError::OutOfChoices {
+                            stage: stage.clone(),
+                            options: owned(),
+                        });
+                    };
+                    options
+                        .iter()
+                        .position(|option| stage_name(option) == choice)
+                        .ok_or_else(|| SimError::UnknownChoice {
+                            stage: stage.clone(),
+                            choice: choice.clone(),
+                            options: owned(),
+                        })
+                })?;
+                match script.len() {
+                    0 => vec![walk],
+                    left => return Err(SimError::UnusedChoices(left)),
+                }
+            }
+            Choices::Random { seed, runs } => {
+                let mut rng = SplitMix64(*seed);
+                (0..*runs)
+                    .map(|_| self.walk(&dead_ends, |_, options| Ok(rng.below(options.len()))))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+
+        let mut endings = Vec::new();
+        let mut entered = HashSet::new();
+        for walk in &walks {
+            if !endings.contains(&walk.ending) {
+                endings.push(walk.ending.clone());
+            }
+            entered.extend(walk.path.iter());
+        }
+        let unreached = self
+            .flow()
+            .into_iter()
+            .filter(|stage| !entered.contains(stage))
+            .collect();
+
+        let failure_reward = match (&self.on_failure, self.time_limit) {
+            (Some(outcome), _) => Some(outcome.reward - outcome.penalty),
+            (None, Some(_)) => Some(0),
+            (None, None) => None,
+        };
+        Ok(SimReport {
+            endings,
+            unreached,
+            dead_ends: dead_ends.into_iter().collect(),
+            success_reward: self.reward + self.on_success.as_ref().map_or(0, |o| o.reward),
+            currency_rewards: self.rewards.clone(),
+            failure_reward,
+            walks,
+        })
+    }
+
+    /// Follows the flow from `start`, asking `choose` for the index of the
+    /// next stage wherever there is more than one.
+    fn walk(
+        &self,
+        dead_ends: &[Stage],
+        mut choose: impl FnMut(&Stage, &[&Stage]) -> Result<usize, SimError>,
+    ) -> Result<Walk, SimError> {
+        let mut path = vec![Stage::Start];
+        let Some(machine) = &self.stages else {
+            path.push(Stage::End);
+            return Ok(Walk {
+                path,
+                ending: Ending::Completed,
+            });
+        };
+        let mut stage = &Stage::Start;
+        let ending = loop {
+            if stage == &Stage::End {
+                break Ending::Completed;
+            }
+            if dead_ends.contains(stage) || path.len() > MAX_WALK_STAGES {
+                break Ending::Stuck(stage.clone());
+            }
+            let options: Vec<&Stage> = machine.next(stage).collect();
+            stage = match options[..] {
+                [only] => only,
+                _ => options[choose(stage, &options)?],
+            };
+            path.push(stage.clone());
+        };
+        Ok(Walk { path, ending })
+    }
+
+    /// Every stage of the flow in declaration order, `start` and `end`
+    /// included.
+    fn flow(&self) -> Vec<Stage> {
+        match &self.stages {
+            Some(machine) => machine.stages().into_iter().cloned().collect(),
+            None => vec![Stage::Start, Stage::End],
+        }
+    }
+
+    fn dead_ends(&self) -> Vec<Stage> {
+        let Some(machine) = &self.stages else {
+            return Vec::new();
+        };
+        // Walk the transitions backwards from `end`.
+        let mut finishing = HashSet::from([&Stage::End]);
+        let mut pending = vec![&Stage::End];
+        while let Some(stage) = pending.pop() {
+            for transition in machine.transitions() {
+                if &transition.to == stage && finishing.insert(&transition.from) {
+                    pending.push(&transition.from);
+                }
+            }
+        }
+        machine
+            .stages()
+            .into_iter()
+            .filter(|stage| !finishing.contains(stage))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The name a scripted choice uses for `stage`.
+fn stage_name(stage: &Stage) -> &str {
+    match stage {
+        Stage::Start => "start",
+        Stage::End => "end",
+        Stage::Named(name) => name,
+    }
+}
+
+/// A small generator, so seeded walks give the same result on every
+/// platform without pulling in a crate for it.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
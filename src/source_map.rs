@@ -0,0 +1,136 @@
+use crate::prelude::*;
+use crate::{Quest, Span};
+
+/// Where the fields of a quest were written, so checks after parsing such as
+/// [`resolve`](crate::resolve) and [`balance`](crate::balance) can point at
+/// the line they are about rather than only name the quest.
+///
+/// Spans are byte offsets into the input named by [`SourceMap::file`].
+/// Quests built in code have an empty map. Source maps never take part in
+/// comparisons: equal quests stay equal wherever they were parsed from.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    pub(crate) file: Option<String>,
+    pub(crate) name: Option<Span>,
+    /// The property that set each field, by the key it is written as, with
+    /// `steps` and `repeat` counted as `step`, in the order the fields were
+    /// first written.
+    pub(crate) properties: Vec<(String, Span)>,
+    pub(crate) steps: Vec<Span>,
+    pub(crate) requires: Vec<Span>,
+    pub(crate) success_unlocks: Vec<Span>,
+    pub(crate) failure_unlocks: Vec<Span>,
+}
+
+impl SourceMap {
+    /// Name of the input the quest was parsed from, if it was given one with
+    /// [`Parser::with_source_name`](crate::Parser::with_source_name).
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    /// Span of the quest's name in its header.
+    pub fn name(&self) -> Option<Span> {
+        self.name
+    }
+
+    /// Span of the property that set the field `key`, from the key to the end
+    /// of its value, such as `reward: 100 gold, 5 gems`. A field written
+    /// several times points at the last property when each one replaces the
+    /// value, like `reward: 1, reward: 2`, and at the first when each one adds
+    /// to it, like `requires`.
+    pub fn property(&self, key: &str) -> Option<Span> {
+        self.properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|&(_, span)| span)
+    }
+
+    /// Span of the step at `index` of [`Quest::steps`]: its text and
+    /// attributes, or the whole `steps: [...]` list it came from. Every copy
+    /// made by `repeat(n) step: ...` points at the repeated step.
+    pub fn step(&self, index: usize) -> Option<Span> {
+        self.steps.get(index).copied()
+    }
+
+    /// Replaces every span with what `relocate` maps it to.
+    #[cfg(feature = "std")]
+    pub(crate) fn relocate(&mut self, relocate: impl Fn(Span) -> Span) {
+        self.name = self.name.map(&relocate);
+        for (_, span) in &mut self.properties {
+            *span = relocate(*span);
+        }
+        let lists = [
+            &mut self.steps,
+            &mut self.requires,
+            &mut self.success_unlocks,
+            &mut self.failure_unlocks,
+        ];
+        for span in lists.into_iter().flatten() {
+            *span = relocate(*span);
+        }
+    }
+
+    /// Records the property setting `key` at `span`, in place of an earlier
+    /// one if it `replaces` the value.
+    pub(crate) fn add_property(&mut self, key: &str, span: Span, replaces: bool) {
+        match self.properties.iter_mut().find(|(k, _)| k == key) {
+            Some((_, earlier)) if replaces => *earlier = span,
+            Some(_) => {}
+            None => self.properties.push((key.to_string(), span)),
+        }
+    }
+
+    /// Forgets where `key` and the spans of its items were written, for a
+    /// field replaced by one from elsewhere.
+    pub(crate) fn forget(&mut self, key: &str) {
+        self.properties.retain(|(k, _)| k != key);
+        match key {
+            "step" => self.steps.clear(),
+            "requires" => self.requires.clear(),
+            "on_success" => self.success_unlocks.clear(),
+            "on_failure" => self.failure_unlocks.clear(),
+            _ => {}
+        }
+    }
+
+    /// Span of the quest name in `requires` at `index`.
+    pub fn requires(&self, index: usize) -> Option<Span> {
+        self.requires.get(index).copied()
+    }
+
+    /// Span of the `unlock` at `index` of the `on_success` block.
+    pub fn success_unlock(&self, index: usize) -> Option<Span> {
+        self.success_unlocks.get(index).copied()
+    }
+
+    /// Span of the `unlock` at `index` of the `on_failure` block.
+    pub fn failure_unlock(&self, index: usize) -> Option<Span> {
+        self.failure_unlocks.get(index).copied()
+    }
+}
+
+impl Quest {
+    /// Where the quest's fields were written, when it was parsed from
+    /// source. Quests built in code have an empty map.
+    ///
+    /// ```
+    /// use game_quest_parser_Hodik::{line_col, Parser};
+    ///
+    /// let source = "quest \"Wolves\" {\n    reward: 40,\n    step: \"Hunt\"\n}";
+    /// let quest = Parser::new(source)?.parse_quest()?;
+    /// let span = quest.source_map().property("reward").unwrap();
+    /// assert_eq!(&source[span.start..span.end], "reward: 40");
+    /// assert_eq!(line_col(source, quest.source_map().step(0).unwrap().start), (3, 11));
+    /// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+    /// ```
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+}
+
+impl PartialEq for SourceMap {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,111 @@
+use thiserror::Error;
+
+use crate::prelude::*;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum StageError {
+    #[error("Stage {0:?} is entered but has no transitions of its own")]
+    UnknownStage(String),
+    #[error("Invalid transition {from} -> {to}")]
+    InvalidTransition { from: Stage, to: Stage },
+    #[error("Stage {0} is not reachable from start")]
+    Unreachable(Stage),
+}
+
+/// A node in a quest's stage flow. `start` and `end` are built in; every
+/// other stage is named by the quest.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Stage {
+    Start,
+    End,
+    Named(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transition {
+    pub from: Stage,
+    pub to: Stage,
+}
+
+/// Quest flow declared with a `stages { ... }` block.
+///
+/// A named stage exists once it has an outgoing transition. `start` must lead
+/// to every stage, including `end`, and nothing leads back into `start` or
+/// out of `end`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateMachine {
+    transitions: Vec<Transition>,
+}
+
+impl StateMachine {
+    pub fn new(transitions: Vec<Transition>) -> Result<Self, StageError> {
+        let machine = Self { transitions };
+        machine.validate()?;
+        Ok(machine)
+    }
+
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /// Every stage in order of first appearance, `start` and `end` included.
+    pub fn stages(&self) -> Vec<&Stage> {
+        let mut stages = Vec::new();
+        for transition in &self.transitions {
+            for stage in [&transition.from, &transition.to] {
+                if !stages.contains(&stage) {
+                    stages.push(stage);
+                }
+            }
+        }
+        stages
+    }
+
+    /// Returns the stages `stage` can move to, in declaration order.
+    pub fn next<'s>(&'s self, stage: &'s Stage) -> impl Iterator<Item = &'s Stage> + 's {
+        self.transitions
+            .iter()
+            .filter(move |t| &t.from == stage)
+            .map(|t| &t.to)
+    }
+
+    fn validate(&self) -> Result<(), StageError> {
+        for transition in &self.transitions {
+            if transition.from == Stage::End || transition.to == Stage::Start {
+                return Err(StageError::InvalidTransition {
+                    from: transition.from.clone(),
+                    to: transition.to.clone(),
+                });
+            }
+        }
+
+        let defined: HashSet<&Stage> = self.transitions.iter().map(|t| &t.from).collect();
+        for transition in &self.transitions {
+            if let Stage::Named(name) = &transition.to {
+                if !defined.contains(&transition.to) {
+                    return Err(StageError::UnknownStage(name.clone()));
+                }
+            }
+        }
+
+        let mut reached: HashSet<_> = HashSet::from([&Stage::Start]);
+        let mut pending = vec![&Stage::Start];
+        while let Some(stage) = pending.pop() {
+            for next in self.next(stage) {
+                if reached.insert(next) {
+                    pending.push(next);
+                }
+            }
+        }
+        let mut stages = self.stages();
+        stages.push(&Stage::End);
+        match stages.into_iter().find(|stage| !reached.contains(stage)) {
+            Some(stage) => Err(StageError::Unreachable(stage.clone())),
+            None => Ok(()),
+        }
+    }
+}
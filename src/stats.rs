@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+
+use crate::Quest;
+
+/// Aggregate numbers over a quest pack, for dashboards and the `stats`
+/// command.
+///
+/// ```
+/// use game_quest_parser_Hodik::{Parser, QuestStats};
+///
+/// let quests = Parser::new(
+///     r#"quest "Intro" { tags: ["main"], reward: 50, step: "Wake up" }
+///        quest "Finale" { tags: ["main", "act2"], requires: "Intro", reward: 1000 }"#,
+/// )?
+/// .parse_quests()?;
+/// let stats = QuestStats::from(quests.as_slice());
+/// assert_eq!(stats.quests, 2);
+/// assert_eq!(stats.average_reward, Some(525.0));
+/// assert_eq!(stats.top_tags(1), [("main".to_string(), 2)]);
+/// assert_eq!(stats.without_prerequisites, ["Intro"]);
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct QuestStats {
+    /// Number of quests.
+    pub quests: usize,
+    /// Mean `reward` of the quests, leaving out rewards given as
+    /// expressions; `None` if no quest has a plain reward.
+    pub average_reward: Option<f64>,
+    /// Highest `reward`, with the same quests left out.
+    pub max_reward: Option<i64>,
+    /// How many quests have each number of steps, keyed by step count.
+    pub step_counts: BTreeMap<usize, usize>,
+    /// Every tag with the number of quests carrying it, most common first
+    /// and tags as common as each other by name.
+    pub tags: Vec<(String, usize)>,
+    /// Names of the quests without a `requires`, in order: the ones a new
+    /// player can start with.
+    pub without_prerequisites: Vec<String>,
+}
+
+impl QuestStats {
+    /// The `n` most common tags, or every tag if there are fewer.
+    pub fn top_tags(&self, n: usize) -> &[(String, usize)] {
+        &self.tags[..n.min(self.tags.len())]
+    }
+}
+
+impl From<&[Quest]> for QuestStats {
+    fn from(quests: &[Quest]) -> Self {
+        let rewards: Vec<i64> = quests
+            .iter()
+            .filter(|quest| !quest.expressions.contains_key("reward"))
+            .map(|quest| quest.reward)
+            .collect();
+        let average_reward = (!rewards.is_empty())
+            .then(|| rewards.iter().map(|&r| r as f64).sum::<f64>() / rewards.len() as f64);
+
+        let mut step_counts = BTreeMap::new();
+        let mut tag_counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for quest in quests {
+            *step_counts.entry(quest.steps.len()).or_insert(0) += 1;
+            for (i, tag) in quest.tags.iter().enumerate() {
+                // A quest listing a tag twice still counts once.
+                if !quest.tags[..i].contains(tag) {
+                    *tag_counts.entry(tag).or_insert(0) += 1;
+                }
+            }
+        }
+        let mut tags: Vec<(String, usize)> = tag_counts
+            .into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect();
+        tags.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+        QuestStats {
+            quests: quests.len(),
+            average_reward,
+            max_reward: rewards.iter().copied().max(),
+            step_counts,
+            tags,
+            without_prerequisites: quests
+                .iter()
+                .filter(|quest| quest.requires.is_empty())
+                .map(|quest| quest.name.clone())
+                .collect(),
+        }
+    }
+}
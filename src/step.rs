@@ -0,0 +1,162 @@
+use crate::prelude::*;
+use crate::{Quest, Text};
+
+/// One step of a quest, declared with `step: "Find the key"` and optionally
+/// followed by attributes:
+///
+/// ```text
+/// step: "Find the key" [optional, hidden, order: 2]
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    pub text: Text,
+    /// The quest can be completed without doing this step.
+    pub optional: bool,
+    /// The step is not shown to the player before they reach it.
+    pub hidden: bool,
+    /// Where to list the step, for games that show steps in a different
+    /// order than they are declared in.
+    pub order: Option<i64>,
+}
+
+impl Step {
+    pub fn new(text: impl Into<Text>) -> Step {
+        Step {
+            text: text.into(),
+            optional: false,
+            hidden: false,
+            order: None,
+        }
+    }
+
+    /// Whether the step has no attributes.
+    pub fn is_plain(&self) -> bool {
+        !self.optional && !self.hidden && self.order.is_none()
+    }
+}
+
+impl From<Text> for Step {
+    fn from(text: Text) -> Self {
+        Step::new(text)
+    }
+}
+
+impl From<String> for Step {
+    fn from(s: String) -> Self {
+        Step::new(s)
+    }
+}
+
+impl From<&str> for Step {
+    fn from(s: &str) -> Self {
+        Step::new(s)
+    }
+}
+
+/// A step equals a string if it is that plain text without attributes.
+impl PartialEq<&str> for Step {
+    fn eq(&self, other: &&str) -> bool {
+        self.is_plain() && self.text == *other
+    }
+}
+
+/// How the steps of a quest declared in a `steps { ... }` block depend on
+/// each other:
+///
+/// ```text
+/// steps {
+///     ordered { "Find the map", "Cross the river" }
+///     any_of { "Bribe the guard", all_of { "Find the key", "Wait for night" } }
+/// }
+/// ```
+///
+/// The block itself is an [`StepTree::AllOf`] of its items, so groups side by
+/// side can be done in any order; wrap them in `ordered { ... }` to chain
+/// them.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum StepTree {
+    Step(Step),
+    /// `ordered { ... }`: every item, one after the other.
+    #[cfg_attr(feature = "serde", serde(rename = "ordered"))]
+    Sequence(Vec<StepTree>),
+    /// `any_of { ... }`: one of the items is enough.
+    AnyOf(Vec<StepTree>),
+    /// `all_of { ... }`: every item, in any order.
+    AllOf(Vec<StepTree>),
+}
+
+impl StepTree {
+    /// Every step of the tree, in declaration order.
+    pub fn steps(&self) -> Vec<&Step> {
+        let mut steps = Vec::new();
+        self.collect_steps(&mut steps);
+        steps
+    }
+
+    fn collect_steps<'t>(&'t self, steps: &mut Vec<&'t Step>) {
+        match self {
+            StepTree::Step(step) => steps.push(step),
+            StepTree::Sequence(items) | StepTree::AnyOf(items) | StepTree::AllOf(items) => {
+                for item in items {
+                    item.collect_steps(steps);
+                }
+            }
+        }
+    }
+
+    /// Whether the tree is done once the steps `done` returns true for are.
+    /// Optional steps count as done either way.
+    pub fn is_complete(&self, done: impl Fn(&Step) -> bool) -> bool {
+        self.complete(&done)
+    }
+
+    fn complete(&self, done: &dyn Fn(&Step) -> bool) -> bool {
+        match self {
+            StepTree::Step(step) => step.optional || done(step),
+            StepTree::Sequence(items) | StepTree::AllOf(items) => {
+                items.iter().all(|item| item.complete(done))
+            }
+            StepTree::AnyOf(items) => items.iter().any(|item| item.complete(done)),
+        }
+    }
+
+    /// The steps the player can work on next, given the steps `done` returns
+    /// true for: the first unfinished item of an `ordered` group and every
+    /// unfinished item of the other groups, in declaration order. Empty once
+    /// the tree is complete.
+    pub fn next_steps(&self, done: impl Fn(&Step) -> bool) -> Vec<&Step> {
+        let mut steps = Vec::new();
+        self.collect_next(&done, &mut steps);
+        steps
+    }
+
+    fn collect_next<'t>(&'t self, done: &dyn Fn(&Step) -> bool, steps: &mut Vec<&'t Step>) {
+        if self.complete(done) {
+            return;
+        }
+        match self {
+            StepTree::Step(step) => steps.push(step),
+            StepTree::Sequence(items) => {
+                if let Some(item) = items.iter().find(|item| !item.complete(done)) {
+                    item.collect_next(done, steps);
+                }
+            }
+            StepTree::AnyOf(items) | StepTree::AllOf(items) => {
+                for item in items {
+                    item.collect_next(done, steps);
+                }
+            }
+        }
+    }
+}
+
+impl Quest {
+    /// The text of every step, in declaration order, for code that does not
+    /// care about step attributes.
+    pub fn step_texts(&self) -> impl Iterator<Item = &Text> {
+        self.steps.iter().map(|step| &step.text)
+    }
+}
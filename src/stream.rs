@@ -0,0 +1,321 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::BufRead;
+use std::sync::Arc;
+
+use crate::{
+    nfc, Diagnostics, GrammarVersion, IncludeResolver, Lexer, ParseError, Parser, ParserOptions,
+    Quest, QuestOverride, Span, SymbolTable, Template, Token, Value,
+};
+
+/// Iterator over the quests of a [`BufRead`] source, created by
+/// [`Parser::from_reader`] or [`QuestStream::with_options`].
+///
+/// Input is read line by line and buffered only until one complete top-level
+/// definition is available, so memory use is bounded by the largest quest
+/// rather than the whole file. A syntax error in one quest is reported for
+/// that item and the stream carries on with the next definition; an I/O
+/// error ends the stream. `let` constants and templates stay in scope for
+/// later quests, a `package` declaration holds up to the next one, and a
+/// `#version` pragma at the top applies to the whole input. Overrides, NPC
+/// and item definitions and warnings are not yielded but collected, see
+/// [`QuestStream::overrides`], [`QuestStream::symbols`] and
+/// [`QuestStream::diagnostics`].
+///
+/// Spans in quests, overrides, warnings and errors are byte offsets into the
+/// whole input, as if it had been parsed at once, so [`line_col`](crate::line_col)
+/// on the input locates them. Those of a quest instantiated from a template
+/// point into the template, as they do with [`Parser::parse_quests`].
+///
+/// Options apply to every quest as with [`Parser::with_options`], except that
+/// [`ParserLimits::max_input_bytes`](crate::ParserLimits::max_input_bytes)
+/// bounds each top-level definition instead of the whole input: the stream
+/// fails with [`ParseError::InputTooLarge`] and ends once one grows larger
+/// while it is being read.
+pub struct QuestStream<R> {
+    reader: R,
+    options: ParserOptions,
+    resolver: Option<Arc<dyn IncludeResolver>>,
+    variables: HashMap<String, Value>,
+    templates: HashMap<String, Template<String>>,
+    /// Names after `from` in the chunk being scanned, the templates it may
+    /// instantiate.
+    referenced: Vec<String>,
+    after_from: bool,
+    package: Option<String>,
+    version: Option<GrammarVersion>,
+    overrides: Vec<QuestOverride>,
+    symbols: SymbolTable,
+    diagnostics: Diagnostics,
+    pending: VecDeque<Quest>,
+    buffer: String,
+    /// Offset in the input of the start of `buffer`.
+    offset: usize,
+    /// Offset in `buffer` of the first token not yet scanned.
+    scanned: usize,
+    depth: usize,
+    done: bool,
+}
+
+impl<R: BufRead> QuestStream<R> {
+    /// Streams the quests of `reader`, parsed with `options`.
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            reader,
+            options,
+            resolver: None,
+            variables: HashMap::new(),
+            templates: HashMap::new(),
+            referenced: Vec::new(),
+            after_from: false,
+            package: None,
+            version: None,
+            overrides: Vec::new(),
+            symbols: SymbolTable::default(),
+            diagnostics: Diagnostics::new(),
+            pending: VecDeque::new(),
+            buffer: String::new(),
+            offset: 0,
+            scanned: 0,
+            depth: 0,
+            done: false,
+        }
+    }
+
+    /// Replaces the resolver used for `include` directives, see
+    /// [`Parser::with_include_resolver`].
+    pub fn with_include_resolver(mut self, resolver: impl IncludeResolver + 'static) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// The `quest ... override` blocks read so far, in source order, to be
+    /// applied with [`merge`](crate::merge) once the stream is exhausted.
+    pub fn overrides(&self) -> &[QuestOverride] {
+        &self.overrides
+    }
+
+    /// The NPCs and items defined so far. A name defined again in a later
+    /// chunk fails that chunk with [`ParseError::DuplicateDefinition`].
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// The warnings raised so far, for the quests yielded so far.
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    /// Lexes the buffered text for the end of the current top-level block and
+    /// returns its byte length if it is complete.
+    ///
+    /// Braces in strings, comments and raw blocks are not counted. The buffer
+    /// always ends at a line break, so only a string or raw block can be cut
+    /// short; it is lexed again from its start once more lines are read.
+    fn find_boundary(&mut self) -> Option<usize> {
+        let mut lexer = Lexer::new(&self.buffer[self.scanned..]);
+        let mut lexed = 0;
+        loop {
+            let (token, span) = match lexer.next_token() {
+                Ok(next) => next,
+                Err(ParseError::UnterminatedString { .. } | ParseError::UnterminatedRaw { .. }) => {
+                    self.scanned += lexed;
+                    return None;
+                }
+                // Left for the parser to report, scanning carries on after it.
+                Err(_) => continue,
+            };
+            lexed = span.end;
+            if std::mem::take(&mut self.after_from) {
+                match &token {
+                    Token::Identifier(name) => self.referenced.push(nfc(name).into_owned()),
+                    Token::StringLiteral(name) => self.referenced.push(name.to_string()),
+                    _ => {}
+                }
+            }
+            self.after_from = token == Token::Identifier("from");
+            match token {
+                Token::LBrace => self.depth += 1,
+                Token::RBrace => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.depth == 0 {
+                        let end = self.scanned + span.end;
+                        self.scanned = 0;
+                        return Some(end);
+                    }
+                }
+                Token::Eof => {
+                    self.scanned += lexed;
+                    return None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Parses one chunk, which starts at `offset` in the input, with what
+    /// earlier chunks left in scope, and keeps what it leaves in scope for
+    /// the next one.
+    fn parse_chunk(&mut self, offset: usize, chunk: String) -> Result<Vec<Quest>, ParseError> {
+        // Templates of earlier chunks are copied after the chunk, where the
+        // parser can lex them but does not read them as definitions.
+        let lexed = chunk.len();
+        let mut source = chunk;
+        let mut inherited = Vec::new();
+        let mut regions = Vec::new();
+        for name in std::mem::take(&mut self.referenced) {
+            let Some(template) = self.templates.get(&name) else {
+                continue;
+            };
+            let start = source.len();
+            source.push_str(&template.body);
+            let end = Span::new(source.len(), source.len() + 1);
+            source.push('}');
+            inherited.push((name, template.params.clone(), start, end));
+            regions.push((start, end.end, template.start));
+        }
+        // Spans within the chunk move by its offset, and those within a
+        // copied template to where the template was written.
+        let relocate_offset = |at: usize| {
+            if at >= lexed {
+                if let Some(&(start, _, to)) = regions
+                    .iter()
+                    .find(|&&(start, end, _)| start <= at && at <= end)
+                {
+                    return to + (at - start);
+                }
+            }
+            offset + at
+        };
+        let relocate =
+            |span: Span| Span::new(relocate_offset(span.start), relocate_offset(span.end));
+
+        let mut parser = Parser::with_options(&source[..lexed], self.options.clone())?;
+        parser.input = &source;
+        if let Some(resolver) = &self.resolver {
+            parser.resolver = Arc::clone(resolver);
+        }
+        for (name, params, start, end) in inherited {
+            let body = &source[start..end.start];
+            let template = Template {
+                params,
+                body,
+                start,
+                end,
+            };
+            parser.templates.insert(name, template);
+        }
+        parser.variables = std::mem::take(&mut self.variables);
+        parser.package = self.package.take();
+        parser.symbols = std::mem::take(&mut self.symbols);
+        // Only the first chunk may hold the pragma.
+        match self.version {
+            Some(version) => parser.version = version,
+            None => self.version = Some(parser.version),
+        }
+
+        let result = parser.parse_quests();
+        self.variables = parser.variables;
+        self.package = parser.package;
+        self.symbols = parser.symbols;
+        // Quests from included files have spans into those files, and carry
+        // their names.
+        for mut patch in parser.overrides {
+            patch.span = relocate(patch.span);
+            if patch.patch.source_map.file.is_none() {
+                patch.patch.source_map.relocate(relocate);
+            }
+            self.overrides.push(patch);
+        }
+        for mut warning in parser.diagnostics.into_warnings() {
+            if let Some(span) = warning.span_mut() {
+                *span = relocate(*span);
+            }
+            self.diagnostics.warn(warning);
+        }
+        let mut quests = result.map_err(|mut error| {
+            if let Some(span) = error.span_mut() {
+                *span = relocate(*span);
+            }
+            error
+        })?;
+        for quest in &mut quests {
+            if quest.source_map.file.is_none() {
+                quest.source_map.relocate(relocate);
+            }
+        }
+        for (name, template) in parser.templates {
+            if template.start >= lexed {
+                continue;
+            }
+            if self.templates.contains_key(&name) {
+                let kind = "template".to_string();
+                return Err(ParseError::DuplicateDefinition { kind, name });
+            }
+            let template = Template {
+                params: template.params,
+                body: template.body.to_string(),
+                start: relocate_offset(template.start),
+                end: relocate(template.end),
+            };
+            self.templates.insert(name, template);
+        }
+        Ok(quests)
+    }
+
+    /// The next top-level block and its offset in the input.
+    fn next_chunk(&mut self) -> Result<Option<(usize, String)>, ParseError> {
+        loop {
+            if let Some(end) = self.find_boundary() {
+                let chunk: String = self.buffer.drain(..end).collect();
+                let offset = self.offset;
+                self.offset += end;
+                return Ok(Some((offset, chunk)));
+            }
+
+            let read = self
+                .reader
+                .read_line(&mut self.buffer)
+                .map_err(|e| ParseError::Io(e.to_string()))?;
+            let limit = self.options.limits.max_input_bytes;
+            if self.buffer.len() > limit {
+                self.done = true;
+                let size = self.buffer.len();
+                return Err(ParseError::InputTooLarge { size, limit });
+            }
+            if read == 0 {
+                self.done = true;
+                if self.buffer.trim().is_empty() {
+                    return Ok(None);
+                }
+                return Ok(Some((self.offset, std::mem::take(&mut self.buffer))));
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for QuestStream<R> {
+    type Item = Result<Quest, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(quest) = self.pending.pop_front() {
+                return Some(Ok(quest));
+            }
+            if self.done {
+                return None;
+            }
+            let (offset, chunk) = match self.next_chunk() {
+                Ok(Some(next)) => next,
+                Ok(None) => return None,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+            match self.parse_chunk(offset, chunk) {
+                Ok(quests) => self.pending.extend(quests),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! Plain-language quest reports for readers who do not know the DSL.
+
+use std::fmt::Write;
+
+use crate::writer::format_duration;
+use crate::{Action, NpcRef, Objective, Outcome, Quest, Step, StepTree, Text, Value};
+
+impl Quest {
+    /// Renders the quest as a readable report: its name, whether it is
+    /// available, what it rewards and what the player has to do.
+    ///
+    /// ```text
+    /// The Lost Sword
+    ///   Status:     active
+    ///   Reward:     500
+    ///   Steps:
+    ///     1. Talk to the blacksmith
+    ///     2. Find the cave entrance
+    /// ```
+    ///
+    /// Steps grouped in a `steps { ... }` block are listed under headings
+    /// such as `in order:` and `any of:`, still numbered in declaration order.
+    ///
+    /// Sections without content are left out. Values that depend on game
+    /// state are shown as their expression; localized text is shown in every
+    /// language, one per line.
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        self.write_summary(&mut out)
+            .expect("writing to a String cannot fail");
+        out
+    }
+
+    fn write_summary(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(out, "{}", self.name)?;
+        if let Some(id) = &self.id {
+            writeln!(out, "  Id:         {id}")?;
+        }
+        if let Some(package) = &self.package {
+            writeln!(out, "  Package:    {package}")?;
+        }
+        match self.expressions.get("active") {
+            Some(expr) => writeln!(out, "  Status:     active when {expr}")?,
+            None if self.active => writeln!(out, "  Status:     active")?,
+            None => writeln!(out, "  Status:     inactive")?,
+        }
+        let mut rewards: Vec<String> = match self.expressions.get("reward") {
+            Some(expr) => vec![expr.to_string()],
+            None if self.reward == 0 && !self.rewards.is_empty() => Vec::new(),
+            None => vec![self.reward.to_string()],
+        };
+        rewards.extend(
+            self.rewards
+                .iter()
+                .map(|(currency, amount)| format!("{amount} {currency}")),
+        );
+        writeln!(out, "  Reward:     {}", rewards.join(", "))?;
+        if !self.reward_tiers.is_empty() {
+            let tiers: Vec<String> = self
+                .reward_tiers
+                .iter()
+                .map(|tier| {
+                    let amounts: Vec<String> = tier
+                        .rewards
+                        .iter()
+                        .map(|(currency, amount)| format!("{amount} {currency}"))
+                        .collect();
+                    format!("{} ({})", tier.name, amounts.join(", "))
+                })
+                .collect();
+            writeln!(out, "  Tiers:      {}", tiers.join(", "))?;
+        }
+        if let Some(time_limit) = self.time_limit {
+            writeln!(out, "  Time limit: {}", format_duration(time_limit))?;
+        }
+        match self.level_range {
+            Some((min, max)) if min == max => writeln!(out, "  Level:      {min}")?,
+            Some((min, max)) => writeln!(out, "  Levels:     {min} to {max}")?,
+            None => {}
+        }
+        if let Some(starts_at) = self.starts_at {
+            writeln!(out, "  Starts:     {starts_at}")?;
+        }
+        if let Some(ends_at) = self.ends_at {
+            writeln!(out, "  Ends:       {ends_at}")?;
+        }
+        if !self.requires.is_empty() {
+            writeln!(out, "  Requires:   {}", self.requires.join(", "))?;
+        }
+        if !self.tags.is_empty() {
+            writeln!(out, "  Tags:       {}", self.tags.join(", "))?;
+        }
+        if let Some(giver) = &self.giver {
+            writeln!(out, "  Giver:      {}", describe_npc(giver))?;
+        }
+        if let Some(turn_in) = &self.turn_in {
+            writeln!(out, "  Turn in:    {}", describe_npc(turn_in))?;
+        }
+        if !self.objectives.is_empty() {
+            writeln!(out, "  Objectives:")?;
+            for objective in &self.objectives {
+                writeln!(out, "    - {}", describe_objective(objective))?;
+            }
+        }
+        if let Some(condition) = &self.complete_when {
+            writeln!(out, "  Completes when: {condition}")?;
+        }
+        if !self.steps.is_empty() {
+            writeln!(out, "  Steps:")?;
+            match &self.step_tree {
+                Some(StepTree::AllOf(items)) => {
+                    let mut number = 0;
+                    for item in items {
+                        write_step_tree(out, item, 4, &mut number)?;
+                    }
+                }
+                Some(tree) => write_step_tree(out, tree, 4, &mut 0)?,
+                None => {
+                    for (i, step) in self.steps.iter().enumerate() {
+                        write_step(out, step, 4, i + 1)?;
+                    }
+                }
+            }
+        }
+        if let Some(outcome) = &self.on_success {
+            writeln!(out, "  On success: {}", describe_outcome(outcome))?;
+        }
+        if let Some(outcome) = &self.on_failure {
+            writeln!(out, "  On failure: {}", describe_outcome(outcome))?;
+        }
+        if !self.on_start.is_empty() {
+            writeln!(out, "  On start:   {}", describe_actions(&self.on_start))?;
+        }
+        if !self.on_complete.is_empty() {
+            writeln!(
+                out,
+                "  On complete: {}",
+                describe_actions(&self.on_complete)
+            )?;
+        }
+        if let Some(stages) = &self.stages {
+            writeln!(out, "  Stages:")?;
+            for transition in stages.transitions() {
+                writeln!(out, "    {} -> {}", transition.from, transition.to)?;
+            }
+        }
+
+        let values = self.extra.iter().map(|(k, v)| (k, v.to_string()));
+        let expressions = self
+            .expressions
+            .iter()
+            .filter(|(k, _)| !matches!(k.as_str(), "active" | "reward"))
+            .map(|(k, v)| (k, v.to_string()));
+        let mut other: Vec<_> = values.chain(expressions).collect();
+        if !other.is_empty() {
+            other.sort();
+            writeln!(out, "  Other properties:")?;
+            for (key, value) in other {
+                writeln!(out, "    {key}: {value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The step's attributes as a parenthesized suffix, or nothing.
+fn describe_step(step: &Step) -> String {
+    let mut notes = Vec::new();
+    if step.optional {
+        notes.push("optional".to_string());
+    }
+    if step.hidden {
+        notes.push("hidden".to_string());
+    }
+    if let Some(order) = step.order {
+        notes.push(format!("shown as {order}"));
+    }
+    if notes.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", notes.join(", "))
+    }
+}
+
+fn describe_outcome(outcome: &Outcome) -> String {
+    let mut parts = Vec::new();
+    if outcome.reward != 0 {
+        parts.push(format!("reward {}", outcome.reward));
+    }
+    if outcome.penalty != 0 {
+        parts.push(format!("penalty {}", outcome.penalty));
+    }
+    if !outcome.unlocks.is_empty() {
+        parts.push(format!("unlocks {}", outcome.unlocks.join(", ")));
+    }
+    let mut extra: Vec<_> = outcome.extra.iter().collect();
+    extra.sort_by(|a, b| a.0.cmp(b.0));
+    parts.extend(
+        extra
+            .into_iter()
+            .map(|(key, value)| format!("{key} {value}")),
+    );
+    if parts.is_empty() {
+        "nothing".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+fn describe_actions(actions: &[Action]) -> String {
+    let actions: Vec<String> = actions
+        .iter()
+        .map(|action| {
+            let args: Vec<String> = action.args.iter().map(Value::to_string).collect();
+            format!("{} {}", action.name, args.join(" "))
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    actions.join(", ")
+}
+
+/// Writes the steps of `tree` indented by `indent` spaces, numbering them
+/// on from `number`.
+fn write_step_tree(
+    out: &mut String,
+    tree: &StepTree,
+    indent: usize,
+    number: &mut usize,
+) -> std::fmt::Result {
+    let (heading, items) = match tree {
+        StepTree::Step(step) => {
+            *number += 1;
+            return write_step(out, step, indent, *number);
+        }
+        StepTree::Sequence(items) => ("in order", items),
+        StepTree::AnyOf(items) => ("any of", items),
+        StepTree::AllOf(items) => ("all of", items),
+    };
+    writeln!(out, "{:indent$}{heading}:", "")?;
+    for item in items {
+        write_step_tree(out, item, indent + 2, number)?;
+    }
+    Ok(())
+}
+
+fn write_step(out: &mut String, step: &Step, indent: usize, number: usize) -> std::fmt::Result {
+    let notes = describe_step(step);
+    match &step.text {
+        Text::Plain(text) => writeln!(out, "{:indent$}{number}. {text}{notes}", "")?,
+        Text::Localized(localized) => {
+            let mut texts: Vec<_> = localized.0.iter().collect();
+            texts.sort();
+            for (j, (lang, text)) in texts.into_iter().enumerate() {
+                let number = format!("{number}.");
+                let (number, notes) = if j == 0 {
+                    (number.as_str(), notes.as_str())
+                } else {
+                    ("", "")
+                };
+                writeln!(out, "{:indent$}{number:<3}[{lang}] {text}{notes}", "")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn describe_npc(npc: &NpcRef) -> String {
+    match &npc.location {
+        Some(location) => format!("{} at {location}", npc.name),
+        None => npc.name.clone(),
+    }
+}
+
+fn describe_objective(objective: &Objective) -> String {
+    match objective {
+        Objective::Kill { target, count } => format!("Kill {count} x {target}"),
+        Objective::Collect { item, count } => format!("Collect {count} x {item}"),
+        Objective::TalkTo { npc } => format!("Talk to {npc}"),
+        Objective::Reach { location } => format!("Reach {location}"),
+        Objective::Custom {
+            kind,
+            target,
+            count,
+        } => {
+            let count = count.map(|n| format!(" {n} x")).unwrap_or_default();
+            let target = target.as_ref().map(|t| format!(" {t}")).unwrap_or_default();
+            format!("{kind}{count}{target}")
+        }
+    }
+}
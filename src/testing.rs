@@ -0,0 +1,449 @@
+//! [`proptest`] strategies for generating quests, behind the `testing`
+//! feature.
+//!
+//! Every generated quest survives being written with [`to_quest_string`]
+//! and parsed back, so downstream crates can check their own extensions of
+//! the format the same way this crate checks the writer:
+//!
+//! ```
+//! use game_quest_parser_Hodik::testing::check_round_trip;
+//! use game_quest_parser_Hodik::Quest;
+//! use proptest::prelude::*;
+//! use proptest::test_runner::TestRunner;
+//!
+//! TestRunner::default()
+//!     .run(&any::<Quest>(), |quest| check_round_trip(&quest))
+//!     .unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
+
+use crate::{
+    nfc, to_quest_string, Action, BinaryOp, Condition, Currency, DateTime, Expr, LocalizedString,
+    NpcRef, Objective, Outcome, Parser, Quest, RewardTier, SourceMap, Stage, StateMachine, Step,
+    StepTree, Text, Transition, Value,
+};
+
+/// Writes `quest` as DSL, parses it back and fails the test case unless the
+/// result equals `quest`.
+pub fn check_round_trip(quest: &Quest) -> Result<(), TestCaseError> {
+    let text = to_quest_string(quest);
+    let parsed = Parser::new(&text)
+        .and_then(|mut p| p.parse_quest())
+        .map_err(|e| TestCaseError::fail(format!("failed to reparse {text:?}: {e}")))?;
+    prop_assert_eq!(&parsed, quest, "written as {:?}", text);
+    Ok(())
+}
+
+/// Any string in NFC, the form the parser normalizes quoted text to.
+fn string_strategy() -> impl Strategy<Value = String> {
+    any::<String>().prop_map(|text| nfc(&text).into_owned())
+}
+
+/// Any objective, built-in kinds included.
+pub fn objective_strategy() -> impl Strategy<Value = Objective> {
+    let custom_kind = "[a-z_]{1,8}".prop_filter("known kind", |k| {
+        !matches!(k.as_str(), "kill" | "collect" | "talk_to" | "reach")
+    });
+    prop_oneof![
+        (string_strategy(), any::<i64>())
+            .prop_map(|(target, count)| Objective::Kill { target, count }),
+        (string_strategy(), any::<i64>())
+            .prop_map(|(item, count)| Objective::Collect { item, count }),
+        string_strategy().prop_map(|npc| Objective::TalkTo { npc }),
+        string_strategy().prop_map(|location| Objective::Reach { location }),
+        (
+            custom_kind,
+            proptest::option::of(string_strategy()),
+            proptest::option::of(any::<i64>())
+        )
+            .prop_map(|(kind, target, count)| Objective::Custom {
+                kind,
+                target,
+                count
+            }),
+    ]
+}
+
+fn localized_strategy() -> impl Strategy<Value = LocalizedString> {
+    proptest::collection::vec(("[a-z]{2}", string_strategy()), 0..3)
+        .prop_map(|texts| LocalizedString(texts.into_iter().collect()))
+}
+
+fn text_strategy() -> impl Strategy<Value = Text> {
+    prop_oneof![
+        string_strategy().prop_map(Text::Plain),
+        localized_strategy().prop_map(Text::Localized),
+    ]
+}
+
+/// A step with any text and attributes.
+pub fn step_strategy() -> impl Strategy<Value = Step> {
+    (
+        text_strategy(),
+        any::<bool>(),
+        any::<bool>(),
+        proptest::option::of(any::<i64>()),
+    )
+        .prop_map(|(text, optional, hidden, order)| Step {
+            text,
+            optional,
+            hidden,
+            order,
+        })
+}
+
+/// The tree of a `steps { ... }` block, with groups nested a few levels.
+pub fn step_tree_strategy() -> impl Strategy<Value = StepTree> {
+    let tree = step_strategy()
+        .prop_map(StepTree::Step)
+        .prop_recursive(3, 8, 3, |inner| {
+            (0..3u8, proptest::collection::vec(inner, 0..3)).prop_map(|(kind, items)| match kind {
+                0 => StepTree::Sequence(items),
+                1 => StepTree::AnyOf(items),
+                _ => StepTree::AllOf(items),
+            })
+        });
+    proptest::collection::vec(tree, 0..3).prop_map(StepTree::AllOf)
+}
+
+fn duration_strategy() -> impl Strategy<Value = Duration> {
+    any::<u32>().prop_map(|secs| Duration::from_secs(secs.into()))
+}
+
+/// Any instant a date and time literal can name.
+fn datetime_strategy() -> impl Strategy<Value = DateTime> {
+    (-62_167_219_200..=253_402_300_799_i64)
+        .prop_map(|seconds| DateTime::from_unix_timestamp(seconds).expect("in range"))
+}
+
+/// When an event quest starts and ends, with the end after the start.
+fn event_window_strategy() -> impl Strategy<Value = (Option<DateTime>, Option<DateTime>)> {
+    (
+        proptest::option::of(datetime_strategy()),
+        proptest::option::of(datetime_strategy()),
+    )
+        .prop_map(|window| match window {
+            (Some(a), Some(b)) if a == b => (Some(a), None),
+            (Some(a), Some(b)) => (Some(a.min(b)), Some(a.max(b))),
+            other => other,
+        })
+}
+
+/// Bounds of a valid range: the first is never greater than the second.
+fn range_strategy() -> impl Strategy<Value = (i64, i64)> {
+    (any::<i64>(), any::<i64>()).prop_map(|(a, b)| (a.min(b), a.max(b)))
+}
+
+/// Any value the writer can express, nested up to three lists deep.
+pub fn value_strategy() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        localized_strategy().prop_map(Value::Localized),
+        string_strategy().prop_map(Value::String),
+        any::<i64>().prop_map(Value::Number),
+        any::<f64>()
+            .prop_filter("finite", |f| f.is_finite())
+            .prop_map(Value::Float),
+        any::<bool>().prop_map(Value::Bool),
+        duration_strategy().prop_map(Value::Duration),
+        range_strategy().prop_map(|(min, max)| Value::Range(min, max)),
+        datetime_strategy().prop_map(Value::DateTime),
+        (
+            proptest::option::of("[a-z][a-z0-9_]{0,5}"),
+            string_strategy()
+        )
+            .prop_map(|(lang, body)| Value::Raw { lang, body }),
+    ];
+    leaf.prop_recursive(3, 16, 4, |inner| {
+        proptest::collection::vec(inner, 0..4).prop_map(Value::List)
+    })
+}
+
+/// Words that cannot be used as an extra property key.
+const RESERVED_KEYS: &[&str] = &[
+    "quest",
+    "let",
+    "include",
+    "true",
+    "false",
+    "id",
+    "active",
+    "reward",
+    "requires",
+    "tags",
+    "giver",
+    "turn_in",
+    "step",
+    "steps",
+    "objective",
+    "complete_when",
+    "stages",
+    "time_limit",
+    "level",
+    "starts_at",
+    "ends_at",
+    "on_success",
+    "on_failure",
+    "on_start",
+    "on_complete",
+];
+
+/// An expression that depends on game state, so the parser keeps it
+/// unevaluated.
+pub fn expr_strategy() -> impl Strategy<Value = Expr> {
+    let ops = [
+        BinaryOp::Add,
+        BinaryOp::Sub,
+        BinaryOp::Mul,
+        BinaryOp::Div,
+        BinaryOp::Rem,
+        BinaryOp::Eq,
+        BinaryOp::Ne,
+        BinaryOp::Lt,
+        BinaryOp::Le,
+        BinaryOp::Gt,
+        BinaryOp::Ge,
+        BinaryOp::And,
+        BinaryOp::Or,
+    ];
+    let leaf = prop_oneof![
+        extra_key_strategy().prop_map(Expr::Identifier),
+        value_strategy().prop_map(Expr::Literal),
+    ];
+    leaf.prop_recursive(3, 12, 2, move |inner| {
+        (proptest::sample::select(ops.to_vec()), inner.clone(), inner).prop_map(|(op, lhs, rhs)| {
+            Expr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            }
+        })
+    })
+    .prop_filter("constant expressions are folded", |e| !e.is_constant())
+}
+
+/// A `complete_when` condition over up to a handful of objective names.
+pub fn condition_strategy() -> impl Strategy<Value = Condition> {
+    extra_key_strategy()
+        .prop_map(Condition::Objective)
+        .prop_recursive(3, 8, 2, |inner| {
+            (any::<bool>(), inner.clone(), inner).prop_map(|(and, lhs, rhs)| {
+                let (lhs, rhs) = (Box::new(lhs), Box::new(rhs));
+                if and {
+                    Condition::And(lhs, rhs)
+                } else {
+                    Condition::Or(lhs, rhs)
+                }
+            })
+        })
+}
+
+fn expressions_strategy() -> impl Strategy<Value = HashMap<String, Expr>> {
+    let key = prop_oneof![
+        extra_key_strategy(),
+        Just("reward".to_string()),
+        Just("active".to_string()),
+    ];
+    proptest::collection::hash_map(key, expr_strategy(), 0..3)
+}
+
+/// A linear flow `start -> a -> b -> end` through the given stages.
+fn stages_strategy() -> impl Strategy<Value = StateMachine> {
+    proptest::collection::vec(string_strategy(), 0..4).prop_map(|names| {
+        let mut stages = vec![Stage::Start];
+        stages.extend(names.into_iter().map(Stage::Named));
+        stages.push(Stage::End);
+        let transitions = stages
+            .windows(2)
+            .map(|pair| Transition {
+                from: pair[0].clone(),
+                to: pair[1].clone(),
+            })
+            .collect();
+        StateMachine::new(transitions).expect("linear flow is valid")
+    })
+}
+
+/// An `on_success` or `on_failure` outcome with any fields.
+pub fn outcome_strategy() -> impl Strategy<Value = Outcome> {
+    let key = extra_key_strategy().prop_filter("outcome key", |k| k != "penalty" && k != "unlock");
+    (
+        any::<i64>(),
+        any::<i64>(),
+        proptest::collection::vec(string_strategy(), 0..3),
+        proptest::collection::hash_map(key, value_strategy(), 0..3),
+    )
+        .prop_map(|(reward, penalty, unlocks, extra)| Outcome {
+            reward,
+            penalty,
+            unlocks,
+            extra: extra.into_iter().collect(),
+        })
+}
+
+/// An action with an identifier name and up to two arguments.
+pub fn action_strategy() -> impl Strategy<Value = Action> {
+    (
+        extra_key_strategy(),
+        proptest::collection::vec(value_strategy(), 0..3),
+    )
+        .prop_map(|(name, args)| Action::new(name, args))
+}
+
+fn extra_key_strategy() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9_]{0,8}".prop_filter("reserved key", |k| !RESERVED_KEYS.contains(&k.as_str()))
+}
+
+/// Amounts in distinct currencies, as in a `reward { ... }` block.
+fn amounts_strategy(size: core::ops::Range<usize>) -> impl Strategy<Value = Vec<(Currency, i64)>> {
+    proptest::collection::hash_map(extra_key_strategy(), any::<i64>(), size).prop_map(|amounts| {
+        amounts
+            .into_iter()
+            .map(|(name, amount)| (Currency::from_name(&name), amount))
+            .collect()
+    })
+}
+
+/// Dotted package names such as `act1.village`.
+fn package_strategy() -> impl Strategy<Value = String> {
+    "[a-z_][a-z0-9_]{0,6}(\\.[a-z_][a-z0-9_]{0,6}){0,2}"
+}
+
+fn npc_ref_strategy() -> impl Strategy<Value = NpcRef> {
+    (string_strategy(), proptest::option::of(string_strategy()))
+        .prop_map(|(name, location)| NpcRef { name, location })
+}
+
+/// A quest that [`check_round_trip`] holds for: any combination of
+/// properties the writer can express.
+pub fn quest_strategy() -> impl Strategy<Value = Quest> {
+    (
+        (
+            string_strategy(),
+            proptest::option::of(string_strategy()),
+            proptest::collection::vec(string_strategy(), 0..3),
+            proptest::option::of(npc_ref_strategy()),
+            proptest::option::of(npc_ref_strategy()),
+            proptest::option::of(package_strategy()),
+        ),
+        (
+            proptest::collection::vec(step_strategy(), 0..5),
+            proptest::option::of(step_tree_strategy()),
+        ),
+        (
+            proptest::collection::vec(objective_strategy(), 0..4),
+            proptest::option::of(condition_strategy()),
+        ),
+        proptest::collection::vec(string_strategy(), 0..3),
+        any::<i64>(),
+        (
+            amounts_strategy(0..3),
+            proptest::collection::hash_map(string_strategy(), amounts_strategy(1..3), 0..3),
+        ),
+        any::<bool>(),
+        proptest::option::of(stages_strategy()),
+        (
+            proptest::option::of(duration_strategy()),
+            proptest::option::of(range_strategy()),
+            event_window_strategy(),
+        ),
+        (
+            proptest::option::of(outcome_strategy()),
+            proptest::option::of(outcome_strategy()),
+            proptest::collection::vec(action_strategy(), 0..3),
+            proptest::collection::vec(action_strategy(), 0..3),
+        ),
+        proptest::collection::hash_map(extra_key_strategy(), value_strategy(), 0..4),
+        expressions_strategy(),
+    )
+        .prop_map(
+            |(
+                (name, id, tags, giver, turn_in, package),
+                (steps, step_tree),
+                (objectives, complete_when),
+                requires,
+                reward,
+                (rewards, reward_tiers),
+                active,
+                stages,
+                (time_limit, level_range, (starts_at, ends_at)),
+                (on_success, on_failure, on_start, on_complete),
+                mut extra,
+                expressions,
+            )| {
+                extra.retain(|key, _| !expressions.contains_key(key));
+                let reward = if expressions.contains_key("reward") {
+                    0
+                } else {
+                    reward
+                };
+                let active = active && !expressions.contains_key("active");
+                let steps = match &step_tree {
+                    Some(tree) => tree.steps().into_iter().cloned().collect(),
+                    None => steps,
+                };
+                Quest {
+                    name,
+                    id,
+                    package,
+                    steps,
+                    step_tree,
+                    objectives,
+                    complete_when,
+                    requires,
+                    tags,
+                    giver,
+                    turn_in,
+                    reward,
+                    rewards,
+                    reward_tiers: reward_tiers
+                        .into_iter()
+                        .map(|(name, rewards)| RewardTier::new(name, rewards))
+                        .collect(),
+                    active,
+                    stages,
+                    time_limit,
+                    level_range,
+                    starts_at,
+                    ends_at,
+                    on_success,
+                    on_failure,
+                    on_start,
+                    on_complete,
+                    extra: extra.into_iter().collect(),
+                    expressions: expressions.into_iter().collect(),
+                    source_map: SourceMap::default(),
+                }
+            },
+        )
+}
+
+macro_rules! arbitrary {
+    ($($ty:ty => $strategy:ident),* $(,)?) => {
+        $(
+            impl Arbitrary for $ty {
+                type Parameters = ();
+                type Strategy = BoxedStrategy<$ty>;
+
+                fn arbitrary_with(_: ()) -> Self::Strategy {
+                    $strategy().boxed()
+                }
+            }
+        )*
+    };
+}
+
+arbitrary! {
+    Quest => quest_strategy,
+    Step => step_strategy,
+    StepTree => step_tree_strategy,
+    Objective => objective_strategy,
+    Outcome => outcome_strategy,
+    Action => action_strategy,
+    Condition => condition_strategy,
+    Value => value_strategy,
+}
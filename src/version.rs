@@ -0,0 +1,45 @@
+use core::fmt;
+
+/// Revision of the quest grammar a file is written in, selected with a
+/// `#version` pragma at the top of the file.
+///
+/// Files without a pragma are read as [`GrammarVersion::V1`], so grammar
+/// changes that would break existing files are only enabled by a newer
+/// version. Using such a construct in an older file fails with
+/// [`ParseError::RequiresVersion`](crate::ParseError::RequiresVersion).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum GrammarVersion {
+    /// The original grammar.
+    #[default]
+    V1,
+    /// Adds rewards in currencies, as `reward: 100 gold, 5 gems` or
+    /// `reward { gold: 100, gems: 5 }`.
+    V2,
+}
+
+impl GrammarVersion {
+    /// The newest version the parser understands.
+    pub const LATEST: GrammarVersion = GrammarVersion::V2;
+
+    /// The version written as `#version n`, if the parser knows it.
+    pub fn from_number(n: i64) -> Option<GrammarVersion> {
+        match n {
+            1 => Some(GrammarVersion::V1),
+            2 => Some(GrammarVersion::V2),
+            _ => None,
+        }
+    }
+
+    pub fn number(self) -> i64 {
+        match self {
+            GrammarVersion::V1 => 1,
+            GrammarVersion::V2 => 2,
+        }
+    }
+}
+
+impl fmt::Display for GrammarVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
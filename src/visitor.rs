@@ -0,0 +1,224 @@
+//! Callbacks fired while quests are parsed, for pipelines that build their
+//! own structures straight from the source instead of keeping [`Quest`]s.
+
+use alloc::borrow::Cow;
+
+use crate::{
+    Action, Condition, Currency, Expr, NpcRef, Objective, Outcome, Quest, RewardTier, Span,
+    StateMachine, Step, Value,
+};
+
+/// What a property set, as passed to [`QuestVisitor::on_property`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue<'a> {
+    /// A literal value: that of a property the parser gives no meaning to,
+    /// or of `id`, `active`, `reward`, `requires`, `tags`, `level`,
+    /// `time_limit`, `starts_at` or `ends_at` as it is written. A single
+    /// `level: 10` is the range `10..10`.
+    Value(Cow<'a, Value>),
+    /// An expression such as `100 + level * 10`.
+    Expr(&'a Expr),
+    /// Amounts in currencies from `reward: 100 gold` or a `reward { ... }`
+    /// block.
+    Amounts(&'a [(Currency, i64)]),
+    RewardTier(&'a RewardTier),
+    /// The NPC of `giver` or `turn_in`.
+    Npc(&'a NpcRef),
+    Objective(&'a Objective),
+    /// The condition of `complete_when`.
+    Condition(&'a Condition),
+    Stages(&'a StateMachine),
+    /// The block of `on_success` or `on_failure`.
+    Outcome(&'a Outcome),
+    /// The actions of one `on_start` or `on_complete` block.
+    Actions(&'a [Action]),
+}
+
+/// Callbacks for [`Parser::parse_with_visitor`](crate::Parser::parse_with_visitor),
+/// each called as soon as the parser has read and checked what it reports.
+/// Every method does nothing by default.
+///
+/// ```
+/// use game_quest_parser_Hodik::{Parser, PropertyValue, QuestVisitor, Span, Value};
+///
+/// /// Adds up the rewards of every quest.
+/// #[derive(Default)]
+/// struct Total(i64);
+///
+/// impl QuestVisitor for Total {
+///     fn on_property(&mut self, key: &str, value: PropertyValue<'_>, _span: Span) {
+///         if let ("reward", PropertyValue::Value(value)) = (key, value) {
+///             if let Value::Number(n) = *value {
+///                 self.0 += n;
+///             }
+///         }
+///     }
+/// }
+///
+/// let mut total = Total::default();
+/// let source = r#"quest "A" { reward: 10 } quest "B" { reward: 20, step: "Go" }"#;
+/// assert_eq!(Parser::new(source)?.parse_with_visitor(&mut total)?, 2);
+/// assert_eq!(total.0, 30);
+/// # Ok::<(), game_quest_parser_Hodik::ParseError>(())
+/// ```
+pub trait QuestVisitor {
+    /// The quest named `name`, written at `span`, begins.
+    fn on_quest_start(&mut self, _name: &str, _span: Span) {}
+
+    /// The property `key`, written at `span`, set `value`. Steps are
+    /// reported through [`QuestVisitor::on_step`] instead.
+    fn on_property(&mut self, _key: &str, _value: PropertyValue<'_>, _span: Span) {}
+
+    /// The quest gained `step`, written at `span`. Steps of a repeated step
+    /// share its span, and those of a `steps { ... }` block are reported in
+    /// declaration order, without their groups.
+    fn on_step(&mut self, _step: &Step, _span: Span) {}
+
+    /// The quest begun by the last [`QuestVisitor::on_quest_start`] ends.
+    fn on_quest_end(&mut self) {}
+}
+
+/// Ignores every callback, for checking input without keeping its quests.
+impl QuestVisitor for () {}
+
+/// Sizes of the fields a property can add to, taken before it is parsed, so
+/// only what it added is reported.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Mark {
+    steps: usize,
+    requires: usize,
+    tags: usize,
+    objectives: usize,
+    reward_tiers: usize,
+    on_start: usize,
+    on_complete: usize,
+}
+
+impl Mark {
+    pub(crate) fn of(quest: &Quest) -> Self {
+        Self {
+            steps: quest.steps.len(),
+            requires: quest.requires.len(),
+            tags: quest.tags.len(),
+            objectives: quest.objectives.len(),
+            reward_tiers: quest.reward_tiers.len(),
+            on_start: quest.on_start.len(),
+            on_complete: quest.on_complete.len(),
+        }
+    }
+}
+
+/// Reports the property `key` of `quest`, written at `span`, which added to
+/// the quest everything after `mark`.
+pub(crate) fn visit_property(
+    visitor: &mut dyn QuestVisitor,
+    quest: &Quest,
+    key: &str,
+    span: Span,
+    mark: Mark,
+) {
+    if key == "step" {
+        let spans = &quest.source_map.steps;
+        for (i, step) in quest.steps.iter().enumerate().skip(mark.steps) {
+            visitor.on_step(step, spans.get(i).copied().unwrap_or(span));
+        }
+    } else if let Some(value) = property_value(quest, key, mark) {
+        visitor.on_property(key, value, span);
+    }
+}
+
+/// The value the property `key` gave `quest`, the first one it added after
+/// `mark` for fields that gain one item per property.
+fn property_value<'q>(quest: &'q Quest, key: &str, mark: Mark) -> Option<PropertyValue<'q>> {
+    let literal = |value| Some(PropertyValue::Value(Cow::Owned(value)));
+    match key {
+        "reward" if quest.reward_tiers.len() > mark.reward_tiers => quest
+            .reward_tiers
+            .get(mark.reward_tiers)
+            .map(PropertyValue::RewardTier),
+        _ if quest.expressions.contains_key(key) => {
+            quest.expressions.get(key).map(PropertyValue::Expr)
+        }
+        "reward" if !quest.rewards.is_empty() => Some(PropertyValue::Amounts(&quest.rewards)),
+        "reward" => literal(Value::Number(quest.reward)),
+        "active" => literal(Value::Bool(quest.active)),
+        "id" => quest.id.clone().and_then(|id| literal(Value::String(id))),
+        "requires" => quest
+            .requires
+            .get(mark.requires)
+            .and_then(|name| literal(Value::String(name.clone()))),
+        "tags" => literal(Value::List(
+            quest.tags[mark.tags..]
+                .iter()
+                .cloned()
+                .map(Value::String)
+                .collect(),
+        )),
+        "level" => quest
+            .level_range
+            .and_then(|(min, max)| literal(Value::Range(min, max))),
+        "time_limit" => quest
+            .time_limit
+            .and_then(|limit| literal(Value::Duration(limit))),
+        "starts_at" => quest.starts_at.and_then(|at| literal(Value::DateTime(at))),
+        "ends_at" => quest.ends_at.and_then(|at| literal(Value::DateTime(at))),
+        "giver" => quest.giver.as_ref().map(PropertyValue::Npc),
+        "turn_in" => quest.turn_in.as_ref().map(PropertyValue::Npc),
+        "objective" => quest
+            .objectives
+            .get(mark.objectives)
+            .map(PropertyValue::Objective),
+        "complete_when" => quest.complete_when.as_ref().map(PropertyValue::Condition),
+        "stages" => quest.stages.as_ref().map(PropertyValue::Stages),
+        "on_success" => quest.on_success.as_ref().map(PropertyValue::Outcome),
+        "on_failure" => quest.on_failure.as_ref().map(PropertyValue::Outcome),
+        "on_start" => Some(PropertyValue::Actions(&quest.on_start[mark.on_start..])),
+        "on_complete" => Some(PropertyValue::Actions(
+            &quest.on_complete[mark.on_complete..],
+        )),
+        _ => quest
+            .extra
+            .get(key)
+            .map(|value| PropertyValue::Value(Cow::Borrowed(value))),
+    }
+}
+
+/// Reports a quest parsed in full, as one from an included file is. Fields
+/// are reported in the order they were first written, each at the span its
+/// source map gives, and a field written several times, like `requires`,
+/// reports all its items there.
+pub(crate) fn visit_quest(visitor: &mut dyn QuestVisitor, quest: &Quest) {
+    let map = &quest.source_map;
+    visitor.on_quest_start(&quest.name, map.name().unwrap_or_default());
+    for (key, span) in &map.properties {
+        let (key, span) = (key.as_str(), *span);
+        let items = match key {
+            "requires" => quest.requires.len(),
+            "objective" => quest.objectives.len(),
+            "reward" => {
+                let base = Mark {
+                    reward_tiers: quest.reward_tiers.len(),
+                    ..Mark::default()
+                };
+                let tiers_only = quest.reward == 0
+                    && quest.rewards.is_empty()
+                    && !quest.expressions.contains_key(key);
+                if quest.reward_tiers.is_empty() || !tiers_only {
+                    visit_property(visitor, quest, key, span, base);
+                }
+                quest.reward_tiers.len()
+            }
+            _ => 1,
+        };
+        for i in 0..items {
+            let mark = Mark {
+                requires: i,
+                objectives: i,
+                reward_tiers: i,
+                ..Mark::default()
+            };
+            visit_property(visitor, quest, key, span, mark);
+        }
+    }
+    visitor.on_quest_end();
+}
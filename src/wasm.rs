@@ -0,0 +1,37 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{line_col, NoIncludeResolver, Parser};
+
+/// Parses a single quest and returns it as JSON.
+///
+/// On failure the error is a JSON object with the message and code and, when
+/// the error has a location, its byte span and 1-based line and column:
+///
+/// ```json
+/// { "message": "Unknown property `rewrd`", "code": "E0102", "span": { "start": 16, "end": 21 }, "line": 2, "column": 5 }
+/// ```
+///
+/// Includes are disabled since there is no filesystem to resolve them from.
+#[wasm_bindgen]
+pub fn parse_quest_json(input: &str) -> Result<String, String> {
+    let result = Parser::new(input)
+        .map(|parser| parser.with_include_resolver(NoIncludeResolver))
+        .and_then(|mut parser| parser.parse_quest());
+
+    match result {
+        Ok(quest) => serde_json::to_string(&quest).map_err(|e| e.to_string()),
+        Err(error) => {
+            let mut json = serde_json::json!({
+                "message": error.to_string(),
+                "code": error.code(),
+            });
+            if let Some(span) = error.span() {
+                let (line, column) = line_col(input, span.start);
+                json["span"] = serde_json::json!(span);
+                json["line"] = line.into();
+                json["column"] = column.into();
+            }
+            Err(json.to_string())
+        }
+    }
+}
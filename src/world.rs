@@ -0,0 +1,149 @@
+use crate::prelude::*;
+use crate::{IndexMap, Objective, ParseError, Quest, QuestOverride, Value};
+
+/// A character declared with a top-level `npc "Name" { ... }` block.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Npc {
+    pub name: String,
+    pub properties: IndexMap<String, Value>,
+}
+
+/// A character a quest refers to, and optionally where to find them, from
+/// `npc("Elder") at "Village Square"`. See [`Quest::giver`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NpcRef {
+    pub name: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub location: Option<String>,
+}
+
+impl NpcRef {
+    pub fn new(name: impl Into<String>) -> Self {
+        NpcRef {
+            name: name.into(),
+            location: None,
+        }
+    }
+
+    pub fn at(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+}
+
+impl Quest {
+    /// The NPC the player returns to when done: `turn_in` if given, and the
+    /// `giver` otherwise.
+    pub fn turn_in_npc(&self) -> Option<&NpcRef> {
+        self.turn_in.as_ref().or(self.giver.as_ref())
+    }
+}
+
+/// An item declared with a top-level `item "Name" { ... }` block.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Item {
+    pub name: String,
+    pub properties: IndexMap<String, Value>,
+}
+
+/// The NPCs and items defined in a set of files, keyed by name, in the order
+/// they were defined.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolTable {
+    pub npcs: IndexMap<String, Npc>,
+    pub items: IndexMap<String, Item>,
+}
+
+impl SymbolTable {
+    pub fn is_empty(&self) -> bool {
+        self.npcs.is_empty() && self.items.is_empty()
+    }
+
+    pub(crate) fn add_npc(&mut self, npc: Npc) -> Result<(), ParseError> {
+        if self.npcs.contains_key(&npc.name) {
+            return Err(duplicate("npc", &npc.name));
+        }
+        self.npcs.insert(npc.name.clone(), npc);
+        Ok(())
+    }
+
+    pub(crate) fn add_item(&mut self, item: Item) -> Result<(), ParseError> {
+        if self.items.contains_key(&item.name) {
+            return Err(duplicate("item", &item.name));
+        }
+        self.items.insert(item.name.clone(), item);
+        Ok(())
+    }
+
+    pub(crate) fn extend(&mut self, other: SymbolTable) -> Result<(), ParseError> {
+        for npc in other.npcs.into_values() {
+            self.add_npc(npc)?;
+        }
+        for item in other.items.into_values() {
+            self.add_item(item)?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything defined in a quest file: its quests plus the NPCs and items
+/// they refer to. Produced by [`Parser::parse_world`](crate::Parser::parse_world).
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct World {
+    pub quests: Vec<Quest>,
+    pub symbols: SymbolTable,
+    /// Overrides of quests defined elsewhere, in source order; apply them
+    /// with [`merge`](crate::merge).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub overrides: Vec<QuestOverride>,
+}
+
+impl World {
+    /// Checks that every `talk_to` objective, `giver` and `turn_in` names a
+    /// defined NPC and every `collect` objective a defined item.
+    pub fn check_references(&self) -> Result<(), ParseError> {
+        for quest in &self.quests {
+            for npc in [&quest.giver, &quest.turn_in].into_iter().flatten() {
+                if !self.symbols.npcs.contains_key(&npc.name) {
+                    return Err(ParseError::UndefinedReference {
+                        quest: quest.name.clone(),
+                        kind: "npc".to_string(),
+                        name: npc.name.clone(),
+                    });
+                }
+            }
+            for objective in &quest.objectives {
+                let (kind, name, defined) = match objective {
+                    Objective::TalkTo { npc } => ("npc", npc, self.symbols.npcs.contains_key(npc)),
+                    Objective::Collect { item, .. } => {
+                        ("item", item, self.symbols.items.contains_key(item))
+                    }
+                    _ => continue,
+                };
+                if !defined {
+                    return Err(ParseError::UndefinedReference {
+                        quest: quest.name.clone(),
+                        kind: kind.to_string(),
+                        name: name.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn duplicate(kind: &str, name: &str) -> ParseError {
+    ParseError::DuplicateDefinition {
+        kind: kind.to_string(),
+        name: name.to_string(),
+    }
+}
@@ -0,0 +1,743 @@
+use core::fmt;
+use core::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use crate::prelude::*;
+use crate::{
+    ast, line_col, Action, Currency, Expr, GrammarVersion, Lexer, LocalizedString, NpcRef,
+    Objective, Outcome, ParseError, Quest, Span, Stage, StateMachine, Step, StepTree, Text, Token,
+    Value,
+};
+
+const INDENT: &str = "    ";
+
+/// Renders a quest as canonical quest DSL.
+///
+/// The output always parses back into an equal [`Quest`]: properties are
+/// written in a fixed order (`id`, `active`, `reward`, named rewards, reward
+/// tiers, `requires`, `tags`, `giver`, `turn_in`, `step` or a `steps` block,
+/// `objective`, `complete_when`, `stages`, `time_limit`, `level`,
+/// `starts_at`, `ends_at`, `on_success`, `on_failure`, `on_start`,
+/// `on_complete`, then extra properties and expressions sorted by key), one
+/// per line, each followed by a comma. A quest using version 2 constructs
+/// is preceded by a `#version 2` pragma.
+pub fn to_quest_string(quest: &Quest) -> String {
+    write_quests(core::slice::from_ref(quest), GrammarVersion::V1)
+}
+
+impl Quest {
+    /// The SHA-256 digest of the quest's [canonical form](to_quest_string).
+    ///
+    /// Equal quests have equal digests however their source was formatted,
+    /// and the value is stable across runs and platforms. Manifests store it
+    /// next to shipped content to catch quests that were edited or tampered
+    /// with.
+    pub fn content_hash(&self) -> [u8; 32] {
+        Sha256::digest(to_quest_string(self).as_bytes()).into()
+    }
+
+    /// A 64-bit FNV-1a hash of the quest's [canonical form](to_quest_string),
+    /// cheaper than [`content_hash`](Quest::content_hash) for noticing
+    /// that a quest changed, such as between two reloads. It is not a
+    /// cryptographic hash and does not stop deliberate forgery.
+    pub fn change_hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        to_quest_string(self)
+            .bytes()
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+            })
+    }
+}
+
+/// Renders quests as one file of canonical quest DSL, separated by blank
+/// lines and behind a `#version 2` pragma if any of them needs it.
+///
+/// A `package` declaration is written in front of each run of quests of one
+/// package. Since a declaration holds up to the next one, quests outside any
+/// package are written first.
+pub fn to_quests_string(quests: &[Quest]) -> String {
+    write_quests(quests, GrammarVersion::V1)
+}
+
+/// Renders quests separated by blank lines, behind a `#version` pragma if
+/// `version` or any of the quests needs one.
+pub(crate) fn write_quests(quests: &[Quest], version: GrammarVersion) -> String {
+    let version = quests.iter().map(required_version).fold(version, Ord::max);
+    let mut order: Vec<usize> = (0..quests.len()).collect();
+    order.sort_by_key(|&i| quests[i].package.is_some());
+    let mut package = None;
+    let rendered: Vec<String> = order
+        .into_iter()
+        .map(|i| {
+            let quest = &quests[i];
+            let mut out = String::new();
+            if quest.package != package {
+                package.clone_from(&quest.package);
+                if let Some(package) = &package {
+                    out.push_str(&format!("package {}\n\n", quoted(package)));
+                }
+            }
+            out.push_str(&quest.to_string());
+            out
+        })
+        .collect();
+    let rendered = rendered.join("\n");
+    match version {
+        GrammarVersion::V1 => rendered,
+        version => format!("#version {version}\n\n{rendered}"),
+    }
+}
+
+/// The oldest grammar version that can express `quest`.
+fn required_version(quest: &Quest) -> GrammarVersion {
+    if quest.rewards.is_empty() && quest.reward_tiers.is_empty() {
+        GrammarVersion::V1
+    } else {
+        GrammarVersion::V2
+    }
+}
+
+/// Lays out `input` in the standard style without changing what it says,
+/// working on its [lossless syntax tree](ast).
+///
+/// Every property goes on a line of its own, indented by four spaces and
+/// followed by a comma, with its attributes on the lines before it and a
+/// comment on the same line kept after it. Properties are put in the order
+/// [`to_quest_string`] writes them in, those of one kind, such as the steps
+/// or two `zone`s, staying in the order they were written. Values, `let`,
+/// `include`, `template`, `npc` and `item` declarations and `from`
+/// instantiations are kept as written, but for their indentation, which
+/// grows by four spaces inside each bracket; text inside multi-line strings
+/// is left alone. Quests are separated by one blank line, and other blank
+/// lines are kept, but never more than one in a row.
+///
+/// A comment the tree does not keep, such as one between a key and its
+/// colon, fails with [`ParseError::Unformattable`] rather than being
+/// dropped.
+pub fn format_source(input: &str) -> Result<String, ParseError> {
+    let tree = ast::parse(input)?;
+    // Laid out in source order the tokens must be those of the input, so
+    // nothing was dropped; the canonical order only moves whole properties.
+    check_tokens_kept(input, &layout(&tree, false)?)?;
+    layout(&tree, true)
+}
+
+/// Lays out `tree`, with the properties of each quest in canonical order if
+/// `canonical` is set and in source order otherwise.
+fn layout(tree: &ast::SyntaxTree, canonical: bool) -> Result<String, ParseError> {
+    let mut out = String::new();
+    let mut after_quest = false;
+    for item in &tree.items {
+        match item {
+            ast::Item::Quest(quest) => {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                write_quest_node(&mut out, tree, quest, canonical)?;
+                after_quest = true;
+            }
+            ast::Item::Declaration(declaration) => {
+                if !out.is_empty() && (after_quest || declaration.blank_line_before) {
+                    out.push('\n');
+                }
+                write_comments(&mut out, "", &declaration.comments);
+                write_text(&mut out, tree, declaration.span, "")?;
+                out.push('\n');
+                after_quest = false;
+            }
+        }
+    }
+    if !tree.trailing_comments.is_empty() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        write_comments(&mut out, "", &tree.trailing_comments);
+    }
+    Ok(out)
+}
+
+/// The known properties in the order [`Quest`]'s `Display` writes them.
+/// `repeat` and `steps` go with `step`.
+const PROPERTY_ORDER: &[&str] = &[
+    "id",
+    "active",
+    "reward",
+    "requires",
+    "tags",
+    "giver",
+    "turn_in",
+    "step",
+    "objective",
+    "complete_when",
+    "stages",
+    "time_limit",
+    "level",
+    "starts_at",
+    "ends_at",
+    "on_success",
+    "on_failure",
+    "on_start",
+    "on_complete",
+];
+
+/// Where a property with `key` goes in canonical order: known properties
+/// by [`PROPERTY_ORDER`], then any other sorted by key.
+fn property_rank(key: &str) -> (usize, &str) {
+    let key = match key {
+        "repeat" | "steps" => "step",
+        key => key,
+    };
+    match PROPERTY_ORDER.iter().position(|&known| known == key) {
+        Some(rank) => (rank, ""),
+        None => (PROPERTY_ORDER.len(), key),
+    }
+}
+
+fn write_quest_node(
+    out: &mut String,
+    tree: &ast::SyntaxTree,
+    quest: &ast::QuestNode,
+    canonical: bool,
+) -> Result<(), ParseError> {
+    write_comments(out, "", &quest.comments);
+    for attribute in &quest.attributes {
+        out.push_str(&format!("#[{}]\n", attribute.text));
+    }
+    out.push_str("quest ");
+    out.push_str(tree.text(quest.name_span));
+    match quest.kind {
+        ast::QuestKind::Instantiation { .. } => {
+            let rest = tree.text(Span::new(quest.name_span.end, quest.span.end));
+            let start = quest.span.end - rest.trim_start().len();
+            out.push(' ');
+            write_text(out, tree, Span::new(start, quest.span.end), "")?;
+            out.push('\n');
+            return Ok(());
+        }
+        ast::QuestKind::Override => out.push_str(" override {"),
+        ast::QuestKind::Definition => out.push_str(" {"),
+    }
+    if quest.properties.is_empty() && quest.closing_comments.is_empty() {
+        out.push_str("}\n");
+        return Ok(());
+    }
+    out.push('\n');
+    let mut properties: Vec<&ast::Property> = quest.properties.iter().collect();
+    if canonical {
+        // Stable, so properties of one rank keep their order.
+        properties.sort_by(|a, b| property_rank(&a.key).cmp(&property_rank(&b.key)));
+    }
+    for (i, property) in properties.into_iter().enumerate() {
+        if i > 0 && property.blank_line_before {
+            out.push('\n');
+        }
+        write_comments(out, INDENT, &property.comments);
+        for attribute in &property.attributes {
+            out.push_str(&format!("{INDENT}#[{}]\n", attribute.text));
+        }
+        out.push_str(INDENT);
+        out.push_str(&property.key);
+        if !property.is_block {
+            out.push_str(": ");
+        } else if !property.value.starts_with('(') {
+            out.push(' ');
+        }
+        write_text(out, tree, property.value_span, INDENT)?;
+        out.push(',');
+        if let Some(comment) = &property.trailing_comment {
+            out.push_str(" //");
+            out.push_str(comment.text.trim_end());
+        }
+        out.push('\n');
+    }
+    write_comments(out, INDENT, &quest.closing_comments);
+    out.push_str("}\n");
+    Ok(())
+}
+
+fn write_comments<'a>(
+    out: &mut String,
+    indent: &str,
+    comments: impl IntoIterator<Item = &'a ast::Comment>,
+) {
+    for comment in comments {
+        out.push_str(indent);
+        out.push_str("//");
+        out.push_str(comment.text.trim_end());
+        out.push('\n');
+    }
+}
+
+/// Writes the source at `span`, indenting each line after the first by
+/// `indent` and four more spaces for every bracket still open at its start.
+/// Line breaks inside a token, which only strings have, are kept with the
+/// text around them.
+fn write_text(
+    out: &mut String,
+    tree: &ast::SyntaxTree,
+    span: Span,
+    indent: &str,
+) -> Result<(), ParseError> {
+    let text = tree.text(span);
+    let mut tokens = Vec::new();
+    for token in Lexer::new(text) {
+        tokens.push(token?);
+    }
+    let in_string = |offset: usize| {
+        tokens.iter().any(|(token, s)| {
+            !matches!(token, Token::Comment(_)) && s.start < offset && offset < s.end
+        })
+    };
+    let depth = |offset: usize| {
+        let mut depth = 0usize;
+        for (token, s) in tokens.iter().take_while(|(_, s)| s.start <= offset) {
+            match token {
+                Token::LBrace | Token::LBracket | Token::LParen if s.start < offset => depth += 1,
+                Token::RBrace | Token::RBracket | Token::RParen => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        depth
+    };
+
+    let mut rest = text;
+    let mut offset = 0;
+    let mut first = true;
+    loop {
+        let end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+        let mut line = &rest[..end];
+        if !in_string(offset + end) {
+            line = line.trim_end();
+        }
+        if !first && !in_string(offset) && !line.is_empty() {
+            let code = line.trim_start();
+            out.push_str(indent);
+            out.push_str(&INDENT.repeat(depth(offset + line.len() - code.len())));
+            line = code;
+        }
+        out.push_str(line);
+        if end == rest.len() {
+            return Ok(());
+        }
+        let break_len = if rest[end..].starts_with("\r\n") {
+            2
+        } else {
+            1
+        };
+        if in_string(offset + end) {
+            out.push_str(&rest[end..end + break_len]);
+        } else {
+            out.push('\n');
+        }
+        rest = &rest[end + break_len..];
+        offset += end + break_len;
+        first = false;
+    }
+}
+
+/// Fails unless `formatted` has the tokens of `input`, commas aside, so
+/// that formatting never drops a comment the syntax tree does not keep.
+fn check_tokens_kept(input: &str, formatted: &str) -> Result<(), ParseError> {
+    let tokens = |text| -> Result<Vec<(Token, Span)>, ParseError> {
+        Lexer::new(text)
+            .filter(|token| !matches!(token, Ok((Token::Comma, _))))
+            .map(|token| match token? {
+                (Token::Comment(text), span) => Ok((Token::Comment(text.trim_end()), span)),
+                token => Ok(token),
+            })
+            .collect()
+    };
+    let before = tokens(input)?;
+    let after = tokens(formatted)?;
+    let kept = before
+        .iter()
+        .zip(&after)
+        .position(|((a, _), (b, _))| a != b)
+        .unwrap_or(before.len().min(after.len()));
+    if kept == before.len() && kept == after.len() {
+        return Ok(());
+    }
+    let what = match before.get(kept) {
+        Some((Token::Comment(_), span)) => {
+            format!("the comment on line {}", line_col(input, span.start).0)
+        }
+        _ => "this file".to_string(),
+    };
+    Err(ParseError::Unformattable(what))
+}
+
+impl fmt::Display for Quest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "quest {} {{", quoted(&self.name))?;
+        if let Some(id) = &self.id {
+            writeln!(f, "{INDENT}id: {},", quoted(id))?;
+        }
+        match self.expressions.get("active") {
+            Some(expr) => writeln!(f, "{INDENT}active: {expr},")?,
+            None => writeln!(f, "{INDENT}active: {},", self.active)?,
+        }
+        match self.expressions.get("reward") {
+            Some(expr) => writeln!(f, "{INDENT}reward: {expr},")?,
+            None => writeln!(f, "{INDENT}reward: {},", self.reward)?,
+        }
+        if !self.rewards.is_empty() {
+            f.write_str(INDENT)?;
+            f.write_str("reward ")?;
+            write_amounts(f, &self.rewards)?;
+            f.write_str(",\n")?;
+        }
+        for tier in &self.reward_tiers {
+            write!(f, "{INDENT}reward tier {} ", quoted(&tier.name))?;
+            write_amounts(f, &tier.rewards)?;
+            f.write_str(",\n")?;
+        }
+        for name in &self.requires {
+            writeln!(f, "{INDENT}requires: {},", quoted(name))?;
+        }
+        if !self.tags.is_empty() {
+            let tags: Vec<String> = self.tags.iter().map(|tag| quoted(tag)).collect();
+            writeln!(f, "{INDENT}tags: [{}],", tags.join(", "))?;
+        }
+        if let Some(giver) = &self.giver {
+            writeln!(f, "{INDENT}giver: {giver},")?;
+        }
+        if let Some(turn_in) = &self.turn_in {
+            writeln!(f, "{INDENT}turn_in: {turn_in},")?;
+        }
+        match &self.step_tree {
+            Some(StepTree::AllOf(items)) if items.is_empty() => {
+                writeln!(f, "{INDENT}steps {{}},")?;
+            }
+            Some(StepTree::AllOf(items)) => {
+                writeln!(f, "{INDENT}steps {{")?;
+                for item in items {
+                    writeln!(f, "{INDENT}{INDENT}{item}")?;
+                }
+                writeln!(f, "{INDENT}}},")?;
+            }
+            Some(tree) => writeln!(f, "{INDENT}steps {{ {tree} }},")?,
+            None => {
+                for step in &self.steps {
+                    writeln!(f, "{INDENT}step: {step},")?;
+                }
+            }
+        }
+        for objective in &self.objectives {
+            writeln!(f, "{INDENT}objective {objective},")?;
+        }
+        if let Some(condition) = &self.complete_when {
+            writeln!(f, "{INDENT}complete_when: {condition},")?;
+        }
+        if let Some(stages) = &self.stages {
+            writeln!(f, "{INDENT}stages {stages},")?;
+        }
+        if let Some(time_limit) = self.time_limit {
+            writeln!(f, "{INDENT}time_limit: {},", format_duration(time_limit))?;
+        }
+        if let Some((min, max)) = self.level_range {
+            writeln!(f, "{INDENT}level: {min}..{max},")?;
+        }
+        if let Some(starts_at) = self.starts_at {
+            writeln!(f, "{INDENT}starts_at: {starts_at},")?;
+        }
+        if let Some(ends_at) = self.ends_at {
+            writeln!(f, "{INDENT}ends_at: {ends_at},")?;
+        }
+        if let Some(outcome) = &self.on_success {
+            writeln!(f, "{INDENT}on_success {outcome},")?;
+        }
+        if let Some(outcome) = &self.on_failure {
+            writeln!(f, "{INDENT}on_failure {outcome},")?;
+        }
+        if !self.on_start.is_empty() {
+            writeln!(f, "{INDENT}on_start {},", format_actions(&self.on_start))?;
+        }
+        if !self.on_complete.is_empty() {
+            writeln!(
+                f,
+                "{INDENT}on_complete {},",
+                format_actions(&self.on_complete)
+            )?;
+        }
+        let values = self.extra.iter().map(|(k, v)| (k, v as &dyn fmt::Display));
+        let expressions = self
+            .expressions
+            .iter()
+            .filter(|(k, _)| !matches!(k.as_str(), "active" | "reward"))
+            .map(|(k, v)| (k, v as &dyn fmt::Display));
+        let mut extra: Vec<_> = values.chain(expressions).collect();
+        extra.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in extra {
+            writeln!(f, "{INDENT}{key}: {value},")?;
+        }
+        f.write_str("}\n")
+    }
+}
+
+/// Writes a `{ gold: 100, gems: 5 }` block of amounts.
+fn write_amounts(f: &mut fmt::Formatter<'_>, rewards: &[(Currency, i64)]) -> fmt::Result {
+    f.write_str("{")?;
+    for (i, (name, amount)) in rewards.iter().enumerate() {
+        let separator = if i > 0 { ", " } else { " " };
+        write!(f, "{separator}{name}: {amount}")?;
+    }
+    f.write_str(" }")
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (kind, target, count) = self.fields();
+        write!(f, "{{ kind: {}", quoted(kind))?;
+        if let Some(target) = target {
+            write!(f, ", target: {}", quoted(target))?;
+        }
+        if let Some(count) = count {
+            write!(f, ", count: {count}")?;
+        }
+        f.write_str(" }")
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl fmt::Display for NpcRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "npc({})", quoted(&self.name))?;
+        if let Some(location) = &self.location {
+            write!(f, " at {}", quoted(location))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fields = Vec::new();
+        if self.reward != 0 {
+            fields.push(format!("reward: {}", self.reward));
+        }
+        if self.penalty != 0 {
+            fields.push(format!("penalty: {}", self.penalty));
+        }
+        for name in &self.unlocks {
+            fields.push(format!("unlock: {}", quoted(name)));
+        }
+        let mut extra: Vec<_> = self.extra.iter().collect();
+        extra.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, value) in extra {
+            fields.push(format!("{key}: {value}"));
+        }
+        if fields.is_empty() {
+            f.write_str("{}")
+        } else {
+            write!(f, "{{ {} }}", fields.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.args.as_slice() {
+            [arg] if !matches!(arg, Value::List(_)) => write!(f, "{}: {arg}", self.name),
+            args => write!(f, "{}: {}", self.name, Value::List(args.to_vec())),
+        }
+    }
+}
+
+/// Renders the block of an `on_start` or `on_complete` property.
+pub(crate) fn format_actions(actions: &[Action]) -> String {
+    if actions.is_empty() {
+        return "{}".to_string();
+    }
+    let actions: Vec<String> = actions.iter().map(Action::to_string).collect();
+    format!("{{ {} }}", actions.join(", "))
+}
+
+impl fmt::Display for StateMachine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("{")?;
+        for (i, transition) in self.transitions().iter().enumerate() {
+            let separator = if i > 0 { ", " } else { " " };
+            write!(f, "{separator}{} -> {}", transition.from, transition.to)?;
+        }
+        f.write_str(" }")
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Start => f.write_str("start"),
+            Stage::End => f.write_str("end"),
+            Stage::Named(name) => f.write_str(&quoted(name)),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Only the parentheses the structure needs are written; operators are
+        // left-associative, so an equal-precedence right operand needs them.
+        match self {
+            Expr::Literal(value) => write!(f, "{value}"),
+            Expr::Identifier(name) => f.write_str(name),
+            Expr::Binary { op, lhs, rhs } => {
+                let binds_looser = |expr: &Expr, right: bool| match expr {
+                    Expr::Binary { op: inner, .. } => {
+                        inner.precedence() < op.precedence()
+                            || (right && inner.precedence() == op.precedence())
+                    }
+                    _ => false,
+                };
+                write_operand(f, lhs, binds_looser(lhs, false))?;
+                write!(f, " {} ", op.symbol())?;
+                write_operand(f, rhs, binds_looser(rhs, true))
+            }
+        }
+    }
+}
+
+fn write_operand(f: &mut fmt::Formatter<'_>, expr: &Expr, parenthesize: bool) -> fmt::Result {
+    if parenthesize {
+        write!(f, "({expr})")
+    } else {
+        write!(f, "{expr}")
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)?;
+        if self.is_plain() {
+            return Ok(());
+        }
+        let mut attributes = Vec::new();
+        if self.optional {
+            attributes.push("optional".to_string());
+        }
+        if self.hidden {
+            attributes.push("hidden".to_string());
+        }
+        if let Some(order) = self.order {
+            attributes.push(format!("order: {order}"));
+        }
+        write!(f, " [{}]", attributes.join(", "))
+    }
+}
+
+/// Writes a group on one line, such as `any_of { "A", ordered { "B", "C" } }`.
+impl fmt::Display for StepTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (keyword, items) = match self {
+            StepTree::Step(step) => return write!(f, "{step}"),
+            StepTree::Sequence(items) => ("ordered", items),
+            StepTree::AnyOf(items) => ("any_of", items),
+            StepTree::AllOf(items) => ("all_of", items),
+        };
+        if items.is_empty() {
+            return write!(f, "{keyword} {{}}");
+        }
+        let items: Vec<String> = items.iter().map(StepTree::to_string).collect();
+        write!(f, "{keyword} {{ {} }}", items.join(", "))
+    }
+}
+
+impl fmt::Display for Text {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Text::Plain(s) => f.write_str(&quoted(s)),
+            Text::Localized(localized) => write!(f, "{localized}"),
+        }
+    }
+}
+
+impl fmt::Display for LocalizedString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut texts: Vec<_> = self.0.iter().collect();
+        texts.sort();
+        f.write_str("{")?;
+        for (i, (lang, text)) in texts.into_iter().enumerate() {
+            let separator = if i > 0 { ", " } else { " " };
+            write!(f, "{separator}{lang}: {}", quoted(text))?;
+        }
+        f.write_str(" }")
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => f.write_str(&quoted(s)),
+            Value::Number(n) => write!(f, "{n}"),
+            // `{}` prints the shortest representation that reads back to the
+            // same value, but drops the fraction for whole numbers.
+            Value::Float(x) if x % 1.0 == 0.0 => write!(f, "{x:.1}"),
+            Value::Float(x) => write!(f, "{x}"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Raw { lang, body } => {
+                // The fence only has to be longer than any run of backticks
+                // in the body.
+                let longest = body.split(|c| c != '`').map(str::len).max();
+                let fence = "`".repeat(longest.unwrap_or_default().max(2) + 1);
+                let lang = lang.as_deref().unwrap_or_default();
+                write!(f, "{fence}{lang}\n{body}\n{fence}")
+            }
+            Value::Localized(localized) => write!(f, "{localized}"),
+            Value::Duration(duration) => f.write_str(&format_duration(*duration)),
+            Value::Range(min, max) => write!(f, "{min}..{max}"),
+            Value::DateTime(instant) => write!(f, "{instant}"),
+            Value::List(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+/// Writes a duration as a literal such as `1d2h30m`, dropping fractions of
+/// a second. A zero duration is written as `0s`.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let mut seconds = duration.as_secs();
+    if seconds == 0 {
+        return "0s".to_string();
+    }
+    let mut out = String::new();
+    for (unit, size) in [('d', 86_400), ('h', 3_600), ('m', 60), ('s', 1)] {
+        if seconds >= size {
+            out.push_str(&format!("{}{unit}", seconds / size));
+            seconds %= size;
+        }
+    }
+    out
+}
+
+/// Quotes a string literal, escaping the characters the lexer treats specially.
+pub(crate) fn quoted(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    quest_diff, to_quest_string, Action, ParseError, Parser, QuestChange, Value,
+};
+
+use common::parse;
+
+#[test]
+fn test_action_blocks_keep_source_order() -> Result<()> {
+    let quest = parse(
+        r#"on_start { give_item: "Map", play_sound: "fanfare", give_item: "Torch" },
+        on_complete { spawn: ["wolf", 3], fade_out: [] }"#,
+    )?;
+    assert_eq!(
+        quest.on_start,
+        [
+            Action::new("give_item", vec![Value::String("Map".into())]),
+            Action::new("play_sound", vec![Value::String("fanfare".into())]),
+            Action::new("give_item", vec![Value::String("Torch".into())]),
+        ]
+    );
+    assert_eq!(
+        quest.on_complete,
+        [
+            Action::new(
+                "spawn",
+                vec![Value::String("wolf".into()), Value::Number(3)]
+            ),
+            Action::new("fade_out", Vec::new()),
+        ]
+    );
+    assert!(quest.summary().contains(
+        "  On start:   give_item \"Map\", play_sound \"fanfare\", give_item \"Torch\"\n"
+    ));
+    assert!(quest
+        .summary()
+        .contains("  On complete: spawn \"wolf\" 3, fade_out\n"));
+    Ok(())
+}
+
+#[test]
+fn test_action_blocks_round_trip() -> Result<()> {
+    let quest = parse(r#"on_start { show: [["a", "b"]], wait: 2s }"#)?;
+    let names = vec![Value::String("a".into()), Value::String("b".into())];
+    assert_eq!(quest.on_start[0].args, [Value::List(names)]);
+    let text = to_quest_string(&quest);
+    assert!(text.contains("    on_start { show: [[\"a\", \"b\"]], wait: 2s },\n"));
+    assert_eq!(Parser::new(&text)?.parse_quest()?, quest);
+    Ok(())
+}
+
+#[test]
+fn test_action_block_needs_values() {
+    assert!(matches!(
+        parse("on_start { give_item }"),
+        Err(ParseError::SyntaxError { .. })
+    ));
+}
+
+#[test]
+fn test_diff_reports_changed_actions() -> Result<()> {
+    let a = parse(r#"on_start { give_item: "Map" }"#)?;
+    let b = parse(r#"on_start { give_item: "Map", play_sound: "fanfare" }"#)?;
+    let changes = quest_diff(&a, &b);
+    assert!(matches!(
+        &changes[..],
+        [QuestChange::ActionsChanged {
+            key: "on_start",
+            ..
+        }]
+    ));
+    assert_eq!(
+        changes[0].to_string(),
+        r#"on_start changed from { give_item: "Map" } to { give_item: "Map", play_sound: "fanfare" }"#
+    );
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::ast::{self, Item, QuestKind};
+use game_quest_parser_Hodik::{format_source, ParseError, Span};
+
+const SOURCE: &str = r#"#version 2
+
+// Opening act
+quest "Intro" {
+    // How much the player gets
+    reward: 100 gold, 5 gems, // starter pack
+    step: "Wake up" [optional]
+
+    objective { kind: "talk_to", target: "Elder" }
+    active: level > 2 && chance
+    // nothing else yet
+}
+
+let BONUS = 5
+quest "Mod" override { reward: 200 }
+quest "Hunt" from hunt(target: "Wolf")
+// end of file
+"#;
+
+#[test]
+fn test_properties_keep_source_order_and_layout() -> Result<()> {
+    let tree = ast::parse(SOURCE)?;
+    let intro = tree.quests().next().unwrap();
+    assert_eq!(intro.name, "Intro");
+    assert_eq!(intro.kind, QuestKind::Definition);
+    assert_eq!(intro.comments[0].text, " Opening act");
+    assert!(intro.blank_line_before);
+
+    let keys: Vec<&str> = intro.properties.iter().map(|p| p.key.as_str()).collect();
+    assert_eq!(keys, ["reward", "step", "objective", "active"]);
+    let [reward, step, objective, active] = &intro.properties[..] else {
+        unreachable!()
+    };
+    assert_eq!(reward.comments[0].text, " How much the player gets");
+    assert_eq!(reward.value, "100 gold, 5 gems");
+    assert!(reward.comma);
+    assert_eq!(
+        reward.trailing_comment.as_ref().unwrap().text,
+        " starter pack"
+    );
+    assert_eq!(step.value, r#""Wake up" [optional]"#);
+    assert!(!step.comma);
+    assert!(objective.is_block && objective.blank_line_before);
+    assert_eq!(objective.value, r#"{ kind: "talk_to", target: "Elder" }"#);
+    assert_eq!(active.value, "level > 2 && chance");
+    assert_eq!(intro.closing_comments[0].text, " nothing else yet");
+    Ok(())
+}
+
+#[test]
+fn test_spans_index_the_source() -> Result<()> {
+    let tree = ast::parse(SOURCE)?;
+    assert_eq!(tree.source(), SOURCE);
+    for quest in tree.quests() {
+        assert!(tree.text(quest.span).starts_with("quest"));
+        assert_eq!(tree.text(quest.name_span), format!("{:?}", quest.name));
+        for property in &quest.properties {
+            assert_eq!(tree.text(property.key_span), property.key);
+            assert_eq!(tree.text(property.value_span), property.value);
+            assert!(tree.text(property.span).starts_with(&property.key));
+        }
+    }
+    let (quest, property) = tree.key_at(SOURCE.find("objective").unwrap() + 3).unwrap();
+    assert_eq!(
+        (quest.name.as_str(), property.key.as_str()),
+        ("Intro", "objective")
+    );
+    assert_eq!(tree.key_at(SOURCE.find("Elder").unwrap()), None);
+    Ok(())
+}
+
+#[test]
+fn test_declarations_and_other_quest_kinds() -> Result<()> {
+    let tree = ast::parse(SOURCE)?;
+    let kinds: Vec<String> = tree
+        .items
+        .iter()
+        .map(|item| match item {
+            Item::Quest(quest) => format!("{:?} {:?}", quest.name, quest.kind),
+            Item::Declaration(declaration) => declaration.keyword.clone(),
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "#",
+            r#""Intro" Definition"#,
+            "let",
+            r#""Mod" Override"#,
+            r#""Hunt" Instantiation { template: "hunt" }"#,
+        ]
+    );
+    let Item::Declaration(bonus) = &tree.items[2] else {
+        unreachable!()
+    };
+    assert_eq!(tree.text(bonus.span), "let BONUS = 5");
+    assert_eq!(tree.trailing_comments[0].text, " end of file");
+    Ok(())
+}
+
+#[test]
+fn test_unbalanced_input_is_rejected() {
+    assert!(matches!(
+        ast::parse(r#"quest "A" { reward: [1, 2 }"#),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    assert_eq!(
+        ast::parse(r#"quest "A" { step: "Go""#),
+        Err(ParseError::UnclosedBrace {
+            opened_at: Span::new(10, 11),
+        })
+    );
+    assert!(matches!(
+        ast::parse(r#"reward: 5"#),
+        Err(ParseError::SyntaxError { .. })
+    ));
+}
+
+#[test]
+fn test_parses_the_example_files() -> Result<()> {
+    for path in [
+        "test_quest.txt",
+        "tests/fixtures/include/campaign.quest",
+        "tests/fixtures/include/common/rewards.quest",
+    ] {
+        let source = std::fs::read_to_string(path)?;
+        ast::parse(&source).map_err(|e| anyhow::anyhow!("{path}: {e}"))?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_format_source_keeps_comments_between_quests() -> Result<()> {
+    let source = "// Act one\nquest \"A\" { reward: 5 }\n\n// Act two\n// (draft)\nquest \"B\" {}\n// todo\n";
+    assert_eq!(
+        format_source(source)?,
+        "// Act one\nquest \"A\" {\n    reward: 5,\n}\n\n\
+         // Act two\n// (draft)\nquest \"B\" {}\n\n// todo\n"
+    );
+    assert_eq!(
+        format_source("quest \"A\" { // draft\n  reward: 5 // five\n  steps {\n    // first\n    \"Go\"\n  }\n}")?,
+        "quest \"A\" {\n    // draft\n    reward: 5, // five\n    steps {\n        // first\n        \"Go\"\n    },\n}\n"
+    );
+    assert_eq!(
+        format_source("quest \"A\" {\n    reward // five\n    : 5\n}"),
+        Err(ParseError::Unformattable("the comment on line 2".into()))
+    );
+    Ok(())
+}
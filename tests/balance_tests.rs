@@ -0,0 +1,87 @@
+use std::fs;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    balance, BalanceConfig, BalanceConfigError, BalanceSort, Parser, ProjectConfig, RewardCurve,
+};
+
+#[test]
+fn test_curve_interpolates_and_clamps() -> Result<()> {
+    let curve =
+        RewardCurve::new(&[(10, 400.0), (1, 20.0), (20, 1000.0)]).map_err(anyhow::Error::msg)?;
+    assert_eq!(curve.expected(1.0), 20.0);
+    assert_eq!(curve.expected(15.0), 700.0);
+    assert_eq!(curve.expected(0.0), 20.0);
+    assert_eq!(curve.expected(99.0), 1000.0);
+
+    assert!(RewardCurve::new(&[]).is_err());
+    assert_eq!(
+        RewardCurve::new(&[(5, 10.0), (5, 20.0)]),
+        Err("level 5 is given twice".to_string())
+    );
+    assert!(RewardCurve::new(&[(1, 0.0)]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_balance_flags_outliers() -> Result<()> {
+    let mut quests = Vec::new();
+    for file in ["main.quest", "side.quest"] {
+        let source = fs::read_to_string(format!("tests/fixtures/balance/{file}"))?;
+        quests.extend(Parser::new(&source)?.parse_quests()?);
+    }
+    let config =
+        BalanceConfig::from_toml(&fs::read_to_string("tests/fixtures/balance/balance.toml")?)?;
+    assert_eq!(config.tags[0].tolerance, 0.3);
+
+    let mut report = balance(&quests, &config);
+    assert_eq!(report.skipped, ["Tutorial"]);
+    let intro = &report.entries[0];
+    assert_eq!(
+        (intro.quest.as_str(), intro.tag.as_deref()),
+        ("Intro", Some("main"))
+    );
+    assert_eq!(intro.ratio, 1.0);
+
+    report.sort_by(BalanceSort::Deviation);
+    let outliers: Vec<_> = report
+        .outliers()
+        .map(|entry| entry.quest.as_str())
+        .collect();
+    assert_eq!(outliers, ["Wolves", "Finale"]);
+    assert_eq!(report.entries[0].deviation(), 1.25);
+
+    report.sort_by(BalanceSort::Level);
+    assert_eq!(report.entries[0].quest, "Intro");
+    assert_eq!(report.entries[4].level, 25.0);
+    report.sort_by(BalanceSort::Reward);
+    assert_eq!(report.entries[0].reward, 1500);
+    Ok(())
+}
+
+#[test]
+fn test_balance_config_errors() {
+    assert_eq!(
+        BalanceConfig::from_toml("curve = [[1, 10, 20]]"),
+        Err(BalanceConfigError::InvalidValue {
+            key: "curve".to_string(),
+            message: "expected a list of [level, reward] points".to_string(),
+        })
+    );
+    assert_eq!(
+        BalanceConfig::from_toml("[tags.main]\ntolerance = 0.1"),
+        Err(BalanceConfigError::InvalidValue {
+            key: "tags.main".to_string(),
+            message: "missing `curve`".to_string(),
+        })
+    );
+    assert!(matches!(
+        BalanceConfig::from_toml("tolerance = -1"),
+        Err(BalanceConfigError::InvalidValue { .. })
+    ));
+
+    let project = ProjectConfig::from_toml("[balance]\ncurve = [[1, 50]]").unwrap();
+    let config = project.balance.expect("a [balance] table");
+    assert_eq!(config.curve.map(|curve| curve.expected(7.0)), Some(50.0));
+    assert_eq!(config.tolerance, 0.5);
+}
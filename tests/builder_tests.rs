@@ -0,0 +1,151 @@
+use std::path::Path;
+use std::thread;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    IncludeResolver, IncludeSource, ParseCache, ParseError, ParserBuilder, ParserLimits,
+    ParserOptions, QuestSchema, ValueType,
+};
+
+/// Serves one included file from memory.
+struct OneFile(&'static str);
+
+impl IncludeResolver for OneFile {
+    fn resolve(&self, path: &str, _from: Option<&str>) -> Result<IncludeSource, String> {
+        Ok(IncludeSource {
+            name: path.to_string(),
+            source: self.0.to_string(),
+        })
+    }
+}
+
+#[test]
+fn test_builders_are_shared_across_threads() -> Result<()> {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ParserBuilder>();
+
+    let builder = ParserBuilder::new().with_cache();
+    let rewards = thread::scope(|scope| {
+        let workers: Vec<_> = (0..4)
+            .map(|i| {
+                let builder = &builder;
+                scope.spawn(move || {
+                    let input = format!("quest \"Q{}\" {{ reward: {} }}", i % 2, i % 2);
+                    builder.parse_quests(&input).map(|quests| quests[0].reward)
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("the worker finishes"))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    assert_eq!(rewards, [0, 1, 0, 1]);
+    assert_eq!(builder.cache().map(|cache| cache.len()), Some(2));
+    Ok(())
+}
+
+#[test]
+fn test_cache_skips_unchanged_inputs() -> Result<()> {
+    let builder = ParserBuilder::new().with_cache();
+    let cache = builder.cache().expect("a cache");
+    let path = Path::new("tests/fixtures/stats/act1.quest");
+    let first = builder.parse_file(path)?;
+    assert_eq!(builder.parse_file(path)?, first);
+    assert_eq!((cache.len(), cache.hits()), (1, 1));
+
+    // The same text under another name, or no name, is another entry.
+    let source = std::fs::read_to_string(path)?;
+    assert_eq!(builder.parse_quests(&source)?, first);
+    assert_eq!((cache.len(), cache.hits()), (2, 1));
+
+    // Failures and inputs with includes are parsed every time.
+    assert!(builder.parse_quests("quest \"A\" { reward: }").is_err());
+    let builder = builder.with_include_resolver(OneFile("quest \"B\" {}"));
+    builder.parse_quests("include \"b.quest\"")?;
+    builder.parse_quests("include \"b.quest\"")?;
+    let cache = builder.cache().expect("a cache");
+    assert_eq!((cache.len(), cache.hits()), (2, 1));
+
+    cache.clear();
+    assert!(cache.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_cache_replaces_changed_files() -> Result<()> {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("builder_changed.quest");
+    let builder = ParserBuilder::new().with_cache();
+    let cache = builder.cache().expect("a cache");
+    std::fs::write(&path, "quest \"A\" { reward: 1 }")?;
+    assert_eq!(builder.parse_file(&path)?[0].reward, 1);
+
+    // An edited file is parsed again and takes the place of its old entry.
+    std::fs::write(&path, "quest \"A\" { reward: 2 }")?;
+    assert_eq!(builder.parse_file(&path)?[0].reward, 2);
+    assert_eq!((cache.len(), cache.hits()), (1, 0));
+    assert_eq!(builder.parse_file(&path)?[0].reward, 2);
+    assert_eq!((cache.len(), cache.hits()), (1, 1));
+
+    // Nor is the old text answered from the cache once it changed back.
+    std::fs::write(&path, "quest \"A\" { reward: 1 }")?;
+    assert_eq!(builder.parse_file(&path)?[0].reward, 1);
+    assert_eq!((cache.len(), cache.hits()), (1, 1));
+
+    // A failed parse drops the stale entry.
+    std::fs::write(&path, "quest \"A\" { reward: }")?;
+    assert!(builder.parse_file(&path).is_err());
+    assert!(cache.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_builders_apply_options_limits_and_schema() -> Result<()> {
+    let strict = ParserBuilder::new().with_options(ParserOptions {
+        strict: true,
+        ..Default::default()
+    });
+    assert!(matches!(
+        strict.parse_quests("quest \"A\" { flavour: 1 }"),
+        Err(ParseError::UnknownProperty { .. })
+    ));
+
+    let small = strict.clone().with_limits(ParserLimits {
+        max_input_bytes: 8,
+        ..ParserLimits::default()
+    });
+    assert!(small.options().strict);
+    assert!(matches!(
+        small.parse_quests("quest \"A\" {}"),
+        Err(ParseError::InputTooLarge { .. })
+    ));
+
+    let schema = QuestSchema::new().required("xp", ValueType::Number);
+    let dynamic = ParserBuilder::new().with_schema(schema);
+    let quests = dynamic.parse_dynamic("quest \"A\" { xp: 5 } quest \"B\" { xp: 6 }")?;
+    assert_eq!(quests.len(), 2);
+    assert!(ParserBuilder::new()
+        .parse_dynamic("quest \"A\" { xp: 5 }")
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_cache_evicts_least_recently_used_inputs() -> Result<()> {
+    let builder = ParserBuilder::new().with_cache();
+    let cache = builder.cache().expect("a cache");
+    let input = |i: usize| format!("quest \"Q{i}\" {{ reward: {i} }}");
+    builder.parse_quests(&input(0))?;
+    for i in 1..=ParseCache::UNNAMED_CAPACITY {
+        builder.parse_quests(&input(i))?;
+        // Keep the first input in use.
+        builder.parse_quests(&input(0))?;
+    }
+    assert_eq!(cache.len(), ParseCache::UNNAMED_CAPACITY);
+
+    let hits = cache.hits();
+    builder.parse_quests(&input(0))?;
+    builder.parse_quests(&input(1))?;
+    assert_eq!(cache.hits(), hits + 1);
+    Ok(())
+}
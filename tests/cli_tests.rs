@@ -0,0 +1,665 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+use std::{fs, thread};
+
+use anyhow::Result;
+use serde_json::Value;
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_game_quest_parser_Hodik"))
+        .args(args)
+        .output()
+        .expect("the CLI runs")
+}
+
+/// The JSON document on every line of the command's stdout.
+fn json_lines(output: &Output) -> Result<Vec<Value>> {
+    let stdout = String::from_utf8(output.stdout.clone())?;
+    Ok(stdout
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn test_parse_json() -> Result<()> {
+    let output = run(&["parse", "-f", "test_quest.txt", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let lines = json_lines(&output)?;
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["file"], "test_quest.txt");
+    assert_eq!(lines[0]["quests"][0]["name"], "The Lost Sword");
+    Ok(())
+}
+
+#[test]
+fn test_parse_errors_exit_with_1() -> Result<()> {
+    let output = run(&["parse", "-f", "tests/fixtures/cli/broken.quest"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.contains("Expected Value, found Comma"));
+
+    let output = run(&[
+        "parse",
+        "-f",
+        "tests/fixtures/cli/broken.quest",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    let error = &json_lines(&output)?[0]["error"];
+    assert_eq!(error["kind"], "parse");
+    assert_eq!(error["code"], "E0101");
+    assert_eq!(error["span"]["line"], 2);
+    assert_eq!(error["span"]["column"], 13);
+
+    let output = run(&["parse", "-d", "tests/fixtures/cli", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(1));
+    let lines = json_lines(&output)?;
+    assert_eq!(lines[0]["files"][0]["error"]["span"]["start"], 29);
+    assert_eq!(lines[0]["files"][1]["quests"], 1);
+    assert_eq!(lines[1]["error"]["kind"], "error");
+    Ok(())
+}
+
+#[test]
+fn test_windows_encodings() -> Result<()> {
+    let output = run(&[
+        "parse",
+        "-f",
+        "tests/fixtures/encoding/utf16.quest",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    let quest = &json_lines(&output)?[0]["quests"][0];
+    assert_eq!(quest["name"], "Wolves");
+    assert_eq!(quest["steps"][0]["text"], "Hunt");
+
+    let output = run(&["parse", "-f", "tests/fixtures/encoding/broken_crlf.quest"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("broken_crlf.quest:2:13"));
+    assert!(stderr.contains("2 |     reward: ,\n"));
+    Ok(())
+}
+
+#[test]
+fn test_failed_checks_exit_with_2() -> Result<()> {
+    let output = run(&[
+        "lint",
+        "-f",
+        "tests/fixtures/cli/trap.quest",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    let lines = json_lines(&output)?;
+    let found = &lines[0]["lints"][0];
+    assert_eq!(found["rule"], "zero-reward");
+    assert_eq!(found["severity"], "error");
+    assert_eq!(found["span"]["start"], 6);
+    assert_eq!(lines[1]["error"]["kind"], "invalid");
+
+    let output = run(&["fmt", "-f", "tests/fixtures/cli/trap.quest", "--check"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = run(&[
+        "simulate",
+        "-f",
+        "tests/fixtures/cli/trap.quest",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(json_lines(&output)?[0]["quests"][0]["dead_ends"][0], "pit");
+    Ok(())
+}
+
+#[test]
+fn test_fmt_fix_migrates_deprecated_syntax() -> Result<()> {
+    let file = "tests/fixtures/warnings/deprecated.quest";
+    let output = run(&["fmt", "-f", file, "--check", "--fix", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(2));
+    let lines = json_lines(&output)?;
+    assert_eq!(lines[0]["fixed"], 2);
+    assert_eq!(lines[0]["written"], false);
+
+    let output = run(&["parse", "-f", file]);
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("A single `level` is deprecated since grammar version 2"));
+    assert!(stderr.contains("help: write `step: \"Wake up\", step: \"Get dressed\"` instead"));
+    Ok(())
+}
+
+#[test]
+fn test_other_failures_exit_with_1() -> Result<()> {
+    let output = run(&[
+        "lint",
+        "-f",
+        "tests/fixtures/cli/missing.quest",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(json_lines(&output)?[0]["error"]["kind"], "error");
+    Ok(())
+}
+
+#[cfg(feature = "convert")]
+#[test]
+fn test_import_json() -> Result<()> {
+    let output = run(&["import", "--from", "json", "tests/fixtures/cli/legacy.json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let dsl = String::from_utf8(output.stdout)?;
+    assert!(dsl.starts_with("#version 2\n\nquest \"Wolves\" {\n"));
+    let quests = game_quest_parser_Hodik::Parser::new(&dsl)?.parse_quests()?;
+    assert_eq!(quests.len(), 2);
+    assert_eq!(quests[1].name, "Trade");
+    Ok(())
+}
+
+#[cfg(feature = "convert")]
+#[test]
+fn test_convert_and_import_json() -> Result<()> {
+    let output = run(&[
+        "convert",
+        "-f",
+        "test_quest.txt",
+        "--from",
+        "dsl",
+        "--to",
+        "toml",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    let lines = json_lines(&output)?;
+    assert_eq!(lines[0]["file"], "test_quest.txt");
+    assert!(lines[0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("name = \"The Lost Sword\""));
+
+    let written = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("imported.quest");
+    let output = Command::new(env!("CARGO_BIN_EXE_game_quest_parser_Hodik"))
+        .args(["import", "--from", "json", "tests/fixtures/cli/legacy.json"])
+        .args(["--format", "json", "-o"])
+        .arg(&written)
+        .output()?;
+    assert_eq!(output.status.code(), Some(0));
+    let lines = json_lines(&output)?;
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["quests"], 2);
+    assert_eq!(lines[0]["output"], written.to_str().unwrap());
+    assert!(lines[0].get("text").is_none());
+    assert!(fs::read_to_string(&written)?.contains("quest \"Trade\""));
+
+    let output = run(&[
+        "convert",
+        "-f",
+        "missing.quest",
+        "--to",
+        "toml",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(json_lines(&output)?[0]["error"]["kind"], "error");
+    Ok(())
+}
+
+#[test]
+fn test_manifest_export_and_grammar_json() -> Result<()> {
+    let output = run(&["manifest", "tests/fixtures/stats", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let lines = json_lines(&output)?;
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["quests"][0]["name"], "Intro");
+    assert_eq!(lines[0]["quests"][0]["file"], "act1.quest");
+    let digest = lines[0]["quests"][0]["sha256"].as_str().unwrap_or_default();
+    assert_eq!(digest.len(), 64);
+    assert!(digest.bytes().all(|byte| byte.is_ascii_hexdigit()));
+
+    let output = run(&[
+        "export",
+        "tests/fixtures/stats/act1.quest",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    let quests = &json_lines(&output)?[0]["quests"];
+    assert_eq!(quests[1]["name"], "Wolves");
+    assert_eq!(quests[1]["requires"][0], "Intro");
+
+    let output = run(&[
+        "export",
+        "tests/fixtures/cli/broken.quest",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(json_lines(&output)?[0]["error"]["kind"], "parse");
+
+    let output = run(&["grammar", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let grammar = &json_lines(&output)?[0];
+    let rules = grammar["rules"].as_array().unwrap();
+    assert!(rules.iter().any(|rule| rule["name"] == "QUEST_DEF"));
+    assert_eq!(grammar["tokens"][0], "IDENTIFIER");
+    Ok(())
+}
+
+#[test]
+fn test_stats() -> Result<()> {
+    let output = run(&["stats", "tests/fixtures/stats", "--format", "json"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stats = &json_lines(&output)?[0];
+    assert_eq!(stats["files"], 2);
+    assert_eq!(stats["quests"], 3);
+    assert_eq!(stats["max_reward"], 1000);
+    assert_eq!(stats["step_counts"], serde_json::json!({ "1": 2, "2": 1 }));
+    assert_eq!(
+        stats["top_tags"][0],
+        serde_json::json!({ "tag": "act1", "quests": 2 })
+    );
+    assert_eq!(stats["without_prerequisites"], serde_json::json!(["Intro"]));
+
+    let output = run(&["stats", "tests/fixtures/stats", "--top-tags", "1"]);
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(
+        stdout.contains("Quests:         3 in 2 file(s)\n"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("Reward:         416.7 on average, 1000 at most\n"));
+    assert!(stdout.contains("Top tags:\n  act1: 2\nNo prerequisites:\n  Intro\n"));
+    Ok(())
+}
+
+#[test]
+fn test_balance() -> Result<()> {
+    let dir = "tests/fixtures/balance";
+    let config = "tests/fixtures/balance/balance.toml";
+    let output = run(&["balance", dir, "--config", config, "--format", "json"]);
+    assert_eq!(output.status.code(), Some(2));
+    let lines = json_lines(&output)?;
+    assert_eq!(lines[0]["checked"], 5);
+    assert_eq!(lines[0]["outliers"], 2);
+    assert_eq!(lines[0]["entries"][0]["quest"], "Wolves");
+    assert_eq!(lines[0]["entries"][0]["line"], 10);
+    assert_eq!(lines[0]["entries"][1]["tag"], "main");
+    assert_eq!(lines[0]["skipped"], serde_json::json!(["Tutorial"]));
+
+    let output = run(&[
+        "balance", dir, "--config", config, "--sort", "name", "--all",
+    ]);
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("QUEST       LEVEL    REWARD  EXPECTED   RATIO\n"));
+    assert!(stdout.contains("\nIntro           1       100       100    1.00\n"));
+    assert!(stdout.contains(
+        "\nWolves         10       900       400    2.25  outlier at \
+         tests/fixtures/balance/side.quest:10:5\n"
+    ));
+    assert!(stdout.ends_with("2 of 5 quest(s) off the curve, 1 skipped\n"));
+    Ok(())
+}
+
+#[test]
+fn test_explain() -> Result<()> {
+    let output = run(&["explain", "e0103"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("E0103: Duplicate property\n\nSomething that can be given once"));
+
+    let output = run(&["explain", "--format", "json"]);
+    let codes = &json_lines(&output)?[0];
+    assert_eq!(codes[0]["code"], "E0001");
+    assert_eq!(codes[0]["title"], "Unexpected character");
+
+    let output = run(&["explain", "E9999"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.contains("Unknown error code \"E9999\""));
+    Ok(())
+}
+
+#[test]
+fn test_find() -> Result<()> {
+    let dir = "tests/fixtures/stats";
+    let output = run(&[
+        "find",
+        "--dir",
+        dir,
+        "--where",
+        "reward >= 100 && steps == 1",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout)?;
+    assert_eq!(
+        stdout,
+        "tests/fixtures/stats/act2/finale.quest: Finale\n1 of 3 quest(s) match\n"
+    );
+
+    let output = run(&[
+        "find",
+        "-d",
+        dir,
+        "--where",
+        "reward > 100",
+        "--format",
+        "json",
+    ]);
+    let found = &json_lines(&output)?[0];
+    assert_eq!(found["quests"], 3);
+    assert_eq!(found["matches"][0]["quest"], "Wolves");
+    assert_eq!(
+        found["matches"][1]["file"],
+        "tests/fixtures/stats/act2/finale.quest"
+    );
+
+    let output = run(&["find", "-d", dir, "--where", "reward >"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)?.contains("--> --where:1:9"));
+    Ok(())
+}
+
+#[test]
+fn test_refactor_check() -> Result<()> {
+    let dir = "tests/fixtures/refactor";
+    let output = run(&[
+        "refactor",
+        "-d",
+        dir,
+        "--renumber-steps",
+        "--rename-quest",
+        "Intro",
+        "Prologue",
+        "--check",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    let lines = json_lines(&output)?;
+    assert_eq!(lines[0]["changes"], 8);
+    assert_eq!(
+        lines[0]["files"][1]["file"],
+        "tests/fixtures/refactor/act2.quest"
+    );
+    assert_eq!(lines[0]["files"][1]["changes"], 4);
+    assert_eq!(lines[0]["written"], false);
+
+    let output = run(&["refactor", "-d", dir, "--rename-quest", "Intro", "Wolves"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr)?;
+    assert!(stderr.contains("already has a quest named \"Wolves\""));
+    Ok(())
+}
+
+#[test]
+fn test_warnings_do_not_fail_the_run() -> Result<()> {
+    let output = run(&["parse", "-f", "tests/fixtures/warnings/typo.quest"]);
+    assert_eq!(output.status.code(), Some(0));
+    let stderr = String::from_utf8(output.stderr.clone())?;
+    assert!(
+        stderr.starts_with("warning: Unknown property `rewrd`\n"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("= help: did you mean `reward`?"));
+
+    let output = run(&[
+        "parse",
+        "-f",
+        "tests/fixtures/warnings/typo.quest",
+        "--format",
+        "json",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    let warning = &json_lines(&output)?[0]["warnings"][0];
+    assert_eq!(warning["message"], "Unknown property `rewrd`");
+    assert_eq!(warning["span"]["line"], 2);
+    Ok(())
+}
+
+#[test]
+fn test_watch_runs_again_after_every_save() -> Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("watch");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)?;
+    let file = dir.join("main.quest");
+    fs::write(&file, r#"quest "A" {}"#)?;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_game_quest_parser_Hodik"))
+        .args(["parse", "--watch", "--format", "json", "-f"])
+        .arg(&file)
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let line: Value = serde_json::from_str(&line.unwrap()).unwrap();
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let next = || lines.recv_timeout(Duration::from_secs(10));
+
+    let result = (|| -> Result<()> {
+        assert_eq!(next()?["quests"][0]["name"], "A");
+        // Give the watcher time to start before the first save.
+        thread::sleep(Duration::from_millis(500));
+        fs::write(&file, r#"quest "A" { reward: }"#)?;
+        assert_eq!(next()?["error"]["kind"], "parse");
+        // Saved the way many editors do, by replacing the file; a change
+        // to another file in the directory is not a change to the quest.
+        fs::write(dir.join("notes.txt"), "todo")?;
+        fs::write(dir.join("main.quest.tmp"), r#"quest "B" {}"#)?;
+        fs::rename(dir.join("main.quest.tmp"), &file)?;
+        assert_eq!(next()?["quests"][0]["name"], "B");
+        assert!(lines.recv_timeout(Duration::from_millis(500)).is_err());
+        Ok(())
+    })();
+    child.kill()?;
+    child.wait()?;
+    result
+}
+
+/// Runs the CLI in `tests/fixtures/project`, which has a quest-parser.toml.
+fn run_in_project(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_game_quest_parser_Hodik"))
+        .args(args)
+        .current_dir("tests/fixtures/project/packs")
+        .output()
+        .expect("the CLI runs")
+}
+
+#[test]
+fn test_project_config_sets_defaults() -> Result<()> {
+    // Version 2 rewards, the shared include path and JSON output all come
+    // from the config.
+    let output = run_in_project(&["parse", "-f", "main.quest"]);
+    assert_eq!(output.status.code(), Some(0));
+    let quests = &json_lines(&output)?[0]["quests"];
+    assert_eq!(quests[0]["name"], "Shared Errand");
+    assert_eq!(quests[1]["name"], "Main Road");
+
+    let output = run_in_project(&["parse", "-f", "flavoured.quest"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(json_lines(&output)?[0]["error"]["kind"], "parse");
+
+    let output = run_in_project(&[
+        "parse",
+        "-f",
+        "flavoured.quest",
+        "--no-strict",
+        "--format",
+        "summary",
+    ]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stdout)?.contains("Sweet Tooth"));
+
+    let output = run_in_project(&["lint", "-f", "free.quest", "--format", "text"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stdout)?.contains("zero-reward"));
+    Ok(())
+}
+
+#[test]
+fn test_repl_parses_each_snippet() -> Result<()> {
+    // Snippets end at a blank line, at `;;` or at the end of the input, and
+    // one that fails does not stop the ones after it.
+    let input = concat!(
+        "quest \"A\" { reward: 5 }\n",
+        "\n",
+        "quest \"B\" {\n",
+        "    step: \"Go\" } ;;\n",
+        "let x = 1;;\n",
+        "quest \"C\" { reward: }\n",
+        "\n",
+        "\n",
+        "quest \"D\" {}",
+    );
+    let mut child = Command::new(env!("CARGO_BIN_EXE_game_quest_parser_Hodik"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    assert_eq!(output.status.code(), Some(0));
+
+    // Without a terminal there are no prompts, only results.
+    let stdout = String::from_utf8(output.stdout)?;
+    let names: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("    name: "))
+        .collect();
+    assert_eq!(names, [r#""A","#, r#""B","#, r#""D","#]);
+    assert!(stdout.contains("text: Plain(\n                \"Go\",\n"));
+    assert!(stdout.contains("(no quests)\n"));
+    assert!(stdout.contains("error[E0101]: Expected Value, found RBrace\n --> <repl>:1:21\n"));
+    assert!(!stdout.contains("quest>"));
+
+    let order: Vec<usize> = ["\"A\"", "\"B\"", "(no quests)", "<repl>", "\"D\""]
+        .iter()
+        .filter_map(|text| stdout.find(text))
+        .collect();
+    assert_eq!(order.len(), 5);
+    assert!(order.is_sorted());
+    Ok(())
+}
+
+#[test]
+fn test_parse_dir_summarizes_every_file() -> Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("parse-dir");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("act1/deep"))?;
+    fs::create_dir_all(dir.join("act2"))?;
+    fs::write(dir.join("act1/one.quest"), r#"quest "A" {} quest "B" {}"#)?;
+    fs::write(dir.join("act1/deep/two.quest"), r#"quest "C" {}"#)?;
+    fs::write(dir.join("act2/broken.quest"), r#"quest "D" { reward: }"#)?;
+    fs::write(dir.join("notes.txt"), "not a quest")?;
+    let dir_arg = dir.to_str().expect("the temp dir is UTF-8");
+    let name = |file: &str| dir.join(file).display().to_string();
+
+    // Every .quest file below the directory, in path order, and a failure
+    // fails the run.
+    let output = run(&["parse", "-d", dir_arg]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8(output.stdout)?;
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+    assert_eq!(rows[0], ["FILE", "STATUS", "QUESTS", "DETAILS"]);
+    assert_eq!(rows[1], [name("act1/deep/two.quest").as_str(), "ok", "1"]);
+    assert_eq!(rows[2], [name("act1/one.quest").as_str(), "ok", "2"]);
+    assert_eq!(
+        rows[3][..3],
+        [name("act2/broken.quest").as_str(), "FAILED", "-"]
+    );
+    assert!(stdout.contains("1:21: Expected Value, found RBrace"));
+    assert!(stdout.ends_with("\n3 files, 2 passed, 1 failed\n"));
+
+    // --glob matches paths relative to the directory; `*` stays within one
+    // directory.
+    for jobs in ["1", "4"] {
+        let output = run(&["parse", "-d", dir_arg, "--glob", "act1/*.quest", "-j", jobs]);
+        assert_eq!(output.status.code(), Some(0));
+        let stdout = String::from_utf8(output.stdout)?;
+        assert!(stdout.contains(&name("act1/one.quest")));
+        assert!(!stdout.contains("two.quest"));
+        assert!(stdout.ends_with("\n1 files, 1 passed, 0 failed\n"));
+    }
+
+    let output = run(&["parse", "-d", dir_arg, "--format", "json"]);
+    assert_eq!(output.status.code(), Some(1));
+    let files = &json_lines(&output)?[0]["files"];
+    assert_eq!(files[0]["file"], name("act1/deep/two.quest"));
+    assert_eq!(files[0]["quests"], 1);
+    assert_eq!(files[1]["quests"], 2);
+    assert_eq!(files[2]["error"]["code"], "E0101");
+    assert_eq!(files.as_array().map(Vec::len), Some(3));
+    Ok(())
+}
+
+#[test]
+fn test_tokenize() -> Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("tokenize");
+    fs::create_dir_all(&dir)?;
+    let file = dir.join("tokens.quest");
+    fs::write(&file, "quest \"A\" {\n  reward: 5,\n}\n")?;
+    let file = file.to_str().expect("the temp dir is UTF-8");
+
+    let output = run(&["tokenize", "-f", file]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        concat!(
+            "1:1\t0..5\tQuestKeyword\n",
+            "1:7\t6..9\tStringLiteral(\"A\")\n",
+            "1:11\t10..11\tLBrace\n",
+            "2:3\t14..20\tIdentifier(\"reward\")\n",
+            "2:9\t20..21\tColon\n",
+            "2:11\t22..23\tNumber(5)\n",
+            "2:12\t23..24\tComma\n",
+            "3:1\t25..26\tRBrace\n",
+        )
+    );
+
+    for flags in [&["--json"][..], &["--format", "json"]] {
+        let mut args = vec!["tokenize", "-f", file];
+        args.extend(flags);
+        let output = run(&args);
+        assert_eq!(output.status.code(), Some(0));
+        let tokens = &json_lines(&output)?[0];
+        assert_eq!(tokens.as_array().map(Vec::len), Some(8));
+        assert_eq!(
+            tokens[1],
+            serde_json::json!({
+                "kind": "StringLiteral", "text": "\"A\"",
+                "start": 6, "end": 9, "line": 1, "column": 7,
+            })
+        );
+    }
+
+    // A character the lexer rejects ends the stream with a rendered error,
+    // after the tokens before it.
+    let bad = dir.join("bad.quest");
+    fs::write(&bad, "quest \"A\" { @ }\n")?;
+    let output = run(&[
+        "tokenize",
+        "-f",
+        bad.to_str().expect("the temp dir is UTF-8"),
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8(output.stdout)?.lines().count(), 3);
+    assert!(String::from_utf8(output.stderr)?.contains("Unexpected character: @"));
+    Ok(())
+}
@@ -0,0 +1,14 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+
+use game_quest_parser_Hodik::{ParseError, Parser, Quest};
+
+/// Parses `body` as the properties of a quest named `A`.
+pub fn parse(body: &str) -> Result<Quest, ParseError> {
+    Parser::new(&format!("quest \"A\" {{ {body} }}")).and_then(|mut p| p.parse_quest())
+}
+
+/// Like [`parse`], in a file behind a `#version 2` pragma.
+pub fn parse_v2(body: &str) -> Result<Quest, ParseError> {
+    Parser::new(&format!("#version 2\nquest \"A\" {{ {body} }}")).and_then(|mut p| p.parse_quest())
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "compile")]
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{compile, load_compiled, LoadError, Parser};
+
+const SOURCE: &str = r#"#version 2
+quest "Wolves" {
+    step: "Hunt",
+    step: { en: "Return", uk: "Повернутися" },
+    requires: "Village",
+    reward: 40,
+    reward { gold: 100, gems: 5 },
+    active: level >= 3,
+    time_limit: 1h30m,
+    objective { kind: "kill", target: "Wolf", count: 3 },
+    objective { kind: "escort", target: "Cart" },
+    stages { start -> hunt, hunt -> end },
+    on_success { reward: 10, unlock: "Den" },
+    difficulty: 2.5,
+    tags: ["hunt", "forest"],
+    cooldown: 30s,
+}
+quest "Den" { reward: 5 }
+"#;
+
+#[test]
+fn test_pack_round_trips_every_field() -> Result<()> {
+    let quests = Parser::new(SOURCE)?.parse_quests()?;
+    let pack = compile(&quests);
+    assert!(pack.starts_with(b"GQPK"));
+    assert_eq!(load_compiled(&pack)?, quests);
+    Ok(())
+}
+
+#[test]
+fn test_empty_pack() -> Result<()> {
+    assert_eq!(load_compiled(&compile(&[]))?, Vec::new());
+    Ok(())
+}
+
+#[test]
+fn test_rejects_other_input() {
+    assert_eq!(load_compiled(b""), Err(LoadError::NotAPack));
+    assert_eq!(load_compiled(b"GQPK"), Err(LoadError::NotAPack));
+    assert_eq!(load_compiled(SOURCE.as_bytes()), Err(LoadError::NotAPack));
+    assert_eq!(
+        load_compiled(b"GQPK\x09"),
+        Err(LoadError::UnsupportedFormat(9))
+    );
+}
+
+#[test]
+fn test_truncated_pack_is_corrupt() -> Result<()> {
+    let quests = Parser::new(SOURCE)?.parse_quests()?;
+    let pack = compile(&quests);
+    let result = load_compiled(&pack[..pack.len() / 2]);
+    assert!(matches!(result, Err(LoadError::Corrupt(_))), "{result:?}");
+    Ok(())
+}
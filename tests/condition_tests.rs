@@ -0,0 +1,112 @@
+mod common;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    quest_diff, to_quest_string, Condition, ParseError, Parser, ProgressState, QuestChange, Span,
+};
+
+use common::parse;
+
+fn condition(source: &str) -> Condition {
+    parse(&format!("complete_when: {source}"))
+        .unwrap()
+        .complete_when
+        .unwrap()
+}
+
+fn objective(name: &str) -> Box<Condition> {
+    Box::new(Condition::Objective(name.into()))
+}
+
+#[test]
+fn test_complete_when_builds_a_condition_tree() {
+    assert_eq!(
+        condition("kill_goblins && (find_key || bribe_guard)"),
+        Condition::And(
+            objective("kill_goblins"),
+            Box::new(Condition::Or(
+                objective("find_key"),
+                objective("bribe_guard")
+            )),
+        )
+    );
+    // `&&` binds tighter than `||`.
+    assert_eq!(
+        condition("a || b && c"),
+        Condition::Or(
+            objective("a"),
+            Box::new(Condition::And(objective("b"), objective("c")))
+        )
+    );
+}
+
+#[test]
+fn test_conditions_evaluate_against_progress() {
+    let condition = condition("kill_goblins && (find_key || bribe_guard)");
+    let mut progress = ProgressState::new();
+    assert!(!condition.evaluate(&progress));
+    progress.complete("kill_goblins");
+    assert!(!condition.evaluate(&progress));
+    progress.complete("bribe_guard");
+    assert!(condition.evaluate(&progress));
+
+    let progress: ProgressState = ["find_key", "unrelated"].into_iter().collect();
+    assert!(!condition.evaluate(&progress));
+    assert_eq!(
+        condition.objectives(),
+        ["kill_goblins", "find_key", "bribe_guard"]
+    );
+}
+
+#[test]
+fn test_conditions_only_join_objectives() {
+    assert_eq!(
+        parse("complete_when: kills > 3").unwrap_err(),
+        ParseError::InvalidCondition {
+            span: Span::new(27, 36),
+        }
+    );
+    for body in [
+        "complete_when: true",
+        "complete_when: a && 5",
+        r#"complete_when: "a""#,
+    ] {
+        assert!(
+            matches!(parse(body), Err(ParseError::InvalidCondition { .. })),
+            "{body}"
+        );
+    }
+    assert!(matches!(
+        parse("complete_when: (a || b"),
+        Err(ParseError::SyntaxError { .. })
+    ));
+}
+
+#[test]
+fn test_conditions_round_trip_and_diff() -> Result<()> {
+    for source in [
+        "a && (b || c)",
+        "(a || b) && c",
+        "a || (b || c)",
+        "a && (b && c)",
+        "a || b && c",
+    ] {
+        let before = parse(&format!("complete_when: {source}"))?;
+        let text = to_quest_string(&before);
+        assert!(
+            text.contains(&format!("    complete_when: {source},\n")),
+            "{text}"
+        );
+        assert_eq!(Parser::new(&text)?.parse_quest()?, before);
+    }
+    let before = parse("complete_when: a")?;
+    let after = parse("complete_when: a || b")?;
+    assert_eq!(
+        quest_diff(&before, &after),
+        [QuestChange::CompletionChanged {
+            from: before.complete_when.clone(),
+            to: after.complete_when.clone(),
+        }]
+    );
+    Ok(())
+}
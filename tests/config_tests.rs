@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use game_quest_parser_Hodik::{
+    ConfigError, GrammarVersion, LintConfigError, Parser, ProjectConfig, Severity,
+};
+
+#[test]
+fn test_project_config_reads_every_setting() -> anyhow::Result<()> {
+    let config = ProjectConfig::from_toml(
+        r#"
+        strict = true
+        version = 2
+        include-paths = ["shared", "../common"]
+        format = "json"
+
+        [lint]
+        max-steps = 5
+        rules = { title-case = "off" }
+        "#,
+    )?;
+    assert!(config.strict);
+    assert!(config.json);
+    assert_eq!(config.version, GrammarVersion::V2);
+    assert_eq!(
+        config.include_paths,
+        [PathBuf::from("shared"), PathBuf::from("../common")]
+    );
+    let lint = config.lint.as_ref().expect("a [lint] table");
+    assert_eq!(lint.max_steps, 5);
+    assert_eq!(lint.severity("title-case"), None);
+    assert_eq!(lint.severity("zero-reward"), Some(Severity::Warning));
+
+    let options = config.parser_options();
+    assert!(options.strict);
+    let quest = Parser::with_options(r#"quest "A" { reward: 5 gold }"#, options)?.parse_quest()?;
+    assert_eq!(quest.rewards.len(), 1);
+
+    assert_eq!(ProjectConfig::from_toml("")?, ProjectConfig::default());
+    Ok(())
+}
+
+#[test]
+fn test_project_config_rejects_bad_settings() {
+    let error = |input: &str| ProjectConfig::from_toml(input).unwrap_err();
+    assert_eq!(
+        error("version = 9"),
+        ConfigError::InvalidValue {
+            key: "version".into(),
+            message: "the latest grammar version is 2".into(),
+        }
+    );
+    assert_eq!(
+        error(r#"format = "yaml""#).to_string(),
+        r#"Invalid value for `format`: expected "text" or "json""#
+    );
+    assert!(matches!(
+        error("stricct = true"),
+        ConfigError::InvalidValue { .. }
+    ));
+    assert!(matches!(error("strict = "), ConfigError::Toml(_)));
+    assert_eq!(
+        error("[lint.rules]\nno-such-rule = \"off\""),
+        ConfigError::Lint(LintConfigError::UnknownRule("no-such-rule".into()))
+    );
+}
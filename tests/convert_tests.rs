@@ -0,0 +1,232 @@
+#![cfg(feature = "convert")]
+
+use std::path::Path;
+
+use game_quest_parser_Hodik::{
+    convert, from_json, from_json_many, from_toml, from_yaml, to_json, to_quests_string, to_toml,
+    to_yaml, ConvertError, Currency, Format, ParseError, Parser, Quest, Step, Value,
+};
+
+const SOURCE: &str = r#"
+quest "Wolves" {
+    active: true,
+    reward: 40,
+    requires: "Intro",
+    step: "Hunt",
+    step: { en: "Return", uk: "Повернись" },
+    objective { kind: "kill", target: "Wolf", count: 3 },
+    objective { kind: "escort", target: "Cart" },
+    stages { start -> hunt, hunt -> end },
+    on_success { reward: 100, unlock: "Den", title: "Hunter" },
+    on_failure { penalty: 5 },
+    region: "North",
+    loot: [1, 2.0, "pelt"],
+    title: { en: "Wolves" },
+    xp: level * 10,
+}
+
+quest "Scaling" { reward: 100 + level * 10, active: day > 3 }
+"#;
+
+fn quests() -> Vec<Quest> {
+    Parser::new(SOURCE).unwrap().parse_quests().unwrap()
+}
+
+#[test]
+fn test_toml_round_trip() {
+    let toml = to_toml(&quests()).unwrap();
+    assert_eq!(from_toml(&toml).unwrap(), quests());
+}
+
+#[test]
+fn test_yaml_round_trip() {
+    let yaml = to_yaml(&quests()).unwrap();
+    assert_eq!(from_yaml(&yaml).unwrap(), quests());
+}
+
+#[test]
+fn test_json_round_trip() {
+    let json = to_json(&quests()).unwrap();
+    assert!(json.starts_with("{\n  \"quest\": [\n"));
+    assert_eq!(from_json_many(&json).unwrap(), quests());
+    let dsl = convert(&json, Format::Json, Format::Dsl).unwrap();
+    assert_eq!(dsl, to_quests_string(&quests()));
+}
+
+#[test]
+fn test_legacy_json_dumps() {
+    let dump = r#"[
+        {
+            "name": "Wolves",
+            "reward": 40,
+            "steps": ["Hunt", { "text": "Return", "optional": true }],
+            "region": "North",
+            "weight": 1.5,
+            "author": null
+        },
+        { "name": "Trade", "rewards": [["gold", 10]], "extra": { "npc": "Mira" } }
+    ]"#;
+    let quests = from_json_many(dump).unwrap();
+    assert_eq!(quests[0].reward, 40);
+    assert_eq!(
+        quests[0].steps[1],
+        Step {
+            optional: true,
+            ..Step::new("Return")
+        }
+    );
+    assert_eq!(quests[0].extra["region"], Value::String("North".into()));
+    assert_eq!(quests[0].extra["weight"], Value::Float(1.5));
+    assert!(!quests[0].extra.contains_key("author"));
+    assert_eq!(quests[1].rewards, [(Currency::Gold, 10)]);
+    assert_eq!(quests[1].extra["npc"], Value::String("Mira".into()));
+
+    let quest = from_json(r#"{ "name": "Solo", "time_limit": "1h" }"#).unwrap();
+    assert_eq!(quest.time_limit, Some(std::time::Duration::from_secs(3600)));
+    assert!(matches!(
+        from_json(dump),
+        Err(ConvertError::Json(message)) if message == "expected one quest, found 2"
+    ));
+    assert!(matches!(from_json("[1]"), Err(ConvertError::Json(_))));
+    assert!(matches!(
+        from_json(r#"{ "name": "A", "level": [5, 1] }"#),
+        Err(ConvertError::InvalidField { field, .. }) if field == "level"
+    ));
+}
+
+#[test]
+fn test_json_exported_by_parse_is_read() {
+    let export = serde_json::json!({ "file": "a.quest", "quests": quests() }).to_string();
+    let imported = from_json_many(&export).unwrap();
+    assert_eq!(imported.len(), 2);
+    for (imported, parsed) in imported.iter().zip(quests()) {
+        assert_eq!(
+            to_quests_string(std::slice::from_ref(imported)),
+            to_quests_string(&[parsed])
+        );
+    }
+}
+
+#[test]
+fn test_convert_toml_to_dsl() {
+    let toml = r#"
+        [[quest]]
+        name = "Fetch"
+        reward = 20
+        steps = ["Find apples"]
+
+        [[quest.objective]]
+        kind = "collect"
+        target = "Apple"
+        count = 5
+
+        [quest.expressions]
+        bonus = "2 * 3"
+    "#;
+    let dsl = convert(toml, Format::Toml, Format::Dsl).unwrap();
+    let quest = Parser::new(&dsl).unwrap().parse_quest().unwrap();
+    assert_eq!(quest.name, "Fetch");
+    assert_eq!(quest.reward, 20);
+    assert_eq!(quest.objectives.len(), 1);
+    assert_eq!(
+        quest.extra["bonus"],
+        game_quest_parser_Hodik::Value::Number(6)
+    );
+}
+
+#[test]
+fn test_named_rewards_convert_to_version_2() {
+    let toml = "[[quest]]\nname = \"Bounty\"\nrewards = [[\"gold\", 100], [\"gems\", 5]]\n";
+    let dsl = convert(toml, Format::Toml, Format::Dsl).unwrap();
+    assert!(dsl.starts_with("#version 2\n"));
+    let quest = Parser::new(&dsl).unwrap().parse_quest().unwrap();
+    assert_eq!(quest.rewards, [(Currency::Gold, 100), (Currency::Gems, 5)]);
+    assert!(to_toml(&[quest])
+        .unwrap()
+        .contains("rewards = [[\"gold\", 100], [\"gems\", 5]]\n"));
+}
+
+#[test]
+fn test_convert_dsl_resolves_constants() {
+    let yaml = convert(
+        r#"let GOLD = 5 quest "A" { reward: $GOLD }"#,
+        Format::Dsl,
+        Format::Yaml,
+    )
+    .unwrap();
+    assert_eq!(from_yaml(&yaml).unwrap()[0].reward, 5);
+}
+
+#[test]
+fn test_convert_rejects_definitions() {
+    let result = convert(r#"npc "Bob" {} quest "A" {}"#, Format::Dsl, Format::Toml);
+    assert!(matches!(
+        result,
+        Err(ConvertError::Parse(ParseError::Unformattable(_)))
+    ));
+}
+
+#[test]
+fn test_unknown_toml_field_is_rejected() {
+    let result = from_toml("[[quest]]\nname = \"A\"\nrewrd = 5\n");
+    assert!(matches!(result, Err(ConvertError::Toml(message)) if message.contains("rewrd")));
+}
+
+#[test]
+fn test_invalid_fields_name_the_quest() {
+    let result = from_yaml("quest:\n- name: A\n  expressions:\n    reward: \"1 +\"\n");
+    assert!(matches!(
+        result,
+        Err(ConvertError::InvalidField { quest, field, .. }) if quest == "A" && field == "reward"
+    ));
+
+    let result = from_yaml("quest:\n- name: A\n  objective:\n  - kind: kill\n");
+    assert!(matches!(
+        result,
+        Err(ConvertError::InvalidField { field, .. }) if field == "objective"
+    ));
+}
+
+#[test]
+fn test_format_from_path() {
+    assert_eq!(Format::from_path(Path::new("a.quest")), Some(Format::Dsl));
+    assert_eq!(Format::from_path(Path::new("a.toml")), Some(Format::Toml));
+    assert_eq!(Format::from_path(Path::new("a.yml")), Some(Format::Yaml));
+    assert_eq!(Format::from_path(Path::new("a.json")), Some(Format::Json));
+    assert_eq!(Format::from_path(Path::new("a.ini")), None);
+}
+
+#[test]
+fn test_step_attributes_convert() {
+    let source = r#"quest "A" { step: "Go", step: "Look around" [optional, order: 2] }"#;
+    let toml = convert(source, Format::Dsl, Format::Toml).unwrap();
+    assert!(
+        toml.contains(r#"steps = ["Go", { text = "Look around", optional = true, order = 2 }]"#)
+    );
+    let back = convert(&toml, Format::Toml, Format::Dsl).unwrap();
+    assert!(back.contains("    step: \"Look around\" [optional, order: 2],\n"));
+}
+
+#[test]
+fn test_actions_become_tables() {
+    let source = r#"quest "A" { on_start { give_item: "Map", spawn: ["wolf", 3] } }"#;
+    let toml = convert(source, Format::Dsl, Format::Toml).unwrap();
+    assert!(toml.contains("[[quest.on_start]]\nname = \"spawn\"\nargs = [\"wolf\", 3]\n"));
+    let back = convert(&toml, Format::Toml, Format::Dsl).unwrap();
+    assert!(back.contains("    on_start { give_item: \"Map\", spawn: [\"wolf\", 3] },\n"));
+}
+
+#[test]
+fn test_step_groups_convert_both_ways() -> Result<(), ConvertError> {
+    let quests = Parser::new(r#"quest "Heist" { steps { ordered { "Map", "River" [hidden] } any_of { "Bribe", "Lock" } } }"#)?
+        .parse_quests()?;
+    let toml = to_toml(&quests)?;
+    assert!(
+        toml.contains("[[quest.step_groups]]\nordered = [\"Map\", "),
+        "{toml}"
+    );
+    assert_eq!(from_toml(&toml)?, quests);
+    assert_eq!(from_yaml(&to_yaml(&quests)?)?, quests);
+    assert_eq!(from_json_many(&to_json(&quests)?)?, quests);
+    Ok(())
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{to_quest_string, Currency, ParseError, Parser};
+
+use common::parse_v2;
+
+#[test]
+fn test_currency_amounts() -> Result<()> {
+    let quest = parse_v2(r#"reward: 100 gold, 5 gems, -2 reputation, 3 stars, step: "Go""#)?;
+    assert_eq!(
+        quest.rewards,
+        vec![
+            (Currency::Gold, 100),
+            (Currency::Gems, 5),
+            (Currency::Reputation, -2),
+            (Currency::Custom("stars".into()), 3),
+        ]
+    );
+    assert_eq!(quest.reward, 0);
+    assert_eq!(quest.steps.len(), 1);
+    assert!(quest
+        .summary()
+        .contains("Reward:     100 gold, 5 gems, -2 reputation, 3 stars\n"));
+    Ok(())
+}
+
+#[test]
+fn test_currency_amounts_next_to_plain_reward() -> Result<()> {
+    let quest = parse_v2("reward: 10, reward: 50 gold\n active: true")?;
+    assert_eq!(quest.reward, 10);
+    assert_eq!(quest.rewards, vec![(Currency::Gold, 50)]);
+    assert!(quest.active);
+
+    let written = to_quest_string(&quest);
+    assert_eq!(Parser::new(&written)?.parse_quest()?, quest);
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_currency() {
+    assert!(matches!(
+        parse_v2("reward: 1 gold, 2 gold"),
+        Err(ParseError::DuplicateProperty { key, .. }) if key == "gold"
+    ));
+    assert!(matches!(
+        parse_v2("reward { gems: 1, gems: 2 }"),
+        Err(ParseError::DuplicateProperty { key, .. }) if key == "gems"
+    ));
+}
+
+#[test]
+fn test_version_1_reads_amount_then_property() -> Result<()> {
+    let quest = Parser::new("quest \"A\" { reward: 100\n gold: 5 }")?.parse_quest()?;
+    assert_eq!(quest.reward, 100);
+    assert!(quest.rewards.is_empty());
+    assert!(quest.extra.contains_key("gold"));
+    Ok(())
+}
+
+#[test]
+fn test_currency_names() {
+    for currency in [
+        Currency::Gold,
+        Currency::Gems,
+        Currency::Reputation,
+        Currency::Custom("stars".into()),
+    ] {
+        assert_eq!(Currency::from_name(currency.name()), currency);
+        assert_eq!(currency.to_string(), currency.name());
+    }
+}
+
+#[test]
+fn test_newline_ends_plain_reward() -> Result<()> {
+    let quest = Parser::new("#version 2\nquest \"A\" {\n  reward: 100\n  active: true\n}")?
+        .parse_quest()?;
+    assert_eq!(quest.reward, 100);
+    assert!(quest.rewards.is_empty());
+    assert!(quest.active);
+
+    let quest = parse_v2("reward: 100 gold\n  stars: 3")?;
+    assert_eq!(quest.rewards, vec![(Currency::Gold, 100)]);
+    assert!(quest.extra.contains_key("stars"));
+    Ok(())
+}
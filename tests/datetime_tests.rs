@@ -0,0 +1,155 @@
+mod common;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    quest_diff, to_quest_string, DateTime, Lexer, ParseError, Parser, QuestChange, Span, Token,
+    Value,
+};
+
+use common::parse;
+
+fn at(text: &str) -> DateTime {
+    DateTime::parse(text).unwrap()
+}
+
+#[test]
+fn test_lexer_reads_datetimes() {
+    let tokens: Vec<_> = Lexer::new("2025-10-31T00:00:00Z 2025-10-31T02:30:00+02:30 2025-10-31")
+        .map(|t| t.unwrap().0)
+        .collect();
+    let instant = DateTime::from_utc(2025, 10, 31, 0, 0, 0).unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::DateTime(instant),
+            Token::DateTime(instant),
+            // Without a time, a date is still a subtraction.
+            Token::Number(2025),
+            Token::Number(-10),
+            Token::Number(-31),
+        ]
+    );
+}
+
+#[test]
+fn test_datetime_parts() {
+    let instant = at("2024-02-29T23:59:58-01:00");
+    assert_eq!(instant.date(), (2024, 3, 1));
+    assert_eq!(instant.time(), (0, 59, 58));
+    assert_eq!(instant.to_string(), "2024-03-01T00:59:58Z");
+    assert_eq!(at("1970-01-01T00:00:00Z").unix_timestamp(), 0);
+    assert_eq!(at("1969-12-31T23:59:59Z").unix_timestamp(), -1);
+    assert_eq!(
+        DateTime::from_unix_timestamp(1_761_868_800),
+        Some(at("2025-10-31T00:00:00Z"))
+    );
+    for text in [
+        "2025-02-29T00:00:00Z",
+        "2025-10-31T24:00:00Z",
+        "2025-10-31T00:00:00",
+        "2025-10-31T00:00:00+2:00",
+        "2025-10-31 00:00:00Z",
+    ] {
+        assert_eq!(DateTime::parse(text), None, "{text}");
+    }
+}
+
+#[test]
+fn test_event_window() -> Result<()> {
+    let quest = parse("starts_at: 2025-10-31T00:00:00Z, ends_at: 2025-11-07T00:00:00Z")?;
+    assert_eq!(quest.starts_at, Some(at("2025-10-31T00:00:00Z")));
+    assert_eq!(quest.ends_at, Some(at("2025-11-07T00:00:00Z")));
+    assert!(!quest.is_running_at(at("2025-10-30T23:59:59Z")));
+    assert!(quest.is_running_at(at("2025-10-31T00:00:00Z")));
+    assert!(!quest.is_running_at(at("2025-11-07T00:00:00Z")));
+
+    let open = parse("starts_at: 2025-10-31T00:00:00Z")?;
+    assert!(open.is_running_at(at("9999-12-31T23:59:59Z")));
+    assert!(parse("reward: 5")?.is_running_at(DateTime::now()));
+    Ok(())
+}
+
+#[test]
+fn test_invalid_datetimes_are_rejected() {
+    assert_eq!(
+        parse("starts_at: 2025-13-01T00:00:00Z").unwrap_err(),
+        ParseError::InvalidDateTime {
+            text: "2025-13-01T00:00:00Z".into(),
+            span: Span::new(23, 43),
+        }
+    );
+    assert_eq!(
+        parse("starts_at: 2025-11-07T00:00:00Z, ends_at: 2025-11-07T01:00:00+01:00").unwrap_err(),
+        ParseError::InvalidEventWindow {
+            starts_at: at("2025-11-07T00:00:00Z"),
+            ends_at: at("2025-11-07T00:00:00Z"),
+            span: Span::new(54, 79),
+        }
+    );
+    assert!(matches!(
+        parse("ends_at: 5"),
+        Err(ParseError::SyntaxError { .. })
+    ));
+}
+
+#[test]
+fn test_datetimes_round_trip_and_diff() -> Result<()> {
+    let before = parse("starts_at: 2025-10-31T02:00:00+02:00, event: 2025-12-24T18:00:00Z")?;
+    let text = to_quest_string(&before);
+    assert!(text.contains("    starts_at: 2025-10-31T00:00:00Z,\n"));
+    assert!(text.contains("    event: 2025-12-24T18:00:00Z,\n"));
+    assert_eq!(Parser::new(&text)?.parse_quest()?, before);
+
+    let after = parse("starts_at: 2025-11-01T00:00:00Z")?;
+    assert!(
+        quest_diff(&before, &after).contains(&QuestChange::EventWindowChanged {
+            key: "starts_at",
+            from: before.starts_at,
+            to: after.starts_at,
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_datetime_expressions() -> Result<()> {
+    let quest = parse("deadline: opened + 1d, late: now > 2025-11-07T00:00:00Z")?;
+    let context: HashMap<String, Value> = [
+        (
+            "opened".to_string(),
+            Value::DateTime(at("2025-10-31T12:00:00Z")),
+        ),
+        (
+            "now".to_string(),
+            Value::DateTime(at("2025-11-08T00:00:00Z")),
+        ),
+    ]
+    .into();
+    assert_eq!(
+        quest.expressions["deadline"].evaluate(&context)?,
+        Value::DateTime(at("2025-11-01T12:00:00Z"))
+    );
+    assert_eq!(
+        quest.expressions["late"].evaluate(&context)?,
+        Value::Bool(true)
+    );
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_datetimes_serialize_as_strings() -> Result<()> {
+    let instant = at("2025-10-31T00:00:00Z");
+    assert_eq!(
+        serde_json::to_string(&instant)?,
+        r#""2025-10-31T00:00:00Z""#
+    );
+    assert_eq!(
+        serde_json::from_str::<DateTime>(r#""2025-10-31T01:00:00+01:00""#)?,
+        instant
+    );
+    assert!(serde_json::from_str::<DateTime>(r#""2025-10-31""#).is_err());
+    Ok(())
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "serde")]
+
+use std::collections::HashMap;
+
+use game_quest_parser_Hodik::{from_str, DeserializeError};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Task {
+    Hunt { target: String, count: u32 },
+    Deliver { target: String },
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Difficulty {
+    Easy,
+    Hard,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Contract {
+    name: String,
+    gold: u32,
+    weight: f64,
+    difficulty: Difficulty,
+    #[serde(default)]
+    step: Vec<String>,
+    #[serde(default)]
+    task: Vec<Task>,
+    tags: Vec<String>,
+    bonus: Option<String>,
+    #[serde(default)]
+    faction: Option<String>,
+}
+
+#[test]
+fn test_from_str_fills_custom_struct() -> Result<(), DeserializeError> {
+    let input = r#"
+        let GOLD = 20
+        quest "Wolf Pack" {
+            gold: $GOLD * 2,
+            weight: 3,
+            difficulty: "hard",
+            step: "Track the pack",
+            task { kind: "hunt", target: "Wolf", count: 5 },
+            task { kind: "deliver", target: "Pelts" },
+            tags: ["forest"],
+            bonus: level * 10,
+        }
+    "#;
+    let contract: Contract = from_str(input)?;
+    assert_eq!(
+        contract,
+        Contract {
+            name: "Wolf Pack".into(),
+            gold: 40,
+            weight: 3.0,
+            difficulty: Difficulty::Hard,
+            step: vec!["Track the pack".into()],
+            task: vec![
+                Task::Hunt {
+                    target: "Wolf".into(),
+                    count: 5
+                },
+                Task::Deliver {
+                    target: "Pelts".into()
+                },
+            ],
+            tags: vec!["forest".into()],
+            bonus: Some("level * 10".into()),
+            faction: None,
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_from_str_reads_many_quests_into_a_sequence() -> Result<(), DeserializeError> {
+    let input = r#"quest A { gold: 1 } quest B { gold: 2, zone: "North" }"#;
+    let quests: Vec<HashMap<String, serde_json::Value>> = from_str(input)?;
+    assert_eq!(quests.len(), 2);
+    assert_eq!(quests[1]["name"], "B");
+    assert_eq!(quests[1]["zone"], "North");
+
+    let single: Result<HashMap<String, serde_json::Value>, _> = from_str(input);
+    assert_eq!(single, Err(DeserializeError::QuestCount(2)));
+    Ok(())
+}
+
+#[test]
+fn test_from_str_reports_errors() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Small {
+        gold: u8,
+    }
+
+    let overflow: Result<Small, _> = from_str(r#"quest A { gold: 300 }"#);
+    assert!(matches!(overflow, Err(DeserializeError::Message(_))));
+
+    let syntax: Result<Small, _> = from_str(r#"quest A { gold 300 }"#);
+    assert!(matches!(syntax, Err(DeserializeError::Parse(_))));
+}
@@ -0,0 +1,107 @@
+use game_quest_parser_Hodik::{
+    line_col, render_error, suggest_property, ParseError, Parser, ParserOptions, Span,
+};
+
+fn parse_strict(input: &str) -> Result<(), ParseError> {
+    let options = ParserOptions {
+        strict: true,
+        ..Default::default()
+    };
+    Parser::with_options(input, options)?
+        .parse_quest()
+        .map(|_| ())
+}
+
+#[test]
+fn test_strict_mode_rejects_unknown_properties() {
+    let input = "quest \"A\" {\n    rewrd: 100\n}";
+    assert_eq!(
+        parse_strict(input),
+        Err(ParseError::UnknownProperty {
+            key: "rewrd".into(),
+            suggestion: Some("reward".into()),
+            span: Span::new(16, 21)
+        })
+    );
+}
+
+#[test]
+fn test_render_error_underlines_span_with_suggestion() {
+    let input = "quest \"A\" {\n    rewrd: 100\n}";
+    let error = parse_strict(input).unwrap_err();
+    let expected = "\
+error[E0102]: Unknown property `rewrd`
+ --> main.quest:2:5
+  |
+2 |     rewrd: 100
+  |     ^^^^^
+  |
+  = help: did you mean `reward`?
+";
+    assert_eq!(render_error(&error, input, "main.quest"), expected);
+}
+
+#[test]
+fn test_render_error_for_type_mismatch() {
+    let input = "quest \"A\" { reward: \"lots\" }";
+    let error = Parser::new(input)
+        .and_then(|mut p| p.parse_quest())
+        .unwrap_err();
+    let rendered = render_error(&error, input, "a.quest");
+    assert!(rendered
+        .starts_with("error[E0101]: Expected Number, found String(\"lots\")\n --> a.quest:1:21\n"));
+    assert!(rendered.contains("\n  |                     ^^^^^^\n"));
+}
+
+#[test]
+fn test_suggestions_and_positions() {
+    assert_eq!(suggest_property("activ"), Some("active"));
+    assert_eq!(suggest_property("requries"), Some("requires"));
+    assert_eq!(suggest_property("difficulty"), None);
+    assert_eq!(line_col("ab\nЁжx", 7), (2, 3));
+}
+
+#[test]
+fn test_unterminated_constructs_point_at_their_start() {
+    let parse = |input: &str| Parser::new(input).and_then(|mut p| p.parse_quests());
+    assert_eq!(
+        parse("quest \"A\" {\n    step: \"Go\n}\n"),
+        Err(ParseError::UnterminatedString {
+            started_at: Span::new(22, 23)
+        })
+    );
+    assert_eq!(
+        parse("quest \"A\" {\n    objective { kind: \"talk_to\", target: \"Elder\"\n"),
+        Err(ParseError::UnclosedBrace {
+            opened_at: Span::new(26, 27)
+        })
+    );
+    assert_eq!(
+        parse("quest \"A\" {\n    reward: 5\n"),
+        Err(ParseError::UnclosedBrace {
+            opened_at: Span::new(10, 11)
+        })
+    );
+    assert_eq!(
+        parse("template t() {\n    step: \"Go\"\n    on_start {\n"),
+        Err(ParseError::UnclosedBrace {
+            opened_at: Span::new(43, 44)
+        })
+    );
+}
+
+#[test]
+fn test_render_error_for_unclosed_brace() {
+    let input = "quest \"A\" {\n    reward: 5\n";
+    let error = Parser::new(input)
+        .and_then(|mut p| p.parse_quest())
+        .unwrap_err();
+    let expected = "\
+error[E0009]: Unclosed `{`; the input ends before its `}`
+ --> a.quest:1:11
+  |
+1 | quest \"A\" {
+  |           ^
+";
+    assert_eq!(render_error(&error, input, "a.quest"), expected);
+}
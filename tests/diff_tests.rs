@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{quest_diff, Objective, Parser, Quest, QuestChange, Value};
+
+fn quest(input: &str) -> Result<Quest> {
+    Ok(Parser::new(input)?.parse_quest()?)
+}
+
+#[test]
+fn test_formatting_changes_are_ignored() -> Result<()> {
+    let a = quest(r#"quest "Wolves" { reward: 40, step: "Hunt", zone: "North" }"#)?;
+    let b = quest("quest Wolves {\n    zone: \"North\"\n    step: \"Hunt\",\n    reward: 40,\n}")?;
+    assert_eq!(quest_diff(&a, &b), []);
+    Ok(())
+}
+
+#[test]
+fn test_scalar_changes() -> Result<()> {
+    let a = quest(r#"quest "Wolves" { active: true, reward: 40, requires: "Intro" }"#)?;
+    let b = quest(
+        r#"quest "Wolf Pack" { active: false, reward: 50, requires: "Camp", time_limit: 1h }"#,
+    )?;
+    assert_eq!(
+        quest_diff(&a, &b),
+        [
+            QuestChange::Renamed {
+                from: "Wolves".into(),
+                to: "Wolf Pack".into()
+            },
+            QuestChange::ActiveToggled { active: false },
+            QuestChange::RewardChanged { from: 40, to: 50 },
+            QuestChange::RequirementRemoved("Intro".into()),
+            QuestChange::RequirementAdded("Camp".into()),
+            QuestChange::TimeLimitChanged {
+                from: None,
+                to: Some(Duration::from_secs(3_600))
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_steps_are_aligned() -> Result<()> {
+    let a = quest(r#"quest "A" { step: "Go", step: "Hunt", step: "Return", step: "Rest" }"#)?;
+    let b = quest(
+        r#"quest "A" { step: "Go", step: "Skin", step: "Hunt", step: "Go home", step: "Rest" }"#,
+    )?;
+    let changes = quest_diff(&a, &b);
+    assert_eq!(
+        changes,
+        [
+            QuestChange::StepAdded {
+                index: 1,
+                step: "Skin".into()
+            },
+            QuestChange::StepChanged {
+                index: 3,
+                from: "Return".into(),
+                to: "Go home".into()
+            },
+        ]
+    );
+    assert_eq!(changes[0].to_string(), r#"step 2 added: "Skin""#);
+    assert_eq!(
+        changes[1].to_string(),
+        r#"step 4 changed from "Return" to "Go home""#
+    );
+
+    let c = quest(r#"quest "A" { step: "Hunt" }"#)?;
+    assert_eq!(
+        quest_diff(&a, &c),
+        [
+            QuestChange::StepRemoved {
+                index: 0,
+                step: "Go".into()
+            },
+            QuestChange::StepRemoved {
+                index: 2,
+                step: "Return".into()
+            },
+            QuestChange::StepRemoved {
+                index: 3,
+                step: "Rest".into()
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_objectives_and_properties() -> Result<()> {
+    let a = quest(
+        r#"quest "A" {
+            objective { kind: "kill", target: "Wolf", count: 3 },
+            zone: "North", xp: level * 10, tier: 1
+        }"#,
+    )?;
+    let b = quest(
+        r#"quest "A" {
+            objective { kind: "kill", target: "Wolf", count: 5 },
+            zone: "South", xp: level * 20, tier: 1, boss: true
+        }"#,
+    )?;
+    let changes = quest_diff(&a, &b);
+    assert_eq!(
+        changes[..2],
+        [
+            QuestChange::ObjectiveChanged {
+                index: 0,
+                from: Objective::Kill {
+                    target: "Wolf".into(),
+                    count: 3
+                },
+                to: Objective::Kill {
+                    target: "Wolf".into(),
+                    count: 5
+                },
+            },
+            QuestChange::PropertyAdded {
+                key: "boss".into(),
+                value: Value::Bool(true)
+            },
+        ]
+    );
+    let rendered: Vec<String> = changes[2..].iter().map(ToString::to_string).collect();
+    assert_eq!(
+        rendered,
+        [
+            "xp changed from level * 10 to level * 20",
+            r#"zone changed from "North" to "South""#,
+        ]
+    );
+    Ok(())
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{to_quest_string, Lexer, ParseError, Parser, Span, Token, Value};
+
+use common::parse;
+
+fn hours(h: u64) -> Duration {
+    Duration::from_secs(h * 3_600)
+}
+
+#[test]
+fn test_lexer_reads_durations() {
+    let tokens: Vec<_> = Lexer::new("2h30m 7d 90s 1d1s 5")
+        .map(|t| t.unwrap().0)
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Duration(Duration::from_secs(9_000)),
+            Token::Duration(hours(7 * 24)),
+            Token::Duration(Duration::from_secs(90)),
+            Token::Duration(Duration::from_secs(86_401)),
+            Token::Number(5),
+        ]
+    );
+}
+
+#[test]
+fn test_lexer_rejects_malformed_durations() {
+    for text in ["30m2h", "1h1h", "2h30", "5days", "1m99999999999999999999s"] {
+        let error = Lexer::new(text).next_token().unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::InvalidDuration {
+                text: text.into(),
+                span: Span::new(0, text.len()),
+            }
+        );
+    }
+}
+
+#[test]
+fn test_negative_durations_are_rejected() {
+    assert_eq!(
+        parse("cooldown: -2h").unwrap_err(),
+        ParseError::NegativeDuration {
+            span: Span::new(22, 25)
+        }
+    );
+}
+
+#[test]
+fn test_time_limit_and_duration_properties() -> Result<()> {
+    let quest = parse("time_limit: 2h30m, cooldown: 7d")?;
+    assert_eq!(quest.time_limit, Some(Duration::from_secs(9_000)));
+    assert_eq!(quest.extra["cooldown"], Value::Duration(hours(168)));
+
+    let result = parse("time_limit: 150");
+    assert!(matches!(result, Err(ParseError::SyntaxError { .. })));
+    Ok(())
+}
+
+#[test]
+fn test_duration_expressions() -> Result<()> {
+    let quest = parse("cooldown: 1h + 30m, urgent: 2h > 90m")?;
+    assert_eq!(
+        quest.extra["cooldown"],
+        Value::Duration(Duration::from_secs(5_400))
+    );
+    assert_eq!(quest.extra["urgent"], Value::Bool(true));
+
+    let quest = parse("left: limit - 10m")?;
+    let context: HashMap<String, Value> = [("limit".to_string(), Value::Duration(hours(1)))].into();
+    assert_eq!(
+        quest.expressions["left"].evaluate(&context)?,
+        Value::Duration(Duration::from_secs(3_000))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_durations_are_written_back() -> Result<()> {
+    let quest = parse("time_limit: 90m, cooldown: 0s, wait: 1d")?;
+    let text = to_quest_string(&quest);
+    assert!(text.contains("time_limit: 1h30m,"));
+    assert!(text.contains("cooldown: 0s,"));
+    assert!(text.contains("wait: 1d,"));
+    assert_eq!(Parser::new(&text)?.parse_quest()?, quest);
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    decode_source, format_source, line_col, render_error, Lexer, ParseError, Parser, Token, Value,
+};
+
+fn utf16(text: &str, bom: [u8; 2], unit: fn(u16) -> [u8; 2]) -> Vec<u8> {
+    let mut bytes = bom.to_vec();
+    for code_unit in text.encode_utf16() {
+        bytes.extend(unit(code_unit));
+    }
+    bytes
+}
+
+#[test]
+fn test_byte_order_mark_is_skipped() -> Result<()> {
+    let source = "\u{feff}quest \"Wolves\" { reward: 10 }";
+    let quest = Parser::new(source)?.parse_quest()?;
+    assert_eq!(quest.name, "Wolves");
+    assert_eq!(quest.source_map().name().unwrap().start, 9);
+
+    let (token, span) = Lexer::new(source).next_token()?;
+    assert_eq!(token, Token::QuestKeyword);
+    assert_eq!(span.start, 3);
+    assert_eq!(line_col(source, span.start), (1, 1));
+    assert_eq!(
+        format_source(source)?,
+        "quest \"Wolves\" {\n    reward: 10,\n}\n"
+    );
+
+    // Only a mark at the very start is skipped.
+    assert!(matches!(
+        Parser::new("quest \"A\" {}\n\u{feff}").and_then(|mut p| p.parse_quests()),
+        Err(ParseError::UnexpectedChar {
+            found: '\u{feff}',
+            ..
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_crlf_line_endings() -> Result<()> {
+    let source = "// Night only\r\nquest \"Wolves\" {\r\n    step: \"Hunt\",\r\n\
+                  \x20   script: <<<END\r\nspawn wolf\r\nEND\r\n    lore: ```\r\nHowl\r\n```\r\n}\r\n";
+    let quest = Parser::new(source)?.parse_quest()?;
+    let step = quest.source_map().step(0).unwrap();
+    assert_eq!(line_col(source, step.start), (3, 11));
+    let raw = |key: &str| match &quest.extra[key] {
+        Value::Raw { body, .. } => body.clone(),
+        other => panic!("{key} is {other:?}"),
+    };
+    assert_eq!(raw("script"), "spawn wolf");
+    assert_eq!(raw("lore"), "Howl");
+    assert!(format_source(source)?.starts_with("// Night only\nquest"));
+
+    // Old Mac line endings count as line breaks too.
+    assert_eq!(line_col("quest \"A\" {\r\r    x", 17), (3, 5));
+
+    let error = Parser::new("quest \"A\" {\r\n    reward: ,\r\n}")?
+        .parse_quest()
+        .unwrap_err();
+    let rendered = render_error(&error, "quest \"A\" {\r\n    reward: ,\r\n}", "a.quest");
+    assert!(rendered.contains("--> a.quest:2:13\n"));
+    assert!(rendered.contains("2 |     reward: ,\n"));
+    assert!(!rendered.contains('\r'));
+    Ok(())
+}
+
+#[test]
+fn test_decode_source() -> Result<()> {
+    let text = "quest \"Ёлка\" {}\r\n";
+    assert_eq!(
+        decode_source(&utf16(text, [0xFF, 0xFE], u16::to_le_bytes))?,
+        text
+    );
+    assert_eq!(
+        decode_source(&utf16(text, [0xFE, 0xFF], u16::to_be_bytes))?,
+        text
+    );
+    assert_eq!(decode_source(b"\xEF\xBB\xBFquest A {}")?, "quest A {}");
+    assert_eq!(decode_source(b"quest A {}")?, "quest A {}");
+
+    let error = decode_source(b"quest \xFF {}").unwrap_err();
+    assert_eq!(
+        error,
+        ParseError::InvalidEncoding {
+            encoding: "UTF-8",
+            offset: 6
+        }
+    );
+    assert_eq!(error.code(), "E0605");
+    assert_eq!(error.to_string(), "Input is not valid UTF-8 at byte 6");
+
+    // An unpaired surrogate, then a truncated code unit.
+    let mut bytes = utf16("ab", [0xFF, 0xFE], u16::to_le_bytes);
+    bytes.extend([0x00, 0xD8, b'c', 0x00]);
+    assert_eq!(
+        decode_source(&bytes),
+        Err(ParseError::InvalidEncoding {
+            encoding: "UTF-16LE",
+            offset: 6
+        })
+    );
+    let mut bytes = utf16("ab", [0xFE, 0xFF], u16::to_be_bytes);
+    bytes.push(0);
+    assert_eq!(
+        decode_source(&bytes),
+        Err(ParseError::InvalidEncoding {
+            encoding: "UTF-16BE",
+            offset: 6
+        })
+    );
+    Ok(())
+}
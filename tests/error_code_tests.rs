@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use game_quest_parser_Hodik::{
+    error_info, IncludeResolver, IncludeSource, Parser, ParserLimits, ParserOptions, ERROR_CODES,
+};
+
+fn parse_error(input: &str) -> game_quest_parser_Hodik::ParseError {
+    Parser::new(input)
+        .and_then(|mut parser| parser.parse_quests())
+        .unwrap_err()
+}
+
+#[test]
+fn test_catalog_codes_are_unique_and_ordered() {
+    for pair in ERROR_CODES.windows(2) {
+        assert!(
+            pair[0].code < pair[1].code,
+            "{} before {}",
+            pair[0].code,
+            pair[1].code
+        );
+    }
+    for info in ERROR_CODES {
+        assert!(
+            info.code.len() == 5 && info.code.starts_with('E'),
+            "{}",
+            info.code
+        );
+        assert!(
+            !info.title.is_empty() && info.explanation.ends_with('.'),
+            "{}",
+            info.code
+        );
+    }
+    assert_eq!(
+        error_info("E0001").map(|info| info.title),
+        Some("Unexpected character")
+    );
+    assert_eq!(error_info("E0000"), None);
+}
+
+#[test]
+fn test_errors_have_codes_and_explanations() {
+    let cases = [
+        ("quest \"A\" { reward: 1 @ }", "E0001"),
+        ("quest \"A\" { step: \"Dig }", "E0003"),
+        (
+            "quest \"A\" { step: \"Dig\" [optional, optional] }",
+            "E0103",
+        ),
+        ("quest \"A\" { step: \"Dig\" [loud] }", "E0106"),
+        ("quest \"A\" { level: 20..10 }", "E0205"),
+        ("quest \"A\" { reward: $gold }", "E0301"),
+        ("#version 99\nquest \"A\" {}", "E0401"),
+    ];
+    for (input, code) in cases {
+        let error = parse_error(input);
+        assert_eq!(error.code(), code, "{input}");
+        assert_eq!(
+            Some(error.explain()),
+            error_info(code).map(|info| info.explanation)
+        );
+    }
+
+    let options = ParserOptions {
+        limits: ParserLimits {
+            max_input_bytes: 4,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let error = Parser::with_options("quest \"A\" {}", options).err();
+    assert_eq!(error.map(|error| error.code()), Some("E0501"));
+}
+
+struct OneFile;
+
+impl IncludeResolver for OneFile {
+    fn resolve(&self, path: &str, _from: Option<&str>) -> Result<IncludeSource, String> {
+        let files = HashMap::from([(
+            "bad.quest",
+            "quest \"B\" { step: \"Dig\" [hidden, hidden] }",
+        )]);
+        files
+            .get(path)
+            .map(|source| IncludeSource {
+                name: path.to_string(),
+                source: source.to_string(),
+            })
+            .ok_or_else(|| "not found".to_string())
+    }
+}
+
+#[test]
+fn test_include_errors_keep_the_inner_code() {
+    let parse = |input: &str| {
+        Parser::new(input)
+            .map(|parser| parser.with_include_resolver(OneFile))
+            .and_then(|mut parser| parser.parse_quests())
+            .unwrap_err()
+    };
+    assert_eq!(parse("include \"bad.quest\"").code(), "E0103");
+    assert_eq!(parse("include \"missing.quest\"").code(), "E0602");
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{BinaryOp, EvalError, Expr, ParseError, Parser, Value};
+
+use common::parse;
+
+fn context(vars: &[(&str, Value)]) -> HashMap<String, Value> {
+    vars.iter()
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect()
+}
+
+#[test]
+fn test_constant_expressions_are_evaluated() -> Result<()> {
+    let quest = parse("reward: 100 + 5 * 10 - 2, active: 3 > 2 && 1 != 2, ratio: 7 / 2.0")?;
+    assert_eq!(quest.reward, 148);
+    assert!(quest.active);
+    assert_eq!(quest.extra["ratio"], Value::Float(3.5));
+    assert!(quest.expressions.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_expressions_with_identifiers_are_kept() -> Result<()> {
+    let quest = parse("reward: 100 + level * 10, active: player_level > 5")?;
+    assert_eq!(quest.reward, 0);
+    assert!(!quest.active);
+
+    let reward = &quest.expressions["reward"];
+    assert_eq!(
+        *reward,
+        Expr::Binary {
+            op: BinaryOp::Add,
+            lhs: Box::new(Expr::Literal(Value::Number(100))),
+            rhs: Box::new(Expr::Binary {
+                op: BinaryOp::Mul,
+                lhs: Box::new(Expr::Identifier("level".into())),
+                rhs: Box::new(Expr::Literal(Value::Number(10))),
+            }),
+        }
+    );
+    let ctx = context(&[
+        ("level", Value::Number(3)),
+        ("player_level", Value::Number(4)),
+    ]);
+    assert_eq!(reward.evaluate(&ctx)?, Value::Number(130));
+    assert_eq!(
+        quest.expressions["active"].evaluate(&ctx)?,
+        Value::Bool(false)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parentheses_and_minus() -> Result<()> {
+    let quest = parse("reward: (2 + 3) * 4, a: 10-4-3, b: x -5, c: x - -5")?;
+    assert_eq!(quest.reward, 20);
+    assert_eq!(quest.extra["a"], Value::Number(3));
+
+    let ctx = context(&[("x", Value::Number(1))]);
+    assert_eq!(quest.expressions["b"].evaluate(&ctx)?, Value::Number(-4));
+    assert_eq!(quest.expressions["c"].evaluate(&ctx)?, Value::Number(6));
+    Ok(())
+}
+
+#[test]
+fn test_constants_are_substituted_into_expressions() -> Result<()> {
+    let input = r#"let BASE = 100 quest "A" { reward: $BASE + bonus }"#;
+    let quest = Parser::new(input)?.parse_quest()?;
+    let ctx = context(&[("bonus", Value::Number(5))]);
+    assert_eq!(
+        quest.expressions["reward"].evaluate(&ctx)?,
+        Value::Number(105)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_evaluation_errors() -> Result<()> {
+    let quest = parse(r#"a: gold / 0, b: name + 1, c: missing || true"#)?;
+    let ctx = context(&[
+        ("gold", Value::Number(1)),
+        ("name", Value::String("x".into())),
+    ]);
+    assert_eq!(
+        quest.expressions["a"].evaluate(&ctx),
+        Err(EvalError::DivisionByZero)
+    );
+    assert_eq!(
+        quest.expressions["b"].evaluate(&ctx),
+        Err(EvalError::InvalidOperands {
+            op: "+",
+            lhs: "String",
+            rhs: "Number"
+        })
+    );
+    assert_eq!(
+        quest.expressions["c"].evaluate(&ctx),
+        Err(EvalError::UnknownVariable("missing".into()))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_constant_expression_errors_are_reported_while_parsing() {
+    assert!(matches!(
+        parse("reward: 1 / 0"),
+        Err(ParseError::InvalidExpression {
+            error: EvalError::DivisionByZero,
+            ..
+        })
+    ));
+    assert!(matches!(
+        parse("reward: 1 > 0"),
+        Err(ParseError::SyntaxError { .. })
+    ));
+}
@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use game_quest_parser_Hodik::{parse_file, ParseError, ParserOptions};
+
+const CAMPAIGN: &str = "tests/fixtures/include/campaign.quest";
+
+#[test]
+fn test_parse_file_resolves_includes_relative_to_path() {
+    let quests = parse_file(Path::new(CAMPAIGN), &ParserOptions::default()).unwrap();
+    let names: Vec<_> = quests.iter().map(|q| q.name.as_str()).collect();
+    assert_eq!(names, ["Warmup", "Finale"]);
+}
+
+#[test]
+fn test_parse_file_missing_file_is_io_error() {
+    let result = parse_file(
+        Path::new("tests/fixtures/missing.quest"),
+        &ParserOptions::default(),
+    );
+    assert!(matches!(result, Err(ParseError::Io(_))));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parse_files_parallel_keeps_input_order() {
+    use game_quest_parser_Hodik::parse_files_parallel;
+    use std::path::PathBuf;
+
+    let paths: Vec<PathBuf> = [CAMPAIGN, "tests/fixtures/missing.quest", CAMPAIGN]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+    let results = parse_files_parallel(&paths);
+
+    let returned: Vec<_> = results.iter().map(|(path, _)| path.clone()).collect();
+    assert_eq!(returned, paths);
+    assert_eq!(results[0].1.as_ref().unwrap().len(), 2);
+    assert!(matches!(results[1].1, Err(ParseError::Io(_))));
+    assert_eq!(results[0].1, results[2].1);
+}
@@ -0,0 +1,144 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    ast, format_source, ParseError, Parser, ParserOptions, Quest, Span, Step,
+};
+
+const SEASONAL: &str = r#"
+quest "Intro" {
+    reward: 100,
+    #[if(feature = "halloween_event")]
+    reward: 150,
+    #[if(feature = "halloween_event")] step: "Carve a pumpkin"
+    step: "Talk to the elder"
+}
+
+#[if(feature = "halloween_event")]
+quest "Haunted Mill" { reward: 500 }
+
+#[if(feature = "halloween_event")]
+#[if(feature = "hard_mode")]
+quest "Headless Rider" { reward: 2000 }
+"#;
+
+fn parse_with(source: &str, flags: &[&str]) -> Result<Vec<Quest>, ParseError> {
+    let options = ParserOptions {
+        flags: flags.iter().map(|flag| flag.to_string()).collect(),
+        ..Default::default()
+    };
+    Parser::with_options(source, options)?.parse_quests()
+}
+
+fn names(quests: &[Quest]) -> Vec<&str> {
+    quests.iter().map(|quest| quest.name.as_str()).collect()
+}
+
+#[test]
+fn test_guarded_content_is_dropped_without_flags() -> Result<()> {
+    let quests = parse_with(SEASONAL, &[])?;
+    assert_eq!(names(&quests), ["Intro"]);
+    assert_eq!(quests[0].reward, 100);
+    assert_eq!(quests[0].steps, [Step::new("Talk to the elder")]);
+    Ok(())
+}
+
+#[test]
+fn test_enabled_flags_keep_guarded_content() -> Result<()> {
+    let quests = parse_with(SEASONAL, &["halloween_event"])?;
+    assert_eq!(names(&quests), ["Intro", "Haunted Mill"]);
+    assert_eq!(quests[0].reward, 150);
+    assert_eq!(
+        quests[0].steps,
+        [Step::new("Carve a pumpkin"), Step::new("Talk to the elder")]
+    );
+
+    let quests = parse_with(SEASONAL, &["halloween_event", "hard_mode"])?;
+    assert_eq!(names(&quests), ["Intro", "Haunted Mill", "Headless Rider"]);
+    let quests = parse_with(SEASONAL, &["hard_mode"])?;
+    assert_eq!(names(&quests), ["Intro"]);
+    Ok(())
+}
+
+#[test]
+fn test_attributes_at_the_start_of_a_file() -> Result<()> {
+    let source = "#[if(feature = \"beta\")]\nquest \"A\" {}\nquest \"B\" {}";
+    assert_eq!(names(&parse_with(source, &[])?), ["B"]);
+    assert_eq!(names(&parse_with(source, &["beta"])?), ["A", "B"]);
+
+    let source = format!("#version 2\n{source}");
+    assert_eq!(names(&parse_with(&source, &["beta"])?), ["A", "B"]);
+    Ok(())
+}
+
+#[test]
+fn test_guarded_overrides() -> Result<()> {
+    let source = r#"
+        quest "A" { reward: 5 }
+        #[if(feature = "mod")]
+        quest "A" override { reward: 10 }
+    "#;
+    let world = |flags: &[&str]| {
+        let options = ParserOptions {
+            flags: flags.iter().map(|flag| flag.to_string()).collect(),
+            ..Default::default()
+        };
+        Parser::with_options(source, options)?.parse_world()
+    };
+    assert!(world(&[])?.overrides.is_empty());
+    assert_eq!(world(&["mod"])?.overrides[0].fields, ["reward"]);
+    Ok(())
+}
+
+#[test]
+fn test_malformed_attributes() {
+    assert!(matches!(
+        parse_with(r#"#[if(feat = "x")] quest "A" {}"#, &[]),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    assert!(matches!(
+        parse_with(r#"quest "A" { #[if(feature = "x")] }"#, &[]),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    // Guarded content must still parse.
+    assert!(matches!(
+        parse_with(r#"#[if(feature = "x")] quest "A" { reward: }"#, &[]),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    assert_eq!(
+        parse_with("quest \"A\" {}\n#version 2", &[]),
+        Err(ParseError::MisplacedVersion {
+            span: Span::new(13, 14)
+        })
+    );
+}
+
+#[test]
+fn test_syntax_tree_keeps_attributes() -> Result<()> {
+    let tree = ast::parse(SEASONAL)?;
+    let quests: Vec<_> = tree.quests().collect();
+    let intro = quests[0];
+    let keys: Vec<&str> = intro.properties.iter().map(|p| p.key.as_str()).collect();
+    assert_eq!(keys, ["reward", "reward", "step", "step"]);
+    assert_eq!(
+        intro.properties[2].attributes[0].text,
+        r#"if(feature = "halloween_event")"#
+    );
+    assert_eq!(intro.properties[2].value, r#""Carve a pumpkin""#);
+    assert!(intro.properties[3].attributes.is_empty());
+    assert_eq!(quests[2].attributes.len(), 2);
+    assert_eq!(
+        tree.text(quests[1].attributes[0].span),
+        r#"#[if(feature = "halloween_event")]"#
+    );
+
+    let formatted = format_source(SEASONAL)?;
+    assert!(formatted.starts_with(
+        "quest \"Intro\" {\n    reward: 100,\n    #[if(feature = \"halloween_event\")]\n    \
+         reward: 150,\n    #[if(feature = \"halloween_event\")]\n    step: \"Carve a pumpkin\",\n"
+    ));
+    assert!(formatted.ends_with(
+        "#[if(feature = \"halloween_event\")]\n#[if(feature = \"hard_mode\")]\n\
+         quest \"Headless Rider\" {\n    reward: 2000,\n}\n"
+    ));
+    assert_eq!(parse_with(&formatted, &["halloween_event"])?.len(), 2);
+    Ok(())
+}
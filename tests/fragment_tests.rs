@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{parse_property, parse_value, ParseError, Span, Value};
+
+#[test]
+fn test_parse_value_accepts_every_kind_of_value() -> Result<()> {
+    assert_eq!(parse_value("-5")?, Value::Number(-5));
+    assert_eq!(
+        parse_value(r#""Find the sword""#)?,
+        Value::String("Find the sword".into())
+    );
+    assert_eq!(
+        parse_value("2h30m")?,
+        Value::Duration(Duration::from_secs(9000))
+    );
+    assert_eq!(parse_value("10..20")?, Value::Range(10, 20));
+    assert_eq!(
+        parse_value("[true, 1.5]")?,
+        Value::List(vec![Value::Bool(true), Value::Float(1.5)])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_value_rejects_trailing_input() {
+    assert_eq!(
+        parse_value("1 2"),
+        Err(ParseError::SyntaxError {
+            expected: "end of input".into(),
+            found: "Number(2)".into(),
+            span: Span::new(2, 3),
+        })
+    );
+    assert!(matches!(
+        parse_value(""),
+        Err(ParseError::SyntaxError { expected, .. }) if expected == "Value"
+    ));
+}
+
+#[test]
+fn test_parse_property_sets_one_field() -> Result<()> {
+    let quest = parse_property(r#"steps: ["Go", "Look"],"#)?;
+    assert_eq!(quest.steps, ["Go", "Look"]);
+    assert_eq!(quest.name, "");
+
+    let quest = parse_property("objective { kind: \"kill\", target: \"Wolf\", count: 3 }")?;
+    assert_eq!(quest.objectives.len(), 1);
+
+    assert_eq!(
+        parse_property("reward: \"lots\"").unwrap_err().span(),
+        Some(Span::new(8, 14))
+    );
+    assert!(matches!(
+        parse_property("reward: 1, active: true"),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    Ok(())
+}
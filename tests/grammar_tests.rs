@@ -0,0 +1,66 @@
+use game_quest_parser_Hodik::grammar::{dot, ebnf, TOKENS};
+
+fn rule_names() -> Vec<&'static str> {
+    ebnf()
+        .lines()
+        .filter(|line| !line.starts_with(' '))
+        .filter_map(|line| line.split_once("::="))
+        .map(|(name, _)| name.trim())
+        .collect()
+}
+
+#[test]
+fn test_every_rule_is_listed_once() {
+    let names = rule_names();
+    for name in [
+        "FILE",
+        "QUEST_DEF",
+        "BODY",
+        "PROPERTY",
+        "VALUE",
+        "STEP_ATTRS",
+    ] {
+        assert_eq!(names.iter().filter(|n| **n == name).count(), 1, "{name}");
+    }
+}
+
+#[test]
+fn test_every_reference_is_defined() {
+    let names = rule_names();
+    for line in ebnf().lines() {
+        let body = line.split_once("::=").map_or(line, |(_, body)| body);
+        let unquoted = body.split('"').step_by(2).collect::<String>();
+        for word in unquoted.split(|c: char| !(c.is_ascii_uppercase() || c == '_')) {
+            assert!(
+                word.is_empty() || names.contains(&word) || TOKENS.contains(&word),
+                "{word} is used but not defined"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_multiline_rules_keep_their_alternatives() {
+    let property: Vec<&str> = ebnf()
+        .lines()
+        .skip_while(|line| !line.starts_with("PROPERTY "))
+        .take_while(|line| line.starts_with("PROPERTY ") || line.starts_with(' '))
+        .collect();
+    assert!(property.len() > 1);
+    assert!(property[1..]
+        .iter()
+        .all(|line| line.trim_start().starts_with("| ")));
+    assert!(property.iter().any(|line| line.contains("OUTCOME_BLOCK")));
+}
+
+#[test]
+fn test_dot_links_rules_to_what_they_use() {
+    let dot = dot();
+    assert!(dot.starts_with("digraph grammar {\n"));
+    assert!(dot.contains("    QUEST_DEF [shape=box];\n"));
+    assert!(dot.contains("    STRING [shape=ellipse];\n"));
+    assert!(dot.contains("    QUEST_DEF -> BODY;\n"));
+    assert!(dot.contains("    PROPERTY -> STEP_ATTRS;\n"));
+    assert_eq!(dot.matches("    QUEST_DEF -> STRING;\n").count(), 1);
+    assert!(!dot.contains("-> quest"));
+}
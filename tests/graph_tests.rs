@@ -0,0 +1,131 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{GraphError, Parser, Quest, QuestGraph};
+
+#[test]
+fn test_requires_is_repeatable() -> Result<()> {
+    let input = r#"quest "Finale" { requires: "Act 1", requires: "Act 2" }"#;
+    let quest = Parser::new(input)?.parse_quest()?;
+    assert_eq!(quest.requires, vec!["Act 1", "Act 2"]);
+    Ok(())
+}
+
+#[test]
+fn test_topological_order_puts_prerequisites_first() -> Result<()> {
+    let input = r#"
+        quest "Finale" { requires: "Forge", requires: "Intro" }
+        quest "Forge" { requires: "Intro" }
+        quest "Intro" { reward: 10 }
+    "#;
+    let quests = Parser::new(input)?.parse_quests()?;
+    let graph = QuestGraph::new(&quests)?;
+    let order: Vec<&str> = graph
+        .topological_order()?
+        .iter()
+        .map(|q| q.name.as_str())
+        .collect();
+    assert_eq!(order, vec!["Intro", "Forge", "Finale"]);
+    Ok(())
+}
+
+#[test]
+fn test_cycle_is_detected() -> Result<()> {
+    let input = r#"
+        quest "A" { requires: "B" }
+        quest "B" { requires: "C" }
+        quest "C" { requires: "A" }
+    "#;
+    let quests = Parser::new(input)?.parse_quests()?;
+    let graph = QuestGraph::new(&quests)?;
+    assert_eq!(
+        graph.find_cycle(),
+        Some(vec!["A".into(), "B".into(), "C".into(), "A".into()])
+    );
+    assert!(matches!(
+        graph.topological_order(),
+        Err(GraphError::Cycle(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_unknown_prerequisite_is_rejected() -> Result<()> {
+    let quests = Parser::new(r#"quest "A" { requires: "Missing" }"#)?.parse_quests()?;
+    assert_eq!(
+        QuestGraph::new(&quests).unwrap_err(),
+        GraphError::UnknownPrerequisite {
+            quest: "A".into(),
+            requires: "Missing".into()
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_to_dot_draws_requires_and_unlocks() -> Result<()> {
+    let input = r#"
+        quest "Intro" { on_success { unlock: "side_01" } on_failure { unlock: "Retry" } }
+        quest "The \"Den\"" { id: "side_01", requires: "Intro" }
+    "#;
+    let quests = Parser::new(input)?.parse_quests()?;
+    let dot = QuestGraph::new(&quests)?.to_dot();
+    assert_eq!(
+        dot,
+        r#"digraph quests {
+    rankdir=LR;
+    node [shape=box];
+    q0 [label="Intro"];
+    q1 [label="The \"Den\""];
+    missing0 [label="Retry", style=dashed];
+    q0 -> q1;
+    q0 -> q1 [style=dashed, label="unlocks on success"];
+    q0 -> missing0 [style=dashed, label="unlocks on failure"];
+}
+"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_long_chains_of_prerequisites() -> Result<()> {
+    // Each quest requires the next one, so the last quest comes first.
+    let count = 200_000;
+    let quests: Vec<Quest> = (0..count)
+        .map(|i| {
+            let requires = (i + 1 < count).then(|| format!("Q{}", i + 1));
+            Quest {
+                name: format!("Q{i}"),
+                requires: requires.into_iter().collect(),
+                ..Default::default()
+            }
+        })
+        .collect();
+    let graph = QuestGraph::new(&quests)?;
+    assert_eq!(graph.find_cycle(), None);
+    let order = graph.topological_order()?;
+    assert_eq!(order.len(), count);
+    assert_eq!(order[0].name, format!("Q{}", count - 1));
+    assert_eq!(order[count - 1].name, "Q0");
+
+    let mut quests = quests;
+    quests[count - 1].requires.push("Q0".into());
+    let cycle = QuestGraph::new(&quests)?.find_cycle().unwrap();
+    assert_eq!(cycle.len(), count + 1);
+    assert_eq!((cycle[0].as_str(), cycle[count].as_str()), ("Q0", "Q0"));
+    Ok(())
+}
+
+#[test]
+fn test_prerequisites_are_found_by_id() -> Result<()> {
+    let input = r#"
+        quest "Return the Sword" { requires: "find_sword" }
+        quest "Find the Sword" { id: "find_sword" }
+    "#;
+    let quests = Parser::new(input)?.parse_quests()?;
+    assert!(game_quest_parser_Hodik::resolve(&quests).is_ok());
+    let graph = QuestGraph::new(&quests)?;
+    let prerequisites = graph.prerequisites("Return the Sword").unwrap();
+    assert_eq!(prerequisites[0].name, "Find the Sword");
+    let order = graph.topological_order()?;
+    assert_eq!(order[0].name, "Find the Sword");
+    Ok(())
+}
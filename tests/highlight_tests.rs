@@ -0,0 +1,45 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{highlight, Parser, Span, TokenClass};
+
+#[test]
+fn test_highlight_classifies_tokens() {
+    let input = "quest A { // note\n  reward: 5 }";
+    assert_eq!(
+        highlight(input),
+        vec![
+            (Span::new(0, 5), TokenClass::Keyword),
+            (Span::new(6, 7), TokenClass::Identifier),
+            (Span::new(8, 9), TokenClass::Punctuation),
+            (Span::new(10, 17), TokenClass::Comment),
+            (Span::new(20, 26), TokenClass::Identifier),
+            (Span::new(26, 27), TokenClass::Punctuation),
+            (Span::new(28, 29), TokenClass::Number),
+            (Span::new(30, 31), TokenClass::Punctuation),
+        ]
+    );
+}
+
+#[test]
+fn test_highlight_recovers_after_bad_characters() {
+    let classes: Vec<TokenClass> = highlight("active @ true")
+        .into_iter()
+        .map(|(_, c)| c)
+        .collect();
+    assert_eq!(classes, vec![TokenClass::Identifier, TokenClass::Keyword]);
+}
+
+#[test]
+fn test_parser_skips_comments() -> Result<()> {
+    let input = r#"
+        // Opening quest
+        quest "A" { // a trailing comment with a } brace
+            reward: 5, // "not a string
+        }
+    "#;
+    let quest = Parser::new(input)?.parse_quest()?;
+    assert_eq!(quest.reward, 5);
+    let streamed: Vec<_> = Parser::from_reader(input.as_bytes()).collect();
+    assert_eq!(streamed.len(), 1);
+    assert_eq!(streamed[0].as_ref().map(|q| q.reward), Ok(5));
+    Ok(())
+}
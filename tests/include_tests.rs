@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{IncludeResolver, IncludeSource, ParseError, Parser, Span};
+
+/// Serves includes from memory, using the path itself as the file name.
+struct MemoryResolver(HashMap<&'static str, &'static str>);
+
+impl IncludeResolver for MemoryResolver {
+    fn resolve(&self, path: &str, _from: Option<&str>) -> Result<IncludeSource, String> {
+        self.0
+            .get(path)
+            .map(|source| IncludeSource {
+                name: path.to_string(),
+                source: source.to_string(),
+            })
+            .ok_or_else(|| "not found".to_string())
+    }
+}
+
+fn parse_with(
+    files: &[(&'static str, &'static str)],
+    root: &str,
+) -> Result<Vec<String>, ParseError> {
+    let resolver = MemoryResolver(files.iter().copied().collect());
+    let quests = Parser::new(root)?
+        .with_source_name("root.quest")
+        .with_include_resolver(resolver)
+        .parse_quests()?;
+    Ok(quests.into_iter().map(|q| q.name).collect())
+}
+
+#[test]
+fn test_filesystem_includes_are_relative_to_the_including_file() -> Result<()> {
+    let path = "tests/fixtures/include/campaign.quest";
+    let source = std::fs::read_to_string(path)?;
+    let quests = Parser::new(&source)?
+        .with_source_name(path)
+        .parse_quests()?;
+    assert_eq!(quests.len(), 2);
+    assert_eq!(quests[0].name, "Warmup");
+    assert_eq!(quests[1].name, "Finale");
+    assert_eq!(quests[1].reward, 1000);
+    Ok(())
+}
+
+#[test]
+fn test_included_quests_keep_file_order() {
+    let files = [
+        ("b.quest", r#"quest "B" {} include "c.quest""#),
+        ("c.quest", r#"quest "C" {}"#),
+    ];
+    let names = parse_with(&files, r#"quest "A" {} include "b.quest" quest "D" {}"#);
+    assert_eq!(
+        names,
+        Ok(vec!["A".into(), "B".into(), "C".into(), "D".into()])
+    );
+}
+
+#[test]
+fn test_include_cycle_is_reported() {
+    let files = [
+        ("a.quest", r#"include "b.quest""#),
+        ("b.quest", r#"include "a.quest""#),
+    ];
+    assert_eq!(
+        parse_with(&files, r#"include "a.quest""#),
+        Err(ParseError::IncludeCycle(vec![
+            "root.quest".into(),
+            "a.quest".into(),
+            "b.quest".into(),
+            "a.quest".into()
+        ]))
+    );
+}
+
+#[test]
+fn test_include_errors_carry_the_chain() {
+    let files = [
+        ("a.quest", r#"include "b.quest""#),
+        ("b.quest", r#"quest "B" { reward: $NOPE }"#),
+    ];
+    let error = parse_with(&files, r#"include "a.quest""#).unwrap_err();
+    assert_eq!(
+        error,
+        ParseError::InInclude {
+            chain: vec!["a.quest".into(), "b.quest".into()],
+            error: Box::new(ParseError::UndefinedVariable {
+                name: "NOPE".into(),
+                span: Span::new(20, 25)
+            }),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "In a.quest -> b.quest: Undefined variable: $NOPE"
+    );
+}
+
+#[test]
+fn test_missing_include_fails() {
+    assert!(matches!(
+        parse_with(&[], r#"include "missing.quest""#),
+        Err(ParseError::IncludeFailed { .. })
+    ));
+}
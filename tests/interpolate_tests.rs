@@ -0,0 +1,119 @@
+mod common;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    format_source, to_quest_string, ParseError, Parser, ParserLimits, ParserOptions, Span, Step,
+    Value,
+};
+
+use common::parse;
+
+fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_repeat_numbers_steps() -> Result<()> {
+    let quest =
+        parse(r#"step: "Enter the arena", repeat(3) step: "Defeat wave {n}", step: "Rest""#)?;
+    assert_eq!(
+        quest.steps,
+        [
+            "Enter the arena",
+            "Defeat wave 1",
+            "Defeat wave 2",
+            "Defeat wave 3",
+            "Rest"
+        ]
+    );
+
+    let quest = parse(r#"repeat(2) step: { en: "Wave {n}", uk: "Хвиля {n}" } [optional]"#)?;
+    assert_eq!(quest.steps.len(), 2);
+    assert!(quest.steps.iter().all(|step| step.optional));
+    assert_eq!(quest.steps[1].text.resolve("uk", "en"), Some("Хвиля 2"));
+    assert!(parse(r#"repeat(0) step: "Never""#)?.steps.is_empty());
+    // Only the call form repeats; `repeat: 3` is an ordinary property.
+    assert_eq!(parse("repeat: 3")?.extra["repeat"], Value::Number(3));
+    Ok(())
+}
+
+#[test]
+fn test_repeat_errors() {
+    assert_eq!(
+        parse(r#"repeat(-1) step: "Wave {n}""#).unwrap_err(),
+        ParseError::InvalidRepeatCount {
+            count: -1,
+            span: Span::new(19, 21),
+        }
+    );
+    assert!(matches!(
+        parse(r#"repeat(2) requires: "Intro""#),
+        Err(ParseError::SyntaxError { expected, .. }) if expected == "step"
+    ));
+
+    let options = ParserOptions {
+        limits: ParserLimits::UNTRUSTED,
+        ..Default::default()
+    };
+    let source = r#"quest "A" { repeat(1000000000000) step: "Wave {n}" }"#;
+    assert!(matches!(
+        Parser::with_options(source, options.clone()).and_then(|mut p| p.parse_quest()),
+        Err(ParseError::TooManyRepeats { limit: 1000, .. })
+    ));
+    let source = r#"quest "A" { repeat(600) step: "Wave {n}", repeat(600) step: "Rest {n}" }"#;
+    assert!(matches!(
+        Parser::with_options(source, options).and_then(|mut p| p.parse_quest()),
+        Err(ParseError::TooManySteps { limit: 1000, .. })
+    ));
+}
+
+#[test]
+fn test_repeated_steps_round_trip_and_are_formatted_as_written() -> Result<()> {
+    let quest = parse(r#"repeat(2) step: "Wave {n}""#)?;
+    assert_eq!(Parser::new(&to_quest_string(&quest))?.parse_quest()?, quest);
+    assert_eq!(
+        format_source(r#"quest "A" { repeat(2) step: "Wave {n}" [optional] step: "Boss" }"#)?,
+        "quest \"A\" {\n    repeat(2) step: \"Wave {n}\" [optional],\n    step: \"Boss\",\n}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_interpolate_placeholders() -> Result<()> {
+    let quest = parse(
+        r#"repeat(2) step: "Hold {arena} against wave {n}", step: { en: "Beat {boss}" },
+           banner: "Welcome to {arena}", taunts: ["{boss} laughs", "{unknown}"],
+           reward: 5"#,
+    )?;
+    let quest = quest.interpolate(&vars(&[("arena", "the Pit"), ("boss", "Gorm")]));
+    assert_eq!(
+        quest.steps[..2],
+        [
+            Step::new("Hold the Pit against wave 1"),
+            Step::new("Hold the Pit against wave 2"),
+        ]
+    );
+    assert_eq!(quest.steps[2].text.resolve("en", "en"), Some("Beat Gorm"));
+    assert_eq!(
+        quest.extra["banner"],
+        Value::String("Welcome to the Pit".into())
+    );
+    assert_eq!(
+        quest.extra["taunts"],
+        Value::List(vec![
+            Value::String("Gorm laughs".into()),
+            Value::String("{unknown}".into()),
+        ])
+    );
+    assert_eq!(quest.reward, 5);
+
+    let quest = parse(r#"step: "{{arena}} {arena""#)?;
+    let quest = quest.interpolate(&vars(&[("arena", "Pit")]));
+    assert_eq!(quest.steps[0], "{Pit} {arena");
+    Ok(())
+}
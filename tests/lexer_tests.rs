@@ -0,0 +1,150 @@
+use std::borrow::Cow;
+
+use game_quest_parser_Hodik::{Lexer, ParseError, Span, Token};
+
+#[test]
+fn test_lexer_yields_tokens_with_spans() {
+    let tokens: Vec<_> = Lexer::new("quest \"Ёж\" { reward: 5 }")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            (Token::QuestKeyword, Span::new(0, 5)),
+            (Token::StringLiteral("Ёж".into()), Span::new(6, 12)),
+            (Token::LBrace, Span::new(13, 14)),
+            (Token::Identifier("reward"), Span::new(15, 21)),
+            (Token::Colon, Span::new(21, 22)),
+            (Token::Number(5), Span::new(23, 24)),
+            (Token::RBrace, Span::new(25, 26)),
+        ]
+    );
+}
+
+#[test]
+fn test_lexer_stops_after_error() {
+    let mut lexer = Lexer::new("active @ true");
+    assert!(matches!(lexer.next(), Some(Ok((Token::Identifier(_), _)))));
+    assert_eq!(
+        lexer.next(),
+        Some(Err(ParseError::UnexpectedChar {
+            found: '@',
+            span: Span::new(7, 8)
+        }))
+    );
+    assert_eq!(lexer.next(), None);
+}
+
+#[test]
+fn test_lexer_reads_floats() {
+    let tokens: Vec<Token> = Lexer::new("0.25 -1.5 7")
+        .map(|t| t.map(|(token, _)| token))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        tokens,
+        vec![Token::Float(0.25), Token::Float(-1.5), Token::Number(7)]
+    );
+}
+
+#[test]
+fn test_lexer_reads_arrow_before_negative_number() {
+    let tokens: Vec<Token> = Lexer::new("a -> -3")
+        .map(|t| t.map(|(token, _)| token))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        tokens,
+        vec![Token::Identifier("a"), Token::Arrow, Token::Number(-3)]
+    );
+}
+
+#[test]
+fn test_lexer_reads_operators() {
+    let tokens: Vec<Token> = Lexer::new("(a+b)*c/d%e == f != g <= h >= i < j > k && l || m - n")
+        .map(|t| t.map(|(token, _)| token))
+        .filter(|t| !matches!(t, Ok(Token::Identifier(_))))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::LParen,
+            Token::Plus,
+            Token::RParen,
+            Token::Star,
+            Token::Slash,
+            Token::Percent,
+            Token::EqEq,
+            Token::NotEq,
+            Token::LessEq,
+            Token::GreaterEq,
+            Token::Less,
+            Token::Greater,
+            Token::AndAnd,
+            Token::OrOr,
+            Token::Minus,
+        ]
+    );
+}
+
+#[test]
+fn test_lexer_borrows_unescaped_strings() {
+    let tokens: Vec<Token> = Lexer::new(r#""plain" "with \"quotes\"" // note"#)
+        .map(|t| t.map(|(token, _)| token))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(matches!(
+        &tokens[0],
+        Token::StringLiteral(Cow::Borrowed("plain"))
+    ));
+    assert!(matches!(&tokens[1], Token::StringLiteral(Cow::Owned(s)) if s == "with \"quotes\""));
+    assert_eq!(tokens[2], Token::Comment(" note"));
+}
+
+#[test]
+fn test_lexer_reads_hex_and_digit_separators() {
+    let tokens: Vec<Token> = Lexer::new("10_000 0x1F -0xff_ff 1_000.5 -9_223_372_036_854_775_808")
+        .map(|t| t.map(|(token, _)| token))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Number(10_000),
+            Token::Number(0x1F),
+            Token::Number(-0xffff),
+            Token::Float(1_000.5),
+            Token::Number(i64::MIN),
+        ]
+    );
+}
+
+#[test]
+fn test_lexer_reports_malformed_numbers() {
+    for (input, text) in [
+        ("--5", "--5"),
+        ("0x", "0x"),
+        ("0x_1", "0x_1"),
+        ("0xFG", "0xFG"),
+        ("1__000", "1__000"),
+        ("10_ ", "10_"),
+        ("1_.5", "1_.5"),
+    ] {
+        let error = Lexer::new(input).next().unwrap().unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::InvalidNumber {
+                text: text.into(),
+                span: Span::new(0, text.len())
+            },
+            "{input}"
+        );
+    }
+    assert_eq!(
+        Lexer::new("0x8000_0000_0000_0000").next(),
+        Some(Err(ParseError::NumberOverflow(
+            "0x8000_0000_0000_0000".into()
+        )))
+    );
+}
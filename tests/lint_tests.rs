@@ -0,0 +1,101 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{lint, LintConfig, LintConfigError, Parser, Severity, LINT_RULES};
+
+fn rules(input: &str, config: &LintConfig) -> Result<Vec<&'static str>> {
+    let quests = Parser::new(input)?.parse_quests()?;
+    Ok(lint(&quests, config).into_iter().map(|l| l.rule).collect())
+}
+
+#[test]
+fn test_default_rules() -> Result<()> {
+    let config = LintConfig::default();
+    assert_eq!(
+        rules(
+            r#"quest "Return of the King" { reward: 5, step: "Ride north" }"#,
+            &config
+        )?,
+        Vec::<&str>::new()
+    );
+    assert_eq!(
+        rules(
+            r#"quest "return of the king" { step: "Ride north." }"#,
+            &config
+        )?,
+        ["title-case", "zero-reward", "step-period"]
+    );
+    assert_eq!(
+        rules(
+            r#"quest "A" { reward: 10 * level, step: { en: "Go", uk: "Іди." } }"#,
+            &config
+        )?,
+        ["step-period"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_too_many_steps() -> Result<()> {
+    let steps = vec![r#"step: "Go""#; 21].join(", ");
+    let input = format!(r#"quest "Long" {{ reward: 1, {steps} }}"#);
+    assert_eq!(rules(&input, &LintConfig::default())?, ["too-many-steps"]);
+
+    let config = LintConfig::from_toml("max-steps = 30")?;
+    assert!(rules(&input, &config)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_config_sets_severities() -> Result<()> {
+    let config = LintConfig::from_toml(
+        r#"
+        [rules]
+        title-case = "off"
+        zero-reward = "error"
+    "#,
+    )?;
+    assert_eq!(config.severity("title-case"), None);
+    assert_eq!(config.severity("zero-reward"), Some(Severity::Error));
+    assert_eq!(config.severity("step-period"), Some(Severity::Warning));
+
+    let quests = Parser::new(r#"quest "lower" {}"#)?.parse_quests()?;
+    let lints = lint(&quests, &config);
+    assert_eq!(lints.len(), 1);
+    assert_eq!(lints[0].severity, Severity::Error);
+    assert_eq!(
+        lints[0].to_string(),
+        r#"error[zero-reward] quest "lower": reward of 0 is suspicious"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_invalid_configs_are_rejected() {
+    assert_eq!(
+        LintConfig::from_toml("[rules]\nnope = \"off\""),
+        Err(LintConfigError::UnknownRule("nope".into()))
+    );
+    assert!(matches!(
+        LintConfig::from_toml("[rules]\nzero-reward = \"loud\""),
+        Err(LintConfigError::InvalidValue { .. })
+    ));
+    assert!(matches!(
+        LintConfig::from_toml("max-steps = -1"),
+        Err(LintConfigError::InvalidValue { .. })
+    ));
+    assert!(matches!(
+        LintConfig::from_toml("colour = 1"),
+        Err(LintConfigError::InvalidValue { .. })
+    ));
+    assert!(matches!(
+        LintConfig::from_toml("[rules"),
+        Err(LintConfigError::Toml(_))
+    ));
+}
+
+#[test]
+fn test_every_rule_is_enabled_by_default() {
+    let config = LintConfig::default();
+    for rule in LINT_RULES {
+        assert_eq!(config.severity(rule.id), Some(rule.severity));
+    }
+}
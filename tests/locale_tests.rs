@@ -0,0 +1,64 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{to_quest_string, LocalizedString, Parser, Text, Value};
+
+const INPUT: &str = r#"
+    let TITLE = { en: "The Lost Sword", uk: "Загублений меч" }
+    quest "Sword" {
+        step: { en: "Find the sword", uk: "Знайди меч" },
+        step: "Return",
+        steps: [{ en: "Celebrate", de: "Feiern" }],
+        title: $TITLE,
+    }
+"#;
+
+#[test]
+fn test_localized_strings_are_parsed() -> Result<()> {
+    let quest = Parser::new(INPUT)?.parse_quest()?;
+    let Text::Localized(first) = &quest.steps[0].text else {
+        panic!("expected a localized step, got {:?}", quest.steps[0]);
+    };
+    assert_eq!(first.get("uk"), Some("Знайди меч"));
+    assert_eq!(quest.steps[1], "Return");
+    assert_eq!(
+        quest.extra["title"],
+        Value::Localized(LocalizedString(
+            [
+                ("en".to_string(), "The Lost Sword".to_string()),
+                ("uk".to_string(), "Загублений меч".to_string()),
+            ]
+            .into()
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_localize_falls_back_to_default_language() -> Result<()> {
+    let quest = Parser::new(INPUT)?.parse_quest()?;
+
+    let uk = quest.localize("uk", "en");
+    assert_eq!(uk.steps, vec!["Знайди меч", "Return", "Celebrate"]);
+    assert_eq!(uk.extra["title"], Value::String("Загублений меч".into()));
+
+    let fr = quest.localize("fr", "de");
+    assert_eq!(fr.steps[2], "Feiern");
+    assert!(matches!(fr.steps[0].text, Text::Localized(_)));
+    Ok(())
+}
+
+#[test]
+fn test_localize_step_groups() -> Result<()> {
+    let input = r#"quest "A" { steps { ordered { { en: "Go", uk: "Йди" }, "B" } } }"#;
+    let quest = Parser::new(input)?.parse_quest()?.localize("uk", "en");
+    assert_eq!(quest.steps, vec!["Йди", "B"]);
+    let written = to_quest_string(&quest);
+    assert!(written.contains(r#""Йди""#), "{written}");
+    assert!(!written.contains("en:"), "{written}");
+    Ok(())
+}
+
+#[test]
+fn test_localized_string_requires_string_values() {
+    let result = Parser::new(r#"quest "A" { step: { en: 5 } }"#).and_then(|mut p| p.parse_quest());
+    assert!(result.is_err());
+}
@@ -0,0 +1,213 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// A `quest-lsp` process and the JSON-RPC framing to talk to it over stdio.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+
+impl Client {
+    fn start(args: &[&str]) -> Result<Client> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_quest-lsp"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let mut client = Client {
+            child,
+            stdin,
+            stdout,
+            next_id: 0,
+        };
+        client.request("initialize", json!({ "capabilities": {} }))?;
+        client.notify("initialized", json!({}))?;
+        Ok(client)
+    }
+
+    fn send(&mut self, message: Value) -> Result<()> {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Value> {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line)?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.parse()?;
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> Result<()> {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    /// Sends a request and returns the result of its response, skipping the
+    /// notifications sent before it.
+    fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))?;
+        loop {
+            let message = self.receive()?;
+            if message["id"] == id {
+                return Ok(message["result"].clone());
+            }
+        }
+    }
+
+    /// Opens a document and returns the diagnostics published for it.
+    fn open(&mut self, uri: &str, text: &str) -> Result<Vec<Value>> {
+        self.notify(
+            "textDocument/didOpen",
+            json!({ "textDocument": {
+                "uri": uri, "languageId": "quest", "version": 1, "text": text,
+            }}),
+        )?;
+        loop {
+            let message = self.receive()?;
+            if message["method"] == "textDocument/publishDiagnostics" {
+                assert_eq!(message["params"]["uri"], uri);
+                return Ok(message["params"]["diagnostics"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default());
+            }
+        }
+    }
+
+    fn at(&mut self, method: &str, uri: &str, line: u32, character: u32) -> Result<Value> {
+        self.request(
+            method,
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character },
+            }),
+        )
+    }
+
+    fn shut_down(mut self) -> Result<()> {
+        self.request("shutdown", Value::Null)?;
+        self.notify("exit", Value::Null)?;
+        assert!(self.child.wait()?.success());
+        Ok(())
+    }
+}
+
+fn project_file(name: &str) -> Result<(String, String)> {
+    let path = Path::new("tests/fixtures/project/packs")
+        .join(name)
+        .canonicalize()?;
+    let text = std::fs::read_to_string(&path)?;
+    Ok((format!("file://{}", path.display()), text))
+}
+
+#[test]
+fn test_diagnostics_use_the_project_config() -> Result<()> {
+    // The project config sets version 2, the shared include path and strict
+    // mode; main.quest needs the first two and flavoured.quest fails the last.
+    let mut client = Client::start(&[])?;
+    let (uri, text) = project_file("main.quest")?;
+    assert_eq!(client.open(&uri, &text)?, Vec::<Value>::new());
+
+    let (uri, text) = project_file("flavoured.quest")?;
+    let diagnostics = client.open(&uri, &text)?;
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["code"], "E0102");
+    client.shut_down()
+}
+
+#[test]
+fn test_diagnostics_include_warnings() -> Result<()> {
+    let mut client = Client::start(&["--no-strict"])?;
+    let (uri, text) = project_file("flavoured.quest")?;
+    assert_eq!(client.open(&uri, &text)?, Vec::<Value>::new());
+
+    let uri = "untitled:typo.quest";
+    let diagnostics = client.open(uri, "quest \"A\" {\n    rewrd: 5,\n}\n")?;
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0]["severity"], 2);
+    assert!(diagnostics[0]["message"]
+        .as_str()
+        .is_some_and(|message| message.contains("(did you mean `reward`?)")));
+    assert_eq!(
+        diagnostics[0]["range"]["start"],
+        json!({ "line": 1, "character": 4 })
+    );
+    client.shut_down()
+}
+
+#[test]
+fn test_positions_count_utf16_code_units() -> Result<()> {
+    // The wolf is one char, four UTF-8 bytes and two UTF-16 code units.
+    let text = "quest \"🐺 Hunt\" { reward: 5, }\n";
+    let uri = "untitled:wolf.quest";
+    let mut client = Client::start(&[])?;
+    assert_eq!(client.open(uri, text)?, Vec::<Value>::new());
+
+    // `reward` starts at char 17, UTF-16 offset 18.
+    let hover = client.at("textDocument/hover", uri, 0, 19)?;
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .is_some_and(|value| value.starts_with("**reward**")));
+    assert_eq!(
+        hover["range"]["start"],
+        json!({ "line": 0, "character": 18 })
+    );
+    assert_eq!(hover["range"]["end"], json!({ "line": 0, "character": 24 }));
+
+    // The error is at `}`: char 18, UTF-16 offset 19.
+    let diagnostics = client.open(uri, "quest \"🐺\" { bogus }\n")?;
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0]["range"]["start"],
+        json!({ "line": 0, "character": 19 })
+    );
+    client.shut_down()
+}
+
+#[test]
+fn test_completion_and_hover() -> Result<()> {
+    let text = "quest \"A\" {\n    reward: 5,\n    \n}\n";
+    let uri = "untitled:complete.quest";
+    let mut client = Client::start(&[])?;
+    client.open(uri, text)?;
+
+    let items = client.at("textDocument/completion", uri, 2, 4)?;
+    let labels: Vec<&str> = items
+        .as_array()
+        .expect("a list of completions")
+        .iter()
+        .filter_map(|item| item["label"].as_str())
+        .collect();
+    assert!(labels.contains(&"step"));
+    assert!(!labels.contains(&"reward"));
+
+    assert_eq!(client.at("textDocument/hover", uri, 0, 1)?, Value::Null);
+    let hover = client.at("textDocument/hover", uri, 1, 6)?;
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .is_some_and(|value| value.starts_with("**reward**")));
+    client.shut_down()
+}
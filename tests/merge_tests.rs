@@ -0,0 +1,121 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    format_source, merge, AppliedOverride, MergeError, Parser, Quest, QuestOverride, Span, World,
+};
+
+fn base() -> Result<Vec<Quest>> {
+    Ok(Parser::new(
+        r#"quest "Main Quest" { reward: 100, active: true, step: "Go", step: "Return", rarity: "common" }
+        quest "Side Quest" { reward: 5 }"#,
+    )?
+    .parse_quests()?)
+}
+
+fn overrides(source: &str) -> Result<Vec<QuestOverride>> {
+    Ok(Parser::new(source)?.parse_world()?.overrides)
+}
+
+#[test]
+fn test_override_is_collected_not_returned() -> Result<()> {
+    let source = r#"quest "Main Quest" override { reward: 200, steps: ["Run"] }
+quest "New Quest" { reward: 1 }"#;
+    let World {
+        quests, overrides, ..
+    } = Parser::new(source)?.parse_world()?;
+    assert_eq!(quests.len(), 1);
+    assert_eq!(quests[0].name, "New Quest");
+    assert_eq!(overrides.len(), 1);
+    assert_eq!(overrides[0].name, "Main Quest");
+    assert_eq!(overrides[0].fields, ["reward", "step"]);
+    assert_eq!(overrides[0].span, Span::new(0, 59));
+    Ok(())
+}
+
+#[test]
+fn test_merge_replaces_only_overridden_fields() -> Result<()> {
+    let mut quests = base()?;
+    let patches = overrides(
+        r#"quest "Main Quest" override { step: "Skip ahead", rarity: "epic" }
+        quest "Side Quest" override { active: false }"#,
+    )?;
+    let applied = merge(&mut quests, &patches)?;
+    assert_eq!(
+        applied,
+        [
+            AppliedOverride {
+                quest: "Main Quest".into(),
+                fields: vec!["step".into(), "rarity".into()],
+            },
+            AppliedOverride {
+                quest: "Side Quest".into(),
+                fields: vec!["active".into()],
+            },
+        ]
+    );
+    assert_eq!(quests[0].reward, 100);
+    assert!(quests[0].active);
+    assert_eq!(quests[0].steps, ["Skip ahead"]);
+    assert_eq!(quests[0].extra["rarity"].to_string(), "\"epic\"");
+    assert!(!quests[1].active);
+    assert_eq!(quests[1].reward, 5);
+    Ok(())
+}
+
+#[test]
+fn test_later_merges_win() -> Result<()> {
+    let mut quests = base()?;
+    merge(
+        &mut quests,
+        &overrides(r#"quest "Main Quest" override { reward: 200 }"#)?,
+    )?;
+    merge(
+        &mut quests,
+        &overrides(r#"quest "Main Quest" override { reward: 300 }"#)?,
+    )?;
+    assert_eq!(quests[0].reward, 300);
+    Ok(())
+}
+
+#[test]
+fn test_conflicting_overrides_change_nothing() -> Result<()> {
+    let mut quests = base()?;
+    let patches = overrides(
+        r#"quest "Side Quest" override { active: false }
+quest "Main Quest" override { reward: 200 }
+quest "Main Quest" override { active: false, reward: 300 }"#,
+    )?;
+    assert_eq!(
+        merge(&mut quests, &patches),
+        Err(MergeError::Conflict {
+            quest: "Main Quest".into(),
+            field: "reward".into(),
+            first: Span::new(46, 89),
+            second: Span::new(90, 148),
+        })
+    );
+    assert_eq!(quests, base()?);
+    Ok(())
+}
+
+#[test]
+fn test_override_of_unknown_quest() -> Result<()> {
+    let mut quests = base()?;
+    let patches = overrides(r#"quest "Main Qeust" override { reward: 1 }"#)?;
+    assert_eq!(
+        merge(&mut quests, &patches),
+        Err(MergeError::UnknownQuest {
+            name: "Main Qeust".into(),
+            span: Span::new(0, 41),
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_overrides_are_formatted() -> Result<()> {
+    assert_eq!(
+        format_source(r#"quest "A" override { reward: 1 }"#)?,
+        "quest \"A\" override {\n    reward: 1,\n}\n"
+    );
+    Ok(())
+}
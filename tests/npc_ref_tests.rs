@@ -0,0 +1,92 @@
+mod common;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    quest_diff, to_quest_string, NpcRef, ParseError, Parser, ParserOptions, QuestChange,
+};
+
+use common::parse;
+
+#[test]
+fn test_giver_and_turn_in() -> Result<()> {
+    let quest = parse(r#"giver: npc("Elder") at "Village Square", turn_in: npc("Smith")"#)?;
+    assert_eq!(
+        quest.giver,
+        Some(NpcRef {
+            name: "Elder".into(),
+            location: Some("Village Square".into()),
+        })
+    );
+    assert_eq!(quest.turn_in, Some(NpcRef::new("Smith")));
+    assert_eq!(quest.turn_in_npc(), quest.turn_in.as_ref());
+
+    let quest = parse(r#"giver: npc("Elder")"#)?;
+    assert_eq!(quest.turn_in, None);
+    assert_eq!(quest.turn_in_npc(), Some(&NpcRef::new("Elder")));
+    assert_eq!(parse("reward: 5")?.turn_in_npc(), None);
+    Ok(())
+}
+
+#[test]
+fn test_npc_refs_need_the_npc_form() {
+    for body in [
+        r#"giver: "Elder""#,
+        r#"giver: person("Elder")"#,
+        r#"giver: npc("Elder" at "Square")"#,
+        r#"turn_in: npc(Elder)"#,
+        r#"turn_in: npc("Elder") at 5"#,
+    ] {
+        assert!(parse(body).is_err(), "{body}");
+    }
+    assert!(matches!(
+        parse(r#"giver: "Elder""#),
+        Err(ParseError::SyntaxError { expected, .. }) if expected == "npc(...)"
+    ));
+}
+
+#[test]
+fn test_npc_refs_round_trip_and_diff() -> Result<()> {
+    let before = parse(r#"giver: npc("Elder") at "Village Square", turn_in: npc("Smith")"#)?;
+    let text = to_quest_string(&before);
+    assert!(text.contains("    giver: npc(\"Elder\") at \"Village Square\",\n"));
+    assert!(text.contains("    turn_in: npc(\"Smith\"),\n"));
+    assert_eq!(Parser::new(&text)?.parse_quest()?, before);
+
+    let after = parse(r#"giver: npc("Elder") at "Town Hall", turn_in: npc("Smith")"#)?;
+    assert_eq!(
+        quest_diff(&before, &after),
+        [QuestChange::NpcChanged {
+            key: "giver",
+            from: before.giver.clone(),
+            to: after.giver.clone(),
+        }]
+    );
+    assert_eq!(
+        quest_diff(&before, &after)[0].to_string(),
+        r#"giver changed from npc("Elder") at "Village Square" to npc("Elder") at "Town Hall""#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_worlds_check_npc_refs() -> Result<()> {
+    let source = r#"
+        npc "Elder" { }
+        quest "A" { giver: npc("Elder"), turn_in: npc("Ghost") }
+    "#;
+    assert_eq!(
+        Parser::new(source)?.parse_world().unwrap_err(),
+        ParseError::UndefinedReference {
+            quest: "A".into(),
+            kind: "npc".into(),
+            name: "Ghost".into(),
+        }
+    );
+    let options = ParserOptions {
+        allow_external_refs: true,
+        ..Default::default()
+    };
+    let world = Parser::with_options(source, options)?.parse_world()?;
+    assert_eq!(world.quests[0].turn_in, Some(NpcRef::new("Ghost")));
+    Ok(())
+}
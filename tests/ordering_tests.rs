@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{to_quest_string, IndexMap, Parser, Quest, Text, Value};
+
+const SOURCE: &str = r#"
+quest "Caravan" {
+    step: { uk: "Вирушай", en: "Set out", de: "Brich auf", fr: "Pars", pl: "Wyrusz" },
+    zone: "North",
+    banner: { pl: "Karawana", fr: "Caravane", en: "Caravan", uk: "Караван", de: "Karawane" },
+    weight: 2.5,
+    bonus: level * 2,
+    escort: true,
+    ambush_at: 3,
+    loot: ["pelt", 2],
+    zone: "South",
+    chance: luck + 1,
+    on_success { reward: 5, title: "Guard", medal: "Bronze", cargo: 3 },
+}
+"#;
+
+fn parse() -> Result<Quest> {
+    Ok(Parser::new(SOURCE)?.parse_quest()?)
+}
+
+#[test]
+fn test_extra_properties_keep_source_order() -> Result<()> {
+    let quest = parse()?;
+    let keys: Vec<&str> = quest.extra.keys().map(String::as_str).collect();
+    // A property written twice keeps the place it was first written at.
+    assert_eq!(
+        keys,
+        ["zone", "banner", "weight", "escort", "ambush_at", "loot"]
+    );
+    assert_eq!(quest.extra["zone"], Value::String("South".into()));
+    // So do the languages of a localized string.
+    let Value::Localized(banner) = &quest.extra["banner"] else {
+        panic!(
+            "expected a localized banner, got {:?}",
+            quest.extra["banner"]
+        );
+    };
+    let langs: Vec<&str> = banner.0.keys().map(String::as_str).collect();
+    assert_eq!(langs, ["pl", "fr", "en", "uk", "de"]);
+    let Text::Localized(step) = &quest.steps[0].text else {
+        panic!("expected a localized step, got {:?}", quest.steps[0]);
+    };
+    let langs: Vec<&str> = step.0.keys().map(String::as_str).collect();
+    assert_eq!(langs, ["uk", "en", "de", "fr", "pl"]);
+    let keys: Vec<&str> = quest.expressions.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["bonus", "chance"]);
+    let outcome = quest.on_success.as_ref().unwrap();
+    let keys: Vec<&str> = outcome.extra.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["title", "medal", "cargo"]);
+    Ok(())
+}
+
+#[test]
+fn test_equality_ignores_property_order() -> Result<()> {
+    let quest = parse()?;
+    let mut reordered = quest.clone();
+    reordered.extra = quest
+        .extra
+        .iter()
+        .rev()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    assert_eq!(reordered, quest);
+    assert_eq!(to_quest_string(&reordered), to_quest_string(&quest));
+    assert_eq!(reordered.content_hash(), quest.content_hash());
+    assert_eq!(reordered.change_hash(), quest.change_hash());
+
+    let empty: IndexMap<String, Value> = IndexMap::new();
+    assert_ne!(
+        Quest {
+            extra: empty,
+            ..quest.clone()
+        },
+        quest
+    );
+    Ok(())
+}
+
+#[test]
+fn test_formatting_is_byte_identical() -> Result<()> {
+    let first = to_quest_string(&parse()?);
+    for _ in 0..10 {
+        let again = to_quest_string(&Parser::new(&first)?.parse_quest()?);
+        assert_eq!(again, first);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serialization_is_byte_identical() -> Result<()> {
+    let first = serde_json::to_string(&parse()?)?;
+    for _ in 0..10 {
+        assert_eq!(serde_json::to_string(&parse()?)?, first);
+    }
+    let zone = first.find(r#""zone""#).unwrap();
+    let loot = first.find(r#""loot""#).unwrap();
+    assert!(zone < loot, "{first}");
+    Ok(())
+}
+
+#[cfg(feature = "compile")]
+#[test]
+fn test_compiled_packs_are_byte_identical() -> Result<()> {
+    use game_quest_parser_Hodik::{compile, load_compiled};
+
+    let pack = compile(&[parse()?]);
+    for _ in 0..10 {
+        assert_eq!(compile(&[parse()?]), pack);
+    }
+    let loaded = load_compiled(&pack)?;
+    let keys: Vec<&str> = loaded[0].extra.keys().map(String::as_str).collect();
+    assert_eq!(
+        keys,
+        ["zone", "banner", "weight", "escort", "ambush_at", "loot"]
+    );
+    Ok(())
+}
+
+/// Runs the CLI on `SOURCE` and returns the file it wrote. Each run is a new
+/// process, so maps with per-process hash seeds would come out differently.
+fn run_cli(args: &[&str], output: &str) -> Result<Vec<u8>> {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ordering");
+    fs::create_dir_all(&dir)?;
+    let input = dir.join("caravan.quest");
+    fs::write(&input, SOURCE)?;
+    let output = dir.join(output);
+    let status = Command::new(env!("CARGO_BIN_EXE_game_quest_parser_Hodik"))
+        .args(args)
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .status()?;
+    assert!(status.success());
+    Ok(fs::read(output)?)
+}
+
+#[cfg(feature = "compile")]
+#[test]
+fn test_compile_is_byte_identical_across_runs() -> Result<()> {
+    let pack = run_cli(&["compile"], "caravan.qpack")?;
+    for _ in 0..3 {
+        assert_eq!(run_cli(&["compile"], "caravan.qpack")?, pack);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "convert")]
+#[test]
+fn test_convert_is_byte_identical_across_runs() -> Result<()> {
+    for to in ["json", "toml", "yaml"] {
+        let output = format!("caravan.{to}");
+        let args = ["convert", "--to", to, "-f"];
+        let first = run_cli(&args, &output)?;
+        for _ in 0..3 {
+            assert_eq!(run_cli(&args, &output)?, first, "--to {to}");
+        }
+        let text = String::from_utf8(first)?;
+        let pl = text.find("Karawana").unwrap();
+        let de = text.find("Karawane").unwrap();
+        assert!(pl < de, "--to {to}: {text}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_definitions_keep_source_order() -> Result<()> {
+    let source = r#"
+        npc "Mira" {} npc "Aldo" {} npc "Zed" {} npc "Bea" {}
+        item "Torch" {} item "Axe" {} item "Rope" {}
+        quest "Errand" {}
+    "#;
+    let world = Parser::new(source)?.parse_world()?;
+    let npcs: Vec<&str> = world.symbols.npcs.keys().map(String::as_str).collect();
+    assert_eq!(npcs, ["Mira", "Aldo", "Zed", "Bea"]);
+    let items: Vec<&str> = world.symbols.items.keys().map(String::as_str).collect();
+    assert_eq!(items, ["Torch", "Axe", "Rope"]);
+    Ok(())
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{quest_diff, Outcome, ParseError, QuestChange, Value};
+
+use common::parse;
+
+#[test]
+fn test_success_and_failure_blocks() -> Result<()> {
+    let quest = parse(
+        r#"reward: 10,
+        on_success { reward: 100, unlock: "Next Quest", unlock: "Side Quest", title: "Hero" },
+        on_failure { penalty: 50 }"#,
+    )?;
+    assert_eq!(quest.reward, 10);
+    assert_eq!(
+        quest.on_success,
+        Some(Outcome {
+            reward: 100,
+            penalty: 0,
+            unlocks: vec!["Next Quest".into(), "Side Quest".into()],
+            extra: [("title".to_string(), Value::String("Hero".into()))].into(),
+        })
+    );
+    assert_eq!(
+        quest.on_failure,
+        Some(Outcome {
+            penalty: 50,
+            ..Default::default()
+        })
+    );
+    assert!(quest
+        .summary()
+        .contains("  On success: reward 100, unlocks Next Quest, Side Quest, title \"Hero\"\n"));
+    assert!(quest.summary().contains("  On failure: penalty 50\n"));
+    Ok(())
+}
+
+#[test]
+fn test_outcomes_are_optional() -> Result<()> {
+    let quest = parse("reward: 10")?;
+    assert_eq!(quest.on_success, None);
+    assert_eq!(quest.on_failure, None);
+    Ok(())
+}
+
+#[test]
+fn test_outcome_field_types_are_checked() {
+    assert!(matches!(
+        parse(r#"on_success { reward: "lots" }"#),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    assert!(matches!(
+        parse("on_failure { unlock: 5 }"),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    assert!(matches!(
+        parse("on_failure: { penalty: 5 }"),
+        Err(ParseError::BlockExpected { .. })
+    ));
+}
+
+#[test]
+fn test_outcome_changes_are_diffed() -> Result<()> {
+    let a = parse("on_success { reward: 100 }")?;
+    let b = parse("on_success { reward: 150 }, on_failure { penalty: 5 }")?;
+    let changes = quest_diff(&a, &b);
+    let rendered: Vec<String> = changes.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        rendered,
+        [
+            "on_success changed from { reward: 100 } to { reward: 150 }",
+            "on_failure changed from none to { penalty: 5 }",
+        ]
+    );
+    assert!(matches!(
+        changes[0],
+        QuestChange::OutcomeChanged {
+            key: "on_success",
+            ..
+        }
+    ));
+    Ok(())
+}
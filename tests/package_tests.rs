@@ -0,0 +1,141 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    from_toml, quest_diff, resolve, to_quests_string, to_toml, ParseError, Parser, Quest,
+    QuestChange, QuestGraph, QuestRegistry, ResolutionError,
+};
+
+const CAMPAIGN: &str = r#"quest "Intro" {}
+
+package "act1.village"
+
+quest "Find Sword" { id: "find_sword", requires: "/Intro" }
+quest "Wolves" { requires: "Find Sword" }
+
+package "act2"
+
+quest "Find Sword" { requires: "act1.village/Find Sword" }
+quest "Den" { requires: "Intro", requires: "Find Sword" }"#;
+
+#[test]
+fn test_package_declarations() -> Result<()> {
+    let quests = Parser::new(CAMPAIGN)?.parse_quests()?;
+    let packages: Vec<_> = quests.iter().map(|q| q.package.as_deref()).collect();
+    assert_eq!(
+        packages,
+        [
+            None,
+            Some("act1.village"),
+            Some("act1.village"),
+            Some("act2"),
+            Some("act2")
+        ]
+    );
+    assert_eq!(quests[0].qualified_id(), "Intro");
+    assert_eq!(quests[1].qualified_id(), "act1.village/find_sword");
+    assert_eq!(quests[1].qualified_name(), "act1.village/Find Sword");
+    assert!(quests[1].summary().contains("  Package:    act1.village\n"));
+
+    let written = to_quests_string(&quests);
+    assert!(written.contains("}\n\npackage \"act2\"\n\nquest \"Find Sword\""));
+    assert_eq!(Parser::new(&written)?.parse_quests()?, quests);
+
+    let mut moved = quests[4].clone();
+    moved.package = None;
+    assert_eq!(
+        quest_diff(&quests[4], &moved),
+        [QuestChange::PackageChanged {
+            from: Some("act2".into()),
+            to: None
+        }]
+    );
+
+    let invalid = Parser::new(r#"package "act1..village" quest "A" {}"#)?.parse_quests();
+    assert!(matches!(
+        invalid,
+        Err(ParseError::InvalidPackage { ref name, .. }) if name == "act1..village"
+    ));
+    assert_eq!(invalid.unwrap_err().code(), "E0308");
+
+    let toml = to_toml(&quests[1..2])?;
+    assert!(toml.contains("package = \"act1.village\"\n"));
+    assert_eq!(from_toml(&toml)?, quests[1..2]);
+    assert!(from_toml("[[quest]]\nname = \"A\"\npackage = \"act 1\"\n").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_relative_and_absolute_references() -> Result<()> {
+    let quests = Parser::new(CAMPAIGN)?.parse_quests()?;
+    let world = resolve(&quests).unwrap();
+    let names = |quests: Vec<&Quest>| {
+        quests
+            .iter()
+            .map(|q| q.qualified_name())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(world.prerequisites("act1.village/Find Sword").unwrap()),
+        ["Intro"]
+    );
+    assert_eq!(
+        names(world.prerequisites("act1.village/Wolves").unwrap()),
+        ["act1.village/Find Sword"]
+    );
+    assert_eq!(
+        names(world.prerequisites("act2/Find Sword").unwrap()),
+        ["act1.village/Find Sword"]
+    );
+    assert_eq!(
+        names(world.prerequisites("act2/Den").unwrap()),
+        ["Intro", "act2/Find Sword"]
+    );
+    assert_eq!(world.get("Find Sword"), None);
+
+    let graph = QuestGraph::new(&quests)?;
+    let order: Vec<_> = graph
+        .topological_order()?
+        .iter()
+        .map(|q| q.qualified_name())
+        .collect();
+    assert_eq!(order[0], "Intro");
+    assert!(order.last().unwrap().starts_with("act2/"));
+
+    let dangling =
+        Parser::new(r#"package "act2" quest "Den" { requires: "/Wolves" }"#)?.parse_quests()?;
+    assert!(matches!(
+        resolve(&dangling).unwrap_err()[..],
+        [ResolutionError::UnknownQuest { ref target, .. }] if target == "/Wolves"
+    ));
+    let duplicate =
+        Parser::new(r#"package "act2" quest "Den" {} quest "Den" {}"#)?.parse_quests()?;
+    assert!(matches!(
+        resolve(&duplicate).unwrap_err()[..],
+        [ResolutionError::DuplicateQuest(ref name)] if name == "act2/Den"
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_registry_is_keyed_by_qualified_id() -> Result<()> {
+    let quests = Parser::new(
+        r#"quest "Intro" { id: "intro" }
+        package "act1.village" quest "Find Sword" { id: "find_sword" }
+        package "act2"
+        quest "Find Sword" { id: "find_sword" }
+        quest "Den" { id: "den" }"#,
+    )?
+    .parse_quests()?;
+    let registry = QuestRegistry::new(quests).unwrap();
+    let village = registry.get("act1.village/find_sword").unwrap();
+    assert_eq!(village.name, "Find Sword");
+    assert_eq!(registry.get("find_sword"), None);
+
+    let den = registry.get("act2/den").unwrap();
+    let resolved = registry.resolve("find_sword", den).unwrap();
+    assert_eq!(resolved.package.as_deref(), Some("act2"));
+    let resolved = registry.resolve("act1.village/find_sword", den).unwrap();
+    assert_eq!(resolved, village);
+    assert_eq!(registry.resolve("intro", den).unwrap().name, "Intro");
+    assert_eq!(registry.resolve("/den", den), None);
+    Ok(())
+}
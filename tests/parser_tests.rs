@@ -1,5 +1,5 @@
 use anyhow::Result;
-use game_quest_parser_Hodik::Parser;
+use game_quest_parser_Hodik::{Objective, ParseError, Parser, ParserOptions, Span, Value};
 
 #[test]
 fn test_parse_full_quest() -> Result<()> {
@@ -17,6 +17,7 @@ fn test_parse_full_quest() -> Result<()> {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_grammar_rule_active_boolean() -> Result<()> {
     let input = r#"quest "Test" { active: false }"#;
     let mut parser = Parser::new(input)?;
@@ -51,3 +52,297 @@ fn test_grammar_error_missing_brace() {
     let mut parser = Parser::new(input).unwrap();
     assert!(parser.parse_quest().is_err());
 }
+
+#[test]
+fn test_grammar_rule_objective_blocks() -> Result<()> {
+    let input = r#"
+        quest "Hunt" {
+            step: "Legacy step",
+            objective { kind: "kill", target: "Goblin", count: 10 },
+            objective { kind: "talk_to", target: "Elder" }
+            objective { kind: "escort", target: "Caravan" }
+        }
+    "#;
+    let mut parser = Parser::new(input)?;
+    let quest = parser.parse_quest()?;
+    assert_eq!(quest.steps, vec!["Legacy step"]);
+    assert_eq!(
+        quest.objectives,
+        vec![
+            Objective::Kill {
+                target: "Goblin".into(),
+                count: 10
+            },
+            Objective::TalkTo {
+                npc: "Elder".into()
+            },
+            Objective::Custom {
+                kind: "escort".into(),
+                target: Some("Caravan".into()),
+                count: None
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_grammar_rule_objective_fields() -> Result<()> {
+    let input = r#"quest "Hunt" {
+        objective {
+            kind: "kill", note: { en: "Wolves", de: "Wölfe" }
+            target: "Wolf"
+            cuont: 3,
+        }
+    }"#;
+    let output = Parser::new(input)?.parse_quest_with_warnings()?;
+    assert_eq!(
+        output.quest.objectives,
+        vec![Objective::Kill {
+            target: "Wolf".into(),
+            count: 1
+        }]
+    );
+    let warnings: Vec<_> = output.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        warnings,
+        ["Unknown property `note`", "Unknown property `cuont`"]
+    );
+
+    let options = ParserOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let error = Parser::with_options(input, options)?
+        .parse_quest()
+        .unwrap_err();
+    let at = input.find("note").unwrap();
+    assert_eq!(
+        error,
+        ParseError::UnknownProperty {
+            key: "note".into(),
+            suggestion: None,
+            span: Span::new(at, at + 4)
+        }
+    );
+
+    let input = r#"quest "Hunt" { objective { kind: "kill", target: "Wolf", kind: "reach" } }"#;
+    let at = input.rfind("kind").unwrap();
+    assert_eq!(
+        Parser::new(input)?.parse_quest(),
+        Err(ParseError::DuplicateProperty {
+            key: "kind".into(),
+            span: Span::new(at, at + 4)
+        })
+    );
+
+    let input = r#"quest "Hunt" { objective { kind: "kill" target: "Wolf" } }"#;
+    assert!(matches!(
+        Parser::new(input)?.parse_quest(),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_grammar_error_objective_missing_target() {
+    let input = r#"quest "Test" { objective { kind: "collect", count: 3 } }"#;
+    let mut parser = Parser::new(input).unwrap();
+    assert_eq!(
+        parser.parse_quest(),
+        Err(ParseError::MissingField {
+            block: "objective".into(),
+            field: "target".into()
+        })
+    );
+}
+
+#[test]
+fn test_grammar_rule_reward_beyond_i32() -> Result<()> {
+    let input = r#"quest "Rich" { reward: 5000000000, chance: 0.25 }"#;
+    let quest = Parser::new(input)?.parse_quest()?;
+    assert_eq!(quest.reward, 5_000_000_000);
+    Ok(())
+}
+
+#[test]
+fn test_grammar_error_number_overflow() {
+    let input = r#"quest "Test" { reward: 99999999999999999999 }"#;
+    let result = Parser::new(input).and_then(|mut p| p.parse_quest());
+    assert_eq!(
+        result,
+        Err(ParseError::NumberOverflow("99999999999999999999".into()))
+    );
+}
+
+#[test]
+fn test_unknown_properties_are_collected() -> Result<()> {
+    let input = r#"quest "Test" { difficulty: 3, zone: "North", chance: 0.5, hidden: true }"#;
+    let quest = Parser::new(input)?.parse_quest()?;
+    assert_eq!(quest.extra.len(), 4);
+    assert_eq!(quest.extra["difficulty"], Value::Number(3));
+    assert_eq!(quest.extra["zone"], Value::String("North".into()));
+    assert_eq!(quest.extra["chance"], Value::Float(0.5));
+    assert_eq!(quest.extra["hidden"], Value::Bool(true));
+    Ok(())
+}
+
+#[test]
+fn test_unknown_properties_can_be_dropped() -> Result<()> {
+    let options = ParserOptions {
+        collect_extra: false,
+        ..Default::default()
+    };
+    let input = r#"quest "Test" { difficulty: 3, reward: 1 }"#;
+    let quest = Parser::with_options(input, options)?.parse_quest()?;
+    assert!(quest.extra.is_empty());
+    assert_eq!(quest.reward, 1);
+    Ok(())
+}
+
+#[test]
+fn test_grammar_rule_list_values() -> Result<()> {
+    let input = r#"
+        quest "Test" {
+            step: "A",
+            steps: ["B", "C",],
+            tags: ["main", "story"],
+            waves: [[1, 2], [], [3.5, true]]
+        }
+    "#;
+    let quest = Parser::new(input)?.parse_quest()?;
+    assert_eq!(quest.steps, vec!["A", "B", "C"]);
+    assert_eq!(quest.tags, ["main", "story"]);
+    assert_eq!(
+        quest.extra["waves"],
+        Value::List(vec![
+            Value::List(vec![Value::Number(1), Value::Number(2)]),
+            Value::List(vec![]),
+            Value::List(vec![Value::Float(3.5), Value::Bool(true)]),
+        ])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_grammar_error_steps_list_requires_strings() {
+    let input = r#"quest "Test" { steps: ["A", 2] }"#;
+    let result = Parser::new(input).and_then(|mut p| p.parse_quest());
+    assert!(matches!(result, Err(ParseError::SyntaxError { .. })));
+}
+
+#[test]
+fn test_trailing_commas_are_accepted() -> Result<()> {
+    let input = r#"quest "Test" {
+        reward: 100,
+        loot: [1, 2,],
+        title: { en: "Hi", },
+        objective { kind: "kill", target: "Wolf", },
+        stages { start -> end, },
+    }"#;
+    let quest = Parser::new(input)?.parse_quest()?;
+    assert_eq!(quest.reward, 100);
+    assert_eq!(
+        quest.extra["loot"],
+        Value::List(vec![Value::Number(1), Value::Number(2)])
+    );
+    assert!(quest.stages.is_some());
+    Ok(())
+}
+
+#[test]
+fn test_newlines_separate_items() -> Result<()> {
+    let commas = r#"quest "Test" {
+        reward: 100, active: true,
+        loot: [1, -2, "three"],
+        title: { en: "Hi", uk: "Привіт" },
+        objective { kind: "collect", target: "Apple", count: 3 },
+        stages { start -> pick, pick -> end },
+    }"#;
+    let newlines = r#"quest "Test" {
+        reward: 100
+        active: true
+        loot: [
+            1
+            -2 // a comment before the line break
+            "three"
+        ]
+        title: {
+            en: "Hi"
+            uk: "Привіт"
+        }
+        objective {
+            kind: "collect"
+            target: "Apple"
+            count: 3
+        }
+        stages {
+            start -> pick
+            pick -> end
+        }
+    }"#;
+    assert_eq!(
+        Parser::new(newlines)?.parse_quest()?,
+        Parser::new(commas)?.parse_quest()?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_items_on_one_line_still_need_commas() {
+    for input in [
+        r#"quest "Test" { loot: [1 2] }"#,
+        r#"quest "Test" { stages { start -> a a -> end } }"#,
+        r#"quest "Test" { reward: 1,, }"#,
+    ] {
+        let result = Parser::new(input).and_then(|mut p| p.parse_quest());
+        assert!(
+            matches!(result, Err(ParseError::SyntaxError { .. })),
+            "{input}: {result:?}"
+        );
+    }
+}
+
+#[test]
+fn test_properties_are_told_apart_by_the_token_after_the_key() {
+    let parse = |input: &str| Parser::new(input).and_then(|mut p| p.parse_quest());
+    assert_eq!(
+        parse(r#"quest "Test" { on_success: { reward: 5 } }"#),
+        Err(ParseError::BlockExpected {
+            key: "on_success".into(),
+            span: Span::new(15, 25),
+        })
+    );
+    assert_eq!(
+        parse(r#"quest "Test" { loot { gold: 5 } }"#),
+        Err(ParseError::ValueExpected {
+            key: "loot".into(),
+            span: Span::new(15, 19),
+        })
+    );
+    assert_eq!(
+        parse("quest \"Test\" { step // no colon\n { en: \"Go\" } }")
+            .unwrap_err()
+            .to_string(),
+        "`step` takes a value, `step: ...`, not a block"
+    );
+    // A localized text is a value in braces after a colon.
+    assert!(parse(r#"quest "Test" { step: { en: "Go" } }"#).is_ok());
+}
+
+#[test]
+fn test_lookahead_survives_template_expansion() -> Result<()> {
+    let quests = Parser::new(
+        r#"template "T"(gold) { on_success { reward: $gold }, objective { kind: "reach", target: "Gate" } }
+        quest "A" from T(gold: 5)
+        quest "B" from T(gold: 7) quest "C" { on_start { } }"#,
+    )?
+    .parse_quests()?;
+    let rewards: Vec<_> = quests
+        .iter()
+        .map(|quest| quest.on_success.as_ref().map(|o| o.reward))
+        .collect();
+    assert_eq!(rewards, [Some(5), Some(7), None]);
+    assert_eq!(quests[1].objectives.len(), 1);
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use game_quest_parser_Hodik::{CompletionContext, Parser, PropertyScope, ValueKind};
+
+/// The context at the `|` in `input`, with the prefix before it.
+fn context_at(input: &str) -> (CompletionContext, String) {
+    let cursor = input.find('|').expect("a cursor");
+    let input = input.replace('|', "");
+    let result = Parser::parse_partial(&input, cursor);
+    assert_eq!(result.prefix_span.end, cursor);
+    (result.context, result.prefix)
+}
+
+fn quest(name: &str, block: Option<&str>) -> PropertyScope {
+    PropertyScope {
+        keyword: "quest".to_string(),
+        name: name.to_string(),
+        block: block.map(str::to_string),
+    }
+}
+
+fn value(key: &str, kind: ValueKind) -> CompletionContext {
+    CompletionContext::PropertyValue {
+        scope: quest("A", None),
+        key: key.to_string(),
+        kind,
+    }
+}
+
+#[test]
+fn test_top_level_and_headers() {
+    assert_eq!(
+        context_at("|"),
+        (CompletionContext::TopLevel, String::new())
+    );
+    assert_eq!(
+        context_at("let gold = 5\nque|"),
+        (CompletionContext::TopLevel, "que".to_string())
+    );
+    assert_eq!(
+        context_at("#version 2\ninclude \"a.quest\"\nquest |"),
+        (
+            CompletionContext::Header {
+                keyword: "quest".to_string(),
+                name: None,
+            },
+            String::new()
+        )
+    );
+    assert_eq!(
+        context_at("quest \"A\" |").0,
+        CompletionContext::Header {
+            keyword: "quest".to_string(),
+            name: Some("A".to_string()),
+        }
+    );
+    assert_eq!(
+        context_at("quest A {}\nlet x = |").0,
+        CompletionContext::None
+    );
+    assert_eq!(context_at("quest A { // rew|").0, CompletionContext::None);
+}
+
+#[test]
+fn test_property_keys() {
+    assert_eq!(
+        context_at("quest A {\n    reward: 10,\n    act|\n}"),
+        (
+            CompletionContext::PropertyKey {
+                scope: quest("A", None),
+                present: vec!["reward".to_string()],
+            },
+            "act".to_string()
+        )
+    );
+    // Without a comma, a new line after a complete value starts a key.
+    assert_eq!(
+        context_at("quest A {\n    id: \"a\"\n    tags: [\"x\"]\n    |").0,
+        CompletionContext::PropertyKey {
+            scope: quest("A", None),
+            present: vec!["id".to_string(), "tags".to_string()],
+        }
+    );
+    assert_eq!(
+        context_at("quest A {\n    objective { kind: \"kill\", |").0,
+        CompletionContext::PropertyKey {
+            scope: quest("A", Some("objective")),
+            present: vec!["kind".to_string()],
+        }
+    );
+    assert_eq!(
+        context_at("npc Elder { |").0,
+        CompletionContext::PropertyKey {
+            scope: PropertyScope {
+                keyword: "npc".to_string(),
+                name: "Elder".to_string(),
+                block: None,
+            },
+            present: Vec::new(),
+        }
+    );
+}
+
+#[test]
+fn test_property_values() {
+    assert_eq!(
+        context_at("quest A { level: |").0,
+        value("level", ValueKind::Range)
+    );
+    assert_eq!(
+        context_at("quest A { active: player_level > 5 && |").0,
+        value("active", ValueKind::Bool)
+    );
+    assert_eq!(
+        context_at("quest A { reward: 100 g|").0,
+        value("reward", ValueKind::Number)
+    );
+    assert_eq!(
+        context_at("quest A { tags: [\"main\", |").0,
+        value("tags", ValueKind::List(Box::new(ValueKind::String)))
+    );
+    assert_eq!(
+        context_at("quest A { objective { kind: |").0,
+        CompletionContext::PropertyValue {
+            scope: quest("A", Some("objective")),
+            key: "kind".to_string(),
+            kind: ValueKind::ObjectiveKind,
+        }
+    );
+    assert_eq!(
+        context_at("quest A { step: \"Dig\" [optional, h|").0,
+        CompletionContext::StepAttribute {
+            quest: "A".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_strings_and_quest_names() {
+    let input = "quest Intro {}\nquest @ \"Bad\" {}\nquest B { on_success { unlock: \"In\" } }";
+    let cursor = input.find("In\"").unwrap() + 2;
+    let result = Parser::parse_partial(input, cursor);
+    assert!(result.in_string);
+    assert_eq!(result.prefix, "In");
+    assert_eq!(
+        result.context,
+        CompletionContext::PropertyValue {
+            scope: quest("B", Some("on_success")),
+            key: "unlock".to_string(),
+            kind: ValueKind::QuestName,
+        }
+    );
+    assert_eq!(result.quests, ["Intro", "Bad", "B"]);
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    parse_expr, quest_diff, to_quest_string, EvalError, ParseError, Parser, Quest, QuestChange,
+    QuestSet,
+};
+
+fn parse(source: &str) -> Result<Vec<Quest>, ParseError> {
+    Parser::new(source)?.parse_quests()
+}
+
+const PACK: &str = r#"
+    quest "Intro" { tags: ["main", "act1"], active: true, reward: 50 }
+    quest "Wolves" { tags: ["side", "act1"], active: true, reward: 200 }
+    quest "Smugglers" { tags: ["side", "act2"], reward: 300 }
+    quest "Finale" { tags: ["main"], tags: ["act2"], active: true, reward: 1000 }
+"#;
+
+#[test]
+fn test_tags_property() -> Result<()> {
+    let quests = parse(PACK)?;
+    assert_eq!(quests[0].tags, ["main", "act1"]);
+    assert_eq!(quests[3].tags, ["main", "act2"]);
+    assert!(quests[1].has_tag("side"));
+    assert!(!quests[1].has_tag("main"));
+    assert!(matches!(
+        parse(r#"quest "A" { tags: ["main", 1] }"#),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_tags_round_trip() -> Result<()> {
+    let quest = parse(r#"quest "A" { tags: ["main", "act \"1\""] }"#)?.remove(0);
+    let text = to_quest_string(&quest);
+    assert!(text.contains(r#"tags: ["main", "act \"1\""],"#));
+    assert_eq!(parse(&text)?, [quest]);
+    Ok(())
+}
+
+#[test]
+fn test_filters_chain() -> Result<()> {
+    let quests = QuestSet::new(parse(PACK)?);
+    assert_eq!(quests.by_tag("main").names(), ["Intro", "Finale"]);
+    assert_eq!(quests.active().by_tag("side").names(), ["Wolves"]);
+    assert_eq!(
+        quests.reward_at_least(200).by_tag("act2").names(),
+        ["Smugglers", "Finale"]
+    );
+    let unlisted = quests.filter(|quest| quest.name.starts_with('S')).active();
+    assert!(unlisted.is_empty());
+    assert_eq!(quests.by_tag("act3").first(), None);
+    Ok(())
+}
+
+#[test]
+fn test_quest_set_accessors() -> Result<()> {
+    let quests: QuestSet = parse(PACK)?.into_iter().collect();
+    assert_eq!(quests.len(), 4);
+    assert_eq!(quests.tags(), ["act1", "act2", "main", "side"]);
+    assert_eq!(quests.get("Wolves").map(|q| q.reward), Some(200));
+    let total: i64 = quests.by_tag("act1").iter().map(|q| q.reward).sum();
+    assert_eq!(total, 250);
+    assert_eq!((&quests).into_iter().count(), 4);
+    Ok(())
+}
+
+#[test]
+fn test_diff_reports_tags() -> Result<()> {
+    let old = parse(r#"quest "A" { tags: ["main", "act1"] }"#)?.remove(0);
+    let new = parse(r#"quest "A" { tags: ["main", "act2"] }"#)?.remove(0);
+    assert_eq!(
+        quest_diff(&old, &new),
+        [
+            QuestChange::TagRemoved("act1".into()),
+            QuestChange::TagAdded("act2".into()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_expression_filters() -> Result<()> {
+    let set = QuestSet::new(parse(PACK)?);
+    let names = |filter: &str| -> Result<Vec<String>> {
+        let query = set.matching(&parse_expr(filter)?)?;
+        Ok(query.names().into_iter().map(String::from).collect())
+    };
+    assert_eq!(names("reward > 100 && active")?, ["Wolves", "Finale"]);
+    assert_eq!(
+        names("steps == 0 && name != \"Intro\"")?,
+        ["Wolves", "Smugglers", "Finale"]
+    );
+    // No quest of the pack has an `id`, so none matches rather than failing.
+    assert!(names("id == \"intro\"")?.is_empty());
+
+    let quest =
+        &parse("#version 2\nquest \"Bounty\" { reward: 5 gold, level: 3..8, flavour: \"grim\" }")?
+            [0];
+    assert!(quest.matches(&parse_expr(
+        "gold == 5 && min_level == 3 && max_level < 10"
+    )?)?);
+    assert!(quest.matches(&parse_expr("flavour == \"grim\"")?)?);
+
+    assert_eq!(
+        set.matching(&parse_expr("reward + 1")?),
+        Err(EvalError::ExpectedBool("Number"))
+    );
+    assert!(matches!(
+        set.matching(&parse_expr("reward > \"lots\"")?),
+        Err(EvalError::InvalidOperands { .. })
+    ));
+    Ok(())
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    quest_diff, to_quest_string, Lexer, ParseError, Parser, QuestChange, Span, Token, Value,
+};
+
+use common::parse;
+
+#[test]
+fn test_lexer_reads_ranges() {
+    let tokens: Vec<_> = Lexer::new("10..20 -5..-1 1.5")
+        .map(|t| t.unwrap().0)
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Number(10),
+            Token::DotDot,
+            Token::Number(20),
+            Token::Number(-5),
+            Token::DotDot,
+            Token::Number(-1),
+            Token::Float(1.5),
+        ]
+    );
+}
+
+#[test]
+fn test_level_range_property() -> Result<()> {
+    assert_eq!(parse("level: 10..20")?.level_range, Some((10, 20)));
+    assert_eq!(parse("level: 7")?.level_range, Some((7, 7)));
+    assert_eq!(parse("reward: 5")?.level_range, None);
+    assert!(matches!(
+        parse(r#"level: "high""#),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_empty_ranges_are_rejected() {
+    assert_eq!(
+        parse("level: 20..10").unwrap_err(),
+        ParseError::InvalidRange {
+            start: 20,
+            end: 10,
+            span: Span::new(19, 25),
+        }
+    );
+    assert!(matches!(
+        parse("difficulty: 3..-3"),
+        Err(ParseError::InvalidRange { .. })
+    ));
+    assert!(parse("level: 10..").is_err());
+}
+
+#[test]
+fn test_ranges_in_other_properties() -> Result<()> {
+    let quest = parse("difficulty: 2..4, waves: [1..3, 5..5]")?;
+    assert_eq!(quest.extra["difficulty"], Value::Range(2, 4));
+    assert_eq!(
+        quest.extra["waves"],
+        Value::List(vec![Value::Range(1, 3), Value::Range(5, 5)])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_ranges_round_trip_and_diff() -> Result<()> {
+    let quest = parse("level: -3..12, difficulty: 2..4")?;
+    let text = to_quest_string(&quest);
+    assert!(text.contains("level: -3..12,"));
+    assert!(text.contains("difficulty: 2..4,"));
+    assert_eq!(Parser::new(&text)?.parse_quest()?, quest);
+
+    let raised = parse("level: 5..12, difficulty: 2..4")?;
+    assert_eq!(
+        quest_diff(&quest, &raised),
+        [QuestChange::LevelRangeChanged {
+            from: Some((-3, 12)),
+            to: Some((5, 12)),
+        }]
+    );
+    assert_eq!(
+        quest_diff(&quest, &raised)[0].to_string(),
+        "level changed from -3..12 to 5..12"
+    );
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{to_quest_string, Lexer, ParseError, Parser, Span, Token, Value};
+
+fn raw(lang: Option<&str>, body: &str) -> Value {
+    Value::Raw {
+        lang: lang.map(str::to_string),
+        body: body.to_string(),
+    }
+}
+
+#[test]
+fn test_fenced_blocks_keep_scripts_verbatim() -> Result<()> {
+    let input = "quest \"Ambush\" {\n    script: ```lua\n    if player.hp < 10 then print(\"{flee}\") end\n    ```,\n    reward: 10\n}";
+    let quest = Parser::new(input)?.parse_quest()?;
+    assert_eq!(
+        quest.extra["script"],
+        raw(
+            Some("lua"),
+            "    if player.hp < 10 then print(\"{flee}\") end"
+        )
+    );
+    assert_eq!(quest.reward, 10);
+
+    let quest = Parser::new("quest \"A\" { on_enter: ```spawn(\"wolf\")``` }")?.parse_quest()?;
+    assert_eq!(quest.extra["on_enter"], raw(None, "spawn(\"wolf\")"));
+    Ok(())
+}
+
+#[test]
+fn test_heredocs_end_at_their_delimiter() -> Result<()> {
+    let input = "quest \"A\" {\n    script: <<<END\nlocal s = \"}\"\nENDING = true\n    END,\n    reward: 5\n}";
+    let quest = Parser::new(input)?.parse_quest()?;
+    assert_eq!(
+        quest.extra["script"],
+        raw(None, "local s = \"}\"\nENDING = true")
+    );
+    assert_eq!(quest.reward, 5);
+    Ok(())
+}
+
+fn first(input: &str) -> Result<(Token<'_>, Span), ParseError> {
+    Lexer::new(input).next().expect("a token")
+}
+
+#[test]
+fn test_raw_block_errors() {
+    assert_eq!(
+        first("```lua\nprint(1)\n``"),
+        Err(ParseError::UnterminatedRaw {
+            started_at: Span::new(0, 3)
+        })
+    );
+    assert_eq!(
+        first("<<<END\nprint(1)\n"),
+        Err(ParseError::UnterminatedRaw {
+            started_at: Span::new(0, 6)
+        })
+    );
+    assert_eq!(
+        first("<<<END print(1)\nEND"),
+        Err(ParseError::InvalidHeredoc {
+            span: Span::new(0, 6)
+        })
+    );
+    assert!(matches!(
+        first("``x``"),
+        Err(ParseError::UnexpectedChar { found: '`', .. })
+    ));
+    assert_eq!(
+        first("````\nuse ``` inside\n````"),
+        Ok((
+            Token::Raw {
+                lang: None,
+                body: "use ``` inside"
+            },
+            Span::new(0, 24)
+        ))
+    );
+}
+
+#[test]
+fn test_raw_blocks_round_trip_through_the_writer() -> Result<()> {
+    let mut quest = Parser::new("quest \"A\" {}")?.parse_quest()?;
+    for (key, value) in [
+        ("script", raw(Some("lua"), "print(\"```\")\n")),
+        ("notes", raw(None, "")),
+        ("indented", raw(None, "  a\n  ")),
+    ] {
+        quest.extra.insert(key.to_string(), value);
+    }
+    let written = to_quest_string(&quest);
+    assert!(
+        written.contains("script: ````lua\nprint(\"```\")\n\n````,"),
+        "{written}"
+    );
+    assert_eq!(Parser::new(&written)?.parse_quest()?, quest);
+    Ok(())
+}
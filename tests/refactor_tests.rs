@@ -0,0 +1,54 @@
+use std::fs;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{rename_quest, renumber_steps, Parser};
+
+#[test]
+fn test_renumber_steps_keeps_their_order() -> Result<()> {
+    let source = fs::read_to_string("tests/fixtures/refactor/act1.quest")?;
+    let rewrite = renumber_steps(&source)?;
+    assert_eq!(
+        rewrite.source,
+        "// The first quest every player gets.\n\
+         quest \"Intro\" {\n    \
+             reward: 50,\n    \
+             step: \"Wake up\" [order: 1],\n    \
+             step: \"Get dressed\" [order: 3], // shown last\n    \
+             step: \"Open the door\" [order: 2],\n\
+         }\n"
+    );
+    assert_eq!(rewrite.changes, 3);
+    assert_eq!(renumber_steps(&rewrite.source)?.changes, 0);
+    Ok(())
+}
+
+#[test]
+fn test_renumber_steps_in_groups() -> Result<()> {
+    let source = fs::read_to_string("tests/fixtures/refactor/act2.quest")?;
+    let rewrite = renumber_steps(&source)?;
+    let quest = Parser::new(&rewrite.source)?.parse_quest()?;
+    let orders: Vec<_> = quest.steps.iter().map(|step| step.order).collect();
+    assert_eq!(orders, [Some(1), Some(2), Some(2)]);
+    assert!(rewrite
+        .source
+        .contains("requires: \"Intro\", // after the tutorial"));
+    Ok(())
+}
+
+#[test]
+fn test_rename_quest_updates_requires() -> Result<()> {
+    let source = fs::read_to_string("tests/fixtures/refactor/act2.quest")?;
+    let rewrite = rename_quest(&source, "Intro", "Tutorial \"1\"")?;
+    assert_eq!(rewrite.changes, 1);
+    assert!(rewrite
+        .source
+        .contains("requires: \"Tutorial \\\"1\\\"\", // after the tutorial"));
+    let quest = Parser::new(&rewrite.source)?.parse_quest()?;
+    assert_eq!(quest.requires, ["Tutorial \"1\""]);
+
+    let rewrite = rename_quest("quest Intro { requires: Intro }", "Intro", "Tutorial")?;
+    assert_eq!(rewrite.source, "quest Tutorial { requires: Intro }");
+    let rewrite = rename_quest("quest Intro {}", "Intro", "The Intro")?;
+    assert_eq!(rewrite.source, "quest \"The Intro\" {}");
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    quest_diff, ParseError, Parser, Quest, QuestChange, QuestRegistry, RegistryError,
+};
+
+fn parse(source: &str) -> Result<Vec<Quest>, ParseError> {
+    Parser::new(source)?.parse_quests()
+}
+
+#[test]
+fn test_id_property() -> Result<()> {
+    let quests = parse(r#"quest "The Lost Sword" { id: "main_01", reward: 5 } quest "Wolves" {}"#)?;
+    assert_eq!(quests[0].id.as_deref(), Some("main_01"));
+    assert_eq!(quests[0].name, "The Lost Sword");
+    assert_eq!(quests[1].id, None);
+    assert!(matches!(
+        parse(r#"quest "A" { id: 1 }"#),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_registry_looks_up_by_id() -> Result<()> {
+    let first = parse(r#"quest "Intro" { id: "main_01" } quest "Untracked" {}"#)?;
+    let second = parse(r#"quest "Wolves" { id: "side_01" }"#)?;
+    let registry = QuestRegistry::new(first.into_iter().chain(second))?;
+    assert_eq!(registry.len(), 3);
+    assert_eq!(
+        registry.get("side_01").map(|q| q.name.as_str()),
+        Some("Wolves")
+    );
+    assert_eq!(registry.get("Intro"), None);
+    assert_eq!(registry.quests()[1].name, "Untracked");
+    Ok(())
+}
+
+#[test]
+fn test_registry_rejects_duplicate_ids() -> Result<()> {
+    let first = parse(r#"quest "Intro" { id: "main_01" }"#)?;
+    let second = parse(r#"quest "Prologue" { id: "main_01" } quest "Other" {}"#)?;
+    let mut registry = QuestRegistry::new(first)?;
+    let mut second = second.into_iter();
+    assert_eq!(
+        registry.insert(second.next().unwrap()),
+        Err(RegistryError::DuplicateId {
+            id: "main_01".into(),
+            first: "Intro".into(),
+            second: "Prologue".into(),
+        })
+    );
+    registry.insert(second.next().unwrap())?;
+    assert_eq!(registry.len(), 2);
+    assert_eq!(registry.get("main_01").unwrap().name, "Intro");
+    Ok(())
+}
+
+#[test]
+fn test_id_survives_rename_in_diff() -> Result<()> {
+    let a = &parse(r#"quest "Wolves" { id: "side_01" }"#)?[0];
+    let b = &parse(r#"quest "Wolf Pack" { id: "side_02" }"#)?[0];
+    let changes = quest_diff(a, b);
+    assert_eq!(
+        changes[1],
+        QuestChange::IdChanged {
+            from: Some("side_01".into()),
+            to: Some("side_02".into()),
+        }
+    );
+    assert_eq!(
+        changes[1].to_string(),
+        r#"id changed from "side_01" to "side_02""#
+    );
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    resolve, ParseError, Parser, Quest, ReferenceKind, ResolutionError, Span,
+};
+
+fn parse(source: &str) -> Result<Vec<Quest>, ParseError> {
+    Parser::new(source)?
+        .with_source_name("main.quest")
+        .parse_quests()
+}
+
+#[test]
+fn test_resolved_references() -> Result<()> {
+    let quests = parse(
+        r#"quest "Intro" { on_success { unlock: "Wolves", unlock: "den" } }
+        quest "Wolves" { requires: "Intro" }
+        quest "Den" { id: "den", requires: "Intro", requires: "Wolves" }"#,
+    )?;
+    let world = resolve(&quests).unwrap();
+    let names = |quests: Vec<&Quest>| quests.iter().map(|q| q.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(world.unlocks("Intro").unwrap()), ["Wolves", "Den"]);
+    assert_eq!(
+        names(world.prerequisites("Den").unwrap()),
+        ["Intro", "Wolves"]
+    );
+    assert_eq!(world.get("den"), None);
+    assert_eq!(world.quests().len(), 3);
+    Ok(())
+}
+
+#[test]
+fn test_dangling_references_point_at_their_source() -> Result<()> {
+    let source = r#"quest "Intro" { requires: "Prologue" }
+quest "Wolves" { on_failure { unlock: "Retreat" } }"#;
+    let quests = parse(source)?;
+    let errors = resolve(&quests).unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            ResolutionError::UnknownQuest {
+                quest: "Intro".into(),
+                kind: ReferenceKind::Requires,
+                target: "Prologue".into(),
+                span: Some(Span::new(26, 36)),
+                file: Some("main.quest".into()),
+            },
+            ResolutionError::UnknownQuest {
+                quest: "Wolves".into(),
+                kind: ReferenceKind::FailureUnlock,
+                target: "Retreat".into(),
+                span: Some(Span::new(77, 86)),
+                file: Some("main.quest".into()),
+            },
+        ]
+    );
+    assert_eq!(&source[26..36], "\"Prologue\"");
+    assert_eq!(&source[77..86], "\"Retreat\"");
+    assert_eq!(
+        errors[1].to_string(),
+        r#"Quest "Wolves" unlocks on failure unknown quest "Retreat""#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_quests_built_in_code_have_no_spans() {
+    let quest = Quest {
+        name: "A".into(),
+        requires: vec!["B".into()],
+        ..Default::default()
+    };
+    let errors = resolve(std::slice::from_ref(&quest)).unwrap_err();
+    assert_eq!(errors[0].span(), None);
+    assert_eq!(
+        resolve(&[quest.clone(), quest]).unwrap_err()[0],
+        ResolutionError::DuplicateQuest("A".into())
+    );
+}
+
+#[test]
+fn test_source_map_does_not_affect_equality() -> Result<()> {
+    let a = parse(r#"quest "A" { requires: "B" }"#)?;
+    let b = parse("quest \"A\" {\n\n    requires: \"B\",\n}")?;
+    assert_ne!(a[0].source_map.requires(0), b[0].source_map.requires(0));
+    assert_eq!(a, b);
+    Ok(())
+}
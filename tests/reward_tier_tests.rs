@@ -0,0 +1,100 @@
+mod common;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    ast, from_toml, quest_diff, to_quest_string, to_toml, Currency, ParseError, Parser,
+    QuestChange, RewardTier,
+};
+
+use common::parse_v2;
+
+const CHALLENGE: &str = r#"reward: 50,
+    reward tier "gold" { gold: 500, gems: 10 }
+    reward tier silver { gold: 250 }
+    step: "Clear the arena""#;
+
+#[test]
+fn test_reward_tiers() -> Result<()> {
+    let quest = parse_v2(CHALLENGE)?;
+    assert_eq!(quest.reward, 50);
+    assert_eq!(
+        quest.reward_tiers,
+        [
+            RewardTier::new("gold", vec![(Currency::Gold, 500), (Currency::Gems, 10)]),
+            RewardTier::new("silver", vec![(Currency::Gold, 250)]),
+        ]
+    );
+    assert_eq!(quest.steps.len(), 1);
+    assert!(quest
+        .summary()
+        .contains("Tiers:      gold (500 gold, 10 gems), silver (250 gold)\n"));
+
+    let written = to_quest_string(&quest);
+    assert!(written.starts_with("#version 2\n"));
+    assert!(written.contains("    reward tier \"silver\" { gold: 250 },\n"));
+    assert_eq!(Parser::new(&written)?.parse_quest()?, quest);
+
+    let source = format!("#version 2\nquest \"A\" {{ {CHALLENGE} }}");
+    let tree = ast::parse(&source)?;
+    let quest = tree.quests().next().unwrap();
+    let keys: Vec<_> = quest.properties.iter().map(|p| &p.key).collect();
+    assert_eq!(keys, ["reward", "reward", "reward", "step"]);
+    Ok(())
+}
+
+#[test]
+fn test_invalid_reward_tiers() {
+    let duplicate = parse_v2(r#"reward tier gold { gold: 1 } reward tier "gold" { gems: 1 }"#);
+    assert!(matches!(
+        duplicate,
+        Err(ParseError::DuplicateTier { ref name, .. }) if name == "gold"
+    ));
+    assert_eq!(duplicate.unwrap_err().code(), "E0111");
+
+    let empty = parse_v2(r#"reward tier "bronze" {}"#);
+    assert!(matches!(
+        empty,
+        Err(ParseError::EmptyTier { ref name, .. }) if name == "bronze"
+    ));
+    assert_eq!(empty.unwrap_err().code(), "E0112");
+
+    assert!(matches!(
+        parse_v2("reward tier { gold: 1 }"),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    assert!(matches!(
+        Parser::new(r#"quest "A" { reward tier "gold" { gold: 1 } }"#)
+            .and_then(|mut p| p.parse_quest()),
+        Err(ParseError::RequiresVersion { .. })
+    ));
+}
+
+#[test]
+fn test_reward_tiers_diff_and_convert() -> Result<()> {
+    let old = parse_v2(CHALLENGE)?;
+    let new = parse_v2(
+        r#"reward: 50, reward tier "gold" { gold: 600, gems: 10 } reward tier bronze { gold: 100 }"#,
+    )?;
+    let changes = quest_diff(&old, &new);
+    assert!(changes.contains(&QuestChange::TierRewardChanged {
+        tier: "gold".into(),
+        currency: Currency::Gold,
+        from: Some(500),
+        to: Some(600),
+    }));
+    assert!(changes.contains(&QuestChange::RewardTierRemoved("silver".into())));
+    assert!(changes.contains(&QuestChange::RewardTierAdded("bronze".into())));
+    assert_eq!(
+        QuestChange::RewardTierAdded("bronze".into()).to_string(),
+        "reward tier \"bronze\" added"
+    );
+
+    let toml = to_toml(std::slice::from_ref(&old))?;
+    assert!(toml.contains("[[quest.reward_tier]]\nname = \"gold\"\n"));
+    assert_eq!(from_toml(&toml)?[0].reward_tiers, old.reward_tiers);
+    let duplicate = "[[quest]]\nname = \"A\"\n\n[[quest.reward_tier]]\nname = \"gold\"\n\
+                     rewards = [[\"gold\", 1]]\n\n[[quest.reward_tier]]\nname = \"gold\"\n\
+                     rewards = [[\"gems\", 1]]\n";
+    assert!(from_toml(duplicate).is_err());
+    Ok(())
+}
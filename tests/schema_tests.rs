@@ -0,0 +1,107 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    ParseError, Parser, PropertySpec, QuestSchema, Span, Value, ValueType,
+};
+
+fn schema() -> QuestSchema {
+    QuestSchema::new()
+        .required("gold", ValueType::Number)
+        .optional("weight", ValueType::Float)
+        .optional("loot", ValueType::List(Box::new(ValueType::String)))
+        .repeatable("hint", ValueType::String)
+        .property(
+            "zone",
+            PropertySpec::new(ValueType::Any).required().repeatable(),
+        )
+}
+
+#[test]
+fn test_dynamic_quest_collects_typed_values() -> Result<()> {
+    let input = r#"
+        let BASE = 10
+        quest "Caravan" {
+            gold: $BASE * 3,
+            weight: 2,
+            loot: ["silk", "spice"],
+            hint: "Leave at dawn",
+            hint: "Avoid the pass",
+            zone: "Desert",
+        }
+        quest Escort { gold: 5, zone: 1, zone: true }
+    "#;
+    let quests = Parser::new(input)?.parse_dynamic_quests(&schema())?;
+    assert_eq!(quests.len(), 2);
+
+    let caravan = &quests[0];
+    assert_eq!(caravan.name, "Caravan");
+    assert_eq!(caravan.get("gold"), Some(&Value::Number(30)));
+    assert_eq!(caravan.get("weight"), Some(&Value::Float(2.0)));
+    assert_eq!(
+        caravan.get_all("hint"),
+        [
+            Value::String("Leave at dawn".into()),
+            Value::String("Avoid the pass".into())
+        ]
+    );
+    assert_eq!(quests[1].get_all("zone").len(), 2);
+    assert_eq!(quests[1].get("weight"), None);
+    Ok(())
+}
+
+#[test]
+fn test_dynamic_quest_is_validated_against_schema() {
+    let parse = |body: &str| {
+        Parser::new(&format!("quest \"A\" {{ {body} }}"))
+            .and_then(|mut p| p.parse_dynamic(&schema()))
+    };
+
+    assert_eq!(
+        parse(r#"gold: 1, zone: "x", reward: 5"#),
+        Err(ParseError::UnknownProperty {
+            key: "reward".into(),
+            suggestion: None,
+            span: Span::new(32, 38)
+        })
+    );
+    assert_eq!(
+        parse(r#"gold: 1, gold: 2, zone: "x""#),
+        Err(ParseError::DuplicateProperty {
+            key: "gold".into(),
+            span: Span::new(21, 25)
+        })
+    );
+    assert!(matches!(
+        parse(r#"gold: "lots", zone: "x""#),
+        Err(ParseError::SyntaxError { expected, .. }) if expected == "Number"
+    ));
+    assert!(matches!(
+        parse(r#"gold: 1, zone: "x", loot: ["a", 2]"#),
+        Err(ParseError::SyntaxError { expected, .. }) if expected == "List of String"
+    ));
+    assert!(matches!(
+        parse(r#"gold: level * 2, zone: "x""#),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    assert_eq!(
+        parse("gold: 1"),
+        Err(ParseError::MissingField {
+            block: "quest".into(),
+            field: "zone".into()
+        })
+    );
+}
+
+#[test]
+fn test_unknown_schema_keys_suggest_registered_ones() {
+    let error = Parser::new(r#"quest "A" { gold: 1, zone: "x", hints: "Go" }"#)
+        .and_then(|mut p| p.parse_dynamic(&schema()))
+        .unwrap_err();
+    assert_eq!(
+        error,
+        ParseError::UnknownProperty {
+            key: "hints".into(),
+            suggestion: Some("hint".into()),
+            span: Span::new(32, 37)
+        }
+    );
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::sim::{Choices, Ending, SimError};
+use game_quest_parser_Hodik::{Parser, Quest, Stage};
+
+const HEIST: &str = r#"#version 2
+quest "Heist" {
+    reward: 100 gold,
+    stages {
+        start -> "scout", start -> "bribe",
+        "scout" -> "vault", "scout" -> "scout",
+        "bribe" -> "vault", "bribe" -> "jail",
+        "vault" -> end,
+        "jail" -> "jail"
+    }
+    on_success { reward: 40 }
+    on_failure { reward: 5, penalty: 30 }
+}
+"#;
+
+fn heist() -> Result<Quest> {
+    Ok(Parser::new(HEIST)?.parse_quest()?)
+}
+
+fn named(name: &str) -> Stage {
+    Stage::Named(name.into())
+}
+
+fn scripted(choices: &[&str]) -> Choices {
+    Choices::Scripted(choices.iter().map(|choice| choice.to_string()).collect())
+}
+
+#[test]
+fn test_scripted_walk_follows_the_choices() -> Result<()> {
+    let report = heist()?.simulate(&scripted(&["scout", "scout", "vault"]))?;
+    assert_eq!(report.walks.len(), 1);
+    assert_eq!(
+        report.walks[0].path,
+        [
+            Stage::Start,
+            named("scout"),
+            named("scout"),
+            named("vault"),
+            Stage::End
+        ]
+    );
+    assert_eq!(report.endings, [Ending::Completed]);
+    assert_eq!(report.unreached, [named("bribe"), named("jail")]);
+    Ok(())
+}
+
+#[test]
+fn test_walks_into_dead_ends_get_stuck() -> Result<()> {
+    let quest = heist()?;
+    let report = quest.simulate(&scripted(&["bribe", "jail"]))?;
+    assert_eq!(report.endings, [Ending::Stuck(named("jail"))]);
+    assert_eq!(report.dead_ends, [named("jail")]);
+    assert_eq!(report.walks[0].path.last(), Some(&named("jail")));
+    Ok(())
+}
+
+#[test]
+fn test_random_walks_are_reproducible() -> Result<()> {
+    let quest = heist()?;
+    let choices = Choices::Random { seed: 7, runs: 200 };
+    let report = quest.simulate(&choices)?;
+    assert_eq!(report, quest.simulate(&choices)?);
+    assert_eq!(report.walks.len(), 200);
+    assert!(report.endings.contains(&Ending::Completed));
+    assert!(report.endings.contains(&Ending::Stuck(named("jail"))));
+    assert!(report.unreached.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_rewards_of_each_ending() -> Result<()> {
+    let report = heist()?.simulate(&scripted(&["scout", "vault"]))?;
+    assert_eq!(report.success_reward, 40);
+    assert_eq!(report.currency_rewards.len(), 1);
+    assert_eq!(report.failure_reward, Some(-25));
+
+    let plain = Parser::new(r#"quest "Intro" { reward: 10 }"#)?.parse_quest()?;
+    let report = plain.simulate(&scripted(&[]))?;
+    assert_eq!(report.walks[0].path, [Stage::Start, Stage::End]);
+    assert_eq!(report.success_reward, 10);
+    assert_eq!(report.failure_reward, None);
+    Ok(())
+}
+
+#[test]
+fn test_bad_scripts_are_rejected() -> Result<()> {
+    let quest = heist()?;
+    assert_eq!(
+        quest.simulate(&scripted(&["scout"])),
+        Err(SimError::OutOfChoices {
+            stage: named("scout"),
+            options: vec![named("vault"), named("scout")],
+        })
+    );
+    let error = quest.simulate(&scripted(&["vault"])).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        r#"Stage start does not lead to "vault", only to "scout", "bribe""#
+    );
+    assert_eq!(
+        quest.simulate(&scripted(&["scout", "vault", "end"])),
+        Err(SimError::UnusedChoices(1))
+    );
+    Ok(())
+}
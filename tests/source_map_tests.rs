@@ -0,0 +1,110 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    balance, line_col, merge, BalanceConfig, ParseError, Parser, Quest, Span,
+};
+
+fn parse(source: &str) -> Result<Vec<Quest>, ParseError> {
+    Parser::new(source)?
+        .with_source_name("main.quest")
+        .parse_quests()
+}
+
+fn text(source: &str, span: Option<Span>) -> &str {
+    let span = span.expect("the span is recorded");
+    &source[span.start..span.end]
+}
+
+#[test]
+fn test_fields_point_at_their_source() -> Result<()> {
+    let source = r#"quest "Wolves" {
+    reward: 100,
+    tags: ["side"],
+    step: "Hunt" [optional],
+    repeat(2) step: "Wave {n}",
+    requires: "Intro",
+    requires: "Den",
+}
+quest Den { steps { ordered { "Enter" "Leave" } } }"#;
+    let quests = parse(source)?;
+    let map = quests[0].source_map();
+    assert_eq!(map.file(), Some("main.quest"));
+    assert_eq!(text(source, map.name()), "\"Wolves\"");
+    assert_eq!(text(source, map.property("reward")), "reward: 100");
+    assert_eq!(
+        text(source, map.property("requires")),
+        "requires: \"Intro\""
+    );
+    assert_eq!(map.property("active"), None);
+
+    assert_eq!(text(source, map.step(0)), "\"Hunt\" [optional]");
+    assert_eq!(text(source, map.step(1)), "\"Wave {n}\"");
+    assert_eq!(map.step(2), map.step(1));
+    assert_eq!(map.step(3), None);
+    let (line, _) = line_col(source, map.property("step").unwrap().start);
+    assert_eq!(line, 4);
+
+    let map = quests[1].source_map();
+    assert_eq!(text(source, map.name()), "Den");
+    assert_eq!(text(source, map.step(1)), "\"Leave\"");
+    Ok(())
+}
+
+#[test]
+fn test_repeated_fields_point_at_what_they_kept() -> Result<()> {
+    let source = r#"#version 2
+quest "Wolves" {
+    reward: 1,
+    reward tier gold { gold: 5 }
+    reward: 2,
+    tags: ["side"],
+    tags: ["night"],
+    id: "wolves",
+    id: "wolves_2",
+}"#;
+    let quest = &parse(source)?[0];
+    let map = quest.source_map();
+    assert_eq!(quest.reward, 2);
+    assert_eq!(text(source, map.property("reward")), "reward: 2");
+    assert_eq!(text(source, map.property("tags")), "tags: [\"side\"]");
+    assert_eq!(text(source, map.property("id")), "id: \"wolves_2\"");
+
+    let source = "quest \"Rats\" {\n    level: 1..1,\n    reward: 50,\n    reward: 500\n}";
+    let config = BalanceConfig::from_toml("curve = [[1, 50]]")?;
+    let report = balance(&parse(source)?, &config);
+    assert_eq!(report.entries[0].reward, 500);
+    assert_eq!(text(source, report.entries[0].span), "reward: 500");
+    Ok(())
+}
+
+#[test]
+fn test_later_passes_use_the_source_map() -> Result<()> {
+    let source = "quest \"Rats\" {\n    level: 1..1,\n    reward: 500\n}\n\
+                  quest \"Bats\" { level: 1..1 }";
+    let mut quests = parse(source)?;
+    let config = BalanceConfig::from_toml("curve = [[1, 50]]")?;
+    let report = balance(&quests, &config);
+    assert_eq!(report.entries[0].file.as_deref(), Some("main.quest"));
+    assert_eq!(text(source, report.entries[0].span), "reward: 500");
+    assert_eq!(text(source, report.entries[1].span), "\"Bats\"");
+
+    // An override's spans point into its own input, so replaced fields
+    // lose theirs.
+    let patch = Parser::new(r#"quest "Rats" override { reward: 50 }"#)?
+        .parse_world()?
+        .overrides;
+    merge(&mut quests, &patch).unwrap();
+    let map = quests[0].source_map();
+    assert_eq!(map.property("reward"), None);
+    assert!(map.property("level").is_some());
+    Ok(())
+}
+
+#[test]
+fn test_listed_steps_get_their_own_spans() -> Result<()> {
+    let source = r#"quest "A" { steps: ["one", "two"] }"#;
+    let quests = parse(source)?;
+    let map = quests[0].source_map();
+    assert_eq!(text(source, map.step(0)), "\"one\"");
+    assert_eq!(text(source, map.step(1)), "\"two\"");
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{ParseError, Parser, Span, Stage, StageError, StateMachine};
+
+fn parse_stages(body: &str) -> Result<Option<StateMachine>, ParseError> {
+    let input = format!("quest \"Sword\" {{ {body} }}");
+    Parser::new(&input)
+        .and_then(|mut p| p.parse_quest())
+        .map(|quest| quest.stages)
+}
+
+#[test]
+fn test_stages_block_builds_state_machine() -> Result<()> {
+    let machine = parse_stages(
+        r#"stages { start -> "find_sword", "find_sword" -> "return", "return" -> end }"#,
+    )?
+    .expect("stages block");
+
+    let named = |name: &str| Stage::Named(name.to_string());
+    assert_eq!(
+        machine.stages(),
+        vec![
+            &Stage::Start,
+            &named("find_sword"),
+            &named("return"),
+            &Stage::End
+        ]
+    );
+    assert_eq!(
+        machine.next(&named("find_sword")).collect::<Vec<_>>(),
+        vec![&named("return")]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_stages_allow_branches_and_identifiers() -> Result<()> {
+    let machine = parse_stages(
+        "stages { start -> fight, start -> sneak, fight -> end, sneak -> fight, sneak -> end, }",
+    )?
+    .expect("stages block");
+    let next: Vec<_> = machine
+        .next(&Stage::Named("sneak".into()))
+        .cloned()
+        .collect();
+    assert_eq!(next, vec![Stage::Named("fight".into()), Stage::End]);
+    Ok(())
+}
+
+#[test]
+fn test_stages_are_validated() {
+    let error = |body: &str| match parse_stages(body) {
+        Err(ParseError::InvalidStages { error, .. }) => error,
+        other => panic!("expected invalid stages for {body:?}, got {other:?}"),
+    };
+
+    assert_eq!(
+        error(r#"stages { start -> "a", "a" -> "b" }"#),
+        StageError::UnknownStage("b".into())
+    );
+    assert_eq!(
+        error(r#"stages { start -> "a", "a" -> "a" }"#),
+        StageError::Unreachable(Stage::End)
+    );
+    assert_eq!(
+        error(r#"stages { start -> end, "lost" -> end }"#),
+        StageError::Unreachable(Stage::Named("lost".into()))
+    );
+    assert_eq!(
+        error(r#"stages { start -> end, end -> "a", "a" -> end }"#),
+        StageError::InvalidTransition {
+            from: Stage::End,
+            to: Stage::Named("a".into())
+        }
+    );
+    assert_eq!(error("stages { }"), StageError::Unreachable(Stage::End));
+}
+
+#[test]
+fn test_invalid_stages_span_covers_block() {
+    let result = parse_stages(r#"stages { start -> "a" }"#);
+    assert_eq!(result.map_err(|e| e.span()), Err(Some(Span::new(16, 39))));
+}
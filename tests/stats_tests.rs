@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{Parser, Quest, QuestStats};
+
+fn quests(source: &str) -> Result<Vec<Quest>> {
+    Ok(Parser::new(source)?.parse_quests()?)
+}
+
+#[test]
+fn test_stats_over_a_pack() -> Result<()> {
+    let quests = quests(
+        r#"quest "Intro" { tags: ["main", "act1", "main"], reward: 50, step: "Wake up" }
+           quest "Wolves" { tags: ["side", "act1"], requires: "Intro", reward: 200 }
+           quest "Finale" { tags: ["main"], requires: "Wolves", reward: 1000, steps: ["a", "b"] }
+           quest "Bonus" { tags: ["side"], reward: level * 10 }"#,
+    )?;
+    let stats = QuestStats::from(quests.as_slice());
+    assert_eq!(stats.quests, 4);
+    // `Bonus` rewards an expression, so it does not count.
+    assert_eq!(stats.average_reward, Some(1250.0 / 3.0));
+    assert_eq!(stats.max_reward, Some(1000));
+    assert_eq!(stats.step_counts, BTreeMap::from([(0, 2), (1, 1), (2, 1)]));
+    assert_eq!(
+        stats.tags,
+        [
+            ("act1".to_string(), 2),
+            ("main".to_string(), 2),
+            ("side".to_string(), 2),
+        ]
+    );
+    assert_eq!(stats.top_tags(1), [("act1".to_string(), 2)]);
+    assert_eq!(stats.top_tags(10).len(), 3);
+    assert_eq!(stats.without_prerequisites, ["Intro", "Bonus"]);
+    Ok(())
+}
+
+#[test]
+fn test_stats_of_nothing() {
+    let stats = QuestStats::from(&[][..]);
+    assert_eq!(stats, QuestStats::default());
+    assert_eq!(stats.average_reward, None);
+    assert!(stats.top_tags(5).is_empty());
+}
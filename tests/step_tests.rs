@@ -0,0 +1,66 @@
+mod common;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{to_quest_string, ParseError, Parser, Span, Step, Text};
+
+use common::parse;
+
+#[test]
+fn test_step_attributes() -> Result<()> {
+    let quest = parse(
+        r#"step: "Find key" [optional, hidden],
+        step: { en: "Open door", uk: "Відчини двері" } [order: 3]
+        step: "Leave" []
+        step: "Rest""#,
+    )?;
+    assert_eq!(
+        quest.steps[0],
+        Step {
+            text: "Find key".into(),
+            optional: true,
+            hidden: true,
+            order: None,
+        }
+    );
+    assert_eq!(quest.steps[1].order, Some(3));
+    assert!(matches!(quest.steps[1].text, Text::Localized(_)));
+    assert_eq!(quest.steps[2], "Leave");
+    assert_eq!(quest.steps[3], "Rest");
+    assert_eq!(quest.steps[0].text, Step::from("Find key").text);
+    assert_eq!(
+        quest.step_texts().nth(2),
+        Some(&Text::Plain("Leave".into()))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_step_attribute_errors() {
+    assert_eq!(
+        parse(r#"step: "Go" [secret]"#),
+        Err(ParseError::UnknownAttribute {
+            name: "secret".into(),
+            span: Span::new(24, 30),
+        })
+    );
+    assert!(matches!(
+        parse(r#"step: "Go" [hidden, hidden]"#),
+        Err(ParseError::DuplicateProperty { key, .. }) if key == "hidden"
+    ));
+    assert!(matches!(
+        parse(r#"step: "Go" [order: "first"]"#),
+        Err(ParseError::SyntaxError { .. })
+    ));
+}
+
+#[test]
+fn test_step_attributes_round_trip() -> Result<()> {
+    let quest = parse(r#"step: "Find key" [hidden, order: -1], step: "Go""#)?;
+    let written = to_quest_string(&quest);
+    assert!(written.contains("    step: \"Find key\" [hidden, order: -1],\n    step: \"Go\",\n"));
+    assert_eq!(Parser::new(&written)?.parse_quest()?, quest);
+    assert!(quest
+        .summary()
+        .contains("    1. Find key (hidden, shown as -1)\n    2. Go\n"));
+    Ok(())
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    format_source, quest_diff, to_quest_string, Diagnostic, ParseError, Parser, QuestChange, Span,
+    Step, StepTree,
+};
+
+use common::parse;
+
+fn step(text: &str) -> StepTree {
+    StepTree::Step(text.into())
+}
+
+#[test]
+fn test_steps_block_builds_a_tree() -> Result<()> {
+    let quest = parse(
+        r#"steps {
+            ordered { "A", "B" } any_of { "C", all_of { "D", "E" [optional] } }
+            "F"
+        }"#,
+    )?;
+    let mut optional = Step::new("E");
+    optional.optional = true;
+    assert_eq!(
+        quest.step_tree,
+        Some(StepTree::AllOf(vec![
+            StepTree::Sequence(vec![step("A"), step("B")]),
+            StepTree::AnyOf(vec![
+                step("C"),
+                StepTree::AllOf(vec![step("D"), StepTree::Step(optional.clone())]),
+            ]),
+            step("F"),
+        ]))
+    );
+    // The flat list still holds every step in declaration order.
+    assert_eq!(quest.steps.len(), 6);
+    assert_eq!(quest.steps[4], optional);
+    Ok(())
+}
+
+#[test]
+fn test_step_trees_track_progress() -> Result<()> {
+    let tree = parse(r#"steps { ordered { "A", "B" } any_of { "C", "D" } }"#)?
+        .step_tree
+        .unwrap();
+    let done = |names: &'static [&'static str]| {
+        move |step: &Step| names.iter().any(|name| step.text == *name)
+    };
+    let texts = |steps: Vec<&Step>| -> Vec<String> {
+        steps.iter().map(|step| step.text.to_string()).collect()
+    };
+    assert_eq!(
+        texts(tree.next_steps(done(&[]))),
+        [r#""A""#, r#""C""#, r#""D""#]
+    );
+    assert_eq!(texts(tree.next_steps(done(&["A", "D"]))), [r#""B""#]);
+    assert!(!tree.is_complete(done(&["A", "D"])));
+    assert!(tree.is_complete(done(&["A", "B", "C"])));
+    assert!(tree.next_steps(done(&["A", "B", "C"])).is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_steps_block_holds_every_step() {
+    for body in [
+        r#"step: "A", steps { "B" }"#,
+        r#"steps { "B" }, step: "A""#,
+        r#"steps { "B" }, steps: ["A"]"#,
+        r#"steps { "B" }, steps { "C" }"#,
+    ] {
+        assert!(
+            matches!(parse(body), Err(ParseError::MixedSteps { .. })),
+            "{body}"
+        );
+    }
+    assert_eq!(
+        parse(r#"steps { ordered { 5 } }"#).unwrap_err().span(),
+        Some(Span::new(30, 31))
+    );
+}
+
+#[test]
+fn test_step_trees_round_trip() -> Result<()> {
+    let source = r#"steps { ordered { "A", any_of { "B", "C" } } all_of {} "D" }"#;
+    let quest = parse(source)?;
+    let text = to_quest_string(&quest);
+    assert!(
+        text.contains(
+            "    steps {\n        ordered { \"A\", any_of { \"B\", \"C\" } }\n        all_of {}\n        \"D\"\n    },\n"
+        ),
+        "{text}"
+    );
+    assert_eq!(Parser::new(&text)?.parse_quest()?, quest);
+    assert_eq!(format_source(&text)?, text);
+    Ok(())
+}
+
+#[test]
+fn test_step_trees_in_diffs_summaries_and_warnings() -> Result<()> {
+    let before = parse(r#"steps { ordered { "A", "B" } }"#)?;
+    let after = parse(r#"steps { any_of { "A", "B" } }"#)?;
+    assert_eq!(
+        quest_diff(&before, &after),
+        [QuestChange::StepGroupsChanged {
+            from: before.step_tree.clone(),
+            to: after.step_tree.clone(),
+        }]
+    );
+    let renamed = parse(r#"steps { ordered { "A", "C" } }"#)?;
+    assert!(matches!(
+        quest_diff(&before, &renamed)[..],
+        [QuestChange::StepChanged { index: 1, .. }]
+    ));
+
+    let summary = after.summary();
+    assert!(
+        summary.contains("  Steps:\n    any of:\n      1. A\n      2. B\n"),
+        "{summary}"
+    );
+
+    let output = Parser::new(r#"quest "A" { steps { "A", any_of { "A" } } }"#)?
+        .parse_quest_with_warnings()?;
+    assert!(matches!(
+        output.warnings[..],
+        [Diagnostic::DuplicateStep { .. }]
+    ));
+    Ok(())
+}
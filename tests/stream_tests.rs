@@ -0,0 +1,213 @@
+use std::fs;
+use std::io::{BufReader, Cursor};
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    line_col, merge, Diagnostics, IncludeResolver, IncludeSource, ParseError, Parser, ParserLimits,
+    ParserOptions, QuestOverride, QuestStream, Value,
+};
+
+#[test]
+fn test_stream_yields_quests_in_order() -> Result<()> {
+    let input =
+        "quest \"A\" { step: \"Say }\" }\nquest \"B\" {\n  reward: 5\n} quest C { active: true }\n";
+    let quests =
+        Parser::from_reader(BufReader::new(Cursor::new(input))).collect::<Result<Vec<_>, _>>()?;
+    let names: Vec<&str> = quests.iter().map(|q| q.name.as_str()).collect();
+    assert_eq!(names, vec!["A", "B", "C"]);
+    assert_eq!(quests[0].steps, vec!["Say }"]);
+    assert_eq!(quests[1].reward, 5);
+    Ok(())
+}
+
+#[test]
+fn test_stream_continues_after_a_bad_quest() {
+    let input = "quest \"A\" { reward: \"lots\" }\nquest \"B\" { reward: 1 }\nquest \"C\" {";
+    let results: Vec<_> = Parser::from_reader(Cursor::new(input)).collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_err());
+    assert_eq!(results[1].as_ref().map(|q| q.reward), Ok(1));
+    assert!(matches!(results[2], Err(ParseError::UnclosedBrace { .. })));
+}
+
+#[test]
+fn test_stream_skips_braces_in_raw_blocks() -> Result<()> {
+    let input = "quest \"A\" {\n    script: ```lua\nif x then print(\"}\") end\n```\n}\n\
+                 quest \"B\" {\n    lore: <<<END\n  a lone \" and a {\nEND\n}\nquest \"C\" {}\n";
+    let quests = Parser::from_reader(Cursor::new(input)).collect::<Result<Vec<_>, _>>()?;
+    let names: Vec<&str> = quests.iter().map(|q| q.name.as_str()).collect();
+    assert_eq!(names, vec!["A", "B", "C"]);
+    assert_eq!(
+        quests[1].extra["lore"],
+        Value::Raw {
+            lang: None,
+            body: "  a lone \" and a {".to_string()
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_stream_keeps_templates() -> Result<()> {
+    let input = "template \"T\"(gold) {\n    reward: $gold,\n    // Said by the elder.\n    step: \"Go\",\n}\n\
+                 quest \"A\" from T(gold: 5)\nquest \"B\" { active: true }\n\
+                 quest \"C\" from T(gold: 7)\n";
+    let quests = Parser::from_reader(Cursor::new(input)).collect::<Result<Vec<_>, _>>()?;
+    let rewards: Vec<i64> = quests.iter().map(|q| q.reward).collect();
+    assert_eq!(rewards, vec![5, 0, 7]);
+    assert_eq!(quests[2].steps, vec!["Go"]);
+
+    // A template is still defined only once.
+    let input = "template T() {}\nquest A {}\ntemplate T() {}\n";
+    let results: Vec<_> = Parser::from_reader(Cursor::new(input)).collect();
+    assert!(matches!(
+        results[1],
+        Err(ParseError::DuplicateDefinition { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_stream_keeps_the_package() -> Result<()> {
+    let input = "package \"act1\"\nquest A {}\nquest B {}\npackage \"act2\"\nquest C {}\n";
+    let quests = Parser::from_reader(Cursor::new(input)).collect::<Result<Vec<_>, _>>()?;
+    let packages: Vec<_> = quests.iter().map(|q| q.package.as_deref()).collect();
+    assert_eq!(packages, vec![Some("act1"), Some("act1"), Some("act2")]);
+    Ok(())
+}
+
+#[test]
+fn test_stream_collects_overrides() -> Result<()> {
+    let input = "quest A { reward: 1 }\nquest A override { reward: 5 }\nquest B {}\n";
+    let mut stream = Parser::from_reader(Cursor::new(input));
+    let mut quests = stream.by_ref().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(quests.len(), 2);
+    assert_eq!(stream.overrides().len(), 1);
+    assert_eq!(stream.overrides()[0].fields, ["reward"]);
+    merge(&mut quests, stream.overrides())?;
+    assert_eq!(quests[0].reward, 5);
+    Ok(())
+}
+
+/// Serves one included file from memory.
+struct OneFile(&'static str);
+
+impl IncludeResolver for OneFile {
+    fn resolve(&self, path: &str, _from: Option<&str>) -> Result<IncludeSource, String> {
+        Ok(IncludeSource {
+            name: path.to_string(),
+            source: self.0.to_string(),
+        })
+    }
+}
+
+#[test]
+fn test_stream_with_options() -> Result<()> {
+    let options = ParserOptions {
+        strict: true,
+        flags: ["beta".to_string()].into_iter().collect(),
+        ..Default::default()
+    };
+    let input = "include \"base.quest\"\n#[if(feature = \"beta\")]\nquest A {}\n\
+                 #[if(feature = \"gamma\")]\nquest B {}\nquest C { region: \"North\" }\n";
+    let results: Vec<_> = QuestStream::with_options(Cursor::new(input), options)
+        .with_include_resolver(OneFile("quest Base {}"))
+        .collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().map(|q| q.name.as_str()), Ok("Base"));
+    assert_eq!(results[1].as_ref().map(|q| q.name.as_str()), Ok("A"));
+    assert!(matches!(
+        results[2],
+        Err(ParseError::UnknownProperty { .. })
+    ));
+
+    // The input limit bounds each quest, not the whole stream.
+    let options = ParserOptions {
+        limits: ParserLimits {
+            max_input_bytes: 40,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let input = "quest A { reward: 1 }\nquest B { reward: 2 }\nquest C {\n    step: \"A long way to go\",\n}\n";
+    let results: Vec<_> = QuestStream::with_options(Cursor::new(input), options).collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[1].as_ref().map(|q| q.reward), Ok(2));
+    assert!(matches!(
+        results[2],
+        Err(ParseError::InputTooLarge { limit: 40, .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_stream_matches_parsing_the_whole_file() -> Result<()> {
+    let source = fs::read_to_string("tests/fixtures/stream/everything.quest")?;
+    let mut parser = Parser::new(&source)?;
+    let world = parser.parse_world()?;
+
+    let mut stream = Parser::from_reader(Cursor::new(&source));
+    let quests = stream.by_ref().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(quests.len(), 4);
+    assert_eq!(quests, world.quests);
+    assert_eq!(stream.symbols(), &world.symbols);
+    // Spans are relative to the chunk, the rest matches.
+    let fields = |overrides: &[QuestOverride]| {
+        let fields = overrides
+            .iter()
+            .map(|o| (o.patch.clone(), o.fields.clone()));
+        fields.collect::<Vec<_>>()
+    };
+    assert_eq!(fields(stream.overrides()), fields(&world.overrides));
+    let messages = |diagnostics: &Diagnostics| {
+        let warnings = diagnostics.warnings().iter().map(ToString::to_string);
+        warnings.collect::<Vec<_>>()
+    };
+    assert_eq!(messages(stream.diagnostics()), ["Unknown property `rewrd`"]);
+    assert_eq!(
+        messages(stream.diagnostics()),
+        messages(parser.diagnostics())
+    );
+
+    // Definitions are unique across chunks, as in a single file.
+    let input = "npc Elder {}\nquest A {}\nnpc Elder {}\n";
+    let results: Vec<_> = Parser::from_reader(Cursor::new(input)).collect();
+    assert!(matches!(
+        results[1],
+        Err(ParseError::DuplicateDefinition { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_stream_spans_point_into_the_whole_input() -> Result<()> {
+    let input = "quest A {\n    reward: 1,\n}\n\
+                 template T() {\n    step: \"Go\",\n}\n\
+                 quest B {\n    reward: 3,\n    step: ,\n}\n\
+                 quest C from T()\n\
+                 quest D {\n    rewrd: 2,\n}\n";
+    let mut stream = Parser::from_reader(Cursor::new(input));
+    let results: Vec<_> = stream.by_ref().collect();
+    assert_eq!(results.len(), 4);
+
+    // The error is in the third block.
+    let Err(error) = &results[1] else {
+        panic!("expected an error, got {:?}", results[1]);
+    };
+    let span = error.span().expect("a syntax error has a span");
+    assert_eq!(line_col(input, span.start), (9, 11));
+
+    let quest = results[0].as_ref().expect("A parses");
+    let reward = quest.source_map.property("reward").expect("A has a reward");
+    assert_eq!(&input[reward.start..reward.end], "reward: 1");
+    assert_eq!(line_col(input, reward.start), (2, 5));
+
+    // An instantiated quest points into its template.
+    let quest = results[2].as_ref().expect("C parses");
+    let step = quest.source_map.step(0).expect("C has a step");
+    assert_eq!(line_col(input, step.start), (5, 11));
+
+    let warning = &stream.diagnostics().warnings()[0];
+    assert_eq!(line_col(input, warning.span().start), (13, 5));
+    Ok(())
+}
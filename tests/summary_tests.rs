@@ -0,0 +1,57 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::Parser;
+
+#[test]
+fn test_simple_summary() -> Result<()> {
+    let quest = Parser::new(
+        r#"quest "The Lost Sword" { active: false, reward: 500, step: "Talk to the blacksmith" }"#,
+    )?
+    .parse_quest()?;
+    assert_eq!(
+        quest.summary(),
+        "The Lost Sword\n  Status:     inactive\n  Reward:     500\n  Steps:\n    1. Talk to the blacksmith\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_full_summary() -> Result<()> {
+    let quest = Parser::new(
+        r#"quest "Wolves" {
+            active: level > 3,
+            reward: 10 * level,
+            requires: "Intro",
+            requires: "Camp",
+            time_limit: 2h30m,
+            objective { kind: "kill", target: "Wolf", count: 3 },
+            objective { kind: "escort", target: "Merchant" },
+            step: { en: "Hunt", uk: "Полюй" },
+            step: "Return",
+            stages { start -> "hunt", "hunt" -> end },
+            zone: "North",
+            xp: level * 2,
+        }"#,
+    )?
+    .parse_quest()?;
+    let expected = r#"Wolves
+  Status:     active when level > 3
+  Reward:     10 * level
+  Time limit: 2h30m
+  Requires:   Intro, Camp
+  Objectives:
+    - Kill 3 x Wolf
+    - escort Merchant
+  Steps:
+    1. [en] Hunt
+       [uk] Полюй
+    2. Return
+  Stages:
+    start -> "hunt"
+    "hunt" -> end
+  Other properties:
+    xp: level * 2
+    zone: "North"
+"#;
+    assert_eq!(quest.summary(), expected);
+    Ok(())
+}
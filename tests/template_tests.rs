@@ -0,0 +1,145 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{format_source, Objective, ParseError, Parser, Span, Value};
+
+const TEMPLATE: &str = r#"
+    let GOLD = 1
+    template "FetchQuest"(target, count, reward_gold) {
+        reward: $reward_gold,
+        step: "Find the goods",
+        objective { kind: "collect", target: $target, count: $count },
+        bonus: $GOLD,
+    }
+"#;
+
+fn parse(quests: &str) -> Result<Vec<game_quest_parser_Hodik::Quest>, ParseError> {
+    Parser::new(&format!("{TEMPLATE}{quests}"))?.parse_quests()
+}
+
+#[test]
+fn test_template_is_expanded() -> Result<()> {
+    let quests = parse(
+        r#"
+        quest "Fetch Apples" from FetchQuest(target: "Apple", count: 5, reward_gold: 20)
+        quest "Fetch Pears" from FetchQuest(count: 2, target: "Pear", reward_gold: $GOLD,)
+        quest "Plain" { reward: 3 }
+        "#,
+    )?;
+    assert_eq!(quests.len(), 3);
+    assert_eq!(quests[0].name, "Fetch Apples");
+    assert_eq!(quests[0].reward, 20);
+    assert_eq!(quests[0].steps, vec!["Find the goods"]);
+    assert_eq!(
+        quests[0].objectives,
+        vec![Objective::Collect {
+            item: "Apple".into(),
+            count: 5
+        }]
+    );
+    assert_eq!(quests[0].extra["bonus"], Value::Number(1));
+    assert_eq!(quests[1].reward, 1);
+    assert_eq!(quests[2].reward, 3);
+    Ok(())
+}
+
+#[test]
+fn test_arguments_shadow_constants_only_inside_the_template() -> Result<()> {
+    let input = r#"
+        let target = "Outer"
+        template T(target) { where: $target }
+        quest "A" from T(target: "Inner")
+        quest "B" { where: $target }
+    "#;
+    let quests = Parser::new(input)?.parse_quests()?;
+    assert_eq!(quests[0].extra["where"], Value::String("Inner".into()));
+    assert_eq!(quests[1].extra["where"], Value::String("Outer".into()));
+    Ok(())
+}
+
+#[test]
+fn test_missing_argument_is_reported() {
+    let source = r#"quest "A" from FetchQuest(target: "Apple", count: 5)"#;
+    let start = TEMPLATE.len() + source.find("FetchQuest").unwrap();
+    assert_eq!(
+        parse(source).unwrap_err(),
+        ParseError::MissingArgument {
+            template: "FetchQuest".into(),
+            name: "reward_gold".into(),
+            span: Span::new(start, TEMPLATE.len() + source.len()),
+        }
+    );
+}
+
+#[test]
+fn test_extra_and_repeated_arguments_are_reported() {
+    for (source, arg) in [
+        (
+            r#"quest "A" from FetchQuest(target: "A", count: 1, reward_gold: 2, colour: "red")"#,
+            "colour",
+        ),
+        (
+            r#"quest "A" from FetchQuest(target: "A", target: "B")"#,
+            "target",
+        ),
+    ] {
+        let start = TEMPLATE.len() + source.rfind(arg).unwrap();
+        assert_eq!(
+            parse(source).unwrap_err(),
+            ParseError::UnexpectedArgument {
+                template: "FetchQuest".into(),
+                name: arg.into(),
+                span: Span::new(start, start + arg.len()),
+            }
+        );
+    }
+}
+
+#[test]
+fn test_unknown_template_is_reported() {
+    let error = Parser::new(r#"quest "A" from Nope()"#)
+        .and_then(|mut p| p.parse_quests())
+        .unwrap_err();
+    assert_eq!(
+        error,
+        ParseError::UnknownTemplate {
+            name: "Nope".into(),
+            span: Span::new(15, 19),
+        }
+    );
+}
+
+#[test]
+fn test_errors_in_the_body_point_into_the_template() {
+    let input = "template T(x) {\n    reward: \"lots\",\n}\nquest \"A\" from T(x: 1)";
+    let error = Parser::new(input)
+        .and_then(|mut p| p.parse_quests())
+        .unwrap_err();
+    let start = input.find("\"lots\"").unwrap();
+    assert_eq!(error.span(), Some(Span::new(start, start + 6)));
+}
+
+#[test]
+fn test_duplicate_templates_and_parameters_are_rejected() {
+    for input in ["template T() {} template T() {}", "template T(a, a) {}"] {
+        let error = Parser::new(input)
+            .and_then(|mut p| p.parse_quests())
+            .unwrap_err();
+        assert!(matches!(error, ParseError::DuplicateDefinition { .. }));
+    }
+}
+
+#[test]
+fn test_template_stays_a_property_key() -> Result<()> {
+    let quests = Parser::new(r#"quest "A" { template: "x", from: 1 }"#)?.parse_quests()?;
+    assert_eq!(quests[0].extra["template"], Value::String("x".into()));
+    Ok(())
+}
+
+#[test]
+fn test_format_source_keeps_templates() -> Result<()> {
+    let source = "template T(n) {\n  reward: $n\n}\nquest \"A\" from T(\n  n: 1\n)";
+    assert_eq!(
+        format_source(source)?,
+        "template T(n) {\n    reward: $n\n}\n\nquest \"A\" from T(\n    n: 1\n)\n"
+    );
+    Ok(())
+}
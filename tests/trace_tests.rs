@@ -0,0 +1,56 @@
+use std::sync::Mutex;
+
+use game_quest_parser_Hodik::{Parser, ParserOptions};
+
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct Collector;
+
+impl log::Log for Collector {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        LINES.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+fn parse(source: &str, trace: bool) -> Vec<String> {
+    let options = ParserOptions {
+        trace,
+        ..Default::default()
+    };
+    let _ = Parser::with_options(source, options)
+        .map(|p| p.with_source_name("a.quest"))
+        .and_then(|mut p| p.parse_quests());
+    std::mem::take(&mut *LINES.lock().unwrap())
+}
+
+// One test only, as the logger is global to the test binary.
+#[test]
+fn test_trace_logs_rules() {
+    log::set_logger(&Collector).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    assert_eq!(
+        parse(r#"quest "A" { reward: 5 }"#, false),
+        Vec::<String>::new()
+    );
+
+    assert_eq!(
+        parse("quest \"A\" {\n  reward: 5\n  tier 3\n}", true),
+        [
+            r#"> quest at QuestKeyword (a.quest:1:1)"#,
+            r#"  > property at Identifier("reward") (a.quest:2:3)"#,
+            r#"    > expression at Number(5) (a.quest:2:11)"#,
+            r#"    < expression before Identifier("tier") (a.quest:3:3)"#,
+            r#"  < property before Identifier("tier") (a.quest:3:3)"#,
+            r#"  > property at Identifier("tier") (a.quest:3:3)"#,
+            r#"  < property failed: Expected Colon, found Number(3)"#,
+            r#"< quest failed: Expected Colon, found Number(3)"#,
+        ]
+    );
+}
@@ -0,0 +1,67 @@
+use std::borrow::Cow;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{nfc, Lexer, ParseError, Parser, Quest, Span, Token, Value};
+
+fn parse(source: &str) -> Result<Quest, ParseError> {
+    Parser::new(source).and_then(|mut p| p.parse_quest())
+}
+
+#[test]
+fn test_cyrillic_identifiers() -> Result<()> {
+    let tokens: Vec<_> = Lexer::new("нагорода_2: золото")
+        .map(|t| t.unwrap().0)
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Identifier("нагорода_2"),
+            Token::Colon,
+            Token::Identifier("золото"),
+        ]
+    );
+
+    let quest = parse(r#"let золото = 10 quest "Ліс" { нагорода: $золото, ранг: "А" }"#)?;
+    assert_eq!(quest.name, "Ліс");
+    assert_eq!(quest.extra["нагорода"], Value::Number(10));
+    assert_eq!(quest.extra["ранг"], Value::String("А".into()));
+    Ok(())
+}
+
+#[test]
+fn test_names_are_normalized_to_nfc() -> Result<()> {
+    // `и` followed by a combining breve is `й` decomposed.
+    let decomposed =
+        parse("quest \"Тисячоли\u{438}\u{306}\" { requires: \"Краи\u{306}\", краи\u{306}: 1 }")?;
+    let precomposed = parse(r#"quest "Тисячолий" { requires: "Край", край: 1 }"#)?;
+    assert_eq!(decomposed, precomposed);
+    assert_eq!(decomposed.requires, ["Край"]);
+    assert!(decomposed.extra.contains_key("край"));
+
+    assert_eq!(nfc("e\u{301}"), "é");
+    assert_eq!(nfc("\u{1100}\u{1161}\u{11A8}"), "각");
+    assert!(matches!(nfc("plain ascii"), Cow::Borrowed(_)));
+    assert!(matches!(nfc("Край"), Cow::Borrowed(_)));
+    Ok(())
+}
+
+#[test]
+fn test_emoji_in_strings_and_identifiers() -> Result<()> {
+    let quest = parse(r#"quest "Dragon 🐉" { tags: ["🔥", "boss"], id: "dragon_🐉" }"#)?;
+    assert_eq!(quest.name, "Dragon 🐉");
+    assert_eq!(quest.tags, ["🔥", "boss"]);
+    assert_eq!(quest.id.as_deref(), Some("dragon_🐉"));
+
+    assert_eq!(
+        parse(r#"quest "A" { 🐉: 1 }"#).unwrap_err(),
+        ParseError::UnexpectedChar {
+            found: '🐉',
+            span: Span::new(12, 16),
+        }
+    );
+    assert!(matches!(
+        parse(r#"quest "A" { dragon🐉: 1 }"#),
+        Err(ParseError::UnexpectedChar { found: '🐉', .. })
+    ));
+    Ok(())
+}
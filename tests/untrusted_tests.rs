@@ -0,0 +1,221 @@
+use game_quest_parser_Hodik::{ParseError, Parser, ParserLimits, ParserOptions};
+use proptest::prelude::*;
+
+fn nested(open: &str, close: &str, depth: usize) -> String {
+    format!("{}1{}", open.repeat(depth), close.repeat(depth))
+}
+
+#[test]
+fn test_parse_untrusted_parses_quests() {
+    let quests =
+        Parser::parse_untrusted(r#"quest "A" { reward: 5 } quest "B" { step: "Go" }"#).unwrap();
+    assert_eq!(quests.len(), 2);
+    assert_eq!(quests[0].reward, 5);
+}
+
+#[test]
+fn test_parse_untrusted_refuses_includes() {
+    let result = Parser::parse_untrusted(r#"include "tests/fixtures/include/campaign.quest""#);
+    assert!(matches!(result, Err(ParseError::IncludeFailed { .. })));
+}
+
+#[test]
+fn test_deeply_nested_lists_are_rejected() {
+    let input = format!(r#"quest "A" {{ loot: {} }}"#, nested("[", "]", 100_000));
+    let error = Parser::parse_untrusted(&input).unwrap_err();
+    assert!(matches!(
+        error,
+        ParseError::NestingTooDeep { limit, .. } if limit == ParserLimits::UNTRUSTED.max_nesting
+    ));
+}
+
+#[test]
+fn test_deeply_nested_parentheses_are_rejected() {
+    let input = format!(r#"quest "A" {{ reward: {} }}"#, nested("(", ")", 100_000));
+    let error = Parser::parse_untrusted(&input).unwrap_err();
+    assert!(matches!(error, ParseError::NestingTooDeep { .. }));
+}
+
+#[test]
+fn test_long_operator_chains_are_rejected() {
+    let chain = vec!["level"; 100_000].join(" + ");
+    let input = format!(r#"quest "A" {{ reward: {chain} }}"#);
+    let error = Parser::parse_untrusted(&input).unwrap_err();
+    assert!(matches!(error, ParseError::NestingTooDeep { .. }));
+}
+
+#[test]
+fn test_nesting_within_limit_is_accepted() {
+    let input = format!(r#"quest "A" {{ reward: {} }}"#, nested("(", ")", 32));
+    let quests = Parser::parse_untrusted(&input).unwrap();
+    assert_eq!(quests[0].reward, 1);
+}
+
+#[test]
+fn test_max_nesting_limit() {
+    let input = format!(r#"quest "A" {{ loot: {} }}"#, nested("[", "]", 3));
+    let options = ParserOptions {
+        limits: ParserLimits {
+            max_nesting: 2,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let error = Parser::with_options(&input, options)
+        .and_then(|mut p| p.parse_quests())
+        .unwrap_err();
+    assert!(matches!(error, ParseError::NestingTooDeep { limit: 2, .. }));
+}
+
+fn parse_with_limits(
+    input: &str,
+    limits: ParserLimits,
+) -> Result<Vec<game_quest_parser_Hodik::Quest>, ParseError> {
+    let options = ParserOptions {
+        limits,
+        ..Default::default()
+    };
+    Parser::with_options(input, options)?.parse_quests()
+}
+
+#[test]
+fn test_oversized_input_is_rejected_before_lexing() {
+    let limits = ParserLimits {
+        max_input_bytes: 16,
+        ..Default::default()
+    };
+    let error = parse_with_limits(r#"quest "A" { reward: 5 }"#, limits).unwrap_err();
+    assert_eq!(
+        error,
+        ParseError::InputTooLarge {
+            size: 23,
+            limit: 16
+        }
+    );
+}
+
+#[test]
+fn test_too_many_steps_are_rejected() {
+    let limits = ParserLimits {
+        max_steps: 2,
+        ..Default::default()
+    };
+    let input = r#"quest "A" { step: "a", steps: ["b", "c"] }"#;
+    let error = parse_with_limits(input, limits).unwrap_err();
+    let span = error.span().unwrap();
+    assert!(matches!(error, ParseError::TooManySteps { limit: 2, .. }));
+    assert_eq!(&input[span.start..span.end], r#"["b", "c"]"#);
+
+    let quests = parse_with_limits(r#"quest "A" { step: "a", step: "b" }"#, limits).unwrap();
+    assert_eq!(quests[0].steps.len(), 2);
+}
+
+#[test]
+fn test_huge_repeat_counts_are_rejected() {
+    // Rejected before a single copy is made, even without other limits.
+    let input = r#"quest "A" { repeat(100000000000) step: "x" }"#;
+    let error = Parser::new(input)
+        .and_then(|mut parser| parser.parse_quests())
+        .unwrap_err();
+    let limit = ParserLimits::DEFAULT_MAX_REPEAT;
+    assert!(matches!(error, ParseError::TooManyRepeats { limit: l, .. } if l == limit));
+    assert_eq!(error.code(), "E0505");
+    let span = error.span().unwrap();
+    assert_eq!(&input[span.start..span.end], "100000000000");
+
+    let limits = ParserLimits {
+        max_repeat: 3,
+        ..Default::default()
+    };
+    let quests = parse_with_limits(r#"quest "A" { repeat(3) step: "x" }"#, limits).unwrap();
+    assert_eq!(quests[0].steps.len(), 3);
+    assert!(parse_with_limits(r#"quest "A" { repeat(4) step: "x" }"#, limits).is_err());
+}
+
+#[test]
+fn test_long_strings_are_rejected() {
+    let limits = ParserLimits {
+        max_string_len: 4,
+        ..Default::default()
+    };
+    let error = parse_with_limits(r#"quest "A" { step: "Go north" }"#, limits).unwrap_err();
+    assert!(matches!(error, ParseError::StringTooLong { limit: 4, .. }));
+    assert!(parse_with_limits(r#"quest "A" { step: "Go" }"#, limits).is_ok());
+}
+
+#[test]
+fn test_parse_untrusted_limits_string_length() {
+    let step = "a".repeat(ParserLimits::UNTRUSTED.max_string_len + 1);
+    let input = format!(r#"quest "A" {{ step: "{step}" }}"#);
+    let error = Parser::parse_untrusted(&input).unwrap_err();
+    assert!(matches!(error, ParseError::StringTooLong { .. }));
+}
+
+fn token_soup() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        Just("quest"),
+        Just("let"),
+        Just("include"),
+        Just("npc"),
+        Just("item"),
+        Just("objective"),
+        Just("stages"),
+        Just("reward"),
+        Just("step"),
+        Just("A"),
+        Just("\"s\""),
+        Just("\"\\"),
+        Just("$X"),
+        Just("1"),
+        Just("-1"),
+        Just("2.5"),
+        Just("99999999999999999999"),
+        Just("true"),
+        Just("{"),
+        Just("}"),
+        Just("["),
+        Just("]"),
+        Just("("),
+        Just(")"),
+        Just(":"),
+        Just(","),
+        Just("="),
+        Just("->"),
+        Just("+"),
+        Just("-"),
+        Just("/"),
+        Just("%"),
+        Just("=="),
+        Just("&&"),
+        Just("//"),
+        Just("\n"),
+        Just("é"),
+    ];
+    prop::collection::vec(piece, 0..40).prop_map(|pieces| pieces.join(" "))
+}
+
+proptest! {
+    #[test]
+    fn test_arbitrary_text_never_panics(input in any::<String>()) {
+        let _ = Parser::parse_untrusted(&input);
+    }
+
+    #[test]
+    fn test_token_soup_never_panics(input in token_soup()) {
+        let _ = Parser::parse_untrusted(&input);
+    }
+}
+
+#[test]
+fn test_default_limit_fits_on_the_stack() {
+    let depth = ParserLimits::DEFAULT_MAX_NESTING;
+    let input = format!(
+        r#"quest "A" {{ reward: {}, loot: {} }}"#,
+        nested("(", ")", depth),
+        nested("[", "]", depth)
+    );
+    let quest = Parser::new(&input)
+        .and_then(|mut p| p.parse_quest())
+        .unwrap();
+    assert_eq!(quest.reward, 1);
+}
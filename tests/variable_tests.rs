@@ -0,0 +1,69 @@
+use std::io::Cursor;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{Objective, ParseError, Parser, Span};
+
+#[test]
+fn test_let_constants_are_substituted() -> Result<()> {
+    let input = r#"
+        let BASE_REWARD = 100
+        let BOSS = "Skeleton King"
+        let TAGS = ["main"]
+        quest "A" {
+            reward: $BASE_REWARD,
+            step: $BOSS,
+            objective { kind: "kill", target: $BOSS },
+            tags: $TAGS
+        }
+        let ACTIVE = true
+        quest "B" { reward: $BASE_REWARD, active: $ACTIVE }
+    "#;
+    let quests = Parser::new(input)?.parse_quests()?;
+    assert_eq!(quests[0].reward, 100);
+    assert_eq!(quests[0].steps, vec!["Skeleton King"]);
+    assert_eq!(
+        quests[0].objectives,
+        vec![Objective::Kill {
+            target: "Skeleton King".into(),
+            count: 1
+        }]
+    );
+    assert_eq!(quests[0].tags, ["main"]);
+    assert_eq!(quests[1].reward, 100);
+    assert!(quests[1].active);
+    Ok(())
+}
+
+#[test]
+fn test_undefined_and_duplicate_variables() {
+    let undefined =
+        Parser::new(r#"quest "A" { reward: $MISSING }"#).and_then(|mut p| p.parse_quest());
+    assert_eq!(
+        undefined,
+        Err(ParseError::UndefinedVariable {
+            name: "MISSING".into(),
+            span: Span::new(20, 28)
+        })
+    );
+
+    let duplicate = Parser::new("let X = 1 let X = 2").and_then(|mut p| p.parse_quests());
+    assert_eq!(duplicate, Err(ParseError::DuplicateVariable("X".into())));
+}
+
+#[test]
+fn test_variable_type_is_checked() {
+    let input = r#"let R = "lots" quest "A" { reward: $R }"#;
+    let result = Parser::new(input).and_then(|mut p| p.parse_quest());
+    assert!(matches!(result, Err(ParseError::SyntaxError { .. })));
+}
+
+#[test]
+fn test_stream_keeps_variables_between_quests() -> Result<()> {
+    let input = "let R = 7\nquest \"A\" { reward: $R }\nquest \"B\" { reward: $R }\n";
+    let quests = Parser::from_reader(Cursor::new(input)).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        quests.iter().map(|q| q.reward).collect::<Vec<_>>(),
+        vec![7, 7]
+    );
+    Ok(())
+}
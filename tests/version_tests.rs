@@ -0,0 +1,110 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    quest_diff, Currency, GrammarVersion, ParseError, Parser, QuestChange, Span,
+};
+
+#[test]
+fn test_files_default_to_version_1() -> Result<()> {
+    let parser = Parser::new(r#"quest "A" { reward: 5 }"#)?;
+    assert_eq!(parser.version(), GrammarVersion::V1);
+
+    let parser = Parser::new("// Bounties\n#version 2\nquest \"A\" { reward: 5 }")?;
+    assert_eq!(parser.version(), GrammarVersion::V2);
+    let parser = Parser::new("#version 1 quest \"A\" { reward: 5 }")?;
+    assert_eq!(parser.version(), GrammarVersion::V1);
+    Ok(())
+}
+
+#[test]
+fn test_named_rewards() -> Result<()> {
+    let quest = Parser::new(
+        "#version 2\nquest \"Bounty\" {\n    reward: 10,\n    reward { gold: 100\n gems: 5 },\n}",
+    )?
+    .parse_quest()?;
+    assert_eq!(quest.reward, 10);
+    assert_eq!(
+        quest.rewards,
+        vec![(Currency::Gold, 100), (Currency::Gems, 5)]
+    );
+    assert!(quest
+        .summary()
+        .contains("Reward:     10, 100 gold, 5 gems\n"));
+
+    let other = Parser::new("#version 2 quest \"Bounty\" { reward: 10, reward { gold: 120 } }")?
+        .parse_quest()?;
+    assert_eq!(
+        quest_diff(&quest, &other),
+        [
+            QuestChange::NamedRewardChanged {
+                currency: Currency::Gold,
+                from: Some(100),
+                to: Some(120)
+            },
+            QuestChange::NamedRewardChanged {
+                currency: Currency::Gems,
+                from: Some(5),
+                to: None
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_version_2_features_are_gated() {
+    let error = Parser::new(r#"quest "A" { reward { gold: 100 } }"#)
+        .and_then(|mut p| p.parse_quest())
+        .unwrap_err();
+    assert_eq!(
+        error,
+        ParseError::RequiresVersion {
+            feature: "A `reward { ... }` block".into(),
+            version: GrammarVersion::V2,
+            span: Span::new(12, 18),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "A `reward { ... }` block requires grammar version 2; add `#version 2` at the top of the file"
+    );
+}
+
+#[test]
+fn test_invalid_pragmas() {
+    let parse = |input| Parser::new(input).and_then(|mut p| p.parse_quests());
+    assert_eq!(
+        parse("#version 3"),
+        Err(ParseError::UnsupportedVersion {
+            version: 3,
+            span: Span::new(0, 10)
+        })
+    );
+    assert_eq!(
+        parse("quest \"A\" {}\n#version 2"),
+        Err(ParseError::MisplacedVersion {
+            span: Span::new(13, 14)
+        })
+    );
+    assert!(matches!(
+        parse("#edition 2"),
+        Err(ParseError::SyntaxError { .. })
+    ));
+    assert_eq!(
+        parse("#version 2 quest \"A\" { reward { gold: 1, gold: 2 } }"),
+        Err(ParseError::DuplicateProperty {
+            key: "gold".into(),
+            span: Span::new(41, 45)
+        })
+    );
+}
+
+#[test]
+fn test_stream_keeps_the_version() {
+    let input = "#version 2\nquest \"A\" { reward: 1 }\nquest \"B\" { reward { gold: 2 } }\n";
+    let quests: Vec<_> = Parser::from_reader(input.as_bytes()).collect();
+    assert_eq!(quests.len(), 2);
+    assert_eq!(
+        quests[1].as_ref().unwrap().rewards,
+        vec![(Currency::Gold, 2)]
+    );
+}
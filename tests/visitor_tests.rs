@@ -0,0 +1,193 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    IncludeResolver, IncludeSource, ParseError, Parser, ParserOptions, PropertyValue, QuestVisitor,
+    Span, Step,
+};
+
+/// Writes down every callback as a line, with the source text of its span.
+struct Recorder<'s> {
+    source: &'s str,
+    events: Vec<String>,
+}
+
+impl<'s> Recorder<'s> {
+    fn new(source: &'s str) -> Self {
+        Self {
+            source,
+            events: Vec::new(),
+        }
+    }
+
+    fn text(&self, span: Span) -> &'s str {
+        self.source.get(span.start..span.end).unwrap_or("?")
+    }
+}
+
+impl QuestVisitor for Recorder<'_> {
+    fn on_quest_start(&mut self, name: &str, span: Span) {
+        let event = format!("start {name} at {}", self.text(span));
+        self.events.push(event);
+    }
+
+    fn on_property(&mut self, key: &str, value: PropertyValue<'_>, span: Span) {
+        let value = match value {
+            PropertyValue::Value(value) => format!("{value:?}"),
+            PropertyValue::Expr(expr) => format!("expr {expr}"),
+            PropertyValue::RewardTier(tier) => format!("tier {}", tier.name),
+            PropertyValue::Npc(npc) => format!("npc {}", npc.name),
+            PropertyValue::Actions(actions) => format!("{} actions", actions.len()),
+            other => format!("{other:?}"),
+        };
+        let event = format!("{key} = {value} at {}", self.text(span));
+        self.events.push(event);
+    }
+
+    fn on_step(&mut self, step: &Step, span: Span) {
+        let event = format!("step {} at {}", step.text, self.text(span));
+        self.events.push(event);
+    }
+
+    fn on_quest_end(&mut self) {
+        self.events.push("end".to_string());
+    }
+}
+
+#[test]
+fn test_callbacks_follow_the_source() -> Result<()> {
+    let source = r#"#version 2
+quest Wolves {
+    id: "wolves",
+    reward: 10 + level,
+    reward tier gold { gold: 5 }
+    giver: npc("Elder"),
+    repeat(2) step: "Wave {n}",
+    requires: "Intro",
+    requires: "Den",
+    tags: ["side"],
+    on_start { play_sound: "howl" }
+    region: "North",
+}
+quest "Intro" { active: true }"#;
+    let mut recorder = Recorder::new(source);
+    assert_eq!(Parser::new(source)?.parse_with_visitor(&mut recorder)?, 2);
+    assert_eq!(
+        recorder.events,
+        [
+            "start Wolves at Wolves",
+            "id = String(\"wolves\") at id: \"wolves\"",
+            "reward = expr 10 + level at reward: 10 + level",
+            "reward = tier gold at reward tier gold { gold: 5 }",
+            "giver = npc Elder at giver: npc(\"Elder\")",
+            "step \"Wave 1\" at \"Wave {n}\"",
+            "step \"Wave 2\" at \"Wave {n}\"",
+            "requires = String(\"Intro\") at requires: \"Intro\"",
+            "requires = String(\"Den\") at requires: \"Den\"",
+            "tags = List([String(\"side\")]) at tags: [\"side\"]",
+            "on_start = 1 actions at on_start { play_sound: \"howl\" }",
+            "region = String(\"North\") at region: \"North\"",
+            "end",
+            "start Intro at \"Intro\"",
+            "active = Bool(true) at active: true",
+            "end",
+        ]
+    );
+    Ok(())
+}
+
+/// Serves one included file from memory.
+struct OneFile(&'static str);
+
+impl IncludeResolver for OneFile {
+    fn resolve(&self, path: &str, _from: Option<&str>) -> Result<IncludeSource, String> {
+        Ok(IncludeSource {
+            name: path.to_string(),
+            source: self.0.to_string(),
+        })
+    }
+}
+
+#[test]
+fn test_what_is_reported() -> Result<()> {
+    let included = r#"quest "Den" { reward: 5, step: "Enter", requires: "A", requires: "B" }"#;
+    let source = r#"include "den.quest"
+#[if(feature = "beta")]
+quest "Beta" { reward: 1 }
+quest "Den" override { reward: 50 }
+quest "Last" {}"#;
+    let mut recorder = Recorder::new(included);
+    let count = Parser::new(source)?
+        .with_include_resolver(OneFile(included))
+        .parse_with_visitor(&mut recorder)?;
+    assert_eq!(count, 2);
+    assert_eq!(
+        recorder.events,
+        [
+            "start Den at \"Den\"",
+            "reward = Number(5) at reward: 5",
+            "step \"Enter\" at \"Enter\"",
+            "requires = String(\"A\") at requires: \"A\"",
+            "requires = String(\"B\") at requires: \"A\"",
+            "end",
+            "start Last at ?",
+            "end",
+        ]
+    );
+
+    // Without `collect_extra` unknown properties are dropped unreported.
+    let options = ParserOptions {
+        collect_extra: false,
+        ..Default::default()
+    };
+    let mut recorder = Recorder::new("");
+    Parser::with_options(r#"quest A { region: "North", reward: 1 }"#, options)?
+        .parse_with_visitor(&mut recorder)?;
+    assert_eq!(recorder.events[1], "reward = Number(1) at ?");
+    assert_eq!(recorder.events.len(), 3);
+    Ok(())
+}
+
+#[test]
+fn test_errors_stop_part_way() -> Result<()> {
+    let source = r#"quest A { reward: 1 } quest B { step: "Go", reward: true }"#;
+    let mut recorder = Recorder::new(source);
+    let error = Parser::new(source)?
+        .parse_with_visitor(&mut recorder)
+        .unwrap_err();
+    assert!(matches!(error, ParseError::SyntaxError { .. }));
+    assert_eq!(
+        recorder.events,
+        [
+            "start A at A",
+            "reward = Number(1) at reward: 1",
+            "end",
+            "start B at B",
+            "step \"Go\" at \"Go\"",
+        ]
+    );
+
+    // Checks beyond the syntax fail where they are written too: nothing
+    // after the unknown property is reported.
+    let source = r#"quest A { id: "a", step: "Go", rewrd: 5, step: "Come back" }"#;
+    let mut recorder = Recorder::new(source);
+    let options = ParserOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let error = Parser::with_options(source, options)?
+        .parse_with_visitor(&mut recorder)
+        .unwrap_err();
+    assert!(matches!(error, ParseError::UnknownProperty { .. }));
+    assert_eq!(
+        recorder.events,
+        [
+            "start A at A",
+            "id = String(\"a\") at id: \"a\"",
+            "step \"Go\" at \"Go\"",
+        ]
+    );
+
+    // A visitor that does nothing still checks the input.
+    let count = Parser::new(r#"quest A {} quest B {} quest C {}"#)?.parse_with_visitor(&mut ())?;
+    assert_eq!(count, 3);
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::{
+    fix_deprecated, render_warning, Diagnostic, GrammarVersion, IncludeResolver, IncludeSource,
+    ParseError, Parser, ParserOptions, Span, Text,
+};
+
+fn warnings(input: &str, options: ParserOptions) -> Result<Vec<Diagnostic>> {
+    Ok(Parser::with_options(input, options)?
+        .parse_quest_with_warnings()?
+        .warnings)
+}
+
+#[test]
+fn test_typos_of_known_properties_warn() -> Result<()> {
+    let input = "quest \"A\" {\n    rewrd: 100,\n    flavour: \"kept\"\n}";
+    let output = Parser::new(input)?.parse_quest_with_warnings()?;
+    assert_eq!(
+        output.warnings,
+        [Diagnostic::UnknownProperty {
+            key: "rewrd".into(),
+            suggestion: Some("reward".into()),
+            span: Span::new(16, 21),
+        }]
+    );
+    // Still kept as extra properties, like before.
+    assert!(output.quest.extra.contains_key("rewrd"));
+    assert!(output.quest.extra.contains_key("flavour"));
+
+    let expected = "\
+warning: Unknown property `rewrd`
+ --> main.quest:2:5
+  |
+2 |     rewrd: 100,
+  |     ^^^^^
+  |
+  = help: did you mean `reward`?
+";
+    assert_eq!(
+        render_warning(&output.warnings[0], input, "main.quest"),
+        expected
+    );
+    Ok(())
+}
+
+#[test]
+fn test_dropped_properties_always_warn() -> Result<()> {
+    let options = ParserOptions {
+        collect_extra: false,
+        ..Default::default()
+    };
+    let found = warnings(r#"quest "A" { flavour: "lost" }"#, options)?;
+    assert_eq!(
+        found,
+        [Diagnostic::UnknownProperty {
+            key: "flavour".into(),
+            suggestion: None,
+            span: Span::new(12, 19),
+        }]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_strict_mode_still_fails() {
+    let options = ParserOptions {
+        strict: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        warnings(r#"quest "A" { rewrd: 1 }"#, options)
+            .unwrap_err()
+            .downcast::<ParseError>(),
+        Ok(ParseError::UnknownProperty { .. })
+    ));
+}
+
+#[test]
+fn test_duplicate_steps_warn() -> Result<()> {
+    let input = r#"quest "A" { step: "Go", steps: ["Look", "Go"], step: "Look" }"#;
+    let found = warnings(input, ParserOptions::default())?;
+    assert_eq!(
+        found,
+        [
+            Diagnostic::DuplicateStep {
+                text: Text::Plain("Go".into()),
+                span: Span::new(40, 44),
+            },
+            Diagnostic::DuplicateStep {
+                text: Text::Plain("Look".into()),
+                span: Span::new(53, 59),
+            },
+        ]
+    );
+    assert_eq!(
+        found[0].to_string(),
+        r#"Step "Go" is listed more than once"#
+    );
+    Ok(())
+}
+
+struct MemoryResolver(HashMap<&'static str, &'static str>);
+
+impl IncludeResolver for MemoryResolver {
+    fn resolve(&self, path: &str, _from: Option<&str>) -> Result<IncludeSource, String> {
+        self.0
+            .get(path)
+            .map(|source| IncludeSource {
+                name: path.to_string(),
+                source: source.to_string(),
+            })
+            .ok_or_else(|| "not found".to_string())
+    }
+}
+
+#[test]
+fn test_warnings_in_includes_name_the_file() -> Result<()> {
+    let resolver = MemoryResolver(HashMap::from([
+        ("a.quest", r#"include "b.quest""#),
+        ("b.quest", r#"quest "B" { rewrd: 1 }"#),
+    ]));
+    let mut parser = Parser::new(r#"include "a.quest" quest "Root" { step: "Go" }"#)?
+        .with_include_resolver(resolver);
+    let quests = parser.parse_quests()?;
+    assert_eq!(quests.len(), 2);
+    let found = parser.take_diagnostics().into_warnings();
+    assert_eq!(
+        found,
+        [Diagnostic::InInclude {
+            chain: vec!["a.quest".into(), "b.quest".into()],
+            warning: Box::new(Diagnostic::UnknownProperty {
+                key: "rewrd".into(),
+                suggestion: Some("reward".into()),
+                span: Span::new(12, 17),
+            }),
+        }]
+    );
+    assert_eq!(found[0].span(), Span::new(12, 17));
+    assert!(parser.diagnostics().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_old_syntax_warns_from_version_2_on() -> Result<()> {
+    let input = "#version 2\nquest \"A\" { level: 5, steps: [\"Go\", \"Return\"] }";
+    assert_eq!(
+        warnings(input, ParserOptions::default())?,
+        [
+            Diagnostic::Deprecated {
+                feature: "A single `level`".into(),
+                since: GrammarVersion::V2,
+                replacement: "5..5".into(),
+                span: Span::new(30, 31),
+            },
+            Diagnostic::Deprecated {
+                feature: "A `steps: [...]` list".into(),
+                since: GrammarVersion::V2,
+                replacement: "step: \"Go\", step: \"Return\"".into(),
+                span: Span::new(33, 56),
+            },
+        ]
+    );
+    // Files in the original grammar parse quietly.
+    assert!(warnings(&input["#version 2\n".len()..], ParserOptions::default())?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_fix_deprecated_keeps_what_the_formatter_rejects() -> Result<()> {
+    let input = "#version 2\nlet TOP = 20\n// Started in the tavern.\nquest \"A\" {\n    level: $TOP, // kept\n    level: 3,\n    steps: [\"Go\"],\n}\n";
+    let mut parser = Parser::new(input)?;
+    let quest = parser.parse_quest()?;
+    let fixed = fix_deprecated(input, parser.diagnostics().warnings());
+    assert_eq!(
+        fixed,
+        "#version 2\nlet TOP = 20\n// Started in the tavern.\nquest \"A\" {\n    level: $TOP, // kept\n    level: 3..3,\n    step: \"Go\",\n}\n"
+    );
+    let mut parser = Parser::new(&fixed)?;
+    assert_eq!(parser.parse_quest()?, quest);
+    assert!(parser.diagnostics().is_empty());
+    Ok(())
+}
@@ -0,0 +1,23 @@
+#![cfg(feature = "wasm")]
+
+use game_quest_parser_Hodik::wasm::parse_quest_json;
+
+#[test]
+fn test_parse_quest_json_returns_quest() {
+    let json = parse_quest_json(r#"quest "A" { reward: 5, zone: "North" }"#).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["name"], "A");
+    assert_eq!(value["reward"], 5);
+    assert_eq!(value["extra"]["zone"], "North");
+}
+
+#[test]
+fn test_parse_quest_json_reports_structured_errors() {
+    let error = parse_quest_json("quest \"A\" {\n  reward: \"x\"\n}").unwrap_err();
+    let value: serde_json::Value = serde_json::from_str(&error).unwrap();
+    assert_eq!(value["message"], "Expected Number, found String(\"x\")");
+    assert_eq!(value["code"], "E0101");
+    assert_eq!(value["line"], 2);
+    assert_eq!(value["column"], 11);
+    assert_eq!(value["span"]["start"], 22);
+}
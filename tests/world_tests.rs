@@ -0,0 +1,72 @@
+use anyhow::Result;
+use game_quest_parser_Hodik::{ParseError, Parser, ParserOptions, Value};
+
+const INPUT: &str = r#"
+    npc "Elder" { location: "Village" }
+    item Sword { value: 50, rare: true }
+    quest "Heirloom" {
+        objective { kind: "talk_to", target: "Elder" },
+        objective { kind: "collect", target: "Sword" },
+        item: "not a definition",
+    }
+"#;
+
+#[test]
+fn test_world_collects_npcs_and_items() -> Result<()> {
+    let world = Parser::new(INPUT)?.parse_world()?;
+    assert_eq!(world.quests.len(), 1);
+    assert_eq!(
+        world.quests[0].extra["item"],
+        Value::String("not a definition".into())
+    );
+    assert_eq!(
+        world.symbols.npcs["Elder"].properties["location"],
+        Value::String("Village".into())
+    );
+    assert_eq!(
+        world.symbols.items["Sword"].properties["value"],
+        Value::Number(50)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_quests_skips_definitions() -> Result<()> {
+    let quests = Parser::new(INPUT)?.parse_quests()?;
+    assert_eq!(quests.len(), 1);
+    assert_eq!(quests[0].name, "Heirloom");
+    Ok(())
+}
+
+#[test]
+fn test_undefined_references_are_rejected() -> Result<()> {
+    let input = r#"quest "Q" { objective { kind: "talk_to", target: "Ghost" } }"#;
+    assert_eq!(
+        Parser::new(input)?.parse_world(),
+        Err(ParseError::UndefinedReference {
+            quest: "Q".into(),
+            kind: "npc".into(),
+            name: "Ghost".into()
+        })
+    );
+
+    let options = ParserOptions {
+        allow_external_refs: true,
+        ..Default::default()
+    };
+    let world = Parser::with_options(input, options)?.parse_world()?;
+    assert!(world.symbols.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_definitions_are_rejected() {
+    let input = r#"item Sword { value: 1 } item "Sword" { value: 2 }"#;
+    assert_eq!(
+        Parser::new(input).and_then(|mut p| p.parse_world()),
+        Err(ParseError::DuplicateDefinition {
+            kind: "item".into(),
+            name: "Sword".into()
+        })
+    );
+}
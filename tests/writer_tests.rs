@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use game_quest_parser_Hodik::testing::check_round_trip;
+use game_quest_parser_Hodik::{
+    format_source, to_quest_string, Action, BinaryOp, Condition, Currency, DateTime, Expr, NpcRef,
+    Objective, Outcome, Parser, Quest, SourceMap, Stage, StateMachine, Transition, Value,
+};
+use proptest::prelude::*;
+
+fn reparse(quest: &Quest) -> Quest {
+    let text = to_quest_string(quest);
+    Parser::new(&text)
+        .and_then(|mut p| p.parse_quest())
+        .unwrap_or_else(|e| panic!("failed to reparse {text:?}: {e}"))
+}
+
+proptest! {
+    #[test]
+    fn test_round_trip_is_identity(quest in any::<Quest>()) {
+        check_round_trip(&quest)?;
+    }
+}
+
+#[test]
+fn test_writer_emits_canonical_dsl() -> Result<()> {
+    let quest = Quest {
+        name: "The \"Lost\" Sword".into(),
+        id: Some("main_01".into()),
+        package: None,
+        steps: vec!["Talk to the blacksmith".into()],
+        step_tree: None,
+        objectives: vec![Objective::Kill {
+            target: "Skeleton".into(),
+            count: 3,
+        }],
+        complete_when: Some(Condition::And(
+            Box::new(Condition::Objective("slay".into())),
+            Box::new(Condition::Or(
+                Box::new(Condition::Objective("forge".into())),
+                Box::new(Condition::Objective("buy".into())),
+            )),
+        )),
+        requires: vec!["Prologue".into()],
+        tags: vec!["main".into(), "act1".into()],
+        giver: Some(NpcRef::new("Elder").at("Village Square")),
+        turn_in: Some(NpcRef::new("Blacksmith")),
+        reward: 500,
+        rewards: Vec::new(),
+        reward_tiers: Vec::new(),
+        active: true,
+        stages: Some(StateMachine::new(vec![
+            Transition {
+                from: Stage::Start,
+                to: Stage::Named("forge".into()),
+            },
+            Transition {
+                from: Stage::Named("forge".into()),
+                to: Stage::End,
+            },
+        ])?),
+        time_limit: Some(Duration::from_secs(9_000)),
+        level_range: Some((10, 20)),
+        starts_at: DateTime::from_utc(2025, 10, 31, 0, 0, 0),
+        ends_at: DateTime::from_utc(2025, 11, 7, 12, 30, 0),
+        on_success: Some(Outcome {
+            reward: 100,
+            unlocks: vec!["Forge".into()],
+            ..Default::default()
+        }),
+        on_failure: Some(Outcome::default()),
+        on_start: vec![Action::new("give_item", vec![Value::String("Map".into())])],
+        on_complete: vec![Action::new(
+            "spawn",
+            vec![Value::String("wolf".into()), Value::Number(3)],
+        )],
+        extra: [
+            ("zone".to_string(), Value::String("North".into())),
+            ("chance".to_string(), Value::Float(1.0)),
+        ]
+        .into(),
+        expressions: [(
+            "chance_bonus".to_string(),
+            Expr::Binary {
+                op: BinaryOp::Mul,
+                lhs: Box::new(Expr::Identifier("luck".into())),
+                rhs: Box::new(Expr::Binary {
+                    op: BinaryOp::Add,
+                    lhs: Box::new(Expr::Literal(Value::Number(1))),
+                    rhs: Box::new(Expr::Identifier("level".into())),
+                }),
+            },
+        )]
+        .into(),
+        source_map: SourceMap::default(),
+    };
+    let expected = r#"quest "The \"Lost\" Sword" {
+    id: "main_01",
+    active: true,
+    reward: 500,
+    requires: "Prologue",
+    tags: ["main", "act1"],
+    giver: npc("Elder") at "Village Square",
+    turn_in: npc("Blacksmith"),
+    step: "Talk to the blacksmith",
+    objective { kind: "kill", target: "Skeleton", count: 3 },
+    complete_when: slay && (forge || buy),
+    stages { start -> "forge", "forge" -> end },
+    time_limit: 2h30m,
+    level: 10..20,
+    starts_at: 2025-10-31T00:00:00Z,
+    ends_at: 2025-11-07T12:30:00Z,
+    on_success { reward: 100, unlock: "Forge" },
+    on_failure {},
+    on_start { give_item: "Map" },
+    on_complete { spawn: ["wolf", 3] },
+    chance: 1.0,
+    chance_bonus: luck * (1 + level),
+    zone: "North",
+}
+"#;
+    assert_eq!(to_quest_string(&quest), expected);
+    Ok(())
+}
+
+#[test]
+fn test_named_rewards_need_version_2() -> Result<()> {
+    let quest = Quest {
+        name: "Bounty".into(),
+        rewards: vec![(Currency::Gold, 100), (Currency::Custom("stars".into()), 5)],
+        ..Default::default()
+    };
+    let expected = "#version 2\n\nquest \"Bounty\" {\n    active: false,\n    reward: 0,\n    \
+                    reward { gold: 100, stars: 5 },\n}\n";
+    assert_eq!(to_quest_string(&quest), expected);
+    assert_eq!(reparse(&quest), quest);
+
+    let source = "#version 2\nquest \"A\" { reward: 1 }\nquest \"B\" { reward: 2 }";
+    let formatted = format_source(source)?;
+    assert!(formatted.starts_with("#version 2\n\nquest \"A\""));
+    assert_eq!(formatted.matches("#version").count(), 1);
+    assert_eq!(format_source(&formatted)?, formatted);
+    Ok(())
+}
+
+#[test]
+fn test_round_trip_sample_file() -> Result<()> {
+    let source = std::fs::read_to_string("test_quest.txt")?;
+    let quest = Parser::new(&source)?.parse_quest()?;
+    assert_eq!(reparse(&quest), quest);
+    Ok(())
+}
+
+#[test]
+fn test_format_source_is_idempotent() -> Result<()> {
+    let messy = r#"quest Intro {step:"Wake up" reward : 5}   quest "Next" { requires: "Intro", active: true }"#;
+    let formatted = format_source(messy)?;
+    assert_eq!(
+        formatted,
+        "quest Intro {\n    reward: 5,\n    step: \"Wake up\",\n}\n\n\
+         quest \"Next\" {\n    active: true,\n    requires: \"Intro\",\n}\n"
+    );
+    assert_eq!(format_source(&formatted)?, formatted);
+    Ok(())
+}
+
+#[test]
+fn test_format_source_orders_properties() -> Result<()> {
+    let expected = "quest A {\n    id: \"a\",\n    active: true,\n    reward: 5,\n    \
+                    step: \"One\",\n    step: \"Two\",\n    loot: 3,\n    zone: \"North\",\n    \
+                    // Wins, being the later zone.\n    zone: \"South\",\n}\n";
+    // Steps keep their order among themselves, and so do the two zones.
+    let shuffled = [
+        "quest A { step: \"One\", reward: 5, zone: \"North\", active: true, step: \"Two\",\n\
+         // Wins, being the later zone.\n zone: \"South\", loot: 3, id: \"a\" }",
+        "quest A { loot: 3, zone: \"North\", id: \"a\", step: \"One\",\n\
+         // Wins, being the later zone.\n zone: \"South\", step: \"Two\", active: true, reward: 5 }",
+        "quest A {\n  zone: \"North\"\n  step: \"One\"\n  step: \"Two\"\n  reward: 5\n  \
+         // Wins, being the later zone.\n  zone: \"South\"\n  active: true\n  loot: 3\n  id: \"a\"\n}",
+    ];
+    for source in shuffled {
+        let formatted = format_source(source)?;
+        assert_eq!(formatted, expected, "{source}");
+        let quest = Parser::new(source)?.parse_quest()?;
+        assert_eq!(Parser::new(&formatted)?.parse_quest()?, quest);
+    }
+    assert_eq!(format_source(expected)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_format_source_keeps_declarations() -> Result<()> {
+    let source = "let R = 5\nlet S = 6 include \"other.quest\"\n\n\n\
+                  npc \"Elder\" {\n\tlocation: \"Village\"  \n}\nquest \"A\" { reward: $R }";
+    let formatted = format_source(source)?;
+    assert_eq!(
+        formatted,
+        "let R = 5\nlet S = 6\ninclude \"other.quest\"\n\n\
+         npc \"Elder\" {\n    location: \"Village\"\n}\n\nquest \"A\" {\n    reward: $R,\n}\n"
+    );
+    assert_eq!(format_source(&formatted)?, formatted);
+    Ok(())
+}
+
+#[test]
+fn test_format_source_leaves_multi_line_strings_alone() -> Result<()> {
+    let source = "quest \"A\" {\n  lore: ```\n  Howl  \n```\n  script: <<<END\n    spawn\nEND\n}";
+    assert_eq!(
+        format_source(source)?,
+        "quest \"A\" {\n    lore: ```\n  Howl  \n```,\n    script: <<<END\n    spawn\nEND,\n}\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_content_hashes_ignore_formatting() -> Result<()> {
+    let parse = |source: &str| Parser::new(source).and_then(|mut p| p.parse_quest());
+    let quest = parse(r#"quest "A" { reward: 5, step: "Go", tier: 2 }"#)?;
+    let reformatted = parse("quest \"A\" {\n  tier: 2 step: \"Go\"\n  reward: 5 // same\n}")?;
+    let edited = parse(r#"quest "A" { reward: 6, step: "Go", tier: 2 }"#)?;
+    assert_eq!(quest.content_hash(), reformatted.content_hash());
+    assert_ne!(quest.content_hash(), edited.content_hash());
+    assert_eq!(quest.change_hash(), reformatted.change_hash());
+    assert_ne!(quest.change_hash(), edited.change_hash());
+    // Shipped manifests rely on the digest never changing between builds.
+    let empty = parse(r#"quest "A" {}"#)?;
+    let digest: String = empty
+        .content_hash()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    assert_eq!(
+        digest,
+        "caec15edd515614ee8e970d21f2628e38555b8bacff7f56316d8ae1398de3019"
+    );
+    assert_eq!(empty.change_hash(), 0xc619_c1eb_ed56_e9e4);
+    Ok(())
+}